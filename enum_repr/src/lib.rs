@@ -38,6 +38,43 @@ pub fn enum_u8(input: TokenStream) -> TokenStream {
   gen.into()
 }
 
+/// Like `EnumU8`, but the conversion from `u8` is fallible (`TryFrom<u8>`),
+/// the invalid value is returned as error.
+#[proc_macro_derive(EnumTryU8)]
+pub fn enum_try_u8(input: TokenStream) -> TokenStream {
+  let ast: DeriveInput = syn::parse(input).unwrap();
+
+  let name = &ast.ident;
+
+  let variants = match &ast.data {
+    syn::Data::Enum(e) => e.variants.iter().map(|v| v.ident.clone()),
+    _ => panic!("EnumTryFrom can only be used with enums"),
+  };
+
+  let gen = quote! {
+    impl TryFrom<u8> for #name {
+      type Error = u8;
+
+      fn try_from(val: u8) -> Result<Self, Self::Error> {
+        match val {
+          #(
+            x if x == #name::#variants as u8 => Ok(#name::#variants),
+          )*
+          _ => Err(val),
+        }
+      }
+    }
+
+    impl From<#name> for u8 {
+      fn from(val: #name) -> u8 {
+        val as u8
+      }
+    }
+  };
+
+  gen.into()
+}
+
 #[proc_macro_derive(EnumUsize)]
 pub fn enum_usize(input: TokenStream) -> TokenStream {
   let ast: DeriveInput = syn::parse(input).unwrap();
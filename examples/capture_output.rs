@@ -0,0 +1,43 @@
+//! Capture values of `print` statements, instead of writing them to standard output.
+//!
+//! ```bash
+//! cargo run --example capture_output
+//! ```
+
+use std::cell::RefCell;
+
+use rlox::{Source, Vm};
+
+thread_local! {
+  static OUTPUT: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+fn capture(text: &str) {
+  OUTPUT.with_borrow_mut(|output| output.push(text.to_owned()));
+}
+
+fn main() {
+  let mut vm = Vm::new();
+  vm.set_print(capture);
+
+  let src = r#"
+    fun lines(i, n) {
+      if (i > n) return;
+      print "line " + str(i);
+      lines(i + 1, n);
+    }
+    lines(1, 3);
+  "#;
+  vm.eval(&Source::new(src)).unwrap();
+
+  let output = OUTPUT.take();
+  assert_eq!(output, ["line 1", "line 2", "line 3"]);
+  for line in output {
+    println!("captured: {}", line);
+  }
+}
+
+#[test]
+fn example() {
+  main();
+}
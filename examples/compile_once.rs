@@ -0,0 +1,34 @@
+//! Compile a script once, inspect its bytecode, then run it many times.
+//!
+//! ```bash
+//! cargo run --example compile_once
+//! ```
+
+use rlox::{Source, Value, Vm};
+
+fn main() {
+  let mut vm = Vm::new();
+  let src = r#"
+    fun fib(n) {
+      if (n < 2) return n;
+      return fib(n - 1) + fib(n - 2);
+    }
+    fib(15);
+  "#;
+  let script = vm.compile(&Source::named("fib.lox", src)).unwrap();
+  assert!(script.has_result() && script.warnings().is_empty());
+
+  let disassembly = vm.debugger().disassemble(&script);
+  assert!(disassembly.contains("fib"));
+  println!("{}", disassembly);
+
+  // running again needn't compile again
+  for _ in 0..3 {
+    assert_eq!(vm.run(&script).unwrap(), Some(Value::Number(610.0)));
+  }
+}
+
+#[test]
+fn example() {
+  main();
+}
@@ -0,0 +1,47 @@
+//! Run scripts cooperatively: each one yields after a budget of instructions,
+//! so a host loop (e.g. a game loop, or an async task) could interleave them with other work.
+//!
+//! ```bash
+//! cargo run --example cooperative
+//! ```
+
+use std::task::Poll;
+
+use rlox::{Source, Value, Vm};
+
+fn main() {
+  let sources = [
+    "fun fib(n) { if (n < 2) return n; return fib(n - 1) + fib(n - 2); }\nfib(12);",
+    "fun sum(n) { if (n == 0) return 0; return n + sum(n - 1); }\nsum(40);",
+  ];
+  let mut scripts: Vec<_> = sources
+    .iter()
+    .map(|src| {
+      let mut vm = Vm::new();
+      let script = vm.compile(&Source::new(*src)).unwrap();
+      vm.start(&script);
+      (vm, None)
+    })
+    .collect();
+
+  // the result of each script, `None` until it's finished
+  // each frame of the host runs every unfinished script for 500 instructions
+  let mut frames = 0;
+  while scripts.iter().any(|(_, result)| result.is_none()) {
+    frames += 1;
+    for (vm, result) in scripts.iter_mut().filter(|(_, result)| result.is_none()) {
+      if let Poll::Ready(value) = vm.run_for(500).unwrap() {
+        *result = Some(value);
+      }
+    }
+  }
+  println!("finished in {} frames", frames);
+  assert!(frames > 1);
+  assert_eq!(scripts[0].1, Some(Some(Value::Number(144.0))));
+  assert_eq!(scripts[1].1, Some(Some(Value::Number(820.0))));
+}
+
+#[test]
+fn example() {
+  main();
+}
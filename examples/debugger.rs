@@ -0,0 +1,33 @@
+//! Inspect the virtual machine after running a script: globals and statistics of the run.
+//!
+//! ```bash
+//! cargo run --example debugger
+//! ```
+
+use rlox::{Source, Vm};
+
+fn main() {
+  let mut vm = Vm::new();
+  let src = r#"
+    fun sum(n) {
+      if (n == 0) return 0;
+      return n + sum(n - 1);
+    }
+    var total = sum(50);
+  "#;
+  vm.eval(&Source::new(src)).unwrap();
+
+  let debugger = vm.debugger();
+  assert!(debugger.globals().ends_with("total = 1275\n"));
+  assert!(debugger.instruction_count() > 50);
+  println!(
+    "{} instructions executed, at most {} stack slots used",
+    debugger.instruction_count(),
+    debugger.peak_stack_size()
+  );
+}
+
+#[test]
+fn example() {
+  main();
+}
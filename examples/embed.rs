@@ -0,0 +1,35 @@
+//! Embed the virtual machine: evaluate scripts, then read their globals and results.
+//!
+//! ```bash
+//! cargo run --example embed
+//! ```
+
+use rlox::{DiagnosticKind, Source, Value, Vm};
+
+fn main() {
+  let mut vm = Vm::new();
+
+  // globals are kept across scripts, like lines of the REPL
+  vm.eval(&Source::new("var greeting = \"hello\";")).unwrap();
+  let result = vm.eval(&Source::new("greeting + \", world\";")).unwrap();
+  assert_eq!(result, Some(Value::String("hello, world".to_owned())));
+  assert_eq!(vm.global("greeting").unwrap().to_string(), "hello");
+
+  // statements have no result
+  assert_eq!(vm.eval(&Source::new("var n = 42;")).unwrap(), None);
+  assert_eq!(vm.global("n"), Some(Value::Number(42.0)));
+
+  // errors are diagnostics, and the virtual machine is still usable after them
+  let error = vm.eval(&Source::named("oops.lox", "n();")).unwrap_err();
+  assert_eq!(error.kind(), DiagnosticKind::RuntimeError);
+  println!("{}", error);
+  assert_eq!(
+    vm.eval(&Source::new("n + 1;")).unwrap(),
+    Some(Value::Number(43.0))
+  );
+}
+
+#[test]
+fn example() {
+  main();
+}
@@ -0,0 +1,51 @@
+//! Register a native function, which is called by scripts like any other function.
+//!
+//! The facade has no native interface yet, so this example uses the internal `vm` module
+//! (which isn't covered by semver).
+//!
+//! ```bash
+//! cargo run --example natives
+//! ```
+
+use rlox::{
+  utils::Init,
+  value::Value,
+  vm::{InterpretError, VM},
+};
+
+/// `hypot(x, y)`: length of the hypotenuse.
+fn hypot(args: &[Value]) -> Result<Value, InterpretError> {
+  match args {
+    [x, y] if x.is_number() && y.is_number() => {
+      Ok(Value::number_val(x.as_number().hypot(y.as_number())))
+    }
+    _ => Err(InterpretError::RuntimeError(
+      "`hypot` expects two numbers.".to_owned(),
+    )),
+  }
+}
+
+fn main() {
+  let mut vm = VM::init();
+  vm.define_native("hypot", 2, hypot);
+
+  let result = vm.interpret("hypot(3, 4);".to_owned()).unwrap();
+  assert_eq!(result, Some(Value::number_val(5.0)));
+
+  // errors of natives are runtime errors of the script, so they could be caught
+  let src = r#"
+    var message;
+    try { hypot("3", 4); } catch (e) { message = e.message; }
+    message;
+  "#;
+  let result = vm.interpret(src.to_owned()).unwrap().unwrap();
+  assert_eq!(
+    result.as_rust_string().unwrap(),
+    "`hypot` expects two numbers."
+  );
+}
+
+#[test]
+fn example() {
+  main();
+}
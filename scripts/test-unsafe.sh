@@ -0,0 +1,30 @@
+#!/usr/bin/env sh
+# Run the tests of the unsafe core (values, objects and tables) under Miri and AddressSanitizer.
+#
+# Both need a nightly toolchain (with the `miri` component for Miri).
+#
+# Usage: scripts/test-unsafe.sh [miri|asan]   (both by default)
+set -eu
+
+# tests which only exercise the unsafe core (no script is run, so they're fast under Miri)
+TESTS="--test value_accessors --test unsafe_core --test arena"
+# objects are never freed yet (no garbage collector), so leaks are expected
+export MIRIFLAGS="${MIRIFLAGS:--Zmiri-ignore-leaks}"
+export ASAN_OPTIONS="${ASAN_OPTIONS:-detect_leaks=0}"
+
+run_miri() {
+  cargo +nightly miri test --no-default-features $TESTS
+}
+
+run_asan() {
+  target="$(rustc +nightly -vV | sed -n 's/^host: //p')"
+  RUSTFLAGS="-Zsanitizer=address" RUSTDOCFLAGS="-Zsanitizer=address" \
+    cargo +nightly test --profile sanitize --target "$target" --no-default-features $TESTS
+}
+
+case "${1:-all}" in
+  miri) run_miri ;;
+  asan) run_asan ;;
+  all) run_miri && run_asan ;;
+  *) echo "Usage: $0 [miri|asan]" >&2 && exit 64 ;;
+esac
@@ -0,0 +1,677 @@
+//! # Api
+//!
+//! This module contains the public API of the crate, which is re-exported at the root:
+//!
+//! - [`Vm`]: compiles and runs scripts
+//! - [`Source`]: the text of a script, with the name it's reported as
+//! - [`CompiledScript`]: a compiled script, which could be run (again) without compiling
+//! - [`Value`]: a value produced by a script, copied out of the virtual machine
+//! - [`Diagnostic`]: an error or warning reported by compiling or running
+//! - [`Debugger`]: inspection of a virtual machine (disassembly, globals, locals, watches)
+//!
+//! [`Capabilities`] (the sandbox policy) and [`Step`] (how far the debugger steps)
+//! are re-exported at the root as well.
+//!
+//! All of them keep their fields private, so internal modules could change freely
+//! without breaking embedders. Internal modules are still reachable (for tests and tools),
+//! but they're hidden from docs and not covered by semver.
+
+use core::{fmt::Display, task::Poll};
+
+use crate::{
+  compiler::{function_table::FunctionTable, stats::CompileStats},
+  debugger::{Breakpoint, HeapGroup, HeapObject, HeapSnapshot},
+  native::{inspect, sandbox::SandboxProfile, Capabilities},
+  object::{ObjFunction, ObjString, ObjTrait},
+  prelude::*,
+  utils::Init,
+  value::{display, Value as RawValue},
+  vm::{InterpretError, RunOutcome as RawRunOutcome, Step, VM},
+};
+
+/// ## Source
+///
+/// The text of a script, with the name (e.g. the path of its file) it's reported as.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Source {
+  text: String,
+  name: Option<String>,
+}
+
+impl Source {
+  /// A script without name (like a line of the REPL).
+  pub fn new(text: impl Into<String>) -> Self {
+    Self {
+      text: text.into(),
+      name: None,
+    }
+  }
+
+  /// A script reported as `name`, i.e. diagnostics are located as `name:line:column`.
+  pub fn named(name: impl Into<String>, text: impl Into<String>) -> Self {
+    Self {
+      text: text.into(),
+      name: Some(name.into()),
+    }
+  }
+
+  /// Read the script from the file, which is reported by its path.
+  #[cfg(feature = "std")]
+  pub fn from_file(path: impl Into<String>) -> Result<Self, Diagnostic> {
+    let path = path.into();
+    match std::fs::read_to_string(&path) {
+      Ok(text) => Ok(Self::named(path, text)),
+      Err(_) => Err(Diagnostic::new(
+        DiagnosticKind::CompileError,
+        format!("Failed to read file `{}`.", path),
+      )),
+    }
+  }
+
+  /// The text of the script.
+  pub fn text(&self) -> &str {
+    &self.text
+  }
+
+  /// The name the script is reported as.
+  pub fn name(&self) -> Option<&str> {
+    self.name.as_deref()
+  }
+}
+
+impl From<&str> for Source {
+  fn from(text: &str) -> Self {
+    Self::new(text)
+  }
+}
+
+impl From<String> for Source {
+  fn from(text: String) -> Self {
+    Self::new(text)
+  }
+}
+
+/// ## DiagnosticKind
+///
+/// An enum which represents what a [`Diagnostic`] reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DiagnosticKind {
+  /// The script can't be compiled.
+  CompileError,
+  /// The script failed at runtime (and didn't catch it).
+  RuntimeError,
+  /// The script is aborted by an unrecoverable fault (e.g. stack overflow).
+  Fault,
+  /// The script ran past its deadline (see [`Vm::set_timeout`]).
+  Timeout,
+  /// The script used up its fuel or heap (see [`Vm::set_fuel`] and [`Vm::set_heap_limit`]).
+  LimitExceeded,
+  /// Something suspicious, which doesn't stop compiling.
+  Warning,
+}
+
+/// ## Diagnostic
+///
+/// An error or warning reported by compiling or running a script.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+  kind: DiagnosticKind,
+  message: String,
+}
+
+impl Diagnostic {
+  fn new(kind: DiagnosticKind, message: String) -> Self {
+    Self { kind, message }
+  }
+
+  /// What the diagnostic reports.
+  pub fn kind(&self) -> DiagnosticKind {
+    self.kind
+  }
+
+  /// The message, which is located (e.g. `[line 1] Error at ...`) if possible.
+  pub fn message(&self) -> &str {
+    &self.message
+  }
+
+  /// If it stops the script (anything but a warning).
+  pub fn is_error(&self) -> bool {
+    self.kind != DiagnosticKind::Warning
+  }
+}
+
+impl Display for Diagnostic {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    write!(f, "{}", self.message)
+  }
+}
+
+impl From<InterpretError> for Diagnostic {
+  fn from(error: InterpretError) -> Self {
+    let kind = match error {
+      InterpretError::CompileError(_) => DiagnosticKind::CompileError,
+      InterpretError::RuntimeError(_) | InterpretError::Raised(..) | InterpretError::Thrown(_) => {
+        DiagnosticKind::RuntimeError
+      }
+      InterpretError::Fault(_) | InterpretError::StackOverflow => DiagnosticKind::Fault,
+      InterpretError::Timeout(_) => DiagnosticKind::Timeout,
+      InterpretError::LimitExceeded(_) => DiagnosticKind::LimitExceeded,
+    };
+    Self::new(kind, error.into_message())
+  }
+}
+
+/// ## Value
+///
+/// A value produced by a script.
+///
+/// Objects other than strings are kept as what the REPL shows (e.g. `<fn add>`),
+/// so values never point into the virtual machine.
+///
+/// With the `serde` feature, values are (de)serialized as plain data (e.g. `null`, `1.5`, `"s"`),
+/// objects are serialized as `{"type_name": .., "repr": ..}`, but never deserialized.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(untagged))]
+#[non_exhaustive]
+pub enum Value {
+  Nil,
+  Bool(bool),
+  Number(f64),
+  String(String),
+  /// An object other than a string, e.g. a function, a class or an instance.
+  Object {
+    /// The type name of the object, e.g. `function` or `instance`.
+    type_name: &'static str,
+    /// What the REPL shows for the object.
+    repr: String,
+  },
+}
+
+impl Value {
+  /// Copy the value out of the virtual machine.
+  fn from_raw(value: &RawValue) -> Self {
+    if value.is_nil() {
+      Value::Nil
+    } else if value.is_bool() {
+      Value::Bool(value.as_bool())
+    } else if value.is_number() {
+      Value::Number(value.as_number())
+    } else if let Ok(string) = value.as_rust_string() {
+      Value::String(string.to_owned())
+    } else {
+      Value::Object {
+        type_name: inspect::type_name(value),
+        repr: display::repr(value),
+      }
+    }
+  }
+
+  /// Copy the value into the virtual machine (`None` for objects other than strings).
+  fn to_raw(&self) -> Option<RawValue> {
+    Some(match self {
+      Value::Nil => RawValue::nil_val(),
+      Value::Bool(b) => RawValue::bool_val(*b),
+      Value::Number(n) => RawValue::number_val(*n),
+      Value::String(s) => RawValue::obj_val(ObjString::from(s.clone()).cast_to_obj_ptr()),
+      Value::Object { .. } => return None,
+    })
+  }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Value {
+  fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    /// Values which could be deserialized, i.e. all but objects.
+    #[derive(serde::Deserialize)]
+    #[serde(untagged)]
+    enum Data {
+      Nil,
+      Bool(bool),
+      Number(f64),
+      String(String),
+    }
+
+    Ok(match Data::deserialize(deserializer)? {
+      Data::Nil => Value::Nil,
+      Data::Bool(b) => Value::Bool(b),
+      Data::Number(n) => Value::Number(n),
+      Data::String(s) => Value::String(s),
+    })
+  }
+}
+
+impl Display for Value {
+  /// What `print` shows for the value.
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      Value::Nil => write!(f, "nil"),
+      Value::Bool(b) => write!(f, "{}", b),
+      Value::Number(n) => write!(f, "{}", display::number(*n)),
+      Value::String(s) => write!(f, "{}", s),
+      Value::Object { repr, .. } => write!(f, "{}", repr),
+    }
+  }
+}
+
+/// ## CompiledScript
+///
+/// A compiled script, which could be run (any times) by the virtual machine compiling it.
+#[derive(Debug, Clone)]
+pub struct CompiledScript {
+  function: ObjFunction,
+  functions: FunctionTable,
+  has_result: bool,
+  warnings: Vec<Diagnostic>,
+}
+
+impl CompiledScript {
+  /// Warnings reported by compiling the script.
+  pub fn warnings(&self) -> &[Diagnostic] {
+    &self.warnings
+  }
+
+  /// Qualified names (e.g. `outer.inner`, `Point.init`) of all functions defined in the script,
+  /// each one after those defined in it.
+  pub fn functions(&self) -> impl Iterator<Item = &str> {
+    self.functions.names()
+  }
+
+  /// How much of the limits of a chunk the script and each function in it use
+  /// (see [`CompileStats`]).
+  pub fn stats(&self) -> CompileStats {
+    CompileStats::of(&self.function, &self.functions)
+  }
+
+  /// If running the script results in the value of its final expression statement.
+  pub fn has_result(&self) -> bool {
+    self.has_result
+  }
+}
+
+/// ## RunOutcome
+///
+/// How a run stops, see [`Vm::resume`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum RunOutcome {
+  /// The script is finished, with the value of its final expression statement (if any).
+  Finished(Option<Value>),
+  /// The script is paused before the first instruction of a line with a breakpoint
+  /// (or where a step stops, see [`Vm::step`]).
+  Paused { line: usize },
+}
+
+/// ## Vm
+///
+/// A virtual machine, which compiles and runs scripts.
+///
+/// Globals defined by a script are kept for later ones (like lines of the REPL).
+#[derive(Debug, Default)]
+pub struct Vm {
+  vm: VM,
+  /// If the script started last results in a value (see [`Vm::start`]).
+  has_result: bool,
+}
+
+impl Vm {
+  /// A virtual machine with natives defined, and no capability granted.
+  pub fn new() -> Self {
+    Self {
+      vm: VM::init(),
+      has_result: false,
+    }
+  }
+
+  /// A virtual machine restricted by the sandbox profile (see [`Vm::set_sandbox`]).
+  pub fn sandboxed(profile: SandboxProfile) -> Self {
+    let mut vm = Self::new();
+    vm.set_sandbox(profile);
+    vm
+  }
+
+  /// Compile the script (without running it).
+  pub fn compile(&mut self, source: &Source) -> Result<CompiledScript, Diagnostic> {
+    let has_result = self
+      .vm
+      .compile_source(source.text.clone(), source.name.as_deref())?;
+    let function = unsafe { self.vm.frames[0].function.as_ref() }.clone();
+    let functions = self.vm.functions().clone();
+    self.vm.reset_execution_state();
+    let warnings = self
+      .vm
+      .warnings()
+      .iter()
+      .map(|warning| Diagnostic::new(DiagnosticKind::Warning, warning.clone()))
+      .collect();
+    Ok(CompiledScript {
+      function,
+      functions,
+      has_result,
+      warnings,
+    })
+  }
+
+  /// Run the compiled script, returns the value of its final expression statement (if any).
+  pub fn run(&mut self, script: &CompiledScript) -> Result<Option<Value>, Diagnostic> {
+    self.vm.rebind_function(script.function.clone());
+    let value = self.vm.run()?;
+    Ok(script.has_result.then(|| Value::from_raw(&value)))
+  }
+
+  /// Start running the compiled script, without running any instruction of it yet,
+  /// then run it by polling [`Vm::run_for`].
+  pub fn start(&mut self, script: &CompiledScript) {
+    self.vm.rebind_function(script.function.clone());
+    self.has_result = script.has_result;
+  }
+
+  /// Run at most `budget` instructions of the script started last,
+  /// `Pending` if it isn't finished yet (so it could be polled again).
+  ///
+  /// Returns the value of its final expression statement (if any) once it's `Ready`.
+  pub fn run_for(&mut self, budget: usize) -> Result<Poll<Option<Value>>, Diagnostic> {
+    let has_result = self.has_result;
+    Ok(
+      self
+        .vm
+        .run_for(budget)?
+        .map(|value| has_result.then(|| Value::from_raw(&value))),
+    )
+  }
+
+  /// Run the script started last (see [`Vm::start`]) until it's finished,
+  /// or paused at a breakpoint (see [`Vm::set_breakpoint`]).
+  ///
+  /// A paused script is resumed from where it's paused by calling it again.
+  pub fn resume(&mut self) -> Result<RunOutcome, Diagnostic> {
+    let outcome = self.vm.resume()?;
+    Ok(self.outcome(outcome))
+  }
+
+  /// Run the script started last (see [`Vm::start`]) until where the step stops,
+  /// e.g. the next line or statement (see [`Step`]), or until it's finished.
+  ///
+  /// Breakpoints still pause it on the way.
+  pub fn step(&mut self, step: Step) -> Result<RunOutcome, Diagnostic> {
+    let outcome = self.vm.step(step)?;
+    Ok(self.outcome(outcome))
+  }
+
+  /// The outcome of the script started last, with its value copied out.
+  fn outcome(&self, outcome: RawRunOutcome) -> RunOutcome {
+    match outcome {
+      RawRunOutcome::Finished(value) => {
+        RunOutcome::Finished(self.has_result.then(|| Value::from_raw(&value)))
+      }
+      RawRunOutcome::Paused { line } => RunOutcome::Paused { line },
+    }
+  }
+
+  /// Pause [`Vm::resume`] each time the line (of any function) is reached.
+  pub fn set_breakpoint(&mut self, line: usize) {
+    self.vm.set_breakpoint(line);
+  }
+
+  /// Pause [`Vm::resume`] at the line only when the condition holds and it's hit enough times,
+  /// e.g. in loops (see [`Breakpoint`]).
+  pub fn set_breakpoint_with(
+    &mut self,
+    line: usize,
+    breakpoint: Breakpoint,
+  ) -> Result<(), Diagnostic> {
+    Ok(self.vm.set_breakpoint_with(line, breakpoint)?)
+  }
+
+  /// Remove the breakpoint of the line, returns if there's one.
+  pub fn clear_breakpoint(&mut self, line: usize) -> bool {
+    self.vm.clear_breakpoint(line)
+  }
+
+  /// Keep runs failed by runtime errors for inspection by [`Vm::debugger`]
+  /// (e.g. [`Debugger::locals`]), instead of discarding them (`false` by default).
+  pub fn set_post_mortem(&mut self, post_mortem: bool) {
+    self.vm.set_post_mortem(post_mortem);
+  }
+
+  /// Watch the expression, which is compiled once, then evaluated at each stop
+  /// (see [`Debugger::watches`]).
+  pub fn add_watch(&mut self, expression: &str) -> Result<(), Diagnostic> {
+    Ok(self.vm.add_watch(expression)?)
+  }
+
+  /// Stop watching the expression, returns if it's watched.
+  pub fn remove_watch(&mut self, expression: &str) -> bool {
+    self.vm.remove_watch(expression)
+  }
+
+  /// Compile the script, then run it.
+  pub fn eval(&mut self, source: &Source) -> Result<Option<Value>, Diagnostic> {
+    let script = self.compile(source)?;
+    self.run(&script)
+  }
+
+  /// Hot-reload the script: run it again, so the functions, classes and enums it defines replace
+  /// the previous ones, while globals already holding data keep their values.
+  ///
+  /// Nothing is changed if it fails to compile or run.
+  pub fn reload(&mut self, source: &Source) -> Result<(), Diagnostic> {
+    Ok(
+      self
+        .vm
+        .reload_source(source.text.clone(), source.name.as_deref())?,
+    )
+  }
+
+  /// The global of the given name (natives included).
+  pub fn global(&self, name: &str) -> Option<Value> {
+    let value = self.vm.globals().get(&ObjString::from(name.to_owned()))?;
+    Some(Value::from_raw(value))
+  }
+
+  /// Globals defined by scripts which hold data (nil, booleans, numbers and strings),
+  /// collected into a map, e.g. `HashMap<String, Value>`.
+  ///
+  /// Functions, classes and instances are left out, as they're definitions of the script
+  /// (defined again by running it), rather than its state.
+  pub fn export_globals<M: FromIterator<(String, Value)>>(&self) -> M {
+    self
+      .vm
+      .script_globals()
+      .map(|(name, value)| (name.to_string(), Value::from_raw(value)))
+      .filter(|(_, value)| !matches!(value, Value::Object { .. }))
+      .collect()
+  }
+
+  /// Define the globals (replacing those of the same names), e.g. exported by
+  /// [`Vm::export_globals`] from a previous run of the host.
+  ///
+  /// Nothing is defined if any of them is an object.
+  pub fn import_globals(
+    &mut self,
+    globals: impl IntoIterator<Item = (String, Value)>,
+  ) -> Result<(), Diagnostic> {
+    let mut values = Vec::new();
+    for (name, value) in globals {
+      let Some(value) = value.to_raw() else {
+        return Err(Diagnostic::new(
+          DiagnosticKind::RuntimeError,
+          format!(
+            "Global `{}` can't be imported, only nil, booleans, numbers and strings could be.",
+            name
+          ),
+        ));
+      };
+      values.push((ObjString::from(name), value));
+    }
+    for (name, value) in values {
+      self.vm.globals.set(name, value);
+    }
+    Ok(())
+  }
+
+  /// Grant capabilities to scripts (none by default).
+  pub fn set_capabilities(&mut self, capabilities: Capabilities) {
+    self.vm.set_capabilities(capabilities);
+  }
+
+  /// Apply the sandbox profile, which grants its capabilities, defines (or undefines)
+  /// natives of the host, and limits fuel, heap and time of each run (see [`SandboxProfile`]).
+  pub fn set_sandbox(&mut self, profile: SandboxProfile) {
+    self.vm.set_sandbox(profile);
+  }
+
+  /// Limit how long each run may take (unlimited by default).
+  #[cfg(feature = "std")]
+  pub fn set_timeout(&mut self, timeout: Option<std::time::Duration>) {
+    self.vm.set_timeout(timeout);
+  }
+
+  /// Limit how many instructions each run may execute (unlimited by default).
+  pub fn set_fuel(&mut self, fuel: Option<usize>) {
+    self.vm.set_fuel(fuel);
+  }
+
+  /// Limit how many bytes of objects each run may allocate (unlimited by default).
+  pub fn set_heap_limit(&mut self, limit: Option<usize>) {
+    self.vm.set_heap_limit(limit);
+  }
+
+  /// Set if ordering values of different types (e.g. `1 < "a"`) is a runtime error
+  /// (`false` by default).
+  pub fn set_strict_comparison(&mut self, strict: bool) {
+    self.vm.set_strict_comparison(strict);
+  }
+
+  /// Set where values of `print` statements are written to.
+  pub fn set_print(&mut self, print: fn(&str)) {
+    self.vm.set_print(print);
+  }
+
+  /// Inspect the virtual machine.
+  pub fn debugger(&mut self) -> Debugger<'_> {
+    Debugger { vm: &mut self.vm }
+  }
+}
+
+/// ## Debugger
+///
+/// Inspection of a virtual machine, see [`Vm::debugger`].
+#[derive(Debug)]
+pub struct Debugger<'vm> {
+  vm: &'vm mut VM,
+}
+
+impl Debugger<'_> {
+  /// Disassembly of the script and all functions in it.
+  pub fn disassemble(&self, script: &CompiledScript) -> String {
+    script.function.disassembly()
+  }
+
+  /// Disassembly of the function of the given qualified name (see [`CompiledScript::functions`]),
+  /// without functions defined in it.
+  pub fn disassemble_function(&self, script: &CompiledScript, name: &str) -> Option<String> {
+    script.functions.disassembly(name)
+  }
+
+  /// All globals defined by scripts, one `name = value` per line (natives excluded).
+  pub fn globals(&self) -> String {
+    self.vm.dump_globals()
+  }
+
+  /// Count of instructions executed by the last run.
+  pub fn instruction_count(&self) -> usize {
+    self.vm.instruction_count()
+  }
+
+  /// Peak size of the stack (in slots) during the last run.
+  pub fn peak_stack_size(&self) -> usize {
+    self.vm.peak_stack_size()
+  }
+
+  /// Locals of the ongoing call at the given depth (`0` for the innermost one)
+  /// of the paused (or kept) run, as `(name, value)` in order of their slots.
+  pub fn locals(&self, depth: usize) -> Vec<(String, Value)> {
+    self
+      .vm
+      .frame_locals(depth)
+      .into_iter()
+      .map(|(name, value)| (name, Value::from_raw(&value)))
+      .collect()
+  }
+
+  /// Variables captured by the ongoing call at the given depth (`0` for the innermost one)
+  /// of the paused (or kept) run, as `(name, value)` in order of their upvalues.
+  pub fn captures(&self, depth: usize) -> Vec<(String, Value)> {
+    self
+      .vm
+      .frame_captures(depth)
+      .into_iter()
+      .map(|(name, value)| (name, Value::from_raw(&value)))
+      .collect()
+  }
+
+  /// Evaluate the expression in the ongoing call at the given depth (`0` for the innermost one)
+  /// of the paused (or kept) run, where its locals, captured variables and `this` refer to
+  /// their current values.
+  ///
+  /// Assignments to variables made by the expression are discarded,
+  /// changes of objects (e.g. of fields) are kept.
+  pub fn eval_in_frame(&mut self, depth: usize, expression: &str) -> Result<Value, Diagnostic> {
+    let value = self.vm.eval_in_frame(depth, expression)?;
+    Ok(Value::from_raw(&value))
+  }
+
+  /// How many times the breakpoint of the line is hit (with its condition holding),
+  /// `None` if there's no such breakpoint.
+  pub fn breakpoint_hits(&self, line: usize) -> Option<usize> {
+    self.vm.breakpoint_hits(line)
+  }
+
+  /// Message of the runtime error the kept run failed by (see [`Vm::set_post_mortem`]).
+  pub fn failure(&self) -> Option<&str> {
+    self.vm.failure()
+  }
+
+  /// Disassembly of the instruction the kept run failed at (see [`Vm::set_post_mortem`]).
+  pub fn failing_instruction(&self) -> Option<String> {
+    self.vm.failing_instruction()
+  }
+
+  /// Live objects of the heap, as traced from globals and the paused (or kept) run.
+  pub fn heap_objects(&self) -> Vec<HeapObject> {
+    self.vm.heap_objects()
+  }
+
+  /// Live objects of the heap grouped by type, with counts and sizes.
+  pub fn heap_summary(&self) -> Vec<HeapGroup> {
+    self.vm.heap_summary()
+  }
+
+  /// Snapshot of live objects of the heap, e.g. to check natives of the host don't leak objects
+  /// by diffing snapshots taken before and after calling them (see [`HeapSnapshot::diff`]).
+  pub fn heap_snapshot(&self) -> HeapSnapshot {
+    self.vm.heap_snapshot()
+  }
+
+  /// Inspection of the live object of the id (see [`HeapObject::id`]),
+  /// `None` if there's no such object.
+  pub fn inspect_object(&self, id: usize) -> Option<String> {
+    self.vm.inspect_object(id)
+  }
+
+  /// Value of each watch (see [`Vm::add_watch`]) in the paused run,
+  /// where locals of the innermost call shadow globals.
+  ///
+  /// Assignments to variables made by watches are discarded, changes of objects are kept.
+  pub fn watches(&mut self) -> Vec<(String, Result<Value, Diagnostic>)> {
+    self
+      .vm
+      .watch_values()
+      .into_iter()
+      .map(|(expression, value)| {
+        let value = value.map(|value| Value::from_raw(&value));
+        (expression, value.map_err(Diagnostic::from))
+      })
+      .collect()
+  }
+}
@@ -0,0 +1,116 @@
+//! # Arena
+//!
+//! This module contains the bump arena of objects created while compiling.
+//!
+//! Identifiers, string literals and functions are constants of the chunk they're compiled into,
+//! so they're allocated in the arena of that chunk, and freed all at once when
+//! the last copy of the chunk is dropped (instead of being boxed and leaked one by one).
+//! A function owns the arena of its own chunk, so dropping the chunk of a script frees
+//! functions defined in it (and everything in their arenas) as well.
+//!
+//! Chunks of scripts bound to a virtual machine are never dropped (objects are never freed,
+//! and values of the script, e.g. in globals, could outlive the run), so arenas are only
+//! freed for chunks which are never bound, e.g. of scripts failing to compile, or copies
+//! of compiled scripts kept by the host or the compile cache (once the last one is dropped).
+//!
+//! Objects never move once allocated: each block is allocated with a fixed capacity,
+//! and a new block is started when the last one is full.
+
+use alloc::rc::Rc;
+use core::{cell::RefCell, ptr::NonNull};
+
+use crate::{
+  object::{Obj, ObjFunction, ObjString},
+  prelude::*,
+};
+
+/// Count of objects in each block.
+const BLOCK_CAPACITY: usize = 64;
+
+/// Blocks of objects of the same type.
+#[derive(Debug)]
+struct Blocks<T>(Vec<Vec<T>>);
+
+impl<T> Default for Blocks<T> {
+  fn default() -> Self {
+    Self(Vec::new())
+  }
+}
+
+impl<T> Blocks<T> {
+  fn alloc(&mut self, obj: T) -> NonNull<Obj> {
+    if self
+      .0
+      .last()
+      .is_none_or(|block| block.len() == block.capacity())
+    {
+      self.0.push(Vec::with_capacity(BLOCK_CAPACITY));
+    }
+    let block = self.0.last_mut().unwrap();
+    // never grows beyond its capacity, so objects allocated before are never moved
+    block.push(obj);
+    NonNull::from(block.last_mut().unwrap()).cast()
+  }
+
+  fn len(&self) -> usize {
+    self.0.iter().map(Vec::len).sum()
+  }
+}
+
+#[derive(Debug, Default)]
+struct Objects {
+  strings: Blocks<ObjString>,
+  functions: Blocks<ObjFunction>,
+}
+
+/// ## Arena
+///
+/// A bump arena of [`ObjString`]s and [`ObjFunction`]s, shared by all copies of the owning chunk.
+#[derive(Debug, Default, Clone)]
+pub struct Arena {
+  objects: Rc<RefCell<Objects>>,
+}
+
+impl Arena {
+  /// Move the string into the arena, then return the object pointer of it,
+  /// which is valid as long as any copy of the arena is alive.
+  pub fn alloc_string(&self, string: ObjString) -> NonNull<Obj> {
+    self.objects.borrow_mut().strings.alloc(string)
+  }
+
+  /// Move the function into the arena, then return the object pointer of it,
+  /// which is valid as long as any copy of the arena is alive.
+  ///
+  /// The function must not be allocated in the arena of its own chunk
+  /// (the two would keep each other alive).
+  pub fn alloc_function(&self, function: ObjFunction) -> NonNull<Obj> {
+    debug_assert!(!Rc::ptr_eq(&self.objects, &function.chunk.arena.objects));
+    self.objects.borrow_mut().functions.alloc(function)
+  }
+
+  /// Count of strings allocated.
+  pub fn strings(&self) -> usize {
+    self.objects.borrow().strings.len()
+  }
+
+  /// Count of functions allocated.
+  pub fn functions(&self) -> usize {
+    self.objects.borrow().functions.len()
+  }
+
+  /// Count of objects allocated.
+  pub fn len(&self) -> usize {
+    self.strings() + self.functions()
+  }
+
+  /// If nothing is allocated.
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  /// Count of copies of the arena alive (including this one),
+  /// the arena is freed when the last one is dropped.
+  pub fn copies(&self) -> usize {
+    Rc::strong_count(&self.objects)
+  }
+}
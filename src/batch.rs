@@ -0,0 +1,239 @@
+//! # Batch
+//!
+//! This module contains the batch mode of the interpreter (`rlox run-dir`).
+//!
+//! Every `.lox` file of a directory (subdirectories excluded) runs on a fresh [`VM`] of its own,
+//! so files can't see globals of each other. Files are run by a pool of worker threads
+//! (see [`BatchOptions::jobs`]), then reported in order of their paths, with:
+//!
+//! - whether the file passed (it compiled and ran without uncaught errors)
+//! - the error otherwise
+//! - the time it took (compile time included)
+//!
+//! Values of `print` statements are discarded, so outputs of files running at once don't interleave.
+//! A panic of the interpreter fails the file it happens in only (as a [`InterpretError::Fault`]).
+
+use std::{
+  fs, io,
+  panic::{self, AssertUnwindSafe},
+  path::{Path, PathBuf},
+  sync::{
+    atomic::{AtomicUsize, Ordering},
+    Mutex,
+  },
+  thread,
+  time::{Duration, Instant},
+};
+
+use crate::{
+  compiler::CompileOptions,
+  native::{sandbox::SandboxProfile, Capabilities},
+  utils::Init,
+  vm::{InterpretError, VM},
+};
+
+/// ## BatchOptions
+///
+/// Options of the batch mode.
+#[derive(Debug, Clone, Copy)]
+pub struct BatchOptions {
+  /// Number of worker threads (at least `1`).
+  pub jobs: usize,
+  /// Options of compiling each file.
+  pub compile_options: CompileOptions,
+  /// Capabilities granted to each file (after its sandbox profile is applied).
+  pub capabilities: Capabilities,
+  /// Sandbox profile applied to each file (if any).
+  pub sandbox: Option<SandboxProfile>,
+  /// Seed of deterministic mode of each file, if it's on (see [`VM::set_deterministic`]).
+  pub deterministic: Option<u64>,
+  /// If ordering values of different types is a runtime error (see [`VM::set_strict_comparison`]).
+  pub strict_comparison: bool,
+  /// Run time of each file, overriding the one of the sandbox profile (if given).
+  pub timeout: Option<Duration>,
+  /// Called on the virtual machine of each file before it runs
+  /// (after all options above are applied), e.g. to define natives of the host.
+  pub setup: Option<fn(&mut VM)>,
+}
+
+impl Default for BatchOptions {
+  fn default() -> Self {
+    Self {
+      jobs: thread::available_parallelism().map_or(1, |jobs| jobs.get()),
+      compile_options: CompileOptions::default(),
+      capabilities: Capabilities::none(),
+      sandbox: None,
+      deterministic: None,
+      strict_comparison: false,
+      timeout: None,
+      setup: None,
+    }
+  }
+}
+
+impl Init for BatchOptions {}
+
+/// ## FileReport
+///
+/// Result of running a single file.
+#[derive(Debug, Clone)]
+pub struct FileReport {
+  pub path: PathBuf,
+  pub result: Result<(), InterpretError>,
+  pub elapsed: Duration,
+}
+
+impl FileReport {
+  /// If the file compiled and ran without uncaught errors.
+  pub fn passed(&self) -> bool {
+    self.result.is_ok()
+  }
+}
+
+/// ## BatchReport
+///
+/// Results of all files, in order of their paths.
+#[derive(Debug, Clone, Default)]
+pub struct BatchReport {
+  pub files: Vec<FileReport>,
+  /// Wall time of the whole batch.
+  pub elapsed: Duration,
+  /// Number of worker threads actually used.
+  pub jobs: usize,
+}
+
+impl BatchReport {
+  /// Count of files which passed.
+  pub fn passed(&self) -> usize {
+    self.files.iter().filter(|file| file.passed()).count()
+  }
+
+  /// Count of files which failed.
+  pub fn failed(&self) -> usize {
+    self.files.len() - self.passed()
+  }
+}
+
+/// All `.lox` files of the directory (subdirectories excluded), sorted by path.
+pub fn lox_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+  let mut paths = vec![];
+  for entry in fs::read_dir(dir)? {
+    let path = entry?.path();
+    if path.is_file() && path.extension().is_some_and(|extension| extension == "lox") {
+      paths.push(path);
+    }
+  }
+  paths.sort();
+  Ok(paths)
+}
+
+/// Run all `.lox` files of the directory.
+pub fn run_dir(dir: &Path, options: BatchOptions) -> io::Result<BatchReport> {
+  Ok(run_files(&lox_files(dir)?, options))
+}
+
+/// Run the files, each on a virtual machine of its own.
+pub fn run_files(paths: &[PathBuf], options: BatchOptions) -> BatchReport {
+  let start = Instant::now();
+  let jobs = options.jobs.clamp(1, paths.len().max(1));
+  let next = AtomicUsize::new(0);
+  let reports = Mutex::new(vec![None; paths.len()]);
+  thread::scope(|scope| {
+    for _ in 0..jobs {
+      scope.spawn(|| loop {
+        let index = next.fetch_add(1, Ordering::Relaxed);
+        let Some(path) = paths.get(index) else {
+          break;
+        };
+        let report = run_file(path, options);
+        reports.lock().unwrap()[index] = Some(report);
+      });
+    }
+  });
+  BatchReport {
+    files: reports
+      .into_inner()
+      .unwrap()
+      .into_iter()
+      .flatten()
+      .collect(),
+    elapsed: start.elapsed(),
+    jobs,
+  }
+}
+
+/// Run the file on a fresh virtual machine, a panic fails the file only.
+fn run_file(path: &Path, options: BatchOptions) -> FileReport {
+  let start = Instant::now();
+  let result = panic::catch_unwind(AssertUnwindSafe(|| interpret_file(path, options)))
+    .unwrap_or_else(|payload| {
+      let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown cause");
+      Err(InterpretError::Fault(format!(
+        "The interpreter panicked: {}",
+        message
+      )))
+    });
+  FileReport {
+    path: path.to_owned(),
+    result,
+    elapsed: start.elapsed(),
+  }
+}
+
+/// Interpret the file on a virtual machine built from the options.
+fn interpret_file(path: &Path, options: BatchOptions) -> Result<(), InterpretError> {
+  let mut vm = VM::init();
+  vm.set_print(|_| {});
+  vm.set_compile_options(options.compile_options);
+  if let Some(profile) = options.sandbox {
+    vm.set_sandbox(profile);
+  }
+  vm.set_capabilities(options.capabilities);
+  vm.set_deterministic(options.deterministic);
+  vm.set_strict_comparison(options.strict_comparison);
+  if options.timeout.is_some() {
+    vm.set_timeout(options.timeout);
+  }
+  if let Some(setup) = options.setup {
+    setup(&mut vm);
+  }
+  let result = vm
+    .interpret_file(path.to_string_lossy().into_owned())
+    .map(|_| ());
+  vm.free();
+  result
+}
+
+/// Print each file as `PASS` or `FAIL` (with its error), followed by a summary.
+pub fn print_report(report: &BatchReport) {
+  for file in &report.files {
+    let status = if file.passed() { "PASS" } else { "FAIL" };
+    println!(
+      "{} {} ({:.3}ms)",
+      status,
+      file.path.display(),
+      millis(file.elapsed)
+    );
+    if let Err(error) = &file.result {
+      for line in error.clone().into_message().lines() {
+        println!("     {}", line);
+      }
+    }
+  }
+  println!(
+    "{} files: {} passed, {} failed in {:.3}ms ({} jobs)",
+    report.files.len(),
+    report.passed(),
+    report.failed(),
+    millis(report.elapsed),
+    report.jobs
+  );
+}
+
+fn millis(duration: Duration) -> f64 {
+  duration.as_secs_f64() * 1e3
+}
@@ -0,0 +1,309 @@
+//! # Bench
+//!
+//! This module contains the benchmark mode of the interpreter (`rlox bench`).
+//!
+//! Every iteration runs on a fresh [`VM`] (or on the same one, see [`BenchOptions::reuse_vm`]),
+//! and reports:
+//!
+//! - compile time
+//! - execution time
+//! - instructions executed
+//! - peak stack usage (in slots)
+//! - peak heap usage (in bytes, only if [`CountingAllocator`] is installed)
+//! - heap allocations (in count, only if [`CountingAllocator`] is installed)
+//!
+//! All durations are measured with the monotonic clock [`Instant`].
+//!
+//! Startup (`rlox bench --startup`) is measured apart, as it's dominated by one-off costs:
+//! creating the first virtual machine and compiling the first script of the process
+//! (see [`startup`]), and running `rlox` as a process (see [`process_times`]).
+
+use std::{
+  alloc::{GlobalAlloc, Layout, System},
+  io,
+  process::{Command, Stdio},
+  sync::atomic::{AtomicUsize, Ordering},
+  time::{Duration, Instant},
+};
+
+use crate::{
+  cache::CacheOptions,
+  utils::Init,
+  vm::{InterpretError, VM},
+};
+
+/// ## CountingAllocator
+///
+/// A wrapper of [`System`] allocator, which tracks current/peak heap usage
+/// and count of allocations.
+///
+/// Install it with `#[global_allocator]` in the binary, otherwise
+/// all heap statistics are reported as `0`.
+pub struct CountingAllocator;
+
+static CURRENT_HEAP: AtomicUsize = AtomicUsize::new(0);
+static PEAK_HEAP: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+  unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+    let ptr = System.alloc(layout);
+    if !ptr.is_null() {
+      let current = CURRENT_HEAP.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+      PEAK_HEAP.fetch_max(current, Ordering::Relaxed);
+      ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    }
+    ptr
+  }
+
+  unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+    System.dealloc(ptr, layout);
+    CURRENT_HEAP.fetch_sub(layout.size(), Ordering::Relaxed);
+  }
+}
+
+impl CountingAllocator {
+  /// Bytes currently allocated on the heap.
+  pub fn current() -> usize {
+    CURRENT_HEAP.load(Ordering::Relaxed)
+  }
+
+  /// Peak bytes allocated on the heap (since last `reset_peak`).
+  pub fn peak() -> usize {
+    PEAK_HEAP.load(Ordering::Relaxed)
+  }
+
+  /// Reset the peak to the current heap usage.
+  pub fn reset_peak() {
+    PEAK_HEAP.store(Self::current(), Ordering::Relaxed);
+  }
+
+  /// Count of allocations made so far (reallocations included).
+  pub fn allocations() -> usize {
+    ALLOCATIONS.load(Ordering::Relaxed)
+  }
+}
+
+/// ## BenchOptions
+///
+/// Options of the benchmark mode.
+#[derive(Debug, Clone, Copy)]
+pub struct BenchOptions {
+  /// Number of measured iterations.
+  pub iterations: usize,
+  /// Number of (unmeasured) warmup runs before the measured ones.
+  pub warmup: usize,
+  /// Run all iterations on the same virtual machine, so its scratch buffers are reused.
+  ///
+  /// The in-memory compile cache of it is turned off, so each iteration still compiles.
+  pub reuse_vm: bool,
+}
+
+impl Default for BenchOptions {
+  fn default() -> Self {
+    Self {
+      iterations: 10,
+      warmup: 1,
+      reuse_vm: false,
+    }
+  }
+}
+
+impl Init for BenchOptions {}
+
+/// ## IterationReport
+///
+/// Measurements of a single benchmark iteration.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IterationReport {
+  pub compile_time: Duration,
+  pub execute_time: Duration,
+  pub instructions: usize,
+  pub peak_stack: usize,
+  pub peak_heap: usize,
+  pub allocations: usize,
+}
+
+/// Run the given source once, on a fresh virtual machine.
+pub fn bench_once(src: &str) -> Result<IterationReport, InterpretError> {
+  let mut vm = VM::init();
+  let report = bench_on(&mut vm, src);
+  vm.free();
+  report
+}
+
+/// Run the given source once, on the given virtual machine.
+pub fn bench_on(vm: &mut VM, src: &str) -> Result<IterationReport, InterpretError> {
+  let heap_base = CountingAllocator::current();
+  let allocations_base = CountingAllocator::allocations();
+  CountingAllocator::reset_peak();
+
+  let start = Instant::now();
+  vm.compile(src.to_owned())?;
+  let compile_time = start.elapsed();
+
+  let start = Instant::now();
+  vm.run()?;
+  let execute_time = start.elapsed();
+
+  let report = IterationReport {
+    compile_time,
+    execute_time,
+    instructions: vm.instruction_count(),
+    peak_stack: vm.peak_stack_size(),
+    peak_heap: CountingAllocator::peak().saturating_sub(heap_base),
+    allocations: CountingAllocator::allocations() - allocations_base,
+  };
+  Ok(report)
+}
+
+/// Run the given source with warmup runs, then report each measured iteration.
+pub fn bench(src: &str, options: BenchOptions) -> Result<Vec<IterationReport>, InterpretError> {
+  if !options.reuse_vm {
+    for _ in 0..options.warmup {
+      bench_once(src)?;
+    }
+    return (0..options.iterations).map(|_| bench_once(src)).collect();
+  }
+  let mut vm = VM::init();
+  vm.set_cache_options(CacheOptions {
+    in_memory: false,
+    on_disk: false,
+  });
+  for _ in 0..options.warmup {
+    bench_on(&mut vm, src)?;
+  }
+  let reports = (0..options.iterations)
+    .map(|_| bench_on(&mut vm, src))
+    .collect();
+  vm.free();
+  reports
+}
+
+/// ## StartupReport
+///
+/// Measurements of starting a virtual machine on a script.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StartupReport {
+  /// Creating the virtual machine (with its natives).
+  pub init_time: Duration,
+  pub compile_time: Duration,
+  pub execute_time: Duration,
+}
+
+impl StartupReport {
+  /// Overhead of the interpreter from creating the virtual machine to the end of the script.
+  pub fn total(&self) -> Duration {
+    self.init_time + self.compile_time + self.execute_time
+  }
+}
+
+/// Create a fresh virtual machine, then compile and run the source on it
+/// (values of `print` statements are discarded).
+///
+/// Only the first startup of a process is cold, later ones reuse what it initialized
+/// (e.g. the allocator and tables of the compiler).
+pub fn startup(src: &str) -> Result<StartupReport, InterpretError> {
+  let start = Instant::now();
+  let mut vm = VM::init();
+  let init_time = start.elapsed();
+  vm.set_print(|_| {});
+
+  let start = Instant::now();
+  vm.compile(src.to_owned())?;
+  let compile_time = start.elapsed();
+
+  let start = Instant::now();
+  vm.run()?;
+  let execute_time = start.elapsed();
+
+  vm.free();
+  Ok(StartupReport {
+    init_time,
+    compile_time,
+    execute_time,
+  })
+}
+
+/// Run the command (e.g. `rlox -e "print 1;"`) as a process, with warmup runs,
+/// then return the wall time of each measured run (output of the process is discarded).
+pub fn process_times(command: &mut Command, options: BenchOptions) -> io::Result<Vec<Duration>> {
+  command.stdout(Stdio::null()).stderr(Stdio::null());
+  let mut times = vec![];
+  for iteration in 0..options.warmup + options.iterations {
+    let start = Instant::now();
+    command.status()?;
+    if iteration >= options.warmup {
+      times.push(start.elapsed());
+    }
+  }
+  Ok(times)
+}
+
+/// Print the report of a cold startup, followed by wall times of processes (if any).
+pub fn print_startup(report: &StartupReport, process_times: &[Duration]) {
+  println!(
+    "cold startup: init {:.3}us, compile {:.3}us, execute {:.3}us (total {:.3}us)",
+    micros(report.init_time),
+    micros(report.compile_time),
+    micros(report.execute_time),
+    micros(report.total())
+  );
+  if process_times.is_empty() {
+    return;
+  }
+  let count = process_times.len() as f64;
+  let mean = process_times.iter().map(|&time| micros(time)).sum::<f64>() / count;
+  let min = process_times
+    .iter()
+    .map(|&time| micros(time))
+    .fold(f64::INFINITY, f64::min);
+  println!(
+    "process: mean {:.3}us, min {:.3}us ({} runs)",
+    mean,
+    min,
+    process_times.len()
+  );
+}
+
+/// Print the reports as a table, followed by a summary.
+pub fn print_reports(reports: &[IterationReport]) {
+  println!(
+    "{:>5} {:>14} {:>14} {:>12} {:>10} {:>12} {:>10}",
+    "iter", "compile(us)", "execute(us)", "insts", "stack", "heap(B)", "allocs"
+  );
+  for (i, report) in reports.iter().enumerate() {
+    println!(
+      "{:>5} {:>14.3} {:>14.3} {:>12} {:>10} {:>12} {:>10}",
+      i + 1,
+      micros(report.compile_time),
+      micros(report.execute_time),
+      report.instructions,
+      report.peak_stack,
+      report.peak_heap,
+      report.allocations
+    );
+  }
+  if reports.is_empty() {
+    return;
+  }
+  let count = reports.len() as f64;
+  let mean_compile = reports.iter().map(|r| micros(r.compile_time)).sum::<f64>() / count;
+  let mean_execute = reports.iter().map(|r| micros(r.execute_time)).sum::<f64>() / count;
+  let min_execute = reports
+    .iter()
+    .map(|r| micros(r.execute_time))
+    .fold(f64::INFINITY, f64::min);
+  let max_execute = reports
+    .iter()
+    .map(|r| micros(r.execute_time))
+    .fold(0.0, f64::max);
+  println!(
+    "mean: compile {:.3}us, execute {:.3}us (min {:.3}us, max {:.3}us)",
+    mean_compile, mean_execute, min_execute, max_execute
+  );
+}
+
+fn micros(duration: Duration) -> f64 {
+  duration.as_secs_f64() * 1e6
+}
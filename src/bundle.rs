@@ -0,0 +1,217 @@
+//! # Bundle
+//!
+//! This module contains precompiled bundles, for shipping scripts without their sources.
+//!
+//! A bundle is a script compiled along with all modules it imports (see
+//! [`module`](crate::compiler::module)), written in the format of cache files
+//! (see [`cache`](crate::cache)) with a magic of its own, so the virtual machine runs it
+//! without scanning or parsing anything.
+//!
+//! - `rlox bundle main.lox -o app.loxb` writes the bundle of a script
+//! - `rlox app.loxb` runs it (bundles are told from sources by their magic)
+//! - `rlox build main.lox -o app` writes a self-contained executable: a copy of the running
+//!   rlox with the bundle appended (followed by a trailer of its length and a magic),
+//!   which runs the bundle at startup instead of parsing arguments (see [`embedded_bundle`])
+//!
+//! Like cache files, bundles record the versions of their format and of the instruction set,
+//! and are refused by an incompatible rlox. By default, functions keep their debug info
+//! (with the files they're compiled from), so runtime errors are located as before.
+//! Stripped bundles (`--strip`, see [`BundleOptions`]) leave out files, source locations and
+//! names of functions (which are named by indices instead, e.g. `#3`): their runtime errors
+//! are located as `[unknown location] in #3`.
+
+use crate::{
+  cache::{self, CachedScript, FileKind},
+  compiler::resolution::ResolutionTable,
+  prelude::*,
+  utils::Init,
+  value::Value,
+  vm::{InterpretError, VM},
+};
+
+/// Extension of bundle files (by convention, bundles are told by their magic).
+pub const BUNDLE_EXTENSION: &str = "loxb";
+
+/// ## BundleOptions
+///
+/// Options of writing bundles.
+#[derive(Debug, Clone, Copy)]
+pub struct BundleOptions {
+  /// Keep files, source locations and names of functions (otherwise they're stripped).
+  pub debug_info: bool,
+}
+
+impl Default for BundleOptions {
+  fn default() -> Self {
+    Self { debug_info: true }
+  }
+}
+
+impl Init for BundleOptions {}
+
+/// Trailing bytes of an executable with an embedded bundle (after the length of the bundle).
+#[cfg(feature = "std")]
+const EXECUTABLE_MAGIC: &[u8; 8] = b"LOXBEXE\0";
+
+/// Length of the trailer of an executable with an embedded bundle: the length of the bundle
+/// (as `u64`), then [`EXECUTABLE_MAGIC`].
+#[cfg(feature = "std")]
+const TRAILER_LEN: u64 = 16;
+
+/// If the bytes are (or seem to be) a bundle.
+pub fn is_bundle(bytes: &[u8]) -> bool {
+  bytes.starts_with(FileKind::Bundle.magic())
+}
+
+impl VM {
+  /// Compile the source along with all modules it imports (without running it),
+  /// then return the bytes of its bundle.
+  ///
+  /// Warnings of compiling are reported by [`VM::warnings`], but not written into the bundle.
+  pub fn bundle_source(
+    &mut self,
+    src: String,
+    file: Option<&str>,
+  ) -> Result<Vec<u8>, InterpretError> {
+    self.bundle_source_with(src, file, BundleOptions::default())
+  }
+
+  /// Compile the source into a bundle written with the options (see [`VM::bundle_source`]).
+  pub fn bundle_source_with(
+    &mut self,
+    src: String,
+    file: Option<&str>,
+    options: BundleOptions,
+  ) -> Result<Vec<u8>, InterpretError> {
+    let has_result = self.compile_source(src, file)?;
+    let script = CachedScript {
+      function: unsafe { self.frames[0].function.as_ref() }.clone(),
+      functions: self.functions.clone(),
+      resolutions: ResolutionTable::default(),
+      has_result,
+      warnings: vec![],
+    };
+    cache::encode(FileKind::Bundle, 0, &script, options.debug_info).ok_or_else(|| {
+      InterpretError::CompileError(format!(
+        "Script `{}` holds a constant which can't be bundled.",
+        file.unwrap_or("script")
+      ))
+    })
+  }
+
+  /// Compile the file into a bundle written with the options (see [`VM::bundle_source`]).
+  #[cfg(feature = "std")]
+  pub fn bundle_file(
+    &mut self,
+    path: String,
+    options: BundleOptions,
+  ) -> Result<Vec<u8>, InterpretError> {
+    let Ok(content) = std::fs::read_to_string(&path) else {
+      return Err(InterpretError::CompileError(format!(
+        "Failed to read file `{}`.",
+        path
+      )));
+    };
+    self.bundle_source_with(content, Some(&path), options)
+  }
+
+  /// Load the bundle named `name` (without running it), refusing it if it's written
+  /// by an incompatible rlox or broken.
+  ///
+  /// Returns if the script results in the value of its final expression statement.
+  pub fn load_bundle(&mut self, bytes: &[u8], name: &str) -> Result<bool, InterpretError> {
+    cache::read_header_of(FileKind::Bundle, bytes, name)?;
+    let Some(script) = cache::decode(FileKind::Bundle, bytes, None) else {
+      return Err(InterpretError::CompileError(format!(
+        "Bundle `{}` is broken.",
+        name
+      )));
+    };
+    self.rebind_function(script.function);
+    self.functions = script.functions;
+    self.resolutions = script.resolutions;
+    self.compile_warnings.clear();
+    self.module_files.clear();
+    Ok(script.has_result)
+  }
+
+  /// Load the bundle named `name`, then run it.
+  ///
+  /// Returns the value of the final expression statement (if any) of the script.
+  pub fn interpret_bundle(
+    &mut self,
+    bytes: &[u8],
+    name: &str,
+  ) -> Result<Option<Value>, InterpretError> {
+    let has_result = self.load_bundle(bytes, name)?;
+    let value = self.run()?;
+    Ok(has_result.then_some(value))
+  }
+}
+
+/// Write an executable running the bundle into `out`: the runtime (e.g. the running rlox,
+/// without the bundle it embeds, if any), then the bundle and the trailer.
+///
+/// The executable keeps permissions of the runtime (so it's executable as well).
+#[cfg(feature = "std")]
+pub fn build_executable(
+  runtime: &std::path::Path,
+  bundle: &[u8],
+  out: &std::path::Path,
+) -> std::io::Result<()> {
+  let mut bytes = std::fs::read(runtime)?;
+  if let Some((start, _)) = embedded_range(&bytes) {
+    bytes.truncate(start);
+  }
+  bytes.extend_from_slice(bundle);
+  bytes.extend_from_slice(&(bundle.len() as u64).to_le_bytes());
+  bytes.extend_from_slice(EXECUTABLE_MAGIC);
+  std::fs::write(out, bytes)?;
+  std::fs::set_permissions(out, std::fs::metadata(runtime)?.permissions())
+}
+
+/// The bundle embedded into the executable, if any.
+///
+/// Only the trailer and the bundle are read, as it's checked at each startup of rlox.
+#[cfg(feature = "std")]
+pub fn embedded_bundle(executable: &std::path::Path) -> std::io::Result<Option<Vec<u8>>> {
+  use std::io::{Read, Seek, SeekFrom};
+
+  let mut file = std::fs::File::open(executable)?;
+  let len = file.metadata()?.len();
+  if len < TRAILER_LEN {
+    return Ok(None);
+  }
+  let mut trailer = [0; TRAILER_LEN as usize];
+  file.seek(SeekFrom::Start(len - TRAILER_LEN))?;
+  file.read_exact(&mut trailer)?;
+  let Some((start, end)) = trailer_range(&trailer, len) else {
+    return Ok(None);
+  };
+  let mut bundle = vec![0; (end - start) as usize];
+  file.seek(SeekFrom::Start(start))?;
+  file.read_exact(&mut bundle)?;
+  Ok(Some(bundle))
+}
+
+/// Range of the bundle embedded into the bytes of an executable, if any.
+#[cfg(feature = "std")]
+fn embedded_range(bytes: &[u8]) -> Option<(usize, usize)> {
+  let len = bytes.len() as u64;
+  let trailer = bytes.get(bytes.len().checked_sub(TRAILER_LEN as usize)?..)?;
+  let (start, end) = trailer_range(trailer, len)?;
+  Some((start as usize, end as usize))
+}
+
+/// Range of the bundle embedded into an executable of `len` bytes, by its trailer
+/// (`None` if it's not a trailer).
+#[cfg(feature = "std")]
+fn trailer_range(trailer: &[u8], len: u64) -> Option<(u64, u64)> {
+  let (bundle_len, magic) = trailer.split_at(8);
+  if magic != EXECUTABLE_MAGIC {
+    return None;
+  }
+  let bundle_len = u64::from_le_bytes(bundle_len.try_into().ok()?);
+  let end = len - TRAILER_LEN;
+  Some((end.checked_sub(bundle_len)?, end))
+}
@@ -0,0 +1,626 @@
+//! # Cache
+//!
+//! This module contains the incremental compilation cache.
+//!
+//! Compiled scripts are keyed by the hash of their source (with file name and
+//! compile options), so compiling an unchanged source skips the scanner/parser entirely.
+//!
+//! - in memory: each [`VM`](crate::vm::VM) keeps the scripts it compiled
+//! - on disk (opt-in, only with the `std` feature): a file script is cached in `<script>.loxc`,
+//!   next to the script
+//!
+//! Strict mode is never cached, as its result depends on globals of the virtual machine.
+//!
+//! A cache file records the versions of its format and of the instruction set
+//! (see [`ISA_VERSION`]). Files written by an incompatible rlox are refused with a warning,
+//! then the script is compiled again (and the file is replaced, see [`read_header`]).
+//!
+//! Bundles (see [`bundle`](crate::bundle)) are written in the same format, with a magic of their own.
+
+// the file format is only written and read with `std`
+#![cfg_attr(not(feature = "std"), allow(dead_code))]
+
+#[cfg(feature = "std")]
+use std::path::{Path, PathBuf};
+
+use alloc::rc::Rc;
+use core::ptr::NonNull;
+use hashbrown::HashMap;
+
+use crate::{
+  arena::Arena,
+  chunk::{Chunk, DebugInfo, SourceLocation},
+  compiler::{
+    function_table::FunctionTable, plugin::Plugins, resolution::ResolutionTable, CompileOptions,
+  },
+  isa::ISA_VERSION,
+  object::{ObjFunction, ObjString, ObjType, Upvalue},
+  prelude::*,
+  utils::Init,
+  value::{Value, ValueType},
+  vm::InterpretError,
+};
+
+/// ## FileKind
+///
+/// Kinds of files written in the format of cache files, each led by a magic of its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FileKind {
+  /// A cache file of a script, keyed by its source.
+  Cache,
+  /// A bundle of a script and all modules it imports (its key is unused).
+  Bundle,
+}
+
+impl FileKind {
+  /// Leading bytes of files of the kind.
+  pub(crate) fn magic(self) -> &'static [u8; 4] {
+    match self {
+      FileKind::Cache => b"LOXC",
+      FileKind::Bundle => b"LOXB",
+    }
+  }
+
+  /// Name of the kind, capitalized (as errors lead with it) or not.
+  fn name(self, capitalized: bool) -> &'static str {
+    match (self, capitalized) {
+      (FileKind::Cache, true) => "Cache file",
+      (FileKind::Cache, false) => "cache file",
+      (FileKind::Bundle, true) => "Bundle",
+      (FileKind::Bundle, false) => "bundle",
+    }
+  }
+}
+
+/// Version of the cache file format, bumped on each change of the format
+/// (changes of bytecode bump [`ISA_VERSION`] instead).
+pub const FORMAT_VERSION: u32 = 10;
+
+/// ## CacheOptions
+///
+/// Options of the incremental compilation cache.
+#[derive(Debug, Clone, Copy)]
+pub struct CacheOptions {
+  /// Keep compiled scripts in memory.
+  pub in_memory: bool,
+  /// Keep compiled file scripts on disk, next to the script.
+  pub on_disk: bool,
+}
+
+impl Default for CacheOptions {
+  fn default() -> Self {
+    Self {
+      in_memory: true,
+      on_disk: false,
+    }
+  }
+}
+
+impl Init for CacheOptions {}
+
+/// ## CachedScript
+///
+/// A compiled script, with everything reported by compiling it.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct CachedScript {
+  pub(crate) function: ObjFunction,
+  pub(crate) functions: FunctionTable,
+  /// Resolutions of variables (kept in memory only).
+  pub(crate) resolutions: ResolutionTable,
+  pub(crate) has_result: bool,
+  pub(crate) warnings: Vec<String>,
+}
+
+/// ## CompileCache
+///
+/// Compiled scripts keyed by [`source_key`].
+#[derive(Debug, Default)]
+pub struct CompileCache {
+  pub(crate) options: CacheOptions,
+  pub(crate) entries: HashMap<u64, CachedScript>,
+  /// Count of compilings skipped.
+  pub(crate) hits: usize,
+  /// Count of compilings done (and cached).
+  pub(crate) misses: usize,
+  /// Why the cache file of the script looked up last is refused (if it is).
+  pub(crate) refused: Option<String>,
+}
+
+impl Init for CompileCache {}
+
+impl CompileCache {
+  /// Look up the script in memory, then on disk (loaded into memory on hit).
+  ///
+  /// A cache file written by an incompatible rlox is refused, see [`CompileCache::take_refusal`].
+  pub(crate) fn get(&mut self, key: u64, file: Option<&str>) -> Option<CachedScript> {
+    self.refused = None;
+    let script = self.entries.get(&key).cloned().or_else(|| {
+      let file = file.filter(|_| self.options.on_disk)?;
+      let script = match load(file, key) {
+        Ok(script) => script?,
+        Err(error) => {
+          self.refused = Some(error.into_message());
+          return None;
+        }
+      };
+      if self.options.in_memory {
+        self.entries.insert(key, script.clone());
+      }
+      Some(script)
+    });
+    if script.is_some() {
+      self.hits += 1;
+    }
+    script
+  }
+
+  /// Record a freshly compiled script.
+  ///
+  /// Writing to disk is best-effort, failures just leave the cache cold.
+  pub(crate) fn insert(&mut self, key: u64, file: Option<&str>, script: CachedScript) {
+    self.misses += 1;
+    if let Some(file) = file.filter(|_| self.options.on_disk) {
+      store(file, key, &script);
+    }
+    if self.options.in_memory {
+      self.entries.insert(key, script);
+    }
+  }
+
+  /// Count of compilings skipped by the cache.
+  pub fn hits(&self) -> usize {
+    self.hits
+  }
+
+  /// Count of compilings done and recorded into the cache.
+  pub fn misses(&self) -> usize {
+    self.misses
+  }
+
+  /// Why the cache file of the script looked up last is refused, if it's written
+  /// by an incompatible rlox (reported as a warning of compiling the script).
+  pub(crate) fn take_refusal(&mut self) -> Option<String> {
+    self.refused.take()
+  }
+
+  /// Forget all scripts kept in memory.
+  pub fn clear(&mut self) {
+    self.entries.clear();
+  }
+}
+
+/// Key of a script: 64-bit FNV-1a hash of its source, file name, compile options, plugins,
+/// and if files could be imported (see [`Capability::Files`](crate::native::Capability::Files)).
+///
+/// FNV is used (instead of `DefaultHasher`) as keys are stored on disk.
+pub fn source_key(
+  src: &str,
+  file: Option<&str>,
+  options: &CompileOptions,
+  plugins: &Plugins,
+  files: bool,
+) -> u64 {
+  let mut hash: u64 = 0xcbf29ce484222325;
+  let mut feed = |bytes: &[u8]| {
+    for &byte in bytes {
+      hash ^= byte as u64;
+      hash = hash.wrapping_mul(0x100000001b3);
+    }
+  };
+  feed(&FORMAT_VERSION.to_le_bytes());
+  feed(&ISA_VERSION.to_le_bytes());
+  feed(src.as_bytes());
+  feed(&[0xff]);
+  feed(file.unwrap_or_default().as_bytes());
+  feed(&[
+    options.warn_shadowing as u8,
+    options.strict as u8,
+    options.opt_level as u8,
+    files as u8,
+    0xff,
+  ]);
+  feed(&(options.max_nesting_depth as u64).to_le_bytes());
+  // formatting the standard dialect (on each compiling) is skipped
+  if !plugins.operators.is_empty() || !plugins.keywords.is_standard() {
+    feed(format!("{:?}", plugins.operators).as_bytes());
+    feed(format!("{:?}", plugins.keywords).as_bytes());
+  }
+  hash
+}
+
+/// Path of the cache file of the given script, e.g. `main.lox` => `main.loxc`.
+#[cfg(feature = "std")]
+pub fn cache_path(file: &str) -> PathBuf {
+  let path = Path::new(file);
+  match path.extension() {
+    Some(extension) => path.with_extension(format!("{}c", extension.to_string_lossy())),
+    None => path.with_extension("loxc"),
+  }
+}
+
+/// Write the script into the cache file of `file`.
+#[cfg(feature = "std")]
+fn store(file: &str, key: u64, script: &CachedScript) {
+  if let Some(buffer) = encode(FileKind::Cache, key, script, true) {
+    let _ = std::fs::write(cache_path(file), buffer);
+  }
+}
+
+/// There's no file system without `std`, so nothing is stored.
+#[cfg(not(feature = "std"))]
+fn store(_: &str, _: u64, _: &CachedScript) {}
+
+/// Read the script from the cache file of `file`
+/// (`Ok(None)` if there's none, or it's stale or broken).
+#[cfg(feature = "std")]
+fn load(file: &str, key: u64) -> Result<Option<CachedScript>, InterpretError> {
+  let path = cache_path(file);
+  let Ok(buffer) = std::fs::read(&path) else {
+    return Ok(None);
+  };
+  read_header(&buffer, &path.to_string_lossy())?;
+  Ok(decode(FileKind::Cache, &buffer, Some(key)))
+}
+
+/// There's no file system without `std`, so nothing is loaded.
+#[cfg(not(feature = "std"))]
+fn load(_: &str, _: u64) -> Result<Option<CachedScript>, InterpretError> {
+  Ok(None)
+}
+
+/// ## CacheHeader
+///
+/// Leading fields of a cache file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheHeader {
+  pub format_version: u32,
+  pub isa_version: u32,
+  /// Key of the cached script (see [`source_key`]).
+  pub key: u64,
+}
+
+/// Read the header of the cache file named `name`, refusing it if it's written
+/// by an incompatible rlox (with both versions in the error).
+pub fn read_header(buffer: &[u8], name: &str) -> Result<CacheHeader, InterpretError> {
+  read_header_of(FileKind::Cache, buffer, name)
+}
+
+/// Read the header of the file of the kind named `name` (see [`read_header`]).
+pub(crate) fn read_header_of(
+  kind: FileKind,
+  buffer: &[u8],
+  name: &str,
+) -> Result<CacheHeader, InterpretError> {
+  let mut reader = Reader {
+    buffer,
+    offset: 0,
+    functions: FunctionTable::default(),
+    arena: Arena::default(),
+  };
+  reader.header(kind).map_err(|message| {
+    InterpretError::CompileError(format!("{} `{}` {}", kind.name(true), name, message))
+  })
+}
+
+/// Bytes of the file of the script (`None` if it can't be written).
+///
+/// Functions are written once (in order of the function table, so each one comes after
+/// those defined in it), then function constants are written as indices into the table.
+///
+/// Without debug info, functions are written without files and source locations,
+/// and named by their indices into the table (e.g. `#3`).
+pub(crate) fn encode(
+  kind: FileKind,
+  key: u64,
+  script: &CachedScript,
+  debug_info: bool,
+) -> Option<Vec<u8>> {
+  let mut writer = Writer {
+    buffer: Vec::new(),
+    functions: &script.functions,
+    debug_info,
+  };
+  writer.bytes(kind.magic());
+  writer.u32(FORMAT_VERSION);
+  writer.u32(ISA_VERSION);
+  writer.u64(key);
+  writer.u8(script.has_result as u8);
+  writer.u32(script.warnings.len() as u32);
+  for warning in &script.warnings {
+    writer.str(warning);
+  }
+  writer.u32(script.functions.len() as u32);
+  for (index, entry) in script.functions.entries.iter().enumerate() {
+    match debug_info {
+      true => writer.str(&entry.name),
+      false => writer.str(&stripped_name(index)),
+    }
+    writer.function(unsafe { entry.function.as_ref() })?;
+  }
+  writer.function(&script.function)?;
+  Some(writer.buffer)
+}
+
+/// The script in the bytes of the file (`None` if it's stale, i.e. not of the key if given,
+/// or broken).
+pub(crate) fn decode(kind: FileKind, buffer: &[u8], key: Option<u64>) -> Option<CachedScript> {
+  let mut reader = Reader {
+    buffer,
+    offset: 0,
+    functions: FunctionTable::default(),
+    arena: Arena::default(),
+  };
+  let header = reader.header(kind).ok()?;
+  if key.is_some_and(|key| key != header.key) {
+    return None;
+  }
+  let has_result = reader.u8()? != 0;
+  let warnings = (0..reader.u32()?)
+    .map(|_| reader.str())
+    .collect::<Option<Vec<_>>>()?;
+  for _ in 0..reader.u32()? {
+    let name = reader.str()?;
+    let function = reader.function(Arena::default())?;
+    let function = reader.arena.alloc_function(function).cast();
+    reader.functions.push(name, function);
+  }
+  let function = reader.function(reader.arena.clone())?;
+  (reader.offset == buffer.len()).then_some(CachedScript {
+    function,
+    functions: reader.functions,
+    resolutions: ResolutionTable::default(),
+    has_result,
+    warnings,
+  })
+}
+
+/// Name of the function of the index in the function table, written without debug info.
+fn stripped_name(index: usize) -> String {
+  format!("#{}", index)
+}
+
+/// Tags of constants in a cache file.
+const TAG_NIL: u8 = 0;
+const TAG_BOOL: u8 = 1;
+const TAG_NUMBER: u8 = 2;
+const TAG_STRING: u8 = 3;
+const TAG_FUNCTION: u8 = 4;
+
+struct Writer<'a> {
+  buffer: Vec<u8>,
+  /// Functions of the script, which function constants refer to.
+  functions: &'a FunctionTable,
+  /// If files, source locations and names of functions are written.
+  debug_info: bool,
+}
+
+impl Writer<'_> {
+  fn bytes(&mut self, bytes: &[u8]) {
+    self.buffer.extend_from_slice(bytes);
+  }
+
+  fn u8(&mut self, byte: u8) {
+    self.buffer.push(byte);
+  }
+
+  fn u32(&mut self, value: u32) {
+    self.bytes(&value.to_le_bytes());
+  }
+
+  fn u64(&mut self, value: u64) {
+    self.bytes(&value.to_le_bytes());
+  }
+
+  fn str(&mut self, value: &str) {
+    self.u32(value.len() as u32);
+    self.bytes(value.as_bytes());
+  }
+
+  /// Returns `None` if the function holds a constant which can't be cached.
+  fn function(&mut self, function: &ObjFunction) -> Option<()> {
+    match (&function.name, self.debug_info) {
+      (Some(name), true) => {
+        self.u8(1);
+        self.str(&name.data);
+      }
+      (Some(_), false) => {
+        self.u8(1);
+        let index = self.functions.index_of(NonNull::from(function))?;
+        self.str(&stripped_name(index));
+      }
+      (None, _) => self.u8(0),
+    }
+    // functions of imported modules are located in files of their own
+    match &function.chunk.debug_info.file {
+      Some(file) if self.debug_info => {
+        self.u8(1);
+        self.str(file);
+      }
+      _ => self.u8(0),
+    }
+    self.u32(function.arity as u32);
+    self.u32(function.required_arity as u32);
+    self.u32(function.upvalues.len() as u32);
+    for upvalue in &function.upvalues {
+      self.u8(upvalue.is_local as u8);
+      self.u32(upvalue.index as u32);
+    }
+    let chunk = &function.chunk;
+    self.u32(chunk.code.len() as u32);
+    self.bytes(&chunk.code);
+    // source locations of all bytes (or none of them)
+    let debug_info = match self.debug_info {
+      true => &chunk.debug_info,
+      false => &DebugInfo::default(),
+    };
+    let has_locations = !debug_info.locations.is_empty();
+    self.u8(has_locations as u8);
+    for offset in (0..chunk.code.len()).filter(|_| has_locations) {
+      let location = debug_info.location(offset);
+      self.u32(location.line as u32);
+      self.u32(location.column as u32);
+    }
+    self.u32(debug_info.statements.len() as u32);
+    for &offset in &debug_info.statements {
+      self.u32(offset as u32);
+    }
+    self.u32(chunk.constants.values.len() as u32);
+    for value in &chunk.constants.values {
+      self.constant(value)?;
+    }
+    Some(())
+  }
+
+  fn constant(&mut self, value: &Value) -> Option<()> {
+    match value.value_type {
+      ValueType::Nil => self.u8(TAG_NIL),
+      ValueType::Bool => {
+        self.u8(TAG_BOOL);
+        self.u8(value.as_bool() as u8);
+      }
+      ValueType::Number => {
+        self.u8(TAG_NUMBER);
+        self.u64(value.as_number().to_bits());
+      }
+      ValueType::Obj => match value.obj_type().ok()? {
+        ObjType::String => {
+          self.u8(TAG_STRING);
+          self.str(value.as_rust_string().ok()?);
+        }
+        ObjType::Function => {
+          self.u8(TAG_FUNCTION);
+          let index = self.functions.index_of(value.as_function().ok()?)?;
+          self.u32(index as u32);
+        }
+        _ => return None,
+      },
+    }
+    Some(())
+  }
+}
+
+struct Reader<'a> {
+  buffer: &'a [u8],
+  offset: usize,
+  /// Functions read so far, which function constants refer to.
+  functions: FunctionTable,
+  /// Arena of the chunk of the script, where functions read before it are allocated.
+  arena: Arena,
+}
+
+impl<'a> Reader<'a> {
+  /// Magic, versions and key, which must be compatible with this rlox
+  /// (otherwise the rest of the error message).
+  fn header(&mut self, kind: FileKind) -> Result<CacheHeader, String> {
+    if self.bytes(4) != Some(kind.magic()) {
+      return Err(format!("isn't a {} of rlox.", kind.name(false)));
+    }
+    let (Some(format_version), Some(isa_version), Some(key)) = (self.u32(), self.u32(), self.u64())
+    else {
+      return Err("is truncated.".into());
+    };
+    if format_version != FORMAT_VERSION {
+      return Err(format!(
+        "is written in format v{}, but this rlox reads format v{}.",
+        format_version, FORMAT_VERSION
+      ));
+    }
+    if isa_version != ISA_VERSION {
+      return Err(format!(
+        "is compiled for instruction set v{}, but this rlox runs instruction set v{}.",
+        isa_version, ISA_VERSION
+      ));
+    }
+    Ok(CacheHeader {
+      format_version,
+      isa_version,
+      key,
+    })
+  }
+
+  fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+    let bytes = self
+      .buffer
+      .get(self.offset..self.offset.checked_add(len)?)?;
+    self.offset += len;
+    Some(bytes)
+  }
+
+  fn u8(&mut self) -> Option<u8> {
+    Some(self.bytes(1)?[0])
+  }
+
+  fn u32(&mut self) -> Option<u32> {
+    Some(u32::from_le_bytes(self.bytes(4)?.try_into().ok()?))
+  }
+
+  fn u64(&mut self) -> Option<u64> {
+    Some(u64::from_le_bytes(self.bytes(8)?.try_into().ok()?))
+  }
+
+  fn str(&mut self) -> Option<String> {
+    let len = self.u32()? as usize;
+    String::from_utf8(self.bytes(len)?.to_vec()).ok()
+  }
+
+  /// A function, with its chunk allocating in the given arena.
+  fn function(&mut self, arena: Arena) -> Option<ObjFunction> {
+    let name = match self.u8()? {
+      0 => None,
+      _ => Some(ObjString::from(self.str()?)),
+    };
+    let file = match self.u8()? {
+      0 => None,
+      _ => Some(Rc::from(self.str()?)),
+    };
+    let mut function = ObjFunction::new(name);
+    function.arity = self.u32()? as usize;
+    function.required_arity = self.u32()? as usize;
+    for _ in 0..self.u32()? {
+      let is_local = self.u8()? != 0;
+      let index = self.u32()? as usize;
+      function.upvalues.push(Upvalue { index, is_local });
+    }
+    let mut chunk = Chunk {
+      arena,
+      ..Chunk::init()
+    };
+    let len = self.u32()? as usize;
+    chunk.code = self.bytes(len)?.to_vec();
+    if self.u8()? != 0 {
+      for _ in 0..len {
+        let location = SourceLocation {
+          line: self.u32()? as usize,
+          column: self.u32()? as usize,
+        };
+        chunk.debug_info.locations.push(location);
+      }
+    }
+    for _ in 0..self.u32()? {
+      chunk.debug_info.statements.push(self.u32()? as usize);
+    }
+    chunk.debug_info.file = file;
+    for _ in 0..self.u32()? {
+      let value = self.constant(&mut chunk)?;
+      chunk.add_constant(value);
+    }
+    function.chunk = chunk;
+    Some(function)
+  }
+
+  /// A constant of the chunk (strings are allocated in its arena).
+  fn constant(&mut self, chunk: &mut Chunk) -> Option<Value> {
+    let value = match self.u8()? {
+      TAG_NIL => Value::nil_val(),
+      TAG_BOOL => Value::bool_val(self.u8()? != 0),
+      TAG_NUMBER => Value::number_val(f64::from_bits(self.u64()?)),
+      TAG_STRING => chunk.alloc_string(self.str()?),
+      TAG_FUNCTION => {
+        let index = self.u32()? as usize;
+        Value::obj_val(self.functions.entries.get(index)?.function.cast())
+      }
+      _ => return None,
+    };
+    Some(value)
+  }
+}
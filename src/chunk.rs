@@ -4,16 +4,20 @@
 //! with it's dependent components.
 
 use crate::{
+  arena::Arena,
+  object::{ObjFunction, ObjString},
+  prelude::*,
   utils::Init,
   value::{Value, ValueArray},
 };
-use enum_repr::EnumU8;
+use alloc::rc::Rc;
+use enum_repr::EnumTryU8;
 
 /// ## OpCode
 ///
 /// An enum which represents the different opcodes used in the
 /// virtual machine.
-#[derive(EnumU8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumTryU8)]
 pub enum OpCode {
   /* Constants */
   Constant,
@@ -27,45 +31,200 @@ pub enum OpCode {
   NotEqual,
   GreaterEqual,
   LessEqual,
+  Is,
+  Contains,
   /* Binary Arith Opts */
   Add,
   Subtract,
   Multiply,
   Divide,
+  FloorDivide,
   /* Unary Arith Opts */
   Not,
   Negate,
   /* Control Flow Opts */
   JumpIfFalse,
   Jump,
+  Loop,
   /* Helper Opts */
   Print,
   Pop,
+  PopN,
+  Peek,
+  Sink,
+  Stringify,
   /* Variable Getters/Setters */
   DefineGlobal,
   GetGlobal,
   GetLocal,
   SetGlobal,
   SetLocal,
+  GetLocalLong,
+  SetLocalLong,
+  MissingArgument,
+  /* Function Opts */
+  Call,
+  CallGlobal,
+  Closure,
+  GetUpvalue,
+  SetUpvalue,
+  CloseUpvalue,
+  /* Class Opts */
+  Class,
+  Trait,
+  Mixin,
+  Inherit,
+  GetProperty,
+  SetProperty,
+  DeleteProperty,
+  Method,
+  Getter,
+  Setter,
+  Invoke,
+  GetSuper,
+  SuperInvoke,
+  Enum,
+  EnumMember,
+  /* List Opts */
+  BuildList,
+  GetIndex,
+  GetSlice,
+  SetIndex,
+  ExtendList,
+  SpreadCall,
+  SpreadInvoke,
+  SpreadSuperInvoke,
+  Unpack,
+  MatchList,
+  MatchListRest,
+  /* Module Opts */
+  Import,
+  /* Exception Opts */
+  PushHandler,
+  PopHandler,
+  Throw,
   /* Return */
   Return,
 }
 
+/// ## SourceLocation
+///
+/// Where a byte of bytecode comes from in the source code.
+///
+/// Column starts from `1`, `0` means it's unknown.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SourceLocation {
+  pub line: usize,
+  pub column: usize,
+}
+
+impl SourceLocation {
+  /// Describe the location as `line L`, or `file:L:C` if the file is known.
+  pub fn describe(&self, file: Option<&str>) -> String {
+    match file {
+      Some(file) => format!("{}:{}:{}", file, self.line, self.column),
+      None => format!("line {}", self.line),
+    }
+  }
+}
+
+/// ## DebugInfo
+///
+/// Debug-info table of a chunk, which holds the source file of the chunk,
+/// the source location of each byte, and where each statement starts.
+#[derive(Debug, Default, Clone)]
+pub struct DebugInfo {
+  /// Name of the source file (`None` if compiled from a string, e.g. REPL lines).
+  pub(crate) file: Option<Rc<str>>,
+  /// Source location of each byte.
+  pub(crate) locations: Vec<SourceLocation>,
+  /// Offset of the first byte of each statement, in ascending order.
+  pub(crate) statements: Vec<usize>,
+}
+
+impl DebugInfo {
+  /// Name of the source file.
+  pub fn file(&self) -> Option<&str> {
+    self.file.as_deref()
+  }
+
+  /// Source location of the byte at the given offset.
+  ///
+  /// Offsets out of range are located at the last byte.
+  pub fn location(&self, offset: usize) -> SourceLocation {
+    self
+      .locations
+      .get(offset)
+      .or(self.locations.last())
+      .copied()
+      .unwrap_or_default()
+  }
+
+  /// If a statement starts at the byte of the given offset.
+  pub fn starts_statement(&self, offset: usize) -> bool {
+    self.statements.binary_search(&offset).is_ok()
+  }
+
+  /// If a line starts at the byte of the given offset
+  /// (the previous byte, if any, is on another line).
+  pub fn starts_line(&self, offset: usize) -> bool {
+    offset == 0 || self.location(offset - 1).line != self.location(offset).line
+  }
+
+  /// Describe the location of the byte at the given offset (with the file),
+  /// or `unknown location` if source locations are stripped (see [`bundle`](crate::bundle)).
+  pub fn describe(&self, offset: usize) -> String {
+    if self.locations.is_empty() {
+      return "unknown location".into();
+    }
+    self.location(offset).describe(self.file())
+  }
+}
+
 /// ## Chunk
 ///
 /// A struct which represents a chunk/sequence of bytecode.
 #[derive(Debug, Default, Clone)]
 pub struct Chunk {
   pub(crate) code: Vec<u8>,
-  pub(crate) lines: Vec<usize>,
+  pub(crate) debug_info: DebugInfo,
   pub(crate) constants: ValueArray,
+  /// Objects created while compiling the chunk (identifiers, literals and functions).
+  pub(crate) arena: Arena,
 }
 
 impl Chunk {
   /// Write a byte to the given chunk.
   pub fn write_chunk(&mut self, byte: u8, line: usize) {
+    self.write_chunk_at(byte, SourceLocation { line, column: 0 });
+  }
+
+  /// Write a byte (from the given source location) to the given chunk.
+  pub fn write_chunk_at(&mut self, byte: u8, location: SourceLocation) {
     self.code.push(byte);
-    self.lines.push(line);
+    self.debug_info.locations.push(location);
+  }
+
+  /// Mark the next byte written to the given chunk as the start of a statement.
+  pub(crate) fn mark_statement(&mut self) {
+    let offset = self.code.len();
+    if self.debug_info.statements.last() != Some(&offset) {
+      self.debug_info.statements.push(offset);
+    }
+  }
+
+  /// Drop the last byte of the given chunk.
+  pub(crate) fn pop_byte(&mut self) -> Option<u8> {
+    self.debug_info.locations.pop();
+    let byte = self.code.pop();
+    let len = self.code.len();
+    self.debug_info.statements.retain(|&offset| offset <= len);
+    byte
+  }
+
+  /// Debug-info table of the given chunk.
+  pub fn debug_info(&self) -> &DebugInfo {
+    &self.debug_info
   }
 
   /// Add a constant to the given chunk,
@@ -75,6 +234,23 @@ impl Chunk {
     self.constants.values.len() - 1
   }
 
+  /// Allocate a string in the arena of the given chunk (see [`Arena`]),
+  /// which lives as long as the chunk (or any copy of it).
+  pub fn alloc_string(&mut self, string: String) -> Value {
+    Value::obj_val(self.arena.alloc_string(ObjString::from(string)))
+  }
+
+  /// Allocate a function defined in the given chunk in its arena (see [`Arena`]),
+  /// which lives as long as the chunk (or any copy of it).
+  pub fn alloc_function(&mut self, function: ObjFunction) -> Value {
+    Value::obj_val(self.arena.alloc_function(function))
+  }
+
+  /// Objects allocated in the given chunk.
+  pub fn arena(&self) -> &Arena {
+    &self.arena
+  }
+
   /// Clear the given chunk.
   pub fn free(&mut self) {
     self.code.resize(0, Default::default());
@@ -7,14 +7,27 @@
 //! It is responsible for executing the bytecode.
 
 use crate::{
-  scanner::{Scanner, Token, TokenType},
+  cache::{source_key, CachedScript},
+  native::Capability,
+  object::{ObjFunction, ObjString},
+  prelude::*,
+  scanner::{Token, TokenType},
   utils::Init,
   vm::{InterpretError, VM},
 };
 
 use self::parser::Parser;
+use alloc::rc::Rc;
+use hashbrown::HashMap;
 
+pub mod function_table;
+pub mod module;
+pub mod optimizer;
 pub mod parser;
+pub mod plugin;
+pub mod resolution;
+pub mod stats;
+pub mod work_buffer;
 
 /// ## Precedence
 ///
@@ -22,10 +35,12 @@ pub mod parser;
 ///
 /// These are all of Lox’s precedence levels,
 /// in order from lowest to highest.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, enum_repr::EnumU8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, enum_repr::EnumU8)]
 pub enum Precedence {
-  None,       // No precedence (reserved for errors)
+  #[default]
+  None, // No precedence (reserved for errors)
   Assignment, // =
+  Ternary,    // ?:
   Or,         // or
   And,        // and
   Equality,   // == !=
@@ -47,11 +62,16 @@ impl Precedence {
     }
   }
 }
-impl Default for Precedence {
-  fn default() -> Self {
-    Self::None
-  }
-}
+
+/// Count of locals addressable by one byte slot (`GetLocal`/`SetLocal`).
+pub const SHORT_LOCALS_MAX: usize = u8::MAX as usize + 1;
+
+/// Count of locals addressable at all, the rest are spilled into
+/// two bytes slot (`GetLocalLong`/`SetLocalLong`).
+pub const LOCALS_MAX: usize = u16::MAX as usize + 1;
+
+/// Count of variables a function could capture (`GetUpvalue`/`SetUpvalue`).
+pub const UPVALUES_MAX: usize = u8::MAX as usize + 1;
 
 /// ## Local
 ///
@@ -64,6 +84,24 @@ pub struct Local {
   pub(crate) depth: usize,
   /// If the bind of local variable initialized.
   pub(crate) is_initialized: bool,
+  /// Index of its declaration in the resolution table (`None` for the implicit slot zero).
+  pub(crate) declaration: Option<usize>,
+  /// If it's captured by a closure, so it's closed (rather than popped) at the end of its scope.
+  pub(crate) is_captured: bool,
+}
+
+/// ## FunctionType
+///
+/// An enum which represents the kind of function being compiled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FunctionType {
+  Function,
+  Getter,
+  Initializer,
+  Method,
+  Setter,
+  #[default]
+  Script,
 }
 
 /// ## Compiler
@@ -71,8 +109,17 @@ pub struct Local {
 /// A struct which represents the compiler.
 ///
 /// Mainly deal with `local variables` via a flat array of all locals.
+///
+/// Each function being compiled owns one compiler,
+/// compilers of enclosing functions are chained via `enclosing`.
 #[derive(Debug, Clone)]
 pub struct Compiler {
+  /// Compiler of the enclosing function (`None` for the top-level script).
+  pub(crate) enclosing: Option<Box<Compiler>>,
+  /// The function being compiled.
+  pub(crate) function: ObjFunction,
+  /// The kind of the function being compiled.
+  pub(crate) function_type: FunctionType,
   /// Represents all locals.
   pub(crate) locals: Vec<Local>,
   /// Tracks how many locals are in scope.
@@ -80,45 +127,267 @@ pub struct Compiler {
   pub(crate) local_count: usize,
   /// Tracks the number of blocks surrounding the current bit of code
   pub(crate) scope_depth: usize,
+  /// Index of each string constant added so far, so equal strings share one constant.
+  pub(crate) string_constants: HashMap<String, u8>,
+  /// Name of the function qualified by enclosing functions and classes (empty for the script).
+  pub(crate) qualified_name: String,
+  /// Declaration of the variable captured by each upvalue (see [`ObjFunction::upvalues`]).
+  pub(crate) captures: Vec<Option<usize>>,
 }
 
 impl Default for Compiler {
   fn default() -> Self {
+    Self::new(FunctionType::Script, None)
+  }
+}
+
+impl Init for Compiler {}
+
+impl Compiler {
+  /// Create a compiler for a function with the given type and name.
+  ///
+  /// Slot zero is claimed for the function being called itself,
+  /// so it's given an empty name which can't be referenced by users.
+  ///
+  /// Inside methods (and accessors), slot zero holds the receiver, which is named `this`.
+  ///
+  /// Other slots are pushed once they're declared.
+  pub fn new(function_type: FunctionType, name: Option<ObjString>) -> Self {
+    let mut locals = vec![Local::default()];
+    locals[0].is_initialized = true;
+    if !matches!(function_type, FunctionType::Function | FunctionType::Script) {
+      locals[0].name.lexeme = "this".into();
+    }
     Self {
-      locals: vec![Local::default(); u8::MAX as usize + 1],
-      local_count: 0,
+      enclosing: None,
+      function: ObjFunction::new(name),
+      function_type,
+      locals,
+      local_count: 1,
       scope_depth: 0,
+      string_constants: HashMap::new(),
+      qualified_name: String::new(),
+      captures: Vec::new(),
     }
   }
 }
 
-impl Init for Compiler {}
+/// ## OptLevel
+///
+/// An enum which represents which passes are run on the compiled bytecode.
+///
+/// See [`optimizer`] for the passes of each level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum OptLevel {
+  /// No passes, bytecode is kept as emitted.
+  #[default]
+  O0,
+  /// Constant folding and constant deduplication.
+  O1,
+  /// `O1`, with peephole and superinstructions.
+  O2,
+}
+
+/// Default limit of nested expressions and statements.
+pub const NESTING_DEPTH_MAX: usize = 256;
+
+/// ## CompileOptions
+///
+/// Opt-in diagnostics and limits of the compiler.
+#[derive(Debug, Clone, Copy)]
+pub struct CompileOptions {
+  /// Warn when a local shadows an outer local or global of the same name.
+  pub warn_shadowing: bool,
+  /// Strict mode: assigning to an undeclared global is a compile error.
+  pub strict: bool,
+  /// How deep expressions and statements could be nested,
+  /// which keeps the (recursive descent) parser from overflowing the host stack.
+  pub max_nesting_depth: usize,
+  /// Passes run on the compiled bytecode.
+  pub opt_level: OptLevel,
+}
+
+impl Default for CompileOptions {
+  fn default() -> Self {
+    Self {
+      warn_shadowing: false,
+      strict: false,
+      max_nesting_depth: NESTING_DEPTH_MAX,
+      opt_level: OptLevel::default(),
+    }
+  }
+}
+
+impl Init for CompileOptions {}
+
+/// ## ClassCompiler
+///
+/// A struct which tracks the innermost class being compiled,
+/// classes of enclosing ones are chained via `enclosing`.
+#[derive(Debug, Clone, Default)]
+pub struct ClassCompiler {
+  /// Compiler of the enclosing class (`None` for the outermost one).
+  pub(crate) enclosing: Option<Box<ClassCompiler>>,
+  /// Name of the class, which qualifies names of its methods.
+  pub(crate) name: String,
+  /// If it inherits from a superclass (held by the hidden local `super`).
+  pub(crate) has_superclass: bool,
+}
 
 impl VM {
   /// This function will compile the source code into bytecode.
   ///
-  /// Chunk which holds bytecode sequence is created by `Parser`,
-  /// and then loaded(moved) into VM.
-  pub(crate) fn compile(&mut self, src: String) -> Result<(), InterpretError> {
-    // parse
+  /// The top-level `script` function which holds bytecode sequence
+  /// is created by `Parser`, and then loaded(moved) into VM.
+  ///
+  /// Returns if the script results in the value of its final expression statement.
+  pub(crate) fn compile(&mut self, src: String) -> Result<bool, InterpretError> {
+    self.compile_source(src, None)
+  }
+
+  /// Compile the source, diagnostics and debug info are located in `file` (if given).
+  ///
+  /// Unchanged sources are loaded from the compile cache instead.
+  pub(crate) fn compile_source(
+    &mut self,
+    src: String,
+    file: Option<&str>,
+  ) -> Result<bool, InterpretError> {
+    let options = self.compile_options;
+    let files = self.capabilities.allows(Capability::Files);
+    let key = (!options.strict).then(|| source_key(&src, file, &options, &self.plugins, files));
+    if let Some(script) = key.and_then(|key| self.compile_cache.get(key, file)) {
+      self.rebind_function(script.function);
+      self.functions = script.functions;
+      self.resolutions = script.resolutions;
+      self.compile_warnings = script.warnings;
+      self.module_files.clear();
+      return Ok(script.has_result);
+    }
+    let has_result = self.parse_source(src, file)?;
+    if let Some(refusal) = self.compile_cache.take_refusal() {
+      self
+        .compile_warnings
+        .push(format!("{} It's compiled again.", refusal));
+    }
+    // edits of imported files wouldn't be noticed by the key
+    if let Some(key) = key.filter(|_| self.module_files.is_empty()) {
+      let script = CachedScript {
+        function: unsafe { self.frames[0].function.as_ref() }.clone(),
+        functions: self.functions.clone(),
+        resolutions: self.resolutions.clone(),
+        has_result,
+        warnings: self.compile_warnings.clone(),
+      };
+      self.compile_cache.insert(key, file, script);
+    }
+    Ok(has_result)
+  }
+
+  /// Scan and parse the source, then bind the compiled script.
+  ///
+  /// The parser borrows scratch buffers of the virtual machine (see [`work_buffer`]).
+  fn parse_source(&mut self, src: String, file: Option<&str>) -> Result<bool, InterpretError> {
     let mut parser = Parser::init();
+    self.work_buffer.lend(&mut parser);
+    // kept even if compiling fails, buffers are reused by the next compiling
+    parser.resolutions = core::mem::take(&mut self.resolutions);
+    parser.resolutions.clear();
+    let result = self.parse_with(&mut parser, src, file);
+    self.resolutions = core::mem::take(&mut parser.resolutions);
+    self.work_buffer.reclaim(parser);
+    result
+  }
+
+  fn parse_with(
+    &mut self,
+    parser: &mut Parser,
+    src: String,
+    file: Option<&str>,
+  ) -> Result<bool, InterpretError> {
+    self.compile_warnings.clear();
+    self.module_files.clear();
+    let (function, has_result) = self.parse_script(parser, src, file)?;
+    // load pre-parsed script into VM (link to VM)
+    self.rebind_function(function);
+    self.functions = core::mem::take(&mut parser.functions);
+    self.compile_warnings = core::mem::take(&mut parser.warnings);
+    self.module_files = core::mem::take(&mut parser.module_files);
+    Ok(has_result)
+  }
+
+  /// Compile the source into a top-level function, without binding it (or anything of it)
+  /// to the virtual machine, so an ongoing run is left intact.
+  ///
+  /// Its first slots are taken by locals of the given names (from slot zero),
+  /// so the function runs on a copy of the slots of another call.
+  /// Slots of empty names can't be referenced, and slot zero named `this` makes `this` valid.
+  ///
+  /// Returns if the script results in the value of its final expression statement.
+  pub(crate) fn compile_detached(
+    &self,
+    src: String,
+    slots: &[String],
+  ) -> Result<(ObjFunction, bool), InterpretError> {
+    let mut parser = Parser::init();
+    for (slot, name) in slots.iter().enumerate() {
+      let local = Local {
+        name: Token {
+          lexeme: name.to_owned(),
+          ..Token::default()
+        },
+        is_initialized: true,
+        ..Local::default()
+      };
+      match slot {
+        0 => parser.compiler.locals[0] = local,
+        _ => parser.compiler.locals.push(local),
+      }
+    }
+    parser.compiler.local_count = parser.compiler.locals.len();
+    if slots.first().is_some_and(|name| name == "this") {
+      parser.class_compiler = Some(Box::default());
+    }
+    self.parse_script(&mut parser, src, None)
+  }
+
+  /// Scan and parse the source into the top-level function.
+  fn parse_script(
+    &self,
+    parser: &mut Parser,
+    src: String,
+    file: Option<&str>,
+  ) -> Result<(ObjFunction, bool), InterpretError> {
+    parser.options = self.compile_options;
+    parser.capabilities = self.capabilities;
+    parser.file = file.map(Rc::from);
     parser.scanner.rebind(src);
-    parser.advance_token()?;
+    parser.scanner.operators = self.plugins.symbols();
+    parser.scanner.keywords = self.plugins.keywords.clone();
+    parser.plugins = self.plugins.clone();
+    parser.advance_and_report();
     while !parser.match_token(TokenType::Eof)? {
       parser.declaration()?;
     }
+    parser.resolutions.link_globals();
+    if parser.had_error {
+      return Err(InterpretError::CompileError(parser.errors.join("\n")));
+    }
+    if parser.options.strict {
+      parser.check_global_assignments(&self.globals)?;
+    }
+    let has_result = parser.keep_script_result();
     // manually end compiler
-    parser.end_compiler()?;
-    // load pre-parsed chunk into VM (link to VM)
-    self.chunk = parser.chunk;
-    Ok(())
+    let function = parser.end_compiler()?;
+    Ok((function, has_result))
   }
 
   /// This function is used for debugging.
   ///
   /// It will only compile to token, skipping `parsing`
+  #[cfg(feature = "std")]
   pub(crate) fn compile_to_token(&mut self, src: String) -> Result<(), InterpretError> {
-    let mut scanner = Scanner::bind(src);
+    let mut scanner = crate::scanner::Scanner::bind(src);
     let mut line = 0_usize;
     loop {
       let token = scanner.scan_token();
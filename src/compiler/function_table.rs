@@ -0,0 +1,65 @@
+//! # FunctionTable
+//!
+//! Submodule of `compiler`, which records all functions compiled from a script.
+//!
+//! Each function is compiled into a chunk of its own (which stays a constant of the chunk
+//! defining it). The table finds them by qualified name, e.g. `outer.inner` or `Point.init`,
+//! so the serializer, disassembler and debugger needn't walk constants.
+
+use core::ptr::NonNull;
+
+use crate::{object::ObjFunction, prelude::*};
+
+/// ## FunctionEntry
+///
+/// A function of the script, with its qualified name.
+#[derive(Debug, Clone)]
+pub(crate) struct FunctionEntry {
+  pub(crate) name: String,
+  pub(crate) function: NonNull<ObjFunction>,
+}
+
+/// ## FunctionTable
+///
+/// All functions compiled from a script (the script itself excluded),
+/// in order of their end, so each function comes after those defined in it.
+#[derive(Debug, Clone, Default)]
+pub struct FunctionTable {
+  pub(crate) entries: Vec<FunctionEntry>,
+}
+
+impl FunctionTable {
+  /// Record a compiled function.
+  pub(crate) fn push(&mut self, name: String, function: NonNull<ObjFunction>) {
+    self.entries.push(FunctionEntry { name, function });
+  }
+
+  /// The function of the given qualified name (the last one, if it's defined again).
+  pub fn get(&self, name: &str) -> Option<&ObjFunction> {
+    let entry = self.entries.iter().rev().find(|entry| entry.name == name)?;
+    Some(unsafe { entry.function.as_ref() })
+  }
+
+  /// Index of the function in the table.
+  pub(crate) fn index_of(&self, function: NonNull<ObjFunction>) -> Option<usize> {
+    self
+      .entries
+      .iter()
+      .position(|entry| entry.function == function)
+  }
+
+  /// Qualified names of all functions, in order of the table.
+  pub fn names(&self) -> impl Iterator<Item = &str> {
+    self.entries.iter().map(|entry| entry.name.as_str())
+  }
+
+  /// Count of functions.
+  pub fn len(&self) -> usize {
+    self.entries.len()
+  }
+
+  /// If the script defines no function.
+  pub fn is_empty(&self) -> bool {
+    self.entries.is_empty()
+  }
+}
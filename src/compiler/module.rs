@@ -0,0 +1,117 @@
+//! # Module
+//!
+//! Submodule of `compiler`, which finds the source of each module named by `import "path";`.
+//!
+//! A path (`.lox` is appended if it's missing) is looked for, in order:
+//!
+//! - relative to the directory of the importing file (or the working directory, for sources
+//!   without a file)
+//! - in each directory of the `RLOX_PATH` environment variable (separated as `PATH` is)
+//! - in the standard library embedded into the interpreter (e.g. `import "math";`)
+//!
+//! Files are only looked for with the `std` feature, and only if the virtual machine grants
+//! [`Capability::Files`](crate::native::Capability::Files): otherwise only the standard library
+//! is, and any other path is refused (without looking at the host).
+//!
+//! Each module is identified by a key (the canonical path of its file, or `<stdlib>/name.lox`),
+//! so a module reached by different paths is compiled (and run) once.
+
+use crate::{native::Capability, prelude::*};
+
+#[cfg(feature = "std")]
+use std::path::{Path, PathBuf};
+
+/// Environment variable listing directories searched for modules.
+pub const SEARCH_PATH_VAR: &str = "RLOX_PATH";
+
+/// Modules of the standard library, as `(name, source)`.
+pub const STDLIB: &[(&str, &str)] = &[("math", include_str!("../../stdlib/math.lox"))];
+
+/// ## ModuleSource
+///
+/// The source of a module found for an `import`.
+#[derive(Debug, Clone)]
+pub struct ModuleSource {
+  /// Identity of the module (see the module docs).
+  pub key: String,
+  /// Name of the file (as diagnostics locate code of the module).
+  pub file: String,
+  /// The source code.
+  pub text: String,
+  /// If it's read from a file (rather than embedded into the interpreter).
+  pub is_file: bool,
+}
+
+/// Find the module of the path imported by the file (`None` for a source without a file),
+/// looking for files only if `files` is granted.
+pub fn resolve(path: &str, importer: Option<&str>, files: bool) -> Result<ModuleSource, String> {
+  let path = match path.ends_with(".lox") {
+    true => path.to_owned(),
+    false => format!("{}.lox", path),
+  };
+  #[cfg(feature = "std")]
+  if files {
+    if let Some(module) = resolve_file(&path, importer) {
+      return Ok(module);
+    }
+  }
+  let name = path.trim_end_matches(".lox");
+  if let Some((_, text)) = STDLIB.iter().find(|(stdlib_name, _)| *stdlib_name == name) {
+    let key = format!("<stdlib>/{}", path);
+    return Ok(ModuleSource {
+      file: key.clone(),
+      key,
+      text: (*text).to_owned(),
+      is_file: false,
+    });
+  }
+  if !files {
+    return Err(format!(
+      "Can't import module `{}` from files without the `{}` capability (only the standard library is available).",
+      path,
+      Capability::Files
+    ));
+  }
+  Err(format!(
+    "Can't find module `{}` (searched relative to `{}`, `{}` and the standard library).",
+    path,
+    importer.unwrap_or("."),
+    SEARCH_PATH_VAR
+  ))
+}
+
+/// Key of the file (its canonical path), to tell if an importing file is imported again.
+#[cfg(feature = "std")]
+pub fn file_key(file: &str) -> Option<String> {
+  let path = std::fs::canonicalize(file).ok()?;
+  Some(path.display().to_string())
+}
+
+#[cfg(not(feature = "std"))]
+pub fn file_key(_: &str) -> Option<String> {
+  None
+}
+
+/// Look for the path relative to the importing file, then in directories of `RLOX_PATH`.
+#[cfg(feature = "std")]
+fn resolve_file(path: &str, importer: Option<&str>) -> Option<ModuleSource> {
+  let relative_to = importer
+    .and_then(|file| Path::new(file).parent())
+    .map_or_else(PathBuf::new, Path::to_path_buf);
+  let search_path = std::env::var_os(SEARCH_PATH_VAR).unwrap_or_default();
+  let candidates = core::iter::once(relative_to.join(path))
+    .chain(std::env::split_paths(&search_path).map(|dir| dir.join(path)));
+  for candidate in candidates {
+    let Ok(text) = std::fs::read_to_string(&candidate) else {
+      continue;
+    };
+    let file = candidate.display().to_string();
+    return Some(ModuleSource {
+      key: file_key(&file).unwrap_or_else(|| file.clone()),
+      file,
+      text,
+      is_file: true,
+    });
+  }
+  None
+}
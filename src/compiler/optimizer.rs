@@ -0,0 +1,537 @@
+//! # Optimizer
+//!
+//! Submodule of `compiler`, which rewrites the bytecode of each compiled function,
+//! according to the [`OptLevel`].
+//!
+//! - `O0`: bytecode is kept as emitted
+//! - `O1`: constant folding, then constants are deduplicated
+//! - `O2`: `O1` + peephole (dead push/pop pairs, jumps to jumps)
+//!   and superinstructions (`Pop` runs => `PopN`)
+//!
+//! The chunk is decoded into instructions (jumps refer to instructions, rather than offsets),
+//! rewritten by each pass, then encoded again.
+
+use crate::{
+  chunk::{Chunk, OpCode, SourceLocation},
+  isa::Operand,
+  prelude::*,
+  utils::Init,
+  value::{Value, ValueType},
+};
+
+use super::OptLevel;
+
+/// ## Instruction
+///
+/// A decoded instruction.
+#[derive(Debug, Clone)]
+struct Instruction {
+  op_code: OpCode,
+  /// The constant operand (if any).
+  constant: Option<Value>,
+  /// Other operands (slots, counts of arguments/values).
+  operands: Vec<u8>,
+  /// Index of the instruction to jump to (only for jumps).
+  target: Option<usize>,
+  location: SourceLocation,
+  /// If a statement starts at it (see [`DebugInfo::statements`](crate::chunk::DebugInfo)).
+  starts_statement: bool,
+}
+
+impl Instruction {
+  fn simple(op_code: OpCode, location: SourceLocation) -> Self {
+    Self {
+      op_code,
+      constant: None,
+      operands: vec![],
+      target: None,
+      location,
+      starts_statement: false,
+    }
+  }
+
+  /// Instruction which pushes the given literal.
+  fn literal(value: Value, location: SourceLocation) -> Self {
+    match value.value_type {
+      ValueType::Nil => Self::simple(OpCode::Nil, location),
+      ValueType::Bool if value.as_bool() => Self::simple(OpCode::True, location),
+      ValueType::Bool => Self::simple(OpCode::False, location),
+      _ => Self {
+        constant: Some(value),
+        ..Self::simple(OpCode::Constant, location)
+      },
+    }
+  }
+
+  /// The literal pushed by this instruction (functions are not literals).
+  fn as_literal(&self) -> Option<Value> {
+    match self.op_code {
+      OpCode::Nil => Some(Value::nil_val()),
+      OpCode::True => Some(Value::bool_val(true)),
+      OpCode::False => Some(Value::bool_val(false)),
+      OpCode::Constant => self
+        .constant
+        .filter(|value| value.is_number() || value.is_string()),
+      _ => None,
+    }
+  }
+
+  /// If it pushes a value without any side effect.
+  fn is_pure_push(&self) -> bool {
+    matches!(
+      self.op_code,
+      OpCode::Constant
+        | OpCode::Nil
+        | OpCode::True
+        | OpCode::False
+        | OpCode::GetLocal
+        | OpCode::GetLocalLong
+        | OpCode::GetUpvalue
+    )
+  }
+}
+
+/// Optimize the given chunk at the given level.
+///
+/// The chunk is kept as is if it can't be decoded,
+/// or the optimized one can't be encoded (e.g. too many constants).
+pub(crate) fn optimize(chunk: &mut Chunk, level: OptLevel) {
+  if level == OptLevel::O0 {
+    return;
+  }
+  let Some(mut instructions) = decode(chunk) else {
+    return;
+  };
+  while fold_constants(&mut instructions) {}
+  if level >= OptLevel::O2 {
+    while peephole(&mut instructions) {}
+    fuse_pops(&mut instructions);
+  }
+  if let Some(mut optimized) = encode(&instructions, &chunk.constants.values) {
+    optimized.debug_info.file = chunk.debug_info.file.clone();
+    // constants (e.g. strings) of the optimized chunk still live in the arena
+    optimized.arena = chunk.arena.clone();
+    *chunk = optimized;
+  }
+}
+
+fn decode(chunk: &Chunk) -> Option<Vec<Instruction>> {
+  let code = &chunk.code;
+  let mut instructions = vec![];
+  // index of the instruction starting at each offset
+  let mut indices = vec![None; code.len() + 1];
+  // (instruction index, target offset) of each jump
+  let mut jumps = vec![];
+  let mut offset = 0;
+  while offset < code.len() {
+    indices[offset] = Some(instructions.len());
+    let op_code = OpCode::try_from(code[offset]).ok()?;
+    let mut instruction = Instruction::simple(op_code, chunk.debug_info.location(offset));
+    instruction.starts_statement = chunk.debug_info.starts_statement(offset);
+    offset += 1;
+    for &operand in op_code.spec().operands {
+      let bytes = code.get(offset..offset + operand.width())?;
+      offset += operand.width();
+      match operand {
+        Operand::Constant => {
+          instruction.constant = Some(*chunk.constants.values.get(bytes[0] as usize)?);
+        }
+        Operand::Jump | Operand::Loop => {
+          let jump = u16::from_be_bytes([bytes[0], bytes[1]]) as usize;
+          let target = match operand {
+            Operand::Loop => offset.checked_sub(jump)?,
+            _ => offset + jump,
+          };
+          jumps.push((instructions.len(), target));
+        }
+        Operand::Slot
+        | Operand::SlotLong
+        | Operand::ArgCount
+        | Operand::Count
+        | Operand::Upvalue => instruction.operands.extend(bytes),
+      }
+    }
+    instructions.push(instruction);
+  }
+  indices[code.len()] = Some(instructions.len());
+  for (index, target) in jumps {
+    instructions[index].target = Some((*indices.get(target)?)?);
+  }
+  Some(instructions)
+}
+
+/// Encode the instructions into a chunk.
+///
+/// Constants still used are kept in their original order, the new ones are appended.
+fn encode(instructions: &[Instruction], constants: &[Value]) -> Option<Chunk> {
+  let mut chunk = Chunk::init();
+  for value in constants {
+    let used = |i: &Instruction| i.constant.is_some_and(|c| identical(&c, value));
+    if instructions.iter().any(used) {
+      add_constant(&mut chunk, *value);
+    }
+  }
+  let mut offsets = Vec::with_capacity(instructions.len() + 1);
+  // (offset of operand, target instruction) of each jump
+  let mut jumps = vec![];
+  for instruction in instructions {
+    offsets.push(chunk.code.len());
+    if instruction.starts_statement {
+      chunk.mark_statement();
+    }
+    chunk.write_chunk_at(instruction.op_code as u8, instruction.location);
+    let mut operands = vec![];
+    let mut others = instruction.operands.iter();
+    for &operand in instruction.op_code.spec().operands {
+      match operand {
+        Operand::Constant => {
+          let value = instruction.constant?;
+          operands.push(u8::try_from(add_constant(&mut chunk, value)).ok()?);
+        }
+        Operand::Jump | Operand::Loop => {
+          let target = instruction.target?;
+          jumps.push((chunk.code.len() + operands.len(), operand, target));
+          operands.extend([0xff, 0xff]);
+        }
+        Operand::Slot
+        | Operand::SlotLong
+        | Operand::ArgCount
+        | Operand::Count
+        | Operand::Upvalue => {
+          for _ in 0..operand.width() {
+            operands.push(*others.next()?);
+          }
+        }
+      }
+    }
+    for byte in operands {
+      chunk.write_chunk_at(byte, instruction.location);
+    }
+  }
+  offsets.push(chunk.code.len());
+  for (offset, operand, target) in jumps {
+    let jump = match operand {
+      Operand::Loop => (offset + 2).checked_sub(offsets[target])?,
+      _ => offsets[target].checked_sub(offset + 2)?,
+    };
+    let jump = u16::try_from(jump).ok()?;
+    chunk.code[offset..offset + 2].copy_from_slice(&jump.to_be_bytes());
+  }
+  Some(chunk)
+}
+
+/// Add the constant to the chunk, reusing an identical one.
+fn add_constant(chunk: &mut Chunk, value: Value) -> usize {
+  match chunk
+    .constants
+    .values
+    .iter()
+    .position(|other| identical(&value, other))
+  {
+    Some(index) => index,
+    None => chunk.add_constant(value),
+  }
+}
+
+/// Numbers are identical iff their bits are, strings iff their contents are,
+/// other objects iff they are the same one.
+fn identical(value: &Value, other: &Value) -> bool {
+  match (value.value_type, other.value_type) {
+    (ValueType::Number, ValueType::Number) => {
+      value.as_number().to_bits() == other.as_number().to_bits()
+    }
+    _ => value == other,
+  }
+}
+
+/// Indices of all instructions which are jumped to.
+fn jump_targets(instructions: &[Instruction]) -> Vec<bool> {
+  let mut targets = vec![false; instructions.len() + 1];
+  for target in instructions.iter().filter_map(|i| i.target) {
+    targets[target] = true;
+  }
+  targets
+}
+
+/// Replace `instructions[start..end]` with the given ones, retargeting jumps.
+///
+/// Jumps into the replaced range are moved to its start, so is the start of a statement.
+fn splice(instructions: &mut Vec<Instruction>, start: usize, end: usize, with: Vec<Instruction>) {
+  let removed = end - start - with.len();
+  let starts_statement = instructions[start..end].iter().any(|i| i.starts_statement);
+  for instruction in instructions.iter_mut() {
+    if let Some(target) = instruction.target.as_mut() {
+      if *target >= end {
+        *target -= removed;
+      } else if *target > start {
+        *target = start;
+      }
+    }
+  }
+  instructions.splice(start..end, with);
+  if let Some(instruction) = instructions.get_mut(start) {
+    instruction.starts_statement |= starts_statement;
+  }
+}
+
+/// Evaluate an operator on literals, as the virtual machine does.
+///
+/// Returns `None` if it results in a runtime error (which is left to the virtual machine),
+/// or if it orders values of different types (which is an error in strict comparison mode).
+fn evaluate(op_code: OpCode, operands: &[Value]) -> Option<Value> {
+  let ordering = matches!(
+    op_code,
+    OpCode::Greater | OpCode::Less | OpCode::GreaterEqual | OpCode::LessEqual
+  );
+  if ordering && operands[0].value_type != operands[1].value_type {
+    return None;
+  }
+  let value = match (op_code, operands) {
+    (OpCode::Not, &[v]) => (!v).ok()?,
+    (OpCode::Negate, &[v]) => (-v).ok()?,
+    (OpCode::Add, &[l, r]) => (l + r).ok()?,
+    (OpCode::Subtract, &[l, r]) => (l - r).ok()?,
+    (OpCode::Multiply, &[l, r]) => (l * r).ok()?,
+    (OpCode::Divide, &[l, r]) => (l / r).ok()?,
+    (OpCode::FloorDivide, &[l, r]) => l.floor_div(r).ok()?,
+    (OpCode::Equal, &[l, r]) => Value::bool_val(l == r),
+    (OpCode::NotEqual, &[l, r]) => Value::bool_val(l != r),
+    (OpCode::Greater, &[l, r]) => Value::bool_val(l > r),
+    (OpCode::Less, &[l, r]) => Value::bool_val(l < r),
+    (OpCode::GreaterEqual, &[l, r]) => Value::bool_val(l >= r),
+    (OpCode::LessEqual, &[l, r]) => Value::bool_val(l <= r),
+    _ => return None,
+  };
+  Some(value)
+}
+
+/// Fold an operator applied on literals into the literal it results in.
+///
+/// Returns if anything is folded.
+fn fold_constants(instructions: &mut Vec<Instruction>) -> bool {
+  let targets = jump_targets(instructions);
+  for end in 1..instructions.len() {
+    let operator = &instructions[end];
+    let arity = match operator.op_code {
+      OpCode::Not | OpCode::Negate => 1,
+      _ => 2,
+    };
+    let Some(start) = end.checked_sub(arity) else {
+      continue;
+    };
+    // operands and operator must be executed in a row
+    if targets[start + 1..=end].iter().any(|&target| target) {
+      continue;
+    }
+    let Some(operands) = instructions[start..end]
+      .iter()
+      .map(Instruction::as_literal)
+      .collect::<Option<Vec<_>>>()
+    else {
+      continue;
+    };
+    if let Some(value) = evaluate(operator.op_code, &operands) {
+      let folded = Instruction::literal(value, operator.location);
+      splice(instructions, start, end + 1, vec![folded]);
+      return true;
+    }
+  }
+  false
+}
+
+/// Remove instructions without any effect, and thread jumps to jumps.
+///
+/// Returns if anything is changed.
+fn peephole(instructions: &mut Vec<Instruction>) -> bool {
+  if thread_jumps(instructions) {
+    return true;
+  }
+  let targets = jump_targets(instructions);
+  for (index, instruction) in instructions.iter().enumerate() {
+    // value pushed then popped at once
+    let next = instructions.get(index + 1);
+    if instruction.is_pure_push()
+      && next.is_some_and(|next| next.op_code == OpCode::Pop)
+      && !targets[index + 1]
+    {
+      splice(instructions, index, index + 2, vec![]);
+      return true;
+    }
+  }
+  false
+}
+
+/// Jump directly to where the jumped-to jump would go.
+///
+/// `JumpIfFalse` keeps the condition on the stack, so it could be threaded
+/// through another `JumpIfFalse` (which is taken as well).
+fn thread_jumps(instructions: &mut [Instruction]) -> bool {
+  let mut changed = false;
+  for index in 0..instructions.len() {
+    let Some(target) = instructions[index].target else {
+      continue;
+    };
+    let Some(next) = instructions.get(target) else {
+      continue;
+    };
+    let threaded = match (instructions[index].op_code, next.op_code) {
+      // a loop threaded through a (forward) jump may no longer jump backward
+      (OpCode::Loop, _) => None,
+      (_, OpCode::Jump) | (OpCode::JumpIfFalse, OpCode::JumpIfFalse) => next.target,
+      _ => None,
+    };
+    if threaded.is_some() {
+      instructions[index].target = threaded;
+      changed = true;
+    }
+  }
+  changed
+}
+
+/// Fuse runs of `Pop` into `PopN`.
+fn fuse_pops(instructions: &mut Vec<Instruction>) {
+  let mut start = 0;
+  while start < instructions.len() {
+    let targets = jump_targets(instructions);
+    let mut end = start;
+    while end < instructions.len()
+      && instructions[end].op_code == OpCode::Pop
+      && (end == start || !targets[end])
+      && end - start < u8::MAX as usize
+    {
+      end += 1;
+    }
+    if end - start >= 2 {
+      let fused = Instruction {
+        operands: vec![(end - start) as u8],
+        ..Instruction::simple(OpCode::PopN, instructions[start].location)
+      };
+      splice(instructions, start, end, vec![fused]);
+    }
+    start += 1;
+  }
+}
+
+/// Change of the stack size by the instruction (before jumping, if it's a jump).
+fn stack_effect(instruction: &Instruction) -> isize {
+  let count = instruction.operands.first().copied().unwrap_or(0) as isize;
+  match instruction.op_code {
+    OpCode::Constant
+    | OpCode::Nil
+    | OpCode::True
+    | OpCode::False
+    | OpCode::GetGlobal
+    | OpCode::GetLocal
+    | OpCode::GetLocalLong
+    | OpCode::MissingArgument
+    | OpCode::GetUpvalue
+    | OpCode::Closure
+    | OpCode::Class
+    | OpCode::Trait
+    | OpCode::Enum
+    | OpCode::Peek => 1,
+    OpCode::Equal
+    | OpCode::Greater
+    | OpCode::Less
+    | OpCode::NotEqual
+    | OpCode::GreaterEqual
+    | OpCode::LessEqual
+    | OpCode::Is
+    | OpCode::Contains
+    | OpCode::Add
+    | OpCode::Subtract
+    | OpCode::Multiply
+    | OpCode::Divide
+    | OpCode::FloorDivide
+    | OpCode::Print
+    | OpCode::Pop
+    | OpCode::DefineGlobal
+    | OpCode::CloseUpvalue
+    | OpCode::Mixin
+    | OpCode::Inherit
+    | OpCode::SetProperty
+    | OpCode::DeleteProperty
+    | OpCode::Method
+    | OpCode::Getter
+    | OpCode::Setter
+    | OpCode::GetSuper
+    | OpCode::GetIndex
+    | OpCode::ExtendList
+    | OpCode::SpreadCall
+    | OpCode::SpreadInvoke
+    | OpCode::Throw
+    | OpCode::Return => -1,
+    OpCode::GetSlice | OpCode::SetIndex | OpCode::SpreadSuperInvoke => -2,
+    OpCode::BuildList => 1 - count,
+    OpCode::Unpack => count - 1,
+    OpCode::PopN | OpCode::Call | OpCode::Invoke => -count,
+    OpCode::CallGlobal => 1 - count,
+    OpCode::SuperInvoke => -count - 1,
+    OpCode::Not
+    | OpCode::Negate
+    | OpCode::Stringify
+    | OpCode::Sink
+    | OpCode::JumpIfFalse
+    | OpCode::Jump
+    | OpCode::Loop
+    | OpCode::SetGlobal
+    | OpCode::SetLocal
+    | OpCode::SetLocalLong
+    | OpCode::SetUpvalue
+    | OpCode::GetProperty
+    | OpCode::EnumMember
+    | OpCode::Import
+    | OpCode::PushHandler
+    | OpCode::PopHandler
+    | OpCode::MatchList
+    | OpCode::MatchListRest => 0,
+  }
+}
+
+/// Stack usage of a call of the chunk, whose first `base` slots are taken on entry
+/// (the callee and parameters): `(peak count of slots, count of slots of locals referenced)`.
+///
+/// The peak is found by following each path of jumps once, `None` if the chunk can't be decoded.
+pub(crate) fn stack_usage(chunk: &Chunk, base: usize) -> Option<(usize, usize)> {
+  let instructions = decode(chunk)?;
+  let mut visited = vec![false; instructions.len()];
+  let mut pending = vec![(0, base as isize)];
+  let mut peak = base as isize;
+  while let Some((index, depth)) = pending.pop() {
+    let Some(instruction) = instructions.get(index) else {
+      continue;
+    };
+    if visited[index] {
+      continue;
+    }
+    visited[index] = true;
+    let after = depth + stack_effect(instruction);
+    peak = peak.max(after);
+    match instruction.op_code {
+      OpCode::Jump | OpCode::Loop => pending.push((instruction.target?, after)),
+      OpCode::JumpIfFalse => {
+        pending.push((instruction.target?, after));
+        pending.push((index + 1, after));
+      }
+      // the error is pushed onto the stack as it's entered
+      OpCode::PushHandler => {
+        peak = peak.max(after + 1);
+        pending.push((instruction.target?, after + 1));
+        pending.push((index + 1, after));
+      }
+      OpCode::Return | OpCode::Throw => {}
+      _ => pending.push((index + 1, after)),
+    }
+  }
+  let local_slots = instructions
+    .iter()
+    .filter_map(|instruction| match instruction.op_code {
+      OpCode::GetLocal | OpCode::SetLocal => Some(instruction.operands[0] as usize + 1),
+      OpCode::GetLocalLong | OpCode::SetLocalLong => {
+        Some(u16::from_be_bytes([instruction.operands[0], instruction.operands[1]]) as usize + 1)
+      }
+      _ => None,
+    })
+    .fold(base, usize::max);
+  Some((peak.max(0) as usize, local_slots))
+}
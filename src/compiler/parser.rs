@@ -3,25 +3,38 @@
 //! Submodule of `compiler`, which is used to parse the source code into bytecode.
 
 use crate::{
-  chunk::{Chunk, OpCode},
-  compiler::Precedence,
-  object::{ObjString, ObjTrait},
-  scanner::{Scanner, Token, TokenType},
+  chunk::{Chunk, OpCode, SourceLocation},
+  compiler::{
+    function_table::FunctionTable,
+    plugin::{OperatorAction, Plugins},
+    resolution::{Declaration, DeclarationKind, ResolutionTable},
+    Precedence,
+  },
+  native::{Capabilities, Capability},
+  object::{ObjString, Upvalue},
+  scanner::{segment_value, string_value, unknown_escape, Scanner, Token, TokenType},
+  table::Table,
   utils::Init,
-  value::Value,
+  value::{display, Value},
   vm::InterpretError,
 };
 
-use once_cell::sync::Lazy;
-use std::collections::HashMap;
+use alloc::rc::Rc;
+use hashbrown::HashMap;
 
+use self::resolve_methods::Resolved;
 use super::*;
 
 type ParseFn = fn(&mut Parser, bool) -> Result<(), InterpretError>;
 
+pub mod class_methods;
 pub mod compile_time_error_handlers;
 pub mod emit_methods;
+pub mod function_methods;
+pub mod import_methods;
+pub mod match_methods;
 pub mod ops_after_get_parse_rule;
+pub mod resolve_methods;
 pub mod statement_methods;
 pub mod variable_methods;
 
@@ -40,7 +53,11 @@ pub struct ParseRule {
 }
 
 impl ParseRule {
-  pub fn new(prefix: Option<ParseFn>, infix: Option<ParseFn>, precedence: Precedence) -> Self {
+  pub const fn new(
+    prefix: Option<ParseFn>,
+    infix: Option<ParseFn>,
+    precedence: Precedence,
+  ) -> Self {
     Self {
       prefix,
       infix,
@@ -51,234 +68,410 @@ impl ParseRule {
 
 /// ## RULES_VEC
 ///
-/// A vec which contains the rules for the different tokens.
-///
-/// Initialized by lazy_static!.
-static RULES_VEC: Lazy<Vec<(TokenType, ParseRule)>> = Lazy::new(|| {
-  vec![
-    (
-      TokenType::LeftParen,
-      ParseRule::new(Some(Parser::grouping_adapter), None, Precedence::None),
-    ),
-    (
-      TokenType::RightParen,
-      ParseRule::new(None, None, Precedence::None),
-    ),
-    (
-      TokenType::LeftBrace,
-      ParseRule::new(None, None, Precedence::None),
-    ),
-    (
-      TokenType::RightBrace,
-      ParseRule::new(None, None, Precedence::None),
-    ),
-    (
-      TokenType::Comma,
-      ParseRule::new(None, None, Precedence::None),
-    ),
-    (TokenType::Dot, ParseRule::new(None, None, Precedence::None)),
-    (
-      TokenType::Minus,
-      ParseRule::new(
-        Some(Parser::unary_adapter),
-        Some(Parser::binary_adapter),
-        Precedence::Term,
-      ),
-    ),
-    (
-      TokenType::Plus,
-      ParseRule::new(None, Some(Parser::binary_adapter), Precedence::Term),
-    ),
-    (
-      TokenType::Semicolon,
-      ParseRule::new(None, None, Precedence::None),
-    ),
-    (
-      TokenType::Slash,
-      ParseRule::new(None, Some(Parser::binary_adapter), Precedence::Factor),
-    ),
-    (
-      TokenType::Star,
-      ParseRule::new(None, Some(Parser::binary_adapter), Precedence::Factor),
-    ),
-    (
-      TokenType::Bang,
-      ParseRule::new(Some(Parser::unary_adapter), None, Precedence::None),
-    ),
-    (
-      TokenType::BangEqual,
-      ParseRule::new(None, Some(Parser::binary_adapter), Precedence::Equality),
-    ),
-    (
-      TokenType::Equal,
-      ParseRule::new(None, None, Precedence::None),
-    ),
-    (
-      TokenType::EqualEqual,
-      ParseRule::new(None, Some(Parser::binary_adapter), Precedence::Equality),
-    ),
-    (
-      TokenType::Greater,
-      ParseRule::new(None, Some(Parser::binary_adapter), Precedence::Comparison),
-    ),
-    (
-      TokenType::GreaterEqual,
-      ParseRule::new(None, Some(Parser::binary_adapter), Precedence::Comparison),
-    ),
-    (
-      TokenType::Less,
-      ParseRule::new(None, Some(Parser::binary_adapter), Precedence::Comparison),
-    ),
-    (
-      TokenType::LessEqual,
-      ParseRule::new(None, Some(Parser::binary_adapter), Precedence::Comparison),
-    ),
-    (
-      TokenType::Identifier,
-      ParseRule::new(Some(Parser::variable), None, Precedence::None),
-    ),
-    (
-      TokenType::String,
-      ParseRule::new(Some(Parser::string_adapter), None, Precedence::None),
-    ),
-    (
-      TokenType::Number,
-      ParseRule::new(Some(Parser::number_adapter), None, Precedence::None),
-    ),
-    (
-      TokenType::And,
-      ParseRule::new(None, Some(Parser::and), Precedence::And),
-    ),
-    (
-      TokenType::Class,
-      ParseRule::new(None, None, Precedence::None),
-    ),
-    (
-      TokenType::Else,
-      ParseRule::new(None, None, Precedence::None),
-    ),
-    (
-      TokenType::False,
-      ParseRule::new(Some(Parser::literal_adapter), None, Precedence::None),
-    ),
-    (TokenType::For, ParseRule::new(None, None, Precedence::None)),
-    (TokenType::Fun, ParseRule::new(None, None, Precedence::None)),
-    (TokenType::If, ParseRule::new(None, None, Precedence::None)),
-    (
-      TokenType::Nil,
-      ParseRule::new(Some(Parser::literal_adapter), None, Precedence::None),
-    ),
-    (
-      TokenType::Or,
-      ParseRule::new(None, Some(Parser::or), Precedence::Or),
-    ),
-    (
-      TokenType::Print,
-      ParseRule::new(None, None, Precedence::None),
-    ),
-    (
-      TokenType::Return,
-      ParseRule::new(None, None, Precedence::None),
-    ),
-    (
-      TokenType::Super,
-      ParseRule::new(None, None, Precedence::None),
-    ),
-    (
-      TokenType::This,
-      ParseRule::new(None, None, Precedence::None),
-    ),
-    (
-      TokenType::True,
-      ParseRule::new(Some(Parser::literal_adapter), None, Precedence::None),
-    ),
-    (TokenType::Var, ParseRule::new(None, None, Precedence::None)),
-    (
-      TokenType::While,
-      ParseRule::new(None, None, Precedence::None),
-    ),
-    (
-      TokenType::Error,
-      ParseRule::new(None, None, Precedence::None),
-    ),
-    (TokenType::Eof, ParseRule::new(None, None, Precedence::None)),
-  ]
-});
+/// The rules for the different tokens.
+const RULES_VEC: &[(TokenType, ParseRule)] = &[
+  (
+    TokenType::LeftParen,
+    ParseRule::new(
+      Some(Parser::grouping_adapter),
+      Some(Parser::call),
+      Precedence::Call,
+    ),
+  ),
+  (
+    TokenType::RightParen,
+    ParseRule::new(None, None, Precedence::None),
+  ),
+  (
+    TokenType::LeftBrace,
+    ParseRule::new(None, None, Precedence::None),
+  ),
+  (
+    TokenType::RightBrace,
+    ParseRule::new(None, None, Precedence::None),
+  ),
+  (
+    TokenType::LeftBracket,
+    ParseRule::new(Some(Parser::list), Some(Parser::index), Precedence::Call),
+  ),
+  (
+    TokenType::RightBracket,
+    ParseRule::new(None, None, Precedence::None),
+  ),
+  (
+    TokenType::Comma,
+    ParseRule::new(None, None, Precedence::None),
+  ),
+  (
+    TokenType::Dot,
+    ParseRule::new(None, Some(Parser::dot), Precedence::Call),
+  ),
+  (
+    TokenType::DotDotDot,
+    ParseRule::new(None, None, Precedence::None),
+  ),
+  (
+    TokenType::Minus,
+    ParseRule::new(
+      Some(Parser::unary_adapter),
+      Some(Parser::binary_adapter),
+      Precedence::Term,
+    ),
+  ),
+  (
+    TokenType::MinusMinus,
+    ParseRule::new(
+      Some(Parser::prefix_increment),
+      Some(Parser::postfix_increment),
+      Precedence::Call,
+    ),
+  ),
+  (
+    TokenType::Plus,
+    ParseRule::new(None, Some(Parser::binary_adapter), Precedence::Term),
+  ),
+  (
+    TokenType::PlusPlus,
+    ParseRule::new(
+      Some(Parser::prefix_increment),
+      Some(Parser::postfix_increment),
+      Precedence::Call,
+    ),
+  ),
+  (
+    TokenType::Semicolon,
+    ParseRule::new(None, None, Precedence::None),
+  ),
+  (
+    TokenType::Slash,
+    ParseRule::new(None, Some(Parser::binary_adapter), Precedence::Factor),
+  ),
+  (
+    TokenType::Star,
+    ParseRule::new(None, Some(Parser::binary_adapter), Precedence::Factor),
+  ),
+  (
+    TokenType::Question,
+    ParseRule::new(None, Some(Parser::ternary), Precedence::Ternary),
+  ),
+  (
+    TokenType::Colon,
+    ParseRule::new(None, None, Precedence::None),
+  ),
+  (
+    TokenType::Bang,
+    ParseRule::new(Some(Parser::unary_adapter), None, Precedence::None),
+  ),
+  (
+    TokenType::BangEqual,
+    ParseRule::new(None, Some(Parser::binary_adapter), Precedence::Equality),
+  ),
+  (
+    TokenType::Equal,
+    ParseRule::new(None, None, Precedence::None),
+  ),
+  (
+    TokenType::EqualEqual,
+    ParseRule::new(None, Some(Parser::binary_adapter), Precedence::Equality),
+  ),
+  (
+    TokenType::FatArrow,
+    ParseRule::new(None, None, Precedence::None),
+  ),
+  (
+    TokenType::Greater,
+    ParseRule::new(None, Some(Parser::binary_adapter), Precedence::Comparison),
+  ),
+  (
+    TokenType::GreaterEqual,
+    ParseRule::new(None, Some(Parser::binary_adapter), Precedence::Comparison),
+  ),
+  (
+    TokenType::Less,
+    ParseRule::new(None, Some(Parser::binary_adapter), Precedence::Comparison),
+  ),
+  (
+    TokenType::LessEqual,
+    ParseRule::new(None, Some(Parser::binary_adapter), Precedence::Comparison),
+  ),
+  (
+    TokenType::Identifier,
+    ParseRule::new(Some(Parser::variable), None, Precedence::None),
+  ),
+  (
+    TokenType::String,
+    ParseRule::new(Some(Parser::string_adapter), None, Precedence::None),
+  ),
+  (
+    TokenType::Dollar,
+    ParseRule::new(Some(Parser::interpolation_adapter), None, Precedence::None),
+  ),
+  (
+    TokenType::Number,
+    ParseRule::new(Some(Parser::number_adapter), None, Precedence::None),
+  ),
+  (
+    TokenType::And,
+    ParseRule::new(None, Some(Parser::and), Precedence::And),
+  ),
+  (
+    TokenType::Catch,
+    ParseRule::new(None, None, Precedence::None),
+  ),
+  (
+    TokenType::Class,
+    ParseRule::new(None, None, Precedence::None),
+  ),
+  (
+    TokenType::Else,
+    ParseRule::new(None, None, Precedence::None),
+  ),
+  (
+    TokenType::Enum,
+    ParseRule::new(None, None, Precedence::None),
+  ),
+  (
+    TokenType::False,
+    ParseRule::new(Some(Parser::literal_adapter), None, Precedence::None),
+  ),
+  (TokenType::For, ParseRule::new(None, None, Precedence::None)),
+  (TokenType::Fun, ParseRule::new(None, None, Precedence::None)),
+  (
+    TokenType::Delete,
+    ParseRule::new(None, None, Precedence::None),
+  ),
+  (
+    TokenType::Div,
+    ParseRule::new(None, Some(Parser::binary_adapter), Precedence::Factor),
+  ),
+  (TokenType::If, ParseRule::new(None, None, Precedence::None)),
+  (
+    TokenType::Import,
+    ParseRule::new(None, None, Precedence::None),
+  ),
+  (
+    TokenType::In,
+    ParseRule::new(None, Some(Parser::binary_adapter), Precedence::Comparison),
+  ),
+  (
+    TokenType::Is,
+    ParseRule::new(None, Some(Parser::binary_adapter), Precedence::Comparison),
+  ),
+  (
+    TokenType::Match,
+    ParseRule::new(None, None, Precedence::None),
+  ),
+  (
+    TokenType::Nil,
+    ParseRule::new(Some(Parser::literal_adapter), None, Precedence::None),
+  ),
+  (
+    TokenType::Or,
+    ParseRule::new(None, Some(Parser::or), Precedence::Or),
+  ),
+  (
+    TokenType::Print,
+    ParseRule::new(None, None, Precedence::None),
+  ),
+  (
+    TokenType::Return,
+    ParseRule::new(None, None, Precedence::None),
+  ),
+  (
+    TokenType::Super,
+    ParseRule::new(Some(Parser::super_), None, Precedence::None),
+  ),
+  (
+    TokenType::This,
+    ParseRule::new(Some(Parser::this), None, Precedence::None),
+  ),
+  (
+    TokenType::Throw,
+    ParseRule::new(None, None, Precedence::None),
+  ),
+  (
+    TokenType::Trait,
+    ParseRule::new(None, None, Precedence::None),
+  ),
+  (
+    TokenType::True,
+    ParseRule::new(Some(Parser::literal_adapter), None, Precedence::None),
+  ),
+  (TokenType::Try, ParseRule::new(None, None, Precedence::None)),
+  (TokenType::Var, ParseRule::new(None, None, Precedence::None)),
+  (
+    TokenType::While,
+    ParseRule::new(None, None, Precedence::None),
+  ),
+  (
+    TokenType::With,
+    ParseRule::new(None, None, Precedence::None),
+  ),
+  (
+    TokenType::Error,
+    ParseRule::new(None, None, Precedence::None),
+  ),
+  (TokenType::Eof, ParseRule::new(None, None, Precedence::None)),
+];
 
 /// ## RULES
 ///
-/// HashMap form of `RULES_VEC`
-///
-/// Initialized by lazy_static!.
-static RULES: Lazy<HashMap<TokenType, ParseRule>> = Lazy::new(|| {
-  let mut map = HashMap::new();
-  for (token_type, rule) in RULES_VEC.iter() {
-    map.insert(*token_type, *rule);
+/// `RULES_VEC` indexed by token types, built at compile time
+/// (so compiling the first script doesn't build a table first).
+static RULES: [ParseRule; TokenType::Eof as usize + 1] = {
+  let mut rules = [ParseRule::new(None, None, Precedence::None); TokenType::Eof as usize + 1];
+  let mut index = 0;
+  while index < RULES_VEC.len() {
+    let (token_type, rule) = RULES_VEC[index];
+    rules[token_type as usize] = rule;
+    index += 1;
   }
-  map
-});
+  rules
+};
+
+/// ## PendingTarget
+///
+/// What the last access of a target (e.g. `a.b[i].c`) does instead of getting it.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum PendingTarget {
+  /// `++`/`--`, by the operator (`Add` or `Subtract`).
+  Increment(OpCode),
+  /// `delete`.
+  Delete,
+}
 
 #[derive(Default)]
 pub struct Parser {
-  /// Chunk used for compiling.
-  pub(crate) chunk: Chunk,
   /// Scanner in parser
   pub(crate) scanner: Scanner,
+  /// Name of the source file (`None` if compiled from a string).
+  pub(crate) file: Option<Rc<str>>,
   /// Current token.
   pub(crate) current: Token,
   /// Previous token.
   pub(crate) previous: Token,
+  /// All compile errors reported (each one is followed by recovery).
+  pub(crate) errors: Vec<String>,
+  /// Scanner offset of the last reported error.
+  pub(crate) last_error_offset: Option<usize>,
   /// If had error.
   pub(crate) had_error: bool,
   /// If in panic mode.
   pub(crate) panic_mode: bool,
-  /// Compiler => handle local variables
+  /// Compiler => handle local variables (of the innermost function)
   pub(crate) compiler: Compiler,
+  /// ClassCompiler => track the innermost class (`None` if not in a class)
+  pub(crate) class_compiler: Option<Box<ClassCompiler>>,
+  /// What the target of the prefix `++`/`--` or `delete` being compiled does, with the nesting
+  /// depth of the target, whose last access takes it once it's compiled.
+  pub(crate) pending_target: Option<(PendingTarget, usize)>,
+  /// Warnings reported during compiling (which never stop it).
+  pub(crate) warnings: Vec<String>,
+  /// Opt-in diagnostics.
+  pub(crate) options: CompileOptions,
+  /// Capabilities of the virtual machine (files of modules are only read if granted).
+  pub(crate) capabilities: Capabilities,
+  /// Declarations and references of variables resolved so far.
+  pub(crate) resolutions: ResolutionTable,
+  /// Names of all assigned globals (only tracked in strict mode).
+  pub(crate) global_assignments: Vec<Token>,
+  /// How many statements surround the current bit of code.
+  pub(crate) statement_depth: usize,
+  /// Offset of the `Pop` of the last top-level expression statement.
+  pub(crate) result_pop: Option<usize>,
+  /// If the script keeps the value of its final expression statement as result.
+  pub(crate) keeps_result: bool,
+  /// How many expressions and statements surround the current bit of code.
+  pub(crate) nesting_depth: usize,
+  /// Extensions registered by the embedder.
+  pub(crate) plugins: Plugins,
+  /// All functions compiled so far.
+  pub(crate) functions: FunctionTable,
+  /// Names of members of enums declared so far, as `{enum: members}`
+  /// (used to check `match` covers all members of an enum).
+  pub(crate) enums: HashMap<String, Vec<String>>,
+  /// Modules being imported (the importing file first), as `(key, name)`, to report cycles.
+  pub(crate) import_chain: Vec<(String, String)>,
+  /// Functions of modules compiled so far, as `{key: function}`.
+  pub(crate) modules: HashMap<String, Value>,
+  /// Files of all modules imported (directly or not) by the script.
+  pub(crate) module_files: Vec<String>,
 }
 
 impl Init for Parser {}
 
+impl Parser {
+  /// Chunk of the function currently being compiled.
+  pub(crate) fn current_chunk(&mut self) -> &mut Chunk {
+    &mut self.compiler.function.chunk
+  }
+}
+
 impl Parser {
   /// This function starts at the current token,
   /// then parses any expression at the given precedence level or higher.
   fn parse_precedence(&mut self, precedence: Precedence) -> Result<(), InterpretError> {
+    self.enter_nesting()?;
+    let result = self.parse_precedence_inner(precedence);
+    self.nesting_depth -= 1;
+    result
+  }
+
+  fn parse_precedence_inner(&mut self, precedence: Precedence) -> Result<(), InterpretError> {
     // if it's valid to operate `assign`
     let can_assign = precedence <= Precedence::Assignment;
 
     // look up `prefix parser` for the `current` token
     self.advance_token()?;
-    let prefix_rule = self.get_rule(self.previous.token_type).prefix;
-    if prefix_rule.is_none() {
-      return Err(InterpretError::CompileError("Expect expression.".into()));
-    }
-    let prefix_rule = prefix_rule.unwrap();
+    let Some(prefix_rule) = self.rule_of(&self.previous).prefix else {
+      return self.error("Expect expression.".into());
+    };
     prefix_rule(self, can_assign)?;
 
     // look for `infix parser` for the `next` token.
-    while precedence <= self.get_rule(self.current.token_type).precedence {
+    while precedence <= self.rule_of(&self.current).precedence {
       self.advance_token()?;
-      let infix_rule = self.get_rule(self.previous.token_type).infix;
-      if infix_rule.is_none() {
+      let Some(infix_rule) = self.rule_of(&self.previous).infix else {
         // no infix rule, so we are done
         break;
-      }
-      infix_rule.unwrap()(self, can_assign)?;
+      };
+      infix_rule(self, can_assign)?;
     }
 
     // Deal with invalid assignment.
     // (E.g. => {...}; a + b = c * d;)
     if can_assign && self.match_token(TokenType::Equal)? {
-      return Err(InterpretError::CompileError(
-        "Invalid assignment target.".into(),
-      ));
+      return self.error("Invalid assignment target.".into());
     }
 
     // done
     Ok(())
   }
 
+  /// Tokens without a rule are treated as having neither parser nor precedence.
   fn get_rule(&self, token_type: TokenType) -> ParseRule {
-    *RULES.get(&token_type).unwrap()
+    RULES[token_type as usize]
+  }
+
+  /// Rule of the given token, registered operators are looked up by their symbols.
+  fn rule_of(&self, token: &Token) -> ParseRule {
+    if token.token_type != TokenType::Operator {
+      return self.get_rule(token.token_type);
+    }
+    match self.plugins.operator(&token.lexeme) {
+      Some(rule) => ParseRule::new(
+        rule
+          .prefix
+          .as_ref()
+          .map(|_| Parser::operator_prefix as ParseFn),
+        rule
+          .infix
+          .as_ref()
+          .map(|_| Parser::operator_infix as ParseFn),
+        rule.precedence,
+      ),
+      None => ParseRule::default(),
+    }
   }
 }
 
@@ -288,29 +481,31 @@ impl Parser {
   /// It asks the scanner for the next token and stores it for later use.
   ///
   /// Before doing that, it takes the old current token and stashes that in a previous field.
+  ///
+  /// Two tokens are swapped instead of cloned, so buffers of their lexemes are reused.
   pub(crate) fn advance_token(&mut self) -> Result<(), InterpretError> {
-    self.previous = self.current.clone();
+    core::mem::swap(&mut self.previous, &mut self.current);
+    // skip all error tokens, only the first one is reported
+    let mut result = Ok(());
     loop {
-      self.current = self.scanner.scan_token();
+      self.scanner.scan_token_into(&mut self.current);
       if self.current.token_type != TokenType::Error {
         break;
       }
-      self.error_at_current(self.current.lexeme.to_owned())?;
+      if result.is_ok() {
+        result = self.error_at_current(self.current.lexeme.to_owned());
+      }
     }
-    Ok(())
+    result
   }
 
   /// Try consuming current(last) token, if can't, throw error.
-  fn consume_token(
-    &mut self,
-    token_type: TokenType,
-    message: String,
-  ) -> Result<(), InterpretError> {
+  fn consume_token(&mut self, token_type: TokenType, message: &str) -> Result<(), InterpretError> {
     if self.current.token_type == token_type {
       self.advance_token()?;
       Ok(())
     } else {
-      self.error_at_current(message)
+      self.error_at_current(message.into())
     }
   }
 
@@ -328,7 +523,7 @@ impl Parser {
     if !self.check_token(expected_type) {
       Ok(false)
     } else {
-      self.advance_token().unwrap();
+      self.advance_token()?;
       Ok(true)
     }
   }
@@ -350,7 +545,12 @@ impl Parser {
       && self.compiler.locals[self.compiler.local_count - 1].depth > self.compiler.scope_depth
     {
       // lifetime of local variable ends here, call pop instruction
-      self.emit_byte(OpCode::Pop as u8)?;
+      // (captured ones are moved into their upvalues first)
+      if self.compiler.locals[self.compiler.local_count - 1].is_captured {
+        self.emit_byte(OpCode::CloseUpvalue as u8)?;
+      } else {
+        self.emit_byte(OpCode::Pop as u8)?;
+      }
       self.compiler.local_count -= 1;
     }
     Ok(())
@@ -361,29 +561,92 @@ impl Parser {
     while !self.check_token(TokenType::RightBrace) && !self.check_token(TokenType::Eof) {
       self.declaration()?;
     }
-    self.consume_token(TokenType::RightBrace, "Expect `}` after block.".into())
+    self.consume_token(TokenType::RightBrace, "Expect `}` after block.")
   }
 
   /// Try matching current token as a declaration.
+  ///
+  /// Compile errors are recorded (rather than returned), then the parser
+  /// recovers by skipping to the next statement.
   pub(crate) fn declaration(&mut self) -> Result<(), InterpretError> {
-    if self.match_token(TokenType::Var)? {
-      self.var_declaration()?;
-    } else {
-      self.statement()?;
+    let start = self.scanner.current;
+    if let Err(error) = self.declaration_inner() {
+      self.report(error);
+      // make progress, or the same declaration would fail forever
+      if self.scanner.current == start {
+        self.advance_and_report();
+      }
     }
     if self.panic_mode {
-      self.synchronize()
+      self.synchronize();
+    }
+    Ok(())
+  }
+
+  fn declaration_inner(&mut self) -> Result<(), InterpretError> {
+    self.current_chunk().mark_statement();
+    if self.match_token(TokenType::Class)? {
+      self.class_declaration(false)
+    } else if self.match_token(TokenType::Trait)? {
+      self.class_declaration(true)
+    } else if self.match_token(TokenType::Enum)? {
+      self.enum_declaration()
+    } else if self.match_token(TokenType::Fun)? {
+      self.fun_declaration()
+    } else if self.match_token(TokenType::Var)? {
+      self.var_declaration()
     } else {
-      Ok(())
+      self.statement()
     }
   }
 
   /// Try matching current token as a statement.
   fn statement(&mut self) -> Result<(), InterpretError> {
+    self.current_chunk().mark_statement();
+    self.enter_nesting()?;
+    self.statement_depth += 1;
+    let result = self.statement_inner();
+    self.statement_depth -= 1;
+    self.nesting_depth -= 1;
+    result
+  }
+
+  /// Step into a nested expression or statement.
+  ///
+  /// Fails if it's nested deeper than `max_nesting_depth`.
+  fn enter_nesting(&mut self) -> Result<(), InterpretError> {
+    self.nesting_depth += 1;
+    if self.nesting_depth > self.options.max_nesting_depth {
+      self.nesting_depth -= 1;
+      return self.error_at_current(format!(
+        "Code is nested too deeply (At most: {}).",
+        self.options.max_nesting_depth
+      ));
+    }
+    Ok(())
+  }
+
+  fn statement_inner(&mut self) -> Result<(), InterpretError> {
     if self.match_token(TokenType::Print)? {
       self.print_statement()
     } else if self.match_token(TokenType::If)? {
       self.if_statement()
+    } else if self.match_token(TokenType::While)? {
+      self.while_statement()
+    } else if self.match_token(TokenType::For)? {
+      self.for_statement()
+    } else if self.match_token(TokenType::Return)? {
+      self.return_statement()
+    } else if self.match_token(TokenType::Delete)? {
+      self.delete_statement()
+    } else if self.match_token(TokenType::Try)? {
+      self.try_statement()
+    } else if self.match_token(TokenType::Throw)? {
+      self.throw_statement()
+    } else if self.match_token(TokenType::Match)? {
+      self.match_statement()
+    } else if self.match_token(TokenType::Import)? {
+      self.import_statement()
     } else if self.match_token(TokenType::LeftBrace)? {
       self.begin_scope();
       self.block()?;
@@ -408,7 +671,7 @@ impl Parser {
   ///
   /// TODO: Wrap the chunk, add support of (1, 2, 4, 8) bytes of peek_next logic.
   fn make_constant(&mut self, value: Value) -> Result<u8, InterpretError> {
-    let index = self.chunk.add_constant(value);
+    let index = self.current_chunk().add_constant(value);
     if index > u8::MAX as usize {
       Err(
         self
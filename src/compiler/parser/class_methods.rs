@@ -0,0 +1,147 @@
+use super::*;
+
+impl Parser {
+  /// Declare: bind a new class (or trait), with all of its methods.
+  ///
+  /// `class A with T1, T2 { ... }` copies methods of traits `T1`, `T2` into `A`
+  /// before its own methods are bound, so the latter override the former.
+  pub(crate) fn class_declaration(&mut self, is_trait: bool) -> Result<(), InterpretError> {
+    let kind = if is_trait { "trait" } else { "class" };
+    self.consume_token(TokenType::Identifier, &format!("Expect {} name.", kind))?;
+    let class_name = self.previous.clone();
+    let name_constant = self.identifier_constant()?;
+    let name = self.previous.lexeme.clone();
+    self.declare_variable()?;
+
+    let op_code = if is_trait {
+      OpCode::Trait
+    } else {
+      OpCode::Class
+    };
+    self.emit_bytes(&[op_code as u8, name_constant])?;
+    self.define_variable(name_constant)?;
+
+    // the superclass is kept in the hidden local `super` (of a new scope),
+    // so methods calling `super` capture it as an upvalue
+    let has_superclass = !is_trait && self.match_token(TokenType::Less)?;
+    if has_superclass {
+      self.consume_token(TokenType::Identifier, "Expect superclass name.")?;
+      if self.previous.lexeme == name {
+        self.error("A class can't inherit from itself.".into())?;
+      }
+      self.variable(false)?;
+      self.begin_scope();
+      self.add_hidden_local("super")?;
+    }
+
+    let enclosing = self.class_compiler.take();
+    self.class_compiler = Some(Box::new(ClassCompiler {
+      enclosing,
+      name,
+      has_superclass,
+    }));
+
+    // load the class back onto the stack, so methods could be bound to it
+    self.previous = class_name;
+    self.named_variable(false)?;
+    if self.match_token(TokenType::With)? {
+      loop {
+        self.consume_token(TokenType::Identifier, "Expect trait name.")?;
+        self.named_variable(false)?;
+        self.emit_byte(OpCode::Mixin as u8)?;
+        if !self.match_token(TokenType::Comma)? {
+          break;
+        }
+      }
+    }
+    // after traits, so methods of traits override inherited ones
+    if has_superclass {
+      self.hidden_variable("super")?;
+      self.emit_byte(OpCode::Inherit as u8)?;
+    }
+    self.consume_token(
+      TokenType::LeftBrace,
+      &format!("Expect `{{` before {} body.", kind),
+    )?;
+    while !self.check_token(TokenType::RightBrace) && !self.check_token(TokenType::Eof) {
+      self.method()?;
+    }
+    self.consume_token(
+      TokenType::RightBrace,
+      &format!("Expect `}}` after {} body.", kind),
+    )?;
+    self.emit_byte(OpCode::Pop as u8)?;
+    if has_superclass {
+      self.end_scope()?;
+    }
+
+    self.class_compiler = self.class_compiler.take().and_then(|c| c.enclosing);
+    Ok(())
+  }
+
+  /// Compile a method, then bind it to the class on top of the stack.
+  ///
+  /// `get name { ... }` and `set name(value) { ... }` declare accessors of
+  /// a computed property, while `get`/`set` alone are ordinary method names.
+  fn method(&mut self) -> Result<(), InterpretError> {
+    self.consume_token(TokenType::Identifier, "Expect method name.")?;
+    let followed_by_name = self.check_token(TokenType::Identifier);
+    let accessor = match self.previous.lexeme.as_str() {
+      "get" if followed_by_name => Some(FunctionType::Getter),
+      "set" if followed_by_name => Some(FunctionType::Setter),
+      _ => None,
+    };
+    if let Some(function_type) = accessor {
+      self.advance_token()?;
+      let name_constant = self.identifier_constant()?;
+      self.function(function_type)?;
+      let op_code = if function_type == FunctionType::Getter {
+        OpCode::Getter
+      } else {
+        OpCode::Setter
+      };
+      return self.emit_bytes(&[op_code as u8, name_constant]);
+    }
+
+    let name_constant = self.identifier_constant()?;
+    let function_type = if self.previous.lexeme == "init" {
+      FunctionType::Initializer
+    } else {
+      FunctionType::Method
+    };
+    self.function(function_type)?;
+    self.emit_bytes(&[OpCode::Method as u8, name_constant])
+  }
+
+  /// Declare: bind a new enum, with all of its members (separated by commas).
+  ///
+  /// `enum Color { Red, Green }` creates the enum, then appends each member to it in order,
+  /// so `Color.Red.ordinal` is `0`. Names of members are recorded for checks of `match`.
+  pub(crate) fn enum_declaration(&mut self) -> Result<(), InterpretError> {
+    self.consume_token(TokenType::Identifier, "Expect enum name.")?;
+    let name = self.previous.lexeme.clone();
+    let name_constant = self.identifier_constant()?;
+    self.declare_variable()?;
+    self.emit_bytes(&[OpCode::Enum as u8, name_constant])?;
+
+    self.consume_token(TokenType::LeftBrace, "Expect `{` before enum body.")?;
+    let mut members: Vec<String> = vec![];
+    while !self.check_token(TokenType::RightBrace) {
+      self.consume_token(TokenType::Identifier, "Expect member name.")?;
+      let member = self.previous.lexeme.clone();
+      if members.contains(&member) {
+        self.error(format!("Duplicate member `{}` of enum `{}`.", member, name))?;
+      }
+      let member_constant = self.identifier_constant()?;
+      self.emit_bytes(&[OpCode::EnumMember as u8, member_constant])?;
+      members.push(member);
+      if !self.match_token(TokenType::Comma)? {
+        break;
+      }
+    }
+    self.consume_token(TokenType::RightBrace, "Expect `}` after enum body.")?;
+
+    self.enums.insert(name, members);
+    self.define_variable(name_constant)
+  }
+}
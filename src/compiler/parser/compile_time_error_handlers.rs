@@ -26,8 +26,12 @@ impl Parser {
     } else {
       &self.previous
     };
+    let location = SourceLocation {
+      line: token.line,
+      column: token.column,
+    };
     let mut error_str = String::new();
-    error_str += &format!("[line {}] Error", token.line);
+    error_str += &format!("[{}] Error", location.describe(self.file.as_deref()));
     match token.token_type {
       TokenType::Eof => error_str += " at end",
       TokenType::Error => {}
@@ -37,4 +41,42 @@ impl Parser {
     self.had_error = true;
     Err(InterpretError::CompileError(error_str))
   }
+
+  /// Record the given compile error, then enter panic mode.
+  ///
+  /// Error repeated at the same place (e.g. unclosed blocks at the end) is only recorded once.
+  pub(crate) fn report(&mut self, error: InterpretError) {
+    self.had_error = true;
+    self.panic_mode = true;
+    let message = error.into_message();
+    let offset = Some(self.scanner.current);
+    if self.last_error_offset != offset || self.errors.last() != Some(&message) {
+      self.errors.push(message);
+      self.last_error_offset = offset;
+    }
+  }
+
+  /// Advance to the next token, error token would be recorded.
+  pub(crate) fn advance_and_report(&mut self) {
+    if let Err(error) = self.advance_token() {
+      self.report(error);
+    }
+  }
+
+  /// Report warning at previous token.
+  ///
+  /// Unlike errors, warnings are collected and compiling goes on.
+  pub(crate) fn warning(&mut self, message: String) {
+    let token = &self.previous;
+    let location = SourceLocation {
+      line: token.line,
+      column: token.column,
+    };
+    self.warnings.push(format!(
+      "[{}] Warning at '{}': {}",
+      location.describe(self.file.as_deref()),
+      token.lexeme,
+      message
+    ));
+  }
 }
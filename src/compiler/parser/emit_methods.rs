@@ -4,33 +4,43 @@ impl Parser {
   /// Emit(pre) jump instructions, and tells `ip`
   /// which instruction to jump to **iff** the condition is false.
   ///
-  /// Conclusion is that, just need to jump to `self.chunk.code.len() - 2`.
+  /// Conclusion is that, just need to jump to `self.current_chunk().code.len() - 2`.
   ///
   /// (Jump back, only need to jump ahead over `if` and `condition` statement)
   pub(crate) fn emit_jump(&mut self, instruction: u8) -> Result<usize, InterpretError> {
     self.emit_byte(instruction)?;
     self.emit_bytes(&[0xff, 0xff])?;
     // -2 => `if` + `condition` = 2_bytes
-    Ok(self.chunk.code.len() - 2)
+    Ok(self.current_chunk().code.len() - 2)
   }
 
   /// Patch the jump instruction correctly.
   pub(crate) fn patch_jump(&mut self, offset: usize) -> Result<(), InterpretError> {
     // -2 to adjust for the bytecode for the jump offset itself
-    let jump = self.chunk.code.len() - offset - 2;
+    let jump = self.current_chunk().code.len() - offset - 2;
 
     if jump > u16::MAX as usize {
-      return Err(InterpretError::CompileError(
-        "Too much code to jump over.".into(),
-      ));
+      return self.error("Too much code to jump over.".into());
     }
 
-    self.chunk.code[offset] = ((jump >> 8) & 0xff) as u8;
-    self.chunk.code[offset + 1] = (jump & 0xff) as u8;
+    let chunk = self.current_chunk();
+    chunk.code[offset] = ((jump >> 8) & 0xff) as u8;
+    chunk.code[offset + 1] = (jump & 0xff) as u8;
 
     Ok(())
   }
 
+  /// Emit a `Loop` jumping back to the given offset.
+  pub(crate) fn emit_loop(&mut self, loop_start: usize) -> Result<(), InterpretError> {
+    self.emit_byte(OpCode::Loop as u8)?;
+    // +2 to adjust for the bytecode for the loop offset itself
+    let offset = self.current_chunk().code.len() - loop_start + 2;
+    if offset > u16::MAX as usize {
+      return self.error("Loop body too large.".into());
+    }
+    self.emit_bytes(&(offset as u16).to_be_bytes())
+  }
+
   /// Appending a sequence of bytes to the chunk (in order).
   pub(crate) fn emit_bytes(&mut self, bytes: &[u8]) -> Result<(), InterpretError> {
     for &byte in bytes {
@@ -39,25 +49,80 @@ impl Parser {
     Ok(())
   }
 
+  /// Appending a local variable instruction,
+  /// `long` (with two bytes slot) is used for spilled locals.
+  pub(crate) fn emit_local(
+    &mut self,
+    short: OpCode,
+    long: OpCode,
+    slot: usize,
+  ) -> Result<(), InterpretError> {
+    if slot < SHORT_LOCALS_MAX {
+      self.emit_bytes(&[short as u8, slot as u8])
+    } else {
+      let [high, low] = (slot as u16).to_be_bytes();
+      self.emit_bytes(&[long as u8, high, low])
+    }
+  }
+
   /// Appending a single byte to the chunk.
   pub(crate) fn emit_byte(&mut self, byte: u8) -> Result<(), InterpretError> {
-    self.chunk.write_chunk(byte, self.previous.line);
+    let location = SourceLocation {
+      line: self.previous.line,
+      column: self.previous.column,
+    };
+    self.current_chunk().write_chunk_at(byte, location);
     Ok(())
   }
 
-  /// Specifically appending the return instruction to the chunk.
+  /// Specifically appending the implicit return instruction to the chunk.
+  ///
+  /// Initializers return `this` (slot zero),
+  /// setters return the assigned value (slot one), others return `nil`.
+  ///
+  /// A script which keeps its result returns the value already on the stack.
   pub(crate) fn emit_return(&mut self) -> Result<(), InterpretError> {
+    match self.compiler.function_type {
+      FunctionType::Initializer => self.emit_bytes(&[OpCode::GetLocal as u8, 0])?,
+      FunctionType::Setter => self.emit_bytes(&[OpCode::GetLocal as u8, 1])?,
+      FunctionType::Script if self.keeps_result => {}
+      _ => self.emit_byte(OpCode::Nil as u8)?,
+    }
     self.emit_byte(OpCode::Return as u8)
   }
 
+  /// Keep the value of the final top-level expression statement (if any)
+  /// as the result of the script, by dropping its `Pop`.
+  ///
+  /// Returns if there's such a value.
+  pub(crate) fn keep_script_result(&mut self) -> bool {
+    let chunk = &mut self.compiler.function.chunk;
+    self.keeps_result =
+      self.result_pop.is_some() && self.result_pop == chunk.code.len().checked_sub(1);
+    if self.keeps_result {
+      chunk.pop_byte();
+    }
+    self.keeps_result
+  }
+
   /// Wrapper for appending `constant` and `index` info to the chunk.
   pub(crate) fn emit_constant(&mut self, value: Value) -> Result<(), InterpretError> {
     let constant_index = self.make_constant(value)?;
     self.emit_bytes(&[OpCode::Constant as u8, constant_index])
   }
 
-  /// Operations after end of compilation.
-  pub(crate) fn end_compiler(&mut self) -> Result<(), InterpretError> {
-    self.emit_return()
+  /// Operations after end of compilation (of current function).
+  ///
+  /// Returns the compiled (and optimized) function, then steps back to the enclosing compiler.
+  pub(crate) fn end_compiler(&mut self) -> Result<ObjFunction, InterpretError> {
+    self.emit_return()?;
+    let mut function = match self.compiler.enclosing.take() {
+      Some(enclosing) => core::mem::replace(&mut self.compiler, *enclosing).function,
+      // the script compiler is kept, so its buffers could be reclaimed (see `WorkBuffer`)
+      None => core::mem::take(&mut self.compiler.function),
+    };
+    function.chunk.debug_info.file = self.file.clone();
+    optimizer::optimize(&mut function.chunk, self.options.opt_level);
+    Ok(function)
   }
 }
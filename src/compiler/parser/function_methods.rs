@@ -0,0 +1,132 @@
+use super::*;
+
+impl Parser {
+  /// Declare: bind a new function.
+  pub(crate) fn fun_declaration(&mut self) -> Result<(), InterpretError> {
+    let global_index = self.parse_variable("Expect function name.")?;
+    // a function could refer to itself (recursion), so mark it initialized early
+    self.mark_initialized();
+    self.function(FunctionType::Function)?;
+    self.define_variable(global_index)
+  }
+
+  /// Compile the parameter list and body of a function,
+  /// then emit it as a constant (or a closure, if it captures variables).
+  ///
+  /// Getters (`get area { ... }`) have no parameter list at all.
+  pub(crate) fn function(&mut self, function_type: FunctionType) -> Result<(), InterpretError> {
+    let name = ObjString::from(self.previous.lexeme.to_owned());
+    let mut compiler = Compiler::new(function_type, Some(name));
+    compiler.qualified_name = self.qualified_name(function_type);
+    let enclosing = core::mem::replace(&mut self.compiler, compiler);
+    self.compiler.enclosing = Some(Box::new(enclosing));
+    self.begin_scope();
+
+    if function_type != FunctionType::Getter {
+      self.parameter_list()?;
+    }
+    if function_type == FunctionType::Setter && self.compiler.function.arity != 1 {
+      return self.error("Setter must have exactly one parameter.".into());
+    }
+    self.consume_token(TokenType::LeftBrace, "Expect `{` before function body.")?;
+    self.block()?;
+
+    let qualified_name = core::mem::take(&mut self.compiler.qualified_name);
+    for (index, declaration) in core::mem::take(&mut self.compiler.captures)
+      .into_iter()
+      .enumerate()
+    {
+      if let Some(declaration) = declaration {
+        self
+          .resolutions
+          .capture(&qualified_name, index, declaration);
+      }
+    }
+    let function = self.end_compiler()?;
+    let captures = !function.upvalues.is_empty();
+    let function = self.current_chunk().alloc_function(function);
+    self
+      .functions
+      .push(qualified_name, function.as_obj().cast());
+    if captures {
+      let constant = self.make_constant(function)?;
+      self.emit_bytes(&[OpCode::Closure as u8, constant])
+    } else {
+      self.emit_constant(function)
+    }
+  }
+
+  /// Name of the function being declared (the previous token), qualified by
+  /// the enclosing function and class (for methods), e.g. `outer.inner` or `Point.init`.
+  fn qualified_name(&self, function_type: FunctionType) -> String {
+    let mut qualified_name = self.compiler.qualified_name.clone();
+    let class = self
+      .class_compiler
+      .as_ref()
+      .map(|class| class.name.as_str());
+    let class = class.filter(|_| function_type != FunctionType::Function);
+    for part in class.into_iter().chain([self.previous.lexeme.as_str()]) {
+      if !qualified_name.is_empty() {
+        qualified_name.push('.');
+      }
+      qualified_name.push_str(part);
+    }
+    qualified_name
+  }
+
+  /// Compile the parenthesized parameter list of a function.
+  ///
+  /// Parameters with default value (`fun f(a, b = 2)`) are compiled into
+  /// conditional initialization at function entry, that is:
+  ///
+  /// `if (/* b is not passed */) b = 2;`
+  fn parameter_list(&mut self) -> Result<(), InterpretError> {
+    self.consume_token(TokenType::LeftParen, "Expect `(` after function name.")?;
+    if !self.check_token(TokenType::RightParen) {
+      loop {
+        if self.compiler.function.arity == u8::MAX as usize {
+          return self.error_at_current("Can't have more than 255 parameters.".into());
+        }
+        self.compiler.function.arity += 1;
+        let constant = self.parse_variable("Expect parameter name.")?;
+        self.mark_parameter();
+        self.define_variable(constant)?;
+        if self.match_token(TokenType::Equal)? {
+          self.default_parameter()?;
+        } else if self.compiler.function.required_arity + 1 != self.compiler.function.arity {
+          return self
+            .error("Parameter without default value can't follow one with default.".into());
+        } else {
+          self.compiler.function.required_arity += 1;
+        }
+        if !self.match_token(TokenType::Comma)? {
+          break;
+        }
+      }
+    }
+    self.consume_token(TokenType::RightParen, "Expect `)` after parameters.")
+  }
+
+  /// Emit the default value initialization of the last declared parameter.
+  ///
+  /// It depends on the count of arguments passed, rather than the value of the parameter,
+  /// so an explicit `nil` argument is kept.
+  fn default_parameter(&mut self) -> Result<(), InterpretError> {
+    let slot = (self.compiler.local_count - 1) as u8;
+
+    // param is not passed ?
+    self.emit_bytes(&[OpCode::MissingArgument as u8, slot])?;
+    let skip_jump = self.emit_jump(OpCode::JumpIfFalse as u8)?;
+
+    // then: param = default
+    self.emit_byte(OpCode::Pop as u8)?;
+    self.expression()?;
+    self.emit_bytes(&[OpCode::SetLocal as u8, slot, OpCode::Pop as u8])?;
+    let end_jump = self.emit_jump(OpCode::Jump as u8)?;
+
+    // else: drop the comparison result
+    self.patch_jump(skip_jump)?;
+    self.emit_byte(OpCode::Pop as u8)?;
+    self.patch_jump(end_jump)
+  }
+}
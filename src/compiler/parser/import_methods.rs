@@ -0,0 +1,115 @@
+use super::*;
+
+use crate::compiler::module::{self, ModuleSource};
+
+impl Parser {
+  /// `import "path";` runs the module (found by [`module::resolve`]) unless it's run already.
+  ///
+  /// The module is compiled along with the importing script, into a function
+  /// (shared by all imports of it) which is called by `Import` once per virtual machine.
+  /// Modules share globals with the script, so functions and classes of the module are
+  /// defined as globals once it's run.
+  pub(crate) fn import_statement(&mut self) -> Result<(), InterpretError> {
+    self.consume_token(TokenType::String, "Expect path of module after `import`.")?;
+    let path = string_value(&self.previous.lexeme);
+    let files = self.capabilities.allows(Capability::Files);
+    let module = match module::resolve(&path, self.file.as_deref(), files) {
+      Ok(module) => module,
+      Err(message) => return self.error(message),
+    };
+    let function = self.compile_module(&path, &module)?;
+    self.consume_token(TokenType::Semicolon, "Expect `;` after import.")?;
+    let Some(function) = function else {
+      return Ok(());
+    };
+    self.emit_constant(function)?;
+    let key_constant = self.string_constant(&module.key)?;
+    self.emit_bytes(&[OpCode::Import as u8, key_constant])?;
+    self.emit_byte(OpCode::Pop as u8)
+  }
+
+  /// The function of the module, compiled by a parser of its own (unless it's compiled already),
+  /// or `None` if it imports itself (directly or not) or fails to compile.
+  ///
+  /// Errors and warnings of the module are reported as ones of the importing script.
+  fn compile_module(
+    &mut self,
+    path: &str,
+    module: &ModuleSource,
+  ) -> Result<Option<Value>, InterpretError> {
+    if self.import_chain.is_empty() {
+      if let Some(file) = self.file.clone() {
+        let key = module::file_key(&file).unwrap_or_else(|| file.to_string());
+        self.import_chain.push((key, file.to_string()));
+      }
+    }
+    if let Some(start) = self
+      .import_chain
+      .iter()
+      .position(|(key, _)| *key == module.key)
+    {
+      let mut cycle: Vec<_> = self.import_chain[start..]
+        .iter()
+        .map(|(_, name)| format!("`{}`", name))
+        .collect();
+      cycle.push(format!("`{}`", path));
+      self.error(format!("Import cycle: {}.", cycle.join(" imports ")))?;
+      return Ok(None);
+    }
+    if let Some(&function) = self.modules.get(&module.key) {
+      return Ok(Some(function));
+    }
+
+    let mut parser = Parser::init();
+    parser.options = self.options;
+    parser.capabilities = self.capabilities;
+    parser.file = Some(Rc::from(module.file.as_str()));
+    parser.scanner.rebind(module.text.clone());
+    parser.scanner.operators = self.plugins.symbols();
+    parser.scanner.keywords = self.plugins.keywords.clone();
+    parser.plugins = self.plugins.clone();
+    parser.import_chain = self.import_chain.clone();
+    parser
+      .import_chain
+      .push((module.key.clone(), path.to_owned()));
+    // shared, so functions of modules stay in order of their end (see `FunctionTable`)
+    parser.functions = core::mem::take(&mut self.functions);
+    parser.modules = core::mem::take(&mut self.modules);
+    parser.module_files = core::mem::take(&mut self.module_files);
+
+    parser.advance_and_report();
+    while !parser.match_token(TokenType::Eof)? {
+      parser.declaration()?;
+    }
+    let function = match parser.had_error {
+      true => None,
+      false => {
+        // the module is a constant of the importing chunk, so it lives in its arena
+        let function = self.current_chunk().alloc_function(parser.end_compiler()?);
+        parser
+          .functions
+          .push(path.to_owned(), function.as_obj().cast());
+        Some(function)
+      }
+    };
+
+    self.functions = core::mem::take(&mut parser.functions);
+    self.modules = core::mem::take(&mut parser.modules);
+    self.module_files = core::mem::take(&mut parser.module_files);
+    self.warnings.append(&mut parser.warnings);
+    if module.is_file {
+      self.module_files.push(module.file.clone());
+    }
+    match function {
+      Some(function) => {
+        self.modules.insert(module.key.clone(), function);
+        Ok(Some(function))
+      }
+      None => {
+        self.had_error = true;
+        self.errors.append(&mut parser.errors);
+        Ok(None)
+      }
+    }
+  }
+}
@@ -0,0 +1,288 @@
+use super::*;
+
+/// ## Pattern
+///
+/// A pattern of an arm of `match`.
+enum Pattern {
+  /// `_`: matches anything.
+  Wildcard,
+  /// `name`: matches anything, which is bound to the name.
+  Binding(Token),
+  /// A number, string, `true`, `false` or `nil`: matches values equal to it.
+  Literal(Value),
+  /// `Enum.Member`: matches the member of the enum.
+  Member { enum_name: Token, member: Token },
+  /// `[a, b]`: matches lists of exactly as many items, each matching its pattern.
+  ///
+  /// With `...rest` (or `..._`) at the end, lists of at least as many items match,
+  /// and the rest of the items are bound to `rest` (as a list).
+  List {
+    items: Vec<Pattern>,
+    rest: Option<Option<Token>>,
+  },
+}
+
+impl Parser {
+  /// `match value { pattern => statement ... }` runs the statement of the first arm
+  /// whose pattern matches the value (arms could be separated by commas), if any.
+  ///
+  /// The value is kept in a hidden local, then each arm is compiled into a sequence of tests
+  /// (on the value, or its items), each of which jumps to the next arm if it fails.
+  /// Once all tests pass, bound names are declared as locals of the arm.
+  ///
+  /// If arms only match members of an enum (without `_` or names), a warning lists the
+  /// members none of them matches.
+  pub(crate) fn match_statement(&mut self) -> Result<(), InterpretError> {
+    let keyword = self.previous.clone();
+    self.begin_scope();
+    self.expression()?;
+    self.add_hidden_local("match")?;
+    self.consume_token(TokenType::LeftBrace, "Expect `{` after value of `match`.")?;
+
+    let mut end_jumps = vec![];
+    let mut patterns = vec![];
+    while !self.check_token(TokenType::RightBrace) && !self.check_token(TokenType::Eof) {
+      let pattern = self.pattern()?;
+      self.consume_token(TokenType::FatArrow, "Expect `=>` after pattern.")?;
+
+      let mut fail_jumps = vec![];
+      self.test_pattern(&pattern, &mut vec![], &mut fail_jumps)?;
+      self.begin_scope();
+      self.bind_pattern(&pattern, &mut vec![])?;
+      self.statement()?;
+      self.end_scope()?;
+      end_jumps.push(self.emit_jump(OpCode::Jump as u8)?);
+
+      // a failed test jumps here with its result on the stack
+      for fail_jump in &fail_jumps {
+        self.patch_jump(*fail_jump)?;
+      }
+      if !fail_jumps.is_empty() {
+        self.emit_byte(OpCode::Pop as u8)?;
+      }
+      self.match_token(TokenType::Comma)?;
+      patterns.push(pattern);
+    }
+    self.consume_token(TokenType::RightBrace, "Expect `}` after arms of `match`.")?;
+    self.check_members_covered(&patterns, keyword);
+
+    for end_jump in end_jumps {
+      self.patch_jump(end_jump)?;
+    }
+    self.end_scope()
+  }
+
+  fn pattern(&mut self) -> Result<Pattern, InterpretError> {
+    self.advance_token()?;
+    let token = self.previous.clone();
+    let literal = match token.token_type {
+      TokenType::Identifier if token.lexeme == "_" => return Ok(Pattern::Wildcard),
+      TokenType::Identifier if self.match_token(TokenType::Dot)? => {
+        self.consume_token(
+          TokenType::Identifier,
+          "Expect member name after `.` in pattern.",
+        )?;
+        let member = self.previous.clone();
+        return Ok(Pattern::Member {
+          enum_name: token,
+          member,
+        });
+      }
+      TokenType::Identifier => return Ok(Pattern::Binding(token)),
+      TokenType::LeftBracket => return self.list_pattern(),
+      TokenType::Nil => Value::nil_val(),
+      TokenType::True => Value::bool_val(true),
+      TokenType::False => Value::bool_val(false),
+      TokenType::String => self
+        .current_chunk()
+        .alloc_string(string_value(&token.lexeme)),
+      TokenType::Number => self.number_pattern(1.0)?,
+      TokenType::Minus => {
+        self.consume_token(TokenType::Number, "Expect number after `-` in pattern.")?;
+        self.number_pattern(-1.0)?
+      }
+      _ => {
+        // in panic mode, parsing goes on as if it's `_`
+        self.error("Expect pattern.".into())?;
+        return Ok(Pattern::Wildcard);
+      }
+    };
+    Ok(Pattern::Literal(literal))
+  }
+
+  fn number_pattern(&mut self, sign: f64) -> Result<Value, InterpretError> {
+    match self.previous.lexeme.parse::<f64>() {
+      Ok(value) => Ok(Value::number_val(sign * value)),
+      Err(_) => Err(InterpretError::CompileError(
+        "Failed to parse number(value).".into(),
+      )),
+    }
+  }
+
+  /// The rest of `[a, b, ...rest]`, after `[`.
+  fn list_pattern(&mut self) -> Result<Pattern, InterpretError> {
+    let mut items = vec![];
+    let mut rest = None;
+    while !self.check_token(TokenType::RightBracket) {
+      if self.match_token(TokenType::DotDotDot)? {
+        self.consume_token(TokenType::Identifier, "Expect name after `...` in pattern.")?;
+        let name = self.previous.clone();
+        rest = Some((name.lexeme != "_").then_some(name));
+        self.match_token(TokenType::Comma)?;
+        if !self.check_token(TokenType::RightBracket) {
+          self.error_at_current("Rest of a list pattern must be its last item.".into())?;
+        }
+        break;
+      }
+      if items.len() == u8::MAX as usize {
+        self.error("Can't have more than 255 items in a list pattern.".into())?;
+      }
+      items.push(self.pattern()?);
+      if !self.match_token(TokenType::Comma)? {
+        break;
+      }
+    }
+    self.consume_token(TokenType::RightBracket, "Expect `]` after list pattern.")?;
+    Ok(Pattern::List { items, rest })
+  }
+
+  /// Emit tests of the pattern on the value at the path (indexes of items),
+  /// each of which jumps (by one of `fail_jumps`) if it fails.
+  fn test_pattern(
+    &mut self,
+    pattern: &Pattern,
+    path: &mut Vec<usize>,
+    fail_jumps: &mut Vec<usize>,
+  ) -> Result<(), InterpretError> {
+    match pattern {
+      Pattern::Wildcard | Pattern::Binding(_) => Ok(()),
+      Pattern::Literal(literal) => {
+        self.load_matched(path)?;
+        self.emit_constant(*literal)?;
+        self.emit_byte(OpCode::Equal as u8)?;
+        self.fail_unless(fail_jumps)
+      }
+      Pattern::Member { enum_name, member } => {
+        self.load_matched(path)?;
+        let previous = core::mem::replace(&mut self.previous, enum_name.clone());
+        let result = self.named_variable(false);
+        self.previous = previous;
+        result?;
+        let member_constant = self.string_constant(&member.lexeme)?;
+        self.emit_bytes(&[OpCode::GetProperty as u8, member_constant])?;
+        self.emit_byte(OpCode::Equal as u8)?;
+        self.fail_unless(fail_jumps)
+      }
+      Pattern::List { items, rest } => {
+        // the length is tested before any of the items is indexed
+        self.load_matched(path)?;
+        let op_code = match rest {
+          Some(_) => OpCode::MatchListRest,
+          None => OpCode::MatchList,
+        };
+        self.emit_bytes(&[op_code as u8, items.len() as u8])?;
+        self.fail_unless(fail_jumps)?;
+        for (index, item) in items.iter().enumerate() {
+          path.push(index);
+          self.test_pattern(item, path, fail_jumps)?;
+          path.pop();
+        }
+        Ok(())
+      }
+    }
+  }
+
+  /// Declare names bound by the pattern as locals, holding the values at their paths.
+  fn bind_pattern(
+    &mut self,
+    pattern: &Pattern,
+    path: &mut Vec<usize>,
+  ) -> Result<(), InterpretError> {
+    match pattern {
+      Pattern::Wildcard | Pattern::Literal(_) | Pattern::Member { .. } => Ok(()),
+      Pattern::Binding(name) => {
+        self.load_matched(path)?;
+        self.bind(name)
+      }
+      Pattern::List { items, rest } => {
+        for (index, item) in items.iter().enumerate() {
+          path.push(index);
+          self.bind_pattern(item, path)?;
+          path.pop();
+        }
+        if let Some(Some(name)) = rest {
+          self.load_matched(path)?;
+          self.emit_constant(Value::number_val(items.len() as f64))?;
+          self.emit_byte(OpCode::Nil as u8)?;
+          self.emit_byte(OpCode::GetSlice as u8)?;
+          self.bind(name)?;
+        }
+        Ok(())
+      }
+    }
+  }
+
+  /// Warn (at the keyword `match`) of members of the enum not matched by any of the patterns,
+  /// if all of them are members of the same enum declared before.
+  fn check_members_covered(&mut self, patterns: &[Pattern], keyword: Token) {
+    let mut enum_name = None;
+    let mut covered = vec![];
+    for pattern in patterns {
+      match pattern {
+        Pattern::Member {
+          enum_name: name,
+          member,
+        } if enum_name.is_none_or(|enum_name| enum_name == &name.lexeme) => {
+          enum_name = Some(&name.lexeme);
+          covered.push(&member.lexeme);
+        }
+        _ => return,
+      }
+    }
+    let Some(members) = enum_name.and_then(|name| self.enums.get(name)) else {
+      return;
+    };
+    let missing: Vec<_> = members
+      .iter()
+      .filter(|member| !covered.contains(member))
+      .map(|member| format!("`{}`", member))
+      .collect();
+    if missing.is_empty() {
+      return;
+    }
+    let message = format!(
+      "`match` doesn't cover member(s) {} of enum `{}`.",
+      missing.join(", "),
+      enum_name.unwrap()
+    );
+    let previous = core::mem::replace(&mut self.previous, keyword);
+    self.warning(message);
+    self.previous = previous;
+  }
+
+  /// Load the matched value, then its item at each index of the path.
+  fn load_matched(&mut self, path: &[usize]) -> Result<(), InterpretError> {
+    self.hidden_variable("match")?;
+    for &index in path {
+      self.emit_constant(Value::number_val(index as f64))?;
+      self.emit_byte(OpCode::GetIndex as u8)?;
+    }
+    Ok(())
+  }
+
+  /// Jump to the next arm if the test failed, otherwise pop its result.
+  fn fail_unless(&mut self, fail_jumps: &mut Vec<usize>) -> Result<(), InterpretError> {
+    fail_jumps.push(self.emit_jump(OpCode::JumpIfFalse as u8)?);
+    self.emit_byte(OpCode::Pop as u8)
+  }
+
+  /// Declare the name as a local, initialized by the value on top of the stack.
+  fn bind(&mut self, name: &Token) -> Result<(), InterpretError> {
+    let previous = core::mem::replace(&mut self.previous, name.clone());
+    let result = self.declare_variable();
+    self.previous = previous;
+    result?;
+    self.mark_initialized();
+    Ok(())
+  }
+}
@@ -5,9 +5,19 @@ impl Parser {
     self.number()
   }
 
+  /// A number literal, warned about if it can't be represented exactly as `f64`
+  /// (e.g. integers beyond `2^53`), since it's silently rounded.
   pub(crate) fn number(&mut self) -> Result<(), InterpretError> {
     match self.previous.lexeme.parse::<f64>() {
-      Ok(value) => self.emit_constant(value.into()),
+      Ok(value) => {
+        if loses_precision(&self.previous.lexeme, value) {
+          self.warning(format!(
+            "Number literal can't be represented exactly, it's rounded to `{}`.",
+            display::number(value)
+          ));
+        }
+        self.emit_constant(value.into())
+      }
       Err(_) => Err(InterpretError::CompileError(
         "Failed to parse number(value).".into(),
       )),
@@ -19,36 +29,305 @@ impl Parser {
   }
 
   pub(crate) fn string(&mut self) -> Result<(), InterpretError> {
-    let len = self.previous.lexeme.len();
-    let rust_string = self.previous.lexeme[1..len - 1].to_owned();
-    let obj_string = ObjString::from(rust_string);
-    let obj = obj_string.cast_to_obj_ptr();
-    self.emit_constant(Value::obj_val(obj))
+    self.warn_unknown_escape();
+    let rust_string = string_value(&self.previous.lexeme);
+    let index = self.string_constant(&rust_string)?;
+    self.emit_bytes(&[OpCode::Constant as u8, index])
+  }
+
+  /// Warn about the first unknown escape sequence (kept as it is) of the previous string
+  /// (or segment of it), e.g. `"C:\dir"`, which would change meaning if it's ever supported.
+  fn warn_unknown_escape(&mut self) {
+    if self.previous.lexeme.starts_with('r') {
+      return;
+    }
+    if let Some(escape) = unknown_escape(&self.previous.lexeme) {
+      self.warning(format!(
+        "Unknown escape sequence `{}` is kept as it is, write `\\\\` for a backslash.",
+        escape
+      ));
+    }
+  }
+
+  pub(crate) fn interpolation_adapter(&mut self, _: bool) -> Result<(), InterpretError> {
+    self.interpolation()
+  }
+
+  /// An interpolated string (`"x = ${x + 1}"`), from its first segment.
+  ///
+  /// Each expression is stringified, then concatenated with segments around it from left
+  /// to right (empty segments are left out).
+  pub(crate) fn interpolation(&mut self) -> Result<(), InterpretError> {
+    let triple = self.previous.lexeme.starts_with("\"\"\"");
+    let mut has_value = self.segment(triple, true, false)?;
+    loop {
+      if self.check_segment(TokenType::String) || self.check_segment(TokenType::Dollar) {
+        return self.error_at_current("Expect expression inside '${}'.".into());
+      }
+      self.expression()?;
+      self.emit_byte(OpCode::Stringify as u8)?;
+      if has_value {
+        self.emit_byte(OpCode::Add as u8)?;
+      }
+      has_value = true;
+      if !self.check_segment(TokenType::Dollar) {
+        break;
+      }
+      self.advance_token()?;
+      self.segment(triple, true, true)?;
+    }
+    if !self.check_segment(TokenType::String) {
+      return self.error_at_current("Expect '}' after interpolated expression.".into());
+    }
+    self.advance_token()?;
+    self.segment(triple, false, true).map(|_| ())
+  }
+
+  /// If the current token is a segment of type `token_type` going on after an interpolated
+  /// expression (rather than a string inside the expression).
+  fn check_segment(&self, token_type: TokenType) -> bool {
+    self.current.token_type == token_type && self.current.lexeme.starts_with('}')
+  }
+
+  /// Emit the previous segment of an interpolated string (added to the string so far if
+  /// `concat`), unless it's empty. Returns if it's emitted.
+  fn segment(&mut self, triple: bool, dollar: bool, concat: bool) -> Result<bool, InterpretError> {
+    self.warn_unknown_escape();
+    let value = segment_value(&self.previous.lexeme, triple, dollar);
+    if value.is_empty() {
+      return Ok(false);
+    }
+    let index = self.string_constant(&value)?;
+    self.emit_bytes(&[OpCode::Constant as u8, index])?;
+    if concat {
+      self.emit_byte(OpCode::Add as u8)?;
+    }
+    Ok(true)
   }
 
   pub(crate) fn named_variable(&mut self, can_assign: bool) -> Result<(), InterpretError> {
-    let arg = self.resolve_local()?;
-    let (arg, get_op, set_op) = if let Some(arg) = arg {
-      (arg as u8, OpCode::GetLocal, OpCode::SetLocal)
-    } else {
-      (
-        self.identifier_constant()?,
-        OpCode::GetGlobal,
-        OpCode::SetGlobal,
-      )
-    };
+    match self.resolve_variable()? {
+      Resolved::Local(slot) => {
+        return if can_assign && self.match_token(TokenType::Equal)? {
+          self.expression()?;
+          self.emit_local(OpCode::SetLocal, OpCode::SetLocalLong, slot)
+        } else {
+          self.emit_local(OpCode::GetLocal, OpCode::GetLocalLong, slot)
+        };
+      }
+      Resolved::Upvalue(index) => {
+        return if can_assign && self.match_token(TokenType::Equal)? {
+          self.expression()?;
+          self.emit_bytes(&[OpCode::SetUpvalue as u8, index as u8])
+        } else {
+          self.emit_bytes(&[OpCode::GetUpvalue as u8, index as u8])
+        };
+      }
+      Resolved::Global => {}
+    }
+    let arg = self.identifier_constant()?;
+    let name = self.options.strict.then(|| self.previous.to_owned());
     if can_assign && self.match_token(TokenType::Equal)? {
+      if let Some(name) = name {
+        self.global_assignments.push(name);
+      }
       self.expression()?;
-      self.emit_bytes(&[set_op as u8, arg])
+      self.emit_bytes(&[OpCode::SetGlobal as u8, arg])
     } else {
-      self.emit_bytes(&[get_op as u8, arg])
+      self.emit_bytes(&[OpCode::GetGlobal as u8, arg])
     }
   }
 
+  /// A variable, or `name++`/`name--` (whose result is the value before the change).
   pub(crate) fn variable(&mut self, can_assign: bool) -> Result<(), InterpretError> {
+    if let Some(op_code) = self.take_increment() {
+      return self.increment_variable(op_code, false);
+    }
+    if self.check_increment() {
+      let name = self.previous.clone();
+      self.advance_token()?;
+      let operator = core::mem::replace(&mut self.previous, name);
+      let result = self.increment_variable(increment_op_code(operator.token_type), true);
+      self.previous = operator;
+      return result;
+    }
     self.named_variable(can_assign)
   }
 
+  /// `++target`/`--target`, whose result is the value after the change.
+  ///
+  /// The target is compiled as any chain of accesses (e.g. `a.b[i].c`),
+  /// whose last access (of a variable, a property or an item) takes the operator.
+  pub(crate) fn prefix_increment(&mut self, _: bool) -> Result<(), InterpretError> {
+    let op_code = increment_op_code(self.previous.token_type);
+    self.target(PendingTarget::Increment(op_code), INVALID_INCREMENT_TARGET)
+  }
+
+  /// Compile a target (a chain of accesses), whose last access takes the pending target,
+  /// reporting the error if it doesn't.
+  pub(crate) fn target(
+    &mut self,
+    pending: PendingTarget,
+    error: &str,
+  ) -> Result<(), InterpretError> {
+    self.pending_target = Some((pending, self.nesting_depth + 1));
+    let result = self.parse_precedence(Precedence::Call);
+    if self.pending_target.take().is_some() && result.is_ok() {
+      return self.error(error.into());
+    }
+    result
+  }
+
+  /// `++`/`--` after anything but a variable, a property or an item
+  /// (which are handled by their own rules).
+  pub(crate) fn postfix_increment(&mut self, _: bool) -> Result<(), InterpretError> {
+    self.error(INVALID_INCREMENT_TARGET.into())
+  }
+
+  /// The pending target, if the access just compiled is the last one of the target
+  /// (the last one of the chain at the nesting depth of the target).
+  fn pending_target(&mut self) -> Option<PendingTarget> {
+    let (pending, depth) = self.pending_target?;
+    let chained = [TokenType::Dot, TokenType::LeftBracket, TokenType::LeftParen]
+      .iter()
+      .any(|&token_type| self.check_token(token_type));
+    (depth == self.nesting_depth && !chained).then_some(pending)
+  }
+
+  /// Operator of the pending prefix `++`/`--`, if the access just compiled is its target.
+  fn take_increment(&mut self) -> Option<OpCode> {
+    let Some(PendingTarget::Increment(op_code)) = self.pending_target() else {
+      return None;
+    };
+    self.pending_target = None;
+    Some(op_code)
+  }
+
+  /// If the access just compiled is the target of the pending `delete`.
+  fn take_delete(&mut self) -> bool {
+    let Some(PendingTarget::Delete) = self.pending_target() else {
+      return false;
+    };
+    self.pending_target = None;
+    true
+  }
+
+  /// Operator of the postfix `++`/`--` following the access just compiled (if any).
+  fn match_increment(&mut self) -> Result<Option<OpCode>, InterpretError> {
+    if !self.check_increment() {
+      return Ok(None);
+    }
+    self.advance_token()?;
+    Ok(Some(increment_op_code(self.previous.token_type)))
+  }
+
+  /// If the current token is `++` or `--`.
+  fn check_increment(&mut self) -> bool {
+    self.check_token(TokenType::PlusPlus) || self.check_token(TokenType::MinusMinus)
+  }
+
+  /// Compile `name = name + 1` (or `- 1`) of the previous name,
+  /// leaving the value before the change instead if it's postfix.
+  fn increment_variable(&mut self, op_code: OpCode, postfix: bool) -> Result<(), InterpretError> {
+    self.named_variable(false)?;
+    if postfix {
+      self.named_variable(false)?;
+    }
+    self.emit_constant(Value::number_val(1.0))?;
+    self.emit_byte(op_code as u8)?;
+    match self.resolve_variable()? {
+      Resolved::Local(slot) => self.emit_local(OpCode::SetLocal, OpCode::SetLocalLong, slot)?,
+      Resolved::Upvalue(index) => self.emit_bytes(&[OpCode::SetUpvalue as u8, index as u8])?,
+      Resolved::Global => {
+        if self.options.strict {
+          self.global_assignments.push(self.previous.to_owned());
+        }
+        let arg = self.identifier_constant()?;
+        self.emit_bytes(&[OpCode::SetGlobal as u8, arg])?;
+      }
+    }
+    if postfix {
+      self.emit_byte(OpCode::Pop as u8)?;
+    }
+    Ok(())
+  }
+
+  /// Compile `target = target + 1` (or `- 1`) of a property or an item, whose `operands`
+  /// (the instance, or the list and the index) are on the stack, by copying them for `get`
+  /// (which are consumed by `set`).
+  ///
+  /// If it's postfix, a copy of the value before the change is sunk below the operands,
+  /// and left as the result.
+  fn increment_access(
+    &mut self,
+    get: &[u8],
+    set: &[u8],
+    operands: u8,
+    op_code: OpCode,
+    postfix: bool,
+  ) -> Result<(), InterpretError> {
+    for _ in 0..operands {
+      self.emit_bytes(&[OpCode::Peek as u8, operands - 1])?;
+    }
+    self.emit_bytes(get)?;
+    if postfix {
+      self.emit_bytes(&[OpCode::Peek as u8, 0])?;
+      self.emit_bytes(&[OpCode::Sink as u8, operands + 1])?;
+    }
+    self.emit_constant(Value::number_val(1.0))?;
+    self.emit_byte(op_code as u8)?;
+    self.emit_bytes(set)?;
+    if postfix {
+      self.emit_byte(OpCode::Pop as u8)?;
+    }
+    Ok(())
+  }
+
+  /// `this` is resolved as the hidden local in slot zero of methods.
+  pub(crate) fn this(&mut self, _: bool) -> Result<(), InterpretError> {
+    if self.class_compiler.is_none() {
+      return self.error("Can't use `this` outside of a class.".into());
+    }
+    // alternative spellings of `this` refer to the same local
+    self.previous.lexeme = "this".into();
+    self.named_variable(false)
+  }
+
+  /// `super.name` binds the method of the superclass to `this`,
+  /// and `super.name(..)` invokes it (without binding).
+  pub(crate) fn super_(&mut self, _: bool) -> Result<(), InterpretError> {
+    match &self.class_compiler {
+      None => return self.error("Can't use `super` outside of a class.".into()),
+      Some(class) if !class.has_superclass => {
+        return self.error("Can't use `super` in a class with no superclass.".into());
+      }
+      Some(_) => {}
+    }
+    self.consume_token(TokenType::Dot, "Expect `.` after `super`.")?;
+    self.consume_token(TokenType::Identifier, "Expect superclass method name.")?;
+    let name = self.identifier_constant()?;
+    self.hidden_variable("this")?;
+    if self.match_token(TokenType::LeftParen)? {
+      let arg_count = self.argument_list()?;
+      self.hidden_variable("super")?;
+      match arg_count {
+        Some(arg_count) => self.emit_bytes(&[OpCode::SuperInvoke as u8, name, arg_count]),
+        None => self.emit_bytes(&[OpCode::SpreadSuperInvoke as u8, name]),
+      }
+    } else {
+      self.hidden_variable("super")?;
+      self.emit_bytes(&[OpCode::GetSuper as u8, name])
+    }
+  }
+
+  /// Load the variable of the name, which can't be spelled by users (e.g. `super`).
+  pub(crate) fn hidden_variable(&mut self, name: &str) -> Result<(), InterpretError> {
+    let lexeme = core::mem::replace(&mut self.previous.lexeme, name.into());
+    let result = self.named_variable(false);
+    self.previous.lexeme = lexeme;
+    result
+  }
+
   pub(crate) fn unary_adapter(&mut self, _: bool) -> Result<(), InterpretError> {
     self.unary()
   }
@@ -85,10 +364,13 @@ impl Parser {
       TokenType::GreaterEqual => self.emit_byte(OpCode::GreaterEqual as u8),
       TokenType::Less => self.emit_byte(OpCode::Less as u8),
       TokenType::LessEqual => self.emit_byte(OpCode::LessEqual as u8),
+      TokenType::Is => self.emit_byte(OpCode::Is as u8),
+      TokenType::In => self.emit_byte(OpCode::Contains as u8),
       TokenType::Plus => self.emit_byte(OpCode::Add as u8),
       TokenType::Minus => self.emit_byte(OpCode::Subtract as u8),
       TokenType::Star => self.emit_byte(OpCode::Multiply as u8),
       TokenType::Slash => self.emit_byte(OpCode::Divide as u8),
+      TokenType::Div => self.emit_byte(OpCode::FloorDivide as u8),
       _ => Err(InterpretError::CompileError(
         "Unknown unary operator.".into(),
       )),
@@ -116,10 +398,152 @@ impl Parser {
 
   pub(crate) fn grouping(&mut self) -> Result<(), InterpretError> {
     self.expression()?;
-    self.consume_token(
+    self.consume_token(TokenType::RightParen, "Expect `)` after expression.")
+  }
+}
+
+impl Parser {
+  pub(crate) fn call(&mut self, _: bool) -> Result<(), InterpretError> {
+    match self.argument_list()? {
+      Some(arg_count) => self.emit_bytes(&[OpCode::Call as u8, arg_count]),
+      None => self.emit_byte(OpCode::SpreadCall as u8),
+    }
+  }
+
+  /// Arguments of a call: their count, or `None` if any is spread (see [`Parser::spread_items`]).
+  fn argument_list(&mut self) -> Result<Option<u8>, InterpretError> {
+    let arg_count = self.spread_items(
       TokenType::RightParen,
-      "Expect `)` after expression.".to_owned(),
-    )
+      false,
+      "Can't have more than 255 arguments.",
+    )?;
+    self.consume_token(TokenType::RightParen, "Expect `)` after arguments.")?;
+    Ok(arg_count)
+  }
+
+  /// Items (of a list literal or a call) separated by commas, up to the closing token,
+  /// where `...list` spreads the items of the list at runtime.
+  ///
+  /// Without spread items, the items are left on the stack as is, and their count is returned.
+  /// Otherwise `None` is returned, and one list of all items is left: items before the first
+  /// spread one are built into a list, which each following group of items (or spread list)
+  /// extends by `ExtendList`.
+  fn spread_items(
+    &mut self,
+    closing: TokenType,
+    trailing_comma: bool,
+    too_many: &str,
+  ) -> Result<Option<u8>, InterpretError> {
+    let mut count = 0_usize;
+    let mut spread = false;
+    if !self.check_token(closing) {
+      loop {
+        if self.match_token(TokenType::DotDotDot)? {
+          if !spread || count > 0 {
+            self.emit_bytes(&[OpCode::BuildList as u8, count as u8])?;
+          }
+          if spread && count > 0 {
+            self.emit_byte(OpCode::ExtendList as u8)?;
+          }
+          self.expression()?;
+          self.emit_byte(OpCode::ExtendList as u8)?;
+          (count, spread) = (0, true);
+        } else {
+          self.expression()?;
+          if count == u8::MAX as usize {
+            self.error(too_many.into())?;
+          }
+          count += 1;
+        }
+        if !self.match_token(TokenType::Comma)? || trailing_comma && self.check_token(closing) {
+          break;
+        }
+      }
+    }
+    if spread && count > 0 {
+      self.emit_bytes(&[OpCode::BuildList as u8, count as u8])?;
+      self.emit_byte(OpCode::ExtendList as u8)?;
+    }
+    Ok((!spread).then_some(count as u8))
+  }
+}
+
+impl Parser {
+  pub(crate) fn dot(&mut self, can_assign: bool) -> Result<(), InterpretError> {
+    self.consume_token(TokenType::Identifier, "Expect property name after `.`.")?;
+    let name = self.identifier_constant()?;
+
+    let get = [OpCode::GetProperty as u8, name];
+    let set = [OpCode::SetProperty as u8, name];
+    if self.take_delete() {
+      self.emit_bytes(&[OpCode::DeleteProperty as u8, name])
+    } else if let Some(op_code) = self.take_increment() {
+      self.increment_access(&get, &set, 1, op_code, false)
+    } else if let Some(op_code) = self.match_increment()? {
+      self.increment_access(&get, &set, 1, op_code, true)
+    } else if can_assign && self.match_token(TokenType::Equal)? {
+      self.expression()?;
+      self.emit_bytes(&[OpCode::SetProperty as u8, name])
+    } else if self.match_token(TokenType::LeftParen)? {
+      // `instance.method(args)` => fused property lookup and call
+      match self.argument_list()? {
+        Some(arg_count) => self.emit_bytes(&[OpCode::Invoke as u8, name, arg_count]),
+        None => self.emit_bytes(&[OpCode::SpreadInvoke as u8, name]),
+      }
+    } else {
+      self.emit_bytes(&[OpCode::GetProperty as u8, name])
+    }
+  }
+}
+
+impl Parser {
+  /// `[a, b, ..]` builds a list of the items (a trailing comma is allowed),
+  /// and `[a, ...rest]` spreads items of `rest` into it.
+  pub(crate) fn list(&mut self, _: bool) -> Result<(), InterpretError> {
+    let count = self.spread_items(
+      TokenType::RightBracket,
+      true,
+      "Can't have more than 255 items in a list literal.",
+    )?;
+    self.consume_token(TokenType::RightBracket, "Expect `]` after list items.")?;
+    match count {
+      Some(count) => self.emit_bytes(&[OpCode::BuildList as u8, count]),
+      None => Ok(()),
+    }
+  }
+
+  /// `list[index]` gets the item, and `list[index] = value` sets it
+  /// (`list[index]++` and the like change it).
+  ///
+  /// `list[start:end]` copies the items within the bounds, either of which could be omitted
+  /// (compiled as `nil`), e.g. `list[:end]` or `list[:]`.
+  pub(crate) fn index(&mut self, can_assign: bool) -> Result<(), InterpretError> {
+    if self.check_token(TokenType::Colon) {
+      self.emit_byte(OpCode::Nil as u8)?;
+    } else {
+      self.expression()?;
+    }
+    if self.match_token(TokenType::Colon)? {
+      if self.check_token(TokenType::RightBracket) {
+        self.emit_byte(OpCode::Nil as u8)?;
+      } else {
+        self.expression()?;
+      }
+      self.consume_token(TokenType::RightBracket, "Expect `]` after slice.")?;
+      return self.emit_byte(OpCode::GetSlice as u8);
+    }
+    self.consume_token(TokenType::RightBracket, "Expect `]` after index.")?;
+    let (get, set) = ([OpCode::GetIndex as u8], [OpCode::SetIndex as u8]);
+    if let Some(op_code) = self.take_increment() {
+      self.increment_access(&get, &set, 2, op_code, false)
+    } else if let Some(op_code) = self.match_increment()? {
+      self.increment_access(&get, &set, 2, op_code, true)
+    } else if can_assign && self.match_token(TokenType::Equal)? {
+      self.expression()?;
+      self.emit_byte(OpCode::SetIndex as u8)
+    } else {
+      self.emit_byte(OpCode::GetIndex as u8)
+    }
   }
 }
 
@@ -154,4 +578,130 @@ impl Parser {
     self.parse_precedence(Precedence::Or)?;
     self.patch_jump(end_jump)
   }
+
+  /// `cond ? then : else`, which nests to the right (`a ? b : c ? d : e`).
+  pub(crate) fn ternary(&mut self, _: bool) -> Result<(), InterpretError> {
+    /* condition: has been compiled */
+
+    // condition == false: jump/ignore then branch
+    let else_jump = self.emit_jump(OpCode::JumpIfFalse as u8)?;
+    self.emit_byte(OpCode::Pop as u8)?;
+    self.expression()?;
+    self.consume_token(
+      TokenType::Colon,
+      "Expect `:` after then branch of conditional expression.",
+    )?;
+
+    // then branch is done: jump/ignore else branch
+    let end_jump = self.emit_jump(OpCode::Jump as u8)?;
+    self.patch_jump(else_jump)?;
+    self.emit_byte(OpCode::Pop as u8)?;
+    self.parse_precedence(Precedence::Ternary)?;
+    self.patch_jump(end_jump)
+  }
+}
+
+impl Parser {
+  /// Action of the registered operator spelled by the previous token.
+  fn operator_action(&mut self, infix: bool) -> Result<OperatorAction, InterpretError> {
+    let rule = self.plugins.operator(&self.previous.lexeme);
+    match rule.and_then(|rule| if infix { &rule.infix } else { &rule.prefix }.clone()) {
+      Some(action) => Ok(action),
+      None => Err(
+        self
+          .error(format!("Unknown operator `{}`.", self.previous.lexeme))
+          .unwrap_err(),
+      ),
+    }
+  }
+
+  /// Registered prefix operator, which binds as unary operators.
+  pub(crate) fn operator_prefix(&mut self, _: bool) -> Result<(), InterpretError> {
+    match self.operator_action(false)? {
+      OperatorAction::OpCodes(op_codes) => {
+        self.parse_precedence(Precedence::Unary)?;
+        self.emit_op_codes(&op_codes)
+      }
+      OperatorAction::Call(name) => {
+        let name = self.current_chunk().alloc_string(name);
+        let name = self.make_constant(name)?;
+        self.emit_bytes(&[OpCode::GetGlobal as u8, name])?;
+        self.parse_precedence(Precedence::Unary)?;
+        self.emit_bytes(&[OpCode::Call as u8, 1])
+      }
+    }
+  }
+
+  /// Registered infix operator, which is left-associative.
+  pub(crate) fn operator_infix(&mut self, _: bool) -> Result<(), InterpretError> {
+    let precedence = self.rule_of(&self.previous).precedence;
+    let action = self.operator_action(true)?;
+    self.parse_precedence(precedence.next())?;
+    match action {
+      OperatorAction::OpCodes(op_codes) => self.emit_op_codes(&op_codes),
+      OperatorAction::Call(name) => {
+        let name = self.current_chunk().alloc_string(name);
+        let name = self.make_constant(name)?;
+        self.emit_bytes(&[OpCode::CallGlobal as u8, name, 2])
+      }
+    }
+  }
+
+  fn emit_op_codes(&mut self, op_codes: &[OpCode]) -> Result<(), InterpretError> {
+    for &op_code in op_codes {
+      self.emit_byte(op_code as u8)?;
+    }
+    Ok(())
+  }
+}
+
+const INVALID_INCREMENT_TARGET: &str =
+  "Can only increment or decrement variables, properties or items.";
+
+/// `Add` for `++`, `Subtract` for `--`.
+fn increment_op_code(token_type: TokenType) -> OpCode {
+  match token_type {
+    TokenType::PlusPlus => OpCode::Add,
+    _ => OpCode::Subtract,
+  }
+}
+
+/// If the decimal literal is rounded when parsed into the value.
+///
+/// Integers must be exact, while other literals only lose precision if they have more
+/// significant digits than the value (e.g. `0.1` is fine, but `0.10000000000000000001` isn't).
+fn loses_precision(literal: &str, value: f64) -> bool {
+  if !value.is_finite() {
+    return true;
+  }
+  // decimals of up to 15 significant digits survive the round trip through `f64`,
+  // which keeps the common case from allocating
+  if literal.bytes().filter(u8::is_ascii_digit).count() <= f64::DIGITS as usize {
+    return false;
+  }
+  let (digits, exponent) = significant_digits(literal);
+  let is_integer = digits.len() as isize <= exponent + 1;
+  let exact = match is_integer {
+    true => format!("{:.0}", value),
+    false => format!("{:e}", value),
+  };
+  (digits, exponent) != significant_digits(&exact)
+}
+
+/// Significant digits of the decimal (`digits[.digits][e exponent]`),
+/// with the exponent of the first one (`("", 0)` for zero).
+fn significant_digits(decimal: &str) -> (String, isize) {
+  let (mantissa, exponent) = match decimal.split_once('e') {
+    Some((mantissa, exponent)) => (mantissa, exponent.parse().unwrap_or(0)),
+    None => (decimal, 0),
+  };
+  let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+  let all = format!("{}{}", integer, fraction);
+  let leading_zeros = all.len() - all.trim_start_matches('0').len();
+  let digits = all.trim_matches('0').to_owned();
+  if digits.is_empty() {
+    return (digits, 0);
+  }
+  let exponent = integer.len() as isize - 1 - leading_zeros as isize + exponent;
+  (digits, exponent)
 }
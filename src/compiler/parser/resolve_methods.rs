@@ -0,0 +1,254 @@
+use super::*;
+
+/// ## Resolved
+///
+/// Where a name refers to, decided before code is emitted for it.
+pub(crate) enum Resolved {
+  /// A local of the current function, in the slot.
+  Local(usize),
+  /// A local of an enclosing function, captured by the upvalue of the index.
+  Upvalue(usize),
+  /// A global (late-bound, so it may be defined later).
+  Global,
+}
+
+impl Parser {
+  /// Declare the variable named by the previous token in the current scope.
+  ///
+  /// Locals get their slots right away, while globals are only recorded
+  /// (they're defined by `DefineGlobal` at runtime).
+  pub(crate) fn declare_variable(&mut self) -> Result<(), InterpretError> {
+    if self.compiler.scope_depth == 0 {
+      self.declare(DeclarationKind::Global, None);
+      return Ok(());
+    }
+
+    // two variables with the same name in the same scope
+    let scope_depth = self.compiler.scope_depth;
+    let duplicate = self.compiler.locals[..self.compiler.local_count]
+      .iter()
+      .rev()
+      .take_while(|local| local.depth == scope_depth)
+      .find(|local| local.name.lexeme == self.previous.lexeme);
+    if let Some(local) = duplicate {
+      let message = format!(
+        "Already a variable with this name in this scope, declared at line {}.",
+        local.name.line
+      );
+      return self.error(message);
+    }
+
+    if self.options.warn_shadowing {
+      self.warn_shadowing();
+    }
+    self.add_local()
+  }
+
+  /// Add an initialized local of the name, which can't be spelled by users (e.g. `super`),
+  /// so it's declared implicitly (like the receiver `this`).
+  pub(crate) fn add_hidden_local(&mut self, name: &str) -> Result<(), InterpretError> {
+    if self.compiler.local_count >= LOCALS_MAX {
+      return self.error(format!(
+        "Too many local variables in function(At most: {}).",
+        LOCALS_MAX
+      ));
+    }
+    let slot = self.compiler.local_count;
+    if slot == self.compiler.locals.len() {
+      self.compiler.locals.push(Local::default());
+    }
+    let local = &mut self.compiler.locals[slot];
+    local.name.clone_from(&self.previous);
+    local.name.lexeme = name.into();
+    local.depth = self.compiler.scope_depth;
+    local.is_initialized = true;
+    local.declaration = None;
+    local.is_captured = false;
+    self.compiler.local_count += 1;
+    Ok(())
+  }
+
+  /// Mark the variable declared last as a parameter.
+  pub(crate) fn mark_parameter(&mut self) {
+    if let Some(declaration) = self.resolutions.declarations.last_mut() {
+      declaration.kind = DeclarationKind::Parameter;
+    }
+  }
+
+  /// Resolve the name of the previous token, then record the reference.
+  ///
+  /// Locals are searched backwards, so the innermost (shadowing) one wins.
+  /// Then locals of enclosing functions, which are captured as upvalues.
+  pub(crate) fn resolve_variable(&mut self) -> Result<Resolved, InterpretError> {
+    let name = &self.previous.lexeme;
+    let Some(slot) = self.compiler.resolve_local(name) else {
+      return match self.compiler.resolve_upvalue(name) {
+        Ok(Some((index, declaration))) => {
+          if let Some(declaration) = declaration {
+            self.refer(Some(declaration));
+          }
+          Ok(Resolved::Upvalue(index))
+        }
+        Ok(None) => {
+          self.refer(None);
+          Ok(Resolved::Global)
+        }
+        Err(message) => {
+          self.error(message.into())?;
+          Ok(Resolved::Global)
+        }
+      };
+    };
+    let Local {
+      is_initialized,
+      declaration,
+      ..
+    } = self.compiler.locals[slot];
+    if !is_initialized {
+      self.error("Can't read local variable in its own initializer.".into())?;
+    }
+    // the receiver (`this`) is declared implicitly
+    if let Some(declaration) = declaration {
+      self.refer(Some(declaration));
+    }
+    Ok(Resolved::Local(slot))
+  }
+
+  /// Warn if the local being declared shadows an outer local or global.
+  fn warn_shadowing(&mut self) {
+    let name = &self.previous.lexeme;
+    let shadowed = self.compiler.locals[..self.compiler.local_count]
+      .iter()
+      .rev()
+      .find(|local| &local.name.lexeme == name)
+      .map(|local| ("local", local.name.line))
+      .or_else(|| {
+        let global = self.resolutions.global(name)?;
+        Some(("global", global.location.line))
+      });
+    if let Some((kind, line)) = shadowed {
+      self.warning(format!(
+        "Local variable shadows {} `{}` declared at line {}.",
+        kind, name, line
+      ));
+    }
+  }
+
+  /// Locals beyond `SHORT_LOCALS_MAX` are spilled into two bytes slot,
+  /// each declaration which triggers spilling is reported as a warning.
+  fn add_local(&mut self) -> Result<(), InterpretError> {
+    if self.compiler.local_count >= LOCALS_MAX {
+      return self.error(format!(
+        "Too many local variables in function(At most: {}).",
+        LOCALS_MAX
+      ));
+    }
+    if self.compiler.local_count >= SHORT_LOCALS_MAX {
+      self.warning(format!(
+        "Local variable spilled into wide slot `{}`.",
+        self.compiler.local_count
+      ));
+    }
+    let slot = self.compiler.local_count;
+    let declaration = self.declare(DeclarationKind::Local, Some(slot));
+    if slot == self.compiler.locals.len() {
+      self.compiler.locals.push(Local::default());
+    }
+    let local = &mut self.compiler.locals[slot];
+    local.name.clone_from(&self.previous);
+    local.depth = self.compiler.scope_depth;
+    local.is_initialized = false;
+    local.declaration = Some(declaration);
+    local.is_captured = false;
+    self.compiler.local_count += 1;
+    Ok(())
+  }
+
+  /// Record the declaration of the previous token, then return its index.
+  fn declare(&mut self, kind: DeclarationKind, slot: Option<usize>) -> usize {
+    let name = self.resolutions.intern(&self.previous.lexeme);
+    let function = self.resolutions.intern(&self.compiler.qualified_name);
+    self.resolutions.declare(Declaration {
+      name,
+      kind,
+      location: self.previous_location(),
+      function,
+      slot,
+    })
+  }
+
+  /// Record the reference of the previous token.
+  fn refer(&mut self, declaration: Option<usize>) {
+    let location = self.previous_location();
+    self
+      .resolutions
+      .refer(&self.previous.lexeme, location, declaration);
+  }
+
+  fn previous_location(&self) -> SourceLocation {
+    SourceLocation {
+      line: self.previous.line,
+      column: self.previous.column,
+    }
+  }
+}
+
+impl Compiler {
+  /// Slot of the innermost local of the given name.
+  fn resolve_local(&self, name: &str) -> Option<usize> {
+    self.locals[..self.local_count]
+      .iter()
+      .rposition(|local| local.name.lexeme == name)
+  }
+
+  /// Index of the upvalue capturing the variable of the given name from enclosing functions
+  /// (with the declaration of the captured local), `None` if it's a global.
+  ///
+  /// The captured local is marked, so it's closed at the end of its scope,
+  /// and each function between captures it as an upvalue of its own.
+  fn resolve_upvalue(
+    &mut self,
+    name: &str,
+  ) -> Result<Option<(usize, Option<usize>)>, &'static str> {
+    let Some(enclosing) = self.enclosing.as_deref_mut() else {
+      return Ok(None);
+    };
+    if let Some(slot) = enclosing.resolve_local(name) {
+      let local = &mut enclosing.locals[slot];
+      local.is_captured = true;
+      let declaration = local.declaration;
+      return Ok(Some((
+        self.add_upvalue(slot, true, declaration)?,
+        declaration,
+      )));
+    }
+    match enclosing.resolve_upvalue(name)? {
+      Some((index, declaration)) => Ok(Some((
+        self.add_upvalue(index, false, declaration)?,
+        declaration,
+      ))),
+      None => Ok(None),
+    }
+  }
+
+  /// Index of the upvalue (capturing the variable of the declaration),
+  /// added if the function doesn't capture it yet.
+  fn add_upvalue(
+    &mut self,
+    index: usize,
+    is_local: bool,
+    declaration: Option<usize>,
+  ) -> Result<usize, &'static str> {
+    let upvalue = Upvalue { index, is_local };
+    let upvalues = &mut self.function.upvalues;
+    if let Some(existing) = upvalues.iter().position(|&captured| captured == upvalue) {
+      return Ok(existing);
+    }
+    if upvalues.len() == UPVALUES_MAX {
+      return Err("Too many closure variables in function.");
+    }
+    upvalues.push(upvalue);
+    self.captures.push(declaration);
+    Ok(upvalues.len() - 1)
+  }
+}
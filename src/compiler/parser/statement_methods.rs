@@ -3,15 +3,15 @@ use super::*;
 impl Parser {
   pub(crate) fn print_statement(&mut self) -> Result<(), InterpretError> {
     self.expression()?;
-    self.consume_token(TokenType::Semicolon, "Expect `;` after value.".into())?;
+    self.consume_token(TokenType::Semicolon, "Expect `;` after value.")?;
     self.emit_byte(OpCode::Print as u8)
   }
 
   pub(crate) fn if_statement(&mut self) -> Result<(), InterpretError> {
     /* condition */
-    self.consume_token(TokenType::LeftParen, "Expect `(` after `if`.".into())?;
+    self.consume_token(TokenType::LeftParen, "Expect `(` after `if`.")?;
     self.expression()?;
-    self.consume_token(TokenType::RightParen, "Expect `)` after condition.".into())?;
+    self.consume_token(TokenType::RightParen, "Expect `)` after condition.")?;
 
     /* `consume`: if {...} */
     let then_jump = self.emit_jump(OpCode::JumpIfFalse as u8)?;
@@ -34,37 +34,198 @@ impl Parser {
     self.patch_jump(else_jump)
   }
 
+  /// `while (condition) body`: the body runs as long as the condition holds,
+  /// then jumps back to check the condition again.
+  pub(crate) fn while_statement(&mut self) -> Result<(), InterpretError> {
+    let loop_start = self.current_chunk().code.len();
+    self.consume_token(TokenType::LeftParen, "Expect `(` after `while`.")?;
+    self.expression()?;
+    self.consume_token(TokenType::RightParen, "Expect `)` after condition.")?;
+
+    let exit_jump = self.emit_jump(OpCode::JumpIfFalse as u8)?;
+    // pop the condition **iff** it's true
+    self.emit_byte(OpCode::Pop as u8)?;
+    self.statement()?;
+    self.emit_loop(loop_start)?;
+
+    self.patch_jump(exit_jump)?;
+    // pop the condition **iff** it's false
+    self.emit_byte(OpCode::Pop as u8)
+  }
+
+  /// `for (initializer; condition; increment) body`, desugared onto jumps of `while`:
+  /// the increment runs after each pass of the body, right before the condition is checked again.
+  ///
+  /// All clauses are optional, and a variable declared by the initializer is scoped to the loop.
+  pub(crate) fn for_statement(&mut self) -> Result<(), InterpretError> {
+    self.begin_scope();
+    self.consume_token(TokenType::LeftParen, "Expect `(` after `for`.")?;
+    if self.match_token(TokenType::Semicolon)? {
+      // no initializer
+    } else if self.match_token(TokenType::Var)? {
+      self.var_declaration()?;
+    } else {
+      self.expression_statement()?;
+    }
+
+    let mut loop_start = self.current_chunk().code.len();
+    let mut exit_jump = None;
+    if !self.match_token(TokenType::Semicolon)? {
+      self.expression()?;
+      self.consume_token(TokenType::Semicolon, "Expect `;` after loop condition.")?;
+      exit_jump = Some(self.emit_jump(OpCode::JumpIfFalse as u8)?);
+      // pop the condition **iff** it's true
+      self.emit_byte(OpCode::Pop as u8)?;
+    }
+
+    if !self.match_token(TokenType::RightParen)? {
+      // the increment is compiled before the body, so jump over it on the first pass
+      let body_jump = self.emit_jump(OpCode::Jump as u8)?;
+      let increment_start = self.current_chunk().code.len();
+      self.expression()?;
+      self.emit_byte(OpCode::Pop as u8)?;
+      self.consume_token(TokenType::RightParen, "Expect `)` after for clauses.")?;
+      self.emit_loop(loop_start)?;
+      loop_start = increment_start;
+      self.patch_jump(body_jump)?;
+    }
+
+    self.statement()?;
+    self.emit_loop(loop_start)?;
+
+    if let Some(exit_jump) = exit_jump {
+      self.patch_jump(exit_jump)?;
+      // pop the condition **iff** it's false
+      self.emit_byte(OpCode::Pop as u8)?;
+    }
+    self.end_scope()
+  }
+
+  pub(crate) fn return_statement(&mut self) -> Result<(), InterpretError> {
+    if self.compiler.function_type == FunctionType::Script {
+      return self.error("Can't return from top-level code.".into());
+    }
+    if self.match_token(TokenType::Semicolon)? {
+      self.emit_return()
+    } else {
+      if self.compiler.function_type == FunctionType::Initializer {
+        return self.error("Can't return a value from an initializer.".into());
+      }
+      if self.compiler.function_type == FunctionType::Setter {
+        return self.error("Can't return a value from a setter.".into());
+      }
+      self.expression()?;
+      // `return a, b;` returns a list of the values (unpacked by `var a, b = ..;`)
+      let mut count = 1_usize;
+      while self.match_token(TokenType::Comma)? {
+        self.expression()?;
+        if count == u8::MAX as usize {
+          return self.error("Can't return more than 255 values.".into());
+        }
+        count += 1;
+      }
+      if count > 1 {
+        self.emit_bytes(&[OpCode::BuildList as u8, count as u8])?;
+      }
+      self.consume_token(TokenType::Semicolon, "Expect `;` after return value.")?;
+      self.emit_byte(OpCode::Return as u8)
+    }
+  }
+
+  /// `delete instance.field;`
+  ///
+  /// The target is compiled as a chain of accesses (see [`Parser::target`]),
+  /// whose last property access emits `DeleteProperty` instead of `GetProperty`.
+  pub(crate) fn delete_statement(&mut self) -> Result<(), InterpretError> {
+    self.target(
+      PendingTarget::Delete,
+      "Can only delete a property of an instance.",
+    )?;
+    self.consume_token(TokenType::Semicolon, "Expect `;` after delete target.")
+  }
+
+  /// `try { ... } catch (e) { ... }`
+  ///
+  /// A value thrown in the `try` block (by `throw`, or as a runtime error)
+  /// unwinds to the `catch` block, where it's bound to the variable.
+  pub(crate) fn try_statement(&mut self) -> Result<(), InterpretError> {
+    /* `consume`: try {...} */
+    let handler = self.emit_jump(OpCode::PushHandler as u8)?;
+    self.consume_token(TokenType::LeftBrace, "Expect `{` after `try`.")?;
+    self.begin_scope();
+    self.block()?;
+    self.end_scope()?;
+    self.emit_byte(OpCode::PopHandler as u8)?;
+
+    /* patch `try` handler */
+    let end_jump = self.emit_jump(OpCode::Jump as u8)?;
+    self.patch_jump(handler)?;
+
+    /* `consume`: catch (e) {...} */
+    self.consume_token(TokenType::Catch, "Expect `catch` after try block.")?;
+    self.consume_token(TokenType::LeftParen, "Expect `(` after `catch`.")?;
+    self.begin_scope();
+    // the thrown value is pushed by the virtual machine, right into the slot of the variable
+    self.consume_token(TokenType::Identifier, "Expect variable name.")?;
+    self.declare_variable()?;
+    self.mark_initialized();
+    self.consume_token(TokenType::RightParen, "Expect `)` after catch variable.")?;
+    self.consume_token(TokenType::LeftBrace, "Expect `{` before catch block.")?;
+    self.block()?;
+    self.end_scope()?;
+
+    /* patch `catch` jump */
+    self.patch_jump(end_jump)
+  }
+
+  pub(crate) fn throw_statement(&mut self) -> Result<(), InterpretError> {
+    self.expression()?;
+    self.consume_token(TokenType::Semicolon, "Expect `;` after thrown value.")?;
+    self.emit_byte(OpCode::Throw as u8)
+  }
+
   /// If in panic_mode, then synchronize (for better recognizing what error has occurred).
   ///
   /// Synchronize means that, we will skip tokens indiscriminately
   /// until we reach something that looks like a statement boundary.
   ///
-  /// E.g.: class | fun | var | for | if | while | print | return
-  pub(crate) fn synchronize(&mut self) -> Result<(), InterpretError> {
+  /// E.g.: class | fun | var | for | if | while | print | return | try | throw
+  ///
+  /// Error tokens met while skipping are recorded as well.
+  pub(crate) fn synchronize(&mut self) {
     self.panic_mode = false;
     while self.current.token_type != TokenType::Eof {
       if self.previous.token_type == TokenType::Semicolon {
-        return Ok(());
+        return;
       }
       match self.current.token_type {
         TokenType::Class
+        | TokenType::Trait
+        | TokenType::Enum
         | TokenType::Fun
         | TokenType::Var
         | TokenType::For
         | TokenType::If
         | TokenType::While
         | TokenType::Print
-        | TokenType::Return => return Ok(()),
+        | TokenType::Return
+        | TokenType::Try
+        | TokenType::Throw
+        | TokenType::Match
+        | TokenType::Import => return,
         _ => {}
       }
-      self.advance_token()?;
+      self.advance_and_report();
     }
-    Ok(())
   }
 
   pub(crate) fn expression_statement(&mut self) -> Result<(), InterpretError> {
     self.expression()?;
-    self.consume_token(TokenType::Semicolon, "Expect `;` after expression.".into())?;
+    self.consume_token(TokenType::Semicolon, "Expect `;` after expression.")?;
+    // only a statement directly in the script (not in a block or branch) could be the final one
+    if self.compiler.enclosing.is_none() && self.statement_depth == 1 {
+      self.result_pop = Some(self.current_chunk().code.len());
+    }
     self.emit_byte(OpCode::Pop as u8)
   }
 }
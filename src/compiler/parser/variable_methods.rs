@@ -1,25 +1,26 @@
 use super::*;
 
 impl Parser {
-  fn parse_variable(&mut self, message: String) -> Result<u8, InterpretError> {
+  pub(crate) fn parse_variable(&mut self, message: &str) -> Result<u8, InterpretError> {
     self.consume_token(TokenType::Identifier, message)?;
 
-    // record if it's a local variable (scope_depth > 0)
     self.declare_variable()?;
 
     // if in local scope, simply exit (with a fake index)
     if self.compiler.scope_depth > 0 {
       return Ok(0);
     }
-
     self.identifier_constant()
   }
 
-  fn mark_initialized(&mut self) {
+  pub(crate) fn mark_initialized(&mut self) {
+    if self.compiler.scope_depth == 0 {
+      return;
+    }
     self.compiler.locals[self.compiler.local_count - 1].is_initialized = true;
   }
 
-  fn define_variable(&mut self, global_index: u8) -> Result<(), InterpretError> {
+  pub(crate) fn define_variable(&mut self, global_index: u8) -> Result<(), InterpretError> {
     if self.compiler.scope_depth > 0 {
       self.mark_initialized();
       Ok(())
@@ -28,69 +29,55 @@ impl Parser {
     }
   }
 
-  /// Records the existence of variable (only for locals).
-  fn declare_variable(&mut self) -> Result<(), InterpretError> {
-    if self.compiler.scope_depth == 0 {
-      return Ok(());
-    }
-
-    // Detect error => two variables with same name
-    // in the same local scope.
-    for i in (0..self.compiler.local_count).rev() {
-      let local = &self.compiler.locals[i];
-      if local.is_initialized && local.depth < self.compiler.scope_depth {
-        break;
-      }
-      if self.previous.lexeme == local.name.lexeme {
-        return Err(InterpretError::CompileError(
-          "Already a variable with this name in this scope.".into(),
-        ));
-      }
-    }
-
-    self.add_local()
+  pub(crate) fn identifier_constant(&mut self) -> Result<u8, InterpretError> {
+    let name = core::mem::take(&mut self.previous.lexeme);
+    let result = self.string_constant(&name);
+    self.previous.lexeme = name;
+    result
   }
 
-  fn add_local(&mut self) -> Result<(), InterpretError> {
-    if self.compiler.local_count > u8::MAX as usize {
-      return Err(InterpretError::CompileError(
-        "Too many local variables in function(At most: 256).".into(),
-      ));
+  /// Add the string as a constant of the current chunk, or reuse the index of
+  /// the same string added before (by the same function).
+  pub(crate) fn string_constant(&mut self, text: &str) -> Result<u8, InterpretError> {
+    if let Some(&index) = self.compiler.string_constants.get(text) {
+      return Ok(index);
     }
-    let local = &mut self.compiler.locals[self.compiler.local_count];
-    local.name = self.previous.to_owned();
-    local.depth = self.compiler.scope_depth;
-    local.is_initialized = false;
-    self.compiler.local_count += 1;
-    Ok(())
-  }
-
-  pub(crate) fn identifier_constant(&mut self) -> Result<u8, InterpretError> {
-    self.make_constant(Value::obj_val(
-      ObjString::from(self.previous.lexeme.to_owned()).cast_to_obj_ptr(),
-    ))
+    let value = self.current_chunk().alloc_string(text.to_owned());
+    let index = self.make_constant(value)?;
+    self
+      .compiler
+      .string_constants
+      .insert(text.to_owned(), index);
+    Ok(index)
   }
 
-  /// Try to find the local variable in the current scope.
-  ///
-  /// If find, return the index of the local variable.
-  pub(crate) fn resolve_local(&mut self) -> Result<Option<usize>, InterpretError> {
-    let pos = self.compiler.locals[..self.compiler.local_count]
-      .iter()
-      .position(|local| local.name.lexeme == self.previous.lexeme);
-    if let Some(pos) = pos {
-      if !self.compiler.locals[pos].is_initialized {
-        return Err(InterpretError::CompileError(
-          "Can't read local variable in its own initializer.".into(),
-        ));
-      };
+  /// Strict mode: each assigned global must be declared (anywhere) in the script,
+  /// or already defined in the virtual machine (natives, previous REPL lines).
+  pub(crate) fn check_global_assignments(&mut self, globals: &Table) -> Result<(), InterpretError> {
+    for name in core::mem::take(&mut self.global_assignments) {
+      if self.resolutions.global(&name.lexeme).is_some()
+        || globals
+          .get(&ObjString::from(name.lexeme.to_owned()))
+          .is_some()
+      {
+        continue;
+      }
+      let message = format!("Assignment to undeclared variable `{}`.", name.lexeme);
+      self.previous = name;
+      return self.error(message);
     }
-    Ok(pos)
+    Ok(())
   }
 
   /// Declare: bind a new variable.
+  ///
+  /// `var a, b = list;` binds each variable to an item of the list instead (see `Unpack`),
+  /// e.g. the multiple results of `return a, b;`.
   pub(crate) fn var_declaration(&mut self) -> Result<(), InterpretError> {
-    let global_index = self.parse_variable("Expect variable name.".into())?;
+    let global_index = self.parse_variable("Expect variable name.")?;
+    if self.check_token(TokenType::Comma) {
+      return self.unpack_declaration(global_index);
+    }
 
     if self.match_token(TokenType::Equal)? {
       self.expression()?;
@@ -100,9 +87,48 @@ impl Parser {
 
     self.consume_token(
       TokenType::Semicolon,
-      "Expect `;` after variable declaration.".into(),
+      "Expect `;` after variable declaration.",
     )?;
 
     self.define_variable(global_index)
   }
+
+  /// The rest of `var a, b, .. = list;`, after the first variable.
+  fn unpack_declaration(&mut self, first_index: u8) -> Result<(), InterpretError> {
+    let mut global_indexes = vec![first_index];
+    while self.match_token(TokenType::Comma)? {
+      if global_indexes.len() == u8::MAX as usize {
+        return self.error("Can't unpack into more than 255 variables.".into());
+      }
+      global_indexes.push(self.parse_variable("Expect variable name.")?);
+    }
+    let count = global_indexes.len();
+
+    if self.match_token(TokenType::Equal)? {
+      self.expression()?;
+      self.emit_bytes(&[OpCode::Unpack as u8, count as u8])?;
+    } else {
+      for _ in 0..count {
+        self.emit_byte(OpCode::Nil as u8)?;
+      }
+    }
+
+    self.consume_token(
+      TokenType::Semicolon,
+      "Expect `;` after variable declaration.",
+    )?;
+
+    if self.compiler.scope_depth > 0 {
+      let local_count = self.compiler.local_count;
+      for local in &mut self.compiler.locals[local_count - count..local_count] {
+        local.is_initialized = true;
+      }
+      return Ok(());
+    }
+    // the last item is on top of the stack
+    for global_index in global_indexes.into_iter().rev() {
+      self.define_variable(global_index)?;
+    }
+    Ok(())
+  }
 }
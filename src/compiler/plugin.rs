@@ -0,0 +1,175 @@
+//! # Plugin
+//!
+//! Submodule of `compiler`, which lets embedders extend the grammar
+//! without forking the parser.
+//!
+//! An [`OperatorRule`] registers a prefix and/or infix operator (spelled by punctuation),
+//! which is compiled into the chosen opcodes, or a call of a global (e.g. native) function.
+//!
+//! ```text
+//! vm.define_native("matches", 2, matches);
+//! vm.register_operator(OperatorRule::infix("~", Precedence::Comparison, OperatorAction::Call("matches".into())))?;
+//! // `"rlox" ~ "lox"` => `matches("rlox", "lox")`
+//! ```
+//!
+//! The reserved words are configurable as well (see [`Keywords`]).
+
+use crate::{chunk::OpCode, prelude::*, scanner::Keywords, vm::InterpretError};
+
+use super::Precedence;
+
+/// Punctuation which could spell a registered operator.
+///
+/// Delimiters (`( ) { } , ; .`) and quotes are excluded.
+const OPERATOR_CHARS: &str = "~!@#$%^&*-+=|\\:<>?/";
+
+/// Spellings of built-in operators, which can't be registered (neither could comments).
+const RESERVED_SYMBOLS: &[&str] = &[
+  "-", "+", "/", "*", "!", "!=", "=", "==", ">", ">=", "<", "<=", "?", ":", "=>", "++", "--",
+];
+
+/// ## OperatorAction
+///
+/// What a registered operator is compiled into, after its operand(s).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OperatorAction {
+  /// Emit the opcodes (which must be simple ones, without operands).
+  OpCodes(Vec<OpCode>),
+  /// Call the global function of the given name, with the operand(s) as arguments.
+  Call(String),
+}
+
+/// ## OperatorRule
+///
+/// A prefix and/or infix operator registered by the embedder.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OperatorRule {
+  /// Spelling of the operator, e.g. `~`.
+  pub symbol: String,
+  /// Precedence of the infix form (the prefix form binds as unary operators).
+  pub precedence: Precedence,
+  pub prefix: Option<OperatorAction>,
+  pub infix: Option<OperatorAction>,
+}
+
+impl OperatorRule {
+  /// A prefix operator.
+  pub fn prefix(symbol: &str, action: OperatorAction) -> Self {
+    Self {
+      symbol: symbol.to_owned(),
+      precedence: Precedence::None,
+      prefix: Some(action),
+      infix: None,
+    }
+  }
+
+  /// An infix operator with the given precedence.
+  pub fn infix(symbol: &str, precedence: Precedence, action: OperatorAction) -> Self {
+    Self {
+      symbol: symbol.to_owned(),
+      precedence,
+      prefix: None,
+      infix: Some(action),
+    }
+  }
+
+  fn validate(&self) -> Result<(), String> {
+    if self.symbol.is_empty() || !self.symbol.chars().all(|c| OPERATOR_CHARS.contains(c)) {
+      return Err(format!(
+        "Operator `{}` should be spelled by `{}`.",
+        self.symbol, OPERATOR_CHARS
+      ));
+    }
+    let starts_comment = self.symbol.starts_with("//") || self.symbol.starts_with("/*");
+    if starts_comment || RESERVED_SYMBOLS.contains(&self.symbol.as_str()) {
+      return Err(format!("Operator `{}` is reserved.", self.symbol));
+    }
+    match (&self.prefix, &self.infix) {
+      (None, None) => return Err(format!("Operator `{}` has no action.", self.symbol)),
+      (_, Some(_)) if self.precedence <= Precedence::Assignment => {
+        return Err(format!(
+          "Infix operator `{}` should bind tighter than assignment.",
+          self.symbol
+        ))
+      }
+      _ => {}
+    }
+    for action in self.prefix.iter().chain(&self.infix) {
+      match action {
+        OperatorAction::OpCodes(op_codes) if op_codes.iter().any(|&op| !is_simple(op)) => {
+          return Err(format!(
+            "Operator `{}` could only emit opcodes without operands.",
+            self.symbol
+          ))
+        }
+        OperatorAction::Call(name) if name.is_empty() => {
+          return Err(format!("Operator `{}` calls nothing.", self.symbol))
+        }
+        _ => {}
+      }
+    }
+    Ok(())
+  }
+}
+
+/// If the opcode could be emitted by operators: it has no operand (see [`OpCode::spec`]),
+/// and it neither leaves the frame nor changes handlers or upvalues of it.
+fn is_simple(op_code: OpCode) -> bool {
+  use OpCode::*;
+  match op_code {
+    Return | Throw | PopHandler | CloseUpvalue => false,
+    Constant | Nil | True | False | Equal | Greater | Less | NotEqual | GreaterEqual
+    | LessEqual | Is | Contains | Add | Subtract | Multiply | Divide | FloorDivide | Not
+    | Negate | JumpIfFalse | Jump | Loop | Print | Pop | PopN | Peek | Sink | Stringify
+    | DefineGlobal | GetGlobal | GetLocal | SetGlobal | SetLocal | GetLocalLong | SetLocalLong
+    | MissingArgument | Call | CallGlobal | Closure | GetUpvalue | SetUpvalue | Class | Trait
+    | Mixin | Inherit | GetProperty | SetProperty | DeleteProperty | Method | Getter | Setter
+    | Invoke | GetSuper | SuperInvoke | Enum | EnumMember | BuildList | GetIndex | GetSlice
+    | SetIndex | ExtendList | SpreadCall | SpreadInvoke | SpreadSuperInvoke | Unpack
+    | MatchList | MatchListRest | Import | PushHandler => op_code.spec().operands.is_empty(),
+  }
+}
+
+/// ## Plugins
+///
+/// All extensions of the compiler registered by the embedder.
+#[derive(Debug, Clone, Default)]
+pub struct Plugins {
+  /// Registered operators, the longest symbol comes first (so it's scanned first).
+  pub(crate) operators: Vec<OperatorRule>,
+  /// Reserved words of the dialect.
+  pub(crate) keywords: Keywords,
+}
+
+impl Plugins {
+  /// Replace the reserved words of the dialect.
+  pub fn set_keywords(&mut self, keywords: Keywords) {
+    self.keywords = keywords;
+  }
+
+  /// Reserved words of the dialect.
+  pub fn keywords(&self) -> &Keywords {
+    &self.keywords
+  }
+
+  /// Register an operator, replacing the one with the same symbol.
+  pub fn register_operator(&mut self, rule: OperatorRule) -> Result<(), InterpretError> {
+    rule.validate().map_err(InterpretError::CompileError)?;
+    self.operators.retain(|r| r.symbol != rule.symbol);
+    self.operators.push(rule);
+    self
+      .operators
+      .sort_by_key(|r| core::cmp::Reverse(r.symbol.len()));
+    Ok(())
+  }
+
+  /// The operator spelled by the given symbol.
+  pub fn operator(&self, symbol: &str) -> Option<&OperatorRule> {
+    self.operators.iter().find(|r| r.symbol == symbol)
+  }
+
+  /// Symbols of all registered operators (the longest one comes first).
+  pub(crate) fn symbols(&self) -> Vec<String> {
+    self.operators.iter().map(|r| r.symbol.clone()).collect()
+  }
+}
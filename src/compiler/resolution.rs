@@ -0,0 +1,207 @@
+//! # Resolution
+//!
+//! Submodule of `compiler`, which records where each variable is declared and referred to.
+//!
+//! Names are resolved by the resolver (see `parser/resolve_methods.rs`) before any code is
+//! emitted for them, and each resolution is recorded into a [`ResolutionTable`]:
+//!
+//! - declarations: name, kind, site, declaring function and slot (of locals)
+//! - references: site and the declaration referred to
+//! - captures: upvalue of the capturing function and the declaration captured by it
+//!
+//! Globals are late-bound, so references to them are linked by a separate pass once the whole
+//! script is parsed (a function may refer to a global declared after it).
+//!
+//! Locals of enclosing functions are visible to functions defined in their scope
+//! (captured as upvalues), so references to them are linked to their declarations
+//! in the enclosing function.
+
+use alloc::rc::Rc;
+use hashbrown::HashSet;
+
+use crate::{chunk::SourceLocation, prelude::*};
+
+/// ## DeclarationKind
+///
+/// An enum which represents what declares a variable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeclarationKind {
+  /// Top-level `var`, `fun`, `class` or `trait`.
+  Global,
+  /// `var`, `fun`, `class` or `trait` in a block, or the variable of `catch`.
+  Local,
+  /// Parameter of a function.
+  Parameter,
+}
+
+/// ## Declaration
+///
+/// Where and how a variable is declared.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Declaration {
+  pub name: Rc<str>,
+  pub kind: DeclarationKind,
+  /// Site of the name in the declaration.
+  pub location: SourceLocation,
+  /// Qualified name of the declaring function (empty for the script).
+  pub function: Rc<str>,
+  /// Slot in the frame of the declaring function (`None` for globals).
+  pub slot: Option<usize>,
+}
+
+/// ## Reference
+///
+/// A variable referred to by name (read or assigned).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reference {
+  pub name: Rc<str>,
+  pub location: SourceLocation,
+  /// Index of the declaration referred to, `None` if it isn't declared by the script
+  /// (e.g. natives, or globals of previous REPL lines).
+  pub declaration: Option<usize>,
+}
+
+/// ## Capture
+///
+/// A variable captured by an upvalue of a function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capture {
+  /// Qualified name of the capturing function.
+  pub function: Rc<str>,
+  /// Index of the upvalue in the function.
+  pub index: usize,
+  /// Index of the declaration of the captured variable.
+  pub declaration: usize,
+}
+
+/// ## ResolutionTable
+///
+/// All declarations and references of a script, in order of their sites.
+#[derive(Debug, Clone, Default)]
+pub struct ResolutionTable {
+  pub(crate) declarations: Vec<Declaration>,
+  pub(crate) references: Vec<Reference>,
+  /// Variables captured by upvalues of each function.
+  pub(crate) captures: Vec<Capture>,
+  /// Names seen so far, shared by all entries (so repeated names allocate nothing).
+  pub(crate) names: HashSet<Rc<str>>,
+}
+
+impl ResolutionTable {
+  /// The shared copy of the name.
+  pub(crate) fn intern(&mut self, name: &str) -> Rc<str> {
+    match self.names.get(name) {
+      Some(name) => name.clone(),
+      None => {
+        let name: Rc<str> = Rc::from(name);
+        self.names.insert(name.clone());
+        name
+      }
+    }
+  }
+
+  /// Forget all entries (buffers and names are kept for the next script).
+  pub(crate) fn clear(&mut self) {
+    self.declarations.clear();
+    self.references.clear();
+    self.captures.clear();
+  }
+
+  /// Record a declaration, then return its index.
+  pub(crate) fn declare(&mut self, declaration: Declaration) -> usize {
+    self.declarations.push(declaration);
+    self.declarations.len() - 1
+  }
+
+  /// Record a reference.
+  pub(crate) fn refer(&mut self, name: &str, location: SourceLocation, declaration: Option<usize>) {
+    let name = self.intern(name);
+    self.references.push(Reference {
+      name,
+      location,
+      declaration,
+    });
+  }
+
+  /// Record the variable of the declaration as captured by the upvalue of the function.
+  pub(crate) fn capture(&mut self, function: &str, index: usize, declaration: usize) {
+    let function = self.intern(function);
+    self.captures.push(Capture {
+      function,
+      index,
+      declaration,
+    });
+  }
+
+  /// The first declaration of the global of the given name.
+  pub(crate) fn global(&self, name: &str) -> Option<&Declaration> {
+    self
+      .declarations
+      .iter()
+      .find(|declaration| declaration.kind == DeclarationKind::Global && &*declaration.name == name)
+  }
+
+  /// Link unresolved references to globals declared anywhere in the script.
+  pub(crate) fn link_globals(&mut self) {
+    for i in 0..self.references.len() {
+      if self.references[i].declaration.is_some() {
+        continue;
+      }
+      let name = &self.references[i].name;
+      self.references[i].declaration = self.declarations.iter().position(|declaration| {
+        declaration.kind == DeclarationKind::Global && declaration.name == *name
+      });
+    }
+  }
+
+  /// All declarations.
+  pub fn declarations(&self) -> &[Declaration] {
+    &self.declarations
+  }
+
+  /// All references.
+  pub fn references(&self) -> &[Reference] {
+    &self.references
+  }
+
+  /// The local (or parameter) in the slot of the function (qualified name, empty for the script)
+  /// when the given line runs, i.e. the last one declared in the slot up to the line,
+  /// as slots are reused by locals of sibling blocks.
+  pub fn local_at(&self, function: &str, slot: usize, line: usize) -> Option<&Declaration> {
+    self.declarations.iter().rev().find(|declaration| {
+      declaration.slot == Some(slot)
+        && &*declaration.function == function
+        && declaration.location.line <= line
+    })
+  }
+
+  /// The variable captured by the upvalue of the function (qualified name).
+  pub fn captured_by(&self, function: &str, index: usize) -> Option<&Declaration> {
+    let capture = self
+      .captures
+      .iter()
+      .find(|capture| &*capture.function == function && capture.index == index)?;
+    self.declarations.get(capture.declaration)
+  }
+
+  /// Declaration of the variable whose name (in a reference or declaration) covers the location,
+  /// i.e. "go to definition".
+  pub fn definition_at(&self, location: SourceLocation) -> Option<&Declaration> {
+    let covers = |name: &str, site: SourceLocation| {
+      site.line == location.line
+        && (site.column..site.column + name.len()).contains(&location.column)
+    };
+    if let Some(declaration) = self
+      .declarations
+      .iter()
+      .find(|declaration| covers(&declaration.name, declaration.location))
+    {
+      return Some(declaration);
+    }
+    let reference = self
+      .references
+      .iter()
+      .find(|reference| covers(&reference.name, reference.location))?;
+    self.declarations.get(reference.declaration?)
+  }
+}
@@ -0,0 +1,144 @@
+//! # Stats
+//!
+//! Submodule of `compiler`, which reports how much of the limits of a chunk each compiled
+//! function uses (shown by `rlox --stats`), warning about functions approaching them,
+//! so the hard errors (e.g. "Too many constants in one chunk.") don't come as a surprise.
+
+use core::fmt::Display;
+
+use crate::{
+  compiler::{function_table::FunctionTable, optimizer, SHORT_LOCALS_MAX, UPVALUES_MAX},
+  object::ObjFunction,
+  prelude::*,
+};
+
+/// Count of constants of a chunk (addressed by one byte).
+pub const CONSTANTS_MAX: usize = u8::MAX as usize + 1;
+
+/// Count of bytes a jump could go over (by a two bytes offset).
+pub const JUMP_MAX: usize = u16::MAX as usize;
+
+/// Percentage of a limit, beyond which a function is warned about.
+pub const WARN_PERCENT: usize = 75;
+
+/// ## FunctionStats
+///
+/// Usage of a compiled function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionStats {
+  /// Qualified name of the function (`<script>` for the top-level script).
+  pub name: String,
+  /// Count of bytes of bytecode.
+  pub code_size: usize,
+  /// Count of entries of the constant pool.
+  pub constants: usize,
+  /// Peak count of stack slots of a call (locals and temporaries),
+  /// `None` if the bytecode can't be analyzed.
+  pub max_stack: Option<usize>,
+  /// Count of slots of locals (the callee and parameters included).
+  pub local_slots: usize,
+  /// Count of captured variables.
+  pub upvalues: usize,
+}
+
+impl FunctionStats {
+  fn of(name: String, function: &ObjFunction) -> Self {
+    let chunk = &function.chunk;
+    let usage = optimizer::stack_usage(chunk, function.arity + 1);
+    Self {
+      name,
+      code_size: chunk.code.len(),
+      constants: chunk.constants.values.len(),
+      max_stack: usage.map(|(max_stack, _)| max_stack),
+      local_slots: usage.map_or(function.arity + 1, |(_, local_slots)| local_slots),
+      upvalues: function.upvalues.len(),
+    }
+  }
+
+  /// Warnings of limits approached by the function.
+  pub fn warnings(&self) -> Vec<String> {
+    let limits = [
+      (self.constants, CONSTANTS_MAX, "constants of a chunk"),
+      (self.upvalues, UPVALUES_MAX, "captured variables"),
+      (
+        self.local_slots,
+        SHORT_LOCALS_MAX,
+        "short slots of locals (the rest are slower)",
+      ),
+      (
+        self.code_size,
+        JUMP_MAX,
+        "bytes of code a jump could go over",
+      ),
+    ];
+    limits
+      .into_iter()
+      .filter(|&(used, limit, _)| used * 100 >= limit * WARN_PERCENT)
+      .map(|(used, limit, what)| {
+        format!(
+          "Function `{}` uses {} of {} {}.",
+          self.name, used, limit, what
+        )
+      })
+      .collect()
+  }
+}
+
+/// ## CompileStats
+///
+/// Usage of all functions of a compiled script, shown as a table followed by warnings.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompileStats {
+  /// The script first, then functions in order of the function table.
+  pub functions: Vec<FunctionStats>,
+}
+
+impl CompileStats {
+  /// Stats of the script and all functions in it.
+  pub fn of(script: &ObjFunction, functions: &FunctionTable) -> Self {
+    let mut stats = vec![FunctionStats::of("<script>".to_owned(), script)];
+    for entry in &functions.entries {
+      let function = unsafe { entry.function.as_ref() };
+      stats.push(FunctionStats::of(entry.name.clone(), function));
+    }
+    Self { functions: stats }
+  }
+
+  /// Warnings of limits approached by all functions.
+  pub fn warnings(&self) -> Vec<String> {
+    self
+      .functions
+      .iter()
+      .flat_map(FunctionStats::warnings)
+      .collect()
+  }
+}
+
+impl Display for CompileStats {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    writeln!(
+      f,
+      "{:<24} {:>8} {:>9} {:>9} {:>6} {:>8}",
+      "function", "code", "constants", "max stack", "locals", "upvalues"
+    )?;
+    for stats in &self.functions {
+      let max_stack = stats
+        .max_stack
+        .map_or("?".to_owned(), |max_stack| max_stack.to_string());
+      writeln!(
+        f,
+        "{:<24} {:>8} {:>9} {:>9} {:>6} {:>8}",
+        stats.name,
+        stats.code_size,
+        format!("{}/{}", stats.constants, CONSTANTS_MAX),
+        max_stack,
+        stats.local_slots,
+        stats.upvalues
+      )?;
+    }
+    for warning in self.warnings() {
+      writeln!(f, "Warning: {}", warning)?;
+    }
+    Ok(())
+  }
+}
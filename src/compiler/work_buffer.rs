@@ -0,0 +1,60 @@
+//! # WorkBuffer
+//!
+//! Submodule of `compiler`, which keeps scratch buffers of the parser across compilings.
+//!
+//! Each `interpret` compiles with a new [`Parser`], so without this module,
+//! buffers of its tokens, locals and constant table are allocated again every time.
+//! They're lent to the parser before compiling, then reclaimed (cleared, capacity kept)
+//! once it's done, no matter it succeeded or not.
+
+use hashbrown::HashMap;
+
+use crate::{compiler::Local, prelude::*, scanner::Token};
+
+use super::parser::Parser;
+
+/// ## WorkBuffer
+///
+/// Scratch buffers reused by all compilings of one virtual machine.
+#[derive(Debug, Default)]
+pub struct WorkBuffer {
+  /// Tokens (`current` and `previous`), whose lexeme buffers are reused.
+  pub(crate) tokens: [Token; 2],
+  /// Locals of the top-level script.
+  pub(crate) locals: Vec<Local>,
+  /// String constants of the top-level script.
+  pub(crate) string_constants: HashMap<String, u8>,
+}
+
+impl WorkBuffer {
+  /// Lend all buffers to the parser (of the top-level script).
+  pub(crate) fn lend(&mut self, parser: &mut Parser) {
+    let [current, previous] = &mut self.tokens;
+    core::mem::swap(&mut parser.current, current);
+    core::mem::swap(&mut parser.previous, previous);
+    // slot zero of reclaimed locals is the one of a script, so they're lent as is
+    if !self.locals.is_empty() {
+      core::mem::swap(&mut parser.compiler.locals, &mut self.locals);
+    }
+    core::mem::swap(
+      &mut parser.compiler.string_constants,
+      &mut self.string_constants,
+    );
+  }
+
+  /// Take all buffers back from the parser.
+  pub(crate) fn reclaim(&mut self, parser: Parser) {
+    // compiling may stop inside a function, so step back to the top-level script
+    let mut compiler = parser.compiler;
+    while let Some(enclosing) = compiler.enclosing.take() {
+      compiler = *enclosing;
+    }
+    self.locals = compiler.locals;
+    self.string_constants = compiler.string_constants;
+    self.string_constants.clear();
+    self.tokens = [parser.current, parser.previous];
+    for token in &mut self.tokens {
+      token.clone_from(&Token::default());
+    }
+  }
+}
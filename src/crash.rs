@@ -0,0 +1,97 @@
+//! # Crash
+//!
+//! This module contains crash reports of internal errors of the virtual machine.
+//!
+//! A fault other than stack overflow means the bytecode is malformed (e.g. a hand-compiled chunk,
+//! or a bug of the compiler), which no script could cause or recover from. So the state of the
+//! virtual machine is captured where the fault occurs, before it's reset (see [`VM::crash_report`]):
+//!
+//! - the message of the fault
+//! - the faulting function, its ip and disassembly
+//! - the stack trace and a snapshot of the whole stack
+//!
+//! The CLI writes it with the source into a bundle (see [`CrashReport::write_bundle`]),
+//! which could be attached to issues.
+
+use core::fmt::Write;
+
+use crate::{debug::Debug, prelude::*, value::display, vm::VM};
+
+/// ## CrashReport
+///
+/// State of the virtual machine captured at an internal error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrashReport {
+  /// Message of the fault (without its location).
+  pub message: String,
+  /// Name of the faulting function.
+  pub function: String,
+  /// Offset of the next byte to decode in the faulting function.
+  pub ip: usize,
+  /// Where each ongoing call is (the innermost one first).
+  pub stack_trace: Vec<String>,
+  /// All values on the stack (the bottom one first).
+  pub stack: Vec<String>,
+  /// Disassembly of the faulting function.
+  pub disassembly: String,
+}
+
+impl CrashReport {
+  /// Capture the state of the virtual machine at the (internal) fault.
+  ///
+  /// Stack overflow is never captured, as it's an
+  /// [`InterpretError::StackOverflow`](crate::vm::InterpretError::StackOverflow) instead.
+  pub(crate) fn capture(vm: &VM, message: &str) -> Option<Self> {
+    let frame = vm.frames.last()?;
+    let function = unsafe { frame.function.as_ref() }.name().to_owned();
+    Some(Self {
+      message: message.to_owned(),
+      ip: frame.ip,
+      stack_trace: vm.stack_trace(),
+      stack: vm.stack.iter().map(display::repr).collect(),
+      disassembly: frame.chunk().disassembly(&function),
+      function,
+    })
+  }
+
+  /// The report as text, with the source (if known).
+  pub fn render(&self, source: Option<&str>) -> String {
+    let mut out = String::new();
+    out.push_str("# rlox crash report\n\n");
+    let _ = writeln!(out, "version: {}", env!("CARGO_PKG_VERSION"));
+    let _ = writeln!(out, "fault: {}", self.message);
+    let _ = writeln!(out, "function: {}", self.function);
+    let _ = writeln!(out, "ip: {}", self.ip);
+    out.push_str("\n## Stack trace\n\n");
+    for line in &self.stack_trace {
+      let _ = writeln!(out, "{}", line);
+    }
+    out.push_str("\n## Stack\n\n");
+    for (slot, value) in self.stack.iter().enumerate() {
+      let _ = writeln!(out, "[{}] {}", slot, value);
+    }
+    out.push_str("\n## Disassembly\n");
+    out.push_str(&self.disassembly);
+    out.push_str("\n## Source\n\n");
+    out.push_str(source.unwrap_or("<unknown>"));
+    if !out.ends_with('\n') {
+      out.push('\n');
+    }
+    out
+  }
+
+  /// Write the report (with the source, if known) into a new file in the temp directory,
+  /// then return its path.
+  #[cfg(feature = "std")]
+  pub fn write_bundle(&self, source: Option<&str>) -> std::io::Result<std::path::PathBuf> {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    let nanos = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map_or(0, |now| now.as_nanos());
+    let path =
+      std::env::temp_dir().join(format!("rlox-crash-{}-{}.txt", std::process::id(), nanos));
+    std::fs::write(&path, self.render(source))?;
+    Ok(path)
+  }
+}
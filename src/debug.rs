@@ -2,124 +2,313 @@
 //!
 //! A module which represents the debugging utilities for the virtual machine.
 
-use crate::chunk::{Chunk, OpCode};
+use crate::{
+  chunk::{Chunk, OpCode},
+  compiler::function_table::FunctionTable,
+  object::ObjFunction,
+  prelude::*,
+};
+use core::fmt::Write;
 
 pub trait Debug {
   /// Disassemble the given chunk.
+  #[cfg(feature = "std")]
   fn disassemble(&self, name: &str);
 
+  /// Disassembly of the given chunk (what's printed by `disassemble`).
+  fn disassembly(&self, name: &str) -> String;
+
   /// Disassemble the given instruction.
+  #[cfg(feature = "std")]
   fn disassemble_instruction(&self, offset: usize) -> usize;
 
+  /// Write the disassembly of the given instruction into `out`,
+  /// then return the offset of the next one.
+  fn write_instruction(&self, out: &mut String, offset: usize) -> usize;
+
   /// Print a simple instruction.
-  fn simple_instruction(&self, name: &str, offset: usize) -> usize;
+  fn simple_instruction(&self, out: &mut String, name: &str, offset: usize) -> usize;
 
   /// Print a constant instruction.
-  fn constant_instruction(&self, name: &str, offset: usize) -> usize;
+  fn constant_instruction(&self, out: &mut String, name: &str, offset: usize) -> usize;
 
   /// Get the line number of the given offset.
   fn line_number(&self, offset: usize) -> usize;
 
   /// Print a byte instruction (mainly used for local_variables).
-  fn byte_instruction(&self, name: &str, offset: usize) -> usize;
+  fn byte_instruction(&self, out: &mut String, name: &str, offset: usize) -> usize;
+
+  /// Print a long byte instruction (local_variables with 2-byte slot).
+  fn long_byte_instruction(&self, out: &mut String, name: &str, offset: usize) -> usize;
+
+  /// Print an upvalue instruction (captured variables).
+  fn upvalue_instruction(&self, out: &mut String, name: &str, offset: usize) -> usize;
+
+  /// Print a call instruction (with count of arguments).
+  fn call_instruction(&self, out: &mut String, name: &str, offset: usize) -> usize;
+
+  /// Print a count instruction (with count of values).
+  fn count_instruction(&self, out: &mut String, name: &str, offset: usize) -> usize;
+
+  /// Print an invoke instruction (with method name and count of arguments).
+  fn invoke_instruction(&self, out: &mut String, name: &str, offset: usize) -> usize;
 
   /// Print a full bunch of jump instruction
-  fn jump_instruction(&self, name: &str, sign: usize, offset: usize) -> usize;
+  fn jump_instruction(&self, out: &mut String, name: &str, sign: isize, offset: usize) -> usize;
 }
 
 impl Debug for Chunk {
+  #[cfg(feature = "std")]
   fn disassemble(&self, name: &str) {
-    println!("\n-*-*-*-> Global Disassembler : {} <-*-*-*-", name);
+    print!("{}", self.disassembly(name));
+  }
+
+  fn disassembly(&self, name: &str) -> String {
+    let mut out = String::new();
+    let _ = match self.debug_info.file() {
+      Some(file) => writeln!(
+        out,
+        "\n-*-*-*-> Global Disassembler : {} ({}) <-*-*-*-",
+        name, file
+      ),
+      None => writeln!(out, "\n-*-*-*-> Global Disassembler : {} <-*-*-*-", name),
+    };
 
     let mut offset = 0;
     while offset < self.code.len() {
-      offset = self.disassemble_instruction(offset);
+      offset = self.write_instruction(&mut out, offset);
     }
 
-    println!("-*-*-*-*-*-*-*- End of: {} -*-*-*-*-*-*-*-\n", name);
+    let _ = writeln!(out, "-*-*-*-*-*-*-*- End of: {} -*-*-*-*-*-*-*-\n", name);
+    out
   }
 
+  #[cfg(feature = "std")]
   fn disassemble_instruction(&self, offset: usize) -> usize {
-    print!("{:04} ", offset);
+    let mut out = String::new();
+    let next = self.write_instruction(&mut out, offset);
+    print!("{}", out);
+    next
+  }
+
+  fn write_instruction(&self, out: &mut String, offset: usize) -> usize {
+    let _ = write!(out, "{:04} ", offset);
 
     if offset > 0 && self.line_number(offset) == self.line_number(offset - 1) {
-      print!("   | ");
+      out.push_str("   | ");
     } else {
-      print!("{:4} ", self.line_number(offset));
+      let _ = write!(out, "{:4} ", self.line_number(offset));
     }
 
-    let instruction = self.code[offset];
-    match instruction.try_into() {
+    let Some(&instruction) = self.code.get(offset) else {
+      out.push_str("End of chunk\n");
+      return offset + 1;
+    };
+    match OpCode::try_from(instruction) {
       Ok(op_code) => match op_code {
-        OpCode::Constant => self.constant_instruction("</Constant/>", offset),
-        OpCode::Nil => self.simple_instruction("</Nil/>", offset),
-        OpCode::True => self.simple_instruction("</True/>", offset),
-        OpCode::False => self.simple_instruction("</False/>", offset),
-        OpCode::Equal => self.simple_instruction("@ Equal", offset),
-        OpCode::Greater => self.simple_instruction("@ Greater", offset),
-        OpCode::Less => self.simple_instruction("@ Less", offset),
-        OpCode::NotEqual => self.simple_instruction("@ NotEqual", offset),
-        OpCode::GreaterEqual => self.simple_instruction("@ GreaterEqual", offset),
-        OpCode::LessEqual => self.simple_instruction("@ LessEqual", offset),
-        OpCode::Add => self.simple_instruction("@ Add", offset),
-        OpCode::Subtract => self.simple_instruction("@ Subtract", offset),
-        OpCode::Multiply => self.simple_instruction("@ Multiply", offset),
-        OpCode::Divide => self.simple_instruction("@ Divide", offset),
-        OpCode::Not => self.simple_instruction("@ Not", offset),
-        OpCode::Negate => self.simple_instruction("@ Negate", offset),
-        OpCode::JumpIfFalse => self.jump_instruction("=>JumpIfFalse", 1, offset),
-        OpCode::Jump => self.jump_instruction("=>Jump", 1, offset),
-        OpCode::Print => self.simple_instruction("..Print", offset),
-        OpCode::Pop => self.simple_instruction("..Pop", offset),
-        OpCode::DefineGlobal => self.constant_instruction(":=DefineGlobal", offset),
-        OpCode::GetGlobal => self.constant_instruction("<-GetGlobal", offset),
-        OpCode::GetLocal => self.byte_instruction("<-GetLocal", offset),
-        OpCode::SetGlobal => self.constant_instruction("->SetGlobal", offset),
-        OpCode::SetLocal => self.byte_instruction("->SetLocal", offset),
-        OpCode::Return => self.simple_instruction("..Return", offset),
+        OpCode::Constant => self.constant_instruction(out, "</Constant/>", offset),
+        OpCode::Nil => self.simple_instruction(out, "</Nil/>", offset),
+        OpCode::True => self.simple_instruction(out, "</True/>", offset),
+        OpCode::False => self.simple_instruction(out, "</False/>", offset),
+        OpCode::Equal => self.simple_instruction(out, "@ Equal", offset),
+        OpCode::Greater => self.simple_instruction(out, "@ Greater", offset),
+        OpCode::Less => self.simple_instruction(out, "@ Less", offset),
+        OpCode::NotEqual => self.simple_instruction(out, "@ NotEqual", offset),
+        OpCode::GreaterEqual => self.simple_instruction(out, "@ GreaterEqual", offset),
+        OpCode::LessEqual => self.simple_instruction(out, "@ LessEqual", offset),
+        OpCode::Is => self.simple_instruction(out, "@ Is", offset),
+        OpCode::Contains => self.simple_instruction(out, "@ Contains", offset),
+        OpCode::Add => self.simple_instruction(out, "@ Add", offset),
+        OpCode::Subtract => self.simple_instruction(out, "@ Subtract", offset),
+        OpCode::Multiply => self.simple_instruction(out, "@ Multiply", offset),
+        OpCode::Divide => self.simple_instruction(out, "@ Divide", offset),
+        OpCode::FloorDivide => self.simple_instruction(out, "@ FloorDivide", offset),
+        OpCode::Not => self.simple_instruction(out, "@ Not", offset),
+        OpCode::Negate => self.simple_instruction(out, "@ Negate", offset),
+        OpCode::JumpIfFalse => self.jump_instruction(out, "=>JumpIfFalse", 1, offset),
+        OpCode::Jump => self.jump_instruction(out, "=>Jump", 1, offset),
+        OpCode::Loop => self.jump_instruction(out, "=>Loop", -1, offset),
+        OpCode::Print => self.simple_instruction(out, "..Print", offset),
+        OpCode::Pop => self.simple_instruction(out, "..Pop", offset),
+        OpCode::PopN => self.count_instruction(out, "..PopN", offset),
+        OpCode::Peek => self.count_instruction(out, "..Peek", offset),
+        OpCode::Sink => self.count_instruction(out, "..Sink", offset),
+        OpCode::Stringify => self.simple_instruction(out, "..Stringify", offset),
+        OpCode::DefineGlobal => self.constant_instruction(out, ":=DefineGlobal", offset),
+        OpCode::GetGlobal => self.constant_instruction(out, "<-GetGlobal", offset),
+        OpCode::GetLocal => self.byte_instruction(out, "<-GetLocal", offset),
+        OpCode::MissingArgument => self.byte_instruction(out, "<-MissingArgument", offset),
+        OpCode::SetGlobal => self.constant_instruction(out, "->SetGlobal", offset),
+        OpCode::SetLocal => self.byte_instruction(out, "->SetLocal", offset),
+        OpCode::GetLocalLong => self.long_byte_instruction(out, "<-GetLocalLong", offset),
+        OpCode::SetLocalLong => self.long_byte_instruction(out, "->SetLocalLong", offset),
+        OpCode::Call => self.call_instruction(out, "=>Call", offset),
+        OpCode::CallGlobal => self.invoke_instruction(out, "=>CallGlobal", offset),
+        OpCode::Closure => self.constant_instruction(out, ":=Closure", offset),
+        OpCode::GetUpvalue => self.upvalue_instruction(out, "<-GetUpvalue", offset),
+        OpCode::SetUpvalue => self.upvalue_instruction(out, "->SetUpvalue", offset),
+        OpCode::CloseUpvalue => self.simple_instruction(out, "..CloseUpvalue", offset),
+        OpCode::Class => self.constant_instruction(out, ":=Class", offset),
+        OpCode::Trait => self.constant_instruction(out, ":=Trait", offset),
+        OpCode::Mixin => self.simple_instruction(out, "..Mixin", offset),
+        OpCode::Inherit => self.simple_instruction(out, "..Inherit", offset),
+        OpCode::GetProperty => self.constant_instruction(out, "<-GetProperty", offset),
+        OpCode::SetProperty => self.constant_instruction(out, "->SetProperty", offset),
+        OpCode::DeleteProperty => self.constant_instruction(out, "..DeleteProperty", offset),
+        OpCode::Method => self.constant_instruction(out, ":=Method", offset),
+        OpCode::Getter => self.constant_instruction(out, ":=Getter", offset),
+        OpCode::Setter => self.constant_instruction(out, ":=Setter", offset),
+        OpCode::Invoke => self.invoke_instruction(out, "=>Invoke", offset),
+        OpCode::GetSuper => self.constant_instruction(out, "<-GetSuper", offset),
+        OpCode::SuperInvoke => self.invoke_instruction(out, "=>SuperInvoke", offset),
+        OpCode::Enum => self.constant_instruction(out, ":=Enum", offset),
+        OpCode::EnumMember => self.constant_instruction(out, ":=EnumMember", offset),
+        OpCode::BuildList => self.count_instruction(out, ":=BuildList", offset),
+        OpCode::GetIndex => self.simple_instruction(out, "<-GetIndex", offset),
+        OpCode::GetSlice => self.simple_instruction(out, "<-GetSlice", offset),
+        OpCode::SetIndex => self.simple_instruction(out, "->SetIndex", offset),
+        OpCode::ExtendList => self.simple_instruction(out, "..ExtendList", offset),
+        OpCode::SpreadCall => self.simple_instruction(out, "=>SpreadCall", offset),
+        OpCode::SpreadInvoke => self.constant_instruction(out, "=>SpreadInvoke", offset),
+        OpCode::SpreadSuperInvoke => self.constant_instruction(out, "=>SpreadSuperInvoke", offset),
+        OpCode::Unpack => self.count_instruction(out, "..Unpack", offset),
+        OpCode::MatchList => self.count_instruction(out, "@ MatchList", offset),
+        OpCode::MatchListRest => self.count_instruction(out, "@ MatchListRest", offset),
+        OpCode::Import => self.constant_instruction(out, "=>Import", offset),
+        OpCode::PushHandler => self.jump_instruction(out, "=>PushHandler", 1, offset),
+        OpCode::PopHandler => self.simple_instruction(out, "..PopHandler", offset),
+        OpCode::Throw => self.simple_instruction(out, "=>Throw", offset),
+        OpCode::Return => self.simple_instruction(out, "..Return", offset),
       },
       _ => {
-        println!("Unknown opcode {}", instruction);
+        let _ = writeln!(out, "Unknown opcode {}", instruction);
         offset + 1
       }
     }
   }
 
-  fn simple_instruction(&self, name: &str, offset: usize) -> usize {
-    println!("{}", name);
+  fn simple_instruction(&self, out: &mut String, name: &str, offset: usize) -> usize {
+    let _ = writeln!(out, "{}", name);
     // move 1 byte ahead
     offset + 1
   }
 
-  fn constant_instruction(&self, name: &str, offset: usize) -> usize {
-    let index = self.code[offset + 1];
-    println!(
+  fn constant_instruction(&self, out: &mut String, name: &str, offset: usize) -> usize {
+    let index = self.operand(offset + 1);
+    let _ = writeln!(
+      out,
       "{:16} {:4} :: {}",
-      name, index, self.constants.values[index as usize]
+      name,
+      index,
+      self.constant_at(index)
     );
     // move 2 byte ahead
     offset + 2
   }
 
-  fn byte_instruction(&self, name: &str, offset: usize) -> usize {
-    let slot = self.code[offset + 1];
-    println!("{:16} {:4}(slot)", name, slot);
+  fn byte_instruction(&self, out: &mut String, name: &str, offset: usize) -> usize {
+    let slot = self.operand(offset + 1);
+    let _ = writeln!(out, "{:16} {:4}(slot)", name, slot);
+    // move 2 byte ahead
+    offset + 2
+  }
+
+  fn long_byte_instruction(&self, out: &mut String, name: &str, offset: usize) -> usize {
+    let slot = ((self.operand(offset + 1) as u16) << 8) | self.operand(offset + 2) as u16;
+    let _ = writeln!(out, "{:16} {:4}(slot)", name, slot);
+    // move 3 byte ahead
+    offset + 3
+  }
+
+  fn upvalue_instruction(&self, out: &mut String, name: &str, offset: usize) -> usize {
+    let index = self.operand(offset + 1);
+    let _ = writeln!(out, "{:16} {:4}(upvalue)", name, index);
+    // move 2 byte ahead
+    offset + 2
+  }
+
+  fn call_instruction(&self, out: &mut String, name: &str, offset: usize) -> usize {
+    let arg_count = self.operand(offset + 1);
+    let _ = writeln!(out, "{:16} {:4}(args)", name, arg_count);
     // move 2 byte ahead
     offset + 2
   }
 
-  fn jump_instruction(&self, name: &str, sign: usize, offset: usize) -> usize {
-    let jump = ((self.code[offset + 1] as u16) << 8) | self.code[offset + 2] as u16;
-    println!(
+  fn count_instruction(&self, out: &mut String, name: &str, offset: usize) -> usize {
+    let count = self.operand(offset + 1);
+    let _ = writeln!(out, "{:16} {:4}(values)", name, count);
+    // move 2 byte ahead
+    offset + 2
+  }
+
+  fn invoke_instruction(&self, out: &mut String, name: &str, offset: usize) -> usize {
+    let index = self.operand(offset + 1);
+    let arg_count = self.operand(offset + 2);
+    let _ = writeln!(
+      out,
+      "{:16} {:4} :: {} ({} args)",
+      name,
+      index,
+      self.constant_at(index),
+      arg_count
+    );
+    // move 3 byte ahead
+    offset + 3
+  }
+
+  fn jump_instruction(&self, out: &mut String, name: &str, sign: isize, offset: usize) -> usize {
+    let jump = ((self.operand(offset + 1) as u16) << 8) | self.operand(offset + 2) as u16;
+    let _ = writeln!(
+      out,
       "{:16} {:4} -> {}",
       name,
       offset,
-      offset + 3 + sign * jump as usize
+      (offset + 3) as isize + sign * jump as isize
     );
     offset + 3
   }
 
   fn line_number(&self, offset: usize) -> usize {
-    self.lines[offset]
+    self.debug_info.location(offset).line
+  }
+}
+
+impl Chunk {
+  /// Operand at the given offset, `0` if the chunk is truncated.
+  fn operand(&self, offset: usize) -> u8 {
+    self.code.get(offset).copied().unwrap_or_default()
+  }
+
+  /// Display of the constant at the given index, `?` if it's undefined.
+  fn constant_at(&self, index: u8) -> String {
+    match self.constants.values.get(index as usize) {
+      Some(value) => value.to_string(),
+      None => "?".into(),
+    }
+  }
+}
+
+impl ObjFunction {
+  /// Disassembly of the function, followed by functions defined in it (recursively).
+  pub fn disassembly(&self) -> String {
+    let name = match &self.name {
+      Some(name) => name.data.to_owned(),
+      None => "<script>".to_owned(),
+    };
+    let mut out = self.chunk.disassembly(&name);
+    for value in &self.chunk.constants.values {
+      if let Ok(function) = value.as_function() {
+        out.push_str(&unsafe { function.as_ref() }.disassembly());
+      }
+    }
+    out
+  }
+}
+
+impl FunctionTable {
+  /// Disassembly of the function of the given qualified name
+  /// (without functions defined in it), `None` if there's no such function.
+  pub fn disassembly(&self, name: &str) -> Option<String> {
+    Some(self.get(name)?.chunk.disassembly(name))
   }
 }
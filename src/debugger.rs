@@ -0,0 +1,614 @@
+//! # Debugger
+//!
+//! This module contains inspection of paused runs (see [`VM::resume`]):
+//!
+//! - locals of each ongoing call, named by declarations of the script compiled last
+//! - watch expressions, which are compiled once, then evaluated at each stop
+//! - expressions evaluated in any ongoing call, with its locals mapped to their slots
+//! - conditions (compiled once, like watches) and hit counts of breakpoints
+//!   (see [`VM::set_breakpoint_with`])
+//! - runs failed by runtime errors, kept as they were at the failing instruction
+//!   (see [`VM::set_post_mortem`])
+//! - live objects of the heap, as traced from roots (see [`VM::reachable_objects`]),
+//!   and diffs of snapshots of them to find leaks (see [`HeapSnapshot::diff`])
+//!
+//! Both are evaluated in isolation: the paused run is set aside (then restored as is).
+//! Locals of the innermost frame shadow globals of the same names for watches, while
+//! expressions evaluated in a frame run on a copy of its slots.
+//! Assignments to variables made by either are discarded, but objects are shared with the run,
+//! so changes of them (e.g. of fields, or items of lists) are kept.
+
+use core::{fmt::Display, ptr::NonNull};
+use hashbrown::{HashMap, HashSet};
+
+use crate::{
+  debug::Debug,
+  gc::{object_size, site_of, AllocSite},
+  native::inspect,
+  object::{ObjFunction, ObjString, ObjTrait, ObjType, ObjUpvalue},
+  prelude::*,
+  table::Table,
+  utils::Init,
+  value::{display, Value},
+  vm::{CallFrame, Handler, InterpretError, Step, VM},
+};
+
+/// ## Watch
+///
+/// An expression evaluated each time a run pauses (see [`VM::add_watch`]).
+#[derive(Debug, Clone)]
+pub struct Watch {
+  /// Source of the expression.
+  pub(crate) expression: String,
+  /// The expression compiled into a script which results in its value.
+  pub(crate) function: NonNull<ObjFunction>,
+}
+
+impl Watch {
+  pub fn expression(&self) -> &str {
+    &self.expression
+  }
+}
+
+/// ## Breakpoint
+///
+/// When a breakpoint pauses runs (see [`VM::set_breakpoint_with`]),
+/// the default one pauses each time its line is reached.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Breakpoint {
+  /// Expression evaluated each time the line is reached (locals of the innermost call
+  /// shadow globals), the hit is counted only if it's truthy (or fails to be evaluated).
+  pub condition: Option<String>,
+  /// Pause only once the breakpoint is hit this many times (and each time after),
+  /// e.g. to skip early iterations of loops.
+  pub hit_count: Option<usize>,
+}
+
+impl Init for Breakpoint {}
+
+/// Breakpoints by line, with their conditions compiled, and how many times they're hit.
+pub(crate) type Breakpoints = HashMap<usize, (Breakpoint, Option<NonNull<ObjFunction>>, usize)>;
+
+/// ## Heap Object
+///
+/// A live object of the heap (see [`VM::heap_objects`]).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeapObject {
+  /// Address of the object, which identifies it while it's alive.
+  pub id: usize,
+  pub obj_type: ObjType,
+  /// Approximate bytes owned by the object (see [`object_size`]).
+  pub size: usize,
+  /// The object shown as a value (e.g. a string is quoted).
+  pub repr: String,
+  /// Where the object is allocated (see [`site_of`]).
+  pub site: Option<AllocSite>,
+}
+
+/// ## Heap Group
+///
+/// Live objects of the same type (see [`VM::heap_summary`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeapGroup {
+  pub obj_type: ObjType,
+  pub count: usize,
+  /// Total bytes owned by objects of the group.
+  pub size: usize,
+}
+
+/// ## Site Group
+///
+/// Live objects allocated at the same site (see [`HeapSnapshot::sites`]).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SiteGroup {
+  /// `None` for objects allocated outside of runs (or all objects, without `alloc_sites`).
+  pub site: Option<AllocSite>,
+  pub count: usize,
+  /// Total bytes owned by objects of the group.
+  pub size: usize,
+}
+
+/// All types of objects, in order of declaration.
+const OBJ_TYPES: [ObjType; 11] = [
+  ObjType::String,
+  ObjType::Function,
+  ObjType::Native,
+  ObjType::Class,
+  ObjType::Instance,
+  ObjType::BoundMethod,
+  ObjType::Closure,
+  ObjType::Upvalue,
+  ObjType::List,
+  ObjType::Enum,
+  ObjType::EnumMember,
+];
+
+/// ## Heap Snapshot
+///
+/// Live objects of the heap at a moment (see [`VM::heap_snapshot`]).
+///
+/// Objects are never freed, so an id never refers to another object in a later snapshot.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HeapSnapshot {
+  /// Objects in order of being reached from roots.
+  pub objects: Vec<HeapObject>,
+}
+
+impl HeapSnapshot {
+  /// Objects grouped by type, in order of declaration of types (types without objects excluded).
+  pub fn summary(&self) -> Vec<HeapGroup> {
+    OBJ_TYPES
+      .into_iter()
+      .filter_map(|obj_type| {
+        let group = self.objects.iter().filter(|obj| obj.obj_type == obj_type);
+        let count = group.clone().count();
+        (count > 0).then(|| HeapGroup {
+          obj_type,
+          count,
+          size: group.map(|obj| obj.size).sum(),
+        })
+      })
+      .collect()
+  }
+
+  /// Objects grouped by their allocation sites, the largest group (in bytes) first.
+  pub fn sites(&self) -> Vec<SiteGroup> {
+    let mut groups: Vec<SiteGroup> = vec![];
+    for obj in &self.objects {
+      match groups.iter_mut().find(|group| group.site == obj.site) {
+        Some(group) => {
+          group.count += 1;
+          group.size += obj.size;
+        }
+        None => groups.push(SiteGroup {
+          site: obj.site.clone(),
+          count: 1,
+          size: obj.size,
+        }),
+      }
+    }
+    groups.sort_by_key(|group| core::cmp::Reverse(group.size));
+    groups
+  }
+
+  /// What changed from this snapshot to the later one, e.g. taken before and after
+  /// calls of a native function to check it doesn't keep objects alive.
+  pub fn diff(&self, later: &HeapSnapshot) -> HeapDiff {
+    let earlier_ids: HashSet<_> = self.objects.iter().map(|obj| obj.id).collect();
+    let later_ids: HashSet<_> = later.objects.iter().map(|obj| obj.id).collect();
+    let (earlier, later_summary) = (self.summary(), later.summary());
+    let group_of = |summary: &[HeapGroup], obj_type| {
+      summary
+        .iter()
+        .find(|group| group.obj_type == obj_type)
+        .map_or((0, 0), |group| (group.count, group.size))
+    };
+    let changes = OBJ_TYPES
+      .into_iter()
+      .filter_map(|obj_type| {
+        let (count_before, size_before) = group_of(&earlier, obj_type);
+        let (count_after, size_after) = group_of(&later_summary, obj_type);
+        let change = HeapChange {
+          obj_type,
+          count: count_after as isize - count_before as isize,
+          size: size_after as isize - size_before as isize,
+        };
+        (change.count != 0 || change.size != 0).then_some(change)
+      })
+      .collect();
+    HeapDiff {
+      changes,
+      new_objects: (later.objects.iter())
+        .filter(|obj| !earlier_ids.contains(&obj.id))
+        .cloned()
+        .collect(),
+      dropped_objects: (self.objects.iter())
+        .filter(|obj| !later_ids.contains(&obj.id))
+        .cloned()
+        .collect(),
+    }
+  }
+}
+
+/// ## Heap Change
+///
+/// How many objects of the type (and bytes owned by them) are gained (or lost) between snapshots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeapChange {
+  pub obj_type: ObjType,
+  pub count: isize,
+  pub size: isize,
+}
+
+/// ## Heap Diff
+///
+/// Differences between two snapshots (see [`HeapSnapshot::diff`]).
+///
+/// Shown as a report: a line of each change, then lines of new objects.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HeapDiff {
+  /// Changes of each type (unchanged types excluded), in order of declaration of types.
+  pub changes: Vec<HeapChange>,
+  /// Objects live only in the later snapshot, which are leaked if they aren't expected.
+  pub new_objects: Vec<HeapObject>,
+  /// Objects live only in the earlier snapshot.
+  pub dropped_objects: Vec<HeapObject>,
+}
+
+impl HeapDiff {
+  /// If no object is gained or lost.
+  pub fn is_empty(&self) -> bool {
+    self.new_objects.is_empty() && self.dropped_objects.is_empty()
+  }
+}
+
+impl Display for HeapDiff {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    for change in &self.changes {
+      writeln!(
+        f,
+        "{:<12} {:>+6} objects {:>+10} bytes",
+        format!("{:?}", change.obj_type),
+        change.count,
+        change.size
+      )?;
+    }
+    for obj in &self.new_objects {
+      write!(f, "+ {:#x} {:>8} bytes  {}", obj.id, obj.size, obj.repr)?;
+      match &obj.site {
+        Some(site) => writeln!(f, "  ({})", site)?,
+        None => writeln!(f)?,
+      }
+    }
+    Ok(())
+  }
+}
+
+/// State of a run set aside while something else runs (see [`VM::isolated`]).
+struct Suspended {
+  frames: Vec<CallFrame>,
+  stack: Vec<Value>,
+  handlers: Vec<Handler>,
+  breakpoints: Breakpoints,
+  open_upvalues: Vec<NonNull<ObjUpvalue>>,
+  paused_at: Option<usize>,
+  stepping: Option<Step>,
+  failure: Option<String>,
+  instruction_count: usize,
+  peak_stack_size: usize,
+  #[cfg(feature = "std")]
+  deadline: Option<std::time::Instant>,
+}
+
+impl VM {
+  /// Locals of the ongoing call at the given depth (`0` for the innermost one),
+  /// as `(name, value)` in order of their slots.
+  ///
+  /// Only locals declared by the script compiled last are named, others are left out.
+  pub fn frame_locals(&self, depth: usize) -> Vec<(String, Value)> {
+    let Some(index) = self.frames.len().checked_sub(depth + 1) else {
+      return vec![];
+    };
+    let frame = &self.frames[index];
+    (frame.slot_base + 1..self.frame_end(index))
+      .filter_map(|index| {
+        let local = self.local_declaration(frame, index - frame.slot_base)?;
+        Some((local.name.to_string(), self.stack[index]))
+      })
+      .collect()
+  }
+
+  /// Variables captured by the ongoing call at the given depth (`0` for the innermost one),
+  /// as `(name, value)` in order of their upvalues.
+  ///
+  /// Only variables declared by the script compiled last are named, others are left out.
+  pub fn frame_captures(&self, depth: usize) -> Vec<(String, Value)> {
+    let Some(index) = self.frames.len().checked_sub(depth + 1) else {
+      return vec![];
+    };
+    let frame = &self.frames[index];
+    let Some(closure) = frame.closure else {
+      return vec![];
+    };
+    let upvalues = &unsafe { closure.as_ref() }.upvalues;
+    upvalues
+      .iter()
+      .enumerate()
+      .filter_map(|(index, upvalue)| {
+        let declaration = self.capture_declaration(frame, index)?;
+        let upvalue = unsafe { upvalue.as_ref() };
+        let value = match upvalue.closed {
+          Some(value) => value,
+          None => *self.stack.get(upvalue.slot)?,
+        };
+        Some((declaration.name.to_string(), value))
+      })
+      .collect()
+  }
+
+  /// End of slots of the ongoing call of the given index, which is where the next call starts.
+  fn frame_end(&self, index: usize) -> usize {
+    self
+      .frames
+      .get(index + 1)
+      .map_or(self.stack.len(), |next| next.slot_base)
+  }
+
+  /// Evaluate the expression in the ongoing call at the given depth (`0` for the innermost one),
+  /// where its locals (and `this` of methods) refer to their current values.
+  ///
+  /// The expression is compiled with the locals mapped to their slots, then run in isolation
+  /// on a copy of the slots, so its assignments (to locals or globals) are discarded.
+  /// Variables captured from enclosing functions are snapshots (see [`VM::frame_captures`]),
+  /// which shadow globals of the same names, so assignments to them are discarded as well.
+  /// Objects are shared with the run, so changes of them (e.g. of fields) are kept.
+  pub fn eval_in_frame(&mut self, depth: usize, expression: &str) -> Result<Value, InterpretError> {
+    let Some(index) = self.frames.len().checked_sub(depth + 1) else {
+      return Err(InterpretError::RuntimeError(format!(
+        "No ongoing call at depth {}.",
+        depth
+      )));
+    };
+    let frame = self.frames[index];
+    // slot zero holds the receiver of methods, or the function being called otherwise
+    let receiver = self.stack[frame.slot_base].is_instance();
+    let mut slots = vec![if receiver { "this" } else { "" }.to_owned()];
+    for slot in 1..self.frame_end(index) - frame.slot_base {
+      let local = self.local_declaration(&frame, slot);
+      slots.push(local.map_or_else(String::new, |local| local.name.to_string()));
+    }
+    // temporaries above the last local are left out
+    while slots.len() > 1 && slots.last().is_some_and(String::is_empty) {
+      slots.pop();
+    }
+    let expression = expression.trim().trim_end_matches(';').trim_end();
+    let (function, has_result) = self.compile_detached(format!("{};", expression), &slots)?;
+    if !has_result {
+      return Err(InterpretError::CompileError(format!(
+        "`{}` isn't an expression.",
+        expression
+      )));
+    }
+    let function: NonNull<ObjFunction> = function.cast_to_obj_ptr().cast();
+    let mut values = self.stack[frame.slot_base..frame.slot_base + slots.len()].to_vec();
+    if !receiver {
+      values[0] = Value::obj_val(function.cast());
+    }
+    let mut environment = self.globals.clone();
+    for (name, value) in self.frame_captures(depth) {
+      environment.set(ObjString::from(name), value);
+    }
+    self.evaluate(function, environment, values)
+  }
+
+  /// Count the hit of the breakpoint of the line (if any), returns if it pauses the run.
+  ///
+  /// Conditions failed to be evaluated count as holding, so they're never silently skipped.
+  pub(crate) fn breakpoint_hit(&mut self, line: usize) -> bool {
+    let Some(&(_, condition, _)) = self.breakpoints.get(&line) else {
+      return false;
+    };
+    if let Some(condition) = condition {
+      let locals = self.frame_locals(0);
+      let value = self.evaluate_with(condition, &locals);
+      if value.is_ok_and(|value| value.is_falsey()) {
+        return false;
+      }
+    }
+    let Some((breakpoint, _, hits)) = self.breakpoints.get_mut(&line) else {
+      return false;
+    };
+    *hits += 1;
+    breakpoint.hit_count.is_none_or(|count| *hits >= count)
+  }
+
+  /// Runtime error the kept run failed by (see [`VM::set_post_mortem`]),
+  /// `None` if there's no such run.
+  pub fn failure(&self) -> Option<&str> {
+    self.failure.as_deref()
+  }
+
+  /// Disassembly of the instruction the kept run failed at (see [`VM::failure`]),
+  /// in the same format as disassembly of whole chunks.
+  pub fn failing_instruction(&self) -> Option<String> {
+    self.failure.as_ref()?;
+    let frame = self.frames.last()?;
+    let chunk = frame.chunk();
+    // `ip` has moved past the failing instruction (and its operands)
+    let failed_at = frame.ip.checked_sub(1)?;
+    let mut offset = 0;
+    while offset < chunk.code.len() {
+      let mut out = String::new();
+      let next = chunk.write_instruction(&mut out, offset);
+      if failed_at < next {
+        return Some(out);
+      }
+      offset = next;
+    }
+    None
+  }
+
+  /// Live objects of the heap, in order of being reached from roots.
+  pub fn heap_objects(&self) -> Vec<HeapObject> {
+    self
+      .reachable_objects()
+      .into_iter()
+      .map(|obj| HeapObject {
+        id: obj.as_ptr() as usize,
+        obj_type: unsafe { obj.as_ref() }.obj_type,
+        size: object_size(obj),
+        repr: display::repr(&Value::obj_val(obj)),
+        site: site_of(obj),
+      })
+      .collect()
+  }
+
+  /// Live objects of the heap grouped by type (see [`HeapSnapshot::summary`]).
+  pub fn heap_summary(&self) -> Vec<HeapGroup> {
+    self.heap_snapshot().summary()
+  }
+
+  /// Snapshot of live objects of the heap, to be compared with a later one
+  /// (see [`HeapSnapshot::diff`]).
+  pub fn heap_snapshot(&self) -> HeapSnapshot {
+    HeapSnapshot {
+      objects: self.heap_objects(),
+    }
+  }
+
+  /// Inspection of the live object of the id (see [`inspect::inspect`]),
+  /// `None` if there's no such object.
+  ///
+  /// Only objects reached from roots are looked up, so a stale id never reads freed memory.
+  pub fn inspect_object(&self, id: usize) -> Option<String> {
+    let obj = self
+      .reachable_objects()
+      .into_iter()
+      .find(|obj| obj.as_ptr() as usize == id)?;
+    let mut inspection = inspect::inspect(&format!("{:#x}", id), &Value::obj_val(obj));
+    if let Some(site) = site_of(obj) {
+      inspection.push_str(&format!("  allocated at: {}\n", site));
+    }
+    Some(inspection)
+  }
+
+  /// Watch the expression: compile it once, then evaluate it at each stop
+  /// (see [`VM::watch_values`]).
+  ///
+  /// Watching the same expression twice does nothing.
+  pub fn add_watch(&mut self, expression: &str) -> Result<(), InterpretError> {
+    let expression = expression.trim().trim_end_matches(';').trim_end();
+    if self
+      .watches
+      .iter()
+      .any(|watch| watch.expression == expression)
+    {
+      return Ok(());
+    }
+    let function = self.compile_expression(expression, "Watch")?;
+    self.watches.push(Watch {
+      expression: expression.to_owned(),
+      function,
+    });
+    Ok(())
+  }
+
+  /// Compile the expression (of a watch or condition, named by `what` in errors)
+  /// into a script which results in its value (see [`VM::evaluate_with`]).
+  pub(crate) fn compile_expression(
+    &self,
+    expression: &str,
+    what: &str,
+  ) -> Result<NonNull<ObjFunction>, InterpretError> {
+    let expression = expression.trim().trim_end_matches(';').trim_end();
+    let (function, has_result) = self.compile_detached(format!("{};", expression), &[])?;
+    if !has_result {
+      return Err(InterpretError::CompileError(format!(
+        "{} `{}` isn't an expression.",
+        what, expression
+      )));
+    }
+    Ok(function.cast_to_obj_ptr().cast())
+  }
+
+  /// Stop watching the expression, returns if it's watched.
+  pub fn remove_watch(&mut self, expression: &str) -> bool {
+    let expression = expression.trim().trim_end_matches(';').trim_end();
+    let len = self.watches.len();
+    self.watches.retain(|watch| watch.expression != expression);
+    self.watches.len() != len
+  }
+
+  /// All watches, in order of being added.
+  pub fn watches(&self) -> &[Watch] {
+    &self.watches
+  }
+
+  /// Evaluate each watch against the paused run, with locals of its innermost frame.
+  ///
+  /// Errors of a watch (e.g. an undefined variable) are its value, rather than stopping others.
+  pub fn watch_values(&mut self) -> Vec<(String, Result<Value, InterpretError>)> {
+    let locals = self.frame_locals(0);
+    let watches = self.watches.clone();
+    watches
+      .into_iter()
+      .map(|watch| {
+        let value = self.evaluate_with(watch.function, &locals);
+        (watch.expression, value)
+      })
+      .collect()
+  }
+
+  /// Run the compiled script in isolation, with the locals shadowing globals.
+  fn evaluate_with(
+    &mut self,
+    function: NonNull<ObjFunction>,
+    locals: &[(String, Value)],
+  ) -> Result<Value, InterpretError> {
+    let mut environment = self.globals.clone();
+    for (name, value) in locals {
+      environment.set(ObjString::from(name.to_owned()), *value);
+    }
+    self.evaluate(function, environment, vec![Value::obj_val(function.cast())])
+  }
+
+  /// Run the compiled script in isolation on the slots (from slot zero) with the globals,
+  /// then restore globals as they were.
+  fn evaluate(
+    &mut self,
+    function: NonNull<ObjFunction>,
+    globals: Table,
+    slots: Vec<Value>,
+  ) -> Result<Value, InterpretError> {
+    let globals = core::mem::replace(&mut self.globals, globals);
+    let result = self.isolated(|vm| {
+      vm.stack = slots;
+      vm.frames.push(CallFrame {
+        function,
+        closure: None,
+        ip: 0,
+        slot_base: 0,
+        arg_count: 0,
+      });
+      vm.run()
+    });
+    self.globals = globals;
+    result
+  }
+
+  /// Set the ongoing run aside, run `f` from a clean state, then restore the run as it was.
+  pub(crate) fn isolated<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> T {
+    let suspended = Suspended {
+      frames: core::mem::take(&mut self.frames),
+      stack: core::mem::take(&mut self.stack),
+      handlers: core::mem::take(&mut self.handlers),
+      // so evaluating (e.g. conditions of breakpoints) never pauses
+      breakpoints: core::mem::take(&mut self.breakpoints),
+      open_upvalues: core::mem::take(&mut self.open_upvalues),
+      paused_at: self.paused_at.take(),
+      stepping: self.stepping.take(),
+      failure: self.failure.take(),
+      instruction_count: self.instruction_count,
+      peak_stack_size: self.peak_stack_size,
+      #[cfg(feature = "std")]
+      deadline: self.deadline.take(),
+    };
+    let result = f(self);
+    self.reset_execution_state();
+    self.frames = suspended.frames;
+    self.stack = suspended.stack;
+    self.handlers = suspended.handlers;
+    self.breakpoints = suspended.breakpoints;
+    self.open_upvalues = suspended.open_upvalues;
+    self.paused_at = suspended.paused_at;
+    self.stepping = suspended.stepping;
+    self.failure = suspended.failure;
+    self.instruction_count = suspended.instruction_count;
+    self.peak_stack_size = suspended.peak_stack_size;
+    #[cfg(feature = "std")]
+    {
+      self.deadline = suspended.deadline;
+    }
+    result
+  }
+}
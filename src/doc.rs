@@ -0,0 +1,213 @@
+//! # Doc
+//!
+//! This module contains the doc comment extraction tool (`rlox doc`).
+//!
+//! Doc comments are `///` line comments right before a top-level
+//! `fun`/`class`/`trait`/`var` declaration:
+//!
+//! ```text
+//! /// Add two numbers.
+//! fun add(a, b = 0) { return a + b; }
+//! ```
+//!
+//! A blank line (or an ordinary comment) between them detaches the doc comment.
+//!
+//! Doc comments are read from the trivia of the scanner (see [`Trivia`]),
+//! so the source doesn't need to compile.
+
+use crate::{
+  prelude::*,
+  scanner::{Scanner, Token, TokenType, Trivia, TriviaKind},
+  vm::InterpretError,
+};
+
+/// ## DocKind
+///
+/// An enum which represents the kinds of documented globals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocKind {
+  Function,
+  Class,
+  Trait,
+  Variable,
+}
+
+/// ## DocItem
+///
+/// A documented global.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocItem {
+  pub kind: DocKind,
+  pub name: String,
+  /// Signature of the declaration, e.g. `fun add(a, b = 0)`.
+  pub signature: String,
+  /// Lines of the doc comment (without `///`).
+  pub doc: Vec<String>,
+  /// The line of the declaration.
+  pub line: usize,
+}
+
+/// Extract documented globals of the source (in order of declaration).
+pub fn extract(src: &str) -> Result<Vec<DocItem>, InterpretError> {
+  let tokens = scan(src)?;
+  let mut items = vec![];
+  let mut depth = 0_usize;
+  for (index, token) in tokens.iter().enumerate() {
+    match token.token_type() {
+      TokenType::LeftBrace => depth += 1,
+      TokenType::RightBrace => depth = depth.saturating_sub(1),
+      TokenType::Fun | TokenType::Class | TokenType::Trait | TokenType::Var if depth == 0 => {
+        let doc = doc_lines(token.leading_trivia());
+        if doc.is_empty() {
+          continue;
+        }
+        if let Some(item) = declaration(&tokens[index..], doc) {
+          items.push(item);
+        }
+      }
+      _ => {}
+    }
+  }
+  Ok(items)
+}
+
+/// Markdown summary of the documented globals.
+pub fn render_markdown(title: &str, items: &[DocItem]) -> String {
+  let mut out = format!("# {}\n", title);
+  if items.is_empty() {
+    out.push_str("\nNo documented globals.\n");
+  }
+  for item in items {
+    out.push_str(&format!("\n## `{}`\n\n", item.signature));
+    for line in &item.doc {
+      out.push_str(line);
+      out.push('\n');
+    }
+  }
+  out
+}
+
+/// All tokens of the source (with trivia), `Eof` excluded.
+fn scan(src: &str) -> Result<Vec<Token>, InterpretError> {
+  let mut scanner = Scanner::bind_with_trivia(src.to_owned());
+  let mut tokens = vec![];
+  loop {
+    let token = scanner.scan_token();
+    match token.token_type() {
+      TokenType::Eof => return Ok(tokens),
+      TokenType::Error => {
+        return Err(InterpretError::CompileError(format!(
+          "[line {}] Error: {}",
+          token.line(),
+          token.lexeme()
+        )))
+      }
+      _ => tokens.push(token),
+    }
+  }
+}
+
+/// Lines of the doc comment attached to a token (by its leading trivia).
+fn doc_lines(trivia: &[Trivia]) -> Vec<String> {
+  let mut doc = vec![];
+  let mut newlines = 0;
+  for piece in trivia {
+    match piece.kind {
+      TriviaKind::Whitespace => {}
+      TriviaKind::Newline => newlines += 1,
+      TriviaKind::LineComment if is_doc_comment(&piece.text) => {
+        if newlines > 1 {
+          doc.clear();
+        }
+        let line = &piece.text[3..];
+        doc.push(line.strip_prefix(' ').unwrap_or(line).trim_end().to_owned());
+        newlines = 0;
+      }
+      TriviaKind::LineComment | TriviaKind::BlockComment => {
+        doc.clear();
+        newlines = 0;
+      }
+    }
+  }
+  if newlines > 1 {
+    doc.clear();
+  }
+  doc
+}
+
+/// `/// ...`, but not `//// ...`.
+fn is_doc_comment(text: &str) -> bool {
+  text.starts_with("///") && !text.starts_with("////")
+}
+
+/// Describe the declaration starting at the first token.
+fn declaration(tokens: &[Token], doc: Vec<String>) -> Option<DocItem> {
+  let keyword = tokens.first()?;
+  let name = tokens
+    .get(1)
+    .filter(|t| t.token_type() == TokenType::Identifier)?;
+  let (kind, signature) = match keyword.token_type() {
+    TokenType::Fun => (
+      DocKind::Function,
+      format!("fun {}{}", name.lexeme(), parameters(&tokens[2..])?),
+    ),
+    TokenType::Class => (DocKind::Class, class_header(tokens)),
+    TokenType::Trait => (DocKind::Trait, format!("trait {}", name.lexeme())),
+    _ => (DocKind::Variable, format!("var {}", name.lexeme())),
+  };
+  Some(DocItem {
+    kind,
+    name: name.lexeme().to_owned(),
+    signature,
+    doc,
+    line: keyword.line(),
+  })
+}
+
+/// Parameter list (starting at `(`), normalized as `(a, b = 0)`.
+fn parameters(tokens: &[Token]) -> Option<String> {
+  if tokens.first()?.token_type() != TokenType::LeftParen {
+    return None;
+  }
+  let mut out = String::from("(");
+  let mut depth = 0_usize;
+  for token in tokens {
+    match token.token_type() {
+      TokenType::LeftParen => {
+        depth += 1;
+        if depth > 1 {
+          out.push('(');
+        }
+      }
+      TokenType::RightParen => {
+        depth -= 1;
+        out.push(')');
+        if depth == 0 {
+          return Some(out);
+        }
+      }
+      TokenType::Comma => out.push_str(", "),
+      TokenType::Equal => out.push_str(" = "),
+      _ => out.push_str(token.lexeme()),
+    }
+  }
+  None
+}
+
+/// Class header up to its body, e.g. `class A with B, C`.
+fn class_header(tokens: &[Token]) -> String {
+  let mut out = String::new();
+  for token in tokens {
+    match token.token_type() {
+      TokenType::LeftBrace => break,
+      TokenType::Comma => out.push(','),
+      _ => {
+        if !out.is_empty() {
+          out.push(' ');
+        }
+        out.push_str(token.lexeme());
+      }
+    }
+  }
+  out
+}
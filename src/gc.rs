@@ -3,3 +3,281 @@
 //! This module is mainly about garbage collection.
 //!
 //! The garbage collector is a simple mark and sweep collector.
+//!
+//! Only the mark phase exists so far: objects reachable from roots of the virtual machine
+//! are traced (see [`VM::reachable_objects`]), while unreachable ones are never swept.
+//! The heap browser of the debugger lists what's traced.
+//!
+//! With the `alloc_sites` feature, the site responsible for each allocation (the line of the
+//! instruction, or the native function being called) is recorded, so heap reports could
+//! tell where objects come from.
+
+use core::{fmt::Display, mem::size_of, ptr::NonNull};
+use hashbrown::HashSet;
+
+use crate::{
+  object::{
+    Obj, ObjBoundMethod, ObjClass, ObjClosure, ObjEnum, ObjEnumMember, ObjFunction, ObjInstance,
+    ObjList, ObjNative, ObjString, ObjType, ObjUpvalue,
+  },
+  prelude::*,
+  table::Table,
+  value::Value,
+  vm::VM,
+};
+
+/// ## Allocation Site
+///
+/// What an object is allocated by (see the `alloc_sites` feature).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum AllocSite {
+  /// An instruction of the line of the script.
+  Line(usize),
+  /// The native function of the name, called by the script.
+  Native(String),
+}
+
+impl Display for AllocSite {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      AllocSite::Line(line) => write!(f, "line {}", line),
+      AllocSite::Native(name) => write!(f, "native `{}`", name),
+    }
+  }
+}
+
+#[cfg(feature = "alloc_sites")]
+std::thread_local! {
+  /// Site of allocations made now, `None` outside of runs (e.g. by the compiler or the host).
+  static CURRENT_SITE: core::cell::RefCell<Option<AllocSite>> = const {
+    core::cell::RefCell::new(None)
+  };
+  /// Site of each object allocated at a site, by its address
+  /// (objects are never freed, so addresses are never reused).
+  static SITES: core::cell::RefCell<hashbrown::HashMap<usize, AllocSite>> =
+    core::cell::RefCell::new(hashbrown::HashMap::new());
+}
+
+#[cfg(feature = "std")]
+std::thread_local! {
+  /// Bytes of objects allocated by this thread so far (see [`allocated_bytes`]).
+  static ALLOCATED: core::cell::Cell<usize> = const { core::cell::Cell::new(0) };
+}
+
+/// Bytes of objects allocated so far (without threads, there's a single counter).
+#[cfg(not(feature = "std"))]
+static ALLOCATED: core::sync::atomic::AtomicUsize = core::sync::atomic::AtomicUsize::new(0);
+
+/// Bytes of objects allocated so far by this thread, each counted by its size when it's
+/// allocated (see [`object_size`]). Objects are never freed, so it never decreases.
+pub fn allocated_bytes() -> usize {
+  #[cfg(feature = "std")]
+  return ALLOCATED.get();
+  #[cfg(not(feature = "std"))]
+  return ALLOCATED.load(core::sync::atomic::Ordering::Relaxed);
+}
+
+/// Move the object onto the heap, recording its allocation site (see [`site_of`]).
+pub(crate) fn allocate<T>(obj: T) -> NonNull<Obj> {
+  let ptr = NonNull::new(Box::into_raw(Box::new(obj))).unwrap().cast();
+  let size = object_size(ptr);
+  #[cfg(feature = "std")]
+  ALLOCATED.set(ALLOCATED.get() + size);
+  #[cfg(not(feature = "std"))]
+  ALLOCATED.fetch_add(size, core::sync::atomic::Ordering::Relaxed);
+  #[cfg(feature = "alloc_sites")]
+  CURRENT_SITE.with_borrow(|site| {
+    if let Some(site) = site {
+      SITES.with_borrow_mut(|sites| sites.insert(ptr.as_ptr() as usize, site.clone()));
+    }
+  });
+  ptr
+}
+
+/// Set the site of allocations made from now on, returns the previous one.
+#[cfg(feature = "alloc_sites")]
+pub(crate) fn set_site(site: Option<AllocSite>) -> Option<AllocSite> {
+  CURRENT_SITE.replace(site)
+}
+
+/// Site the object is allocated at, `None` if it's allocated outside of runs
+/// (or the `alloc_sites` feature is off).
+pub fn site_of(obj: NonNull<Obj>) -> Option<AllocSite> {
+  #[cfg(feature = "alloc_sites")]
+  return SITES.with_borrow(|sites| sites.get(&(obj.as_ptr() as usize)).cloned());
+  #[cfg(not(feature = "alloc_sites"))]
+  {
+    let _ = obj;
+    None
+  }
+}
+
+/// Objects being traced: gray ones are reached but not scanned yet.
+#[derive(Default)]
+struct Tracer {
+  reached: HashSet<NonNull<Obj>>,
+  gray: Vec<NonNull<Obj>>,
+  /// All reached objects, in order of being reached.
+  order: Vec<NonNull<Obj>>,
+}
+
+impl Tracer {
+  fn mark(&mut self, obj: NonNull<Obj>) {
+    if self.reached.insert(obj) {
+      self.gray.push(obj);
+      self.order.push(obj);
+    }
+  }
+
+  fn mark_value(&mut self, value: &Value) {
+    if value.is_obj() {
+      self.mark(value.as_obj());
+    }
+  }
+
+  fn mark_table(&mut self, table: &Table) {
+    for (_, value) in table.iter() {
+      self.mark_value(value);
+    }
+  }
+
+  /// Scan gray objects (marking what they refer to) until there's none.
+  fn trace(mut self) -> Vec<NonNull<Obj>> {
+    while let Some(obj) = self.gray.pop() {
+      self.blacken(obj);
+    }
+    self.order
+  }
+
+  fn blacken(&mut self, obj: NonNull<Obj>) {
+    match unsafe { obj.as_ref() }.obj_type {
+      ObjType::String | ObjType::Native => {}
+      ObjType::Function => {
+        let function = unsafe { obj.cast::<ObjFunction>().as_ref() };
+        for value in &function.chunk.constants.values {
+          self.mark_value(value);
+        }
+      }
+      ObjType::Closure => {
+        let closure = unsafe { obj.cast::<ObjClosure>().as_ref() };
+        self.mark(closure.function.cast());
+        for upvalue in &closure.upvalues {
+          self.mark(upvalue.cast());
+        }
+      }
+      ObjType::Upvalue => {
+        // open upvalues refer to the stack, which is traced as a root
+        if let Some(value) = &unsafe { obj.cast::<ObjUpvalue>().as_ref() }.closed {
+          self.mark_value(value);
+        }
+      }
+      ObjType::Class => {
+        let class = unsafe { obj.cast::<ObjClass>().as_ref() };
+        self.mark_table(&class.methods);
+        self.mark_table(&class.getters);
+        self.mark_table(&class.setters);
+        if let Some(superclass) = class.superclass {
+          self.mark(superclass.cast());
+        }
+      }
+      ObjType::Instance => {
+        let instance = unsafe { obj.cast::<ObjInstance>().as_ref() };
+        self.mark(instance.class.cast());
+        self.mark_table(&instance.fields);
+        self.mark_table(&instance.bound_methods);
+      }
+      ObjType::BoundMethod => {
+        let bound = unsafe { obj.cast::<ObjBoundMethod>().as_ref() };
+        self.mark_value(&bound.receiver);
+        self.mark_value(&bound.method);
+      }
+      ObjType::List => {
+        for item in &unsafe { obj.cast::<ObjList>().as_ref() }.items {
+          self.mark_value(item);
+        }
+      }
+      ObjType::Enum => {
+        for member in &unsafe { obj.cast::<ObjEnum>().as_ref() }.members {
+          self.mark(member.cast());
+        }
+      }
+      ObjType::EnumMember => {
+        self.mark(unsafe { obj.cast::<ObjEnumMember>().as_ref() }.enum_.cast());
+      }
+    }
+  }
+}
+
+/// Approximate bytes owned by the object: itself, and its buffers
+/// (contents of strings, bytecode and constants of functions, entries of tables, items of lists).
+pub fn object_size(obj: NonNull<Obj>) -> usize {
+  let table_size = |table: &Table| table.len() * (size_of::<ObjString>() + size_of::<Value>());
+  match unsafe { obj.as_ref() }.obj_type {
+    ObjType::String => {
+      size_of::<ObjString>() + unsafe { obj.cast::<ObjString>().as_ref() }.data.capacity()
+    }
+    ObjType::Function => {
+      let function = unsafe { obj.cast::<ObjFunction>().as_ref() };
+      size_of::<ObjFunction>()
+        + function.chunk.code.capacity()
+        + function.chunk.constants.values.capacity() * size_of::<Value>()
+    }
+    ObjType::Native => size_of::<ObjNative>(),
+    ObjType::Closure => {
+      let closure = unsafe { obj.cast::<ObjClosure>().as_ref() };
+      size_of::<ObjClosure>() + closure.upvalues.capacity() * size_of::<NonNull<ObjUpvalue>>()
+    }
+    ObjType::Upvalue => size_of::<ObjUpvalue>(),
+    ObjType::Class => {
+      let class = unsafe { obj.cast::<ObjClass>().as_ref() };
+      size_of::<ObjClass>()
+        + table_size(&class.methods)
+        + table_size(&class.getters)
+        + table_size(&class.setters)
+    }
+    ObjType::Instance => {
+      let instance = unsafe { obj.cast::<ObjInstance>().as_ref() };
+      size_of::<ObjInstance>() + table_size(&instance.fields) + table_size(&instance.bound_methods)
+    }
+    ObjType::BoundMethod => size_of::<ObjBoundMethod>(),
+    ObjType::List => {
+      let list = unsafe { obj.cast::<ObjList>().as_ref() };
+      size_of::<ObjList>() + list.items.capacity() * size_of::<Value>()
+    }
+    ObjType::Enum => {
+      let enum_ = unsafe { obj.cast::<ObjEnum>().as_ref() };
+      size_of::<ObjEnum>() + enum_.members.capacity() * size_of::<NonNull<ObjEnumMember>>()
+    }
+    ObjType::EnumMember => size_of::<ObjEnumMember>(),
+  }
+}
+
+impl VM {
+  /// All objects reachable from roots (the stack, globals, ongoing calls, open upvalues,
+  /// watches and conditions of breakpoints), each once, in order of being reached.
+  pub fn reachable_objects(&self) -> Vec<NonNull<Obj>> {
+    let mut tracer = Tracer::default();
+    for value in &self.stack {
+      tracer.mark_value(value);
+    }
+    tracer.mark_table(&self.globals);
+    for frame in &self.frames {
+      tracer.mark(frame.function.cast());
+      if let Some(closure) = frame.closure {
+        tracer.mark(closure.cast());
+      }
+    }
+    for upvalue in &self.open_upvalues {
+      tracer.mark(upvalue.cast());
+    }
+    for watch in &self.watches {
+      tracer.mark(watch.function.cast());
+    }
+    for (_, condition, _) in self.breakpoints.values() {
+      if let Some(condition) = condition {
+        tracer.mark(condition.cast());
+      }
+    }
+    tracer.trace()
+  }
+}
@@ -0,0 +1,279 @@
+//! # Isa
+//!
+//! This module contains the reference of the instruction set (`rlox isa`).
+//!
+//! Every opcode is described by [`OpCode::spec`], which matches opcodes exhaustively,
+//! so a new opcode can't be added without being documented.
+//! `docs/instruction_set.md` is generated from it, and checked by tests.
+//!
+//! The encoding of opcodes (their codes and operands) is versioned by [`ISA_VERSION`],
+//! which is recorded in cache files, so bytecode compiled by an incompatible rlox is never run.
+//! Tests pin the [`fingerprint`] of the encoding, so it can't change without a bump.
+
+use core::fmt::Write;
+
+use crate::{chunk::OpCode, prelude::*};
+
+/// Version of the instruction set, bumped on each change of the encoding of opcodes.
+pub const ISA_VERSION: u32 = 15;
+
+/// ## Operand
+///
+/// An operand following the opcode in the bytecode.
+///
+/// Operands wider than a byte are big-endian.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operand {
+  /// Index of a constant of the chunk (`u8`).
+  Constant,
+  /// Slot of a local, relative to the frame (`u8`).
+  Slot,
+  /// Slot of a local, relative to the frame (`u16`).
+  SlotLong,
+  /// Count of arguments (`u8`).
+  ArgCount,
+  /// Count of values (`u8`).
+  Count,
+  /// Forward offset from the end of the instruction (`u16`).
+  Jump,
+  /// Backward offset from the end of the instruction (`u16`).
+  Loop,
+  /// Index of an upvalue of the closure (`u8`).
+  Upvalue,
+}
+
+impl Operand {
+  /// Count of bytes of the operand.
+  pub fn width(self) -> usize {
+    match self {
+      Operand::SlotLong | Operand::Jump | Operand::Loop => 2,
+      _ => 1,
+    }
+  }
+
+  /// Name of the operand in the reference.
+  pub fn name(self) -> &'static str {
+    match self {
+      Operand::Constant => "constant",
+      Operand::Slot => "slot",
+      Operand::SlotLong => "slot (u16)",
+      Operand::ArgCount => "args",
+      Operand::Count => "count",
+      Operand::Jump => "offset (u16)",
+      Operand::Loop => "back offset (u16)",
+      Operand::Upvalue => "upvalue",
+    }
+  }
+}
+
+/// ## OpSpec
+///
+/// What an opcode takes, does to the stack, and which syntax emits it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpSpec {
+  /// Operands following the opcode, in order.
+  pub operands: &'static [Operand],
+  /// Effect on the top of the stack, as `before -> after`.
+  pub stack: &'static str,
+  /// Syntax which emits the opcode.
+  pub syntax: &'static str,
+}
+
+impl OpSpec {
+  const fn new(operands: &'static [Operand], stack: &'static str, syntax: &'static str) -> Self {
+    Self {
+      operands,
+      stack,
+      syntax,
+    }
+  }
+
+  /// Count of bytes of the instruction (opcode included).
+  pub fn width(&self) -> usize {
+    1 + self
+      .operands
+      .iter()
+      .map(|operand| operand.width())
+      .sum::<usize>()
+  }
+}
+
+impl OpCode {
+  /// All opcodes, in order of their encoding.
+  pub fn all() -> impl Iterator<Item = OpCode> {
+    (0..=u8::MAX).map_while(|byte| OpCode::try_from(byte).ok())
+  }
+
+  /// Specification of the opcode.
+  pub fn spec(self) -> OpSpec {
+    use Operand::*;
+    match self {
+      /* Constants */
+      OpCode::Constant => OpSpec::new(&[Constant], "-> value", "literals, `fun`"),
+      OpCode::Nil => OpSpec::new(&[], "-> nil", "`nil`, missing initializers"),
+      OpCode::True => OpSpec::new(&[], "-> true", "`true`"),
+      OpCode::False => OpSpec::new(&[], "-> false", "`false`"),
+      /* Comparisons */
+      OpCode::Equal => OpSpec::new(&[], "a, b -> a == b", "`==`"),
+      OpCode::Greater => OpSpec::new(&[], "a, b -> a > b", "`>`"),
+      OpCode::Less => OpSpec::new(&[], "a, b -> a < b", "`<`"),
+      OpCode::NotEqual => OpSpec::new(&[], "a, b -> a != b", "`!=`"),
+      OpCode::GreaterEqual => OpSpec::new(&[], "a, b -> a >= b", "`>=`"),
+      OpCode::LessEqual => OpSpec::new(&[], "a, b -> a <= b", "`<=`"),
+      OpCode::Is => OpSpec::new(&[], "value, class -> bool", "`is`"),
+      OpCode::Contains => OpSpec::new(&[], "needle, haystack -> bool", "`in`"),
+      /* Binary Arith Opts */
+      OpCode::Add => OpSpec::new(&[], "a, b -> a + b", "`+`"),
+      OpCode::Subtract => OpSpec::new(&[], "a, b -> a - b", "`-` (binary)"),
+      OpCode::Multiply => OpSpec::new(&[], "a, b -> a * b", "`*`"),
+      OpCode::Divide => OpSpec::new(&[], "a, b -> a / b", "`/`"),
+      OpCode::FloorDivide => OpSpec::new(&[], "a, b -> floor(a / b)", "`div`"),
+      /* Unary Arith Opts */
+      OpCode::Not => OpSpec::new(&[], "a -> !a", "`!`"),
+      OpCode::Negate => OpSpec::new(&[], "a -> -a", "`-` (unary)"),
+      /* Control Flow Opts */
+      OpCode::JumpIfFalse => OpSpec::new(&[Jump], "cond -> cond", "`if`, `and`, `or`"),
+      OpCode::Jump => OpSpec::new(&[Jump], "->", "`else`, `or`, `try`"),
+      OpCode::Loop => OpSpec::new(&[Loop], "->", "end of `while` bodies"),
+      /* Helper Opts */
+      OpCode::Print => OpSpec::new(&[], "value ->", "`print`"),
+      OpCode::Pop => OpSpec::new(&[], "value ->", "expression statements, end of blocks"),
+      OpCode::PopN => OpSpec::new(&[Count], "values.. ->", "consecutive `Pop`s (`-O1`)"),
+      OpCode::Peek => OpSpec::new(
+        &[Count],
+        "value, values.. -> value, values.., value",
+        "`++`/`--` of properties and items",
+      ),
+      OpCode::Sink => OpSpec::new(
+        &[Count],
+        "values.., value -> value, values..",
+        "`++`/`--` of properties and items",
+      ),
+      OpCode::Stringify => OpSpec::new(&[], "value -> string", "`${..}` in strings"),
+      /* Variable Getters/Setters */
+      OpCode::DefineGlobal => OpSpec::new(&[Constant], "value ->", "top-level declarations"),
+      OpCode::GetGlobal => OpSpec::new(&[Constant], "-> value", "global variables"),
+      OpCode::GetLocal => OpSpec::new(&[Slot], "-> value", "local variables"),
+      OpCode::SetGlobal => OpSpec::new(&[Constant], "value -> value", "`=` of globals"),
+      OpCode::SetLocal => OpSpec::new(&[Slot], "value -> value", "`=` of locals"),
+      OpCode::GetLocalLong => OpSpec::new(&[SlotLong], "-> value", "local variables (> 256)"),
+      OpCode::SetLocalLong => OpSpec::new(&[SlotLong], "value -> value", "`=` of locals (> 256)"),
+      OpCode::MissingArgument => OpSpec::new(&[Slot], "-> bool", "default values of parameters"),
+      /* Function Opts */
+      OpCode::Call => OpSpec::new(&[ArgCount], "callee, args.. -> result", "calls"),
+      OpCode::CallGlobal => OpSpec::new(
+        &[Constant, ArgCount],
+        "args.. -> result",
+        "registered operators",
+      ),
+      OpCode::Closure => OpSpec::new(&[Constant], "-> closure", "`fun` capturing variables"),
+      OpCode::GetUpvalue => OpSpec::new(&[Upvalue], "-> value", "captured variables"),
+      OpCode::SetUpvalue => OpSpec::new(&[Upvalue], "value -> value", "`=` of captured variables"),
+      OpCode::CloseUpvalue => OpSpec::new(&[], "value ->", "end of blocks (captured locals)"),
+      /* Class Opts */
+      OpCode::Class => OpSpec::new(&[Constant], "-> class", "`class`"),
+      OpCode::Trait => OpSpec::new(&[Constant], "-> trait", "`trait`"),
+      OpCode::Mixin => OpSpec::new(&[], "class, trait -> class", "`with`"),
+      OpCode::Inherit => OpSpec::new(&[], "class, superclass -> class", "`<` of classes"),
+      OpCode::GetProperty => OpSpec::new(&[Constant], "instance -> value", "`.name`"),
+      OpCode::SetProperty => OpSpec::new(&[Constant], "instance, value -> value", "`.name =`"),
+      OpCode::DeleteProperty => OpSpec::new(&[Constant], "instance ->", "`delete`"),
+      OpCode::Method => OpSpec::new(&[Constant], "class, method -> class", "methods"),
+      OpCode::Getter => OpSpec::new(&[Constant], "class, getter -> class", "`get`"),
+      OpCode::Setter => OpSpec::new(&[Constant], "class, setter -> class", "`set`"),
+      OpCode::Invoke => OpSpec::new(
+        &[Constant, ArgCount],
+        "instance, args.. -> result",
+        "`.name(..)`",
+      ),
+      OpCode::GetSuper => OpSpec::new(&[Constant], "this, superclass -> method", "`super.name`"),
+      OpCode::SuperInvoke => OpSpec::new(
+        &[Constant, ArgCount],
+        "this, args.., superclass -> result",
+        "`super.name(..)`",
+      ),
+      OpCode::Enum => OpSpec::new(&[Constant], "-> enum", "`enum`"),
+      OpCode::EnumMember => OpSpec::new(&[Constant], "enum -> enum", "members of `enum`"),
+      /* List Opts */
+      OpCode::BuildList => OpSpec::new(&[Count], "items.. -> list", "`[..]`"),
+      OpCode::GetIndex => OpSpec::new(&[], "list, index -> item", "`[index]`"),
+      OpCode::GetSlice => OpSpec::new(&[], "list, start, end -> slice", "`[start:end]`"),
+      OpCode::SetIndex => OpSpec::new(&[], "list, index, item -> item", "`[index] =`"),
+      OpCode::ExtendList => {
+        OpSpec::new(&[], "list, items -> list", "`[.., ...items]`, `f(...args)`")
+      }
+      OpCode::SpreadCall => OpSpec::new(&[], "callee, args -> result", "`f(...args)`"),
+      OpCode::SpreadInvoke => OpSpec::new(
+        &[Constant],
+        "receiver, args -> result",
+        "`receiver.name(...args)`",
+      ),
+      OpCode::SpreadSuperInvoke => OpSpec::new(
+        &[Constant],
+        "this, args, superclass -> result",
+        "`super.name(...args)`",
+      ),
+      OpCode::Unpack => OpSpec::new(&[Count], "list -> items..", "`var a, b = list;`"),
+      OpCode::MatchList => OpSpec::new(&[Count], "value -> bool", "`[a, b]` patterns of `match`"),
+      OpCode::MatchListRest => OpSpec::new(
+        &[Count],
+        "value -> bool",
+        "`[a, ...rest]` patterns of `match`",
+      ),
+      /* Module Opts */
+      OpCode::Import => OpSpec::new(&[Constant], "module -> result", "`import`"),
+      /* Exception Opts */
+      OpCode::PushHandler => OpSpec::new(&[Jump], "->", "`try`"),
+      OpCode::PopHandler => OpSpec::new(&[], "->", "end of `try` blocks"),
+      OpCode::Throw => OpSpec::new(&[], "error ->", "`throw`, runtime errors in `try`"),
+      /* Return */
+      OpCode::Return => OpSpec::new(&[], "result ->", "`return`, end of functions"),
+    }
+  }
+}
+
+/// Fingerprint of the encoding of all opcodes:
+/// 64-bit FNV-1a hash of their codes, names and operands.
+pub fn fingerprint() -> u64 {
+  let mut hash: u64 = 0xcbf29ce484222325;
+  for op_code in OpCode::all() {
+    let spec = op_code.spec();
+    let encoding = format!("{}:{:?}:{:?};", op_code as u8, op_code, spec.operands);
+    for byte in encoding.bytes() {
+      hash ^= byte as u64;
+      hash = hash.wrapping_mul(0x100000001b3);
+    }
+  }
+  hash
+}
+
+/// Reference of the instruction set, as a markdown table.
+pub fn reference() -> String {
+  let mut out = String::new();
+  out.push_str("# Instruction set\n\n");
+  out.push_str("<!-- Generated by `rlox isa`, don't edit by hand. -->\n\n");
+  let _ = writeln!(out, "Version {} of the instruction set.\n", ISA_VERSION);
+  out.push_str("Operands follow the opcode, those wider than a byte are big-endian.\n\n");
+  out.push_str("| Code | Name | Operands | Width | Stack | Syntax |\n");
+  out.push_str("| ---: | ---- | -------- | ----: | ----- | ------ |\n");
+  for op_code in OpCode::all() {
+    let spec = op_code.spec();
+    let operands = spec
+      .operands
+      .iter()
+      .map(|operand| operand.name())
+      .collect::<Vec<_>>()
+      .join(", ");
+    let _ = writeln!(
+      out,
+      "| {} | `{:?}` | {} | {} | `{}` | {} |",
+      op_code as u8,
+      op_code,
+      if operands.is_empty() { "-" } else { &operands },
+      spec.width(),
+      spec.stack,
+      spec.syntax
+    );
+  }
+  out
+}
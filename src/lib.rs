@@ -1,11 +1,69 @@
+//! # rlox
+//!
+//! A bytecode virtual machine of Lox.
+//!
+//! The core (scanner, compiler and virtual machine) only needs `alloc`,
+//! everything which needs an operating system (files, the REPL, bench and batch modes,
+//! host natives like `clock` and `exec`) is behind the (default) `std` feature.
+//!
+//! The public API is re-exported at the root (see [`api`]).
+//! Other modules are internal: those used by the binary, tests and tools are reachable,
+//! but hidden from docs and not covered by semver, the rest are private.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod api;
+pub use api::{
+  CompiledScript, Debugger, Diagnostic, DiagnosticKind, RunOutcome, Source, Value, Vm,
+};
+pub use compiler::stats::{CompileStats, FunctionStats};
+pub use debugger::{
+  Breakpoint, HeapChange, HeapDiff, HeapGroup, HeapObject, HeapSnapshot, SiteGroup,
+};
+pub use gc::AllocSite;
+pub use native::{sandbox::SandboxProfile, Capabilities};
+pub use vm::Step;
+
+pub(crate) mod arena;
+#[doc(hidden)]
+#[cfg(feature = "std")]
+pub mod batch;
+#[doc(hidden)]
+#[cfg(feature = "std")]
+pub mod bench;
+#[doc(hidden)]
+pub mod bundle;
+#[doc(hidden)]
+pub mod cache;
+#[doc(hidden)]
 pub mod chunk;
+#[doc(hidden)]
 pub mod compiler;
+pub(crate) mod crash;
+#[doc(hidden)]
 pub mod debug;
+pub(crate) mod debugger;
+#[doc(hidden)]
+pub mod doc;
+#[doc(hidden)]
 pub mod gc;
-pub mod memory;
+#[doc(hidden)]
+pub mod isa;
+pub(crate) mod memory;
+#[doc(hidden)]
+pub mod native;
+#[doc(hidden)]
 pub mod object;
+mod prelude;
+#[doc(hidden)]
 pub mod scanner;
+#[doc(hidden)]
 pub mod table;
+#[doc(hidden)]
 pub mod utils;
+#[doc(hidden)]
 pub mod value;
+#[doc(hidden)]
 pub mod vm;
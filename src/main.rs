@@ -1,29 +1,178 @@
+use rlox::batch::{self, BatchOptions};
+use rlox::bench::{self, BenchOptions, CountingAllocator};
+use rlox::bundle::{self, BundleOptions};
+use rlox::cache::{self, CacheOptions};
+use rlox::compiler::{CompileOptions, OptLevel};
+use rlox::doc;
+use rlox::isa;
+use rlox::native::{sandbox::SandboxProfile, Capabilities};
 use rlox::utils::Init;
+use rlox::value::display;
 use rlox::{utils, vm::InterpretError, vm::VM};
 use std::io::{self, Write};
 use std::process::exit;
+use std::time::{Duration, SystemTime};
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
 
 pub fn main() {
+  // a self-contained executable (see `rlox build`) runs its bundle only
+  if let Some(bytes) = std::env::current_exe()
+    .ok()
+    .and_then(|executable| bundle::embedded_bundle(&executable).ok().flatten())
+  {
+    run_embedded(&bytes);
+    return;
+  }
+
   let argv = utils::args();
   let argc = argv.len();
-  if argc > 2 {
-    eprintln!("Usage: rlox [path]");
-    exit(64);
+
+  if argc >= 2 && argv[1] == "bench" {
+    run_bench(&argv[2..]);
+    return;
+  }
+  if argc == 2 && argv[1] == "isa" {
+    print!("{}", isa::reference());
+    return;
+  }
+  if argc >= 2 && argv[1] == "doc" {
+    run_doc(&argv[2..]);
+    return;
   }
 
   let mut vm = VM::init();
-  if argc == 1 {
-    repl(&mut vm).unwrap();
-  } else if argc == 2 {
-    run_file(&mut vm, argv[1].to_owned()).unwrap();
+  let mut args = &argv[1..];
+  let mut compile_options = CompileOptions::init();
+  // scripts run by the command line may block and import files (unlike embedded ones)
+  let mut capabilities = Capabilities {
+    sleep: true,
+    files: true,
+    ..Capabilities::none()
+  };
+  let mut sandbox = None;
+  // options of the virtual machine, kept for those of each file of `run-dir`
+  let mut deterministic = None;
+  let mut strict_comparison = false;
+  let mut timeout = None;
+  let mut stats = false;
+  while let Some(arg) = args.first() {
+    match arg.as_str() {
+      "--cache" => vm.set_cache_options(CacheOptions {
+        on_disk: true,
+        ..CacheOptions::init()
+      }),
+      "-O0" => compile_options.opt_level = OptLevel::O0,
+      "-O1" => compile_options.opt_level = OptLevel::O1,
+      "-O2" => compile_options.opt_level = OptLevel::O2,
+      "--allow-exec" => capabilities.exec = true,
+      "--allow-net" => capabilities.network = true,
+      "--allow-files" => capabilities.files = true,
+      "--deterministic" => {
+        deterministic = Some(0);
+        vm.set_deterministic(deterministic);
+      }
+      "--strict-comparison" => {
+        strict_comparison = true;
+        vm.set_strict_comparison(strict_comparison);
+      }
+      "--post-mortem" => vm.set_post_mortem(true),
+      "--stats" => stats = true,
+      "--sandbox" => {
+        let Some(profile) = args.get(1).and_then(|name| SandboxProfile::from_name(name)) else {
+          usage()
+        };
+        vm.set_sandbox(profile);
+        capabilities = profile.capabilities();
+        sandbox = Some(profile);
+        // replaced by the one of the profile
+        timeout = None;
+        args = &args[1..];
+      }
+      "--timeout" => {
+        let Some(millis) = args.get(1).and_then(|millis| millis.parse().ok()) else {
+          usage()
+        };
+        timeout = Some(Duration::from_millis(millis));
+        vm.set_timeout(timeout);
+        args = &args[1..];
+      }
+      _ => break,
+    }
+    args = &args[1..];
+  }
+  vm.set_compile_options(compile_options);
+  vm.set_capabilities(capabilities);
+  match args {
+    [] => repl(&mut vm).unwrap(),
+    [flag, src] if flag == "-e" => run_source(&mut vm, src),
+    [watch, path] if watch == "watch" => run_watch(&mut vm, path),
+    [command, path, rest @ ..] if command == "cache" => run_cache(&mut vm, path, rest),
+    [command, path, rest @ ..] if command == "bundle" => run_bundle(&mut vm, path, rest),
+    [command, path, rest @ ..] if command == "build" => run_build(&mut vm, path, rest),
+    [run_dir, dir, rest @ ..] if run_dir == "run-dir" => {
+      let options = BatchOptions {
+        compile_options,
+        capabilities,
+        sandbox,
+        deterministic,
+        strict_comparison,
+        timeout,
+        ..BatchOptions::init()
+      };
+      run_batch(dir, rest, options)
+    }
+    [path] => {
+      if stats {
+        show_stats(&mut vm, path);
+      }
+      run_file(&mut vm, path.to_owned())
+    }
+    _ => usage(),
   }
   vm.free();
 }
 
+/// Print usage, then exit.
+fn usage() -> ! {
+  eprintln!("Usage: rlox [options] [path]");
+  eprintln!("       rlox [options] -e <source>");
+  eprintln!("       rlox [options] watch <path>");
+  eprintln!("       rlox [options] run-dir <dir> [--jobs N]");
+  eprintln!("       rlox [options] cache <path> [--migrate]");
+  eprintln!("       rlox [options] bundle <path> [-o <out>] [--strip|--debug-info]");
+  eprintln!("       rlox [options] build <path> [-o <out>] [--strip|--debug-info]");
+  eprintln!("       rlox bench <path> [--iterations N] [--warmup N] [--reuse] [--startup]");
+  eprintln!("       rlox doc <path>");
+  eprintln!("       rlox isa");
+  eprintln!();
+  eprintln!("Options:");
+  eprintln!("  --cache          cache compiled scripts on disk (as `<path>c`)");
+  eprintln!("  -O0|-O1|-O2      optimization level (default: -O0)");
+  eprintln!("  --allow-exec     let scripts run other programs by `exec`");
+  eprintln!(
+    "  --allow-net      let scripts access the network by `fetch` (with the `http` feature)"
+  );
+  eprintln!("  --allow-files    let scripts import modules from files (granted unless sandboxed)");
+  eprintln!(
+    "  --deterministic  replace `clock`, `now_unix`, `random` and `sleep` by deterministic ones"
+  );
+  eprintln!("  --strict-comparison  make ordering values of different types a runtime error");
+  eprintln!("  --post-mortem    inspect locals of failed runs at their runtime errors");
+  eprintln!("  --stats          report code size, constants and stack usage of each function");
+  eprintln!("  --timeout <ms>   abort scripts running longer than the given milliseconds");
+  eprintln!(
+    "  --sandbox <pure|io|full>  restrict capabilities, natives, fuel, heap and run time by a profile"
+  );
+  exit(64);
+}
+
 /// Run the REPL.
 fn repl(vm: &mut VM) -> Result<(), InterpretError> {
   println!("Welcome to lox's REPL!");
   println!("Press <Ctrl> + <C> to exit.");
+  let mut depth = display::PRETTY_DEPTH;
   loop {
     print!("|> ");
     io::stdout().flush().unwrap();
@@ -31,13 +180,503 @@ fn repl(vm: &mut VM) -> Result<(), InterpretError> {
     let mut line = String::new();
     io::stdin().read_line(&mut line).unwrap();
 
-    if let Err(e) = vm.interpret(line) {
+    if line.trim() == ":globals" {
+      print!("{}", vm.dump_globals());
+      continue;
+    }
+    if let Some(arg) = line.trim().strip_prefix(":depth ") {
+      match arg.trim().parse() {
+        Ok(max_depth) => depth = max_depth,
+        Err(_) => eprintln!("Expect a non-negative integer depth."),
+      }
+      continue;
+    }
+    if let Some(arg) = line.trim().strip_prefix(":heap") {
+      show_heap(vm, arg);
+      continue;
+    }
+    if let Some(name) = line.trim().strip_prefix(":inspect ") {
+      match vm.inspect(name.trim()) {
+        Some(inspection) => print!("{}", inspection),
+        None => eprintln!("Undefined variable `{}`.", name.trim()),
+      }
+      continue;
+    }
+    let result = match line.trim().strip_prefix(":load ") {
+      Some(path) => vm.interpret_file(path.trim().to_owned()),
+      None => vm.interpret(line.clone()),
+    };
+    for warning in vm.warnings() {
+      eprintln!("{}", warning);
+    }
+    match result {
+      Ok(Some(value)) => println!("{}", display::pretty(&value, depth)),
+      Ok(None) => {}
+      Err(e) => {
+        eprintln!("{:?}", e);
+        report_crash(vm, Some(&line));
+        post_mortem(vm);
+      }
+    }
+  }
+}
+
+/// Show how much of the limits of a chunk each function of the file uses,
+/// with warnings of those approaching them.
+fn show_stats(vm: &mut VM, path: &str) {
+  // compile errors are reported by running the file
+  if let Ok(stats) = vm.compile_stats_file(path.to_owned()) {
+    eprint!("{}", stats);
+  }
+}
+
+/// Run the given file (a script, or a bundle told by its magic).
+fn run_file(vm: &mut VM, path: String) {
+  let result = match std::fs::read(&path) {
+    Ok(bytes) if bundle::is_bundle(&bytes) => vm.interpret_bundle(&bytes, &path),
+    _ => vm.interpret_file(path.clone()),
+  };
+  let source = || std::fs::read_to_string(&path).ok();
+  report_result(vm, result.map(|_| ()), source);
+}
+
+/// Run the given source (of `-e`).
+fn run_source(vm: &mut VM, src: &str) {
+  let result = vm.interpret(src.to_owned());
+  report_result(vm, result.map(|_| ()), || Some(src.to_owned()));
+}
+
+/// Report warnings and the error (if any) of running a script, then exit if it failed.
+fn report_result(
+  vm: &mut VM,
+  result: Result<(), InterpretError>,
+  source: impl FnOnce() -> Option<String>,
+) {
+  for warning in vm.warnings() {
+    eprintln!("{}", warning);
+  }
+  match result {
+    Ok(_) => {}
+    Err(InterpretError::CompileError(message)) => {
+      eprintln!("{}", message);
+      exit(65);
+    }
+    Err(error) => {
+      eprintln!("{}", error.into_message());
+      report_crash(vm, source().as_deref());
+      post_mortem(vm);
+      exit(70);
+    }
+  }
+}
+
+/// Write the crash report of the last run (if any) with the source into a bundle,
+/// then tell where it is.
+fn report_crash(vm: &VM, source: Option<&str>) {
+  let Some(report) = vm.crash_report() else {
+    return;
+  };
+  match report.write_bundle(source) {
+    Ok(path) => eprintln!(
+      "Internal error of the interpreter, a crash report is written to `{}`.\n\
+       Please attach it to an issue.",
+      path.display()
+    ),
+    Err(_) => eprintln!("Internal error of the interpreter, failed to write a crash report."),
+  }
+}
+
+/// Inspect the run kept at its runtime error (if any, see [`VM::set_post_mortem`]),
+/// until `:quit` (or the end of input).
+///
+/// Other lines are expressions, evaluated in the failed call (see [`VM::eval_in_frame`]).
+fn post_mortem(vm: &mut VM) {
+  if vm.failure().is_none() {
+    return;
+  }
+  let show_locals = |vm: &VM, depth: usize| {
+    for (name, value) in vm.frame_locals(depth) {
+      eprintln!(
+        "  {} = {}",
+        name,
+        display::pretty(&value, display::PRETTY_DEPTH)
+      );
+    }
+  };
+  eprintln!(
+    "Post-mortem (`:locals [depth]`, `:instruction`, `:error`, `:heap [id]`, `:quit` or expressions):"
+  );
+  if let Some(instruction) = vm.failing_instruction() {
+    eprint!("{}", instruction);
+  }
+  show_locals(vm, 0);
+  loop {
+    eprint!("(post-mortem) ");
+    io::stderr().flush().unwrap();
+    let mut line = String::new();
+    if io::stdin().read_line(&mut line).unwrap_or(0) == 0 {
+      break;
+    }
+    match line.trim() {
+      "" => {}
+      ":quit" => break,
+      ":instruction" => eprint!("{}", vm.failing_instruction().unwrap_or_default()),
+      ":error" => eprintln!("{}", vm.failure().unwrap_or_default()),
+      ":locals" => show_locals(vm, 0),
+      command if command.starts_with(":heap") => show_heap(vm, &command[":heap".len()..]),
+      command if command.starts_with(':') => match command
+        .strip_prefix(":locals ")
+        .and_then(|depth| depth.trim().parse().ok())
+      {
+        Some(depth) => show_locals(vm, depth),
+        None => eprintln!("Unknown command `{}`.", command),
+      },
+      expression => match vm.eval_in_frame(0, expression) {
+        Ok(value) => eprintln!("{}", display::pretty(&value, display::PRETTY_DEPTH)),
+        Err(error) => eprintln!("{}", error.into_message()),
+      },
+    }
+  }
+}
+
+/// Show live objects of the heap grouped by type (`:heap`) or by allocation site (`:heap sites`),
+/// list objects of a type with their ids (e.g. `:heap instance`),
+/// or inspect the one of the id (`:heap 0x1f2e`).
+fn show_heap(vm: &VM, arg: &str) {
+  let arg = arg.trim();
+  if arg.is_empty() {
+    let groups = vm.heap_summary();
+    for group in &groups {
+      println!(
+        "{:<12} {:>6} objects {:>10} bytes",
+        format!("{:?}", group.obj_type),
+        group.count,
+        group.size
+      );
+    }
+    let (count, size) = groups.iter().fold((0, 0), |(count, size), group| {
+      (count + group.count, size + group.size)
+    });
+    println!("{:<12} {:>6} objects {:>10} bytes", "Total", count, size);
+    return;
+  }
+  if arg == "sites" {
+    for group in vm.heap_snapshot().sites() {
+      let site = group
+        .site
+        .map_or("(unknown)".to_owned(), |site| site.to_string());
+      println!(
+        "{:<24} {:>6} objects {:>10} bytes",
+        site, group.count, group.size
+      );
+    }
+    return;
+  }
+  let objects: Vec<_> = vm
+    .heap_objects()
+    .into_iter()
+    .filter(|obj| format!("{:?}", obj.obj_type).eq_ignore_ascii_case(arg))
+    .collect();
+  if !objects.is_empty() {
+    for obj in objects {
+      match obj.site {
+        Some(site) => println!(
+          "{:#x} {:>8} bytes  {}  ({})",
+          obj.id, obj.size, obj.repr, site
+        ),
+        None => println!("{:#x} {:>8} bytes  {}", obj.id, obj.size, obj.repr),
+      }
+    }
+    return;
+  }
+  match usize::from_str_radix(arg.trim_start_matches("0x"), 16)
+    .ok()
+    .and_then(|id| vm.inspect_object(id))
+  {
+    Some(inspection) => print!("{}", inspection),
+    None => eprintln!("No live object of type or id `{}`.", arg),
+  }
+}
+
+/// Run the given file, then hot-reload it each time it's modified (see [`VM::reload`]),
+/// so globals holding data survive edits of functions and classes.
+///
+/// Unchanged sources (e.g. only touched) are not compiled again, thanks to the compile cache.
+fn run_watch(vm: &mut VM, path: &str) {
+  let modified = || {
+    std::fs::metadata(path)
+      .and_then(|meta| meta.modified())
+      .ok()
+  };
+  let mut last_modified: Option<SystemTime> = None;
+  loop {
+    let current = modified();
+    if current.is_some() && current != last_modified {
+      let result = match last_modified {
+        None => vm.interpret_file(path.to_owned()).map(|_| ()),
+        Some(_) => vm.reload_file(path.to_owned()),
+      };
+      last_modified = current;
+      for warning in vm.warnings() {
+        eprintln!("{}", warning);
+      }
+      if let Err(error) = result {
+        eprintln!("{}", error.into_message());
+      }
+      eprintln!("[watching `{}` for changes]", path);
+    }
+    std::thread::sleep(Duration::from_millis(200));
+  }
+}
+
+/// Run the given file in benchmark mode, or with `--startup`, measure the startup of rlox
+/// on the file (cold in this process, then as processes).
+///
+/// Args: `<path> [--iterations N] [--warmup N] [--reuse] [--startup]`
+fn run_bench(args: &[String]) {
+  let mut path = None;
+  let mut options = BenchOptions::init();
+  let mut startup = false;
+  let mut args = args.iter();
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "--iterations" | "-n" => options.iterations = parse_count(args.next()),
+      "--warmup" => options.warmup = parse_count(args.next()),
+      "--reuse" => options.reuse_vm = true,
+      "--startup" => startup = true,
+      _ if path.is_none() => path = Some(arg.to_owned()),
+      _ => usage(),
+    }
+  }
+  let Some(path) = path else { usage() };
+  let Ok(src) = std::fs::read_to_string(&path) else {
+    eprintln!("Failed to read file `{}`.", path);
+    exit(74);
+  };
+  if startup {
+    run_startup_bench(&path, &src, options);
+    return;
+  }
+  match bench::bench(&src, options) {
+    Ok(reports) => bench::print_reports(&reports),
+    Err(e) => {
       eprintln!("{:?}", e);
+      exit(70);
+    }
+  }
+}
+
+/// Measure the startup of rlox on the file (see [`bench::startup`]), cold as nothing else
+/// ran in this process, then run `rlox <path>` as processes.
+fn run_startup_bench(path: &str, src: &str, options: BenchOptions) {
+  let report = match bench::startup(src) {
+    Ok(report) => report,
+    Err(e) => {
+      eprintln!("{:?}", e);
+      exit(70);
+    }
+  };
+  let process_times = match std::env::current_exe() {
+    Ok(rlox) => bench::process_times(std::process::Command::new(rlox).arg(path), options),
+    Err(error) => Err(error),
+  };
+  let Ok(process_times) = process_times else {
+    eprintln!("Failed to run rlox as a process.");
+    exit(74);
+  };
+  bench::print_startup(&report, &process_times);
+}
+
+/// Check the cache file of the script is compatible with this rlox,
+/// or with `--migrate`, compile the script again into it and print the disassembly.
+///
+/// Args: `[--migrate]`
+fn run_cache(vm: &mut VM, path: &str, args: &[String]) {
+  let migrate = match args {
+    [] => false,
+    [flag] if flag == "--migrate" => true,
+    _ => usage(),
+  };
+  let cache_path = cache::cache_path(path);
+  let Ok(buffer) = std::fs::read(&cache_path) else {
+    eprintln!("Failed to read file `{}`.", cache_path.display());
+    exit(74);
+  };
+  let error = match cache::read_header(&buffer, &cache_path.to_string_lossy()) {
+    Ok(header) => {
+      println!(
+        "Cache file `{}` is compatible (format v{}, instruction set v{}).",
+        cache_path.display(),
+        header.format_version,
+        header.isa_version
+      );
+      return;
+    }
+    Err(error) => error.into_message(),
+  };
+  if !migrate {
+    eprintln!("{}", error);
+    eprintln!("Run `rlox cache {} --migrate` to compile it again.", path);
+    exit(65);
+  }
+  vm.set_cache_options(CacheOptions {
+    on_disk: true,
+    ..CacheOptions::init()
+  });
+  match vm.disassemble_file(path.to_owned()) {
+    Ok(disassembly) => {
+      print!("{}", disassembly);
+      println!(
+        "Migrated `{}` to instruction set v{}.",
+        cache_path.display(),
+        isa::ISA_VERSION
+      );
+    }
+    Err(error) => {
+      eprintln!("{}", error.into_message());
+      exit(65);
+    }
+  }
+}
+
+/// Compile the script along with all modules it imports into a bundle
+/// (`<path>` with the extension `.loxb` by default), with debug info unless it's stripped.
+///
+/// Args: `[-o <out>] [--strip|--debug-info]`
+fn run_bundle(vm: &mut VM, path: &str, args: &[String]) {
+  let default_out = std::path::Path::new(path).with_extension(bundle::BUNDLE_EXTENSION);
+  let (bytes, out) = compile_bundle(vm, path, args, default_out);
+  if std::fs::write(&out, bytes).is_err() {
+    eprintln!("Failed to write file `{}`.", out.display());
+    exit(74);
+  }
+}
+
+/// Compile the script into a bundle (see [`run_bundle`]), then write a self-contained executable
+/// running it (`<path>` without its extension by default).
+///
+/// Args: `[-o <out>] [--strip|--debug-info]`
+fn run_build(vm: &mut VM, path: &str, args: &[String]) {
+  let default_out = std::path::Path::new(path).with_extension(std::env::consts::EXE_EXTENSION);
+  let (bytes, out) = compile_bundle(vm, path, args, default_out);
+  let Ok(runtime) = std::env::current_exe() else {
+    eprintln!("Failed to find the executable of rlox.");
+    exit(74);
+  };
+  if bundle::build_executable(&runtime, &bytes, &out).is_err() {
+    eprintln!("Failed to write file `{}`.", out.display());
+    exit(74);
+  }
+}
+
+/// Compile the script into a bundle, with the path to write it into.
+///
+/// Args: `[-o <out>] [--strip|--debug-info]`
+fn compile_bundle(
+  vm: &mut VM,
+  path: &str,
+  args: &[String],
+  default_out: std::path::PathBuf,
+) -> (Vec<u8>, std::path::PathBuf) {
+  let mut out = default_out;
+  let mut options = BundleOptions::init();
+  let mut args = args.iter();
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "-o" | "--output" => match args.next() {
+        Some(path) => out = path.into(),
+        None => usage(),
+      },
+      "--strip" => options.debug_info = false,
+      "--debug-info" => options.debug_info = true,
+      _ => usage(),
+    }
+  }
+  if out == std::path::Path::new(path) {
+    eprintln!("Output `{}` would replace the script.", out.display());
+    exit(64);
+  }
+  let result = vm.bundle_file(path.to_owned(), options);
+  for warning in vm.warnings() {
+    eprintln!("{}", warning);
+  }
+  match result {
+    Ok(bytes) => (bytes, out),
+    Err(error) => {
+      eprintln!("{}", error.into_message());
+      exit(65);
     }
   }
 }
 
-/// Run the given file.
-fn run_file(vm: &mut VM, path: String) -> Result<(), InterpretError> {
-  vm.interpret_file(path)
+/// Run the bundle embedded into the executable (see [`bundle::embedded_bundle`]),
+/// with the capabilities of scripts run by the command line.
+fn run_embedded(bytes: &[u8]) {
+  let mut vm = VM::init();
+  vm.set_capabilities(Capabilities {
+    sleep: true,
+    files: true,
+    ..Capabilities::none()
+  });
+  let name = utils::args().into_iter().next().unwrap_or_default();
+  match vm.interpret_bundle(bytes, &name) {
+    Ok(_) => {}
+    Err(InterpretError::CompileError(message)) => {
+      eprintln!("{}", message);
+      exit(65);
+    }
+    Err(error) => {
+      eprintln!("{}", error.into_message());
+      report_crash(&vm, None);
+      exit(70);
+    }
+  }
+  vm.free();
+}
+
+/// Run all `.lox` files of the directory in batch mode, then exit with `1` if any failed.
+///
+/// Args: `[--jobs N]`
+fn run_batch(dir: &str, args: &[String], mut options: BatchOptions) {
+  let mut args = args.iter();
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "--jobs" | "-j" => options.jobs = parse_count(args.next()),
+      _ => usage(),
+    }
+  }
+  let report = match batch::run_dir(dir.as_ref(), options) {
+    Ok(report) => report,
+    Err(_) => {
+      eprintln!("Failed to read directory `{}`.", dir);
+      exit(74);
+    }
+  };
+  batch::print_report(&report);
+  if report.failed() > 0 {
+    exit(1);
+  }
+}
+
+/// Print a Markdown summary of documented globals of the given file.
+fn run_doc(args: &[String]) {
+  let [path] = args else { usage() };
+  let Ok(src) = std::fs::read_to_string(path) else {
+    eprintln!("Failed to read file `{}`.", path);
+    exit(74);
+  };
+  match doc::extract(&src) {
+    Ok(items) => print!("{}", doc::render_markdown(path, &items)),
+    Err(error) => {
+      eprintln!("{}", error.into_message());
+      exit(65);
+    }
+  }
+}
+
+fn parse_count(arg: Option<&String>) -> usize {
+  match arg.map(|arg| arg.parse::<usize>()) {
+    Some(Ok(count)) => count,
+    _ => usage(),
+  }
 }
@@ -6,7 +6,10 @@
 //!
 //! - transformation between `rust_defined_types` and `obj_types`
 
-use crate::object::{Obj, ObjString, ObjType};
+use crate::{
+  object::{Obj, ObjString, ObjType},
+  prelude::*,
+};
 
 impl From<String> for ObjString {
   fn from(rust_string: String) -> Self {
@@ -0,0 +1,379 @@
+//! # Native
+//!
+//! This module contains all native functions (implemented in rust),
+//! which are defined as globals when the virtual machine is initialized.
+//!
+//! Nondeterministic natives (`clock`, `now_unix`, `random`) could be replaced
+//! by deterministic ones (see [`deterministic`]).
+//!
+//! Natives of the host (`clock`, `random`, [`time`], [`process`]) and deterministic mode
+//! are only defined with the `std` feature.
+//!
+//! Values could be introspected by natives of [`inspect`] (e.g. `type_of`),
+//! and strings parsed to numbers by [`number`] (`parse_num`), regardless of the host locale.
+//!
+//! Natives of [`list`] (e.g. `map`) call back functions passed to them,
+//! so they're [`CallbackFn`]s, which take the virtual machine as well.
+//!
+//! Errors (see [`error`]) are instances of the `Error` class, which is defined as a global as well.
+//!
+//! Natives with side effects (beyond computing) are gated by [`Capabilities`],
+//! which are granted by the embedder (none by default), or bundled with limits by named profiles
+//! (see [`sandbox`]).
+//!
+//! With the `regex` feature, regular expression natives are defined as well (see [`regex`]),
+//! and with the `http` feature, the (gated) `fetch` native (see [`http`]).
+
+pub mod deep;
+#[cfg(feature = "std")]
+pub mod deterministic;
+pub mod error;
+#[cfg(feature = "http")]
+pub mod http;
+pub mod inspect;
+pub mod list;
+pub mod number;
+#[cfg(feature = "std")]
+pub mod process;
+#[cfg(feature = "regex")]
+pub mod regex;
+pub mod sandbox;
+#[cfg(feature = "std")]
+pub mod time;
+
+#[cfg(feature = "std")]
+use std::{
+  cell::Cell,
+  time::{SystemTime, UNIX_EPOCH},
+};
+
+use core::fmt::Display;
+
+use crate::{
+  object::{CallbackFn, NativeFn, ObjNative, ObjString, ObjTrait},
+  prelude::*,
+  value::{display, Value},
+  vm::{InterpretError, VM},
+};
+
+/// All native functions, as `(name, arity, function)`.
+pub const NATIVES: &[(&str, usize, NativeFn)] = &[
+  #[cfg(feature = "std")]
+  ("clock", 0, clock),
+  #[cfg(feature = "std")]
+  ("random", 0, random),
+  ("str", 1, str),
+  ("byteAt", 2, byte_at),
+  ("codePointAt", 2, code_point_at),
+  ("fromCodePoint", 1, from_code_point),
+];
+
+/// All gated native functions, as `(capability, name, arity, function)`.
+pub const GATED_NATIVES: &[(Capability, &str, usize, NativeFn)] = &[
+  #[cfg(feature = "std")]
+  (Capability::Sleep, "sleep", 1, time::sleep),
+  #[cfg(feature = "std")]
+  (Capability::Exec, "exec", 2, process::exec),
+];
+
+/// ## Capability
+///
+/// A side effect (beyond computing) which gated natives (or imports) could have.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capability {
+  /// Block the running thread (e.g. `sleep`).
+  Sleep,
+  /// Run other programs (e.g. `exec`).
+  Exec,
+  /// Access the network (e.g. `fetch`).
+  Network,
+  /// Read files of the host (e.g. modules imported from files by `import`).
+  Files,
+}
+
+impl Display for Capability {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      Capability::Sleep => write!(f, "sleep"),
+      Capability::Exec => write!(f, "exec"),
+      Capability::Network => write!(f, "network"),
+      Capability::Files => write!(f, "files"),
+    }
+  }
+}
+
+/// ## Capabilities
+///
+/// The sandbox policy: which capabilities are granted to scripts.
+///
+/// Gated natives are always defined, but calling one without its capability is a runtime error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Capabilities {
+  pub sleep: bool,
+  pub exec: bool,
+  pub network: bool,
+  pub files: bool,
+}
+
+impl Capabilities {
+  /// Grant nothing (the default of embedders).
+  pub fn none() -> Self {
+    Self::default()
+  }
+
+  /// Grant everything.
+  pub fn all() -> Self {
+    Self {
+      sleep: true,
+      exec: true,
+      network: true,
+      files: true,
+    }
+  }
+
+  /// If the capability is granted.
+  pub fn allows(&self, capability: Capability) -> bool {
+    match capability {
+      Capability::Sleep => self.sleep,
+      Capability::Exec => self.exec,
+      Capability::Network => self.network,
+      Capability::Files => self.files,
+    }
+  }
+}
+
+impl VM {
+  /// Define a native function as a global variable.
+  pub fn define_native(&mut self, name: &str, arity: usize, function: NativeFn) {
+    let native = ObjNative::new(name, arity, function);
+    self.globals.set(
+      ObjString::from(name.to_owned()),
+      Value::obj_val(native.cast_to_obj_ptr()),
+    );
+  }
+
+  /// Define a native function calling back into the virtual machine as a global variable,
+  /// which takes `required_arity` to `arity` arguments (missing ones are `nil`).
+  pub fn define_callback_native(
+    &mut self,
+    name: &str,
+    required_arity: usize,
+    arity: usize,
+    function: CallbackFn,
+  ) {
+    let native = ObjNative::callback(name, required_arity, arity, function);
+    self.globals.set(
+      ObjString::from(name.to_owned()),
+      Value::obj_val(native.cast_to_obj_ptr()),
+    );
+  }
+
+  /// Grant capabilities to scripts (see [`Capabilities`]), which takes effect immediately.
+  pub fn set_capabilities(&mut self, capabilities: Capabilities) {
+    self.capabilities = capabilities;
+  }
+
+  /// Capabilities granted to scripts.
+  pub fn capabilities(&self) -> Capabilities {
+    self.capabilities
+  }
+
+  /// Define all built-in native functions.
+  pub(crate) fn define_natives(&mut self) {
+    #[cfg(feature = "std")]
+    let time_natives = time::NATIVES;
+    #[cfg(not(feature = "std"))]
+    let time_natives = &[];
+    let all = NATIVES
+      .iter()
+      .chain(time_natives)
+      .chain(deep::NATIVES)
+      .chain(inspect::NATIVES)
+      .chain(list::NATIVES)
+      .chain(number::NATIVES)
+      .chain(error::NATIVES);
+    // grown once, rather than rehashed along the way
+    self
+      .globals
+      .reserve(all.clone().count() + list::CALLBACK_NATIVES.len() + GATED_NATIVES.len() + 1);
+    for &(name, arity, function) in all {
+      self.define_native(name, arity, function);
+    }
+    for &(name, required_arity, arity, function) in list::CALLBACK_NATIVES {
+      self.define_callback_native(name, required_arity, arity, function);
+    }
+    self
+      .globals
+      .set(ObjString::from("Error".to_owned()), error::error_class());
+    for &(capability, name, arity, function) in GATED_NATIVES {
+      self.define_gated_native(capability, name, arity, function);
+    }
+    #[cfg(feature = "regex")]
+    for &(name, arity, function) in regex::NATIVES {
+      self.define_native(name, arity, function);
+    }
+    #[cfg(feature = "http")]
+    for &(capability, name, arity, function) in http::GATED_NATIVES {
+      self.define_gated_native(capability, name, arity, function);
+    }
+    #[cfg(feature = "std")]
+    self.define_deterministic_natives();
+    self.undefine_host_natives();
+  }
+
+  /// Define a native function (which needs the capability to be called) as a global variable.
+  fn define_gated_native(
+    &mut self,
+    capability: Capability,
+    name: &str,
+    arity: usize,
+    function: NativeFn,
+  ) {
+    let native = ObjNative::gated(name, arity, function, capability);
+    self.globals.set(
+      ObjString::from(name.to_owned()),
+      Value::obj_val(native.cast_to_obj_ptr()),
+    );
+  }
+}
+
+/// Seconds elapsed since the unix epoch.
+#[cfg(feature = "std")]
+fn clock(_: &[Value]) -> Result<Value, InterpretError> {
+  let now = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map_err(|_| InterpretError::RuntimeError("System time is before unix epoch.".into()))?;
+  Ok(Value::number_val(now.as_secs_f64()))
+}
+
+/// `str(v)`: the plain string of the value (see [`display::plain`]).
+fn str(args: &[Value]) -> Result<Value, InterpretError> {
+  Ok(string_val(display::plain(&args[0])))
+}
+
+#[cfg(feature = "std")]
+thread_local! {
+  /// State of the generator of `random` (seeded by the system time on first use).
+  static RANDOM_STATE: Cell<u64> = Cell::new(
+    SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map_or(0, |now| now.as_nanos() as u64),
+  );
+}
+
+/// `random()`: a pseudo-random number in `[0, 1)`.
+#[cfg(feature = "std")]
+fn random(_: &[Value]) -> Result<Value, InterpretError> {
+  Ok(RANDOM_STATE.with(random_from))
+}
+
+/// Next pseudo-random number in `[0, 1)` of the generator (`splitmix64`).
+#[cfg(feature = "std")]
+fn random_from(state: &Cell<u64>) -> Value {
+  let next = state.get().wrapping_add(0x9E3779B97F4A7C15);
+  state.set(next);
+  let mut z = next;
+  z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+  z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+  z ^= z >> 31;
+  Value::number_val((z >> 11) as f64 / (1_u64 << 53) as f64)
+}
+
+/// `byteAt(s, i)`: the `i`-th byte (from `0`) of the string, or `nil` if it's out of range.
+fn byte_at(args: &[Value]) -> Result<Value, InterpretError> {
+  let s = string_arg("byteAt", &args[0])?;
+  let index = index_arg("byteAt", &args[1])?;
+  Ok(match index.and_then(|i| s.as_bytes().get(i)) {
+    Some(&byte) => Value::number_val(byte as f64),
+    None => Value::nil_val(),
+  })
+}
+
+/// `codePointAt(s, i)`: code point of the `i`-th character (from `0`) of the string,
+/// or `nil` if it's out of range.
+fn code_point_at(args: &[Value]) -> Result<Value, InterpretError> {
+  let s = string_arg("codePointAt", &args[0])?;
+  let index = index_arg("codePointAt", &args[1])?;
+  Ok(match index.and_then(|i| s.chars().nth(i)) {
+    Some(c) => Value::number_val(c as u32 as f64),
+    None => Value::nil_val(),
+  })
+}
+
+/// `fromCodePoint(n)`: the one-character string of the code point.
+fn from_code_point(args: &[Value]) -> Result<Value, InterpretError> {
+  let c = Some(&args[0])
+    .filter(|v| v.is_number() && v.as_number() % 1.0 == 0.0)
+    .and_then(|v| u32::try_from(v.as_number() as i64).ok())
+    .and_then(char::from_u32)
+    .ok_or_else(|| {
+      InterpretError::RuntimeError("`fromCodePoint` expected a valid code point.".into())
+    })?;
+  Ok(string_val(c.to_string()))
+}
+
+/// A string value.
+fn string_val(s: String) -> Value {
+  Value::obj_val(ObjString::from(s).cast_to_obj_ptr())
+}
+
+/// The class of records of a name, shared by all of them (and all virtual machines).
+#[cfg(any(feature = "regex", feature = "http"))]
+struct RecordClass(&'static str, core::ptr::NonNull<crate::object::ObjClass>);
+
+// SAFETY: like the `Error` class, the class is never changed (nor freed) once created.
+#[cfg(any(feature = "regex", feature = "http"))]
+unsafe impl Send for RecordClass {}
+
+/// Classes of records created so far (see [`record`]).
+#[cfg(any(feature = "regex", feature = "http"))]
+static RECORD_CLASSES: std::sync::Mutex<Vec<RecordClass>> = std::sync::Mutex::new(Vec::new());
+
+/// An instance of the class of the name with the fields,
+/// the class is created once, then shared by all records of the name.
+///
+/// There's no map in the language, so natives return structured results as records.
+#[cfg(any(feature = "regex", feature = "http"))]
+fn record(class_name: &'static str, fields: impl IntoIterator<Item = (String, Value)>) -> Value {
+  use crate::object::{ObjClass, ObjInstance};
+
+  let mut classes = RECORD_CLASSES.lock().unwrap();
+  let class = match classes.iter().find(|class| class.0 == class_name) {
+    Some(class) => class.1,
+    None => {
+      let class = ObjClass::new(ObjString::from(class_name.to_owned()));
+      let class = class.cast_to_obj_ptr().cast();
+      classes.push(RecordClass(class_name, class));
+      class
+    }
+  };
+  drop(classes);
+  let mut instance = ObjInstance::new(class);
+  for (name, value) in fields {
+    instance.fields.set(ObjString::from(name), value);
+  }
+  Value::obj_val(instance.cast_to_obj_ptr())
+}
+
+/// The string argument of the native function.
+fn string_arg<'a>(name: &str, arg: &'a Value) -> Result<&'a str, InterpretError> {
+  if !arg.is_string() {
+    return Err(InterpretError::RuntimeError(format!(
+      "`{}` expected a string.",
+      name
+    )));
+  }
+  arg.as_rust_string()
+}
+
+/// The index argument of the native function, `None` if it's negative.
+fn index_arg(name: &str, arg: &Value) -> Result<Option<usize>, InterpretError> {
+  if !arg.is_number() || arg.as_number() % 1.0 != 0.0 {
+    return Err(InterpretError::RuntimeError(format!(
+      "`{}` expected an integer index.",
+      name
+    )));
+  }
+  let index = arg.as_number();
+  Ok((index >= 0.0).then_some(index as usize))
+}
@@ -0,0 +1,105 @@
+//! # Deep
+//!
+//! Submodule of `native`, which contains structural equality and copying natives.
+//!
+//! Instances and lists are the aggregates (there's no map yet),
+//! so their fields and items are compared/copied recursively, and anything else is compared by `==`
+//! (and shared by copies, e.g. strings, functions and classes).
+//!
+//! Both handle cycles (e.g. `a.next = a`): a pair of aggregates already being compared
+//! is assumed equal, and an aggregate already copied is reused by the copy.
+
+use core::ptr::NonNull;
+use hashbrown::{HashMap, HashSet};
+
+use crate::{
+  object::{NativeFn, Obj, ObjInstance, ObjList, ObjTrait},
+  prelude::*,
+  value::Value,
+  vm::InterpretError,
+};
+
+/// All structural natives, as `(name, arity, function)`.
+pub const NATIVES: &[(&str, usize, NativeFn)] =
+  &[("deep_equals", 2, deep_equals), ("clone", 1, clone)];
+
+/// `deep_equals(a, b)`: if the values are structurally equal, i.e. instances of the same class
+/// with deeply equal fields, or lists of deeply equal items.
+fn deep_equals(args: &[Value]) -> Result<Value, InterpretError> {
+  Ok(Value::bool_val(equals(
+    args[0],
+    args[1],
+    &mut HashSet::new(),
+  )))
+}
+
+/// `clone(v)`: a deep copy of the value (preserving sharing and cycles between aggregates).
+fn clone(args: &[Value]) -> Result<Value, InterpretError> {
+  Ok(copy(args[0], &mut HashMap::new()))
+}
+
+type Pair = (NonNull<Obj>, NonNull<Obj>);
+
+fn equals(a: Value, b: Value, assumed: &mut HashSet<Pair>) -> bool {
+  let aggregates = (a.is_instance() && b.is_instance()) || (a.is_list() && b.is_list());
+  if !aggregates {
+    return a == b;
+  }
+  let (lhs, rhs) = (a.as_obj(), b.as_obj());
+  if lhs == rhs || !assumed.insert((lhs, rhs)) {
+    return true;
+  }
+  if let (Ok(lhs), Ok(rhs)) = (a.as_list(), b.as_list()) {
+    let (lhs, rhs) = unsafe { (lhs.as_ref(), rhs.as_ref()) };
+    return lhs.items.len() == rhs.items.len()
+      && lhs
+        .items
+        .iter()
+        .zip(&rhs.items)
+        .all(|(lhs, rhs)| equals(*lhs, *rhs, assumed));
+  }
+  let (lhs, rhs) = unsafe {
+    (
+      lhs.cast::<ObjInstance>().as_ref(),
+      rhs.cast::<ObjInstance>().as_ref(),
+    )
+  };
+  lhs.class == rhs.class
+    && lhs.fields.len() == rhs.fields.len()
+    && lhs.fields.iter().all(|(name, value)| {
+      rhs
+        .fields
+        .get(name)
+        .is_some_and(|other| equals(*value, *other, assumed))
+    })
+}
+
+fn copy(value: Value, copies: &mut HashMap<NonNull<Obj>, NonNull<Obj>>) -> Value {
+  if !value.is_instance() && !value.is_list() {
+    return value;
+  }
+  let original = value.as_obj();
+  if let Some(copied) = copies.get(&original) {
+    return Value::obj_val(*copied);
+  }
+  // register the copy before its fields (or items), so cycles end up at it
+  if let Ok(original_list) = value.as_list() {
+    let mut copied: NonNull<ObjList> = ObjList::new(Vec::new()).cast_to_obj_ptr().cast();
+    copies.insert(original, copied.cast());
+    for item in unsafe { original_list.as_ref() }.items.iter() {
+      let item = copy(*item, copies);
+      unsafe { copied.as_mut() }.items.push(item);
+    }
+    return Value::obj_val(copied.cast());
+  }
+  let original_ref = unsafe { original.cast::<ObjInstance>().as_ref() };
+  let mut copied: NonNull<ObjInstance> = ObjInstance::new(original_ref.class)
+    .cast_to_obj_ptr()
+    .cast();
+  copies.insert(original, copied.cast());
+  for (name, field) in original_ref.fields.iter() {
+    let field = copy(*field, copies);
+    unsafe { copied.as_mut() }.fields.set(name.clone(), field);
+  }
+  Value::obj_val(copied.cast())
+}
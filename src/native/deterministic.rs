@@ -0,0 +1,102 @@
+//! # Deterministic
+//!
+//! Submodule of `native`, which contains the deterministic replacements of
+//! nondeterministic natives (see [`VM::set_deterministic`]).
+//!
+//! - `clock` and `now_unix` read a virtual clock, which starts at [`EPOCH`]
+//!   and advances `1ms` per read
+//! - `sleep` advances the virtual clock, instead of blocking
+//! - `random` is a generator seeded by the given seed
+//!
+//! The state is kept per thread, and reset each time the mode is turned on.
+
+use std::cell::Cell;
+
+use crate::{
+  object::NativeFn,
+  value::Value,
+  vm::{InterpretError, VM},
+};
+
+use super::{random_from, Capability};
+
+/// Virtual seconds since the unix epoch when the mode is turned on (`2000-01-01T00:00:00Z`).
+pub const EPOCH: u64 = 946684800;
+
+/// Deterministic natives, as `(name, arity, function)`.
+const NATIVES: &[(&str, usize, NativeFn)] = &[
+  ("clock", 0, clock),
+  ("now_unix", 0, now_unix),
+  ("random", 0, random),
+];
+
+/// Deterministic gated natives, as `(capability, name, arity, function)`.
+const GATED_NATIVES: &[(Capability, &str, usize, NativeFn)] =
+  &[(Capability::Sleep, "sleep", 1, sleep)];
+
+thread_local! {
+  /// Milliseconds since the unix epoch, of the virtual clock.
+  static NOW_MS: Cell<u64> = const { Cell::new(EPOCH * 1000) };
+  /// State of the seeded generator.
+  static RANDOM_STATE: Cell<u64> = const { Cell::new(0) };
+}
+
+impl VM {
+  /// Deterministic mode: replace nondeterministic natives by deterministic ones,
+  /// so repeated runs of a script produce the same output (e.g. for golden tests).
+  ///
+  /// `Some(seed)` turns it on (resetting the virtual clock and seeding `random`),
+  /// and `None` turns it off (restoring the ordinary natives).
+  pub fn set_deterministic(&mut self, seed: Option<u64>) {
+    self.deterministic = seed;
+    self.define_natives();
+  }
+
+  /// The seed of deterministic mode, if it's on.
+  pub fn deterministic(&self) -> Option<u64> {
+    self.deterministic
+  }
+
+  /// Replace nondeterministic natives, if deterministic mode is on.
+  pub(super) fn define_deterministic_natives(&mut self) {
+    let Some(seed) = self.deterministic else {
+      return;
+    };
+    NOW_MS.with(|now| now.set(EPOCH * 1000));
+    RANDOM_STATE.with(|state| state.set(seed));
+    for &(name, arity, function) in NATIVES {
+      self.define_native(name, arity, function);
+    }
+    for &(capability, name, arity, function) in GATED_NATIVES {
+      self.define_gated_native(capability, name, arity, function);
+    }
+  }
+}
+
+/// Read the virtual clock (in milliseconds), then advance it.
+fn tick() -> u64 {
+  NOW_MS.with(|now| {
+    let ms = now.get();
+    now.set(ms.saturating_add(1));
+    ms
+  })
+}
+
+fn clock(_: &[Value]) -> Result<Value, InterpretError> {
+  Ok(Value::number_val(tick() as f64 / 1000.0))
+}
+
+fn now_unix(_: &[Value]) -> Result<Value, InterpretError> {
+  Ok(Value::number_val((tick() / 1000) as f64))
+}
+
+fn random(_: &[Value]) -> Result<Value, InterpretError> {
+  Ok(RANDOM_STATE.with(random_from))
+}
+
+fn sleep(args: &[Value]) -> Result<Value, InterpretError> {
+  let ms = super::time::sleep_duration(&args[0])?;
+  let ms = u64::try_from(ms.as_millis()).map_err(|_| super::time::too_long_sleep())?;
+  NOW_MS.with(|now| now.set(now.get().saturating_add(ms)));
+  Ok(Value::nil_val())
+}
@@ -0,0 +1,121 @@
+//! # Error
+//!
+//! Submodule of `native`, which contains the `Error` class and the `error` native.
+//!
+//! An error is an instance of `Error`, with the fields:
+//!
+//! - `kind`: what went wrong, e.g. `TypeError` (`Error` for ones created by `error(message)`)
+//! - `message`: the message of the error
+//! - `cause`: the error which caused it (`nil` by default, could be assigned)
+//! - `stack`: where it's thrown, as lines of `[location] in name` (the innermost call first)
+//!
+//! Runtime errors inside a `try` block are thrown as errors, so scripts could
+//! check `e.kind` (or `e is Error`) instead of matching messages.
+//! Faults (see [`InterpretError::Fault`]) are never thrown, they always abort the script.
+
+use core::ptr::NonNull;
+
+use crate::{
+  object::{NativeFn, ObjClass, ObjInstance, ObjString, ObjTrait},
+  prelude::*,
+  utils::Lazy,
+  value::{display, Value},
+  vm::InterpretError,
+};
+
+use super::{string_arg, string_val};
+
+/// All error natives, as `(name, arity, function)`.
+pub const NATIVES: &[(&str, usize, NativeFn)] = &[("error", 1, error)];
+
+/// Pointer of the `Error` class.
+struct ErrorClass(NonNull<ObjClass>);
+
+// SAFETY: the class is never changed (nor freed) once created, so it could be read from any thread.
+unsafe impl Send for ErrorClass {}
+unsafe impl Sync for ErrorClass {}
+
+/// The `Error` class, shared by all virtual machines (so errors could be passed between them).
+static ERROR_CLASS: Lazy<ErrorClass> = Lazy::new(|| {
+  let class = ObjClass::new(ObjString::from("Error".to_owned()));
+  ErrorClass(class.cast_to_obj_ptr().cast())
+});
+
+/// The `Error` class.
+pub fn error_class() -> Value {
+  Value::obj_val(ERROR_CLASS.0.cast())
+}
+
+/// A new error, which isn't thrown yet (so its `stack` is `nil`).
+pub fn new_error(kind: &str, message: String, cause: Value) -> Value {
+  let mut instance = ObjInstance::new(ERROR_CLASS.0);
+  let fields = [
+    ("kind", string_val(kind.to_owned())),
+    ("message", string_val(message)),
+    ("cause", cause),
+    ("stack", Value::nil_val()),
+  ];
+  for (name, value) in fields {
+    instance.fields.set(ObjString::from(name.to_owned()), value);
+  }
+  Value::obj_val(instance.cast_to_obj_ptr())
+}
+
+/// The instance, if the value is an error.
+pub fn as_error(value: Value) -> Option<NonNull<ObjInstance>> {
+  let instance = value.as_instance().ok()?;
+  let is_error = unsafe { instance.as_ref() }.class == ERROR_CLASS.0;
+  is_error.then_some(instance)
+}
+
+/// Describe the thrown value, e.g. `TypeError: Operands must be numbers.`.
+pub fn describe(value: Value) -> String {
+  let Some(instance) = as_error(value) else {
+    return display::repr(&value);
+  };
+  let instance = unsafe { instance.as_ref() };
+  let field = |name: &str| {
+    instance
+      .fields
+      .get(&ObjString::from(name.to_owned()))
+      .map_or_else(String::new, display::plain)
+  };
+  format!("{}: {}", field("kind"), field("message"))
+}
+
+/// ## ErrorKind
+///
+/// Kinds of runtime errors raised by the virtual machine (see [`InterpretError::Raised`]),
+/// as `kind` of errors thrown to `try` blocks. Other runtime errors are of kind `RuntimeError`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+  /// An undefined variable.
+  Name,
+  /// An undefined property or field.
+  Property,
+  /// A call with a wrong count of arguments.
+  Arity,
+  /// A native function called without the capability it needs.
+  Capability,
+  /// An operand (or a value) of a wrong type.
+  Type,
+}
+
+impl ErrorKind {
+  /// The name of the kind, e.g. `TypeError`.
+  pub fn name(&self) -> &'static str {
+    match self {
+      ErrorKind::Name => "NameError",
+      ErrorKind::Property => "PropertyError",
+      ErrorKind::Arity => "ArityError",
+      ErrorKind::Capability => "CapabilityError",
+      ErrorKind::Type => "TypeError",
+    }
+  }
+}
+
+/// `error(message)`: a new error of kind `Error`.
+fn error(args: &[Value]) -> Result<Value, InterpretError> {
+  let message = string_arg("error", &args[0])?;
+  Ok(new_error("Error", message.to_owned(), Value::nil_val()))
+}
@@ -0,0 +1,44 @@
+//! # Http
+//!
+//! Submodule of `native`, which contains the HTTP natives (only with the `http` feature).
+//!
+//! `fetch` is gated by [`Capability::Network`](super::Capability::Network) like any other
+//! gated native: it's always defined (with the feature), but calling it is a runtime error
+//! unless the embedder grants the capability (the command line does by `--allow-net`).
+//! Requests are blocking, so they also block the virtual machine.
+
+use crate::{object::NativeFn, value::Value, vm::InterpretError};
+
+use super::{record, string_arg, string_val, Capability};
+
+/// All HTTP natives, as `(capability, name, arity, function)`.
+pub const GATED_NATIVES: &[(Capability, &str, usize, NativeFn)] =
+  &[(Capability::Network, "fetch", 1, fetch)];
+
+/// `fetch(url)`: `GET` the url, then return a `Response` record of `status` and `body`.
+///
+/// Error statuses (`4xx`/`5xx`) are responses as well,
+/// only failures of the transport (e.g. an unreachable host) are runtime errors.
+fn fetch(args: &[Value]) -> Result<Value, InterpretError> {
+  let url = string_arg("fetch", &args[0])?;
+  let response = match ureq::get(url).call() {
+    Ok(response) | Err(ureq::Error::Status(_, response)) => response,
+    Err(error) => {
+      return Err(InterpretError::RuntimeError(format!(
+        "`fetch` failed to get `{}`: {}",
+        url, error
+      )))
+    }
+  };
+  let status = Value::number_val(response.status() as f64);
+  let body = response.into_string().map_err(|error| {
+    InterpretError::RuntimeError(format!("`fetch` failed to read `{}`: {}", url, error))
+  })?;
+  Ok(record(
+    "Response",
+    [
+      ("status".to_owned(), status),
+      ("body".to_owned(), string_val(body)),
+    ],
+  ))
+}
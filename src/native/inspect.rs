@@ -0,0 +1,138 @@
+//! # Inspect
+//!
+//! Submodule of `native`, which contains introspection natives (`type_of`, `pretty`),
+//! and the inspection of a variable shown by `:inspect` of the REPL.
+
+use core::fmt::Write;
+
+use crate::{
+  object::{NativeFn, ObjType},
+  prelude::*,
+  table::Table,
+  value::{display, Value},
+  vm::InterpretError,
+};
+
+use super::string_val;
+
+/// All introspection natives, as `(name, arity, function)`.
+pub const NATIVES: &[(&str, usize, NativeFn)] = &[("type_of", 1, type_of), ("pretty", 2, pretty)];
+
+/// `type_of(v)`: name of the type of the value (see [`type_name`]).
+fn type_of(args: &[Value]) -> Result<Value, InterpretError> {
+  Ok(string_val(type_name(&args[0]).to_owned()))
+}
+
+/// `pretty(v, max_depth)`: the value with fields of instances (and items of lists)
+/// on indented lines, down to the depth (see [`display::pretty`]).
+fn pretty(args: &[Value]) -> Result<Value, InterpretError> {
+  let depth = &args[1];
+  if !depth.is_number() || depth.as_number() % 1.0 != 0.0 || depth.as_number() < 0.0 {
+    return Err(InterpretError::RuntimeError(
+      "`pretty` expected a non-negative integer depth.".into(),
+    ));
+  }
+  Ok(string_val(display::pretty(
+    &args[0],
+    depth.as_number() as usize,
+  )))
+}
+
+/// Name of the type of the value, one of `nil`, `bool`, `number`, `string`, `function`,
+/// `native`, `class`, `instance`, `bound method` and `list`.
+pub fn type_name(value: &Value) -> &'static str {
+  if value.is_nil() {
+    return "nil";
+  } else if value.is_bool() {
+    return "bool";
+  } else if value.is_number() {
+    return "number";
+  }
+  match value.obj_type() {
+    Ok(ObjType::String) => "string",
+    Ok(ObjType::Function | ObjType::Closure) => "function",
+    Ok(ObjType::Native) => "native",
+    Ok(ObjType::Class) => "class",
+    Ok(ObjType::Instance) => "instance",
+    Ok(ObjType::BoundMethod) => "bound method",
+    Ok(ObjType::Upvalue) => "upvalue",
+    Ok(ObjType::List) => "list",
+    Ok(ObjType::Enum) => "enum",
+    Ok(ObjType::EnumMember) => "enum member",
+    Err(_) => "unknown",
+  }
+}
+
+/// Inspection of the variable: its value and type, then details of objects
+/// (length of strings and lists, arity of functions, members of classes and enums, fields of instances).
+pub fn inspect(name: &str, value: &Value) -> String {
+  let mut out = String::new();
+  let _ = writeln!(out, "{} = {}", name, display::repr(value));
+  let _ = writeln!(out, "  type: {}", type_name(value));
+  if let Ok(s) = value.as_rust_string() {
+    let _ = writeln!(out, "  length: {} bytes", s.len());
+  } else if let Ok(list) = value.as_list() {
+    let _ = writeln!(
+      out,
+      "  length: {} items",
+      unsafe { list.as_ref() }.items.len()
+    );
+  } else if let Ok((function, closure)) = value.as_callable() {
+    let function = unsafe { function.as_ref() };
+    let _ = write!(out, "  arity: {}", function.arity);
+    if function.required_arity != function.arity {
+      let _ = write!(out, " ({} required)", function.required_arity);
+    }
+    out.push('\n');
+    if let Some(closure) = closure {
+      let _ = writeln!(
+        out,
+        "  captured: {}",
+        unsafe { closure.as_ref() }.upvalues.len()
+      );
+    }
+  } else if let Ok(native) = value.as_native() {
+    let _ = writeln!(out, "  arity: {}", unsafe { native.as_ref() }.arity_range());
+  } else if let Ok(class) = value.as_class() {
+    let class = unsafe { class.as_ref() };
+    if let Some(superclass) = class.superclass {
+      let _ = writeln!(out, "  superclass: {}", unsafe { superclass.as_ref() }.name);
+    }
+    if !class.traits.is_empty() {
+      let _ = writeln!(out, "  traits: {}", class.traits.join(", "));
+    }
+    members(&mut out, "methods", &class.methods);
+    members(&mut out, "getters", &class.getters);
+    members(&mut out, "setters", &class.setters);
+  } else if let Ok(instance) = value.as_instance() {
+    let instance = unsafe { instance.as_ref() };
+    let _ = writeln!(out, "  class: {}", instance.class().name);
+    let _ = writeln!(out, "  fields: {}", instance.fields.len());
+    for (name, value) in instance.fields.iter() {
+      let _ = writeln!(out, "    {} = {}", name, display::repr(value));
+    }
+  } else if let Ok(enum_) = value.as_enum() {
+    let names: Vec<_> = unsafe { enum_.as_ref() }
+      .members
+      .iter()
+      .map(|member| unsafe { member.as_ref() }.name.to_string())
+      .collect();
+    let _ = writeln!(out, "  members: {}", names.join(", "));
+  } else if let Ok(member) = value.as_enum_member() {
+    let _ = writeln!(out, "  ordinal: {}", unsafe { member.as_ref() }.ordinal);
+  } else if let Ok(bound) = value.as_bound_method() {
+    let bound = unsafe { bound.as_ref() };
+    let _ = writeln!(out, "  method: {}", display::repr(&bound.method));
+    let _ = writeln!(out, "  receiver: {}", display::repr(&bound.receiver));
+  }
+  out
+}
+
+/// Names of members of a class (if any), in order of declaration.
+fn members(out: &mut String, kind: &str, table: &Table) {
+  if table.is_empty() {
+    return;
+  }
+  let names: Vec<_> = table.iter().map(|(name, _)| name.to_string()).collect();
+  let _ = writeln!(out, "  {}: {}", kind, names.join(", "));
+}
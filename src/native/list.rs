@@ -0,0 +1,176 @@
+//! # List
+//!
+//! Submodule of `native`, which contains natives of lists: basic ones (`len`, which counts
+//! characters of strings too, and `push`),
+//! and higher-order ones (`sort`, `map`, `filter`, `reduce`), which call back functions
+//! passed to them (see [`VM::call_function`]).
+//!
+//! Higher-order natives never change the given list, they return a new one
+//! (or the reduced value). Items are taken before the first call back,
+//! so functions changing the list don't affect the ongoing call.
+
+use core::{cmp::Ordering, ptr::NonNull};
+
+use crate::{
+  object::{CallbackFn, NativeFn, ObjList, ObjTrait},
+  prelude::*,
+  value::Value,
+  vm::{InterpretError, VM},
+};
+
+/// All basic list natives, as `(name, arity, function)`.
+pub const NATIVES: &[(&str, usize, NativeFn)] = &[("len", 1, len), ("push", 2, push)];
+
+/// All higher-order list natives, as `(name, required arity, arity, function)`.
+pub const CALLBACK_NATIVES: &[(&str, usize, usize, CallbackFn)] = &[
+  ("sort", 1, 2, sort),
+  ("map", 2, 2, map),
+  ("filter", 2, 2, filter),
+  ("reduce", 3, 3, reduce),
+];
+
+/// `len(list)`: count of items of the list, or `len(s)`: count of characters of the string
+/// (which are what string indexes count, not bytes).
+fn len(args: &[Value]) -> Result<Value, InterpretError> {
+  let len = if let Ok(s) = args[0].as_rust_string() {
+    s.chars().count()
+  } else if let Ok(list) = args[0].as_list() {
+    unsafe { list.as_ref() }.items.len()
+  } else {
+    return Err(InterpretError::RuntimeError(
+      "`len` expected a list or a string.".into(),
+    ));
+  };
+  Ok(Value::number_val(len as f64))
+}
+
+/// `push(list, item)`: append the item to the list.
+fn push(args: &[Value]) -> Result<Value, InterpretError> {
+  let mut list = list_arg("push", &args[0])?;
+  unsafe { list.as_mut() }.items.push(args[1]);
+  Ok(Value::nil_val())
+}
+
+/// `sort(list, cmp?)`: a sorted copy of the list (the sort is stable).
+///
+/// Without `cmp`, items must be all numbers or all strings, in ascending order.
+/// Otherwise `cmp(a, b)` returns a negative number if `a` goes before `b`,
+/// a positive one if it goes after `b`, or `0` if their order doesn't matter.
+fn sort(vm: &mut VM, args: &[Value]) -> Result<Value, InterpretError> {
+  let mut items = items_arg("sort", &args[0])?;
+  let cmp = args[1];
+  if cmp.is_nil() {
+    let numbers = items.iter().all(Value::is_number);
+    if !numbers && !items.iter().all(Value::is_string) {
+      return Err(InterpretError::RuntimeError(
+        "`sort` expected numbers or strings, or a comparator.".into(),
+      ));
+    }
+    // a total order (`NaN` goes last), so it never fails
+    merge_sort(&mut items, |a, b| {
+      Ok(match numbers {
+        true => a.as_number().total_cmp(&b.as_number()),
+        false => a.as_rust_string()?.cmp(b.as_rust_string()?),
+      })
+    })?;
+  } else {
+    merge_sort(&mut items, |a, b| {
+      let order = vm.call_function(cmp, &[*a, *b])?;
+      if !order.is_number() {
+        return Err(InterpretError::RuntimeError(
+          "`sort` expected the comparator to return a number.".into(),
+        ));
+      }
+      Ok(
+        order
+          .as_number()
+          .partial_cmp(&0.0)
+          .unwrap_or(Ordering::Equal),
+      )
+    })?;
+  }
+  Ok(list_val(items))
+}
+
+/// `map(list, fn)`: a list of results of calling the function with each item.
+fn map(vm: &mut VM, args: &[Value]) -> Result<Value, InterpretError> {
+  let items = items_arg("map", &args[0])?;
+  let mapped = items
+    .iter()
+    .map(|item| vm.call_function(args[1], &[*item]))
+    .collect::<Result<Vec<_>, _>>()?;
+  Ok(list_val(mapped))
+}
+
+/// `filter(list, fn)`: a list of items for which the function returns a truthy value.
+fn filter(vm: &mut VM, args: &[Value]) -> Result<Value, InterpretError> {
+  let items = items_arg("filter", &args[0])?;
+  let mut kept = Vec::new();
+  for item in items {
+    if !vm.call_function(args[1], &[item])?.is_falsey() {
+      kept.push(item);
+    }
+  }
+  Ok(list_val(kept))
+}
+
+/// `reduce(list, fn, init)`: the result of calling the function with the accumulated value
+/// (`init` at first) and each item, e.g. `reduce([1, 2], add, 0)` is `add(add(0, 1), 2)`.
+fn reduce(vm: &mut VM, args: &[Value]) -> Result<Value, InterpretError> {
+  let items = items_arg("reduce", &args[0])?;
+  let mut accumulated = args[2];
+  for item in items {
+    accumulated = vm.call_function(args[1], &[accumulated, item])?;
+  }
+  Ok(accumulated)
+}
+
+/// Stable merge sort by the fallible comparison, which stops at its first error.
+///
+/// Unlike `slice::sort_by`, comparisons which aren't a total order (e.g. of a buggy comparator)
+/// only make the order unspecified.
+fn merge_sort<F>(items: &mut [Value], mut compare: F) -> Result<(), InterpretError>
+where
+  F: FnMut(&Value, &Value) -> Result<Ordering, InterpretError>,
+{
+  let mut buffer = items.to_vec();
+  let mut width = 1;
+  while width < items.len() {
+    for start in (0..items.len()).step_by(2 * width) {
+      let middle = (start + width).min(items.len());
+      let end = (start + 2 * width).min(items.len());
+      let (mut left, mut right) = (start, middle);
+      for slot in &mut buffer[start..end] {
+        let take_left =
+          right == end || left < middle && compare(&items[left], &items[right])?.is_le();
+        if take_left {
+          *slot = items[left];
+          left += 1;
+        } else {
+          *slot = items[right];
+          right += 1;
+        }
+      }
+    }
+    items.copy_from_slice(&buffer);
+    width *= 2;
+  }
+  Ok(())
+}
+
+/// The list argument of the native function.
+fn list_arg(name: &str, arg: &Value) -> Result<NonNull<ObjList>, InterpretError> {
+  arg
+    .as_list()
+    .map_err(|_| InterpretError::RuntimeError(format!("`{}` expected a list.", name)))
+}
+
+/// Items of the list argument of the native function (copied out of the list).
+pub(super) fn items_arg(name: &str, arg: &Value) -> Result<Vec<Value>, InterpretError> {
+  Ok(unsafe { list_arg(name, arg)?.as_ref() }.items.clone())
+}
+
+/// A list value of the items.
+pub(super) fn list_val(items: Vec<Value>) -> Value {
+  Value::obj_val(ObjList::new(items).cast_to_obj_ptr())
+}
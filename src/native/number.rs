@@ -0,0 +1,200 @@
+//! # Number
+//!
+//! Submodule of `native`, which contains conversions between numbers and strings:
+//! parsing (`parse_num`), and formatting with the given digits (`to_fixed`, `to_precision`)
+//! or radix (`to_hex`, `to_oct`, `to_bin`) instead of the default of `print` and `str()`.
+//!
+//! Neither number literals (lexed by the scanner) nor printed numbers (see [`display::number`])
+//! depend on the locale of the host: the decimal separator is always `.`,
+//! there are never grouping separators, and infinities and `NaN` print as `inf`, `-inf` and `NaN`.
+//!
+//! [`display::number`]: crate::value::display::number
+
+use crate::{
+  object::NativeFn,
+  prelude::*,
+  value::{display, Value},
+  vm::InterpretError,
+};
+
+use super::{string_arg, string_val};
+
+/// All number natives, as `(name, arity, function)`.
+pub const NATIVES: &[(&str, usize, NativeFn)] = &[
+  ("parse_num", 1, parse_num),
+  ("to_fixed", 2, to_fixed),
+  ("to_precision", 2, to_precision),
+  ("to_hex", 1, to_hex),
+  ("to_oct", 1, to_oct),
+  ("to_bin", 1, to_bin),
+];
+
+/// Max count of digits `to_fixed` and `to_precision` could be asked for.
+pub const DIGITS_MAX: usize = 100;
+
+/// Max magnitude of integers `to_hex`, `to_oct` and `to_bin` accept (`2^53`),
+/// beyond which integers can't be represented exactly.
+pub const INTEGER_MAX: f64 = 9007199254740992.0;
+
+/// `parse_num(s)`: the number written in the string (see [`parse`]), or `nil` if it isn't one.
+fn parse_num(args: &[Value]) -> Result<Value, InterpretError> {
+  let s = string_arg("parse_num", &args[0])?;
+  Ok(parse(s).map_or(Value::nil_val(), Value::number_val))
+}
+
+/// `to_fixed(x, n)`: the number with `n` digits after the decimal point (rounded half to even
+/// on the exact binary value, e.g. `to_fixed(2.5, 0)` is `"2"`), e.g. `to_fixed(1.23456, 2)` is
+/// `"1.23"`.
+fn to_fixed(args: &[Value]) -> Result<Value, InterpretError> {
+  let x = number_arg("to_fixed", &args[0])?;
+  let digits = digits_arg("to_fixed", &args[1], 0)?;
+  Ok(string_val(fixed(x, digits)))
+}
+
+/// `to_precision(x, n)`: the number with `n` significant digits, e.g. `to_precision(1.23456, 3)`
+/// is `"1.23"`, in exponential notation if the exponent is less than `-6` or not less than `n`,
+/// e.g. `to_precision(123456, 2)` is `"1.2e5"`.
+fn to_precision(args: &[Value]) -> Result<Value, InterpretError> {
+  let x = number_arg("to_precision", &args[0])?;
+  let digits = digits_arg("to_precision", &args[1], 1)?;
+  Ok(string_val(precision(x, digits)))
+}
+
+/// `to_hex(n)`: the integer in lowercase hexadecimal digits, e.g. `to_hex(255)` is `"ff"`.
+fn to_hex(args: &[Value]) -> Result<Value, InterpretError> {
+  radix("to_hex", &args[0], |n| format!("{:x}", n))
+}
+
+/// `to_oct(n)`: the integer in octal digits, e.g. `to_oct(8)` is `"10"`.
+fn to_oct(args: &[Value]) -> Result<Value, InterpretError> {
+  radix("to_oct", &args[0], |n| format!("{:o}", n))
+}
+
+/// `to_bin(n)`: the integer in binary digits, e.g. `to_bin(5)` is `"101"`.
+fn to_bin(args: &[Value]) -> Result<Value, InterpretError> {
+  radix("to_bin", &args[0], |n| format!("{:b}", n))
+}
+
+/// The number with the digits after the decimal point
+/// (infinities and `NaN` as [`display::number`]).
+pub fn fixed(x: f64, digits: usize) -> String {
+  if !x.is_finite() {
+    return display::number(x);
+  }
+  format!("{:.*}", digits, x)
+}
+
+/// The number with the significant digits (infinities and `NaN` as [`display::number`]).
+pub fn precision(x: f64, digits: usize) -> String {
+  if !x.is_finite() {
+    return display::number(x);
+  }
+  // The exponent after rounding (e.g. `9.99` to 2 digits is `1.0e1`)
+  let exponential = format!("{:.*e}", digits - 1, x);
+  let exponent: isize = exponential[exponential.find('e').unwrap() + 1..]
+    .parse()
+    .unwrap();
+  if exponent < -6 || exponent >= digits as isize {
+    exponential
+  } else {
+    format!("{:.*}", (digits as isize - 1 - exponent) as usize, x)
+  }
+}
+
+/// The integer argument formatted (by its magnitude) with a leading `-` if it's negative.
+fn radix(name: &str, arg: &Value, format: impl Fn(u64) -> String) -> Result<Value, InterpretError> {
+  let n = number_arg(name, arg)?;
+  if n % 1.0 != 0.0 || n.abs() > INTEGER_MAX {
+    return Err(InterpretError::RuntimeError(format!(
+      "`{}` expected an integer between -2^53 and 2^53.",
+      name
+    )));
+  }
+  let sign = if n < 0.0 { "-" } else { "" };
+  Ok(string_val(format!("{}{}", sign, format(n.abs() as u64))))
+}
+
+/// The number argument of the native function.
+fn number_arg(name: &str, arg: &Value) -> Result<f64, InterpretError> {
+  if !arg.is_number() {
+    return Err(InterpretError::RuntimeError(format!(
+      "`{}` expected a number.",
+      name
+    )));
+  }
+  Ok(arg.as_number())
+}
+
+/// The count of digits argument of the native function, from `min` to [`DIGITS_MAX`].
+fn digits_arg(name: &str, arg: &Value, min: usize) -> Result<usize, InterpretError> {
+  let n = Some(arg)
+    .filter(|v| v.is_number() && v.as_number() % 1.0 == 0.0)
+    .map(|v| v.as_number())
+    .filter(|&n| n >= min as f64 && n <= DIGITS_MAX as f64);
+  n.map(|n| n as usize).ok_or_else(|| {
+    InterpretError::RuntimeError(format!(
+      "`{}` expected an integer count of digits from {} to {}.",
+      name, min, DIGITS_MAX
+    ))
+  })
+}
+
+/// Parse a number the same way regardless of the locale of the host:
+///
+/// - whitespace around the number is ignored
+/// - an optional sign (`+` or `-`) is followed by digits with an optional fraction (`1`, `1.5`,
+///   `1.`, `.5`) and an optional exponent (`1e3`, `1.5E-2`)
+/// - `Infinity` (or `inf`, as printed) with an optional sign, and `NaN` (without a sign)
+///   are the special values, case-sensitively
+///
+/// Anything else (e.g. `1,5`, `1_000`, `0x10`, `infinity`, an empty string) is not a number.
+pub fn parse(s: &str) -> Option<f64> {
+  let s = s.trim();
+  if s == "NaN" {
+    return Some(f64::NAN);
+  }
+  let (negative, body) = match s.as_bytes().first() {
+    Some(b'+') => (false, &s[1..]),
+    Some(b'-') => (true, &s[1..]),
+    _ => (false, s),
+  };
+  let magnitude = match body {
+    "Infinity" | "inf" => f64::INFINITY,
+    _ if is_decimal(body) => body.parse::<f64>().ok()?,
+    _ => return None,
+  };
+  Some(if negative { -magnitude } else { magnitude })
+}
+
+/// If the string is `digits[.digits][(e|E)[+|-]digits]` (with digits on at least one side of `.`).
+fn is_decimal(s: &str) -> bool {
+  let bytes = s.as_bytes();
+  let digits = |from: usize| {
+    bytes[from..]
+      .iter()
+      .take_while(|b| b.is_ascii_digit())
+      .count()
+  };
+  let mut i = digits(0);
+  let mut mantissa = i;
+  if bytes.get(i) == Some(&b'.') {
+    let fraction = digits(i + 1);
+    mantissa += fraction;
+    i += 1 + fraction;
+  }
+  if mantissa == 0 {
+    return false;
+  }
+  if matches!(bytes.get(i), Some(b'e' | b'E')) {
+    i += 1;
+    if matches!(bytes.get(i), Some(b'+' | b'-')) {
+      i += 1;
+    }
+    let exponent = digits(i);
+    if exponent == 0 {
+      return false;
+    }
+    i += exponent;
+  }
+  i == bytes.len()
+}
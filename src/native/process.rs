@@ -0,0 +1,48 @@
+//! # Process
+//!
+//! Submodule of `native`, which contains the natives running other programs
+//! (gated by [`Capability::Exec`](super::Capability::Exec)).
+
+use std::process::Command;
+
+use crate::{prelude::*, value::Value, vm::InterpretError};
+
+use super::{
+  list::{items_arg, list_val},
+  string_arg, string_val,
+};
+
+/// `exec(cmd, args)`: run the program (without a shell) and wait for it.
+///
+/// `args` is a list of strings (or `nil`), each passed as it is (no splitting nor quoting).
+///
+/// Returns the list `[status, stdout, stderr]` (`status` is `nil` if it's killed by a signal).
+pub(super) fn exec(args: &[Value]) -> Result<Value, InterpretError> {
+  let cmd = string_arg("exec", &args[0])?;
+  let cmd_args = if args[1].is_nil() {
+    vec![]
+  } else {
+    items_arg("exec", &args[1])?
+  };
+  let cmd_args = cmd_args
+    .iter()
+    .map(|arg| {
+      arg
+        .as_rust_string()
+        .map_err(|_| InterpretError::RuntimeError("`exec` expected a list of strings.".into()))
+    })
+    .collect::<Result<Vec<_>, _>>()?;
+  let output = Command::new(cmd).args(cmd_args).output().map_err(|error| {
+    InterpretError::RuntimeError(format!("`exec` failed to run `{}`: {}", cmd, error))
+  })?;
+  let status = match output.status.code() {
+    Some(code) => Value::number_val(code as f64),
+    None => Value::nil_val(),
+  };
+  let stream = |bytes: &[u8]| string_val(String::from_utf8_lossy(bytes).into_owned());
+  Ok(list_val(vec![
+    status,
+    stream(&output.stdout),
+    stream(&output.stderr),
+  ]))
+}
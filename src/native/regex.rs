@@ -0,0 +1,91 @@
+//! # Regex
+//!
+//! Submodule of `native`, which contains the regular expression natives
+//! (only with the `regex` feature).
+//!
+//! There's no map in the language, so a match is a `Match` record:
+//!
+//! - `text`, `start`, `end`: the whole match and its byte range
+//! - `groups`: list of the whole match followed by numbered capture groups
+//!   (`nil` if a group doesn't participate), so `groups[1]` is the first group
+//! - `named`: a `Groups` record of named capture groups, by their names
+//!
+//! Named groups are kept apart from fields of the match, so they may be named anything.
+
+use regex::{Captures, Regex};
+
+use crate::{object::NativeFn, prelude::*, value::Value, vm::InterpretError};
+
+use super::{list::list_val, record, string_arg, string_val};
+
+/// All regular expression natives, as `(name, arity, function)`.
+pub const NATIVES: &[(&str, usize, NativeFn)] = &[
+  ("regex_match", 2, regex_match),
+  ("regex_find_all", 2, regex_find_all),
+  ("regex_replace", 3, regex_replace),
+];
+
+/// `regex_match(pattern, s)`: the first match in the string, or `nil`.
+fn regex_match(args: &[Value]) -> Result<Value, InterpretError> {
+  let regex = regex_arg("regex_match", &args[0])?;
+  let s = string_arg("regex_match", &args[1])?;
+  Ok(match regex.captures(s) {
+    Some(captures) => match_value(&regex, &captures),
+    None => Value::nil_val(),
+  })
+}
+
+/// `regex_find_all(pattern, s)`: list of all (non-overlapping) matches in the string.
+fn regex_find_all(args: &[Value]) -> Result<Value, InterpretError> {
+  let regex = regex_arg("regex_find_all", &args[0])?;
+  let s = string_arg("regex_find_all", &args[1])?;
+  let all = regex
+    .captures_iter(s)
+    .map(|captures| match_value(&regex, &captures))
+    .collect();
+  Ok(list_val(all))
+}
+
+/// `regex_replace(pattern, s, replacement)`: replace all matches in the string.
+///
+/// The replacement could refer to groups, as `$1` or `${name}` (written as `"\${name}"`
+/// or `r"${name}"` in scripts, as `${` starts an interpolation in ordinary strings).
+fn regex_replace(args: &[Value]) -> Result<Value, InterpretError> {
+  let regex = regex_arg("regex_replace", &args[0])?;
+  let s = string_arg("regex_replace", &args[1])?;
+  let replacement = string_arg("regex_replace", &args[2])?;
+  let replaced = regex.replace_all(s, replacement).into_owned();
+  Ok(string_val(replaced))
+}
+
+/// Compile the pattern argument.
+fn regex_arg(name: &str, arg: &Value) -> Result<Regex, InterpretError> {
+  Regex::new(string_arg(name, arg)?).map_err(|error| {
+    InterpretError::RuntimeError(format!("`{}` got an invalid pattern: {}", name, error))
+  })
+}
+
+/// Make a `Match` record of the captures.
+fn match_value(regex: &Regex, captures: &Captures) -> Value {
+  let group = |i: usize| {
+    captures
+      .get(i)
+      .map_or(Value::nil_val(), |m| string_val(m.as_str().to_owned()))
+  };
+  let whole = captures.get(0).unwrap();
+  let groups = (0..captures.len()).map(group).collect();
+  let named = regex
+    .capture_names()
+    .enumerate()
+    .filter_map(|(i, name)| Some((name?.to_owned(), group(i))));
+  record(
+    "Match",
+    [
+      ("text".to_owned(), string_val(whole.as_str().to_owned())),
+      ("start".to_owned(), Value::number_val(whole.start() as f64)),
+      ("end".to_owned(), Value::number_val(whole.end() as f64)),
+      ("groups".to_owned(), list_val(groups)),
+      ("named".to_owned(), record("Groups", named)),
+    ],
+  )
+}
@@ -0,0 +1,148 @@
+//! # Sandbox
+//!
+//! Submodule of `native`, which contains named sandbox profiles (see [`VM::set_sandbox`]).
+//!
+//! A profile bundles what scripts are allowed to do:
+//!
+//! - which capabilities are granted (see [`Capabilities`])
+//! - if natives of the host (`clock`, `random`, `now_unix`, `sleep`, `exec`, `fetch`) are defined
+//! - how many instructions each run may execute (see [`VM::set_fuel`])
+//! - how many bytes of objects each run may allocate (see [`VM::set_heap_limit`])
+//! - how long each run may take (see [`VM::set_timeout`])
+//!
+//! | profile | capabilities            | natives of the host | fuel    | heap     | timeout |
+//! | ------- | ----------------------- | ------------------- | ------- | -------- | ------- |
+//! | `pure`  | none                    | undefined           | `10^7`  | `16 MiB` | `1s`    |
+//! | `io`    | `sleep`, `network`      | defined             | `10^8`  | `64 MiB` | `10s`   |
+//! | `full`  | all                     | defined             | none    | none     | none    |
+//!
+//! Profiles are applied by [`VM::set_sandbox`] (or `Vm::sandboxed` of the facade, and
+//! `--sandbox <profile>` of the command line), there's no builder of virtual machines.
+
+use core::fmt::Display;
+
+use crate::{object::ObjString, prelude::*, vm::VM};
+
+use super::Capabilities;
+
+/// Natives of the host, which observe or affect the world outside the script.
+pub const HOST_NATIVES: &[&str] = &["clock", "random", "now_unix", "sleep", "exec", "fetch"];
+
+/// ## SandboxProfile
+///
+/// A named bundle of capabilities, natives and limits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SandboxProfile {
+  /// Computing only: nothing of the host is reachable, and runs are short.
+  Pure,
+  /// Blocking, the network and files, without running other programs.
+  Io,
+  /// Everything, without limits (like scripts run by the command line).
+  Full,
+}
+
+impl SandboxProfile {
+  /// All profiles, from the most restricted one.
+  pub const ALL: [SandboxProfile; 3] = [
+    SandboxProfile::Pure,
+    SandboxProfile::Io,
+    SandboxProfile::Full,
+  ];
+
+  /// The profile of the given name (`pure`, `io` or `full`).
+  pub fn from_name(name: &str) -> Option<Self> {
+    Self::ALL.into_iter().find(|profile| profile.name() == name)
+  }
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      SandboxProfile::Pure => "pure",
+      SandboxProfile::Io => "io",
+      SandboxProfile::Full => "full",
+    }
+  }
+
+  /// Capabilities granted by the profile.
+  pub fn capabilities(&self) -> Capabilities {
+    match self {
+      SandboxProfile::Pure => Capabilities::none(),
+      SandboxProfile::Io => Capabilities {
+        sleep: true,
+        network: true,
+        files: true,
+        ..Capabilities::none()
+      },
+      SandboxProfile::Full => Capabilities::all(),
+    }
+  }
+
+  /// If natives of the host (see [`HOST_NATIVES`]) are defined.
+  pub fn host_natives(&self) -> bool {
+    *self != SandboxProfile::Pure
+  }
+
+  /// How many instructions each run may execute (if it's limited).
+  pub fn fuel(&self) -> Option<usize> {
+    match self {
+      SandboxProfile::Pure => Some(10_000_000),
+      SandboxProfile::Io => Some(100_000_000),
+      SandboxProfile::Full => None,
+    }
+  }
+
+  /// How many bytes of objects each run may allocate (if it's limited).
+  pub fn heap_limit(&self) -> Option<usize> {
+    match self {
+      SandboxProfile::Pure => Some(16 << 20),
+      SandboxProfile::Io => Some(64 << 20),
+      SandboxProfile::Full => None,
+    }
+  }
+
+  /// How long each run may take (if it's limited).
+  #[cfg(feature = "std")]
+  pub fn timeout(&self) -> Option<std::time::Duration> {
+    match self {
+      SandboxProfile::Pure => Some(std::time::Duration::from_secs(1)),
+      SandboxProfile::Io => Some(std::time::Duration::from_secs(10)),
+      SandboxProfile::Full => None,
+    }
+  }
+}
+
+impl Display for SandboxProfile {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    write!(f, "{}", self.name())
+  }
+}
+
+impl VM {
+  /// Apply the sandbox profile: grant its capabilities, define (or undefine) natives of the host,
+  /// and limit runs by its fuel, heap and timeout (see [`SandboxProfile`]).
+  ///
+  /// Each part could still be adjusted afterwards (e.g. by [`VM::set_timeout`]).
+  pub fn set_sandbox(&mut self, profile: SandboxProfile) {
+    self.sandbox = Some(profile);
+    self.set_capabilities(profile.capabilities());
+    self.set_fuel(profile.fuel());
+    self.set_heap_limit(profile.heap_limit());
+    #[cfg(feature = "std")]
+    self.set_timeout(profile.timeout());
+    self.define_natives();
+  }
+
+  /// The sandbox profile applied last (if any).
+  pub fn sandbox(&self) -> Option<SandboxProfile> {
+    self.sandbox
+  }
+
+  /// Undefine natives of the host, if the sandbox profile excludes them.
+  pub(super) fn undefine_host_natives(&mut self) {
+    if self.sandbox.is_none_or(|profile| profile.host_natives()) {
+      return;
+    }
+    for &name in HOST_NATIVES {
+      self.globals.remove(&ObjString::from(name.to_owned()));
+    }
+  }
+}
@@ -0,0 +1,128 @@
+//! # Time
+//!
+//! Submodule of `native`, which contains the date/time natives.
+//!
+//! Timestamps are seconds since the unix epoch, and dates are always in UTC.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::{object::NativeFn, value::Value, vm::InterpretError};
+
+use super::{string_arg, string_val};
+
+/// All (ungated) date/time natives, as `(name, arity, function)`.
+pub const NATIVES: &[(&str, usize, NativeFn)] =
+  &[("now_unix", 0, now_unix), ("format_time", 2, format_time)];
+
+const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+const MONTHS: [&str; 12] = [
+  "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// `now_unix()`: whole seconds since the unix epoch.
+fn now_unix(_: &[Value]) -> Result<Value, InterpretError> {
+  let now = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map_err(|_| InterpretError::RuntimeError("System time is before unix epoch.".into()))?;
+  Ok(Value::number_val(now.as_secs() as f64))
+}
+
+/// `format_time(ts, fmt)`: format the timestamp (in UTC), like `strftime`.
+///
+/// Supported specifiers:
+///
+/// - `%Y` year, `%m` month (`01`-`12`), `%d` day (`01`-`31`), `%j` day of year (`001`-`366`)
+/// - `%H` hour, `%M` minute, `%S` second (all zero padded)
+/// - `%a` weekday (`Mon`), `%b` month (`Jan`)
+/// - `%s` the timestamp (whole seconds), `%Z` the time zone (`UTC`), `%%` a `%`
+fn format_time(args: &[Value]) -> Result<Value, InterpretError> {
+  if !args[0].is_number() || !args[0].as_number().is_finite() {
+    return Err(InterpretError::RuntimeError(
+      "`format_time` expected a timestamp.".into(),
+    ));
+  }
+  let ts = args[0].as_number().floor() as i64;
+  let fmt = string_arg("format_time", &args[1])?;
+
+  let days = ts.div_euclid(86400);
+  let secs = ts.rem_euclid(86400);
+  let (year, month, day) = civil_from_days(days);
+  let day_of_year = days - days_from_civil(year, 1, 1) + 1;
+
+  let mut out = String::with_capacity(fmt.len());
+  let mut chars = fmt.chars();
+  while let Some(c) = chars.next() {
+    if c != '%' {
+      out.push(c);
+      continue;
+    }
+    match chars.next() {
+      Some('Y') => out.push_str(&year.to_string()),
+      Some('m') => out.push_str(&format!("{:02}", month)),
+      Some('d') => out.push_str(&format!("{:02}", day)),
+      Some('j') => out.push_str(&format!("{:03}", day_of_year)),
+      Some('H') => out.push_str(&format!("{:02}", secs / 3600)),
+      Some('M') => out.push_str(&format!("{:02}", secs % 3600 / 60)),
+      Some('S') => out.push_str(&format!("{:02}", secs % 60)),
+      Some('a') => out.push_str(WEEKDAYS[(days + 4).rem_euclid(7) as usize]),
+      Some('b') => out.push_str(MONTHS[month as usize - 1]),
+      Some('s') => out.push_str(&ts.to_string()),
+      Some('Z') => out.push_str("UTC"),
+      Some('%') => out.push('%'),
+      other => {
+        return Err(InterpretError::RuntimeError(format!(
+          "`format_time` got an unknown specifier `%{}`.",
+          other.map(String::from).unwrap_or_default()
+        )))
+      }
+    }
+  }
+  Ok(string_val(out))
+}
+
+/// `sleep(ms)`: block for the milliseconds (gated by [`Capability::Sleep`](super::Capability::Sleep)).
+pub(super) fn sleep(args: &[Value]) -> Result<Value, InterpretError> {
+  std::thread::sleep(sleep_duration(&args[0])?);
+  Ok(Value::nil_val())
+}
+
+/// The duration argument (in milliseconds) of `sleep`.
+pub(super) fn sleep_duration(arg: &Value) -> Result<Duration, InterpretError> {
+  let ms = Some(arg)
+    .filter(|v| v.is_number() && v.as_number() >= 0.0)
+    .map(Value::as_number)
+    .ok_or_else(|| {
+      InterpretError::RuntimeError("`sleep` expected non-negative milliseconds.".into())
+    })?;
+  Duration::try_from_secs_f64(ms / 1000.0).map_err(|_| too_long_sleep())
+}
+
+/// Error of `sleep` for milliseconds beyond what a duration (or the clock) holds.
+pub(super) fn too_long_sleep() -> InterpretError {
+  InterpretError::RuntimeError("`sleep` got too many milliseconds.".into())
+}
+
+/// `(year, month, day)` of the days since the unix epoch (proleptic gregorian calendar).
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+  let z = days + 719468;
+  let era = z.div_euclid(146097);
+  let doe = z.rem_euclid(146097);
+  let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+  let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+  let mp = (5 * doy + 2) / 153;
+  let day = doy - (153 * mp + 2) / 5 + 1;
+  let month = if mp < 10 { mp + 3 } else { mp - 9 };
+  let year = yoe + era * 400 + i64::from(month <= 2);
+  (year, month, day)
+}
+
+/// Days since the unix epoch of the date, the inverse of [`civil_from_days`].
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+  let year = if month <= 2 { year - 1 } else { year };
+  let era = year.div_euclid(400);
+  let yoe = year.rem_euclid(400);
+  let mp = if month > 2 { month - 3 } else { month + 9 };
+  let doy = (153 * mp + 2) / 5 + day - 1;
+  let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+  era * 146097 + doe - 719468
+}
@@ -4,10 +4,16 @@
 //! objects in the virtual machine.
 
 use crate::{
+  chunk::Chunk,
+  gc::allocate,
+  native::{error::ErrorKind, Capability},
+  prelude::*,
+  table::Table,
+  utils::Init,
   value::{ValUnion, Value, ValueType},
-  vm::InterpretError,
+  vm::{InterpretError, VM},
 };
-use std::{
+use core::{
   fmt::{Debug, Display},
   ptr::NonNull,
 };
@@ -15,15 +21,20 @@ use std::{
 /// ## Object Type
 ///
 /// An enum which represents the type of the object.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum ObjType {
+  #[default]
   String,
-}
-
-impl Default for ObjType {
-  fn default() -> Self {
-    Self::String
-  }
+  Function,
+  Native,
+  Class,
+  Instance,
+  BoundMethod,
+  Closure,
+  Upvalue,
+  List,
+  Enum,
+  EnumMember,
 }
 
 /// ## Object
@@ -47,14 +58,6 @@ pub trait ObjTrait {
   fn cast_to_obj_ptr(self) -> NonNull<Obj>;
 }
 
-impl Value {
-  pub(crate) fn format_object(&self) -> String {
-    match self.obj_type().unwrap() {
-      ObjType::String => format!("\"{}\"", self.as_rust_string().unwrap()),
-    }
-  }
-}
-
 impl Value {
   pub fn obj_val(value: NonNull<Obj>) -> Self {
     Self {
@@ -67,7 +70,9 @@ impl Value {
     self.value_type == ValueType::Obj
   }
 
-  pub fn as_obj(&self) -> NonNull<Obj> {
+  /// The object pointer, only for object values (checked by callers).
+  pub(crate) fn as_obj(&self) -> NonNull<Obj> {
+    debug_assert!(self.is_obj(), "{:?} is not an object", self);
     unsafe { self.val_union.obj }
   }
 }
@@ -76,8 +81,12 @@ impl Value {
   pub fn obj_type(&self) -> Result<ObjType, InterpretError> {
     match self.value_type {
       ValueType::Obj => Ok(unsafe { self.val_union.obj.as_ref() }.obj_type),
-      ValueType::Nil => Err(InterpretError::RuntimeError("Value is `nil`.".into())),
-      _ => Err(InterpretError::RuntimeError(
+      ValueType::Nil => Err(InterpretError::Raised(
+        ErrorKind::Type,
+        "Value is `nil`.".into(),
+      )),
+      _ => Err(InterpretError::Raised(
+        ErrorKind::Type,
         "Value is not an object.".into(),
       )),
     }
@@ -86,6 +95,17 @@ impl Value {
   fn is_obj_type(&self, expect: ObjType) -> bool {
     self.is_obj() && self.obj_type().unwrap() == expect
   }
+
+  /// Check if two object values refer to the same object.
+  ///
+  /// Strings are compared by content, all other objects by identity.
+  pub(crate) fn obj_equals(&self, other: &Self) -> bool {
+    if self.is_string() && other.is_string() {
+      self.as_rust_string().unwrap() == other.as_rust_string().unwrap()
+    } else {
+      self.as_obj() == other.as_obj()
+    }
+  }
 }
 
 /// ## Object String
@@ -103,6 +123,25 @@ impl ObjString {
   pub fn is_empty(&self) -> bool {
     self.data.is_empty()
   }
+
+  /// The character at the index (see [`sequence_index`]), indexes count characters, not bytes.
+  pub fn char_at(&self, index: Value) -> Result<String, InterpretError> {
+    let index = sequence_index("String", index, self.data.chars().count())?;
+    Ok(self.data.chars().nth(index).into_iter().collect())
+  }
+
+  /// The characters within the bounds (see [`sequence_range`]).
+  pub fn slice(&self, start: Value, end: Value) -> Result<String, InterpretError> {
+    let range = sequence_range("String", start, end, self.data.chars().count())?;
+    Ok(
+      self
+        .data
+        .chars()
+        .skip(range.start)
+        .take(range.len())
+        .collect(),
+    )
+  }
 }
 
 impl Default for ObjString {
@@ -117,13 +156,12 @@ impl Default for ObjString {
 
 impl ObjTrait for ObjString {
   fn cast_to_obj_ptr(self) -> NonNull<Obj> {
-    // NonNull::from(&self).cast() /* => Error! */
-    NonNull::new(Box::into_raw(Box::new(self))).unwrap().cast() /* => Ok */
+    allocate(self)
   }
 }
 
 impl Display for ObjString {
-  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
     write!(f, "{}", self.data)
   }
 }
@@ -137,14 +175,899 @@ impl Value {
     if self.is_string() {
       Ok(self.as_obj().cast())
     } else {
-      Err(InterpretError::RuntimeError(
+      Err(InterpretError::Raised(
+        ErrorKind::Type,
         "Value is not a string.".into(),
       ))
     }
   }
 
-  pub fn as_rust_string(&self) -> Result<&mut String, InterpretError> {
-    let str_ref = &mut unsafe { self.as_string()?.as_mut() }.data;
-    Ok(str_ref)
+  /// Content of the string.
+  ///
+  /// Strings are immutable once created, so it's only borrowed
+  /// (mutating one takes the `NonNull<ObjString>` of [`as_string`](Self::as_string)).
+  pub fn as_rust_string(&self) -> Result<&str, InterpretError> {
+    Ok(&unsafe { self.as_string()?.as_ref() }.data)
+  }
+}
+
+/// ## Object Function
+///
+/// The type of the function object (also used for the top-level `script`).
+#[repr(C)]
+#[derive(Debug, Clone)]
+pub struct ObjFunction {
+  pub(crate) obj: Obj,
+  /// Count of all parameters.
+  pub(crate) arity: usize,
+  /// Count of parameters without default value.
+  pub(crate) required_arity: usize,
+  /// Bytecode of the function body.
+  pub(crate) chunk: Chunk,
+  /// Name of the function (`None` for the top-level script).
+  pub(crate) name: Option<ObjString>,
+  /// Variables captured from enclosing functions, in order of their upvalue indices.
+  pub(crate) upvalues: Vec<Upvalue>,
+}
+
+impl ObjFunction {
+  pub fn new(name: Option<ObjString>) -> Self {
+    Self {
+      obj: Obj::new(ObjType::Function),
+      arity: 0,
+      required_arity: 0,
+      chunk: Chunk::default(),
+      name,
+      upvalues: Vec::new(),
+    }
+  }
+
+  /// Name of the function, for diagnostics.
+  pub fn name(&self) -> &str {
+    match &self.name {
+      Some(name) => &name.data,
+      None => "script",
+    }
+  }
+
+  /// Bytecode of the function body.
+  pub fn chunk(&self) -> &Chunk {
+    &self.chunk
+  }
+
+  /// Describe the expected count of arguments, e.g. `2` or `1 to 2`.
+  pub fn arity_range(&self) -> String {
+    if self.required_arity == self.arity {
+      format!("{}", self.arity)
+    } else {
+      format!("{} to {}", self.required_arity, self.arity)
+    }
+  }
+}
+
+impl Default for ObjFunction {
+  fn default() -> Self {
+    Self::new(None)
+  }
+}
+
+impl Init for ObjFunction {}
+
+impl ObjTrait for ObjFunction {
+  fn cast_to_obj_ptr(self) -> NonNull<Obj> {
+    allocate(self)
+  }
+}
+
+impl Display for ObjFunction {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match &self.name {
+      Some(name) => write!(f, "<fn {}>", name),
+      None => write!(f, "<script>"),
+    }
+  }
+}
+
+impl Value {
+  pub fn is_function(&self) -> bool {
+    self.is_obj_type(ObjType::Function)
+  }
+
+  pub fn as_function(&self) -> Result<NonNull<ObjFunction>, InterpretError> {
+    if self.is_function() {
+      Ok(self.as_obj().cast())
+    } else {
+      Err(InterpretError::Raised(
+        ErrorKind::Type,
+        "Value is not a function.".into(),
+      ))
+    }
+  }
+
+  /// The function to call, with the closure which wraps it (if it's a closure).
+  pub fn as_callable(
+    &self,
+  ) -> Result<(NonNull<ObjFunction>, Option<NonNull<ObjClosure>>), InterpretError> {
+    if let Ok(closure) = self.as_closure() {
+      Ok((unsafe { closure.as_ref() }.function, Some(closure)))
+    } else {
+      Ok((self.as_function()?, None))
+    }
+  }
+}
+
+/// ## Upvalue
+///
+/// Where a closure captures a variable from when it's created:
+/// a local of the enclosing function, or an upvalue of the enclosing closure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Upvalue {
+  /// Slot of the local, or index of the upvalue.
+  pub(crate) index: usize,
+  /// If it's a local of the enclosing function.
+  pub(crate) is_local: bool,
+}
+
+/// ## Object Closure
+///
+/// The type of a function with variables captured from its enclosing functions.
+///
+/// Functions capturing nothing are never wrapped, so they're created once (as constants).
+#[repr(C)]
+#[derive(Debug, Clone)]
+pub struct ObjClosure {
+  pub(crate) obj: Obj,
+  /// The wrapped function.
+  pub(crate) function: NonNull<ObjFunction>,
+  /// The captured variables, see [`ObjFunction::upvalues`].
+  pub(crate) upvalues: Vec<NonNull<ObjUpvalue>>,
+}
+
+impl ObjClosure {
+  pub fn new(function: NonNull<ObjFunction>) -> Self {
+    Self {
+      obj: Obj::new(ObjType::Closure),
+      function,
+      upvalues: Vec::new(),
+    }
+  }
+}
+
+impl ObjTrait for ObjClosure {
+  fn cast_to_obj_ptr(self) -> NonNull<Obj> {
+    allocate(self)
+  }
+}
+
+impl Display for ObjClosure {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    write!(f, "{}", unsafe { self.function.as_ref() })
+  }
+}
+
+/// ## Object Upvalue
+///
+/// A variable captured by closures.
+///
+/// It's open (refers to the slot of the local) while the local is on the stack,
+/// then closed (holds the value itself) once the local goes out of scope.
+#[repr(C)]
+#[derive(Debug, Clone)]
+pub struct ObjUpvalue {
+  pub(crate) obj: Obj,
+  /// Index of the captured local in the stack.
+  pub(crate) slot: usize,
+  /// The value, once it's closed.
+  pub(crate) closed: Option<Value>,
+}
+
+impl ObjUpvalue {
+  pub fn new(slot: usize) -> Self {
+    Self {
+      obj: Obj::new(ObjType::Upvalue),
+      slot,
+      closed: None,
+    }
+  }
+}
+
+impl ObjTrait for ObjUpvalue {
+  fn cast_to_obj_ptr(self) -> NonNull<Obj> {
+    allocate(self)
+  }
+}
+
+impl Display for ObjUpvalue {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    write!(f, "<upvalue>")
+  }
+}
+
+impl Value {
+  pub fn is_closure(&self) -> bool {
+    self.is_obj_type(ObjType::Closure)
+  }
+
+  pub fn as_closure(&self) -> Result<NonNull<ObjClosure>, InterpretError> {
+    if self.is_closure() {
+      Ok(self.as_obj().cast())
+    } else {
+      Err(InterpretError::Raised(
+        ErrorKind::Type,
+        "Value is not a closure.".into(),
+      ))
+    }
+  }
+}
+
+/// ## Native Function
+///
+/// Signature of functions implemented in rust, which receive all arguments as a slice.
+pub type NativeFn = fn(&[Value]) -> Result<Value, InterpretError>;
+
+/// ## Callback Function
+///
+/// Signature of functions implemented in rust, which call back into the virtual machine
+/// (e.g. to call functions passed as arguments, see [`VM::call_function`]).
+pub type CallbackFn = fn(&mut VM, &[Value]) -> Result<Value, InterpretError>;
+
+/// ## Native Function Kind
+///
+/// The rust function of a native, with or without access to the virtual machine.
+#[derive(Debug, Clone, Copy)]
+pub enum NativeFunction {
+  Plain(NativeFn),
+  Callback(CallbackFn),
+}
+
+/// ## Object Native
+///
+/// The type of the native function object.
+#[repr(C)]
+#[derive(Debug, Clone)]
+pub struct ObjNative {
+  pub(crate) obj: Obj,
+  /// Name of the native function, for diagnostics.
+  pub(crate) name: String,
+  /// Count of all arguments.
+  pub(crate) arity: usize,
+  /// Count of arguments which can't be omitted (missing ones are `nil`).
+  pub(crate) required_arity: usize,
+  /// The rust function.
+  pub(crate) function: NativeFunction,
+  /// The capability needed to call it (if it's gated).
+  pub(crate) capability: Option<Capability>,
+}
+
+impl ObjNative {
+  pub fn new(name: &str, arity: usize, function: NativeFn) -> Self {
+    Self {
+      obj: Obj::new(ObjType::Native),
+      name: name.to_owned(),
+      arity,
+      required_arity: arity,
+      function: NativeFunction::Plain(function),
+      capability: None,
+    }
+  }
+
+  /// A native function which needs the capability to be called.
+  pub fn gated(name: &str, arity: usize, function: NativeFn, capability: Capability) -> Self {
+    Self {
+      capability: Some(capability),
+      ..Self::new(name, arity, function)
+    }
+  }
+
+  /// A native function which calls back into the virtual machine,
+  /// taking `required_arity` to `arity` arguments.
+  pub fn callback(name: &str, required_arity: usize, arity: usize, function: CallbackFn) -> Self {
+    Self {
+      obj: Obj::new(ObjType::Native),
+      name: name.to_owned(),
+      arity,
+      required_arity,
+      function: NativeFunction::Callback(function),
+      capability: None,
+    }
+  }
+
+  /// Describe the expected count of arguments, e.g. `2` or `1 to 2`.
+  pub fn arity_range(&self) -> String {
+    if self.required_arity == self.arity {
+      format!("{}", self.arity)
+    } else {
+      format!("{} to {}", self.required_arity, self.arity)
+    }
+  }
+}
+
+impl ObjTrait for ObjNative {
+  fn cast_to_obj_ptr(self) -> NonNull<Obj> {
+    allocate(self)
+  }
+}
+
+impl Display for ObjNative {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    write!(f, "<native fn>")
+  }
+}
+
+impl Value {
+  pub fn is_native(&self) -> bool {
+    self.is_obj_type(ObjType::Native)
+  }
+
+  pub fn as_native(&self) -> Result<NonNull<ObjNative>, InterpretError> {
+    if self.is_native() {
+      Ok(self.as_obj().cast())
+    } else {
+      Err(InterpretError::Raised(
+        ErrorKind::Type,
+        "Value is not a native function.".into(),
+      ))
+    }
+  }
+}
+
+/// ## Object List
+///
+/// The type of the list object, a growable sequence of values (e.g. `[1, "two", nil]`).
+#[repr(C)]
+#[derive(Debug, Clone)]
+pub struct ObjList {
+  pub(crate) obj: Obj,
+  /// All items, in order.
+  pub(crate) items: Vec<Value>,
+}
+
+impl ObjList {
+  pub fn new(items: Vec<Value>) -> Self {
+    Self {
+      obj: Obj::new(ObjType::List),
+      items,
+    }
+  }
+
+  /// Position of the item at the index (see [`sequence_index`]).
+  pub fn index(&self, index: Value) -> Result<usize, InterpretError> {
+    sequence_index("List", index, self.items.len())
+  }
+
+  /// Copy of the items within the bounds (see [`sequence_range`]).
+  pub fn slice(&self, start: Value, end: Value) -> Result<Vec<Value>, InterpretError> {
+    let range = sequence_range("List", start, end, self.items.len())?;
+    Ok(self.items[range].to_vec())
+  }
+}
+
+/// Position of the index in a sequence (a list or a string, named by `what` in errors)
+/// of the length, where negative indexes count from the end (e.g. `-1` is the last one).
+pub fn sequence_index(what: &str, index: Value, len: usize) -> Result<usize, InterpretError> {
+  if !index.is_number() || index.as_number() % 1.0 != 0.0 {
+    return Err(InterpretError::RuntimeError(format!(
+      "{} index must be an integer.",
+      what
+    )));
+  }
+  let number = index.as_number();
+  let position = if number < 0.0 {
+    number + len as f64
+  } else {
+    number
+  };
+  if position < 0.0 || position >= len as f64 {
+    return Err(InterpretError::RuntimeError(format!(
+      "{} index {} is out of bounds for length {}.",
+      what, number, len
+    )));
+  }
+  Ok(position as usize)
+}
+
+/// Range of the bounds (`start:end`) in a sequence of the length, where a `nil` start is `0`,
+/// a `nil` end is the length, and negative bounds count from the end (as [`sequence_index`]).
+///
+/// Bounds aren't clamped: they must be within the sequence, and `start` must not be after `end`.
+pub fn sequence_range(
+  what: &str,
+  start: Value,
+  end: Value,
+  len: usize,
+) -> Result<core::ops::Range<usize>, InterpretError> {
+  let position = |bound: Value, default: usize| {
+    if bound.is_nil() {
+      return Ok(default as f64);
+    }
+    if !bound.is_number() || bound.as_number() % 1.0 != 0.0 {
+      return Err(InterpretError::RuntimeError(format!(
+        "{} slice bounds must be integers.",
+        what
+      )));
+    }
+    let number = bound.as_number();
+    Ok(if number < 0.0 {
+      number + len as f64
+    } else {
+      number
+    })
+  };
+  let (from, to) = (position(start, 0)?, position(end, len)?);
+  if from < 0.0 || from > to || to > len as f64 {
+    let show = |bound: Value| match bound.is_nil() {
+      true => String::new(),
+      false => format!("{}", bound.as_number()),
+    };
+    return Err(InterpretError::RuntimeError(format!(
+      "{} slice [{}:{}] is out of bounds for length {}.",
+      what,
+      show(start),
+      show(end),
+      len
+    )));
+  }
+  Ok(from as usize..to as usize)
+}
+
+impl ObjTrait for ObjList {
+  fn cast_to_obj_ptr(self) -> NonNull<Obj> {
+    allocate(self)
+  }
+}
+
+impl Value {
+  pub fn is_list(&self) -> bool {
+    self.is_obj_type(ObjType::List)
+  }
+
+  pub fn as_list(&self) -> Result<NonNull<ObjList>, InterpretError> {
+    if self.is_list() {
+      Ok(self.as_obj().cast())
+    } else {
+      Err(InterpretError::Raised(
+        ErrorKind::Type,
+        "Value is not a list.".into(),
+      ))
+    }
+  }
+}
+
+/// ## Object Enum
+///
+/// The type of the enum object (e.g. `enum Color { Red, Green, Blue }`),
+/// whose members are constants, each one a distinct object.
+#[repr(C)]
+#[derive(Debug, Clone)]
+pub struct ObjEnum {
+  pub(crate) obj: Obj,
+  /// Name of the enum.
+  pub(crate) name: ObjString,
+  /// All members, in order of declaration.
+  pub(crate) members: Vec<NonNull<ObjEnumMember>>,
+}
+
+impl ObjEnum {
+  pub fn new(name: ObjString) -> Self {
+    Self {
+      obj: Obj::new(ObjType::Enum),
+      name,
+      members: Vec::new(),
+    }
+  }
+
+  /// The member of the name (e.g. `Color.Red`).
+  pub fn member(&self, name: &ObjString) -> Result<NonNull<ObjEnumMember>, InterpretError> {
+    self
+      .members
+      .iter()
+      .find(|member| unsafe { member.as_ref() }.name == *name)
+      .copied()
+      .ok_or_else(|| {
+        InterpretError::RuntimeError(format!("Enum `{}` has no member `{}`.", self.name, name))
+      })
+  }
+}
+
+impl ObjTrait for ObjEnum {
+  fn cast_to_obj_ptr(self) -> NonNull<Obj> {
+    allocate(self)
+  }
+}
+
+impl Display for ObjEnum {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    write!(f, "<enum {}>", self.name)
+  }
+}
+
+/// ## Object Enum Member
+///
+/// The type of a member of an enum, which knows its name and position (`ordinal`).
+#[repr(C)]
+#[derive(Debug, Clone)]
+pub struct ObjEnumMember {
+  pub(crate) obj: Obj,
+  /// The enum declaring the member.
+  pub(crate) enum_: NonNull<ObjEnum>,
+  /// Name of the member.
+  pub(crate) name: ObjString,
+  /// Position of the member in its enum (from `0`).
+  pub(crate) ordinal: usize,
+}
+
+impl ObjEnumMember {
+  pub fn new(enum_: NonNull<ObjEnum>, name: ObjString, ordinal: usize) -> Self {
+    Self {
+      obj: Obj::new(ObjType::EnumMember),
+      enum_,
+      name,
+      ordinal,
+    }
+  }
+
+  /// Property of the member: its `name` (as a string) or its `ordinal`.
+  pub fn property(&self, name: &str) -> Result<Value, InterpretError> {
+    match name {
+      "name" => Ok(Value::obj_val(self.name.clone().cast_to_obj_ptr())),
+      "ordinal" => Ok(Value::number_val(self.ordinal as f64)),
+      _ => Err(InterpretError::RuntimeError(format!(
+        "Enum members only have `name` and `ordinal`, not `{}`.",
+        name
+      ))),
+    }
+  }
+}
+
+impl ObjTrait for ObjEnumMember {
+  fn cast_to_obj_ptr(self) -> NonNull<Obj> {
+    allocate(self)
+  }
+}
+
+impl Display for ObjEnumMember {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    write!(f, "{}.{}", unsafe { self.enum_.as_ref() }.name, self.name)
+  }
+}
+
+impl Value {
+  pub fn is_enum(&self) -> bool {
+    self.is_obj_type(ObjType::Enum)
+  }
+
+  pub fn as_enum(&self) -> Result<NonNull<ObjEnum>, InterpretError> {
+    if self.is_enum() {
+      Ok(self.as_obj().cast())
+    } else {
+      Err(InterpretError::Raised(
+        ErrorKind::Type,
+        "Value is not an enum.".into(),
+      ))
+    }
+  }
+
+  pub fn is_enum_member(&self) -> bool {
+    self.is_obj_type(ObjType::EnumMember)
+  }
+
+  pub fn as_enum_member(&self) -> Result<NonNull<ObjEnumMember>, InterpretError> {
+    if self.is_enum_member() {
+      Ok(self.as_obj().cast())
+    } else {
+      Err(InterpretError::Raised(
+        ErrorKind::Type,
+        "Value is not an enum member.".into(),
+      ))
+    }
+  }
+}
+
+/// ## Object Class
+///
+/// The type of the class object.
+#[repr(C)]
+#[derive(Debug, Clone)]
+pub struct ObjClass {
+  pub(crate) obj: Obj,
+  /// Name of the class.
+  pub(crate) name: ObjString,
+  /// All methods, as `{name: function}`.
+  pub(crate) methods: Table,
+  /// Getters of computed properties, as `{name: function}`.
+  pub(crate) getters: Table,
+  /// Setters of computed properties, as `{name: function}`.
+  pub(crate) setters: Table,
+  /// If it's a trait (which could only be mixed into classes).
+  pub(crate) is_trait: bool,
+  /// Names of all traits mixed into the class, in order.
+  pub(crate) traits: Vec<String>,
+  /// The class it inherits from (if any).
+  pub(crate) superclass: Option<NonNull<ObjClass>>,
+}
+
+impl ObjClass {
+  pub fn new(name: ObjString) -> Self {
+    Self {
+      obj: Obj::new(ObjType::Class),
+      name,
+      methods: Table::default(),
+      getters: Table::default(),
+      setters: Table::default(),
+      is_trait: false,
+      traits: Vec::new(),
+      superclass: None,
+    }
+  }
+}
+
+impl ObjClass {
+  /// Copy all methods (and accessors) of the trait into the class.
+  ///
+  /// A method provided by two different traits is reported as a conflict.
+  pub fn mix_in(&mut self, from: &ObjClass) -> Result<(), InterpretError> {
+    if !from.is_trait {
+      return Err(InterpretError::Raised(
+        ErrorKind::Type,
+        format!(
+          "Can only mix traits into `{}`, but `{}` is a class.",
+          self.name, from.name
+        ),
+      ));
+    }
+    let tables = [
+      (&mut self.methods, &from.methods),
+      (&mut self.getters, &from.getters),
+      (&mut self.setters, &from.setters),
+    ];
+    for (into, from_table) in tables {
+      for (name, method) in from_table.iter() {
+        if into.set(name.to_owned(), *method).is_some() {
+          return Err(InterpretError::RuntimeError(format!(
+            "Method `{}` of trait `{}` conflicts with one of trait(s) [{}] mixed into `{}`.",
+            name,
+            from.name,
+            self.traits.join(", "),
+            self.name
+          )));
+        }
+      }
+    }
+    self.traits.push(from.name.data.to_owned());
+    Ok(())
+  }
+
+  /// Inherit all methods (and accessors) of the superclass, which aren't defined yet
+  /// (e.g. by traits mixed in), then link to it.
+  ///
+  /// Methods declared by the class itself are defined afterwards, so they override inherited ones.
+  pub fn inherit(&mut self, superclass: NonNull<ObjClass>) -> Result<(), InterpretError> {
+    let from = unsafe { superclass.as_ref() };
+    if from.is_trait {
+      return Err(InterpretError::RuntimeError(format!(
+        "Can't inherit from trait `{}`, mix it into `{}` by `with` instead.",
+        from.name, self.name
+      )));
+    }
+    let tables = [
+      (&mut self.methods, &from.methods),
+      (&mut self.getters, &from.getters),
+      (&mut self.setters, &from.setters),
+    ];
+    for (into, from_table) in tables {
+      for (name, method) in from_table.iter() {
+        if into.get(name).is_none() {
+          into.set(name.to_owned(), *method);
+        }
+      }
+    }
+    self.superclass = Some(superclass);
+    Ok(())
+  }
+
+  /// Check if the class is the given class, or one of its subclasses.
+  pub fn is_subclass_of(&self, class: &ObjClass) -> bool {
+    let mut current = Some(self);
+    while let Some(ancestor) = current {
+      if core::ptr::eq(ancestor, class) {
+        return true;
+      }
+      current = ancestor
+        .superclass
+        .map(|superclass| unsafe { superclass.as_ref() });
+    }
+    false
+  }
+}
+
+impl ObjTrait for ObjClass {
+  fn cast_to_obj_ptr(self) -> NonNull<Obj> {
+    allocate(self)
+  }
+}
+
+impl Display for ObjClass {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    write!(f, "{}", self.name)
+  }
+}
+
+/// ## Object Instance
+///
+/// The type of the instance object (of a class).
+#[repr(C)]
+#[derive(Debug, Clone)]
+pub struct ObjInstance {
+  pub(crate) obj: Obj,
+  /// The class of the instance.
+  pub(crate) class: NonNull<ObjClass>,
+  /// All fields, as `{name: value}`.
+  pub(crate) fields: Table,
+  /// Methods already bound to the instance, as `{name: bound_method}`.
+  ///
+  /// Methods of a class never change once declared,
+  /// so each of them is bound (allocated) at most once per instance.
+  pub(crate) bound_methods: Table,
+}
+
+impl ObjInstance {
+  pub fn new(class: NonNull<ObjClass>) -> Self {
+    Self {
+      obj: Obj::new(ObjType::Instance),
+      class,
+      fields: Table::default(),
+      bound_methods: Table::default(),
+    }
+  }
+
+  /// The class of the instance.
+  pub fn class(&self) -> &ObjClass {
+    unsafe { self.class.as_ref() }
+  }
+}
+
+impl ObjTrait for ObjInstance {
+  fn cast_to_obj_ptr(self) -> NonNull<Obj> {
+    allocate(self)
+  }
+}
+
+impl Display for ObjInstance {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    write!(f, "{} instance", self.class().name)
+  }
+}
+
+/// ## Object Bound Method
+///
+/// The type of a method bound to its receiver (e.g. `var m = instance.method;`).
+#[repr(C)]
+#[derive(Debug, Clone)]
+pub struct ObjBoundMethod {
+  pub(crate) obj: Obj,
+  /// The instance which the method is bound to (`this`).
+  pub(crate) receiver: Value,
+  /// The method (a function or a closure).
+  pub(crate) method: Value,
+}
+
+impl ObjBoundMethod {
+  pub fn new(receiver: Value, method: Value) -> Self {
+    Self {
+      obj: Obj::new(ObjType::BoundMethod),
+      receiver,
+      method,
+    }
+  }
+}
+
+impl ObjTrait for ObjBoundMethod {
+  fn cast_to_obj_ptr(self) -> NonNull<Obj> {
+    allocate(self)
+  }
+}
+
+impl Display for ObjBoundMethod {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self.method.as_callable() {
+      Ok((function, _)) => write!(f, "{}", unsafe { function.as_ref() }),
+      Err(_) => write!(f, "<fn>"),
+    }
+  }
+}
+
+impl Value {
+  pub fn is_class(&self) -> bool {
+    self.is_obj_type(ObjType::Class)
+  }
+
+  pub fn as_class(&self) -> Result<NonNull<ObjClass>, InterpretError> {
+    if self.is_class() {
+      Ok(self.as_obj().cast())
+    } else {
+      Err(InterpretError::Raised(
+        ErrorKind::Type,
+        "Value is not a class.".into(),
+      ))
+    }
+  }
+
+  /// Implementation of `value is Class`.
+  ///
+  /// Non-instance values are never instances of any class.
+  pub fn is_instance_of(&self, class: Value) -> Result<bool, InterpretError> {
+    let class = class.as_class().map_err(|_| {
+      InterpretError::Raised(
+        ErrorKind::Type,
+        "Right operand of `is` must be a class.".into(),
+      )
+    })?;
+    match self.as_instance() {
+      Ok(instance) => Ok(
+        unsafe { instance.as_ref() }
+          .class()
+          .is_subclass_of(unsafe { class.as_ref() }),
+      ),
+      Err(_) => Ok(false),
+    }
+  }
+
+  /// Implementation of `item in self`:
+  ///
+  /// - `"ab" in s`: if the string is a substring of `s`
+  /// - `x in list`: if any item of the list equals `x` (as `==`)
+  /// - `"field" in instance`: if the instance has the field (methods aren't fields)
+  pub fn contains(&self, item: Value) -> Result<bool, InterpretError> {
+    if let Ok(s) = self.as_rust_string() {
+      let part = item.as_rust_string().map_err(|_| {
+        InterpretError::RuntimeError(
+          "Left operand of `in` must be a string if the right one is.".into(),
+        )
+      })?;
+      Ok(s.contains(part))
+    } else if let Ok(list) = self.as_list() {
+      Ok(unsafe { list.as_ref() }.items.contains(&item))
+    } else if let Ok(instance) = self.as_instance() {
+      let name = item
+        .as_string()
+        .map_err(|_| InterpretError::RuntimeError("Field name must be a string.".into()))?;
+      let instance = unsafe { instance.as_ref() };
+      Ok(instance.fields.get(unsafe { name.as_ref() }).is_some())
+    } else {
+      Err(InterpretError::Raised(
+        ErrorKind::Type,
+        "Right operand of `in` must be a string, a list or an instance.".into(),
+      ))
+    }
+  }
+
+  pub fn is_instance(&self) -> bool {
+    self.is_obj_type(ObjType::Instance)
+  }
+
+  pub fn as_instance(&self) -> Result<NonNull<ObjInstance>, InterpretError> {
+    if self.is_instance() {
+      Ok(self.as_obj().cast())
+    } else {
+      Err(InterpretError::Raised(
+        ErrorKind::Type,
+        "Value is not an instance.".into(),
+      ))
+    }
+  }
+
+  pub fn is_bound_method(&self) -> bool {
+    self.is_obj_type(ObjType::BoundMethod)
+  }
+
+  pub fn as_bound_method(&self) -> Result<NonNull<ObjBoundMethod>, InterpretError> {
+    if self.is_bound_method() {
+      Ok(self.as_obj().cast())
+    } else {
+      Err(InterpretError::Raised(
+        ErrorKind::Type,
+        "Value is not a bound method.".into(),
+      ))
+    }
   }
 }
@@ -0,0 +1,13 @@
+//! # Prelude
+//!
+//! Items of the standard prelude which actually live in `alloc`,
+//! so the core could be built without `std` (see the `std` feature).
+
+pub(crate) use alloc::{
+  borrow::ToOwned,
+  boxed::Box,
+  format,
+  string::{String, ToString},
+  vec,
+  vec::Vec,
+};
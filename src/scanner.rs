@@ -8,9 +8,14 @@
 //! Scanner (aka. Lexer) is the first stage of the interpreter,
 //! which is also know as `front-end`.
 
-use std::hash::Hash;
+use alloc::{collections::BTreeMap, sync::Arc};
+use core::hash::Hash;
 
-use crate::utils::{Identifier, Init};
+use crate::{
+  prelude::*,
+  utils::{Identifier, Init, Lazy},
+  vm::InterpretError,
+};
 
 /// ## TokenType
 ///
@@ -22,81 +27,317 @@ pub enum TokenType {
   RightParen,
   LeftBrace,
   RightBrace,
+  LeftBracket,
+  RightBracket,
   Comma,
   Dot,
   Minus,
+  MinusMinus,
   Plus,
+  PlusPlus,
   Semicolon,
   Slash,
   Star,
+  Question,
+  Colon,
   // One or two character tokens.
   Bang,
   BangEqual,
   Equal,
   EqualEqual,
+  FatArrow,
   Greater,
   GreaterEqual,
   Less,
   LessEqual,
+  // Three character tokens.
+  DotDotDot,
   // Literals.
   Identifier,
   String,
   Number,
   // Keywords.
   And,
+  Catch,
   Class,
+  Delete,
+  Div,
   Else,
+  Enum,
   False,
   For,
   Fun,
   If,
+  Import,
+  In,
+  Is,
+  Match,
   Nil,
   Or,
   Print,
   Return,
   Super,
   This,
+  Throw,
+  Trait,
   True,
+  Try,
   Var,
   While,
-  // Dollar sign.
+  With,
+  // Segment of an interpolated string, up to (and including) `${`.
   Dollar,
+  // Operators registered by plugins.
+  Operator,
   // Error and EOF.
   Error,
   Eof,
 }
 
 impl Hash for TokenType {
-  fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+  fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
     core::mem::discriminant(self).hash(state);
   }
 }
 
-// TODO: Add support of `dollar` sign => "var = ${var}".
+/// Reserved words of standard Lox (with extensions of this implementation).
+pub const STANDARD_KEYWORDS: &[(&str, TokenType)] = &[
+  ("and", TokenType::And),
+  ("catch", TokenType::Catch),
+  ("class", TokenType::Class),
+  ("delete", TokenType::Delete),
+  ("div", TokenType::Div),
+  ("else", TokenType::Else),
+  ("enum", TokenType::Enum),
+  ("false", TokenType::False),
+  ("for", TokenType::For),
+  ("fun", TokenType::Fun),
+  ("if", TokenType::If),
+  ("import", TokenType::Import),
+  ("in", TokenType::In),
+  ("is", TokenType::Is),
+  ("match", TokenType::Match),
+  ("nil", TokenType::Nil),
+  ("or", TokenType::Or),
+  ("print", TokenType::Print),
+  ("return", TokenType::Return),
+  ("super", TokenType::Super),
+  ("this", TokenType::This),
+  ("throw", TokenType::Throw),
+  ("trait", TokenType::Trait),
+  ("true", TokenType::True),
+  ("try", TokenType::Try),
+  ("var", TokenType::Var),
+  ("while", TokenType::While),
+  ("with", TokenType::With),
+];
+
+/// ## Keywords
+///
+/// Table of reserved words, which maps each spelling to its token type.
+///
+/// Embedders could enable alternative spellings (e.g. localized ones) of keywords,
+/// or remove some of them (which become ordinary identifiers).
+///
+/// Copies share one table until one of them is changed, so cloning it is cheap.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keywords {
+  table: Arc<BTreeMap<String, TokenType>>,
+}
+
+/// Table of standard Lox, shared by all standard keywords.
+static STANDARD_TABLE: Lazy<Arc<BTreeMap<String, TokenType>>> = Lazy::new(|| {
+  Arc::new(
+    STANDARD_KEYWORDS
+      .iter()
+      .map(|&(spelling, token_type)| (spelling.to_owned(), token_type))
+      .collect(),
+  )
+});
+
+impl Default for Keywords {
+  fn default() -> Self {
+    Self::standard()
+  }
+}
+
+impl Keywords {
+  /// Keywords of standard Lox.
+  pub fn standard() -> Self {
+    Self {
+      table: STANDARD_TABLE.clone(),
+    }
+  }
+
+  /// An empty table, nothing is reserved.
+  pub fn empty() -> Self {
+    Self {
+      table: Arc::default(),
+    }
+  }
+
+  /// Spell the keyword of the given token type (also) as `spelling`.
+  ///
+  /// The spelling must be an identifier, and the token type must be a keyword.
+  pub fn insert(&mut self, spelling: &str, token_type: TokenType) -> Result<(), InterpretError> {
+    let bytes = spelling.as_bytes();
+    let is_identifier = bytes.first().is_some_and(|c| c.is_ascii_identifier())
+      && bytes
+        .iter()
+        .all(|c| c.is_ascii_identifier() || c.is_ascii_digit());
+    if !is_identifier {
+      return Err(InterpretError::CompileError(format!(
+        "Keyword `{}` should be spelled as an (ascii) identifier.",
+        spelling
+      )));
+    }
+    if !STANDARD_KEYWORDS.iter().any(|&(_, t)| t == token_type) {
+      return Err(InterpretError::CompileError(format!(
+        "`{:?}` is not a keyword.",
+        token_type
+      )));
+    }
+    Arc::make_mut(&mut self.table).insert(spelling.to_owned(), token_type);
+    Ok(())
+  }
+
+  /// Stop reserving the spelling, returns the token type it was spelling.
+  pub fn remove(&mut self, spelling: &str) -> Option<TokenType> {
+    Arc::make_mut(&mut self.table).remove(spelling)
+  }
+
+  /// If it's (a copy of) the table of standard Lox, which is never changed.
+  pub fn is_standard(&self) -> bool {
+    Arc::ptr_eq(&self.table, &STANDARD_TABLE)
+  }
+
+  /// Token type of the lexeme, `Identifier` if it's not reserved.
+  pub fn lookup(&self, lexeme: &str) -> TokenType {
+    self
+      .table
+      .get(lexeme)
+      .copied()
+      .unwrap_or(TokenType::Identifier)
+  }
+
+  /// All spellings of the given token type.
+  pub fn spellings(&self, token_type: TokenType) -> Vec<&str> {
+    self
+      .table
+      .iter()
+      .filter(|&(_, &t)| t == token_type)
+      .map(|(spelling, _)| spelling.as_str())
+      .collect()
+  }
+}
+
+/// ## TriviaKind
+///
+/// An enum which represents the kinds of trivia (source text between tokens).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriviaKind {
+  /// A run of spaces, tabs and carriage returns.
+  Whitespace,
+  /// A single `\n`.
+  Newline,
+  /// `// ...` (without the ending newline).
+  LineComment,
+  /// `/* ... */`.
+  BlockComment,
+}
+
+/// ## Trivia
+///
+/// A piece of source text between tokens, which is meaningless to the parser,
+/// but needed by the formatter and doc tooling.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trivia {
+  pub kind: TriviaKind,
+  /// The source text of the trivia.
+  pub text: String,
+  /// The line where the trivia starts.
+  pub line: usize,
+  /// The column (starts from `1`) where the trivia starts.
+  pub column: usize,
+}
 
 /// ## Token
 ///
 /// A struct which represents a token.
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct Token {
   /// The type of the token.
   pub(crate) token_type: TokenType,
   /// The line of the token.
   pub(crate) line: usize,
+  /// The column (starts from `1`) of the token.
+  pub(crate) column: usize,
   /// The lexeme of the token.
   ///
   /// A lexeme is the text that the token represents.
   pub(crate) lexeme: String,
+  /// Trivia right before the token (only kept in trivia mode).
+  pub(crate) leading_trivia: Vec<Trivia>,
 }
 
 impl Init for Token {}
 
+impl Token {
+  /// The type of the token.
+  pub fn token_type(&self) -> TokenType {
+    self.token_type
+  }
+
+  /// The text that the token represents (the message for error tokens).
+  pub fn lexeme(&self) -> &str {
+    &self.lexeme
+  }
+
+  /// The line of the token.
+  pub fn line(&self) -> usize {
+    self.line
+  }
+
+  /// The column (starts from `1`) of the token.
+  pub fn column(&self) -> usize {
+    self.column
+  }
+
+  /// Trivia right before the token (always empty unless the scanner keeps trivia).
+  pub fn leading_trivia(&self) -> &[Trivia] {
+    &self.leading_trivia
+  }
+}
+
+impl Clone for Token {
+  fn clone(&self) -> Self {
+    Self {
+      token_type: self.token_type,
+      line: self.line,
+      column: self.column,
+      lexeme: self.lexeme.clone(),
+      leading_trivia: self.leading_trivia.clone(),
+    }
+  }
+
+  /// Reuse buffers of the lexeme and trivia.
+  fn clone_from(&mut self, source: &Self) {
+    self.token_type = source.token_type;
+    self.line = source.line;
+    self.column = source.column;
+    self.lexeme.clone_from(&source.lexeme);
+    self.leading_trivia.clone_from(&source.leading_trivia);
+  }
+}
+
 impl Default for Token {
   fn default() -> Self {
     Self {
       token_type: TokenType::Eof,
       line: 1,
+      column: 1,
       lexeme: "".into(),
+      leading_trivia: vec![],
     }
   }
 }
@@ -114,6 +355,31 @@ pub struct Scanner {
   pub(crate) current: usize,
   /// The current line.
   pub(crate) line: usize,
+  /// The position where the current line starts.
+  pub(crate) line_start: usize,
+  /// The column of the start position.
+  pub(crate) start_column: usize,
+  /// Symbols of operators registered by plugins (the longest one comes first).
+  pub(crate) operators: Vec<String>,
+  /// Reserved words.
+  pub(crate) keywords: Keywords,
+  /// Trivia mode: keep whitespace and comments as trivia of the next token.
+  pub(crate) keep_trivia: bool,
+  /// Trivia met since the last token (only in trivia mode).
+  pub(crate) trivia: Vec<Trivia>,
+  /// Strings whose interpolated expressions are being scanned (the innermost one comes last).
+  pub(crate) interpolations: Vec<Interpolation>,
+}
+
+/// ## Interpolation
+///
+/// A string whose interpolated expression (`${...}`) is being scanned.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Interpolation {
+  /// Unclosed `{` of the expression, so its own `}` is told from the one ending it.
+  pub(crate) braces: usize,
+  /// If the string is quoted by `"""`.
+  pub(crate) triple: bool,
 }
 
 impl Default for Scanner {
@@ -123,76 +389,187 @@ impl Default for Scanner {
       start: 0,
       current: 0,
       line: 1,
+      line_start: 0,
+      start_column: 1,
+      operators: vec![],
+      keywords: Keywords::default(),
+      keep_trivia: false,
+      trivia: vec![],
+      interpolations: vec![],
     }
   }
 }
 
 impl Scanner {
-  /// Try to match reserved keyword.
-  ///
-  /// Match `rest` and `&self.source[self.current::-len]`
-  fn check_keyword(&self, rest: &str, candidate: TokenType) -> TokenType {
-    let len = rest.len();
-    // 1. steps from start index to current index `should be equal to` len, or the match must failed
-    // 2. if `1.` suits, then check if the rest of the source code is equal to the rest pattern
-    if self.current >= len && &self.source[self.current - len..self.current] == rest {
-      candidate
-    } else {
-      TokenType::Identifier
-    }
+  /// Generate correct identifier token (keywords are looked up in the table).
+  fn identifier_type(&self) -> TokenType {
+    self.keywords.lookup(&self.source[self.start..self.current])
+  }
+}
+
+/// Opening/closing quotes of multi-line strings.
+const TRIPLE_QUOTE: &str = "\"\"\"";
+
+/// Escape sequences of (non-raw) strings, i.e. `\n` => newline.
+const ESCAPES: &[(u8, char)] = &[
+  (b'n', '\n'),
+  (b't', '\t'),
+  (b'r', '\r'),
+  (b'0', '\0'),
+  (b'\\', '\\'),
+  (b'"', '"'),
+  (b'$', '$'),
+];
+
+/// The value of a string literal, given its (valid) lexeme.
+///
+/// A newline right after the opening `"""` is dropped, so the content could start on the next line.
+pub(crate) fn string_value(lexeme: &str) -> String {
+  let (raw, quoted) = match lexeme.strip_prefix('r') {
+    Some(quoted) => (true, quoted),
+    None => (false, lexeme),
+  };
+  let content = if quoted.len() >= 2 * TRIPLE_QUOTE.len() && quoted.starts_with(TRIPLE_QUOTE) {
+    let content = &quoted[TRIPLE_QUOTE.len()..quoted.len() - TRIPLE_QUOTE.len()];
+    content
+      .strip_prefix("\r\n")
+      .or_else(|| content.strip_prefix('\n'))
+      .unwrap_or(content)
+  } else {
+    &quoted[1..quoted.len() - 1]
+  };
+  match raw {
+    true => content.to_owned(),
+    false => unescape(content),
   }
+}
 
-  /// Generate correct identifier token.
-  fn identifier_type(&self) -> TokenType {
-    match self.source.as_bytes()[self.start] {
-      b'a' => self.check_keyword("nd", TokenType::And),
-      b'c' => self.check_keyword("lass", TokenType::Class),
-      b'e' => self.check_keyword("lse", TokenType::Else),
-      b'i' => self.check_keyword("f", TokenType::If),
-      b'n' => self.check_keyword("il", TokenType::Nil),
-      b'o' => self.check_keyword("r", TokenType::Or),
-      b'p' => self.check_keyword("rint", TokenType::Print),
-      b'r' => self.check_keyword("eturn", TokenType::Return),
-      b's' => self.check_keyword("uper", TokenType::Super),
-      b'v' => self.check_keyword("ar", TokenType::Var),
-      b'w' => self.check_keyword("hile", TokenType::While),
-      b'f' if self.current - self.start > 1 => match self.source.as_bytes()[self.start + 1] {
-        b'a' => self.check_keyword("lse", TokenType::False),
-        b'o' => self.check_keyword("r", TokenType::For),
-        b'u' => self.check_keyword("n", TokenType::Fun),
-        _ => TokenType::Identifier,
-      },
-      b't' if self.current - self.start > 1 => match self.source.as_bytes()[self.start + 1] {
-        b'h' => self.check_keyword("is", TokenType::This),
-        b'r' => self.check_keyword("ue", TokenType::True),
-        _ => TokenType::Identifier,
-      },
-      _ => TokenType::Identifier,
+/// The value of a segment of an interpolated string, given its (valid) lexeme: the opening
+/// quote(s) or the `}` ending the previous expression, the content, then `${` (if `dollar`)
+/// or the closing quote(s).
+pub(crate) fn segment_value(lexeme: &str, triple: bool, dollar: bool) -> String {
+  let quote = if triple { TRIPLE_QUOTE } else { "\"" };
+  let content = match lexeme.strip_prefix('}') {
+    Some(content) => content,
+    None => {
+      let content = &lexeme[quote.len()..];
+      match triple {
+        true => content
+          .strip_prefix("\r\n")
+          .or_else(|| content.strip_prefix('\n'))
+          .unwrap_or(content),
+        false => content,
+      }
+    }
+  };
+  let end = if dollar { "${" } else { quote };
+  unescape(&content[..content.len() - end.len()])
+}
+
+/// The first unknown escape sequence (e.g. `\q`) of the lexeme of a (non-raw) string,
+/// which is kept as it is (as backslashes used to be in all strings), but warned about.
+pub(crate) fn unknown_escape(lexeme: &str) -> Option<&str> {
+  let mut chars = lexeme.char_indices();
+  while let Some((start, c)) = chars.next() {
+    if c != '\\' {
+      continue;
+    }
+    let (end, escaped) = chars.next()?;
+    if !ESCAPES.iter().any(|&(e, _)| e as char == escaped) {
+      return Some(&lexeme[start..end + escaped.len_utf8()]);
     }
   }
+  None
 }
 
-impl Scanner {
-  /// Make a token, specifically from `string`.
-  fn string(&mut self) -> Token {
-    // Try finding the closing quote.
-    while self.peek() != b'"' && !self.is_at_end() {
-      if self.peek() == b'\n' {
-        self.line += 1;
+/// Process escape sequences of the content of a string (see [`ESCAPES`]).
+fn unescape(content: &str) -> String {
+  let mut value = String::with_capacity(content.len());
+  let mut chars = content.chars();
+  while let Some(c) = chars.next() {
+    if c != '\\' {
+      value.push(c);
+      continue;
+    }
+    if let Some(escaped) = chars.next() {
+      match ESCAPES.iter().find(|&&(e, _)| e as char == escaped) {
+        Some(&(_, c)) => value.push(c),
+        // unknown escapes are kept as they are, e.g. `"C:\dir"`
+        None => {
+          value.push('\\');
+          value.push(escaped);
+        }
       }
-      self.advance_char();
     }
+  }
+  value
+}
 
-    // Cannot find the closing quote.
-    if self.is_at_end() {
-      return self.error_token("Unterminated string.".into());
+impl Scanner {
+  /// Make a token, specifically from `string` (the opening quote is consumed).
+  ///
+  /// - `"..."` processes escape sequences (see [`ESCAPES`]), unknown ones are kept as they are
+  /// - `r"..."` is raw, i.e. without escape processing
+  /// - `"""..."""` (or `r"""..."""`) may span multiple lines
+  ///
+  /// The lexeme keeps the prefix and quotes, see [`string_value`] for the value.
+  ///
+  /// `${` in a non-raw string starts an interpolated expression, see [`Scanner::string_segment`].
+  fn string(&mut self, raw: bool) -> Token {
+    let triple = self.peek() == b'"' && self.peek_next() == b'"';
+    if triple {
+      self.advance_char();
+      self.advance_char();
     }
+    self.string_segment(raw, triple)
+  }
 
-    self.advance_char();
-    self.make_token(TokenType::String)
+  /// Make a token of the rest of a string, after its opening quote(s) or the `}` ending
+  /// an interpolated expression.
+  ///
+  /// Up to `${`, it's a `Dollar` token (the tokens of the expression come next),
+  /// otherwise it's a `String` token up to the closing quote(s).
+  /// See [`segment_value`] for values of segments.
+  fn string_segment(&mut self, raw: bool, triple: bool) -> Token {
+    let start_line = self.line;
+    // Try finding the closing quote(s) or `${`, newlines inside are counted.
+    let token_type = loop {
+      if self.is_at_end() {
+        // Cannot find the closing quote, report where the string starts.
+        return self.error_token_at("Unterminated string.".into(), start_line);
+      }
+      match self.peek() {
+        b'"' if !triple || self.source[self.current..].starts_with(TRIPLE_QUOTE) => {
+          self.current += if triple { TRIPLE_QUOTE.len() } else { 1 };
+          break TokenType::String;
+        }
+        b'$' if !raw && self.peek_next() == b'{' => {
+          self.current += 2;
+          self
+            .interpolations
+            .push(Interpolation { braces: 0, triple });
+          break TokenType::Dollar;
+        }
+        b'\n' => self.new_line(),
+        b'\\' if !raw => {
+          self.advance_char();
+          if self.peek() == b'\n' {
+            self.new_line();
+          }
+          if self.is_at_end() {
+            continue;
+          }
+        }
+        _ => {}
+      }
+      self.advance_char();
+    };
+    self.make_token(token_type)
   }
 
   /// Make a token, specifically from `number`.
+  ///
+  /// Only ASCII digits and `.` are accepted, whatever the locale of the host is.
   fn number(&mut self) -> Token {
     while self.peek().is_ascii_digit() {
       self.advance_char();
@@ -223,41 +600,86 @@ impl Scanner {
 
 impl Scanner {
   /// Make a token.
+  ///
+  /// The lexeme is left empty, it's copied from the source by [`Scanner::scan_token_into`].
   fn make_token(&self, token_type: TokenType) -> Token {
     Token {
       token_type,
       line: self.line,
-      lexeme: (&self.source[self.start..self.current]).into(),
+      column: self.start_column,
+      lexeme: String::new(),
+      leading_trivia: vec![],
     }
   }
 
   /// Make an error token.
   fn error_token(&self, message: String) -> Token {
+    self.error_token_at(message, self.line)
+  }
+
+  /// Make an error token at the given line.
+  fn error_token_at(&self, message: String, line: usize) -> Token {
     Token {
       token_type: TokenType::Error,
-      line: self.line,
+      line,
+      column: self.start_column,
       lexeme: message,
+      leading_trivia: vec![],
     }
   }
 }
 
 impl Scanner {
   /// Scan token from scanner
+  ///
+  /// In trivia mode, whitespace and comments before the token are attached to it.
   pub fn scan_token(&mut self) -> Token {
-    self.skip_white_space();
+    let mut token = Token::default();
+    self.scan_token_into(&mut token);
+    token
+  }
+
+  /// Scan token from scanner into the given one,
+  /// so the buffer of its lexeme is reused instead of allocating a new one.
+  pub fn scan_token_into(&mut self, token: &mut Token) {
+    let mut lexeme = core::mem::take(&mut token.lexeme);
+    *token = self.scan_token_without_trivia();
+    // lexemes of error tokens are their messages
+    if token.token_type != TokenType::Error {
+      lexeme.clear();
+      lexeme.push_str(&self.source[self.start..self.current]);
+      token.lexeme = lexeme;
+    }
+    if self.keep_trivia {
+      token.leading_trivia = core::mem::take(&mut self.trivia);
+    }
+  }
+
+  fn scan_token_without_trivia(&mut self) -> Token {
+    if let Some(error) = self.skip_white_space() {
+      return error;
+    }
 
     // reset start position
     self.start = self.current;
+    self.start_column = self.start - self.line_start + 1;
 
     if self.is_at_end() {
       return self.make_token(TokenType::Eof);
     }
 
+    if let Some(token) = self.registered_operator() {
+      return token;
+    }
+
     let c = self.advance_char();
 
     if c.is_ascii_digit() {
       return self.number();
     }
+    if c == b'r' && self.match_next(b'"') {
+      return self.string(true);
+    }
     if c.is_ascii_identifier() {
       return self.identifier();
     }
@@ -266,15 +688,41 @@ impl Scanner {
       // mono-character tokens
       b'(' => self.make_token(TokenType::LeftParen),
       b')' => self.make_token(TokenType::RightParen),
-      b'{' => self.make_token(TokenType::LeftBrace),
-      b'}' => self.make_token(TokenType::RightBrace),
+      b'{' => {
+        if let Some(interpolation) = self.interpolations.last_mut() {
+          interpolation.braces += 1;
+        }
+        self.make_token(TokenType::LeftBrace)
+      }
+      b'}' => match self.interpolations.last_mut() {
+        // ends the interpolated expression, so the string goes on
+        Some(&mut Interpolation { braces: 0, triple }) => {
+          self.interpolations.pop();
+          self.string_segment(false, triple)
+        }
+        Some(interpolation) => {
+          interpolation.braces -= 1;
+          self.make_token(TokenType::RightBrace)
+        }
+        None => self.make_token(TokenType::RightBrace),
+      },
+      b'[' => self.make_token(TokenType::LeftBracket),
+      b']' => self.make_token(TokenType::RightBracket),
       b';' => self.make_token(TokenType::Semicolon),
       b',' => self.make_token(TokenType::Comma),
+      b'.' if self.peek() == b'.' && self.peek_next() == b'.' => {
+        self.current += 2;
+        self.make_token(TokenType::DotDotDot)
+      }
       b'.' => self.make_token(TokenType::Dot),
+      b'-' if self.match_next(b'-') => self.make_token(TokenType::MinusMinus),
       b'-' => self.make_token(TokenType::Minus),
+      b'+' if self.match_next(b'+') => self.make_token(TokenType::PlusPlus),
       b'+' => self.make_token(TokenType::Plus),
       b'/' => self.make_token(TokenType::Slash),
       b'*' => self.make_token(TokenType::Star),
+      b'?' => self.make_token(TokenType::Question),
+      b':' => self.make_token(TokenType::Colon),
       // possible two-character tokens
       b'!' => {
         if self.match_next(b'=') {
@@ -286,6 +734,8 @@ impl Scanner {
       b'=' => {
         if self.match_next(b'=') {
           self.make_token(TokenType::EqualEqual)
+        } else if self.match_next(b'>') {
+          self.make_token(TokenType::FatArrow)
         } else {
           self.make_token(TokenType::Equal)
         }
@@ -305,12 +755,26 @@ impl Scanner {
         }
       }
       // string
-      b'"' => self.string(),
+      b'"' => self.string(false),
       _ => self.error_token("Unexpected character.".into()),
     }
   }
 }
 
+impl Scanner {
+  /// Try to match a registered operator (the longest one wins).
+  fn registered_operator(&mut self) -> Option<Token> {
+    let rest = &self.source.as_bytes()[self.start..];
+    let len = self
+      .operators
+      .iter()
+      .find(|symbol| rest.starts_with(symbol.as_bytes()))?
+      .len();
+    self.current += len;
+    Some(self.make_token(TokenType::Operator))
+  }
+}
+
 impl Scanner {
   /// Check if the scanner is at the end of the source code.
   fn is_at_end(&self) -> bool {
@@ -345,6 +809,12 @@ impl Scanner {
     self.source.as_bytes()[self.current]
   }
 
+  /// Step into the next line, the current char should be `\n`.
+  fn new_line(&mut self) {
+    self.line += 1;
+    self.line_start = self.current + 1;
+  }
+
   /// Get the next char, without advancing the scanner.
   fn peek_next(&self) -> u8 {
     if self.current + 1 >= self.source.len() {
@@ -353,27 +823,60 @@ impl Scanner {
     self.source.as_bytes()[self.current + 1]
   }
 
-  fn skip_white_space(&mut self) {
+  /// Skip white spaces and comments (`// ...` and `/* ... */`).
+  ///
+  /// In trivia mode, each skipped piece is recorded as trivia.
+  ///
+  /// Returns an error token if a block comment is unterminated.
+  fn skip_white_space(&mut self) -> Option<Token> {
     loop {
-      let c = self.peek();
-      match c {
+      let start = self.current;
+      let (line, column) = (self.line, self.current - self.line_start + 1);
+      let kind = match self.peek() {
         b' ' | b'\r' | b'\t' => {
-          self.advance_char();
+          while matches!(self.peek(), b' ' | b'\r' | b'\t') {
+            self.advance_char();
+          }
+          TriviaKind::Whitespace
         }
         b'\n' => {
-          self.line += 1;
+          self.new_line();
           self.advance_char();
+          TriviaKind::Newline
         }
-        b'/' => {
-          if self.peek_next() == b'/' {
-            while self.peek() != b'\n' && !self.is_at_end() {
-              self.advance_char();
+        b'/' if self.peek_next() == b'/' => {
+          while self.peek() != b'\n' && !self.is_at_end() {
+            self.advance_char();
+          }
+          TriviaKind::LineComment
+        }
+        b'/' if self.peek_next() == b'*' => {
+          let start_line = self.line;
+          self.start_column = self.current - self.line_start + 1;
+          self.advance_char();
+          self.advance_char();
+          while !(self.peek() == b'*' && self.peek_next() == b'/') {
+            if self.is_at_end() {
+              return Some(self.error_token_at("Unterminated block comment.".into(), start_line));
+            }
+            if self.peek() == b'\n' {
+              self.new_line();
             }
-          } else {
-            return;
+            self.advance_char();
           }
+          self.advance_char();
+          self.advance_char();
+          TriviaKind::BlockComment
         }
-        _ => return,
+        _ => return None,
+      };
+      if self.keep_trivia {
+        self.trivia.push(Trivia {
+          kind,
+          text: self.source[start..self.current].to_owned(),
+          line,
+          column,
+        });
       }
     }
   }
@@ -385,9 +888,7 @@ impl Scanner {
   pub fn init_with(src: String) -> Self {
     Self {
       source: src,
-      start: 0,
-      current: 0,
-      line: 1,
+      ..Default::default()
     }
   }
 
@@ -397,10 +898,15 @@ impl Scanner {
     Scanner::init_with(src)
   }
 
+  /// Bind a new scanner to the source code, which keeps trivia (see [`Trivia`]).
+  pub fn bind_with_trivia(src: String) -> Self {
+    Self {
+      keep_trivia: true,
+      ..Scanner::init_with(src)
+    }
+  }
+
   pub fn rebind(&mut self, src: String) {
-    self.source = src;
-    self.start = 0;
-    self.current = 0;
-    self.line = 1;
+    *self = Self::init_with(src);
   }
 }
@@ -1,41 +1,113 @@
 //! # Table
 //!
-//! A module which wraps [`std::collections::HashMap`].
+//! A module which wraps [`hashbrown::HashMap`].
 //!
 //! Currently, only support `{ObjString: Value}` pairs.
+//!
+//! Keys are hashed by the hash pre-computed in [`ObjString`],
+//! so lookups never re-hash the string itself.
+//!
+//! Iteration follows insertion order (each entry remembers when its key is inserted),
+//! so dumps of globals/fields are stable across runs and platforms.
 
-use std::{collections::HashMap, hash::Hash};
+use core::hash::{BuildHasherDefault, Hash, Hasher};
+use hashbrown::HashMap;
 
-use crate::{object::ObjString, utils::Init, value::Value};
+use crate::{object::ObjString, prelude::*, utils::Init, value::Value};
 
 impl Hash for ObjString {
-  fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-    self.hash.hash(state);
+  fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+    state.write_usize(self.hash);
+  }
+}
+
+/// ## PrecomputedHasher
+///
+/// A hasher which directly takes the pre-computed hash of [`ObjString`].
+///
+/// Any other input falls back to `FNV-1a`.
+#[derive(Debug, Clone, Copy)]
+pub struct PrecomputedHasher(u64);
+
+impl Default for PrecomputedHasher {
+  fn default() -> Self {
+    Self(2166136261)
+  }
+}
+
+impl Hasher for PrecomputedHasher {
+  fn finish(&self) -> u64 {
+    self.0
+  }
+
+  fn write(&mut self, bytes: &[u8]) {
+    for &byte in bytes {
+      self.0 ^= byte as u64;
+      self.0 = self.0.wrapping_mul(16777619);
+    }
+  }
+
+  fn write_usize(&mut self, hash: usize) {
+    self.0 = hash as u64;
   }
 }
 
+/// ## Table
+///
+/// `{ObjString: Value}` pairs, as `{key: (insertion sequence, value)}`.
 #[derive(Debug, Clone, Default)]
-pub struct Table(HashMap<ObjString, Value>);
+pub struct Table {
+  entries: HashMap<ObjString, (u64, Value), BuildHasherDefault<PrecomputedHasher>>,
+  /// Sequence of the next inserted key.
+  next_sequence: u64,
+}
 
 impl Table {
   pub fn get(&self, key: &ObjString) -> Option<&Value> {
-    self.0.get(key)
+    self.entries.get(key).map(|(_, value)| value)
   }
 
   pub fn get_mut(&mut self, key: &ObjString) -> Option<&mut Value> {
-    self.0.get_mut(key)
+    self.entries.get_mut(key).map(|(_, value)| value)
   }
 
+  /// Reserve room for at least `additional` more keys.
+  pub fn reserve(&mut self, additional: usize) {
+    self.entries.reserve(additional);
+  }
+
+  /// Insert or overwrite (an overwritten key keeps its position).
   pub fn set(&mut self, key: ObjString, value: Value) -> Option<Value> {
-    self.0.insert(key, value)
+    if let Some((_, old)) = self.entries.get_mut(&key) {
+      return Some(core::mem::replace(old, value));
+    }
+    self.entries.insert(key, (self.next_sequence, value));
+    self.next_sequence += 1;
+    None
   }
 
   pub fn remove(&mut self, key: &ObjString) -> Option<Value> {
-    self.0.remove(key)
+    self.entries.remove(key).map(|(_, value)| value)
+  }
+
+  /// All pairs, in insertion order.
+  pub fn iter(&self) -> impl Iterator<Item = (&ObjString, &Value)> {
+    let mut entries: Vec<_> = self.entries.iter().collect();
+    entries.sort_unstable_by_key(|(_, (sequence, _))| *sequence);
+    entries.into_iter().map(|(key, (_, value))| (key, value))
+  }
+
+  pub fn len(&self) -> usize {
+    self.entries.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.entries.is_empty()
   }
 
   pub fn free(&mut self) {
-    self.0.clear()
+    self.entries.clear();
+    self.next_sequence = 0;
   }
 }
 
@@ -4,6 +4,12 @@
 
 #![allow(dead_code)]
 
+use core::ops::Deref;
+
+use once_cell::race::OnceBox;
+
+use crate::prelude::*;
+
 /// A trait for initializing a type.
 pub trait Init: Default {
   /// Creates a new instance of the given type.
@@ -13,6 +19,7 @@ pub trait Init: Default {
 }
 
 /// Get the command line arguments (without first `root` path argument).
+#[cfg(feature = "std")]
 ///
 /// We have to rewrite this, as [`std::env::args`]'s first argument
 /// is always the path to the executable
@@ -26,6 +33,7 @@ pub fn args_without_root() -> Vec<String> {
 /// Get the command line arguments (with first `root` path argument).
 ///
 /// A simple wrapper for [`std::env::args`].
+#[cfg(feature = "std")]
 pub fn args() -> Vec<String> {
   use std::env::args;
   args().collect::<Vec<_>>()
@@ -45,3 +53,31 @@ impl Identifier for u8 {
 
 /// A trait which gives [`Vec`] a better ability to be used as a stack.
 pub trait StackLikedVec<T> {}
+
+/// ## Lazy
+///
+/// A static value which is initialized on first access.
+///
+/// It only needs `alloc` (unlike `once_cell::sync::Lazy`), as it's built on [`OnceBox`]:
+/// threads racing on the first access may all run `init`, but only one result is kept.
+pub struct Lazy<T> {
+  cell: OnceBox<T>,
+  init: fn() -> T,
+}
+
+impl<T> Lazy<T> {
+  pub const fn new(init: fn() -> T) -> Self {
+    Self {
+      cell: OnceBox::new(),
+      init,
+    }
+  }
+}
+
+impl<T> Deref for Lazy<T> {
+  type Target = T;
+
+  fn deref(&self) -> &T {
+    self.cell.get_or_init(|| Box::new((self.init)()))
+  }
+}
@@ -6,14 +6,20 @@
 //! Core implementation is about `union`.
 //!
 //! Wrappers of `Value` (e.g. `ValueArray`) are also included in this module.
+//!
+//! All stringification of values lives in [`display`].
+
+pub mod display;
 
-use std::{
+use core::{
   fmt::{Debug, Display},
   ptr::NonNull,
 };
 
 use crate::{
+  native::error::ErrorKind,
   object::{Obj, ObjString, ObjTrait},
+  prelude::*,
   utils::Init,
   vm::InterpretError,
 };
@@ -22,20 +28,15 @@ use crate::{
 ///
 /// A type alias for the value used in the virtual machine.
 // pub type Value = f64;
-#[derive(Debug, Clone, Copy, PartialEq, Eq, enum_repr::EnumU8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, enum_repr::EnumU8)]
 pub enum ValueType {
   Bool,
+  #[default]
   Nil,
   Number,
   Obj,
 }
 
-impl Default for ValueType {
-  fn default() -> Self {
-    Self::Nil
-  }
-}
-
 /// ## Value Union
 ///
 /// A union which holds all possible representation of a value.
@@ -52,18 +53,6 @@ impl Default for ValUnion {
   }
 }
 
-impl Display for ValUnion {
-  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    write!(f, "{}", unsafe { self.number })
-  }
-}
-
-impl Debug for ValUnion {
-  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    unsafe { f.write_str(&self.number.to_string()) }
-  }
-}
-
 /// ## Value
 ///
 /// A type which represents the value used in the virtual machine.
@@ -72,26 +61,31 @@ impl Debug for ValUnion {
 ///
 /// - `value_type`: The type of the value.
 /// - `val_union`: The representation in memory of the value.
+///
+/// The union is only read as the field which matches `value_type`
+/// (reading other fields may read uninitialized bytes).
 #[repr(C)]
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Clone, Copy, Default)]
 pub struct Value {
   pub(crate) value_type: ValueType,
   pub(crate) val_union: ValUnion,
 }
 
+/// Values of the same type are ordered, except objects other than strings
+/// (strings are ordered lexicographically, by code points).
 impl PartialOrd for Value {
-  fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+  fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
     if self.value_type != other.value_type {
       None
     } else {
       match self.value_type {
         ValueType::Bool => self.as_bool().partial_cmp(&other.as_bool()),
-        ValueType::Nil => Some(std::cmp::Ordering::Equal),
+        ValueType::Nil => Some(core::cmp::Ordering::Equal),
         ValueType::Number => self.as_number().partial_cmp(&other.as_number()),
         ValueType::Obj => {
-          let lhs = self.as_rust_string().unwrap();
-          let rhs = other.as_rust_string().unwrap();
-          (*lhs).partial_cmp(rhs)
+          let lhs = self.as_rust_string().ok()?;
+          let rhs = other.as_rust_string().ok()?;
+          lhs.partial_cmp(rhs)
         }
       }
     }
@@ -107,45 +101,44 @@ impl PartialEq for Value {
         ValueType::Bool => self.as_bool() == other.as_bool(),
         ValueType::Nil => true,
         ValueType::Number => self.as_number() == other.as_number(),
-        ValueType::Obj => {
-          let lhs = self.as_rust_string().unwrap();
-          let rhs = other.as_rust_string().unwrap();
-          *lhs == *rhs
-        }
+        ValueType::Obj => self.obj_equals(other),
       }
     }
   }
 }
 
-impl std::ops::Not for Value {
+impl core::ops::Not for Value {
   type Output = Result<Self, InterpretError>;
   fn not(self) -> Self::Output {
     match self.value_type {
       ValueType::Bool => Ok(Self::bool_val(!self.as_bool())),
       ValueType::Nil => Ok(Self::bool_val(true)),
-      ValueType::Number => Err(InterpretError::RuntimeError(
+      ValueType::Number => Err(InterpretError::Raised(
+        ErrorKind::Type,
         "Operand could only be `boolean` or `nil`.".to_owned(),
       )),
-      ValueType::Obj => Err(InterpretError::RuntimeError(
+      ValueType::Obj => Err(InterpretError::Raised(
+        ErrorKind::Type,
         "Operand could only be `boolean` or `nil`.".to_owned(),
       )),
     }
   }
 }
 
-impl std::ops::Neg for Value {
+impl core::ops::Neg for Value {
   type Output = Result<Self, InterpretError>;
   fn neg(self) -> Self::Output {
     if self.is_number() {
       Ok(Value::number_val(-self.as_number()))
     } else {
-      Err(InterpretError::RuntimeError(
+      Err(InterpretError::Raised(
+        ErrorKind::Type,
         "Operand must be a number.".to_owned(),
       ))
     }
   }
 }
-impl std::ops::Add for Value {
+impl core::ops::Add for Value {
   type Output = Result<Self, InterpretError>;
   fn add(self, rhs: Self) -> Self::Output {
     if self.is_number() && rhs.is_number() {
@@ -157,60 +150,94 @@ impl std::ops::Add for Value {
         ObjString::from(format!("{}{}", lhs, rhs)).cast_to_obj_ptr(),
       ))
     } else {
-      Err(InterpretError::RuntimeError(
+      Err(InterpretError::Raised(
+        ErrorKind::Type,
         "Operands must be 2 numbers or 2 strings.".to_owned(),
       ))
     }
   }
 }
-impl std::ops::Sub for Value {
+impl core::ops::Sub for Value {
   type Output = Result<Self, InterpretError>;
   fn sub(self, rhs: Self) -> Self::Output {
     if self.is_number() && rhs.is_number() {
       Ok(Value::number_val(self.as_number() - rhs.as_number()))
     } else {
-      Err(InterpretError::RuntimeError(
+      Err(InterpretError::Raised(
+        ErrorKind::Type,
         "Operands must be numbers.".to_owned(),
       ))
     }
   }
 }
-impl std::ops::Mul for Value {
+impl core::ops::Mul for Value {
   type Output = Result<Self, InterpretError>;
   fn mul(self, rhs: Self) -> Self::Output {
     if self.is_number() && rhs.is_number() {
       Ok(Value::number_val(self.as_number() * rhs.as_number()))
     } else {
-      Err(InterpretError::RuntimeError(
+      Err(InterpretError::Raised(
+        ErrorKind::Type,
         "Operands must be numbers.".to_owned(),
       ))
     }
   }
 }
-impl std::ops::Div for Value {
+impl core::ops::Div for Value {
   type Output = Result<Self, InterpretError>;
   fn div(self, rhs: Self) -> Self::Output {
     if self.is_number() && rhs.is_number() {
       Ok(Value::number_val(self.as_number() / rhs.as_number()))
     } else {
-      Err(InterpretError::RuntimeError(
+      Err(InterpretError::Raised(
+        ErrorKind::Type,
         "Operands must be numbers.".to_owned(),
       ))
     }
   }
 }
 
-impl Display for Value {
-  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl Value {
+  /// Floor division (`a div b`), i.e. the quotient rounded towards negative infinity,
+  /// e.g. `7 div 2` is `3`, and `-7 div 2` is `-4`.
+  pub fn floor_div(self, rhs: Self) -> Result<Self, InterpretError> {
+    Ok(Value::number_val(floor((self / rhs)?.as_number())))
+  }
+}
+
+/// `f64::floor`, which needs `std`.
+fn floor(x: f64) -> f64 {
+  // from 2^52 on (and for infinities and `NaN`), numbers have no fraction
+  if x.is_nan() || x.abs() >= 4503599627370496.0 {
+    return x;
+  }
+  let truncated = x as i64 as f64;
+  if truncated > x {
+    truncated - 1.0
+  } else {
+    truncated
+  }
+}
+
+/// Shows the variant of the union which is in use.
+impl Debug for Value {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
     match self.value_type {
-      ValueType::Bool => write!(f, "{}", self.as_bool()),
-      ValueType::Nil => write!(f, "nil"),
-      ValueType::Number => write!(f, "{}", self.as_number()),
-      ValueType::Obj => write!(f, "{}", self.format_object()),
+      ValueType::Bool => write!(f, "Bool({})", self.as_bool()),
+      ValueType::Nil => write!(f, "Nil"),
+      ValueType::Number => write!(f, "Number({})", self.as_number()),
+      ValueType::Obj => write!(f, "Obj({})", display::repr(self)),
     }
   }
 }
 
+/// Displayed as [`display::repr`] (strings are quoted).
+impl Display for Value {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    write!(f, "{}", display::repr(self))
+  }
+}
+
 impl From<f64> for Value {
   fn from(value: f64) -> Self {
     Self::number_val(value)
@@ -285,12 +312,18 @@ impl Value {
 }
 
 impl Value {
+  /// The boolean, `false` if it's not a boolean.
   pub fn as_bool(&self) -> bool {
-    unsafe { self.val_union.boolean }
+    self.is_bool() && unsafe { self.val_union.boolean }
   }
 
+  /// The number, `NaN` if it's not a number.
   pub fn as_number(&self) -> f64 {
-    unsafe { self.val_union.number }
+    if self.is_number() {
+      unsafe { self.val_union.number }
+    } else {
+      f64::NAN
+    }
   }
 }
 
@@ -0,0 +1,180 @@
+//! # Display
+//!
+//! Submodule of `value`, the only place where values are turned into strings.
+//!
+//! There are two styles:
+//!
+//! - [`plain`]: what scripts see, i.e. `print` and `str()`, where a string is its content
+//! - [`repr`]: what developers see, i.e. the REPL, dumps, the disassembler and diagnostics,
+//!   where a string is quoted (and escaped) like a literal, so `"1"` differs from `1`
+//!
+//! Strings nested in other values (e.g. items of lists) are always quoted (by [`repr`]).
+//!
+//! [`pretty`] is [`repr`] of aggregates (i.e. instances and lists), which shows their fields
+//! (or items) on indented lines, down to the given depth.
+
+use core::{fmt::Write, ptr::NonNull};
+
+use crate::{
+  object::{Obj, ObjList, ObjType},
+  prelude::*,
+};
+
+use super::{Value, ValueType};
+
+/// The plain string of the value (a string is its content).
+pub fn plain(value: &Value) -> String {
+  match value.as_rust_string() {
+    Ok(s) => s.to_owned(),
+    Err(_) => repr(value),
+  }
+}
+
+/// The string of the value, where a string is quoted like a literal.
+pub fn repr(value: &Value) -> String {
+  match value.value_type {
+    ValueType::Bool => value.as_bool().to_string(),
+    ValueType::Nil => "nil".into(),
+    ValueType::Number => number(value.as_number()),
+    ValueType::Obj => object(value),
+  }
+}
+
+/// Default depth of [`pretty`] (e.g. of the REPL).
+pub const PRETTY_DEPTH: usize = 4;
+
+/// The string of the value, where fields of instances and items of lists are shown
+/// on indented lines.
+///
+/// Instances and lists deeper than `max_depth` are truncated as `Class {..}` and `[..]`,
+/// and an instance or a list nested in itself is shown as `<cycle>`.
+pub fn pretty(value: &Value, max_depth: usize) -> String {
+  let mut out = String::new();
+  pretty_into(&mut out, value, max_depth, &mut Vec::new());
+  out
+}
+
+fn pretty_into(out: &mut String, value: &Value, depth: usize, path: &mut Vec<NonNull<Obj>>) {
+  let (instance, list) = (value.as_instance(), value.as_list());
+  if instance.is_err() && list.is_err() {
+    out.push_str(&repr(value));
+    return;
+  }
+  if path.contains(&value.as_obj()) {
+    out.push_str("<cycle>");
+    return;
+  }
+  if let Ok(instance) = instance {
+    let instance = unsafe { instance.as_ref() };
+    let _ = write!(out, "{} {{", instance.class().name);
+    let fields = instance
+      .fields
+      .iter()
+      .map(|(name, field)| (Some(name.to_string()), *field))
+      .collect();
+    pretty_entries(out, value, fields, '}', depth, path);
+  } else if let Ok(list) = list {
+    out.push('[');
+    let items = unsafe { list.as_ref() }
+      .items
+      .iter()
+      .map(|item| (None, *item))
+      .collect();
+    pretty_entries(out, value, items, ']', depth, path);
+  }
+}
+
+/// Entries of the aggregate (fields of an instance as `(Some(name), value)`, or items of a list
+/// as `(None, item)`) on indented lines, then the closing bracket.
+fn pretty_entries(
+  out: &mut String,
+  aggregate: &Value,
+  entries: Vec<(Option<String>, Value)>,
+  close: char,
+  depth: usize,
+  path: &mut Vec<NonNull<Obj>>,
+) {
+  if entries.is_empty() {
+    out.push(close);
+    return;
+  }
+  if depth == 0 {
+    let _ = write!(out, "..{}", close);
+    return;
+  }
+  path.push(aggregate.as_obj());
+  let indent = "  ".repeat(path.len());
+  for (name, entry) in entries {
+    let _ = write!(out, "\n{}", indent);
+    if let Some(name) = name {
+      let _ = write!(out, "{}: ", name);
+    }
+    pretty_into(out, &entry, depth - 1, path);
+    out.push(',');
+  }
+  path.pop();
+  let _ = write!(out, "\n{}{}", "  ".repeat(path.len()), close);
+}
+
+/// The string of a number (integers without the fractional part).
+///
+/// It never depends on the locale of the host (always `.` as the decimal separator,
+/// without grouping), so printed numbers could be read back by `parse_num`.
+pub fn number(value: f64) -> String {
+  value.to_string()
+}
+
+/// Quote the string like a literal, escaping `"`, `\` and control characters.
+pub fn quote(s: &str) -> String {
+  let mut out = String::with_capacity(s.len() + 2);
+  out.push('"');
+  for c in s.chars() {
+    match c {
+      '"' => out.push_str("\\\""),
+      '\\' => out.push_str("\\\\"),
+      '\n' => out.push_str("\\n"),
+      '\t' => out.push_str("\\t"),
+      '\r' => out.push_str("\\r"),
+      '\0' => out.push_str("\\0"),
+      _ => out.push(c),
+    }
+  }
+  out.push('"');
+  out
+}
+
+fn object(value: &Value) -> String {
+  unsafe {
+    match value.obj_type().unwrap() {
+      ObjType::String => quote(value.as_rust_string().unwrap()),
+      ObjType::Function => value.as_function().unwrap().as_ref().to_string(),
+      ObjType::Native => value.as_native().unwrap().as_ref().to_string(),
+      ObjType::Class => value.as_class().unwrap().as_ref().to_string(),
+      ObjType::Instance => value.as_instance().unwrap().as_ref().to_string(),
+      ObjType::BoundMethod => value.as_bound_method().unwrap().as_ref().to_string(),
+      ObjType::Closure => value.as_closure().unwrap().as_ref().to_string(),
+      ObjType::Upvalue => "<upvalue>".into(),
+      ObjType::List => list(value.as_list().unwrap(), &mut Vec::new()),
+      ObjType::Enum => value.as_enum().unwrap().as_ref().to_string(),
+      ObjType::EnumMember => value.as_enum_member().unwrap().as_ref().to_string(),
+    }
+  }
+}
+
+/// Items of the list in brackets, where a list nested in itself is shown as `[...]`.
+fn list(list: NonNull<ObjList>, path: &mut Vec<NonNull<ObjList>>) -> String {
+  if path.contains(&list) {
+    return "[...]".into();
+  }
+  path.push(list);
+  let items: Vec<_> = unsafe { list.as_ref() }
+    .items
+    .iter()
+    .map(|item| match item.as_list() {
+      Ok(nested) => self::list(nested, path),
+      Err(_) => repr(item),
+    })
+    .collect();
+  path.pop();
+  format!("[{}]", items.join(", "))
+}
@@ -11,11 +11,50 @@
 #[cfg(feature = "debug_trace_execution")]
 use crate::debug::Debug;
 use crate::{
+  cache::{CacheOptions, CompileCache},
   chunk::{Chunk, OpCode},
+  compiler::{
+    function_table::FunctionTable,
+    plugin::{OperatorRule, Plugins},
+    resolution::{Declaration, ResolutionTable},
+    stats::CompileStats,
+    work_buffer::WorkBuffer,
+    CompileOptions,
+  },
+  crash::CrashReport,
+  debugger::{Breakpoint, Breakpoints, Watch},
+  native::{
+    error::{self, ErrorKind},
+    inspect,
+    sandbox::SandboxProfile,
+    Capabilities,
+  },
+  object::{
+    NativeFunction, ObjBoundMethod, ObjClass, ObjClosure, ObjEnum, ObjEnumMember, ObjFunction,
+    ObjInstance, ObjList, ObjNative, ObjString, ObjTrait, ObjType, ObjUpvalue,
+  },
+  prelude::*,
+  scanner::Keywords,
   table::Table,
   utils::Init,
-  value::Value,
+  value::{display, Value},
 };
+use core::{ptr::NonNull, task::Poll};
+use hashbrown::{HashMap, HashSet};
+
+use crate::gc;
+#[cfg(feature = "alloc_sites")]
+use crate::gc::AllocSite;
+
+/// How many instructions are executed between checks of the deadline (see [`VM::set_timeout`]).
+#[cfg(feature = "std")]
+pub const TIMEOUT_CHECK_INTERVAL: usize = 1024;
+
+/// Max depth of call frames.
+pub const FRAMES_MAX: usize = 64;
+
+/// Message of [`InterpretError::StackOverflow`].
+const STACK_OVERFLOW: &str = "Stack overflow.";
 
 /// ## InterpretError
 ///
@@ -24,49 +63,335 @@ use crate::{
 #[derive(Debug, Clone)]
 pub enum InterpretError {
   CompileError(String),
+  /// An ordinary runtime error, which could be caught by `try` (see [`error`]).
   RuntimeError(String),
+  /// An ordinary runtime error of a known kind (e.g. a `TypeError`), as raised by the failing
+  /// instruction (or native). It's thrown to `try` blocks with the kind, otherwise it's reported
+  /// as an [`InterpretError::RuntimeError`].
+  Raised(ErrorKind, String),
+  /// An unrecoverable fault (stack overflow, or malformed bytecode),
+  /// which always aborts the script, even inside `try`.
+  Fault(String),
+  /// Too many nested calls (see [`FRAMES_MAX`]), the only fault caused by scripts themselves,
+  /// which is reported as an [`InterpretError::Fault`] (without a crash report).
+  StackOverflow,
+  /// The script ran past its deadline (see [`VM::set_timeout`]),
+  /// which always aborts the script, even inside `try`.
+  Timeout(String),
+  /// The script used up its fuel or heap (see [`VM::set_fuel`] and [`VM::set_heap_limit`]),
+  /// which always aborts the script, even inside `try`.
+  LimitExceeded(String),
+  /// A value thrown (and not caught) inside [`VM::call_function`], e.g. by a callback of `map`,
+  /// with the message of it as an uncaught error. The value is kept by the virtual machine,
+  /// and thrown again to `try` blocks of the caller as it is.
+  Thrown(String),
+}
+
+/// ## RunOutcome
+///
+/// How a run stops (see [`VM::resume`]).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RunOutcome {
+  /// The script is finished, with the value left by it (see [`VM::run`]).
+  Finished(Value),
+  /// The script is paused before the first instruction of a line with a breakpoint
+  /// (or where a step stops), and could be resumed from there.
+  Paused { line: usize },
+}
+
+/// ## Step
+///
+/// How far [`VM::step`] runs, before pausing again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+  /// A single instruction.
+  Instruction,
+  /// To the first instruction of the next line reached (in any function).
+  Line,
+  /// To the first instruction of the next statement reached (in any function).
+  Statement,
+}
+
+impl InterpretError {
+  /// If a script could recover from it (only ordinary runtime errors).
+  pub fn is_recoverable(&self) -> bool {
+    matches!(
+      self,
+      InterpretError::RuntimeError(_) | InterpretError::Raised(..) | InterpretError::Thrown(_)
+    )
+  }
+
+  /// The message of the error.
+  pub fn into_message(self) -> String {
+    match self {
+      InterpretError::CompileError(message)
+      | InterpretError::RuntimeError(message)
+      | InterpretError::Raised(_, message)
+      | InterpretError::Fault(message)
+      | InterpretError::Timeout(message)
+      | InterpretError::LimitExceeded(message)
+      | InterpretError::Thrown(message) => message,
+      InterpretError::StackOverflow => STACK_OVERFLOW.into(),
+    }
+  }
+}
+
+/// ## CallFrame
+///
+/// A struct which represents a single ongoing function call.
+#[derive(Debug, Clone, Copy)]
+pub struct CallFrame {
+  /// The function being called.
+  pub(crate) function: NonNull<ObjFunction>,
+  /// The closure wrapping the function (if it captures variables).
+  pub(crate) closure: Option<NonNull<ObjClosure>>,
+  /// The instruction pointer (actually, the index) of this call.
+  pub(crate) ip: usize,
+  /// Index of the first stack slot this function can use.
+  pub(crate) slot_base: usize,
+  /// Count of arguments passed (missing ones are filled with `nil`).
+  pub(crate) arg_count: usize,
+}
+
+impl CallFrame {
+  /// The chunk of the function being called.
+  pub(crate) fn chunk(&self) -> &Chunk {
+    &unsafe { self.function.as_ref() }.chunk
+  }
+}
+
+/// ## Handler
+///
+/// An ongoing `try` block, which catches values thrown inside it.
+#[derive(Debug, Clone, Copy)]
+pub struct Handler {
+  /// Count of call frames when entering the block.
+  pub(crate) frame_count: usize,
+  /// Size of the stack when entering the block.
+  pub(crate) stack_len: usize,
+  /// Where the `catch` block starts (in the innermost frame when entering the block).
+  pub(crate) catch_ip: usize,
 }
 
 /// ## VM
 ///
 /// A struct which represents the virtual machine.
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct VM {
-  /// A reference to the chunk (or, None).
-  pub(crate) chunk: Chunk,
-  /// The instruction pointer (actually, the index).
-  pub(crate) ip: usize,
+  /// All ongoing function calls (the innermost one on top).
+  pub(crate) frames: Vec<CallFrame>,
   /// The stack of the virtual machine.
   pub(crate) stack: Vec<Value>,
+  /// All ongoing `try` blocks (the innermost one on top).
+  pub(crate) handlers: Vec<Handler>,
+  /// The value of the last [`InterpretError::Thrown`], until it's thrown again (or reported).
+  pub(crate) thrown: Option<Value>,
   /// TODO: Existed strings (used for intern all strings).
   pub(crate) strings: Table,
   /// All globals.
   pub(crate) globals: Table,
+  /// Count of instructions executed since last rebind.
+  pub(crate) instruction_count: usize,
+  /// Peak size of the stack since last rebind.
+  pub(crate) peak_stack_size: usize,
+  /// Name of initializers (`init`), cached to avoid rebuilding it on each call.
+  pub(crate) init_string: ObjString,
+  /// Warnings reported by the last compiling.
+  pub(crate) compile_warnings: Vec<String>,
+  /// Functions of the script compiled (or loaded) last.
+  pub(crate) functions: FunctionTable,
+  /// Files of modules imported by the script compiled last.
+  pub(crate) module_files: Vec<String>,
+  /// Keys of modules run so far (each module runs once, see `import`).
+  pub(crate) imported_modules: HashSet<String>,
+  /// Declarations and references of variables of the script compiled last.
+  pub(crate) resolutions: ResolutionTable,
+  /// Opt-in diagnostics of the compiler.
+  pub(crate) compile_options: CompileOptions,
+  /// Scripts compiled before, keyed by their source.
+  pub(crate) compile_cache: CompileCache,
+  /// Scratch buffers of the parser, reused by each compiling.
+  pub(crate) work_buffer: WorkBuffer,
+  /// Extensions of the compiler registered by the embedder.
+  pub(crate) plugins: Plugins,
+  /// Capabilities granted to gated natives.
+  pub(crate) capabilities: Capabilities,
+  /// Sandbox profile applied last (if any).
+  pub(crate) sandbox: Option<SandboxProfile>,
+  /// Seed of deterministic mode (if it's on).
+  #[cfg(feature = "std")]
+  pub(crate) deterministic: Option<u64>,
+  /// How long each run may take (if it's limited).
+  #[cfg(feature = "std")]
+  pub(crate) timeout: Option<std::time::Duration>,
+  /// Deadline of the ongoing run (if it's limited).
+  #[cfg(feature = "std")]
+  pub(crate) deadline: Option<std::time::Instant>,
+  /// How many instructions each run may execute (if it's limited).
+  pub(crate) fuel: Option<usize>,
+  /// How many bytes of objects each run may allocate (if it's limited).
+  pub(crate) heap_limit: Option<usize>,
+  /// Bytes of objects allocated before the ongoing run (see [`gc::allocated_bytes`]).
+  pub(crate) heap_base: usize,
+  /// Where values of `print` statements are written to.
+  pub(crate) print_fn: fn(&str),
+  /// If ordering values of different types (e.g. `1 < "a"`) is a runtime error
+  /// (instead of `false`).
+  pub(crate) strict_comparison: bool,
+  /// State captured at the internal error of the last run (if any).
+  pub(crate) crash_report: Option<CrashReport>,
+  /// Upvalues still referring to slots of the stack, ordered by their slots.
+  pub(crate) open_upvalues: Vec<NonNull<ObjUpvalue>>,
+  /// Breakpoints of lines to pause at (see [`VM::set_breakpoint`]),
+  /// with how many times each of them is hit.
+  pub(crate) breakpoints: Breakpoints,
+  /// Line the ongoing run is paused at (if any).
+  pub(crate) paused_at: Option<usize>,
+  /// How far the ongoing step runs (if it's stepping, see [`VM::step`]).
+  pub(crate) stepping: Option<Step>,
+  /// Expressions evaluated at each stop (see [`VM::add_watch`]).
+  pub(crate) watches: Vec<Watch>,
+  /// If runs failed by runtime errors are kept for inspection (see [`VM::set_post_mortem`]).
+  pub(crate) post_mortem: bool,
+  /// Runtime error the kept run failed by (if any).
+  pub(crate) failure: Option<String>,
 }
 
 impl VM {
   /// Interpret from string.
-  pub fn interpret(&mut self, src: String) -> Result<(), InterpretError> {
-    self.rebind(Chunk::init());
+  ///
+  /// Returns the value of the final expression statement (if any) of the script.
+  pub fn interpret(&mut self, src: String) -> Result<Option<Value>, InterpretError> {
+    let has_result = self.compile(src)?;
+    let value = self.run()?;
+    Ok(has_result.then_some(value))
+  }
+
+  /// Compile the source (without running it),
+  /// then return the disassembly of the script and all functions in it.
+  pub fn disassemble_source(&mut self, src: String) -> Result<String, InterpretError> {
     self.compile(src)?;
-    self.run()
+    let function = unsafe { self.frame().function.as_ref() };
+    Ok(function.disassembly())
+  }
+
+  /// Compile the file (without running it),
+  /// then return the disassembly of the script and all functions in it.
+  #[cfg(feature = "std")]
+  pub fn disassemble_file(&mut self, path: String) -> Result<String, InterpretError> {
+    let Ok(content) = std::fs::read_to_string(&path) else {
+      return Err(InterpretError::CompileError(format!(
+        "Failed to read file `{}`.",
+        path
+      )));
+    };
+    self.compile_source(content, Some(&path))?;
+    let function = unsafe { self.frame().function.as_ref() };
+    Ok(function.disassembly())
+  }
+
+  /// Compile the source (without running it),
+  /// then return stats of the script and all functions in it (see [`CompileStats`]).
+  pub fn compile_stats(&mut self, src: String) -> Result<CompileStats, InterpretError> {
+    self.compile(src)?;
+    let function = unsafe { self.frame().function.as_ref() };
+    Ok(CompileStats::of(function, &self.functions))
+  }
+
+  /// Compile the file (without running it),
+  /// then return stats of the script and all functions in it (see [`CompileStats`]).
+  #[cfg(feature = "std")]
+  pub fn compile_stats_file(&mut self, path: String) -> Result<CompileStats, InterpretError> {
+    let Ok(content) = std::fs::read_to_string(&path) else {
+      return Err(InterpretError::CompileError(format!(
+        "Failed to read file `{}`.",
+        path
+      )));
+    };
+    self.compile_source(content, Some(&path))?;
+    let function = unsafe { self.frame().function.as_ref() };
+    Ok(CompileStats::of(function, &self.functions))
   }
 
   /// Interpret from string, but only show tokens.
+  #[cfg(feature = "std")]
   pub fn interpret_to_token(&mut self, src: String) -> Result<(), InterpretError> {
     self.compile_to_token(src)
   }
 
   /// Interpret from file(path).
-  pub fn interpret_file(&mut self, path: String) -> Result<(), InterpretError> {
+  #[cfg(feature = "std")]
+  ///
+  /// Diagnostics are located as `path:line:column`.
+  pub fn interpret_file(&mut self, path: String) -> Result<Option<Value>, InterpretError> {
     use std::fs::read_to_string;
-    if let Ok(content) = read_to_string(path) {
-      self.interpret_to_token(content)
-    } else {
-      Err(InterpretError::CompileError(
-        "Failed to interpret from file.".into(),
-      ))
+    let Ok(content) = read_to_string(&path) else {
+      return Err(InterpretError::CompileError(format!(
+        "Failed to read file `{}`.",
+        path
+      )));
+    };
+    let has_result = self.compile_source(content, Some(&path))?;
+    let value = self.run()?;
+    Ok(has_result.then_some(value))
+  }
+
+  /// Hot-reload the file (see [`VM::reload`]).
+  #[cfg(feature = "std")]
+  pub fn reload_file(&mut self, path: String) -> Result<(), InterpretError> {
+    let Ok(content) = std::fs::read_to_string(&path) else {
+      return Err(InterpretError::CompileError(format!(
+        "Failed to read file `{}`.",
+        path
+      )));
+    };
+    self.reload_source(content, Some(&path))
+  }
+
+  /// Hot-reload the source: compile it, then run it, so the functions, classes and enums it defines
+  /// replace the previous ones, while globals already holding data (e.g. counters, instances)
+  /// keep their values.
+  ///
+  /// Globals holding members of enums are reassigned as well, so they're members of the enums
+  /// reloaded (as compared by `==` and `match`) rather than of the previous ones.
+  ///
+  /// It's atomic: if compiling or running fails, no global is changed.
+  ///
+  /// Top-level statements run again (e.g. `print`), definitions removed from the source are kept,
+  /// and existing instances keep the classes they were created by.
+  pub fn reload(&mut self, src: String) -> Result<(), InterpretError> {
+    self.reload_source(src, None)
+  }
+
+  /// Hot-reload the source, diagnostics are located in `file` (if given).
+  pub(crate) fn reload_source(
+    &mut self,
+    src: String,
+    file: Option<&str>,
+  ) -> Result<(), InterpretError> {
+    self.compile_source(src, file)?;
+    let before = self.globals.clone();
+    if let Err(error) = self.run() {
+      self.globals = before;
+      return Err(error);
+    }
+    let is_definition = |value: &Value| {
+      matches!(
+        value.obj_type(),
+        Ok(
+          ObjType::Function
+            | ObjType::Closure
+            | ObjType::Class
+            | ObjType::Native
+            | ObjType::Enum
+            | ObjType::EnumMember
+        )
+      )
+    };
+    for (name, value) in before.iter().filter(|(_, value)| !is_definition(value)) {
+      self.globals.set(name.clone(), *value);
     }
+    Ok(())
   }
 }
 
@@ -79,9 +404,7 @@ impl VM {
       self.stack.push(op(value)?);
       Ok(())
     } else {
-      Err(InterpretError::RuntimeError(
-        "Operate on an empty stack.".into(),
-      ))
+      Err(InterpretError::Fault("Operate on an empty stack.".into()))
     }
   }
 
@@ -93,32 +416,571 @@ impl VM {
       self.stack.push(op(a, b)?);
       Ok(())
     } else {
-      Err(InterpretError::RuntimeError(
-        "Operate on an empty stack.".into(),
-      ))
+      Err(InterpretError::Fault("Operate on an empty stack.".into()))
     }
   }
+
+  /// Order 2 operands on the top of the stack,
+  /// which must be of the same type in strict comparison mode.
+  fn compare_op(&mut self, op: fn(&Value, &Value) -> bool) -> Result<(), InterpretError> {
+    let strict = self.strict_comparison;
+    self.binary_op(|l, r| {
+      let (lhs, rhs) = (inspect::type_name(&l), inspect::type_name(&r));
+      if strict && lhs != rhs {
+        return Err(InterpretError::Raised(
+          ErrorKind::Type,
+          format!(
+            "Operands must be of the same type to compare, got `{}` and `{}`.",
+            lhs, rhs
+          ),
+        ));
+      }
+      Ok(Value::bool_val(op(&l, &r)))
+    })
+  }
 }
 
 impl VM {
+  /// The innermost call frame.
+  fn frame(&self) -> &CallFrame {
+    self.frames.last().unwrap()
+  }
+
+  /// The innermost call frame (mutable).
+  fn frame_mut(&mut self) -> &mut CallFrame {
+    self.frames.last_mut().unwrap()
+  }
+
+  /// Index of the value `distance` slots down from the top of the stack.
+  fn stack_index(&self, distance: usize) -> Result<usize, InterpretError> {
+    self
+      .stack
+      .len()
+      .checked_sub(distance + 1)
+      .ok_or_else(|| InterpretError::Fault("Stack underflow.".into()))
+  }
+
+  /// Value `distance` slots down from the top of the stack (without popping it).
+  fn peek(&self, distance: usize) -> Result<Value, InterpretError> {
+    Ok(self.stack[self.stack_index(distance)?])
+  }
+
+  /// Replace the value on top of the stack.
+  fn set_top(&mut self, value: Value) -> Result<(), InterpretError> {
+    let index = self.stack_index(0)?;
+    self.stack[index] = value;
+    Ok(())
+  }
+
+  /// Pop the value on top of the stack.
+  fn pop(&mut self) -> Result<Value, InterpretError> {
+    self
+      .stack
+      .pop()
+      .ok_or_else(|| InterpretError::Fault("Stack underflow.".into()))
+  }
+
   /// Read a byte from the chunk (update ip).
-  fn read_byte(&mut self) -> u8 {
-    let byte = self.chunk.code[self.ip];
-    self.ip += 1;
-    byte
+  fn read_byte(&mut self) -> Result<u8, InterpretError> {
+    let frame = self.frame_mut();
+    frame.ip += 1;
+    frame
+      .chunk()
+      .code
+      .get(frame.ip - 1)
+      .copied()
+      .ok_or_else(|| InterpretError::Fault("Unexpected end of bytecode.".into()))
   }
 
   /// Read a constant from the chunk (update ip).
-  fn read_constant(&mut self) -> Value {
-    let index = self.chunk.code[self.ip];
-    self.ip += 1;
-    self.chunk.constants.values[index as usize]
+  fn read_constant(&mut self) -> Result<Value, InterpretError> {
+    let index = self.read_byte()? as usize;
+    let constants = &self.frame().chunk().constants.values;
+    constants
+      .get(index)
+      .copied()
+      .ok_or_else(|| InterpretError::Fault(format!("Undefined constant at index `{}`.", index)))
   }
 
   /// Read a short(u16) from the chunk (update ip).
-  fn read_u16(&mut self) -> u16 {
-    self.ip += 2;
-    u16::from_be_bytes([self.chunk.code[self.ip - 2], self.chunk.code[self.ip - 1]])
+  fn read_u16(&mut self) -> Result<u16, InterpretError> {
+    Ok(u16::from_be_bytes([self.read_byte()?, self.read_byte()?]))
+  }
+
+  /// Absolute stack index of the given local slot.
+  ///
+  /// All local addressing goes through the frame base (`slot_base`) of the
+  /// innermost frame, as slot `0` of each frame is its callee (or receiver).
+  fn local_index(&self, slot: usize) -> Option<usize> {
+    let index = self.frame().slot_base + slot;
+    (index < self.stack.len()).then_some(index)
+  }
+
+  /// Push the local at the given slot.
+  fn get_local(&mut self, slot: usize) -> Result<(), InterpretError> {
+    if let Some(index) = self.local_index(slot) {
+      self.stack.push(self.stack[index]);
+      Ok(())
+    } else {
+      Err(self.undefined_local(slot))
+    }
+  }
+
+  /// Assign the top of the stack to the local at the given slot (without popping it).
+  fn set_local(&mut self, slot: usize) -> Result<(), InterpretError> {
+    if let Some(index) = self.local_index(slot) {
+      self.stack[index] = self.peek(0)?;
+      Ok(())
+    } else {
+      Err(self.undefined_local(slot))
+    }
+  }
+
+  /// Fault of accessing the local at the given slot beyond the stack,
+  /// which names the local if the script compiled last declares it.
+  fn undefined_local(&self, slot: usize) -> InterpretError {
+    match self.local_declaration(self.frame(), slot) {
+      Some(local) => InterpretError::Fault(format!(
+        "Undefined local variable `{}` (declared at line {}).",
+        local.name, local.location.line
+      )),
+      None => InterpretError::Fault(format!("Undefined local variable at slot `{}`.", slot)),
+    }
+  }
+
+  /// Declaration of the local at the given slot of the frame (where the frame is at),
+  /// if the script compiled last declares it.
+  pub(crate) fn local_declaration(&self, frame: &CallFrame, slot: usize) -> Option<&Declaration> {
+    let function = self.function_name(frame)?;
+    let line = frame
+      .chunk()
+      .debug_info
+      .location(frame.ip.saturating_sub(1))
+      .line;
+    self.resolutions.local_at(function, slot, line)
+  }
+
+  /// Declaration of the variable captured by the upvalue of the given index of the frame,
+  /// if the script compiled last declares it.
+  pub(crate) fn capture_declaration(
+    &self,
+    frame: &CallFrame,
+    index: usize,
+  ) -> Option<&Declaration> {
+    self
+      .resolutions
+      .captured_by(self.function_name(frame)?, index)
+  }
+
+  /// Qualified name of the function of the frame (empty for the script),
+  /// if the script compiled last defines it.
+  fn function_name(&self, frame: &CallFrame) -> Option<&str> {
+    if frame.function == self.frames.first()?.function {
+      return Some("");
+    }
+    self
+      .functions
+      .entries
+      .iter()
+      .find(|entry| entry.function == frame.function)
+      .map(|entry| entry.name.as_str())
+  }
+
+  /// Move ip of the innermost frame by the given (signed) offset.
+  fn jump_by(&mut self, offset: u16) {
+    let frame = self.frame_mut();
+    frame.ip = (frame.ip as isize + offset as i16 as isize) as usize;
+  }
+}
+
+impl VM {
+  /// Call the given value with `arg_count` arguments on top of the stack.
+  fn call_value(&mut self, callee: Value, arg_count: usize) -> Result<(), InterpretError> {
+    let callee_index = self.stack_index(arg_count)?;
+    if callee.is_function() || callee.is_closure() {
+      self.call(callee, arg_count)
+    } else if let Ok(bound) = callee.as_bound_method() {
+      let bound = unsafe { bound.as_ref() };
+      self.stack[callee_index] = bound.receiver;
+      self.call(bound.method, arg_count)
+    } else if let Ok(class) = callee.as_class() {
+      if unsafe { class.as_ref() }.is_trait {
+        return Err(InterpretError::RuntimeError(format!(
+          "Can't instantiate trait `{}`.",
+          unsafe { class.as_ref() }.name
+        )));
+      }
+      let instance = ObjInstance::new(class);
+      self.stack[callee_index] = Value::obj_val(instance.cast_to_obj_ptr());
+      let class = unsafe { class.as_ref() };
+      if let Some(initializer) = class.methods.get(&self.init_string) {
+        let initializer = *initializer;
+        let initializer_ref = unsafe { initializer.as_callable()?.0.as_ref() };
+        if arg_count < initializer_ref.required_arity || arg_count > initializer_ref.arity {
+          return Err(InterpretError::Raised(
+            ErrorKind::Arity,
+            format!(
+              "Initializer of class `{}` expected {} arguments but got {}.",
+              class.name,
+              initializer_ref.arity_range(),
+              arg_count
+            ),
+          ));
+        }
+        self.call(initializer, arg_count)
+      } else if arg_count != 0 {
+        Err(InterpretError::Raised(
+          ErrorKind::Arity,
+          format!(
+            "Class `{}` has no initializer, expected 0 arguments but got {}.",
+            class.name, arg_count
+          ),
+        ))
+      } else {
+        Ok(())
+      }
+    } else if let Ok(native) = callee.as_native() {
+      self.call_native(unsafe { native.as_ref() }, arg_count)
+    } else {
+      Err(InterpretError::Raised(
+        ErrorKind::Type,
+        "Can only call functions and classes.".into(),
+      ))
+    }
+  }
+
+  /// Call the native function directly, then replace callee and arguments with the result.
+  ///
+  /// Missing (optional) arguments are filled with `nil`.
+  fn call_native(&mut self, native: &ObjNative, arg_count: usize) -> Result<(), InterpretError> {
+    if arg_count < native.required_arity || arg_count > native.arity {
+      return Err(InterpretError::Raised(
+        ErrorKind::Arity,
+        format!(
+          "Native function `{}` expected {} arguments but got {}.",
+          native.name,
+          native.arity_range(),
+          arg_count
+        ),
+      ));
+    }
+    if let Some(capability) = native.capability {
+      if !self.capabilities.allows(capability) {
+        return Err(InterpretError::Raised(
+          ErrorKind::Capability,
+          format!(
+            "Native function `{}` needs the `{}` capability.",
+            native.name, capability
+          ),
+        ));
+      }
+    }
+    let args_start = self.stack.len() - arg_count;
+    for _ in arg_count..native.arity {
+      self.stack.push(Value::nil_val());
+    }
+    #[cfg(feature = "alloc_sites")]
+    let site = gc::set_site(Some(AllocSite::Native(native.name.clone())));
+    let result = match native.function {
+      NativeFunction::Plain(function) => function(&self.stack[args_start..]),
+      // the stack grows while calling back, so arguments are copied out of it
+      NativeFunction::Callback(function) => {
+        let args = self.stack[args_start..].to_vec();
+        function(self, &args)
+      }
+    };
+    #[cfg(feature = "alloc_sites")]
+    gc::set_site(site);
+    let result = result?;
+    self.stack.truncate(args_start - 1);
+    self.stack.push(result);
+    Ok(())
+  }
+
+  /// Replace the list of arguments (built by `ExtendList`) on top of the stack with its items,
+  /// which are then arguments of a call as usual, and return their count.
+  fn spread_args(&mut self) -> Result<usize, InterpretError> {
+    let args = self.pop()?.as_list()?;
+    self
+      .stack
+      .extend_from_slice(&unsafe { args.as_ref() }.items);
+    Ok(unsafe { args.as_ref() }.items.len())
+  }
+
+  /// Invoke method `name` of the receiver below the arguments, without
+  /// creating an intermediate bound method.
+  ///
+  /// Fields shadow methods, so a field holding a function is called as well.
+  fn invoke(&mut self, name: &ObjString, arg_count: usize) -> Result<(), InterpretError> {
+    let callee_index = self.stack_index(arg_count)?;
+    let instance = self.stack[callee_index].as_instance().map_err(|_| {
+      InterpretError::Raised(ErrorKind::Type, "Only instances have methods.".into())
+    })?;
+    let instance = unsafe { instance.as_ref() };
+    if let Some(&field) = instance.fields.get(name) {
+      self.stack[callee_index] = field;
+      return self.call_value(field, arg_count);
+    }
+    self.invoke_from_class(instance.class(), name, arg_count)
+  }
+
+  /// Call the method `name` of the class, with the receiver (below arguments) as `this`.
+  fn invoke_from_class(
+    &mut self,
+    class: &ObjClass,
+    name: &ObjString,
+    arg_count: usize,
+  ) -> Result<(), InterpretError> {
+    if let Some(method) = class.methods.get(name) {
+      self.call(*method, arg_count)
+    } else if class.getters.get(name).is_some() {
+      Err(InterpretError::RuntimeError(format!(
+        "Property `{}` is a getter, wrap it in parentheses to call its value.",
+        name
+      )))
+    } else {
+      Err(InterpretError::Raised(
+        ErrorKind::Property,
+        format!("Undefined property `{}`.", name),
+      ))
+    }
+  }
+
+  /// Replace the instance on top of the stack with its method `name` bound to it.
+  ///
+  /// Bound methods are cached in the instance.
+  fn bind_method(
+    &mut self,
+    instance: &mut ObjInstance,
+    name: &ObjString,
+  ) -> Result<(), InterpretError> {
+    if let Some(&bound) = instance.bound_methods.get(name) {
+      self.set_top(bound)?;
+      Ok(())
+    } else if let Some(method) = instance.class().methods.get(name) {
+      let receiver = self.peek(0)?;
+      let bound = ObjBoundMethod::new(receiver, *method);
+      let bound = Value::obj_val(bound.cast_to_obj_ptr());
+      instance.bound_methods.set(name.to_owned(), bound);
+      self.set_top(bound)?;
+      Ok(())
+    } else {
+      Err(InterpretError::Raised(
+        ErrorKind::Property,
+        format!("Undefined property `{}`.", name),
+      ))
+    }
+  }
+
+  /// Replace the instance on top of the stack with the method `name` of its superclass
+  /// bound to it (or the value of its getter).
+  ///
+  /// Unlike [`VM::bind_method`], it's never cached, as the instance's own method may differ.
+  fn bind_super_method(
+    &mut self,
+    superclass: &ObjClass,
+    name: &ObjString,
+  ) -> Result<(), InterpretError> {
+    if let Some(getter) = superclass.getters.get(name) {
+      return self.call(*getter, 0);
+    }
+    let Some(method) = superclass.methods.get(name) else {
+      return Err(InterpretError::Raised(
+        ErrorKind::Property,
+        format!("Undefined property `{}`.", name),
+      ));
+    };
+    let bound = ObjBoundMethod::new(self.peek(0)?, *method);
+    self.set_top(Value::obj_val(bound.cast_to_obj_ptr()))
+  }
+
+  /// Bind the function on top of the stack into the given table of the class below it.
+  fn define_method<F>(&mut self, table_of: F) -> Result<(), InterpretError>
+  where
+    F: Fn(&mut ObjClass) -> &mut Table,
+  {
+    let name = self.read_constant()?.as_string()?;
+    let method = self.pop()?;
+    let mut class = self.peek(0)?.as_class()?;
+    let class = unsafe { class.as_mut() };
+    table_of(class).set(unsafe { name.as_ref() }.to_owned(), method);
+    Ok(())
+  }
+
+  /// Push a new call frame for the given function (or closure).
+  ///
+  /// Missing (defaulted) arguments are filled with `nil`, the count of arguments passed is kept
+  /// by the frame (see [`OpCode::MissingArgument`]).
+  fn call(&mut self, callee: Value, arg_count: usize) -> Result<(), InterpretError> {
+    let (function, closure) = callee.as_callable()?;
+    let function_ref = unsafe { function.as_ref() };
+    if arg_count < function_ref.required_arity || arg_count > function_ref.arity {
+      return Err(InterpretError::Raised(
+        ErrorKind::Arity,
+        format!(
+          "Function `{}` expected {} arguments but got {}.",
+          function_ref.name(),
+          function_ref.arity_range(),
+          arg_count
+        ),
+      ));
+    }
+    if self.frames.len() == FRAMES_MAX {
+      return Err(InterpretError::StackOverflow);
+    }
+    for _ in arg_count..function_ref.arity {
+      self.stack.push(Value::nil_val());
+    }
+    self.frames.push(CallFrame {
+      function,
+      closure,
+      ip: 0,
+      slot_base: self.stack.len() - function_ref.arity - 1,
+      arg_count,
+    });
+    Ok(())
+  }
+}
+
+impl VM {
+  /// Call the value (a function, closure, bound method, class or native) with the arguments,
+  /// then run the call to its end, e.g. for natives calling back functions passed to them.
+  ///
+  /// It could be called by the host between runs as well, to call functions defined by scripts.
+  ///
+  /// Breakpoints and budgets are ignored inside the call, and `try` blocks around it never catch
+  /// errors of it: they're returned (with calls inside it unwound), for the caller to handle.
+  /// Values thrown (and not caught) inside it are returned as [`InterpretError::Thrown`],
+  /// so they're thrown again to `try` blocks of the caller as they are (and runtime errors keep
+  /// their kinds). Called by the host between runs, errors are returned as they're reported.
+  pub fn call_function(&mut self, callee: Value, args: &[Value]) -> Result<Value, InterpretError> {
+    let stack_len = self.stack.len();
+    let frame_count = self.frames.len();
+    self.stack.push(callee);
+    self.stack.extend_from_slice(args);
+    let handlers = core::mem::take(&mut self.handlers);
+    let result = self
+      .call_value(callee, args.len())
+      .and_then(|()| self.run_nested(frame_count));
+    // an aborted run (e.g. timed out) is discarded as a whole
+    if self.frames.len() < frame_count {
+      return result.map(|()| Value::nil_val());
+    }
+    self.handlers = handlers;
+    if let Err(error) = result {
+      self.frames.truncate(frame_count);
+      self.close_upvalues(stack_len);
+      self.stack.truncate(stack_len);
+      // called by the host between runs, errors are returned as they're reported
+      return Err(match frame_count {
+        0 => self.reported(error),
+        _ => error,
+      });
+    }
+    let value = self.stack.pop().unwrap_or_default();
+    self.stack.truncate(stack_len);
+    Ok(value)
+  }
+
+  /// The error as it's reported once it stops the script, e.g. a `TypeError` (see
+  /// [`InterpretError::Raised`]) is an ordinary [`InterpretError::RuntimeError`].
+  fn reported(&mut self, error: InterpretError) -> InterpretError {
+    match error {
+      InterpretError::Raised(_, message) => InterpretError::RuntimeError(message),
+      InterpretError::Thrown(message) => {
+        self.thrown = None;
+        InterpretError::RuntimeError(message)
+      }
+      InterpretError::StackOverflow => InterpretError::Fault(STACK_OVERFLOW.into()),
+      error => error,
+    }
+  }
+
+  /// Run until calls beyond the given count of frames return.
+  ///
+  /// Runtime errors are thrown to `try` blocks inside these calls (if any), or returned as is.
+  fn run_nested(&mut self, frame_count: usize) -> Result<(), InterpretError> {
+    while self.frames.len() > frame_count {
+      #[cfg(feature = "std")]
+      self.check_deadline()?;
+      self.check_limits()?;
+      #[cfg(feature = "alloc_sites")]
+      let site = gc::set_site(self.current_line().map(AllocSite::Line));
+      let result = self
+        .execute_instruction()
+        .or_else(|error| self.catch(error));
+      self.instruction_count += 1;
+      self.peak_stack_size = self.peak_stack_size.max(self.stack.len());
+      #[cfg(feature = "alloc_sites")]
+      gc::set_site(site);
+      result?;
+    }
+    Ok(())
+  }
+}
+
+impl VM {
+  /// Push a closure of the function, capturing variables described by its upvalues.
+  ///
+  /// Locals of the innermost frame are captured by (shared) open upvalues,
+  /// while upvalues of the innermost closure are captured as they are.
+  ///
+  /// The closure is pushed first, as a local function captures itself (to recur)
+  /// from the slot the closure is pushed into.
+  fn make_closure(&mut self, function: NonNull<ObjFunction>) -> Result<(), InterpretError> {
+    let mut closure = ObjClosure::new(function)
+      .cast_to_obj_ptr()
+      .cast::<ObjClosure>();
+    self.stack.push(Value::obj_val(closure.cast()));
+    for upvalue in &unsafe { function.as_ref() }.upvalues {
+      let captured = if upvalue.is_local {
+        let slot = self.frame().slot_base + upvalue.index;
+        if slot >= self.stack.len() {
+          return Err(self.undefined_local(upvalue.index));
+        }
+        self.capture_upvalue(slot)
+      } else {
+        self.upvalue(upvalue.index)?
+      };
+      unsafe { closure.as_mut() }.upvalues.push(captured);
+    }
+    Ok(())
+  }
+
+  /// The open upvalue of the stack slot, created if there's none yet,
+  /// so closures capturing the same variable share it.
+  fn capture_upvalue(&mut self, slot: usize) -> NonNull<ObjUpvalue> {
+    let position = self
+      .open_upvalues
+      .partition_point(|upvalue| unsafe { upvalue.as_ref() }.slot < slot);
+    if let Some(&upvalue) = self.open_upvalues.get(position) {
+      if unsafe { upvalue.as_ref() }.slot == slot {
+        return upvalue;
+      }
+    }
+    let upvalue = ObjUpvalue::new(slot).cast_to_obj_ptr().cast();
+    self.open_upvalues.insert(position, upvalue);
+    upvalue
+  }
+
+  /// Upvalue at the given index of the closure of the innermost frame.
+  fn upvalue(&self, index: usize) -> Result<NonNull<ObjUpvalue>, InterpretError> {
+    self
+      .frame()
+      .closure
+      .and_then(|closure| unsafe { closure.as_ref() }.upvalues.get(index).copied())
+      .ok_or_else(|| InterpretError::Fault(format!("Undefined upvalue at index `{}`.", index)))
+  }
+
+  /// Close open upvalues of slots from the given one, as their locals are leaving the stack.
+  fn close_upvalues(&mut self, from_slot: usize) {
+    let position = self
+      .open_upvalues
+      .partition_point(|upvalue| unsafe { upvalue.as_ref() }.slot < from_slot);
+    for mut upvalue in self.open_upvalues.drain(position..) {
+      let upvalue = unsafe { upvalue.as_mut() };
+      upvalue.closed = Some(self.stack.get(upvalue.slot).copied().unwrap_or_default());
+    }
   }
 }
 
@@ -130,7 +992,8 @@ impl VM {
   #[cfg(feature = "debug_trace_execution")]
   #[allow(dead_code)]
   fn disassemble_instruction(&self) -> Result<(), InterpretError> {
-    self.chunk.disassemble_instruction(self.ip);
+    let frame = self.frame();
+    frame.chunk().disassemble_instruction(frame.ip);
     Ok(())
   }
 
@@ -155,10 +1018,11 @@ impl VM {
 impl VM {
   /// Link the given chunk to the virtual machine, then interpret it.
   pub fn interpret_chunk(&mut self, chunk: Chunk) -> Result<(), InterpretError> {
+    #[cfg(feature = "std")]
     println!("-x-x-x-x- Called : Chunk Interpreter -x-x-x-x-");
-    self.chunk = chunk;
-    self.ip = 0;
-    if let Ok(()) = self.run() {
+    self.rebind(chunk);
+    if self.run().is_ok() {
+      #[cfg(feature = "std")]
       println!("-x-x-x-x- End of : Chunk Interpreter -x-x-x-x-\n");
       return Ok(());
     }
@@ -168,9 +1032,92 @@ impl VM {
   }
 
   /// Run the virtual machine (with a valid chunk reference).
-  pub fn run(&mut self) -> Result<(), InterpretError> {
-    let mut result = Ok(());
-    while self.ip < self.chunk.code.len() {
+  ///
+  /// Returns the value returned by the top-level function.
+  pub fn run(&mut self) -> Result<Value, InterpretError> {
+    loop {
+      if let Poll::Ready(value) = self.run_for(usize::MAX)? {
+        return Ok(value);
+      }
+    }
+  }
+
+  /// Run at most `budget` instructions, then yield to the host if the script isn't finished.
+  ///
+  /// Returns `Pending` if there're instructions left, so hosts (e.g. a game loop or an async task)
+  /// could interleave scripts with other work, and poll again to resume where it yields.
+  /// Nothing else should be compiled or run by the virtual machine until it's `Ready`.
+  ///
+  /// The deadline (see [`VM::set_timeout`]) counts from the first poll, across all polls.
+  pub fn run_for(&mut self, budget: usize) -> Result<Poll<Value>, InterpretError> {
+    #[cfg(feature = "std")]
+    if self.deadline.is_none() {
+      self.deadline = self
+        .timeout
+        .map(|timeout| std::time::Instant::now() + timeout);
+    }
+    let result = self.execute(budget);
+    #[cfg(feature = "std")]
+    if !matches!(result, Ok(Poll::Pending)) {
+      self.deadline = None;
+    }
+    result
+  }
+
+  /// Run until the script is finished, or paused at a breakpoint (see [`VM::set_breakpoint`]).
+  ///
+  /// A paused script is resumed by calling it again, which runs the instruction it's paused before
+  /// (without pausing at the same breakpoint again). Unlike it, [`VM::run`] ignores breakpoints.
+  pub fn resume(&mut self) -> Result<RunOutcome, InterpretError> {
+    loop {
+      match self.run_for(usize::MAX)? {
+        Poll::Ready(value) => return Ok(RunOutcome::Finished(value)),
+        Poll::Pending => {
+          if let Some(line) = self.paused_at {
+            return Ok(RunOutcome::Paused { line });
+          }
+        }
+      }
+    }
+  }
+
+  /// Run until where the step stops (see [`Step`]), the script is finished,
+  /// or paused at a breakpoint.
+  ///
+  /// The instruction it's at (paused or not) is always run first, so each step makes progress.
+  pub fn step(&mut self, step: Step) -> Result<RunOutcome, InterpretError> {
+    if self.paused_at.is_none() {
+      self.paused_at = self.current_line();
+    }
+    self.stepping = Some(step);
+    let outcome = self.resume();
+    self.stepping = None;
+    outcome
+  }
+
+  /// Line of the next instruction of the innermost frame (if any).
+  fn current_line(&self) -> Option<usize> {
+    let frame = self.frames.last()?;
+    Some(frame.chunk().debug_info.location(frame.ip).line)
+  }
+
+  /// Execute at most `budget` instructions, stopping early at breakpoints (and steps).
+  fn execute(&mut self, budget: usize) -> Result<Poll<Value>, InterpretError> {
+    // a run kept for post-mortem inspection never continues
+    if let Some(message) = &self.failure {
+      return Err(InterpretError::RuntimeError(message.clone()));
+    }
+    for _ in 0..budget {
+      if self.frames.is_empty() {
+        break;
+      }
+      if let Some(line) = self.stop_hit() {
+        self.paused_at = Some(line);
+        return Ok(Poll::Pending);
+      }
+      #[cfg(feature = "std")]
+      self.check_deadline()?;
+      self.check_limits()?;
       #[cfg(feature = "debug_print_code")]
       {
         #[cfg(feature = "debug_trace_stack")]
@@ -178,20 +1125,127 @@ impl VM {
         #[cfg(feature = "debug_trace_execution")]
         self.disassemble_instruction()?;
       }
-      result = self.run_one_step();
-      if result.is_err() {
-        break;
-      }
+      #[cfg(feature = "alloc_sites")]
+      gc::set_site(self.current_line().map(AllocSite::Line));
+      let result = self.run_one_step();
+      self.instruction_count += 1;
+      self.peak_stack_size = self.peak_stack_size.max(self.stack.len());
+      #[cfg(feature = "alloc_sites")]
+      gc::set_site(None);
+      result?;
     }
-    result
+    if !self.frames.is_empty() {
+      return Ok(Poll::Pending);
+    }
+    Ok(Poll::Ready(self.stack.pop().unwrap_or_default()))
+  }
+
+  /// Line of the next instruction, if it's the first one of a line with a breakpoint,
+  /// or where the ongoing step stops (and the run isn't just resumed from there).
+  ///
+  /// Instructions in the middle of a line (e.g. after returning from a call) never pause,
+  /// unless it's stepping by instruction.
+  fn stop_hit(&mut self) -> Option<usize> {
+    if self.paused_at.take().is_some() || (self.breakpoints.is_empty() && self.stepping.is_none()) {
+      return None;
+    }
+    let frame = self.frames.last()?;
+    let debug_info = &frame.chunk().debug_info;
+    let line = debug_info.location(frame.ip).line;
+    let starts_line = debug_info.starts_line(frame.ip);
+    let stepped = match self.stepping {
+      Some(Step::Instruction) => true,
+      Some(Step::Line) => starts_line,
+      Some(Step::Statement) => debug_info.starts_statement(frame.ip),
+      None => false,
+    };
+    (stepped || starts_line && self.breakpoint_hit(line)).then_some(line)
+  }
+
+  /// Abort the script if it's past its deadline
+  /// (checked every [`TIMEOUT_CHECK_INTERVAL`] instructions).
+  #[cfg(feature = "std")]
+  fn check_deadline(&mut self) -> Result<(), InterpretError> {
+    let (Some(deadline), Some(timeout)) = (self.deadline, self.timeout) else {
+      return Ok(());
+    };
+    if !self
+      .instruction_count
+      .is_multiple_of(TIMEOUT_CHECK_INTERVAL)
+      || std::time::Instant::now() < deadline
+    {
+      return Ok(());
+    }
+    let message = self.locate(format!("Script timed out after {}ms.", timeout.as_millis()));
+    self.reset_execution_state();
+    Err(InterpretError::Timeout(message))
+  }
+
+  /// Abort the script if it used up its fuel or heap
+  /// (see [`VM::set_fuel`] and [`VM::set_heap_limit`]).
+  #[inline]
+  fn check_limits(&mut self) -> Result<(), InterpretError> {
+    if self.fuel.is_none() && self.heap_limit.is_none() {
+      return Ok(());
+    }
+    let message = if let Some(fuel) = self.fuel.filter(|&fuel| self.instruction_count >= fuel) {
+      format!("Script ran out of fuel after {} instructions.", fuel)
+    } else if let Some(limit) = self
+      .heap_limit
+      .filter(|&limit| gc::allocated_bytes() - self.heap_base > limit)
+    {
+      format!("Script allocated more than {} bytes of objects.", limit)
+    } else {
+      return Ok(());
+    };
+    let message = self.locate(message);
+    self.reset_execution_state();
+    Err(InterpretError::LimitExceeded(message))
   }
 
   #[inline]
   fn run_one_step(&mut self) -> Result<(), InterpretError> {
-    let raw_result = match self.read_byte().into() {
+    match self
+      .execute_instruction()
+      .or_else(|error| self.catch(error))
+    {
+      Err(InterpretError::RuntimeError(message) | InterpretError::Raised(_, message)) => {
+        self.runtime_error(message)
+      }
+      Err(InterpretError::Thrown(message)) => {
+        self.thrown = None;
+        self.runtime_error(message)
+      }
+      Err(InterpretError::StackOverflow) => {
+        let message = self.locate(STACK_OVERFLOW.into());
+        self.reset_execution_state();
+        Err(InterpretError::Fault(message))
+      }
+      Err(InterpretError::Fault(message)) => {
+        self.crash_report = CrashReport::capture(self, &message);
+        let message = self.locate(message);
+        self.reset_execution_state();
+        Err(InterpretError::Fault(message))
+      }
+      result => result,
+    }
+  }
+
+  /// Decode then execute the next instruction of the innermost frame.
+  ///
+  /// Runtime errors are returned as is, and will be located by `run_one_step`.
+  fn execute_instruction(&mut self) -> Result<(), InterpretError> {
+    let instruction = self.read_byte()?;
+    let Ok(op_code) = OpCode::try_from(instruction) else {
+      return Err(InterpretError::Fault(format!(
+        "Unknown opcode `{}`.",
+        instruction
+      )));
+    };
+    match op_code {
       /* Constants */
       OpCode::Constant => {
-        let constant = self.read_constant();
+        let constant = self.read_constant()?;
         self.stack.push(constant);
         Ok(())
       }
@@ -209,39 +1263,47 @@ impl VM {
       }
       /* Comparisons */
       OpCode::Equal => self.binary_op(|l, r| Ok(Value::bool_val(l == r))),
-      OpCode::Greater => self.binary_op(|l, r| Ok(Value::bool_val(l > r))),
-      OpCode::Less => self.binary_op(|l, r| Ok(Value::bool_val(l < r))),
+      OpCode::Greater => self.compare_op(|l, r| l > r),
+      OpCode::Less => self.compare_op(|l, r| l < r),
       OpCode::NotEqual => self.binary_op(|l, r| Ok(Value::bool_val(l != r))),
-      OpCode::GreaterEqual => self.binary_op(|l, r| Ok(Value::bool_val(l >= r))),
-      OpCode::LessEqual => self.binary_op(|l, r| Ok(Value::bool_val(l <= r))),
+      OpCode::GreaterEqual => self.compare_op(|l, r| l >= r),
+      OpCode::LessEqual => self.compare_op(|l, r| l <= r),
+      OpCode::Is => self.binary_op(|l, r| l.is_instance_of(r).map(Value::bool_val)),
+      OpCode::Contains => self.binary_op(|l, r| r.contains(l).map(Value::bool_val)),
       /* Binary Arith Opts */
       OpCode::Add => self.binary_op(|l, r| l + r),
       OpCode::Subtract => self.binary_op(|l, r| l - r),
       OpCode::Multiply => self.binary_op(|l, r| l * r),
       OpCode::Divide => self.binary_op(|l, r| l / r),
+      OpCode::FloorDivide => self.binary_op(Value::floor_div),
       /* Unary Arith Opts */
       OpCode::Not => self.unary_op(|v| !v),
       OpCode::Negate => self.unary_op(|v| -v),
       /* Control Flow Opts */
       OpCode::JumpIfFalse => {
-        let offset = self.read_u16();
-        if self.stack.last().unwrap().is_falsey() {
-          self.ip = (self.ip as isize + offset as i16 as isize) as usize;
+        let offset = self.read_u16()?;
+        if self.peek(0)?.is_falsey() {
+          self.jump_by(offset);
         }
         Ok(())
       }
       OpCode::Jump => {
-        let offset = self.read_u16();
-        self.ip = (self.ip as isize + offset as i16 as isize) as usize;
+        let offset = self.read_u16()?;
+        self.jump_by(offset);
+        Ok(())
+      }
+      OpCode::Loop => {
+        let offset = self.read_u16()?;
+        self.frame_mut().ip -= offset as usize;
         Ok(())
       }
       /* Helper Opts */
       OpCode::Print => {
         if let Some(value) = self.stack.pop() {
-          println!("StdOut => {}", value);
+          (self.print_fn)(&display::plain(&value));
           Ok(())
         } else {
-          Err(InterpretError::RuntimeError(
+          Err(InterpretError::Fault(
             "Expect a value after `print` statement.".into(),
           ))
         }
@@ -250,118 +1312,644 @@ impl VM {
         self.stack.pop();
         Ok(())
       }
+      OpCode::PopN => {
+        let count = self.read_byte()? as usize;
+        self.stack.truncate(self.stack.len().saturating_sub(count));
+        Ok(())
+      }
+      OpCode::Peek => {
+        let distance = self.read_byte()? as usize;
+        let value = self.peek(distance)?;
+        self.stack.push(value);
+        Ok(())
+      }
+      OpCode::Sink => {
+        let distance = self.read_byte()? as usize;
+        let index = self.stack_index(distance)?;
+        let value = self.pop()?;
+        self.stack.insert(index, value);
+        Ok(())
+      }
+      OpCode::Stringify => self.unary_op(|value| match value.is_string() {
+        true => Ok(value),
+        false => Ok(Value::obj_val(
+          ObjString::from(display::plain(&value)).cast_to_obj_ptr(),
+        )),
+      }),
       /* Variable Getters/Setters */
       OpCode::DefineGlobal => {
-        let name = self.read_constant();
+        let name = self.read_constant()?;
         if let Ok(name) = name.as_string() {
-          let value = self.stack.pop().unwrap();
+          let value = self.pop()?;
           self.globals.set(unsafe { name.as_ref() }.to_owned(), value);
           Ok(())
         } else {
-          Err(InterpretError::RuntimeError(
+          Err(InterpretError::Fault(
             "Expect a string as global variable name.".into(),
           ))
         }
       }
       OpCode::GetGlobal => {
-        let name = self.read_constant();
+        let name = self.read_constant()?;
         if let Ok(name) = name.as_string() {
           if let Some(&value) = self.globals.get(unsafe { name.as_ref() }) {
             self.stack.push(value);
             Ok(())
           } else {
-            Err(InterpretError::RuntimeError(format!(
-              "Undefined variable `{}`.",
-              unsafe { name.as_ref() }
-            )))
+            Err(InterpretError::Raised(
+              ErrorKind::Name,
+              format!("Undefined variable `{}`.", unsafe { name.as_ref() }),
+            ))
           }
         } else {
-          Err(InterpretError::RuntimeError(
+          Err(InterpretError::Fault(
             "Expect a string as global variable name.".into(),
           ))
         }
       }
       OpCode::GetLocal => {
-        let slot = self.read_byte();
-        if let Some(value) = self.stack.get(slot as usize) {
-          self.stack.push(value.to_owned());
-          Ok(())
-        } else {
-          Err(InterpretError::RuntimeError(format!(
-            "Undefined local variable at slot `{}`.",
-            slot
-          )))
-        }
+        let slot = self.read_byte()? as usize;
+        self.get_local(slot)
+      }
+      OpCode::MissingArgument => {
+        // parameters start from slot one
+        let slot = self.read_byte()? as usize;
+        let missing = slot > self.frame().arg_count;
+        self.stack.push(Value::bool_val(missing));
+        Ok(())
       }
       OpCode::SetGlobal => {
-        let name = self.read_constant();
+        let name = self.read_constant()?;
         if let Ok(name) = name.as_string() {
           if self
             .globals
-            .set(
-              unsafe { name.as_ref().to_owned() },
-              self.stack.last().unwrap().to_owned(),
-            )
+            .set(unsafe { name.as_ref().to_owned() }, self.peek(0)?)
             .is_none()
           {
             self.globals.remove(unsafe { name.as_ref() });
-            Err(InterpretError::RuntimeError(format!(
-              "Undefined variable `{}`.",
-              unsafe { name.as_ref() }
-            )))
+            Err(InterpretError::Raised(
+              ErrorKind::Name,
+              format!("Undefined variable `{}`.", unsafe { name.as_ref() }),
+            ))
           } else {
             Ok(())
           }
         } else {
-          Err(InterpretError::RuntimeError(
+          Err(InterpretError::Fault(
             "Expect a string as global variable name.".into(),
           ))
         }
       }
       OpCode::SetLocal => {
-        let slot = self.read_byte();
-        let top = *self.stack.last().unwrap();
-        if let Some(value) = self.stack.get_mut(slot as usize) {
-          *value = top;
-          Ok(())
+        let slot = self.read_byte()? as usize;
+        self.set_local(slot)
+      }
+      OpCode::GetLocalLong => {
+        let slot = self.read_u16()? as usize;
+        self.get_local(slot)
+      }
+      OpCode::SetLocalLong => {
+        let slot = self.read_u16()? as usize;
+        self.set_local(slot)
+      }
+      /* Function Opts */
+      OpCode::Call => {
+        let arg_count = self.read_byte()? as usize;
+        let callee = self.peek(arg_count)?;
+        self.call_value(callee, arg_count)
+      }
+      OpCode::CallGlobal => {
+        let name = self.read_constant()?.as_string()?;
+        let arg_count = self.read_byte()? as usize;
+        let Some(&callee) = self.globals.get(unsafe { name.as_ref() }) else {
+          return Err(InterpretError::Raised(
+            ErrorKind::Name,
+            format!("Undefined variable `{}`.", unsafe { name.as_ref() }),
+          ));
+        };
+        // the callee is expected right below its arguments
+        let index = self.stack_index(arg_count)? + 1;
+        self.stack.insert(index, callee);
+        self.call_value(callee, arg_count)
+      }
+      OpCode::Closure => {
+        let function = self.read_constant()?.as_function()?;
+        self.make_closure(function)
+      }
+      OpCode::GetUpvalue => {
+        let index = self.read_byte()? as usize;
+        let upvalue = unsafe { self.upvalue(index)?.as_ref() };
+        let value = match upvalue.closed {
+          Some(value) => value,
+          None => self.stack.get(upvalue.slot).copied().ok_or_else(|| {
+            InterpretError::Fault(format!("Undefined upvalue at slot `{}`.", upvalue.slot))
+          })?,
+        };
+        self.stack.push(value);
+        Ok(())
+      }
+      OpCode::SetUpvalue => {
+        let index = self.read_byte()? as usize;
+        let value = self.peek(0)?;
+        let upvalue = unsafe { self.upvalue(index)?.as_mut() };
+        match upvalue.closed.as_mut() {
+          Some(closed) => *closed = value,
+          None => match self.stack.get_mut(upvalue.slot) {
+            Some(slot) => *slot = value,
+            None => {
+              return Err(InterpretError::Fault(format!(
+                "Undefined upvalue at slot `{}`.",
+                upvalue.slot
+              )))
+            }
+          },
+        }
+        Ok(())
+      }
+      OpCode::CloseUpvalue => {
+        let slot = self.stack_index(0)?;
+        self.close_upvalues(slot);
+        self.pop()?;
+        Ok(())
+      }
+      /* Class Opts */
+      OpCode::Class => {
+        let name = self.read_constant()?.as_string()?;
+        let class = ObjClass::new(unsafe { name.as_ref() }.to_owned());
+        self.stack.push(Value::obj_val(class.cast_to_obj_ptr()));
+        Ok(())
+      }
+      OpCode::Trait => {
+        let name = self.read_constant()?.as_string()?;
+        let mut class = ObjClass::new(unsafe { name.as_ref() }.to_owned());
+        class.is_trait = true;
+        self.stack.push(Value::obj_val(class.cast_to_obj_ptr()));
+        Ok(())
+      }
+      OpCode::Enum => {
+        let name = self.read_constant()?.as_string()?;
+        let enum_ = ObjEnum::new(unsafe { name.as_ref() }.to_owned());
+        self.stack.push(Value::obj_val(enum_.cast_to_obj_ptr()));
+        Ok(())
+      }
+      OpCode::EnumMember => {
+        let name = self.read_constant()?.as_string()?;
+        let mut enum_ = self.peek(0)?.as_enum()?;
+        let ordinal = unsafe { enum_.as_ref() }.members.len();
+        let member = ObjEnumMember::new(enum_, unsafe { name.as_ref() }.to_owned(), ordinal);
+        let member = member.cast_to_obj_ptr().cast();
+        unsafe { enum_.as_mut() }.members.push(member);
+        Ok(())
+      }
+      OpCode::Mixin => {
+        let from = self.pop()?;
+        let mut class = self.peek(0)?.as_class()?;
+        if let Ok(from) = from.as_class() {
+          unsafe { class.as_mut() }.mix_in(unsafe { from.as_ref() })
         } else {
-          Err(InterpretError::RuntimeError(format!(
-            "Undefined local variable at slot `{}`.",
-            slot
-          )))
+          Err(InterpretError::Raised(
+            ErrorKind::Type,
+            "Can only mix traits into classes.".into(),
+          ))
+        }
+      }
+      OpCode::Inherit => {
+        let superclass = self.pop()?;
+        let mut class = self.peek(0)?.as_class()?;
+        match superclass.as_class() {
+          Ok(superclass) => unsafe { class.as_mut() }.inherit(superclass),
+          Err(_) => Err(InterpretError::RuntimeError(
+            "Superclass must be a class.".into(),
+          )),
         }
       }
+      OpCode::GetProperty => {
+        let name = self.read_constant()?.as_string()?;
+        let name = unsafe { name.as_ref() };
+        if let Ok(mut instance) = self.peek(0)?.as_instance() {
+          let instance = unsafe { instance.as_mut() };
+          if let Some(&value) = instance.fields.get(name) {
+            self.set_top(value)?;
+            Ok(())
+          } else if let Some(getter) = instance.class().getters.get(name) {
+            // the instance on top of the stack becomes `this` of the getter
+            self.call(*getter, 0)
+          } else {
+            self.bind_method(instance, name)
+          }
+        } else if let Ok(enum_) = self.peek(0)?.as_enum() {
+          let member = unsafe { enum_.as_ref() }.member(name)?;
+          self.set_top(Value::obj_val(member.cast()))
+        } else if let Ok(member) = self.peek(0)?.as_enum_member() {
+          let property = unsafe { member.as_ref() }.property(&name.data)?;
+          self.set_top(property)
+        } else {
+          Err(InterpretError::Raised(
+            ErrorKind::Type,
+            "Only instances have properties.".into(),
+          ))
+        }
+      }
+      OpCode::SetProperty => {
+        let name = self.read_constant()?.as_string()?;
+        let name = unsafe { name.as_ref() };
+        let receiver = self.peek(1)?;
+        if let Ok(mut instance) = receiver.as_instance() {
+          let instance = unsafe { instance.as_mut() };
+          if let Some(setter) = instance.class().setters.get(name) {
+            // [instance, value] becomes [`this`, argument] of the setter
+            self.call(*setter, 1)
+          } else if instance.class().getters.get(name).is_some() {
+            Err(InterpretError::RuntimeError(format!(
+              "Property `{}` has a getter but no setter.",
+              name
+            )))
+          } else {
+            let value = self.pop()?;
+            self.stack.pop();
+            instance.fields.set(name.to_owned(), value);
+            self.stack.push(value);
+            Ok(())
+          }
+        } else {
+          Err(InterpretError::Raised(
+            ErrorKind::Type,
+            "Only instances have fields.".into(),
+          ))
+        }
+      }
+      OpCode::DeleteProperty => {
+        let name = self.read_constant()?.as_string()?;
+        let name = unsafe { name.as_ref() };
+        if let Ok(mut instance) = self.pop()?.as_instance() {
+          let instance = unsafe { instance.as_mut() };
+          if instance.fields.remove(name).is_some() {
+            Ok(())
+          } else {
+            Err(InterpretError::Raised(
+              ErrorKind::Property,
+              format!("Undefined field `{}`.", name),
+            ))
+          }
+        } else {
+          Err(InterpretError::Raised(
+            ErrorKind::Type,
+            "Only instances have fields.".into(),
+          ))
+        }
+      }
+      OpCode::Invoke => {
+        let name = self.read_constant()?.as_string()?;
+        let arg_count = self.read_byte()? as usize;
+        self.invoke(unsafe { name.as_ref() }, arg_count)
+      }
+      OpCode::GetSuper => {
+        let name = self.read_constant()?.as_string()?;
+        let superclass = self.pop()?.as_class()?;
+        self.bind_super_method(unsafe { superclass.as_ref() }, unsafe { name.as_ref() })
+      }
+      OpCode::SuperInvoke => {
+        let name = self.read_constant()?.as_string()?;
+        let arg_count = self.read_byte()? as usize;
+        let superclass = self.pop()?.as_class()?;
+        self.invoke_from_class(
+          unsafe { superclass.as_ref() },
+          unsafe { name.as_ref() },
+          arg_count,
+        )
+      }
+      OpCode::Method => self.define_method(|class| &mut class.methods),
+      OpCode::Getter => self.define_method(|class| &mut class.getters),
+      OpCode::Setter => self.define_method(|class| &mut class.setters),
+      /* List Opts */
+      OpCode::BuildList => {
+        let count = self.read_byte()? as usize;
+        let Some(start) = self.stack.len().checked_sub(count) else {
+          return Err(InterpretError::Fault("Stack underflow.".into()));
+        };
+        let items = self.stack.split_off(start);
+        let list = ObjList::new(items);
+        self.stack.push(Value::obj_val(list.cast_to_obj_ptr()));
+        Ok(())
+      }
+      OpCode::GetIndex => {
+        let index = self.pop()?;
+        let target = self.pop()?;
+        let item = if let Ok(list) = target.as_list() {
+          let list = unsafe { list.as_ref() };
+          list.items[list.index(index)?]
+        } else if let Ok(string) = target.as_string() {
+          let char = unsafe { string.as_ref() }.char_at(index)?;
+          Value::obj_val(ObjString::from(char).cast_to_obj_ptr())
+        } else {
+          return Err(InterpretError::RuntimeError(
+            "Only lists and strings can be indexed.".into(),
+          ));
+        };
+        self.stack.push(item);
+        Ok(())
+      }
+      OpCode::GetSlice => {
+        let end = self.pop()?;
+        let start = self.pop()?;
+        let target = self.pop()?;
+        let slice = if let Ok(list) = target.as_list() {
+          let items = unsafe { list.as_ref() }.slice(start, end)?;
+          ObjList::new(items).cast_to_obj_ptr()
+        } else if let Ok(string) = target.as_string() {
+          let chars = unsafe { string.as_ref() }.slice(start, end)?;
+          ObjString::from(chars).cast_to_obj_ptr()
+        } else {
+          return Err(InterpretError::RuntimeError(
+            "Only lists and strings can be sliced.".into(),
+          ));
+        };
+        self.stack.push(Value::obj_val(slice));
+        Ok(())
+      }
+      OpCode::SetIndex => {
+        let item = self.pop()?;
+        let index = self.pop()?;
+        let list = self.pop()?;
+        let mut list = list.as_list().map_err(|_| {
+          InterpretError::RuntimeError("Only items of lists can be assigned.".into())
+        })?;
+        let list = unsafe { list.as_mut() };
+        let index = list.index(index)?;
+        list.items[index] = item;
+        self.stack.push(item);
+        Ok(())
+      }
+      OpCode::Unpack => {
+        let count = self.read_byte()? as usize;
+        let list = self
+          .pop()?
+          .as_list()
+          .map_err(|_| InterpretError::RuntimeError("Only lists can be unpacked.".into()))?;
+        let items = &unsafe { list.as_ref() }.items;
+        if items.len() != count {
+          return Err(InterpretError::RuntimeError(format!(
+            "Can't unpack {} values into {} variables.",
+            items.len(),
+            count
+          )));
+        }
+        self.stack.extend_from_slice(items);
+        Ok(())
+      }
+      OpCode::MatchList | OpCode::MatchListRest => {
+        let count = self.read_byte()? as usize;
+        let value = self.pop()?;
+        let matches = value.as_list().is_ok_and(|list| {
+          let len = unsafe { list.as_ref() }.items.len();
+          match op_code {
+            OpCode::MatchList => len == count,
+            _ => len >= count,
+          }
+        });
+        self.stack.push(Value::bool_val(matches));
+        Ok(())
+      }
+      OpCode::ExtendList => {
+        let items = self.pop()?;
+        let mut list = self.peek(0)?.as_list()?;
+        let items = items
+          .as_list()
+          .map_err(|_| InterpretError::RuntimeError("Only lists can be spread.".into()))?;
+        // The list being built isn't reachable by scripts, so it's never the spread one
+        unsafe { list.as_mut() }
+          .items
+          .extend_from_slice(&unsafe { items.as_ref() }.items);
+        Ok(())
+      }
+      OpCode::SpreadCall => {
+        let arg_count = self.spread_args()?;
+        let callee = self.peek(arg_count)?;
+        self.call_value(callee, arg_count)
+      }
+      OpCode::SpreadInvoke => {
+        let name = self.read_constant()?.as_string()?;
+        let arg_count = self.spread_args()?;
+        self.invoke(unsafe { name.as_ref() }, arg_count)
+      }
+      OpCode::SpreadSuperInvoke => {
+        let name = self.read_constant()?.as_string()?;
+        let superclass = self.pop()?.as_class()?;
+        let arg_count = self.spread_args()?;
+        self.invoke_from_class(
+          unsafe { superclass.as_ref() },
+          unsafe { name.as_ref() },
+          arg_count,
+        )
+      }
+      /* Exception Opts */
+      /* Module Opts */
+      OpCode::Import => {
+        let key = self.read_constant()?.as_string()?;
+        if self
+          .imported_modules
+          .insert(unsafe { key.as_ref() }.data.to_owned())
+        {
+          self.call(self.peek(0)?, 0)
+        } else {
+          self.set_top(Value::nil_val())
+        }
+      }
+      OpCode::PushHandler => {
+        let offset = self.read_u16()? as usize;
+        self.handlers.push(Handler {
+          frame_count: self.frames.len(),
+          stack_len: self.stack.len(),
+          catch_ip: self.frame().ip + offset,
+        });
+        Ok(())
+      }
+      OpCode::PopHandler => {
+        self.handlers.pop();
+        Ok(())
+      }
+      OpCode::Throw => {
+        let value = self.pop()?;
+        self.throw(value)
+      }
       /* Return */
       OpCode::Return => {
-        return Ok(());
+        let result = self.stack.pop().unwrap_or_default();
+        let frame = self.frames.pop().unwrap();
+        self.close_upvalues(frame.slot_base);
+        self.stack.truncate(frame.slot_base);
+        // `try` blocks of the returning function are left as well
+        let frame_count = self.frames.len();
+        self
+          .handlers
+          .retain(|handler| handler.frame_count <= frame_count);
+        // result of the top-level function is left for `run`
+        self.stack.push(result);
+        Ok(())
       }
-    };
-    if let Err(InterpretError::RuntimeError(message)) = raw_result {
-      self.runtime_error(message)
-    } else {
-      Ok(())
     }
   }
 }
 
 impl VM {
+  /// Locate the runtime error, then discard the failed run
+  /// (or keep it for inspection, see [`VM::set_post_mortem`]).
   pub fn runtime_error(&mut self, message: String) -> Result<(), InterpretError> {
-    // Index should be `ip - 1`, as ip has increased before error occurred.
-    let inst_index = self.ip - 1;
+    let message = self.locate(message);
+    if self.post_mortem {
+      self.failure = Some(message.clone());
+    } else {
+      self.reset_execution_state();
+    }
+    Err(InterpretError::RuntimeError(message))
+  }
 
-    let line = self.chunk.lines[inst_index];
-    let message = format!("[line {}] in script: {}", line, message);
+  /// Prefix the message with where it occurs, followed by the rest of the stack trace.
+  fn locate(&self, message: String) -> String {
+    let mut message = message;
+    for (depth, line) in self.stack_trace().into_iter().enumerate() {
+      if depth == 0 {
+        message = format!("{}: {}", line, message);
+      } else {
+        message += &format!("\n{}", line);
+      }
+    }
+    message
+  }
 
-    self.stack.clear();
+  /// Where each ongoing call is, as `[location] in name` (the innermost one first).
+  pub(crate) fn stack_trace(&self) -> Vec<String> {
+    let mut lines = vec![];
+    for frame in self.frames.iter().rev() {
+      // Index should be `ip - 1`, as ip has increased before error occurred.
+      let inst_index = frame.ip.saturating_sub(1);
+      let location = frame.chunk().debug_info.describe(inst_index);
+      let name = unsafe { frame.function.as_ref() }.name();
+      lines.push(format!("[{}] in {}", location, name));
+    }
+    lines
+  }
 
-    Err(InterpretError::RuntimeError(message))
+  /// Unwind to the innermost `try` block, then jump to its `catch` block with the value.
+  ///
+  /// The `stack` of an error is filled in where it's (first) thrown.
+  ///
+  /// Without any `try` block, it's an uncaught runtime error.
+  fn throw(&mut self, value: Value) -> Result<(), InterpretError> {
+    if let Some(mut instance) = error::as_error(value) {
+      let fields = &mut unsafe { instance.as_mut() }.fields;
+      let stack = ObjString::from("stack".to_owned());
+      if fields.get(&stack).is_none_or(Value::is_nil) {
+        let trace = self.stack_trace().join("\n");
+        fields.set(
+          stack,
+          Value::obj_val(ObjString::from(trace).cast_to_obj_ptr()),
+        );
+      }
+    }
+    let Some(handler) = self.handlers.pop() else {
+      self.thrown = Some(value);
+      return Err(InterpretError::Thrown(format!(
+        "Uncaught {}",
+        error::describe(value)
+      )));
+    };
+    self.frames.truncate(handler.frame_count);
+    self.close_upvalues(handler.stack_len);
+    self.stack.truncate(handler.stack_len);
+    self.stack.push(value);
+    self.frame_mut().ip = handler.catch_ip;
+    Ok(())
+  }
+  /// Throw the recoverable error to the innermost `try` block, as an error of its kind
+  /// (or the value of [`InterpretError::Thrown`] as it is).
+  ///
+  /// Without any `try` block (or if it's unrecoverable), it's returned as is.
+  fn catch(&mut self, error: InterpretError) -> Result<(), InterpretError> {
+    if self.handlers.is_empty() {
+      return Err(error);
+    }
+    match error {
+      InterpretError::RuntimeError(message) => {
+        self.throw(error::new_error("RuntimeError", message, Value::nil_val()))
+      }
+      InterpretError::Raised(kind, message) => {
+        self.throw(error::new_error(kind.name(), message, Value::nil_val()))
+      }
+      InterpretError::Thrown(_) => self.rethrow(),
+      error => Err(error),
+    }
+  }
+
+  /// Throw the value of the last [`InterpretError::Thrown`] again (to `try` blocks of the caller).
+  fn rethrow(&mut self) -> Result<(), InterpretError> {
+    let value = self.thrown.take().unwrap_or_default();
+    self.throw(value)
+  }
+}
+
+/// Write the value of a `print` statement to standard output.
+#[cfg(feature = "std")]
+fn default_print(text: &str) {
+  println!("StdOut => {}", text);
+}
+
+/// There's no standard output without `std`, see [`VM::set_print`].
+#[cfg(not(feature = "std"))]
+fn default_print(_: &str) {}
+
+impl Default for VM {
+  fn default() -> Self {
+    let mut vm = Self {
+      frames: Vec::default(),
+      stack: Vec::default(),
+      handlers: Vec::default(),
+      thrown: None,
+      strings: Table::default(),
+      globals: Table::default(),
+      instruction_count: 0,
+      peak_stack_size: 0,
+      init_string: ObjString::from("init".to_owned()),
+      compile_warnings: Vec::default(),
+      functions: FunctionTable::default(),
+      module_files: Vec::default(),
+      imported_modules: HashSet::default(),
+      resolutions: ResolutionTable::default(),
+      compile_options: CompileOptions::default(),
+      compile_cache: CompileCache::default(),
+      work_buffer: WorkBuffer::default(),
+      plugins: Plugins::default(),
+      capabilities: Capabilities::none(),
+      sandbox: None,
+      #[cfg(feature = "std")]
+      deterministic: None,
+      #[cfg(feature = "std")]
+      timeout: None,
+      #[cfg(feature = "std")]
+      deadline: None,
+      fuel: None,
+      heap_limit: None,
+      heap_base: 0,
+      print_fn: default_print,
+      strict_comparison: false,
+      crash_report: None,
+      open_upvalues: Vec::default(),
+      breakpoints: HashMap::default(),
+      paused_at: None,
+      stepping: None,
+      watches: Vec::default(),
+      post_mortem: false,
+      failure: None,
+    };
+    vm.define_natives();
+    vm
   }
 }
 
 impl Init for VM {}
 
 impl VM {
-  /// Create a new virtual machine (with no chunk linked, ip as 0).
+  // Create a new virtual machine (with no chunk linked, ip as 0).
   // pub fn init() -> Self {
   //   Self {
   //     chunk: Chunk::default(),
@@ -372,17 +1960,279 @@ impl VM {
   //   }
   // }
 
-  /// Free the chunk (if any).
+  /// Reset the virtual machine to idle state, which is the same as after a successful run.
+  ///
+  /// Recovery protocol of runtime errors:
+  ///
+  /// - all frames (with their `ip` and function), `try` blocks and the whole stack are discarded,
+  ///   so the next `interpret` starts from a clean state
+  /// - globals defined before the error are kept (like statements of previous REPL lines)
+  /// - statistics (`instruction_count`, `peak_stack_size`) of the failed run are kept
+  ///   until the next rebind
+  ///
+  /// Capacities of frames and the stack are kept, so later runs needn't grow them again.
+  pub fn reset_execution_state(&mut self) {
+    // closures which outlive the run keep values of their captured variables
+    self.close_upvalues(0);
+    self.frames.clear();
+    self.stack.clear();
+    self.handlers.clear();
+    self.paused_at = None;
+    self.failure = None;
+    self.thrown = None;
+  }
+
+  /// If there's no ongoing function call.
+  pub fn is_idle(&self) -> bool {
+    self.frames.is_empty() && self.stack.is_empty() && self.handlers.is_empty()
+  }
+
+  /// Free all frames (if any).
   pub fn free(&mut self) {
-    self.chunk.free();
-    self.stack.resize(0, Default::default());
+    self.reset_execution_state();
+    self.stack.shrink_to_fit();
+    self.work_buffer = WorkBuffer::default();
     self.strings.free();
     self.globals.free();
   }
 
   /// Rebind the virtual machine to the given chunk.
+  ///
+  /// The chunk is wrapped as the top-level `script` function.
   pub fn rebind(&mut self, chunk: Chunk) {
-    self.chunk = chunk;
-    self.ip = 0;
+    let mut function = ObjFunction::init();
+    function.chunk = chunk;
+    self.rebind_function(function);
+    self.functions = FunctionTable::default();
+    self.resolutions.clear();
+  }
+
+  /// Rebind the virtual machine to the given top-level function.
+  pub(crate) fn rebind_function(&mut self, function: ObjFunction) {
+    let function = Value::obj_val(function.cast_to_obj_ptr());
+    self.reset_execution_state();
+    self.stack.push(function);
+    self.frames.push(CallFrame {
+      function: function.as_function().unwrap(),
+      closure: None,
+      ip: 0,
+      slot_base: 0,
+      arg_count: 0,
+    });
+    self.instruction_count = 0;
+    self.heap_base = gc::allocated_bytes();
+    self.peak_stack_size = 0;
+    self.crash_report = None;
+    #[cfg(feature = "std")]
+    {
+      self.deadline = None;
+    }
+  }
+
+  /// State captured at the internal error (a fault other than stack overflow)
+  /// of the last run, `None` if there's no such error.
+  pub fn crash_report(&self) -> Option<&CrashReport> {
+    self.crash_report.as_ref()
+  }
+
+  /// Count of instructions executed since last rebind.
+  pub fn instruction_count(&self) -> usize {
+    self.instruction_count
+  }
+
+  /// Peak size of the stack since last rebind.
+  pub fn peak_stack_size(&self) -> usize {
+    self.peak_stack_size
+  }
+
+  /// Set where values of `print` statements are written to
+  /// (standard output by default, or nowhere without the `std` feature).
+  pub fn set_print(&mut self, print: fn(&str)) {
+    self.print_fn = print;
+  }
+
+  /// Limit how long each run may take (`None` by default, i.e. unlimited).
+  ///
+  /// The deadline is checked every [`TIMEOUT_CHECK_INTERVAL`] instructions,
+  /// so a run is aborted by [`InterpretError::Timeout`] soon after it's passed
+  /// (but not during a blocking native, e.g. `sleep`).
+  #[cfg(feature = "std")]
+  pub fn set_timeout(&mut self, timeout: Option<std::time::Duration>) {
+    self.timeout = timeout;
+  }
+
+  /// How long each run may take (if it's limited).
+  #[cfg(feature = "std")]
+  pub fn timeout(&self) -> Option<std::time::Duration> {
+    self.timeout
+  }
+
+  /// Limit how many instructions each run may execute (`None` by default, i.e. unlimited),
+  /// so a run is aborted by [`InterpretError::LimitExceeded`] once it's used up.
+  ///
+  /// Unlike [`VM::set_timeout`], it doesn't depend on how fast the host is.
+  pub fn set_fuel(&mut self, fuel: Option<usize>) {
+    self.fuel = fuel;
+  }
+
+  /// How many instructions each run may execute (if it's limited).
+  pub fn fuel(&self) -> Option<usize> {
+    self.fuel
+  }
+
+  /// Limit how many bytes of objects each run may allocate (`None` by default, i.e. unlimited),
+  /// so a run is aborted by [`InterpretError::LimitExceeded`] once it allocates more.
+  ///
+  /// Objects are counted by their sizes when they're allocated (see [`gc::allocated_bytes`]),
+  /// so lists growing afterwards (e.g. by `push`) are only limited by fuel.
+  pub fn set_heap_limit(&mut self, limit: Option<usize>) {
+    self.heap_limit = limit;
+  }
+
+  /// How many bytes of objects each run may allocate (if it's limited).
+  pub fn heap_limit(&self) -> Option<usize> {
+    self.heap_limit
+  }
+
+  /// Pause runs of [`VM::resume`] before the first instruction of the line (in any function),
+  /// each time it's reached.
+  pub fn set_breakpoint(&mut self, line: usize) {
+    self
+      .breakpoints
+      .insert(line, (Breakpoint::default(), None, 0));
+  }
+
+  /// Pause runs of [`VM::resume`] at the line, only when the condition of the breakpoint holds
+  /// and it's hit enough times (see [`Breakpoint`]).
+  ///
+  /// The condition is compiled once here, then evaluated at each hit like watches
+  /// (see [`VM::add_watch`]): locals of the innermost call shadow globals of the same names.
+  pub fn set_breakpoint_with(
+    &mut self,
+    line: usize,
+    breakpoint: Breakpoint,
+  ) -> Result<(), InterpretError> {
+    let condition = match &breakpoint.condition {
+      Some(condition) => Some(self.compile_expression(condition, "Condition")?),
+      None => None,
+    };
+    self.breakpoints.insert(line, (breakpoint, condition, 0));
+    Ok(())
+  }
+
+  /// Remove the breakpoint of the line, returns if there's one.
+  pub fn clear_breakpoint(&mut self, line: usize) -> bool {
+    self.breakpoints.remove(&line).is_some()
+  }
+
+  /// How many times the breakpoint of the line is hit (with its condition holding),
+  /// `None` if there's no such breakpoint.
+  pub fn breakpoint_hits(&self, line: usize) -> Option<usize> {
+    self.breakpoints.get(&line).map(|&(_, _, hits)| hits)
+  }
+
+  /// Lines with breakpoints, in ascending order.
+  pub fn breakpoints(&self) -> Vec<usize> {
+    let mut lines: Vec<_> = self.breakpoints.keys().copied().collect();
+    lines.sort_unstable();
+    lines
+  }
+
+  /// Line the ongoing run is paused at, by a breakpoint or a step (if any).
+  pub fn paused_at(&self) -> Option<usize> {
+    self.paused_at
+  }
+
+  /// Set if ordering values of different types (e.g. `1 < "a"`) is a runtime error
+  /// (`false` by default).
+  pub fn set_strict_comparison(&mut self, strict: bool) {
+    self.strict_comparison = strict;
+  }
+
+  /// Set if runs failed by runtime errors are kept for inspection (`false` by default).
+  ///
+  /// Instead of being discarded, frames and the stack of the failed run are left as they were
+  /// at the failing instruction (see [`VM::failure`]), until the next script is compiled.
+  /// The kept run never continues: running it again fails by the same error.
+  pub fn set_post_mortem(&mut self, post_mortem: bool) {
+    self.post_mortem = post_mortem;
+  }
+
+  /// Set opt-in diagnostics of the compiler.
+  pub fn set_compile_options(&mut self, options: CompileOptions) {
+    self.compile_options = options;
+  }
+
+  /// Register an operator (see [`plugin`](crate::compiler::plugin)) for all scripts compiled later.
+  pub fn register_operator(&mut self, rule: OperatorRule) -> Result<(), InterpretError> {
+    self.plugins.register_operator(rule)
+  }
+
+  /// Replace the reserved words for all scripts compiled later.
+  pub fn set_keywords(&mut self, keywords: Keywords) {
+    self.plugins.set_keywords(keywords);
+  }
+
+  /// Extensions of the compiler registered so far.
+  pub fn plugins(&self) -> &Plugins {
+    &self.plugins
+  }
+
+  /// Set where compiled scripts are cached.
+  pub fn set_cache_options(&mut self, options: CacheOptions) {
+    self.compile_cache.options = options;
+  }
+
+  /// The incremental compilation cache.
+  pub fn compile_cache(&mut self) -> &mut CompileCache {
+    &mut self.compile_cache
+  }
+
+  /// Warnings reported by the last (successful) compiling.
+  pub fn warnings(&self) -> &[String] {
+    &self.compile_warnings
+  }
+
+  /// Functions of the script compiled (or loaded) last.
+  pub fn functions(&self) -> &FunctionTable {
+    &self.functions
+  }
+
+  /// Declarations and references of variables of the script compiled last (even if it failed).
+  ///
+  /// Scripts loaded from the on-disk cache aren't parsed at all, so they have none.
+  pub fn resolutions(&self) -> &ResolutionTable {
+    &self.resolutions
+  }
+
+  /// Inspection of the global of the given name (see [`inspect::inspect`]),
+  /// `None` if it's undefined.
+  pub fn inspect(&self, name: &str) -> Option<String> {
+    let value = self.globals.get(&ObjString::from(name.to_owned()))?;
+    Some(inspect::inspect(name, value))
+  }
+
+  /// All globals (natives included), in order of definition.
+  pub fn globals(&self) -> &Table {
+    &self.globals
+  }
+
+  /// Globals defined by scripts (natives and the `Error` class excluded) as `name = value` lines,
+  /// in order of definition.
+  pub fn dump_globals(&self) -> String {
+    let mut out = String::new();
+    for (name, value) in self.script_globals() {
+      out.push_str(&format!("{} = {}\n", name, value));
+    }
+    out
+  }
+
+  /// Globals defined by scripts (natives and the `Error` class excluded), in order of definition.
+  pub(crate) fn script_globals(&self) -> impl Iterator<Item = (&ObjString, &Value)> {
+    let is_builtin = |value: &Value| value.is_native() || *value == error::error_class();
+    self
+      .globals
+      .iter()
+      .filter(move |(_, value)| !is_builtin(value))
   }
 }
@@ -0,0 +1,96 @@
+#![cfg(feature = "alloc_sites")]
+
+use rlox::{
+  object::{ObjString, ObjTrait, ObjType},
+  utils::Init,
+  value::Value,
+  vm::{InterpretError, VM},
+  AllocSite,
+};
+
+/// A native of the host which allocates a string each call.
+fn greeting(_: &[Value]) -> Result<Value, InterpretError> {
+  let s = ObjString::from("hello".to_owned());
+  Ok(Value::obj_val(s.cast_to_obj_ptr()))
+}
+
+/// A virtual machine with `greeting` defined, and the source interpreted.
+fn interpreted(src: &str) -> VM {
+  let mut vm = VM::init();
+  vm.define_native("greeting", 0, greeting);
+  vm.interpret(src.to_owned()).unwrap();
+  vm
+}
+
+#[test]
+fn objects_record_lines_allocating_them() {
+  let vm = interpreted(
+    "class Box {}
+var a = Box();
+var b = \"x\" + \"y\";
+fun make() {
+  return Box();
+}
+var c = make();",
+  );
+  let sites: Vec<_> = vm
+    .heap_objects()
+    .into_iter()
+    .filter(|obj| matches!(obj.obj_type, ObjType::Instance | ObjType::String))
+    .filter_map(|obj| obj.site)
+    .collect();
+  assert!(sites.contains(&AllocSite::Line(2)));
+  assert!(sites.contains(&AllocSite::Line(3)));
+  assert!(sites.contains(&AllocSite::Line(5)));
+}
+
+#[test]
+fn objects_record_natives_allocating_them() {
+  let vm = interpreted("var a = greeting();");
+  let greeting = vm
+    .heap_objects()
+    .into_iter()
+    .find(|obj| obj.repr == "\"hello\"")
+    .unwrap();
+  assert_eq!(
+    greeting.site,
+    Some(AllocSite::Native("greeting".to_owned()))
+  );
+  assert!(vm
+    .inspect_object(greeting.id)
+    .unwrap()
+    .contains("allocated at: native `greeting`"));
+}
+
+#[test]
+fn objects_allocated_outside_of_runs_have_no_site() {
+  let vm = interpreted("var s = \"constant\";");
+  let constant = vm
+    .heap_objects()
+    .into_iter()
+    .find(|obj| obj.repr == "\"constant\"")
+    .unwrap();
+  assert_eq!(constant.site, None);
+}
+
+#[test]
+fn reports_group_objects_by_site() {
+  let mut vm = interpreted("class Box {}");
+  let before = vm.heap_snapshot();
+  vm.interpret(
+    "var boxes = Box();\nboxes.a = Box();\nboxes.b = Box();\nboxes.c = Box();".to_owned(),
+  )
+  .unwrap();
+  let after = vm.heap_snapshot();
+  let sites = after.sites();
+  assert!(sites.windows(2).all(|pair| pair[0].size >= pair[1].size));
+  for line in 2..=4 {
+    let group = sites
+      .iter()
+      .find(|group| group.site == Some(AllocSite::Line(line)))
+      .unwrap();
+    assert_eq!(group.count, 1);
+  }
+  let report = before.diff(&after).to_string();
+  assert!(report.contains("Box instance  (line 3)"), "{}", report);
+}
@@ -0,0 +1,59 @@
+use rlox::{chunk::Chunk, object::ObjFunction, utils::Init, vm::VM};
+
+#[test]
+fn strings_live_as_long_as_any_copy_of_the_chunk() {
+  let mut chunk = Chunk::init();
+  // more than one block, so later blocks never move earlier strings
+  let values: Vec<_> = (0..200)
+    .map(|i| chunk.alloc_string(format!("s{}", i)))
+    .collect();
+  assert_eq!(chunk.arena().len(), 200);
+
+  let copy = chunk.clone();
+  drop(chunk);
+  for (i, value) in values.iter().enumerate() {
+    assert_eq!(value.as_rust_string().unwrap(), format!("s{}", i));
+  }
+  assert_eq!(copy.arena().len(), 200);
+}
+
+#[test]
+fn arenas_of_different_chunks_are_independent() {
+  let mut a = Chunk::init();
+  let mut b = Chunk::init();
+  let x = a.alloc_string("x".to_owned());
+  let y = b.alloc_string("x".to_owned());
+  assert!(a.arena().len() == 1 && b.arena().len() == 1);
+  // compared by content, though they're different objects
+  assert_eq!(x, y);
+  drop(b);
+  assert_eq!(x.as_rust_string().unwrap(), "x");
+}
+
+#[test]
+fn functions_are_freed_with_the_chunk_defining_them() {
+  let mut chunk = Chunk::init();
+  let function = ObjFunction::init();
+  // a copy of the arena of the function, to see when the function is dropped
+  let arena = function.chunk().arena().clone();
+  chunk.alloc_function(function);
+  assert_eq!(chunk.arena().functions(), 1);
+  assert_eq!(arena.copies(), 2);
+
+  let copy = chunk.clone();
+  drop(chunk);
+  assert_eq!(arena.copies(), 2);
+  drop(copy);
+  assert_eq!(arena.copies(), 1);
+}
+
+#[test]
+fn functions_are_allocated_in_the_chunk_defining_them() {
+  let mut vm = VM::init();
+  let src = "fun outer() { fun inner() { return \"s\"; } return inner; }";
+  vm.interpret(src.to_owned()).unwrap();
+  let outer = vm.functions().get("outer").unwrap();
+  assert_eq!(outer.chunk().arena().functions(), 1);
+  let inner = vm.functions().get("outer.inner").unwrap();
+  assert_eq!(inner.chunk().arena().strings(), 1);
+}
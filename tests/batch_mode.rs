@@ -0,0 +1,142 @@
+use std::{fs, path::PathBuf, time::Duration};
+
+use rlox::{
+  batch::{run_dir, BatchOptions},
+  utils::Init,
+  value::Value,
+  vm::{InterpretError, VM},
+};
+
+/// A fresh directory with the given files.
+fn dir_with(name: &str, files: &[(&str, &str)]) -> PathBuf {
+  let dir = std::env::temp_dir().join(format!("rlox_batch_{}", name));
+  let _ = fs::remove_dir_all(&dir);
+  fs::create_dir_all(&dir).unwrap();
+  for (file, src) in files {
+    fs::write(dir.join(file), src).unwrap();
+  }
+  dir
+}
+
+#[test]
+fn every_lox_file_is_reported_in_order() {
+  let dir = dir_with(
+    "report",
+    &[
+      ("b_fail.lox", "var a = 1;\nprint a + nil;\n"),
+      ("a_pass.lox", "var a = 1;\nprint a + 1;\n"),
+      ("c_bad.lox", "var = 1;\n"),
+      ("notes.txt", "not a script"),
+    ],
+  );
+  let options = BatchOptions {
+    jobs: 2,
+    ..BatchOptions::init()
+  };
+  let report = run_dir(&dir, options).unwrap();
+
+  let names: Vec<_> = report
+    .files
+    .iter()
+    .map(|file| file.path.file_name().unwrap().to_str().unwrap())
+    .collect();
+  assert_eq!(names, ["a_pass.lox", "b_fail.lox", "c_bad.lox"]);
+  assert_eq!((report.passed(), report.failed(), report.jobs), (1, 2, 2));
+  assert!(matches!(
+    report.files[1].result,
+    Err(InterpretError::RuntimeError(_))
+  ));
+  assert!(matches!(
+    report.files[2].result,
+    Err(InterpretError::CompileError(_))
+  ));
+}
+
+#[test]
+fn files_run_on_isolated_virtual_machines() {
+  // each file defines `leaked`, and fails (by rethrowing `1`) if it's already defined
+  let files: Vec<_> = (0..8)
+    .map(|i| {
+      (
+        format!("{}.lox", i),
+        "try { leaked; throw 1; } catch (e) { if (e == 1) throw e; }\nvar leaked = 0;\n".to_owned(),
+      )
+    })
+    .collect();
+  let files: Vec<_> = files
+    .iter()
+    .map(|(f, s)| (f.as_str(), s.as_str()))
+    .collect();
+  let dir = dir_with("isolation", &files);
+  let options = BatchOptions {
+    jobs: 4,
+    ..BatchOptions::init()
+  };
+  let report = run_dir(&dir, options).unwrap();
+  assert_eq!((report.passed(), report.failed()), (8, 0));
+}
+
+#[test]
+fn jobs_are_bounded_by_files() {
+  let dir = dir_with("jobs", &[("only.lox", "print 1;\n")]);
+  let options = BatchOptions {
+    jobs: 16,
+    ..BatchOptions::init()
+  };
+  let report = run_dir(&dir, options).unwrap();
+  assert_eq!((report.passed(), report.jobs), (1, 1));
+}
+
+#[test]
+fn options_of_the_virtual_machine_are_applied_to_each_file() {
+  let dir = dir_with(
+    "options",
+    &[
+      ("a_clock.lox", "if (now_unix() != 946684800) throw 1;\n"),
+      ("b_compare.lox", "print 1 < \"2\";\n"),
+      ("c_loop.lox", "while (true) {}\n"),
+    ],
+  );
+  let options = BatchOptions {
+    deterministic: Some(0),
+    strict_comparison: true,
+    timeout: Some(Duration::from_millis(50)),
+    ..BatchOptions::init()
+  };
+  let report = run_dir(&dir, options).unwrap();
+  assert!(report.files[0].passed());
+  assert!(matches!(
+    report.files[1].result,
+    Err(InterpretError::RuntimeError(_))
+  ));
+  assert!(matches!(
+    report.files[2].result,
+    Err(InterpretError::Timeout(_))
+  ));
+}
+
+fn explode(_: &[Value]) -> Result<Value, InterpretError> {
+  panic!("exploded");
+}
+
+#[test]
+fn panics_fail_their_files_only() {
+  let dir = dir_with(
+    "panic",
+    &[
+      ("a_panic.lox", "explode();\n"),
+      ("b_pass.lox", "print 1;\n"),
+    ],
+  );
+  let options = BatchOptions {
+    jobs: 1,
+    setup: Some(|vm: &mut VM| vm.define_native("explode", 0, explode)),
+    ..BatchOptions::init()
+  };
+  let report = run_dir(&dir, options).unwrap();
+  assert_eq!((report.passed(), report.failed()), (1, 1));
+  match &report.files[0].result {
+    Err(InterpretError::Fault(message)) => assert!(message.contains("exploded"), "{}", message),
+    other => panic!("expect a fault, but got: {:?}", other),
+  }
+}
@@ -0,0 +1,47 @@
+#[test]
+fn bench_reports_each_iteration() {
+  use rlox::bench::{bench, BenchOptions};
+
+  let src = "var a = 1; var b = a + 2; print b;";
+  let options = BenchOptions {
+    iterations: 3,
+    warmup: 1,
+    reuse_vm: false,
+  };
+  let reports = bench(src, options).unwrap();
+
+  assert_eq!(reports.len(), 3);
+  for report in reports {
+    assert!(report.instructions > 0);
+    assert!(report.peak_stack >= 2);
+  }
+}
+
+#[test]
+fn startup_reports_each_step() {
+  use rlox::bench::startup;
+
+  let report = startup("var a = 1; print a + 1;").unwrap();
+  assert_eq!(
+    report.total(),
+    report.init_time + report.compile_time + report.execute_time
+  );
+  assert!(startup("print nil + 1;").is_err());
+}
+
+#[test]
+fn process_times_are_measured_per_run() {
+  use rlox::bench::{process_times, BenchOptions};
+  use std::process::Command;
+
+  let options = BenchOptions {
+    iterations: 2,
+    warmup: 1,
+    reuse_vm: false,
+  };
+  // the test binary itself, which lists its tests
+  let mut command = Command::new(std::env::current_exe().unwrap());
+  command.arg("--list");
+  let times = process_times(&mut command, options).unwrap();
+  assert_eq!(times.len(), 2);
+}
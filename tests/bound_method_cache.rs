@@ -0,0 +1,34 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn bound_methods_are_cached_per_instance() {
+  let src = r#"
+    class C { m() { return this; } }
+    var a = C();
+    var b = C();
+    assert(a.m == a.m);
+    assert(a.m != b.m);
+    assert(a.m() == a);
+    var m = b.m;
+    assert(m() == b);
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn fields_shadow_cached_methods() {
+  let src = r#"
+    class C { m() { return 1; } }
+    fun two() { return 2; }
+    var c = C();
+    var bound = c.m;
+    c.m = two;
+    assert(c.m() == 2);
+    assert(bound() == 1);
+    delete c.m;
+    assert(c.m() == 1);
+  "#;
+  run(src).unwrap();
+}
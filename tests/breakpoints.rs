@@ -0,0 +1,80 @@
+use rlox::{utils::Init, value::Value as RawValue, vm::VM, RunOutcome, Source, Value, Vm};
+
+const SRC: &str = "var a = 1;
+fun double(x) {
+  return x * 2;
+}
+var b = double(a);
+var c = double(b);
+c;";
+
+/// Compile the source, then start it (without running any instruction).
+fn started(vm: &mut Vm, src: &str) {
+  let script = vm.compile(&Source::new(src)).unwrap();
+  vm.start(&script);
+}
+
+#[test]
+fn runs_pause_at_each_breakpoint() {
+  let mut vm = Vm::new();
+  started(&mut vm, SRC);
+  vm.set_breakpoint(5);
+  vm.set_breakpoint(6);
+  assert_eq!(vm.resume().unwrap(), RunOutcome::Paused { line: 5 });
+  assert_eq!(vm.global("a"), Some(Value::Number(1.0)));
+  assert_eq!(vm.global("b"), None, "line 5 hasn't run yet");
+  assert_eq!(vm.resume().unwrap(), RunOutcome::Paused { line: 6 });
+  assert_eq!(vm.global("b"), Some(Value::Number(2.0)));
+  assert_eq!(
+    vm.resume().unwrap(),
+    RunOutcome::Finished(Some(Value::Number(4.0)))
+  );
+}
+
+#[test]
+fn breakpoints_in_functions_pause_on_each_call() {
+  let mut vm = Vm::new();
+  started(&mut vm, SRC);
+  vm.set_breakpoint(3);
+  assert_eq!(vm.resume().unwrap(), RunOutcome::Paused { line: 3 });
+  assert_eq!(vm.resume().unwrap(), RunOutcome::Paused { line: 3 });
+  assert!(matches!(vm.resume().unwrap(), RunOutcome::Finished(_)));
+}
+
+#[test]
+fn returning_to_a_line_doesnt_pause_again() {
+  let mut vm = Vm::new();
+  started(&mut vm, SRC);
+  vm.set_breakpoint(5);
+  vm.set_breakpoint(3);
+  assert_eq!(vm.resume().unwrap(), RunOutcome::Paused { line: 5 });
+  assert_eq!(vm.resume().unwrap(), RunOutcome::Paused { line: 3 });
+  // back on line 5 after the call, then line 6 calls `double` again
+  assert_eq!(vm.resume().unwrap(), RunOutcome::Paused { line: 3 });
+  assert_eq!(vm.global("b"), Some(Value::Number(2.0)));
+}
+
+#[test]
+fn cleared_breakpoints_dont_pause() {
+  let mut vm = Vm::new();
+  started(&mut vm, SRC);
+  vm.set_breakpoint(5);
+  assert!(vm.clear_breakpoint(5));
+  assert!(!vm.clear_breakpoint(5));
+  assert_eq!(
+    vm.resume().unwrap(),
+    RunOutcome::Finished(Some(Value::Number(4.0)))
+  );
+}
+
+#[test]
+fn run_ignores_breakpoints() {
+  let mut vm = VM::init();
+  vm.set_breakpoint(1);
+  vm.set_breakpoint(3);
+  assert_eq!(
+    vm.interpret(SRC.to_owned()).unwrap(),
+    Some(RawValue::number_val(4.0))
+  );
+  assert_eq!(vm.breakpoints(), [1, 3]);
+}
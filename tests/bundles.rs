@@ -0,0 +1,217 @@
+use rlox::{
+  bundle::{build_executable, embedded_bundle, is_bundle, BundleOptions},
+  native::Capabilities,
+  utils::Init,
+  value::Value,
+  vm::{InterpretError, VM},
+};
+use std::path::PathBuf;
+
+/// A fresh temporary directory (named after the test) with the files, as `(path, source)`.
+fn project(name: &str, files: &[(&str, &str)]) -> PathBuf {
+  let dir = std::env::temp_dir().join(format!("rlox_bundles_{}", name));
+  let _ = std::fs::remove_dir_all(&dir);
+  for (path, src) in files {
+    let path = dir.join(path);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, src).unwrap();
+  }
+  dir
+}
+
+/// A virtual machine bundling files, which imports modules from files.
+fn bundler() -> VM {
+  let mut vm = VM::init();
+  vm.set_capabilities(Capabilities {
+    files: true,
+    ..Capabilities::none()
+  });
+  vm
+}
+
+#[test]
+fn bundles_run_without_sources() {
+  let dir = project(
+    "without_sources",
+    &[
+      (
+        "main.lox",
+        "import \"lib/greet\"; import \"math\"; greet(\"lox\") + \" \" + str(max(1, 2));",
+      ),
+      (
+        "lib/greet.lox",
+        "fun greet(name) { return \"hello \" + name; }",
+      ),
+    ],
+  );
+  let bytes = bundler()
+    .bundle_file(
+      dir.join("main.lox").display().to_string(),
+      BundleOptions::init(),
+    )
+    .unwrap();
+  assert!(is_bundle(&bytes));
+  std::fs::remove_dir_all(&dir).unwrap();
+
+  let mut vm = VM::init();
+  assert_eq!(
+    vm.interpret_bundle(&bytes, "app.loxb")
+      .unwrap()
+      .map(|value| value.to_string()),
+    Some("\"hello lox 2\"".to_owned())
+  );
+}
+
+#[test]
+fn modules_of_bundles_run_once() {
+  let dir = project(
+    "once",
+    &[
+      (
+        "main.lox",
+        "var runs = 0; import \"a\"; import \"counter\"; runs;",
+      ),
+      ("a.lox", "import \"counter\";"),
+      ("counter.lox", "runs = runs + 1;"),
+    ],
+  );
+  let bytes = bundler()
+    .bundle_file(
+      dir.join("main.lox").display().to_string(),
+      BundleOptions::init(),
+    )
+    .unwrap();
+  let mut vm = VM::init();
+  assert_eq!(
+    vm.interpret_bundle(&bytes, "app.loxb").unwrap(),
+    Some(Value::from(1.0))
+  );
+}
+
+#[test]
+fn runtime_errors_are_located_in_module_files() {
+  let dir = project(
+    "locations",
+    &[
+      ("main.lox", "import \"lib\";\nboom();"),
+      ("lib.lox", "fun boom() {\n  return nil + 1;\n}"),
+    ],
+  );
+  let bytes = bundler()
+    .bundle_file(
+      dir.join("main.lox").display().to_string(),
+      BundleOptions::init(),
+    )
+    .unwrap();
+  let mut vm = VM::init();
+  let Err(InterpretError::RuntimeError(message)) = vm.interpret_bundle(&bytes, "app.loxb") else {
+    panic!("expected a runtime error");
+  };
+  assert!(message.contains("lib.lox:2:"), "{}", message);
+  assert!(message.contains("main.lox:2:"), "{}", message);
+}
+
+#[test]
+fn compile_errors_fail_bundling() {
+  let dir = project("compile_error", &[("main.lox", "import \"missing\";")]);
+  let error = bundler()
+    .bundle_file(
+      dir.join("main.lox").display().to_string(),
+      BundleOptions::init(),
+    )
+    .unwrap_err()
+    .into_message();
+  assert!(
+    error.contains("Can't find module `missing.lox`"),
+    "{}",
+    error
+  );
+}
+
+#[test]
+fn broken_bundles_are_refused() {
+  let mut vm = VM::init();
+  let error = vm
+    .interpret_bundle(b"LOXC", "app.loxb")
+    .unwrap_err()
+    .into_message();
+  assert_eq!(error, "Bundle `app.loxb` isn't a bundle of rlox.");
+
+  let mut bytes = VM::init().bundle_source("1 + 2;".into(), None).unwrap();
+  bytes.truncate(bytes.len() - 1);
+  let error = vm
+    .interpret_bundle(&bytes, "app.loxb")
+    .unwrap_err()
+    .into_message();
+  assert_eq!(error, "Bundle `app.loxb` is broken.");
+}
+
+#[test]
+fn stripped_bundles_run_without_debug_info() {
+  let dir = project(
+    "stripped",
+    &[
+      ("main.lox", "import \"lib\";\nprint boom;\nboom();"),
+      ("lib.lox", "fun boom() {\n  return nil + 1;\n}"),
+    ],
+  );
+  let path = dir.join("main.lox").display().to_string();
+  let full = bundler()
+    .bundle_file(path.clone(), BundleOptions::init())
+    .unwrap();
+  let stripped = bundler()
+    .bundle_file(path, BundleOptions { debug_info: false })
+    .unwrap();
+  assert!(stripped.len() < full.len());
+  let contains = |bytes: &[u8], text: &str| bytes.windows(text.len()).any(|w| w == text.as_bytes());
+  assert!(contains(&full, "main.lox") && contains(&full, "boom"));
+  assert!(!contains(&stripped, "main.lox"));
+
+  let mut vm = VM::init();
+  let Err(InterpretError::RuntimeError(message)) = vm.interpret_bundle(&stripped, "app.loxb")
+  else {
+    panic!("expected a runtime error");
+  };
+  assert_eq!(
+    message,
+    "[unknown location] in #0: Operands must be 2 numbers or 2 strings.\n\
+     [unknown location] in script"
+  );
+}
+
+#[test]
+fn executables_embed_bundles() {
+  let dir = project(
+    "executable",
+    &[("main.lox", "var answer = 6 * 7; answer;"), ("runtime", "")],
+  );
+  std::fs::write(dir.join("runtime"), b"\x7fELF runtime").unwrap();
+  let bytes = bundler()
+    .bundle_file(
+      dir.join("main.lox").display().to_string(),
+      BundleOptions::init(),
+    )
+    .unwrap();
+  assert_eq!(embedded_bundle(&dir.join("runtime")).unwrap(), None);
+
+  build_executable(&dir.join("runtime"), &bytes, &dir.join("app")).unwrap();
+  let executable = std::fs::read(dir.join("app")).unwrap();
+  assert!(executable.starts_with(b"\x7fELF runtime"));
+  let embedded = embedded_bundle(&dir.join("app")).unwrap().unwrap();
+  assert_eq!(embedded, bytes);
+  let mut vm = VM::init();
+  assert_eq!(
+    vm.interpret_bundle(&embedded, "app").unwrap(),
+    Some(Value::from(42.0))
+  );
+
+  // building from an executable replaces the bundle it embeds
+  let other = VM::init().bundle_source("1;".into(), None).unwrap();
+  build_executable(&dir.join("app"), &other, &dir.join("other")).unwrap();
+  let executable = std::fs::read(dir.join("other")).unwrap();
+  assert_eq!(
+    executable.len(),
+    b"\x7fELF runtime".len() + other.len() + 16
+  );
+  assert_eq!(embedded_bundle(&dir.join("other")).unwrap(), Some(other));
+}
@@ -0,0 +1,66 @@
+mod common;
+
+use common::{run, runtime_error};
+
+#[test]
+fn bytes_of_a_string() {
+  let src = r#"
+    assert(byteAt("AZ", 0) == 65);
+    assert(byteAt("AZ", 1) == 90);
+    assert(byteAt("é", 0) == 195);
+    assert(byteAt("é", 1) == 169);
+    assert(byteAt("AZ", 2) == nil);
+    assert(byteAt("AZ", -1) == nil);
+    assert(byteAt("", 0) == nil);
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn code_points_of_a_string() {
+  let src = r#"
+    assert(codePointAt("aé🦀", 0) == 97);
+    assert(codePointAt("aé🦀", 1) == 233);
+    assert(codePointAt("aé🦀", 2) == 129408);
+    assert(codePointAt("aé🦀", 3) == nil);
+    assert(fromCodePoint(233) == "é");
+    assert(fromCodePoint(codePointAt("lox", 0)) + "ox" == "lox");
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn character_level_processing() {
+  let src = r#"
+    fun count(s, c, i) {
+      var b = byteAt(s, i);
+      if (b == nil) return 0;
+      if (b == c) return 1 + count(s, c, i + 1);
+      return count(s, c, i + 1);
+    }
+    assert(count("a,b,,c", byteAt(",", 0), 0) == 3);
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn invalid_arguments() {
+  let message = runtime_error("byteAt(1, 0);");
+  assert!(
+    message.contains("`byteAt` expected a string."),
+    "{}",
+    message
+  );
+  let message = runtime_error("codePointAt(\"a\", 0.5);");
+  assert!(
+    message.contains("`codePointAt` expected an integer index."),
+    "{}",
+    message
+  );
+  let message = runtime_error("fromCodePoint(55296);");
+  assert!(
+    message.contains("`fromCodePoint` expected a valid code point."),
+    "{}",
+    message
+  );
+}
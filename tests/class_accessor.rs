@@ -0,0 +1,45 @@
+mod common;
+
+use common::{compile_error, run, runtime_error};
+
+#[test]
+fn getter_and_setter() {
+  let src = r#"
+    class Rect {
+      init(w, h) { this.w = w; this.h = h; }
+      get area { return this.w * this.h; }
+      set side(v) { this.w = v; this.h = v; }
+    }
+    var r = Rect(2, 3);
+    assert(r.area == 6);
+    assert((r.side = 4) == 4);
+    assert(r.area == 16);
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn get_and_set_are_still_method_names() {
+  let src = r#"
+    class Box {
+      get(x) { return x; }
+      set(x) { this.x = x; }
+    }
+    var b = Box();
+    b.set(1);
+    assert(b.get(2) == 2 and b.x == 1);
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn assign_to_read_only_property() {
+  let src = "class C { get x { return 1; } } C().x = 2;";
+  assert!(runtime_error(src).contains("Property `x` has a getter but no setter."));
+}
+
+#[test]
+fn setter_arity() {
+  let src = "class C { set x(a, b) {} }";
+  assert!(compile_error(src).contains("Setter must have exactly one parameter."));
+}
@@ -0,0 +1,115 @@
+mod common;
+
+use common::{compile_error, run, runtime_error};
+
+#[test]
+fn methods_are_inherited() {
+  let src = r#"
+    class Animal {
+      init(name) { this.name = name; }
+      speak() { return this.name + " makes a sound"; }
+      get loud { return this.speak() + "!"; }
+    }
+    class Dog < Animal {}
+    var d = Dog("Rex");
+    assert(d.speak() == "Rex makes a sound");
+    assert(d.loud == "Rex makes a sound!");
+    assert(d is Dog and d is Animal);
+    assert(!(Animal("Cat") is Dog));
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn methods_are_overridden() {
+  let src = r#"
+    class A { name() { return "A"; } describe() { return "I'm " + this.name(); } }
+    class B < A { name() { return "B"; } }
+    assert(B().describe() == "I'm B");
+    assert(A().describe() == "I'm A");
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn super_calls_methods_of_the_superclass() {
+  let src = r#"
+    class A {
+      init(n) { this.n = n; }
+      value() { return this.n; }
+      get doubled { return this.n * 2; }
+    }
+    class B < A {
+      init(n) { super.init(n + 1); }
+      value() { return super.value() * 10; }
+      bound() { return super.value; }
+      doubled_by_super() { return super.doubled; }
+    }
+    class C < B {
+      value() { return super.value() + 1; }
+    }
+    var c = C(1);
+    assert(c.n == 2);
+    assert(c.value() == 21);
+    assert(c.bound()() == 2);
+    assert(c.doubled_by_super() == 4);
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn super_is_captured_by_nested_functions() {
+  let src = r#"
+    class A { hi() { return "A"; } }
+    class B < A {
+      hi() {
+        fun inner() { return super.hi() + "B"; }
+        return inner;
+      }
+    }
+    assert(B().hi()() == "AB");
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn local_classes_inherit() {
+  let src = r#"
+    fun make() {
+      class Base { tag() { return "base"; } }
+      class Derived < Base { tag() { return super.tag() + "+"; } }
+      return Derived();
+    }
+    assert(make().tag() == "base+");
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn traits_override_inherited_methods() {
+  let src = r#"
+    class A { m() { return "A"; } n() { return "A"; } }
+    trait T { m() { return "T"; } }
+    class B < A with T { n() { return super.n() + "B"; } }
+    assert(B().m() == "T");
+    assert(B().n() == "AB");
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn errors_of_inheritance() {
+  assert!(compile_error("class A < A {}").contains("A class can't inherit from itself."));
+  assert!(compile_error("print super.m;").contains("Can't use `super` outside of a class."));
+  assert!(compile_error("class A { m() { super.m(); } }")
+    .contains("Can't use `super` in a class with no superclass."));
+  assert!(compile_error("class A {} class B < A { m() { super; } }")
+    .contains("Expect `.` after `super`."));
+  assert!(runtime_error("var A = 1; class B < A {}").contains("Superclass must be a class."));
+  assert!(runtime_error("trait T {} class B < T {}")
+    .contains("Can't inherit from trait `T`, mix it into `B` by `with` instead."));
+  assert!(
+    runtime_error("class A {} class B < A { m() { return super.missing(); } } B().m();")
+      .contains("Undefined property `missing`.")
+  );
+}
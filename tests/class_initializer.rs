@@ -0,0 +1,52 @@
+mod common;
+
+use common::{compile_error, run, runtime_error};
+
+#[test]
+fn initializer_runs_on_construction() {
+  let src = r#"
+    class Point {
+      init(x, y = 0) { this.x = x; this.y = y; }
+      sum() { return this.x + this.y; }
+    }
+    var p = Point(1, 2);
+    assert(p.x == 1 and p.y == 2);
+    assert(p.sum() == 3);
+    assert(Point(5).sum() == 5);
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn initializer_returns_instance_implicitly() {
+  let src = r#"
+    class Foo {
+      init() { this.count = 0; return; }
+    }
+    var foo = Foo();
+    assert(foo.init() == foo);
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn initializer_arity_is_validated() {
+  let src = "class Point { init(x, y) {} } Point(1);";
+  let message = runtime_error(src);
+  assert!(message.contains("Initializer of class `Point` expected 2 arguments but got 1."));
+
+  let message = runtime_error("class Empty {} Empty(1);");
+  assert!(message.contains("Class `Empty` has no initializer, expected 0 arguments but got 1."));
+}
+
+#[test]
+fn return_value_from_initializer() {
+  let message = compile_error("class Foo { init() { return 1; } }");
+  assert!(message.contains("Can't return a value from an initializer."));
+}
+
+#[test]
+fn this_outside_of_class() {
+  let message = compile_error("print this;");
+  assert!(message.contains("Can't use `this` outside of a class."));
+}
@@ -0,0 +1,165 @@
+mod common;
+
+use common::{compile_error, run, runtime_error};
+use rlox::{Source, Value, Vm};
+
+#[test]
+fn counter_keeps_its_captured_local() {
+  let src = r#"
+    fun make_counter() {
+      var count = 0;
+      fun counter() { count = count + 1; return count; }
+      return counter;
+    }
+    var a = make_counter();
+    var b = make_counter();
+    assert(a() == 1);
+    assert(a() == 2);
+    assert(b() == 1);
+    assert(a() == 3);
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn closures_share_captured_variables() {
+  let src = r#"
+    var get;
+    var set;
+    {
+      var shared = "before";
+      fun getter() { return shared; }
+      fun setter(value) { shared = value; }
+      get = getter;
+      set = setter;
+      shared = "local";
+      assert(get() == "local");
+    }
+    assert(get() == "local");
+    set("after");
+    assert(get() == "after");
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn locals_are_captured_through_enclosing_functions() {
+  let src = r#"
+    fun outer() {
+      var x = "outer";
+      fun middle() {
+        fun inner() { return x; }
+        return inner;
+      }
+      return middle;
+    }
+    assert(outer()()() == "outer");
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn local_functions_recur_through_themselves() {
+  let src = r#"
+    fun factorial(n) {
+      fun fact(k) {
+        if (k <= 1) return 1;
+        return k * fact(k - 1);
+      }
+      return fact(n);
+    }
+    assert(factorial(5) == 120);
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn each_scope_entry_captures_a_fresh_variable() {
+  let src = r#"
+    fun make(value) {
+      var captured = value;
+      fun get() { return captured; }
+      return get;
+    }
+    var one = make(1);
+    var two = make(2);
+    assert(one() == 1);
+    assert(two() == 2);
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn thrown_errors_close_captured_variables() {
+  let src = r#"
+    var escaped;
+    fun fail() {
+      var kept = "kept";
+      fun get() { return kept; }
+      escaped = get;
+      throw "failed";
+    }
+    try { fail(); } catch (e) { assert(e == "failed"); }
+    assert(escaped() == "kept");
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn this_is_captured_by_functions_in_methods() {
+  let src = r#"
+    class Box {
+      init(value) { this.value = value; }
+      reader() {
+        fun read() { return this.value; }
+        return read;
+      }
+    }
+    var read = Box(7).reader();
+    assert(read() == 7);
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn methods_capture_locals_of_enclosing_functions() {
+  let src = r#"
+    fun make_class(greeting) {
+      class Greeter {
+        greet(name) { return greeting + ", " + name; }
+      }
+      return Greeter;
+    }
+    var Greeter = make_class("hello");
+    assert(Greeter().greet("lox") == "hello, lox");
+    var greet = Greeter().greet;
+    assert(greet("bound") == "hello, bound");
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn closures_are_functions() {
+  let mut vm = Vm::new();
+  let src = "fun f() { var x = 1; fun g() { return x; } return g; } var g = f(); str(g) + \" \" + type_of(g);";
+  assert_eq!(
+    vm.eval(&Source::new(src)).unwrap(),
+    Some(Value::String("<fn g> function".to_owned()))
+  );
+}
+
+#[test]
+fn arity_of_closures_is_checked() {
+  let message = runtime_error("fun f() { var x = 1; fun g(a) { return x + a; } return g; } f()();");
+  assert!(
+    message.contains("Function `g` expected 1 arguments but got 0."),
+    "{}",
+    message
+  );
+}
+
+#[test]
+fn reading_a_local_in_its_own_initializer_is_still_an_error() {
+  let message = compile_error("fun f() { var a = 1; { var a = a; } }");
+  assert!(message.contains("Can't read local variable in its own initializer."));
+}
@@ -0,0 +1,31 @@
+#![allow(dead_code)]
+
+use rlox::{
+  utils::Init,
+  vm::{InterpretError, VM},
+};
+
+/// `assert(cond)` fails at runtime (by reading an undefined global) iff `cond` is false.
+pub const PRELUDE: &str = "fun assert(cond) { if (!cond) assertion_failed; }\n";
+
+/// Interpret the given source (with `PRELUDE`) on a fresh virtual machine.
+pub fn run(src: &str) -> Result<(), InterpretError> {
+  let mut vm = VM::init();
+  vm.interpret(PRELUDE.to_owned() + src).map(|_| ())
+}
+
+/// Interpret the given source, then return the message of the runtime error.
+pub fn runtime_error(src: &str) -> String {
+  match run(src) {
+    Err(InterpretError::RuntimeError(message)) => message,
+    other => panic!("expect a runtime error, but got: {:?}", other),
+  }
+}
+
+/// Interpret the given source, then return the message of the compile error.
+pub fn compile_error(src: &str) -> String {
+  match run(src) {
+    Err(InterpretError::CompileError(message)) => message,
+    other => panic!("expect a compile error, but got: {:?}", other),
+  }
+}
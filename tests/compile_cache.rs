@@ -0,0 +1,292 @@
+use rlox::{
+  cache::{cache_path, read_header, CacheOptions},
+  compiler::{CompileOptions, OptLevel},
+  isa::ISA_VERSION,
+  scanner::{Keywords, TokenType},
+  utils::Init,
+  value::Value,
+  vm::{InterpretError, VM},
+};
+use std::path::PathBuf;
+
+/// Write the source into a temporary file, named after the test.
+fn script(name: &str, src: &str) -> PathBuf {
+  let path = std::env::temp_dir().join(format!("rlox_compile_cache_{}.lox", name));
+  std::fs::write(&path, src).unwrap();
+  path
+}
+
+fn on_disk_vm() -> VM {
+  let mut vm = VM::init();
+  vm.set_cache_options(CacheOptions {
+    on_disk: true,
+    ..CacheOptions::init()
+  });
+  vm
+}
+
+#[test]
+fn unchanged_source_is_compiled_once() {
+  let mut vm = VM::init();
+  let src = "fun f(a, b) { return a * b; } f(6, 7);";
+  assert_eq!(
+    vm.interpret(src.to_owned()).unwrap(),
+    Some(Value::from(42.0))
+  );
+  assert_eq!(
+    vm.interpret(src.to_owned()).unwrap(),
+    Some(Value::from(42.0))
+  );
+  assert_eq!(vm.compile_cache().misses(), 1);
+  assert_eq!(vm.compile_cache().hits(), 1);
+
+  vm.interpret("f(2, 3);".to_owned()).unwrap();
+  assert_eq!(vm.compile_cache().misses(), 2);
+}
+
+#[test]
+fn cached_script_replays_its_warnings() {
+  let mut vm = VM::init();
+  vm.set_compile_options(CompileOptions {
+    warn_shadowing: true,
+    ..CompileOptions::init()
+  });
+  let src = "{ var a = 1; { var a = 2; } }";
+  vm.interpret(src.to_owned()).unwrap();
+  let warnings = vm.warnings().to_vec();
+  assert!(!warnings.is_empty());
+  vm.interpret(src.to_owned()).unwrap();
+  assert_eq!(vm.compile_cache().hits(), 1);
+  assert_eq!(vm.warnings(), warnings);
+}
+
+#[test]
+fn compile_options_are_part_of_the_key() {
+  let mut vm = VM::init();
+  let src = "{ var a = 1; { var a = 2; } }";
+  vm.interpret(src.to_owned()).unwrap();
+  vm.set_compile_options(CompileOptions {
+    warn_shadowing: true,
+    ..CompileOptions::init()
+  });
+  vm.interpret(src.to_owned()).unwrap();
+  assert_eq!(vm.compile_cache().hits(), 0);
+  assert!(!vm.warnings().is_empty());
+}
+
+#[test]
+fn keyword_dialects_are_part_of_the_key() {
+  let mut vm = VM::init();
+  let src = "var let = 1; let;";
+  assert_eq!(
+    vm.interpret(src.to_owned()).unwrap(),
+    Some(Value::from(1.0))
+  );
+  let mut keywords = Keywords::standard();
+  keywords.insert("let", TokenType::Var).unwrap();
+  vm.set_keywords(keywords);
+  assert!(vm.interpret(src.to_owned()).is_err());
+  assert_eq!(vm.compile_cache().hits(), 0);
+}
+
+#[test]
+fn strict_mode_is_never_cached() {
+  let mut vm = VM::init();
+  vm.set_compile_options(CompileOptions {
+    strict: true,
+    ..CompileOptions::init()
+  });
+  let src = "a = 1;";
+  assert!(matches!(
+    vm.interpret(src.to_owned()),
+    Err(InterpretError::CompileError(_))
+  ));
+  vm.interpret("var a;".to_owned()).unwrap();
+  vm.interpret(src.to_owned()).unwrap();
+  assert_eq!(vm.compile_cache().hits(), 0);
+}
+
+#[test]
+fn compile_errors_are_not_cached() {
+  let mut vm = VM::init();
+  for _ in 0..2 {
+    assert!(matches!(
+      vm.interpret("var = 1;".to_owned()),
+      Err(InterpretError::CompileError(_))
+    ));
+  }
+  assert_eq!(vm.compile_cache().hits(), 0);
+  assert_eq!(vm.compile_cache().misses(), 0);
+}
+
+#[test]
+fn disabled_in_memory_cache_compiles_each_time() {
+  let mut vm = VM::init();
+  vm.set_cache_options(CacheOptions {
+    in_memory: false,
+    on_disk: false,
+  });
+  vm.interpret("1;".to_owned()).unwrap();
+  vm.interpret("1;".to_owned()).unwrap();
+  assert_eq!(vm.compile_cache().hits(), 0);
+}
+
+#[test]
+fn file_script_is_cached_on_disk() {
+  let src = r#"
+    class Pair {
+      init(a, b) { this.a = a; this.b = b; }
+      sum() { return this.a + this.b; }
+    }
+    var greeting = "hello" + " " + "world";
+    Pair(1.5, 2).sum();
+  "#;
+  let path = script("on_disk", src);
+  let file = path.to_string_lossy().to_string();
+
+  let mut vm = on_disk_vm();
+  assert_eq!(
+    vm.interpret_file(file.clone()).unwrap(),
+    Some(Value::from(3.5))
+  );
+  assert!(cache_path(&file).exists());
+
+  // a fresh virtual machine loads the script from disk
+  let mut vm = on_disk_vm();
+  assert_eq!(
+    vm.interpret_file(file.clone()).unwrap(),
+    Some(Value::from(3.5))
+  );
+  assert_eq!(vm.compile_cache().hits(), 1);
+  assert_eq!(vm.compile_cache().misses(), 0);
+
+  // runtime errors of the loaded script are still located in the file
+  std::fs::write(&path, "var a = 1;\nvar b = a + nil;\n").unwrap();
+  let mut vm = on_disk_vm();
+  vm.interpret_file(file.clone()).unwrap_err();
+  let mut vm = on_disk_vm();
+  match vm.interpret_file(file.clone()) {
+    Err(InterpretError::RuntimeError(message)) => {
+      assert!(message.starts_with(&format!("[{}:2:", file)), "{}", message)
+    }
+    other => panic!("expect a runtime error, but got: {:?}", other),
+  }
+  assert_eq!(vm.compile_cache().hits(), 1);
+
+  std::fs::remove_file(cache_path(&file)).unwrap();
+  std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn stale_or_corrupted_cache_file_is_ignored() {
+  let path = script("corrupted", "40 + 2;");
+  let file = path.to_string_lossy().to_string();
+  std::fs::write(cache_path(&file), b"LOXC garbage").unwrap();
+
+  let mut vm = on_disk_vm();
+  assert_eq!(
+    vm.interpret_file(file.clone()).unwrap(),
+    Some(Value::from(42.0))
+  );
+  assert_eq!(vm.compile_cache().misses(), 1);
+
+  // the script changed since it's cached
+  std::fs::write(&path, "40 + 3;").unwrap();
+  let mut vm = on_disk_vm();
+  assert_eq!(
+    vm.interpret_file(file.clone()).unwrap(),
+    Some(Value::from(43.0))
+  );
+  assert_eq!(vm.compile_cache().hits(), 0);
+
+  std::fs::remove_file(cache_path(&file)).unwrap();
+  std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn opt_level_is_part_of_the_key() {
+  let mut vm = VM::init();
+  let src = "1 + 2;";
+  vm.interpret(src.to_owned()).unwrap();
+  vm.set_compile_options(CompileOptions {
+    opt_level: OptLevel::O1,
+    ..CompileOptions::init()
+  });
+  vm.interpret(src.to_owned()).unwrap();
+  assert_eq!(vm.compile_cache().hits(), 0);
+}
+
+#[test]
+fn cache_files_of_incompatible_instruction_sets_are_refused() {
+  let path = script("incompatible_isa", "40 + 2;");
+  let file = path.to_string_lossy().to_string();
+  let cache_file = cache_path(&file);
+  let cache_name = cache_file.to_string_lossy().to_string();
+
+  on_disk_vm().interpret_file(file.clone()).unwrap();
+  let mut buffer = std::fs::read(&cache_file).unwrap();
+  let header = read_header(&buffer, &cache_name).unwrap();
+  assert_eq!(header.isa_version, ISA_VERSION);
+
+  // as if it's compiled by a future rlox
+  buffer[8..12].copy_from_slice(&(ISA_VERSION + 1).to_le_bytes());
+  std::fs::write(&cache_file, &buffer).unwrap();
+  let expected = format!(
+    "Cache file `{}` is compiled for instruction set v{}, but this rlox runs instruction set v{}.",
+    cache_name,
+    ISA_VERSION + 1,
+    ISA_VERSION
+  );
+  match read_header(&buffer, &cache_name) {
+    Err(InterpretError::CompileError(message)) => assert_eq!(message, expected),
+    other => panic!("expect a compile error, but got: {:?}", other),
+  }
+
+  // refused with a warning, then compiled again into a compatible file
+  let mut vm = on_disk_vm();
+  assert_eq!(
+    vm.interpret_file(file.clone()).unwrap(),
+    Some(Value::from(42.0))
+  );
+  assert_eq!(vm.compile_cache().hits(), 0);
+  assert_eq!(
+    vm.warnings(),
+    [format!("{} It's compiled again.", expected)]
+  );
+  let buffer = std::fs::read(&cache_file).unwrap();
+  assert_eq!(
+    read_header(&buffer, &cache_name).unwrap().isa_version,
+    ISA_VERSION
+  );
+
+  std::fs::remove_file(&cache_file).unwrap();
+  std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn captured_variables_survive_cache_files() {
+  let src = r#"
+    fun make_adder(a) {
+      fun add(b) { return a + b; }
+      return add;
+    }
+    make_adder(40)(2);
+  "#;
+  let path = script("closures", src);
+  let file = path.to_string_lossy().to_string();
+
+  let mut vm = on_disk_vm();
+  assert_eq!(
+    vm.interpret_file(file.clone()).unwrap(),
+    Some(Value::from(42.0))
+  );
+  let mut vm = on_disk_vm();
+  assert_eq!(
+    vm.interpret_file(file.clone()).unwrap(),
+    Some(Value::from(42.0))
+  );
+  assert_eq!(vm.compile_cache().hits(), 1);
+
+  std::fs::remove_file(cache_path(&file)).unwrap();
+  std::fs::remove_file(&path).unwrap();
+}
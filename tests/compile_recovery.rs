@@ -0,0 +1,71 @@
+mod common;
+
+use common::{compile_error, run};
+
+#[test]
+fn unterminated_string_reports_starting_line() {
+  let src = "var a = 1;\nvar s = \"never\nclosed\n";
+  // line 1 is taken by `PRELUDE`
+  assert_eq!(compile_error(src), "[line 3] Error: Unterminated string.");
+}
+
+#[test]
+fn block_comments_are_skipped() {
+  let src = r#"
+    /* a block comment */
+    var a = 1; /* spans
+    multiple lines */ assert(a == 1);
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn block_comments_count_lines() {
+  let src = "/*\n\n*/ var;";
+  assert_eq!(
+    compile_error(src),
+    "[line 4] Error at ';': Expect variable name."
+  );
+}
+
+#[test]
+fn unterminated_block_comment_reports_starting_line() {
+  let src = "var a = 1;\n/* never\nclosed\n";
+  assert_eq!(
+    compile_error(src),
+    "[line 3] Error: Unterminated block comment."
+  );
+}
+
+#[test]
+fn errors_of_each_statement_are_reported() {
+  let src = "var = 1;\nprint 2;\nprint ;\nvar b = 3;";
+  assert_eq!(
+    compile_error(src),
+    "[line 2] Error at '=': Expect variable name.\n[line 4] Error at ';': Expect expression."
+  );
+}
+
+#[test]
+fn parser_recovers_from_error_tokens() {
+  let src = "print 1; @ print 2; # print 3;";
+  assert_eq!(
+    compile_error(src),
+    "[line 2] Error: Unexpected character.\n[line 2] Error: Unexpected character."
+  );
+}
+
+#[test]
+fn errors_in_function_bodies_recover_within_the_body() {
+  let src = r#"
+fun f() {
+  var = 1;
+  return 2;
+}
+print f(;
+"#;
+  assert_eq!(
+    compile_error(src),
+    "[line 4] Error at '=': Expect variable name.\n[line 7] Error at ';': Expect expression."
+  );
+}
@@ -0,0 +1,101 @@
+use rlox::{
+  compiler::stats::CONSTANTS_MAX, utils::Init, vm::VM, CompileStats, FunctionStats, Source, Vm,
+};
+
+/// Stats of the source, compiled by the facade.
+fn stats_of(src: &str) -> CompileStats {
+  let mut vm = Vm::new();
+  vm.compile(&Source::new(src)).unwrap().stats()
+}
+
+/// Stats of the function of the qualified name.
+fn function<'a>(stats: &'a CompileStats, name: &str) -> &'a FunctionStats {
+  stats
+    .functions
+    .iter()
+    .find(|function| function.name == name)
+    .unwrap()
+}
+
+#[test]
+fn each_function_is_reported() {
+  let stats = stats_of(
+    "fun outer(a) { fun inner() { return a; } return inner; }
+    class Point { init(x, y) { this.x = x; this.y = y; } }",
+  );
+  let names: Vec<_> = stats.functions.iter().map(|f| f.name.as_str()).collect();
+  assert_eq!(names, ["<script>", "outer.inner", "outer", "Point.init"]);
+  assert_eq!(function(&stats, "outer.inner").upvalues, 1);
+  assert_eq!(function(&stats, "Point.init").local_slots, 3);
+  assert!(stats.functions.iter().all(|f| f.code_size > 0));
+  assert!(stats.warnings().is_empty());
+}
+
+#[test]
+fn max_stack_counts_locals_and_temporaries() {
+  let stats = stats_of(
+    "fun add(a, b) {
+      var sum = a + b;
+      return sum;
+    }
+    fun nested(a) { return a + (a + (a + a)); }",
+  );
+  // callee, a, b, sum, then `a` and `b` pushed to be added
+  let add = function(&stats, "add");
+  assert_eq!(add.local_slots, 4);
+  assert_eq!(add.max_stack, Some(5));
+  // callee, a, then four `a` pushed before any is added
+  assert_eq!(function(&stats, "nested").max_stack, Some(6));
+}
+
+#[test]
+fn max_stack_follows_branches_and_handlers() {
+  let stats = stats_of(
+    "fun f(c) {
+      if (c) { var x = 1; var y = 2; return x + y; }
+      for (var i = 0; i < 2; i = i + 1) {}
+      try { throw c; } catch (e) { return e; }
+    }",
+  );
+  // callee, c, x, y, then `x` and `y` pushed to be added
+  assert_eq!(function(&stats, "f").max_stack, Some(6));
+}
+
+#[test]
+fn constants_approaching_the_limit_are_warned() {
+  let count = CONSTANTS_MAX * 4 / 5;
+  let body: String = (0..count).map(|i| format!("print {}.5;", i)).collect();
+  let stats = stats_of(&format!("fun many() {{ {} }}", body));
+  assert_eq!(function(&stats, "many").constants, count);
+  assert_eq!(
+    stats.warnings(),
+    [format!(
+      "Function `many` uses {} of {} constants of a chunk.",
+      count, CONSTANTS_MAX
+    )]
+  );
+  let report = stats.to_string();
+  assert!(report.starts_with("function"));
+  assert!(report.contains(&format!("{}/{}", count, CONSTANTS_MAX)));
+  assert!(report.ends_with(&format!(
+    "Warning: Function `many` uses {} of {} constants of a chunk.\n",
+    count, CONSTANTS_MAX
+  )));
+}
+
+#[test]
+fn stats_of_sources_match_the_facade() {
+  let src = "var a = 1; fun f(x) { return x + a; }";
+  let mut vm = VM::init();
+  assert_eq!(vm.compile_stats(src.to_owned()).unwrap(), stats_of(src));
+}
+
+#[test]
+fn max_stack_matches_runs_without_calls() {
+  let src = "var a = 1; { var b = 2; var c = a + (b * (a - b)); print c; } a = a + 1;";
+  let mut vm = Vm::new();
+  let script = vm.compile(&Source::new(src)).unwrap();
+  let max_stack = script.stats().functions[0].max_stack.unwrap();
+  vm.run(&script).unwrap();
+  assert_eq!(vm.debugger().peak_stack_size(), max_stack);
+}
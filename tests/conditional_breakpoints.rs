@@ -0,0 +1,153 @@
+use rlox::{Breakpoint, DiagnosticKind, RunOutcome, Source, Value, Vm};
+
+const SRC: &str = "fun count(n) {
+  var left = n - 1;
+  if (left > 0) count(left);
+  return n;
+}
+count(5);";
+
+/// Compile the source, then start it (without running any instruction).
+fn started() -> Vm {
+  let mut vm = Vm::new();
+  let script = vm.compile(&Source::new(SRC)).unwrap();
+  vm.start(&script);
+  vm
+}
+
+/// Values of `n` at each pause, until the script is finished.
+fn pauses(vm: &mut Vm) -> Vec<Value> {
+  let mut values = vec![];
+  while let RunOutcome::Paused { line } = vm.resume().unwrap() {
+    assert_eq!(line, 2);
+    values.push(vm.debugger().eval_in_frame(0, "n").unwrap());
+  }
+  values
+}
+
+#[test]
+fn conditions_are_evaluated_in_the_innermost_call() {
+  let mut vm = started();
+  vm.set_breakpoint_with(
+    2,
+    Breakpoint {
+      condition: Some("n == 3".to_owned()),
+      ..Breakpoint::default()
+    },
+  )
+  .unwrap();
+  assert_eq!(pauses(&mut vm), [Value::Number(3.0)]);
+  assert_eq!(vm.debugger().breakpoint_hits(2), Some(1));
+}
+
+#[test]
+fn hit_counts_skip_early_hits() {
+  let mut vm = started();
+  vm.set_breakpoint_with(
+    2,
+    Breakpoint {
+      hit_count: Some(3),
+      ..Breakpoint::default()
+    },
+  )
+  .unwrap();
+  assert_eq!(
+    pauses(&mut vm),
+    [Value::Number(3.0), Value::Number(2.0), Value::Number(1.0)]
+  );
+  assert_eq!(vm.debugger().breakpoint_hits(2), Some(5));
+}
+
+#[test]
+fn hits_are_counted_only_when_conditions_hold() {
+  let mut vm = started();
+  vm.set_breakpoint_with(
+    2,
+    Breakpoint {
+      condition: Some("n < 4".to_owned()),
+      hit_count: Some(2),
+    },
+  )
+  .unwrap();
+  assert_eq!(pauses(&mut vm), [Value::Number(2.0), Value::Number(1.0)]);
+  assert_eq!(vm.debugger().breakpoint_hits(2), Some(3));
+}
+
+#[test]
+fn failed_conditions_pause() {
+  let mut vm = started();
+  vm.set_breakpoint_with(
+    2,
+    Breakpoint {
+      condition: Some("missing > 1".to_owned()),
+      ..Breakpoint::default()
+    },
+  )
+  .unwrap();
+  assert_eq!(pauses(&mut vm).len(), 5);
+}
+
+#[test]
+fn conditions_leave_the_run_intact() {
+  let mut vm = started();
+  vm.set_breakpoint_with(
+    4,
+    Breakpoint {
+      condition: Some("(n = 100) == 0".to_owned()),
+      ..Breakpoint::default()
+    },
+  )
+  .unwrap();
+  assert_eq!(
+    vm.resume().unwrap(),
+    RunOutcome::Finished(Some(Value::Number(5.0)))
+  );
+}
+
+#[test]
+fn conditions_must_be_expressions() {
+  let mut vm = started();
+  let error = vm
+    .set_breakpoint_with(
+      2,
+      Breakpoint {
+        condition: Some("print n".to_owned()),
+        ..Breakpoint::default()
+      },
+    )
+    .unwrap_err();
+  assert_eq!(error.kind(), DiagnosticKind::CompileError);
+  assert_eq!(error.message(), "Condition `print n` isn't an expression.");
+  assert_eq!(vm.debugger().breakpoint_hits(2), None);
+}
+
+#[test]
+fn setting_a_breakpoint_again_resets_its_hits() {
+  let mut vm = started();
+  vm.set_breakpoint(2);
+  assert_eq!(vm.resume().unwrap(), RunOutcome::Paused { line: 2 });
+  assert_eq!(vm.debugger().breakpoint_hits(2), Some(1));
+  vm.set_breakpoint(2);
+  assert_eq!(vm.debugger().breakpoint_hits(2), Some(0));
+}
+
+#[test]
+fn conditions_are_compiled_once() {
+  let mut vm = Vm::new();
+  let src = "var i = 0;\nwhile (i < 1000) {\n  i = i + 1;\n}";
+  let script = vm.compile(&Source::new(src)).unwrap();
+  vm.start(&script);
+  vm.set_breakpoint_with(
+    3,
+    Breakpoint {
+      condition: Some("i < 0".to_owned()),
+      ..Breakpoint::default()
+    },
+  )
+  .unwrap();
+  // neither the script nor the condition allocates objects while running
+  let allocated = rlox::gc::allocated_bytes();
+  assert_eq!(vm.resume().unwrap(), RunOutcome::Finished(None));
+  assert_eq!(rlox::gc::allocated_bytes(), allocated);
+  assert_eq!(vm.debugger().breakpoint_hits(3), Some(0));
+}
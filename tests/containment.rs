@@ -0,0 +1,49 @@
+mod common;
+
+use common::{run, runtime_error};
+
+#[test]
+fn substrings_are_in_strings() {
+  let src = r#"
+    var s = "crafting";
+    assert("craft" in s);
+    assert("ting" in s);
+    assert("" in s);
+    assert(!("Craft" in s));
+    assert(!("interpreters" in s));
+    assert("é" in "café");
+  "#;
+  assert!(run(src).is_ok());
+}
+
+#[test]
+fn items_are_in_lists() {
+  let src = r#"
+    var xs = [1, "two", nil, [3]];
+    assert(1 in xs);
+    assert("two" in xs);
+    assert(nil in xs);
+    assert(!(2 in xs));
+    assert(!("one" in xs));
+    assert(!(1 in []));
+    // lists equal by identity, as `==`
+    assert(!([3] in xs));
+    assert(xs[3] in xs);
+  "#;
+  assert!(run(src).is_ok());
+}
+
+#[test]
+fn in_binds_as_comparison() {
+  let src = r#"
+    assert(1 + 1 in [2] == true);
+    assert(!("a" in "b") and "b" in "abc");
+  "#;
+  assert!(run(src).is_ok());
+}
+
+#[test]
+fn searching_strings_for_non_strings_is_runtime_error() {
+  assert!(runtime_error("1 in \"123\";")
+    .contains("Left operand of `in` must be a string if the right one is."));
+}
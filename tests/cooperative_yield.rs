@@ -0,0 +1,90 @@
+use std::task::Poll;
+
+use rlox::{DiagnosticKind, Source, Value, Vm};
+
+/// Calls itself `2^n` times, so runs thousands of instructions (without loops).
+const BUSY: &str = "fun busy(n) { if (n > 0) { busy(n - 1); busy(n - 1); } return n; }\n";
+
+/// A virtual machine with the script started.
+fn started(src: &str) -> Vm {
+  let mut vm = Vm::new();
+  let script = vm.compile(&Source::new(src)).unwrap();
+  vm.start(&script);
+  vm
+}
+
+#[test]
+fn budgeted_runs_yield_until_finished() {
+  let mut vm = started(&(BUSY.to_owned() + "busy(6);"));
+  let mut polls = 0;
+  let value = loop {
+    polls += 1;
+    let before = vm.debugger().instruction_count();
+    match vm.run_for(100).unwrap() {
+      Poll::Ready(value) => break value,
+      Poll::Pending => assert_eq!(vm.debugger().instruction_count() - before, 100),
+    }
+  };
+  assert_eq!(value, Some(Value::Number(6.0)));
+  assert_eq!(polls, vm.debugger().instruction_count().div_ceil(100));
+}
+
+#[test]
+fn zero_budget_runs_nothing() {
+  let mut vm = started("1 + 2;");
+  assert_eq!(vm.run_for(0).unwrap(), Poll::Pending);
+  assert_eq!(vm.debugger().instruction_count(), 0);
+  assert_eq!(
+    vm.run_for(usize::MAX).unwrap(),
+    Poll::Ready(Some(Value::Number(3.0)))
+  );
+}
+
+#[test]
+fn errors_end_budgeted_runs() {
+  let mut vm = started(&(BUSY.to_owned() + "busy(4);\nnil + 1;"));
+  let error = loop {
+    match vm.run_for(10) {
+      Ok(Poll::Pending) => continue,
+      Ok(Poll::Ready(value)) => panic!("expect an error, but got: {:?}", value),
+      Err(error) => break error,
+    }
+  };
+  assert_eq!(error.kind(), DiagnosticKind::RuntimeError);
+
+  // the virtual machine could run other scripts then
+  assert_eq!(
+    vm.eval(&Source::new("busy(2);")).unwrap(),
+    Some(Value::Number(2.0))
+  );
+}
+
+#[test]
+fn scripts_could_be_restarted() {
+  let mut vm = Vm::new();
+  let script = vm
+    .compile(&Source::new(BUSY.to_owned() + "busy(5);"))
+    .unwrap();
+  for _ in 0..2 {
+    vm.start(&script);
+    let mut polls = 1;
+    while vm.run_for(50).unwrap().is_pending() {
+      polls += 1;
+    }
+    assert!(polls > 1);
+  }
+}
+
+#[test]
+fn timeouts_count_across_polls() {
+  let mut vm = started(&(BUSY.to_owned() + "busy(12);"));
+  vm.set_timeout(Some(std::time::Duration::from_millis(20)));
+  let error = loop {
+    match vm.run_for(1000) {
+      Ok(Poll::Pending) => std::thread::sleep(std::time::Duration::from_millis(5)),
+      Ok(Poll::Ready(_)) => panic!("expect a timeout"),
+      Err(error) => break error,
+    }
+  };
+  assert_eq!(error.kind(), DiagnosticKind::Timeout);
+}
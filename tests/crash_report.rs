@@ -0,0 +1,95 @@
+use rlox::{
+  chunk::{Chunk, OpCode},
+  utils::Init,
+  vm::{InterpretError, VM},
+};
+
+/// Run the hand-compiled chunk, expecting a fault.
+fn fault(vm: &mut VM, chunk: Chunk) -> String {
+  vm.rebind(chunk);
+  match vm.run() {
+    Err(InterpretError::Fault(message)) => message,
+    other => panic!("expect a fault, but got: {:?}", other),
+  }
+}
+
+#[test]
+fn internal_errors_capture_the_state() {
+  let mut chunk = Chunk::init();
+  let constant = chunk.add_constant(1.5.into());
+  chunk.write_chunk(OpCode::Constant.into(), 1);
+  chunk.write_chunk(constant as u8, 1);
+  chunk.write_chunk(u8::MAX, 2);
+  let mut vm = VM::init();
+  fault(&mut vm, chunk);
+
+  let report = vm.crash_report().expect("a crash report");
+  assert_eq!(report.message, "Unknown opcode `255`.");
+  assert_eq!(report.function, "script");
+  assert_eq!(report.ip, 3);
+  assert_eq!(report.stack_trace, ["[line 2] in script"]);
+  assert_eq!(report.stack, ["<script>", "1.5"]);
+  assert!(
+    report.disassembly.contains("Constant"),
+    "{}",
+    report.disassembly
+  );
+
+  let rendered = report.render(Some("print 1.5;\n"));
+  for section in [
+    "fault: Unknown opcode `255`.",
+    "## Stack\n\n[0] <script>\n[1] 1.5\n",
+    "## Source\n\nprint 1.5;\n",
+  ] {
+    assert!(rendered.contains(section), "{}", rendered);
+  }
+}
+
+#[test]
+fn bundles_are_written_to_temp_files() {
+  let mut chunk = Chunk::init();
+  chunk.write_chunk(OpCode::Pop.into(), 1);
+  let mut vm = VM::init();
+  fault(&mut vm, chunk);
+
+  let path = vm.crash_report().unwrap().write_bundle(None).unwrap();
+  let bundle = std::fs::read_to_string(&path).unwrap();
+  std::fs::remove_file(&path).unwrap();
+  assert!(bundle.starts_with("# rlox crash report\n"), "{}", bundle);
+  assert!(bundle.contains("## Source\n\n<unknown>\n"), "{}", bundle);
+}
+
+#[test]
+fn script_errors_are_not_crashes() {
+  let mut vm = VM::init();
+  assert!(vm.interpret("nil + 1;".to_owned()).is_err());
+  assert!(vm.crash_report().is_none());
+
+  let src = "fun f() { f(); }\nf();";
+  assert!(matches!(
+    vm.interpret(src.to_owned()),
+    Err(InterpretError::Fault(_))
+  ));
+  assert!(vm.crash_report().is_none());
+
+  // stack overflow of callbacks as well
+  let src = "fun g(x) { return g(x); }\nmap([1], g);";
+  match vm.interpret(src.to_owned()) {
+    Err(InterpretError::Fault(message)) => {
+      assert!(message.contains("Stack overflow."), "{}", message)
+    }
+    other => panic!("expect a fault, but got: {:?}", other),
+  }
+  assert!(vm.crash_report().is_none());
+}
+
+#[test]
+fn reports_are_cleared_by_next_run() {
+  let mut chunk = Chunk::init();
+  chunk.write_chunk(u8::MAX, 1);
+  let mut vm = VM::init();
+  fault(&mut vm, chunk);
+  assert!(vm.crash_report().is_some());
+  vm.interpret("1;".to_owned()).unwrap();
+  assert!(vm.crash_report().is_none());
+}
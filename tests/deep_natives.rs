@@ -0,0 +1,85 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn instances_are_compared_by_structure() {
+  let src = r#"
+    class Point { init(x, y) { this.x = x; this.y = y; } }
+    class Other { init(x, y) { this.x = x; this.y = y; } }
+    var a = Point(1, "one");
+    var b = Point(1, "one");
+    assert(a != b);
+    assert(deep_equals(a, b));
+    assert(!deep_equals(a, Point(1, "two")));
+    assert(!deep_equals(a, Other(1, "one")));
+
+    b.z = nil;
+    assert(!deep_equals(a, b));
+
+    a.inner = Point(2, 3);
+    var c = Point(1, "one");
+    c.inner = Point(2, 3);
+    assert(deep_equals(a, c));
+
+    assert(deep_equals(1, 1) and deep_equals("s", "s") and !deep_equals(1, "1"));
+    assert(deep_equals(clock, clock));
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn cycles_are_compared() {
+  let src = r#"
+    class Node {}
+    var a = Node(); a.next = a;
+    var b = Node(); b.next = b;
+    assert(deep_equals(a, b));
+
+    var c = Node(); var d = Node();
+    c.next = d; d.next = c;
+    assert(deep_equals(a, c));
+
+    var e = Node(); e.next = Node(); e.next.next = nil;
+    assert(!deep_equals(a, e));
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn clones_are_deep() {
+  let src = r#"
+    class Box { get() { return this.value; } }
+    var shared = Box(); shared.value = "shared";
+    var a = Box();
+    a.value = 1;
+    a.left = shared;
+    a.right = shared;
+
+    var b = clone(a);
+    assert(b != a);
+    assert(b is Box);
+    assert(b.get() == 1);
+    assert(deep_equals(a, b));
+    assert(b.left != shared);
+    assert(b.left == b.right);
+
+    b.left.value = "changed";
+    assert(shared.value == "shared");
+    assert(clone(42) == 42 and clone("s") == "s" and clone(nil) == nil);
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn cycles_are_cloned() {
+  let src = r#"
+    class Node {}
+    var a = Node(); a.next = Node(); a.next.next = a;
+    var b = clone(a);
+    assert(b.next.next == b);
+    assert(b.next != a.next);
+    assert(deep_equals(a, b));
+  "#;
+  run(src).unwrap();
+}
@@ -0,0 +1,90 @@
+mod common;
+
+use common::{run, PRELUDE};
+use rlox::{
+  native::{deterministic::EPOCH, Capabilities},
+  utils::Init,
+  value::Value,
+  vm::VM,
+};
+
+/// Interpret the given source (with `PRELUDE`) in deterministic mode, then return its result.
+fn result_of(seed: u64, src: &str) -> Option<Value> {
+  let mut vm = VM::init();
+  vm.set_deterministic(Some(seed));
+  vm.interpret(PRELUDE.to_owned() + src).unwrap()
+}
+
+#[test]
+fn random_is_in_unit_interval() {
+  let src = r#"
+    fun check(n) {
+      if (n == 0) return;
+      var r = random();
+      assert(r >= 0 and r < 1);
+      check(n - 1);
+    }
+    check(50);
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn runs_with_the_same_seed_are_identical() {
+  let src = "random() + random() * 10 + clock() * 100;";
+  let first = result_of(42, src);
+  assert_eq!(result_of(42, src), first);
+  assert_ne!(result_of(7, src), first);
+}
+
+#[test]
+fn virtual_clock_advances_per_read() {
+  let src = format!(
+    r#"
+    assert(now_unix() == {epoch});
+    var a = clock();
+    var b = clock();
+    assert(b - a > 0.0009 and b - a < 0.0011);
+    assert(format_time(now_unix(), "%Y-%m-%d") == "2000-01-01");
+    "#,
+    epoch = EPOCH
+  );
+  result_of(0, &src);
+}
+
+#[test]
+fn sleep_advances_the_virtual_clock() {
+  let mut vm = VM::init();
+  vm.set_deterministic(Some(0));
+  vm.set_capabilities(Capabilities::all());
+  let started = std::time::Instant::now();
+  let src =
+    PRELUDE.to_owned() + "var a = now_unix(); sleep(3600000); assert(now_unix() - a == 3600);";
+  vm.interpret(src).unwrap();
+  assert!(started.elapsed().as_secs() < 60);
+
+  // the virtual clock saturates instead of overflowing
+  let huge = "10000000000000000000"; // 1e19
+  vm.interpret(format!("sleep({0}); sleep({0}); clock();", huge))
+    .unwrap();
+  let message = vm
+    .interpret(format!("sleep(1{});", "0".repeat(300)))
+    .unwrap_err()
+    .into_message();
+  assert!(
+    message.contains("`sleep` got too many milliseconds."),
+    "{}",
+    message
+  );
+}
+
+#[test]
+fn mode_could_be_turned_off() {
+  let mut vm = VM::init();
+  vm.set_deterministic(Some(1));
+  assert_eq!(vm.deterministic(), Some(1));
+  vm.set_deterministic(None);
+  assert_eq!(vm.deterministic(), None);
+  let src = PRELUDE.to_owned() + "assert(now_unix() > 1600000000);";
+  vm.interpret(src).unwrap();
+}
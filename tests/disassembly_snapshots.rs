@@ -0,0 +1,101 @@
+//! Snapshots of the disassembly of representative programs, so changes of codegen are
+//! reviewed as diffs of `tests/snapshots/`.
+//!
+//! Review changed snapshots by `cargo insta review` (or accept all of them by
+//! `INSTA_UPDATE=always cargo test --test disassembly_snapshots`).
+
+use rlox::{
+  compiler::{CompileOptions, OptLevel},
+  utils::Init,
+  vm::VM,
+  Source, Vm,
+};
+
+fn disassemble(src: &str) -> String {
+  let mut vm = Vm::new();
+  let script = vm.compile(&Source::new(src)).unwrap();
+  vm.debugger().disassemble(&script)
+}
+
+#[test]
+fn expressions_and_globals() {
+  let src = r#"
+    var a = 1 + 2 * 3;
+    var b = -a / 4 - 5;
+    print a >= b and !(a == b) or a < nil;
+    b = "str" in "string";
+  "#;
+  insta::assert_snapshot!(disassemble(src));
+}
+
+#[test]
+fn control_flow_and_locals() {
+  let src = r#"
+    {
+      var x = 1;
+      if (x > 0) {
+        var y = x;
+        print y;
+      } else {
+        print -x;
+      }
+    }
+  "#;
+  insta::assert_snapshot!(disassemble(src));
+}
+
+#[test]
+fn functions_and_calls() {
+  let src = r#"
+    fun add(a, b = 1) {
+      return a + b;
+    }
+    print add(1, 2) + add(3);
+  "#;
+  insta::assert_snapshot!(disassemble(src));
+}
+
+#[test]
+fn classes_and_traits() {
+  let src = r#"
+    trait Named {
+      describe() { return "named " + this.name; }
+    }
+    class Point with Named {
+      init(x) { this.x = x; this.name = "point"; }
+      get double { return this.x * 2; }
+      set double(value) { this.x = value / 2; }
+    }
+    var p = Point(1);
+    p.double = 4;
+    print p.describe();
+    delete p.name;
+  "#;
+  insta::assert_snapshot!(disassemble(src));
+}
+
+#[test]
+fn exceptions() {
+  let src = r#"
+    try {
+      throw Error("boom");
+    } catch (e) {
+      print e.message;
+    }
+  "#;
+  insta::assert_snapshot!(disassemble(src));
+}
+
+#[test]
+fn optimized_at_o2() {
+  let src = r#"
+    var a = 1 + 2 * 3;
+    { var x = a; var y = x; print x + y; }
+  "#;
+  let mut vm = VM::init();
+  vm.set_compile_options(CompileOptions {
+    opt_level: OptLevel::O2,
+    ..CompileOptions::init()
+  });
+  insta::assert_snapshot!(vm.disassemble_source(src.to_owned()).unwrap());
+}
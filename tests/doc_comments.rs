@@ -0,0 +1,92 @@
+use rlox::doc::{extract, render_markdown, DocKind};
+
+const SRC: &str = r#"
+/// Add two numbers.
+///
+/// `b` is optional.
+fun add(a, b = 0) { return a + b; }
+
+/// A point on the plane.
+class Point with Printable {
+  /// Methods are not globals.
+  init(x, y) { this.x = x; this.y = y; }
+}
+
+/// The answer.
+var answer = add(40, 2);
+
+// An ordinary comment.
+var undocumented = nil;
+
+/// Detached by a blank line.
+
+fun detached() {}
+
+/// Detached by an ordinary comment.
+// not a doc comment
+fun commented() {}
+
+//// Not a doc comment either.
+fun quadruple() {}
+
+{
+  /// Locals are not globals.
+  var local = 1;
+}
+"#;
+
+#[test]
+fn documented_globals_are_extracted() {
+  let items = extract(SRC).unwrap();
+  let summary: Vec<_> = items
+    .iter()
+    .map(|item| (item.kind, item.name.as_str(), item.signature.as_str()))
+    .collect();
+  assert_eq!(
+    summary,
+    [
+      (DocKind::Function, "add", "fun add(a, b = 0)"),
+      (DocKind::Class, "Point", "class Point with Printable"),
+      (DocKind::Variable, "answer", "var answer"),
+    ]
+  );
+  assert_eq!(items[0].doc, ["Add two numbers.", "", "`b` is optional."]);
+  assert_eq!(items[0].line, 5);
+  assert_eq!(items[2].doc, ["The answer."]);
+}
+
+#[test]
+fn summary_is_rendered_as_markdown() {
+  let items = extract(SRC).unwrap();
+  let markdown = render_markdown("lib.lox", &items);
+  assert!(markdown.starts_with("# lib.lox\n"), "{}", markdown);
+  assert!(
+    markdown.contains("\n## `fun add(a, b = 0)`\n\nAdd two numbers.\n\n`b` is optional.\n"),
+    "{}",
+    markdown
+  );
+  assert!(
+    markdown.contains("\n## `var answer`\n\nThe answer.\n"),
+    "{}",
+    markdown
+  );
+  assert_eq!(
+    render_markdown("empty.lox", &[]),
+    "# empty.lox\n\nNo documented globals.\n"
+  );
+}
+
+#[test]
+fn doc_comments_are_ignored_by_the_compiler() {
+  use rlox::{utils::Init, value::Value, vm::VM};
+  let src = "/// Doc.\nfun f() { return 1; }\n/// Doc.\nvar a = f();\na;";
+  assert_eq!(
+    VM::init().interpret(src.into()).unwrap(),
+    Some(Value::from(1.0))
+  );
+}
+
+#[test]
+fn scan_errors_are_reported() {
+  assert!(extract("/// Doc.\nvar a = \"unterminated;").is_err());
+}
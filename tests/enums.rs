@@ -0,0 +1,120 @@
+mod common;
+
+use common::{compile_error, run, runtime_error};
+use rlox::{DiagnosticKind, Source, Vm};
+
+/// Messages of warnings of compiling the source.
+fn warnings_of(src: &str) -> Vec<String> {
+  let mut vm = Vm::new();
+  let script = vm.compile(&Source::new(src)).unwrap();
+  script
+    .warnings()
+    .iter()
+    .map(|warning| {
+      assert_eq!(warning.kind(), DiagnosticKind::Warning);
+      warning.message().to_owned()
+    })
+    .collect()
+}
+
+#[test]
+fn members_are_distinct_constants() {
+  let src = r#"
+    enum Color { Red, Green, Blue }
+    assert(Color.Red == Color.Red);
+    assert(Color.Red != Color.Green);
+    var favorite = Color.Blue;
+    assert(favorite == Color.Blue);
+  "#;
+  assert!(run(src).is_ok());
+}
+
+#[test]
+fn members_have_names_and_ordinals() {
+  let src = r#"
+    enum Color { Red, Green, Blue, }
+    assert(Color.Red.ordinal == 0);
+    assert(Color.Blue.ordinal == 2);
+    assert(Color.Green.name == "Green");
+    assert(str(Color.Green) == "Color.Green");
+    assert(str(Color) == "<enum Color>");
+  "#;
+  assert!(run(src).is_ok());
+}
+
+#[test]
+fn enums_could_be_local() {
+  let src = r#"
+    fun light() {
+      enum Light { Off, On }
+      return Light.On;
+    }
+    assert(light().name == "On");
+    assert(light() != light());
+  "#;
+  assert!(run(src).is_ok());
+}
+
+#[test]
+fn unknown_members_are_runtime_errors() {
+  assert!(runtime_error("enum Color { Red } Color.Purple;")
+    .ends_with("Enum `Color` has no member `Purple`."));
+  assert!(runtime_error("enum Color { Red } Color.Red.value;")
+    .ends_with("Enum members only have `name` and `ordinal`, not `value`."));
+  assert!(
+    runtime_error("enum Color { Red } Color.Red = 1;").ends_with("Only instances have fields.")
+  );
+}
+
+#[test]
+fn malformed_enums_are_compile_errors() {
+  assert!(compile_error("enum { A }").contains("Expect enum name."));
+  assert!(compile_error("enum C { A, A }").contains("Duplicate member `A` of enum `C`."));
+  assert!(compile_error("enum C { A B }").contains("Expect `}` after enum body."));
+}
+
+#[test]
+fn match_arms_match_members() {
+  let src = r#"
+    enum Color { Red, Green, Blue }
+    fun describe(color) {
+      match color {
+        Color.Red => return "red";
+        Color.Green => return "green";
+        _ => return "other";
+      }
+    }
+    assert(describe(Color.Red) == "red");
+    assert(describe(Color.Green) == "green");
+    assert(describe(Color.Blue) == "other");
+    assert(describe(0) == "other");
+  "#;
+  assert!(run(src).is_ok());
+}
+
+#[test]
+fn match_warns_of_uncovered_members() {
+  let src = r#"
+    enum Color { Red, Green, Blue }
+    match Color.Red {
+      Color.Red => print 1;
+    }
+  "#;
+  assert_eq!(
+    warnings_of(src),
+    vec!["[line 3] Warning at 'match': `match` doesn't cover member(s) `Green`, `Blue` of enum `Color`."]
+  );
+}
+
+#[test]
+fn exhaustive_matches_are_not_warned() {
+  for arms in [
+    "Color.Red => print 1; Color.Green => print 2;",
+    "Color.Red => print 1; _ => print 2;",
+    "Color.Red => print 1; other => print other;",
+    "Color.Red => print 1; 0 => print 2;",
+  ] {
+    let src = format!("enum Color {{ Red, Green }} match Color.Red {{ {} }}", arms);
+    assert_eq!(warnings_of(&src), Vec::<String>::new(), "{}", arms);
+  }
+}
@@ -0,0 +1,69 @@
+use rlox::{
+  utils::Init,
+  value::Value,
+  vm::{InterpretError, VM},
+};
+
+fn is_runtime_error(result: Result<Option<Value>, InterpretError>) -> bool {
+  matches!(result, Err(InterpretError::RuntimeError(_)))
+}
+
+#[test]
+fn vm_is_idle_after_runtime_error() {
+  let mut vm = VM::init();
+  let src = r#"
+    fun inner(x) { var y = x; return y + nil; }
+    fun outer() { var z = 1; return inner(z); }
+    outer();
+  "#;
+  assert!(is_runtime_error(vm.interpret(src.into())));
+  assert!(vm.is_idle());
+}
+
+#[test]
+fn next_line_runs_after_runtime_error() {
+  let mut vm = VM::init();
+  vm.interpret("fun add(a, b) { var c = a + b; return c; }".into())
+    .unwrap();
+  assert!(is_runtime_error(vm.interpret("add(1, nil);".into())));
+  assert_eq!(
+    vm.interpret("add(1, 2);".into()).unwrap(),
+    Some(Value::from(3.0))
+  );
+}
+
+#[test]
+fn next_line_runs_after_stack_overflow() {
+  let mut vm = VM::init();
+  vm.interpret("fun f() { return f(); }".into()).unwrap();
+  assert!(matches!(
+    vm.interpret("f();".into()),
+    Err(InterpretError::Fault(_))
+  ));
+  assert!(vm.is_idle());
+  assert_eq!(
+    vm.interpret("{ var a = 1; var b = 2; a + b; } 3;".into())
+      .unwrap(),
+    Some(Value::from(3.0))
+  );
+}
+
+#[test]
+fn globals_defined_before_error_are_kept() {
+  let mut vm = VM::init();
+  assert!(is_runtime_error(
+    vm.interpret("var a = 1; a + nil; var b = 2;".into())
+  ));
+  assert_eq!(vm.interpret("a;".into()).unwrap(), Some(Value::from(1.0)));
+  assert!(is_runtime_error(vm.interpret("b;".into())));
+}
+
+#[test]
+fn reset_execution_state_is_idempotent() {
+  let mut vm = VM::init();
+  vm.reset_execution_state();
+  vm.reset_execution_state();
+  assert!(vm.is_idle());
+  assert_eq!(vm.interpret("1;".into()).unwrap(), Some(Value::from(1.0)));
+  assert!(vm.is_idle());
+}
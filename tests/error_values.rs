@@ -0,0 +1,181 @@
+mod common;
+
+use common::{compile_error, run, runtime_error};
+use rlox::{
+  utils::Init,
+  value::Value,
+  vm::{InterpretError, VM},
+};
+
+#[test]
+fn thrown_values_are_caught() {
+  let src = r#"
+    var caught;
+    try {
+      throw "oops";
+      caught = "unreachable";
+    } catch (e) {
+      caught = e;
+    }
+    assert(caught == "oops");
+
+    var after = "nothing thrown";
+    try { after = "try"; } catch (e) { after = "catch"; }
+    assert(after == "try");
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn errors_carry_message_cause_and_stack() {
+  let src = r#"
+    fun fail() {
+      var e = error("disk is full");
+      e.cause = error("quota exceeded");
+      throw e;
+    }
+    var caught;
+    try { fail(); } catch (e) { caught = e; }
+    assert(caught is Error);
+    assert(caught.kind == "Error");
+    assert(caught.message == "disk is full");
+    assert(caught.cause.message == "quota exceeded");
+    assert(caught.cause.stack == nil);
+    assert(caught.stack == "[line 6] in fail\n[line 9] in script");
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn runtime_errors_are_thrown_as_errors() {
+  let src = r#"
+    class Point {}
+    fun kind_of(f) {
+      try { f(); } catch (e) { return e.kind; }
+      return nil;
+    }
+    fun add() { return 1 + "one"; }
+    fun name() { return missing; }
+    fun property() { return Point().x; }
+    fun arity() { return add(1); }
+    fun native() { return error(1); }
+    assert(kind_of(add) == "TypeError");
+    assert(kind_of(name) == "NameError");
+    assert(kind_of(property) == "PropertyError");
+    assert(kind_of(arity) == "ArityError");
+    assert(kind_of(native) == "RuntimeError");
+    assert(kind_of(clock) == nil);
+
+    var caught;
+    try { add(); } catch (e) { caught = e; }
+    assert(caught.message == "Operands must be 2 numbers or 2 strings.");
+    assert(caught.stack == "[line 8] in add\n[line 21] in script");
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn stack_is_unwound_to_the_innermost_handler() {
+  let src = r#"
+    var log = "";
+    fun inner() {
+      try { throw "first"; } catch (e) { log = log + e + ";"; }
+      var a = 1;
+      throw "second";
+    }
+    fun outer() {
+      var b = 2;
+      try { inner(); } catch (e) { log = log + e + ";" + str(b) + ";"; }
+      return b;
+    }
+    assert(outer() == 2);
+    assert(log == "first;second;2;");
+
+    try {
+      try { throw "again"; } catch (e) { throw e + "!"; }
+    } catch (e) {
+      log = e;
+    }
+    assert(log == "again!");
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn handlers_of_returned_functions_are_dropped() {
+  let src = r#"
+    fun guarded() {
+      try { return 1; } catch (e) { return 2; }
+    }
+    guarded();
+    throw error("after return");
+  "#;
+  let message = runtime_error(src);
+  assert!(
+    message.starts_with("[line 7] in script: Uncaught Error: after return"),
+    "{}",
+    message
+  );
+}
+
+#[test]
+fn uncaught_values_are_runtime_errors() {
+  assert!(runtime_error("throw 42;").ends_with("Uncaught 42"));
+  assert!(runtime_error(r#"throw "bad";"#).ends_with(r#"Uncaught "bad""#));
+  // runtime errors outside `try` blocks are reported as before
+  assert!(runtime_error("1 + nil;").ends_with("Operands must be 2 numbers or 2 strings."));
+}
+
+#[test]
+fn try_requires_a_catch_block() {
+  let message = compile_error("try { 1; }");
+  assert!(
+    message.contains("Expect `catch` after try block."),
+    "{}",
+    message
+  );
+  let message = compile_error("try { 1; } catch { 2; }");
+  assert!(message.contains("Expect `(` after `catch`."), "{}", message);
+}
+
+#[test]
+fn kinds_are_kept_across_callbacks() {
+  let src = r#"
+    fun kind_of(f) {
+      try { map([1], f); } catch (e) { return e.kind; }
+      return nil;
+    }
+    fun add(x) { return x + "one"; }
+    fun name(x) { return missing; }
+    fun unknown_native(x) { return str(x, 2); }
+    assert(kind_of(add) == "TypeError");
+    assert(kind_of(name) == "NameError");
+    assert(kind_of(unknown_native) == "ArityError");
+    // kinds never depend on messages
+    fun message_alike(x) { throw error("Operands must be numbers."); }
+    assert(kind_of(message_alike) == "Error");
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn faults_are_never_caught() {
+  let mut vm = VM::init();
+  let src = r#"
+    fun f() { return f(); }
+    var caught = false;
+    try { f(); } catch (e) { caught = true; }
+  "#;
+  match vm.interpret(src.into()) {
+    Err(error @ InterpretError::Fault(_)) => {
+      assert!(!error.is_recoverable());
+      assert!(error.into_message().contains("Stack overflow."));
+    }
+    other => panic!("expect a fault, but got: {:?}", other),
+  }
+  assert!(vm.is_idle());
+  assert_eq!(
+    vm.interpret("caught;".into()).unwrap(),
+    Some(Value::from(false))
+  );
+}
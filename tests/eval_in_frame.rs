@@ -0,0 +1,138 @@
+use rlox::{DiagnosticKind, RunOutcome, Source, Value, Vm};
+
+const SRC: &str = "var limit = 10;
+class Counter {
+  init(start) { this.count = start; }
+  add(step) {
+    var next = this.count + step;
+    this.count = next;
+    return next;
+  }
+}
+fun run(times) {
+  var counter = Counter(times);
+  return counter.add(2);
+}
+run(3);";
+
+/// Compile the source, then run it until the breakpoint of the line.
+fn paused_at(line: usize) -> Vm {
+  let mut vm = Vm::new();
+  let script = vm.compile(&Source::new(SRC)).unwrap();
+  vm.start(&script);
+  vm.set_breakpoint(line);
+  assert_eq!(vm.resume().unwrap(), RunOutcome::Paused { line });
+  vm
+}
+
+#[test]
+fn locals_of_the_innermost_call_are_mapped() {
+  let mut vm = paused_at(6);
+  assert_eq!(
+    vm.debugger().eval_in_frame(0, "next * step").unwrap(),
+    Value::Number(10.0)
+  );
+  assert_eq!(
+    vm.debugger().eval_in_frame(0, "next + limit;").unwrap(),
+    Value::Number(15.0)
+  );
+}
+
+#[test]
+fn this_refers_to_the_receiver_of_methods() {
+  let mut vm = paused_at(6);
+  assert_eq!(
+    vm.debugger().eval_in_frame(0, "this.count").unwrap(),
+    Value::Number(3.0)
+  );
+}
+
+#[test]
+fn outer_frames_are_evaluated_with_their_own_locals() {
+  let mut vm = paused_at(6);
+  assert_eq!(
+    vm.debugger().eval_in_frame(1, "times + 1").unwrap(),
+    Value::Number(4.0)
+  );
+  let error = vm.debugger().eval_in_frame(1, "next").unwrap_err();
+  assert_eq!(error.kind(), DiagnosticKind::RuntimeError);
+  assert!(error.message().contains("Undefined variable `next`."));
+  let error = vm.debugger().eval_in_frame(1, "this").unwrap_err();
+  assert_eq!(error.kind(), DiagnosticKind::CompileError);
+}
+
+#[test]
+fn assignments_are_discarded() {
+  let mut vm = paused_at(6);
+  vm.debugger().eval_in_frame(0, "next = 100").unwrap();
+  vm.debugger().eval_in_frame(0, "limit = 0").unwrap();
+  assert_eq!(
+    vm.debugger().eval_in_frame(0, "next").unwrap(),
+    Value::Number(5.0)
+  );
+  assert_eq!(
+    vm.resume().unwrap(),
+    RunOutcome::Finished(Some(Value::Number(5.0))),
+    "the paused run is intact"
+  );
+  assert_eq!(vm.global("limit"), Some(Value::Number(10.0)));
+}
+
+#[test]
+fn statements_and_missing_frames_are_errors() {
+  let mut vm = paused_at(6);
+  let error = vm.debugger().eval_in_frame(0, "print next;").unwrap_err();
+  assert_eq!(error.kind(), DiagnosticKind::CompileError);
+  assert_eq!(error.message(), "`print next` isn't an expression.");
+  let error = vm.debugger().eval_in_frame(3, "limit").unwrap_err();
+  assert_eq!(error.message(), "No ongoing call at depth 3.");
+}
+
+#[test]
+fn captured_variables_are_visible() {
+  let src = "fun outer() {
+  var secret = 40;
+  fun inner(x) {
+    var sum = secret + x;
+    return sum;
+  }
+  return inner(2);
+}
+outer();";
+  let mut vm = Vm::new();
+  let script = vm.compile(&Source::new(src)).unwrap();
+  vm.start(&script);
+  vm.set_breakpoint(5);
+  assert_eq!(vm.resume().unwrap(), RunOutcome::Paused { line: 5 });
+  assert_eq!(
+    vm.debugger().captures(0),
+    [("secret".to_owned(), Value::Number(40.0))]
+  );
+  assert_eq!(
+    vm.debugger().eval_in_frame(0, "secret + sum").unwrap(),
+    Value::Number(82.0)
+  );
+  // captured variables are snapshots, so assigning them is discarded
+  vm.debugger().eval_in_frame(0, "secret = 0").unwrap();
+  assert_eq!(
+    vm.debugger().eval_in_frame(1, "secret").unwrap(),
+    Value::Number(40.0)
+  );
+}
+
+#[test]
+fn changes_of_objects_are_kept() {
+  let mut vm = paused_at(6);
+  vm.debugger().eval_in_frame(0, "this.count = 100").unwrap();
+  assert_eq!(
+    vm.resume().unwrap(),
+    RunOutcome::Finished(Some(Value::Number(5.0)))
+  );
+  // `next` was computed before the change, which is kept by the instance
+  let mut vm = paused_at(5);
+  vm.debugger().eval_in_frame(0, "this.count = 100").unwrap();
+  assert_eq!(
+    vm.resume().unwrap(),
+    RunOutcome::Finished(Some(Value::Number(102.0)))
+  );
+}
@@ -0,0 +1,69 @@
+mod common;
+
+use common::{runtime_error, PRELUDE};
+use rlox::{
+  native::Capabilities,
+  utils::Init,
+  vm::{InterpretError, VM},
+};
+
+/// Interpret the given source (with `PRELUDE`), with `exec` granted.
+fn run_with_exec(src: &str) -> Result<(), InterpretError> {
+  let mut vm = VM::init();
+  vm.set_capabilities(Capabilities {
+    exec: true,
+    ..Capabilities::none()
+  });
+  vm.interpret(PRELUDE.to_owned() + src).map(|_| ())
+}
+
+#[test]
+fn exec_is_denied_by_default() {
+  let message = runtime_error(r#"exec("echo", ["hi"]);"#);
+  assert!(
+    message.contains("Native function `exec` needs the `exec` capability."),
+    "{}",
+    message
+  );
+}
+
+#[cfg(unix)]
+#[test]
+fn exec_captures_status_and_output() {
+  let src = r#"
+    var p = exec("echo", ["hello   rlox", "it's \"quoted\""]);
+    assert(p[0] == 0);
+    assert(p[1] == "hello   rlox it's \"quoted\"\n");
+    assert(p[2] == "");
+
+    assert(exec("sh", ["-c", "echo oops >&2; exit 3"])[0] == 3);
+    assert(exec("sh", ["-c", "echo oops >&2; exit 3"])[2] == "oops\n");
+    assert(exec("true", nil)[0] == 0);
+    assert(exec("true", [])[0] == 0);
+  "#;
+  run_with_exec(src).unwrap();
+}
+
+#[test]
+fn exec_of_missing_program() {
+  let message = match run_with_exec(r#"exec("rlox-no-such-program", nil);"#) {
+    Err(InterpretError::RuntimeError(message)) => message,
+    other => panic!("expect a runtime error, but got: {:?}", other),
+  };
+  assert!(
+    message.contains("`exec` failed to run `rlox-no-such-program`"),
+    "{}",
+    message
+  );
+}
+
+#[test]
+fn exec_arguments_are_lists_of_strings() {
+  for args in [r#""a b""#, "[1]"] {
+    let message = match run_with_exec(&format!("exec(\"true\", {});", args)) {
+      Err(InterpretError::RuntimeError(message)) => message,
+      other => panic!("expect a runtime error, but got: {:?}", other),
+    };
+    assert!(message.contains("`exec` expected a list"), "{}", message);
+  }
+}
@@ -0,0 +1,70 @@
+mod common;
+
+use common::{compile_error, run, runtime_error};
+
+#[test]
+fn check_and_delete_fields() {
+  let src = r#"
+    class Record {}
+    var r = Record();
+    r.name = "rlox";
+    assert("name" in r);
+    assert(!("age" in r));
+    delete r.name;
+    assert(!("name" in r));
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn methods_are_not_fields() {
+  run("class C { m() {} } assert(!(\"m\" in C()));").unwrap();
+}
+
+#[test]
+fn delete_undefined_field() {
+  let src = "class C {} delete C().x;";
+  assert!(runtime_error(src).contains("Undefined field `x`."));
+}
+
+#[test]
+fn delete_last_property_of_chains() {
+  let src = r#"
+    class Record {}
+    var r = Record();
+    r.inner = Record();
+    r.inner.name = "rlox";
+    r.i = 0;
+    var xs = [r.inner];
+    delete xs[r.i].name;
+    assert(!("name" in r.inner));
+    assert("i" in r);
+    delete r.inner;
+    assert(!("inner" in r));
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn delete_non_property() {
+  for src in [
+    "var a = 1; delete a;",
+    "var a = [1]; delete a[0];",
+    "class C { m() {} } delete C().m();",
+    "class C {} var c = C(); c.x = 1; delete (c.x);",
+  ] {
+    assert!(
+      compile_error(src).contains("Can only delete a property of an instance."),
+      "{}",
+      src
+    );
+  }
+  assert!(compile_error("class C {} var c = C(); delete c.x + 1;")
+    .contains("Expect `;` after delete target."));
+}
+
+#[test]
+fn in_requires_container() {
+  assert!(runtime_error("\"a\" in 1;")
+    .contains("Right operand of `in` must be a string, a list or an instance."));
+}
@@ -0,0 +1,46 @@
+mod common;
+
+use common::{compile_error, run, runtime_error};
+
+#[test]
+fn quotients_are_rounded_down() {
+  let src = r#"
+    assert(7 div 2 == 3);
+    assert(-7 div 2 == -4);
+    assert(7 div -2 == -4);
+    assert(6 div 3 == 2);
+    assert(7.5 div 2 == 3);
+    assert(7 / 2 == 3.5);
+    assert(1 div 0 == 1 / 0);
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn div_binds_as_tightly_as_multiplication() {
+  let src = r#"
+    assert(1 + 7 div 2 == 4);
+    assert(20 div 3 * 3 == 18);
+    assert(2 * 7 div 4 == 3);
+    var n = 17;
+    var tens = n div 10;
+    assert(tens == 1);
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn comments_are_still_comments() {
+  let src = r#"
+    var n = 7 // 2;
+    ;
+    assert(n == 7);
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn operands_must_be_numbers() {
+  assert!(runtime_error("\"7\" div 2;").contains("Operands must be numbers."));
+  assert!(compile_error("var div = 1;").contains("Expect variable name."));
+}
@@ -0,0 +1,82 @@
+mod common;
+
+use common::{compile_error, run, runtime_error};
+
+#[test]
+fn all_clauses_run_in_order() {
+  let src = r#"
+    var sum = 0;
+    for (var i = 0; i < 5; i = i + 1) sum = sum + i;
+    assert(sum == 10);
+    for (var i = 0; false; i = i + 1) assertion_failed;
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn clauses_are_optional() {
+  let src = r#"
+    var i = 0;
+    for (; i < 3;) i = i + 1;
+    assert(i == 3);
+    for (i = 10; i > 5; i = i - 1) {}
+    assert(i == 5);
+    fun first_square_over(limit) {
+      var n = 0;
+      for (;;) {
+        if (n * n > limit) return n;
+        n = n + 1;
+      }
+    }
+    assert(first_square_over(30) == 6);
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn initializer_is_scoped_to_the_loop() {
+  let src = r#"
+    var i = "global";
+    for (var i = 0; i < 2; i = i + 1) {}
+    assert(i == "global");
+    {
+      var i = "local";
+      for (var i = 0; i < 2; i = i + 1) { var j = i * 2; }
+      assert(i == "local");
+    }
+  "#;
+  run(src).unwrap();
+  assert!(
+    runtime_error("for (var k = 0; k < 1; k = k + 1) {} print k;")
+      .contains("Undefined variable `k`.")
+  );
+}
+
+#[test]
+fn loops_nest_inside_functions() {
+  let src = r#"
+    fun count(n) {
+      var cells = 0;
+      for (var row = 0; row < n; row = row + 1)
+        for (var col = 0; col < row; col = col + 1)
+          cells = cells + 1;
+      return cells;
+    }
+    assert(count(5) == 10);
+    var last;
+    for (var i = 0; i < 3; i = i + 1) {
+      fun get() { return i; }
+      last = get;
+    }
+    assert(last() == 3);
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn syntax_errors() {
+  assert!(compile_error("for var i = 0; i < 1;) {}").contains("Expect `(` after `for`."));
+  assert!(compile_error("for (var i = 0; i < 1) {}").contains("Expect `;` after loop condition."));
+  assert!(compile_error("for (;; i = i + 1 {}").contains("Expect `)` after for clauses."));
+  assert!(compile_error("for (print 1;;) {}").contains("Expect expression."));
+}
@@ -0,0 +1,89 @@
+mod common;
+
+use common::{compile_error, run};
+
+#[test]
+fn nested_blocks_read_innermost_slot() {
+  let src = r#"
+    {
+      var a = 1;
+      {
+        var a = 2;
+        assert(a == 2);
+        a = 3;
+        assert(a == 3);
+      }
+      assert(a == 1);
+    }
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn calls_address_locals_from_frame_base() {
+  let src = r#"
+    fun f(x) {
+      var y = x + 1;
+      {
+        var x = 10;
+        assert(x + y == 16);
+      }
+      return x;
+    }
+    {
+      var pad1 = "a";
+      var pad2 = "b";
+      var z = 5;
+      assert(f(z) == 5);
+      assert(pad1 == "a" and pad2 == "b" and z == 5);
+    }
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn recursion_keeps_frames_apart() {
+  let src = r#"
+    fun sum(n) {
+      var here = n;
+      if (n == 0) return 0;
+      var rest = sum(n - 1);
+      assert(here == n);
+      return here + rest;
+    }
+    assert(sum(10) == 55);
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn methods_address_receiver_in_slot_zero() {
+  let src = r#"
+    class C {
+      init(v) { var local = v * 2; this.v = local; }
+      get double { var v = this.v; return v; }
+    }
+    { var outer = 1; assert(C(outer).double == 2); }
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn shadowed_local_in_own_initializer() {
+  let src = "{ var a = 1; { var a = a; } }";
+  assert!(compile_error(src).contains("Can't read local variable in its own initializer."));
+}
+
+#[test]
+fn keyword_prefixed_identifiers_are_not_keywords() {
+  let src = r#"
+    {
+      var outer = 1;
+      var iffy = 2;
+      var truest = 3;
+      var vinegar = 4;
+      assert(outer + iffy + truest + vinegar == 10);
+    }
+  "#;
+  run(src).unwrap();
+}
@@ -0,0 +1,46 @@
+mod common;
+
+use common::{compile_error, run, runtime_error};
+
+#[test]
+fn default_parameters() {
+  let src = r#"
+    fun add(a, b = 2, c = a + 10) { return a + b + c; }
+    assert(add(1) == 14);
+    assert(add(1, 5) == 17);
+    assert(add(1, 5, 0) == 6);
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn explicit_nil_arguments_are_kept() {
+  let src = r#"
+    fun f(a, b = 2) { return b; }
+    assert(f(1, nil) == nil);
+    assert(f(...[1, nil]) == nil);
+    assert(f(...[1]) == 2);
+
+    class Point {
+      init(x = 0, y = 0) { this.x = x; this.y = y; }
+      moved(dx = 1) { return dx; }
+    }
+    var p = Point(nil);
+    assert(p.x == nil and p.y == 0);
+    assert(p.moved(nil) == nil);
+    assert(p.moved() == 1);
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn arity_error_reports_name_and_range() {
+  let message = runtime_error("fun add(a, b = 2) { return a + b; } add(1, 2, 3);");
+  assert!(message.contains("Function `add` expected 1 to 2 arguments but got 3."));
+}
+
+#[test]
+fn required_parameter_after_default() {
+  let message = compile_error("fun f(a = 1, b) {}");
+  assert!(message.contains("Parameter without default value can't follow one with default."));
+}
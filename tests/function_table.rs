@@ -0,0 +1,84 @@
+use rlox::{
+  cache::{cache_path, CacheOptions},
+  utils::Init,
+  value::Value,
+  vm::VM,
+  Source, Vm,
+};
+
+const SRC: &str = r#"
+  fun outer() {
+    fun inner() { return 1; }
+    return inner() + 1;
+  }
+  class Point {
+    init(x) { this.x = x; }
+    get double {
+      fun twice(n) { return n * 2; }
+      return twice(this.x);
+    }
+  }
+  outer() + Point(2).double;
+"#;
+
+#[test]
+fn functions_are_qualified_by_enclosing_functions_and_classes() {
+  let mut vm = Vm::new();
+  let script = vm.compile(&Source::new(SRC)).unwrap();
+  let names: Vec<_> = script.functions().collect();
+  assert_eq!(
+    names,
+    [
+      "outer.inner",
+      "outer",
+      "Point.init",
+      "Point.double.twice",
+      "Point.double"
+    ]
+  );
+
+  let debugger = vm.debugger();
+  let disassembly = debugger.disassemble_function(&script, "outer").unwrap();
+  assert!(disassembly.contains("Global Disassembler : outer <"));
+  // functions defined in it are disassembled apart
+  assert!(!disassembly.contains("Global Disassembler : outer.inner"));
+  assert!(debugger.disassemble_function(&script, "inner").is_none());
+}
+
+#[test]
+fn function_table_is_kept_by_the_cache() {
+  let path = std::env::temp_dir().join("rlox_function_table.lox");
+  std::fs::write(&path, SRC).unwrap();
+  let file = path.to_string_lossy().to_string();
+  let on_disk_vm = || {
+    let mut vm = VM::init();
+    vm.set_cache_options(CacheOptions {
+      on_disk: true,
+      ..CacheOptions::init()
+    });
+    vm
+  };
+
+  let mut vm = on_disk_vm();
+  assert_eq!(
+    vm.interpret_file(file.clone()).unwrap(),
+    Some(Value::from(6.0))
+  );
+  let names: Vec<String> = vm.functions().names().map(str::to_owned).collect();
+
+  // a fresh virtual machine loads the script (and its functions) from disk
+  let mut vm = on_disk_vm();
+  assert_eq!(
+    vm.interpret_file(file.clone()).unwrap(),
+    Some(Value::from(6.0))
+  );
+  assert_eq!(vm.compile_cache().hits(), 1);
+  assert!(vm.functions().names().eq(names.iter().map(String::as_str)));
+  assert_eq!(
+    vm.functions().get("Point.init").unwrap().to_string(),
+    "<fn init>"
+  );
+
+  std::fs::remove_file(cache_path(&file)).unwrap();
+  std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,101 @@
+use std::collections::{BTreeMap, HashMap};
+
+use rlox::{DiagnosticKind, Source, Value, Vm};
+
+/// Globals left by the script (run on a fresh virtual machine), as a map.
+fn exported(source: &str) -> BTreeMap<String, Value> {
+  let mut vm = Vm::new();
+  vm.eval(&Source::new(source)).unwrap();
+  vm.export_globals()
+}
+
+#[test]
+fn data_globals_are_exported() {
+  let globals = exported("var a = 1; var b = \"two\"; var c = true; var d;");
+  assert_eq!(
+    globals,
+    BTreeMap::from([
+      ("a".to_owned(), Value::Number(1.0)),
+      ("b".to_owned(), Value::String("two".to_owned())),
+      ("c".to_owned(), Value::Bool(true)),
+      ("d".to_owned(), Value::Nil),
+    ])
+  );
+}
+
+#[test]
+fn definitions_and_builtins_are_not_exported() {
+  let globals = exported("fun f() {} class C {} var i = C(); var n = 1;");
+  assert_eq!(
+    globals.keys().collect::<Vec<_>>(),
+    ["n"],
+    "functions, classes, instances, natives and `Error` are left out"
+  );
+}
+
+#[test]
+fn imported_globals_are_visible_to_scripts() {
+  let globals: HashMap<String, Value> = {
+    let mut vm = Vm::new();
+    vm.eval(&Source::new("var count = 2; var name = \"lox\";"))
+      .unwrap();
+    vm.export_globals()
+  };
+  let mut vm = Vm::new();
+  vm.import_globals(globals).unwrap();
+  assert_eq!(
+    vm.eval(&Source::new("count = count + 1; name + \"!\";"))
+      .unwrap(),
+    Some(Value::String("lox!".to_owned()))
+  );
+  assert_eq!(vm.global("count"), Some(Value::Number(3.0)));
+}
+
+#[test]
+fn importing_replaces_globals_of_the_same_names() {
+  let mut vm = Vm::new();
+  vm.eval(&Source::new("var a = 1;")).unwrap();
+  vm.import_globals([("a".to_owned(), Value::Number(2.0))])
+    .unwrap();
+  assert_eq!(vm.global("a"), Some(Value::Number(2.0)));
+}
+
+#[test]
+fn objects_are_not_imported() {
+  let mut vm = Vm::new();
+  let error = vm
+    .import_globals([
+      ("a".to_owned(), Value::Number(1.0)),
+      (
+        "f".to_owned(),
+        Value::Object {
+          type_name: "function",
+          repr: "<fn f>".to_owned(),
+        },
+      ),
+    ])
+    .unwrap_err();
+  assert_eq!(error.kind(), DiagnosticKind::RuntimeError);
+  assert_eq!(
+    error.message(),
+    "Global `f` can't be imported, only nil, booleans, numbers and strings could be."
+  );
+  assert_eq!(vm.global("a"), None, "nothing is defined on errors");
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn globals_round_trip_through_json() {
+  let globals = exported("var a = 1.5; var b = \"s\"; var c = false; var d = nil;");
+  let json = serde_json::to_string(&globals).unwrap();
+  assert_eq!(json, r#"{"a":1.5,"b":"s","c":false,"d":null}"#);
+
+  let mut vm = Vm::new();
+  vm.import_globals(serde_json::from_str::<HashMap<String, Value>>(&json).unwrap())
+    .unwrap();
+  assert_eq!(
+    vm.eval(&Source::new("a * 2;")).unwrap(),
+    Some(Value::Number(3.0))
+  );
+  assert_eq!(vm.export_globals::<BTreeMap<_, _>>(), globals);
+}
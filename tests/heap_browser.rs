@@ -0,0 +1,133 @@
+use rlox::{object::ObjType, HeapGroup, RunOutcome, Source, Vm};
+
+/// Count of live objects of the type.
+fn count(vm: &mut Vm, obj_type: ObjType) -> usize {
+  vm.debugger()
+    .heap_summary()
+    .iter()
+    .find(|group| group.obj_type == obj_type)
+    .map_or(0, |group| group.count)
+}
+
+/// Run the source on a fresh virtual machine.
+fn ran(src: &str) -> Vm {
+  let mut vm = Vm::new();
+  let script = vm.compile(&Source::new(src)).unwrap();
+  vm.run(&script).unwrap();
+  vm
+}
+
+#[test]
+fn objects_are_grouped_by_type() {
+  let mut vm = ran(
+    "class Point { init(x) { this.x = x; } }
+    var points = Point(1);
+    var more = Point(2);
+    var bound = more.init;",
+  );
+  assert_eq!(count(&mut vm, ObjType::Instance), 2);
+  assert_eq!(count(&mut vm, ObjType::BoundMethod), 1);
+  assert!(count(&mut vm, ObjType::Native) > 0);
+  let summary = vm.debugger().heap_summary();
+  let objects = vm.debugger().heap_objects();
+  assert_eq!(
+    summary.iter().map(|group| group.count).sum::<usize>(),
+    objects.len()
+  );
+  for HeapGroup {
+    obj_type,
+    count,
+    size,
+  } in summary
+  {
+    let group: Vec<_> = objects
+      .iter()
+      .filter(|obj| obj.obj_type == obj_type)
+      .collect();
+    assert_eq!(group.len(), count);
+    assert_eq!(group.iter().map(|obj| obj.size).sum::<usize>(), size);
+    assert!(group.iter().all(|obj| obj.size > 0));
+  }
+}
+
+#[test]
+fn unreachable_objects_are_not_listed() {
+  let mut vm = ran(
+    "class Node {}
+    var kept = Node();
+    var dropped = Node();
+    dropped = nil;",
+  );
+  assert_eq!(count(&mut vm, ObjType::Instance), 1);
+}
+
+#[test]
+fn objects_are_reached_through_fields_and_closures() {
+  let mut vm = ran(
+    r#"class Box {}
+    var outer = Box();
+    outer.inner = Box();
+    outer.inner.inner = Box();
+    fun make() {
+      var captured = Box();
+      fun get() { return captured; }
+      return get;
+    }
+    var get = make();"#,
+  );
+  assert_eq!(count(&mut vm, ObjType::Instance), 4);
+  assert_eq!(count(&mut vm, ObjType::Upvalue), 1);
+  assert_eq!(count(&mut vm, ObjType::Closure), 1);
+}
+
+#[test]
+fn locals_of_paused_runs_are_roots() {
+  let src = "class Box {}
+fun run() {
+  var local = Box();
+  return nil;
+}
+run();";
+  let mut vm = Vm::new();
+  let script = vm.compile(&Source::new(src)).unwrap();
+  vm.start(&script);
+  vm.set_breakpoint(4);
+  assert_eq!(vm.resume().unwrap(), RunOutcome::Paused { line: 4 });
+  assert_eq!(count(&mut vm, ObjType::Instance), 1);
+  assert_eq!(
+    vm.resume().unwrap(),
+    RunOutcome::Finished(Some(rlox::Value::Nil))
+  );
+  assert_eq!(count(&mut vm, ObjType::Instance), 0);
+}
+
+#[test]
+fn objects_are_inspected_by_id() {
+  let mut vm = ran(
+    r#"class Pair { init(a, b) { this.a = a; this.b = b; } }
+    var pair = Pair("left", 2);"#,
+  );
+  let objects = vm.debugger().heap_objects();
+  let pair = objects
+    .iter()
+    .find(|obj| obj.obj_type == ObjType::Instance)
+    .unwrap();
+  let inspection = vm.debugger().inspect_object(pair.id).unwrap();
+  assert!(inspection.starts_with(&format!("{:#x} = ", pair.id)));
+  assert!(inspection.contains("class: Pair"));
+  assert!(inspection.contains("a = \"left\""));
+  let left = objects.iter().find(|obj| obj.repr == "\"left\"").unwrap();
+  assert_eq!(left.obj_type, ObjType::String);
+  assert!(vm
+    .debugger()
+    .inspect_object(left.id)
+    .unwrap()
+    .contains("length: 4 bytes"));
+}
+
+#[test]
+fn unknown_ids_are_not_inspected() {
+  let mut vm = ran("var s = \"text\";");
+  assert_eq!(vm.debugger().inspect_object(0), None);
+  assert_eq!(vm.debugger().inspect_object(1), None);
+}
@@ -0,0 +1,97 @@
+use rlox::{
+  object::{ObjString, ObjTrait, ObjType},
+  utils::Init,
+  value::Value,
+  vm::{InterpretError, VM},
+  HeapChange, Source, Vm,
+};
+
+/// A native of the host which allocates a string each call.
+fn greeting(_: &[Value]) -> Result<Value, InterpretError> {
+  let s = ObjString::from("hello".to_owned());
+  Ok(Value::obj_val(s.cast_to_obj_ptr()))
+}
+
+/// A virtual machine with `greeting` defined, and the source interpreted.
+fn interpreted(src: &str) -> VM {
+  let mut vm = VM::init();
+  vm.define_native("greeting", 0, greeting);
+  vm.interpret(src.to_owned()).unwrap();
+  vm
+}
+
+#[test]
+fn discarded_results_leave_no_diff() {
+  let mut vm = interpreted("var kept = 1;");
+  let before = vm.heap_snapshot();
+  vm.interpret("greeting(); greeting();".to_owned()).unwrap();
+  let diff = before.diff(&vm.heap_snapshot());
+  assert!(diff.is_empty(), "{}", diff);
+  assert!(diff.changes.is_empty());
+  assert_eq!(diff.to_string(), "");
+}
+
+#[test]
+fn kept_results_are_new_objects() {
+  let mut vm = interpreted("class Box {}");
+  let before = vm.heap_snapshot();
+  vm.interpret("var a = greeting(); var b = Box(); b.greeting = greeting();".to_owned())
+    .unwrap();
+  let diff = before.diff(&vm.heap_snapshot());
+  assert_eq!(
+    diff
+      .changes
+      .iter()
+      .map(|change| (change.obj_type, change.count))
+      .collect::<Vec<_>>(),
+    [(ObjType::String, 2), (ObjType::Instance, 1)]
+  );
+  assert_eq!(diff.new_objects.len(), 3);
+  assert!(diff
+    .new_objects
+    .iter()
+    .all(|obj| obj.repr == "\"hello\"" || obj.repr == "Box instance"));
+  assert!(diff.dropped_objects.is_empty());
+  let report = diff.to_string();
+  assert!(report.starts_with("String"));
+  assert!(report.contains("+2 objects"));
+  assert_eq!(
+    report
+      .lines()
+      .filter(|line| line.starts_with("+ 0x"))
+      .count(),
+    3
+  );
+}
+
+#[test]
+fn dropped_objects_are_listed() {
+  let mut vm = interpreted("var a = greeting();");
+  let before = vm.heap_snapshot();
+  vm.interpret("a = nil;".to_owned()).unwrap();
+  let diff = before.diff(&vm.heap_snapshot());
+  assert!(!diff.is_empty());
+  assert!(diff.new_objects.is_empty());
+  assert_eq!(diff.dropped_objects.len(), 1);
+  assert_eq!(
+    diff.changes,
+    [HeapChange {
+      obj_type: ObjType::String,
+      count: -1,
+      size: -(diff.dropped_objects[0].size as isize),
+    }]
+  );
+}
+
+#[test]
+fn snapshots_summarize_as_the_debugger() {
+  let mut vm = Vm::new();
+  let script = vm
+    .compile(&Source::new("class A {} var a = A(); var b = A();"))
+    .unwrap();
+  vm.run(&script).unwrap();
+  let snapshot = vm.debugger().heap_snapshot();
+  assert_eq!(snapshot.summary(), vm.debugger().heap_summary());
+  assert_eq!(snapshot.objects, vm.debugger().heap_objects());
+  assert!(snapshot.diff(&snapshot).is_empty());
+}
@@ -0,0 +1,109 @@
+use rlox::{
+  utils::Init,
+  value::Value as RawValue,
+  vm::{InterpretError, VM},
+  Source, Value, Vm,
+};
+
+const V1: &str = r#"
+  var score = 0;
+  fun bonus() { return 1; }
+  class Greeter { greet() { return "hi"; } }
+  fun tick() { score = score + bonus(); }
+"#;
+
+const V2: &str = r#"
+  var score = 0;
+  var lives = 3;
+  fun bonus() { return 10; }
+  class Greeter { greet() { return "hello"; } }
+  fun tick() { score = score + bonus(); }
+"#;
+
+#[test]
+fn definitions_are_replaced_and_data_kept() {
+  let mut vm = VM::init();
+  vm.interpret(V1.to_owned()).unwrap();
+  vm.interpret("tick(); tick();".to_owned()).unwrap();
+  vm.reload(V2.to_owned()).unwrap();
+  vm.interpret("tick();".to_owned()).unwrap();
+  assert_eq!(
+    vm.interpret("score;".to_owned()).unwrap(),
+    Some(RawValue::number_val(12.0)),
+    "`score` survives, and `tick` calls the new `bonus`"
+  );
+  assert_eq!(
+    vm.interpret("lives;".to_owned()).unwrap(),
+    Some(RawValue::number_val(3.0)),
+    "new globals are defined"
+  );
+  assert_eq!(
+    vm.interpret("Greeter().greet() == \"hello\";".to_owned())
+      .unwrap(),
+    Some(RawValue::bool_val(true))
+  );
+}
+
+#[test]
+fn enums_are_replaced() {
+  let mut vm = VM::init();
+  vm.interpret("enum Color { Red, Green } var favorite = Color.Green;".to_owned())
+    .unwrap();
+  vm.reload("enum Color { Red, Green, Blue } var favorite = Color.Green;".to_owned())
+    .unwrap();
+  assert_eq!(
+    vm.interpret("Color.Blue.ordinal;".to_owned()).unwrap(),
+    Some(RawValue::number_val(2.0)),
+    "the edited enum replaces the previous one"
+  );
+  assert_eq!(
+    vm.interpret("favorite == Color.Green;".to_owned()).unwrap(),
+    Some(RawValue::bool_val(true)),
+    "members are of the reloaded enum"
+  );
+}
+
+#[test]
+fn failed_runs_change_nothing() {
+  let mut vm = VM::init();
+  vm.interpret(V1.to_owned()).unwrap();
+  let broken = "fun bonus() { return 2; } var score = 5; undefined_global;";
+  match vm.reload(broken.to_owned()) {
+    Err(InterpretError::RuntimeError(message)) => {
+      assert!(
+        message.contains("Undefined variable `undefined_global`."),
+        "{}",
+        message
+      )
+    }
+    other => panic!("expect a runtime error, but got: {:?}", other),
+  }
+  assert_eq!(
+    vm.interpret("bonus() + score;".to_owned()).unwrap(),
+    Some(RawValue::number_val(1.0))
+  );
+}
+
+#[test]
+fn failed_compiles_change_nothing() {
+  let mut vm = VM::init();
+  vm.interpret(V1.to_owned()).unwrap();
+  assert!(matches!(
+    vm.reload("fun bonus( { return 2; }".to_owned()),
+    Err(InterpretError::CompileError(_))
+  ));
+  assert_eq!(
+    vm.interpret("bonus();".to_owned()).unwrap(),
+    Some(RawValue::number_val(1.0))
+  );
+}
+
+#[test]
+fn facade_reloads_sources() {
+  let mut vm = Vm::new();
+  vm.eval(&Source::named("game.lox", V1)).unwrap();
+  vm.eval(&Source::new("tick();")).unwrap();
+  vm.reload(&Source::named("game.lox", V2)).unwrap();
+  vm.eval(&Source::new("tick();")).unwrap();
+  assert_eq!(vm.global("score"), Some(Value::Number(11.0)));
+}
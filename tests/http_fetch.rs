@@ -0,0 +1,77 @@
+#![cfg(feature = "http")]
+
+mod common;
+
+use common::{runtime_error, PRELUDE};
+use rlox::{native::Capabilities, utils::Init, vm::VM};
+use std::{
+  io::{Read, Write},
+  net::TcpListener,
+  thread,
+};
+
+/// Serve one request with the status line and body, then return the url.
+fn serve_once(status_line: &'static str, body: &'static str) -> String {
+  let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+  let url = format!("http://{}/hello", listener.local_addr().unwrap());
+  thread::spawn(move || {
+    let (mut stream, _) = listener.accept().unwrap();
+    let mut request = [0; 1024];
+    let _ = stream.read(&mut request).unwrap();
+    let response = format!(
+      "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+      status_line,
+      body.len(),
+      body
+    );
+    stream.write_all(response.as_bytes()).unwrap();
+  });
+  url
+}
+
+fn vm_with_network() -> VM {
+  let mut vm = VM::init();
+  vm.set_capabilities(Capabilities {
+    network: true,
+    ..Capabilities::none()
+  });
+  vm
+}
+
+#[test]
+fn fetch_is_denied_by_default() {
+  let message = runtime_error(r#"fetch("http://127.0.0.1:1/");"#);
+  assert!(
+    message.contains("Native function `fetch` needs the `network` capability."),
+    "{}",
+    message
+  );
+}
+
+#[test]
+fn fetch_returns_status_and_body() {
+  let url = serve_once("200 OK", "hello from rlox");
+  let src = format!(
+    "var r = fetch(\"{}\"); assert(r.status == 200); assert(r.body == \"hello from rlox\");",
+    url
+  );
+  vm_with_network()
+    .interpret(PRELUDE.to_owned() + &src)
+    .unwrap();
+
+  let url = serve_once("404 Not Found", "missing");
+  let src = format!(
+    "var r = fetch(\"{}\"); assert(r.status == 404); assert(r.body == \"missing\");",
+    url
+  );
+  vm_with_network()
+    .interpret(PRELUDE.to_owned() + &src)
+    .unwrap();
+}
+
+#[test]
+fn unreachable_host_is_a_runtime_error() {
+  let src = r#"fetch("http://127.0.0.1:1/");"#;
+  let error = vm_with_network().interpret(src.to_owned()).unwrap_err();
+  assert!(format!("{:?}", error).contains("`fetch` failed to get"));
+}
@@ -0,0 +1,137 @@
+use rlox::{Capabilities, Source, Value, Vm};
+use std::path::{Path, PathBuf};
+
+/// A fresh temporary directory (named after the test) with the files, as `(path, source)`.
+fn project(name: &str, files: &[(&str, &str)]) -> PathBuf {
+  let dir = std::env::temp_dir().join(format!("rlox_imports_{}", name));
+  let _ = std::fs::remove_dir_all(&dir);
+  for (path, src) in files {
+    let path = dir.join(path);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, src).unwrap();
+  }
+  dir
+}
+
+/// Compile then run the file (with files granted), returning the virtual machine (to read globals).
+fn run_file(path: &Path) -> Result<Vm, String> {
+  let mut vm = Vm::new();
+  vm.set_capabilities(Capabilities {
+    files: true,
+    ..Capabilities::none()
+  });
+  let source = Source::from_file(path.display().to_string()).map_err(|e| e.to_string())?;
+  let script = vm.compile(&source).map_err(|e| e.to_string())?;
+  vm.run(&script).map_err(|e| e.to_string())?;
+  Ok(vm)
+}
+
+#[test]
+fn imports_resolve_relative_to_the_importing_file() {
+  let dir = project(
+    "relative",
+    &[
+      (
+        "main.lox",
+        "import \"lib/greet\"; var message = greet(\"lox\");",
+      ),
+      (
+        "lib/greet.lox",
+        "import \"prefix.lox\"; fun greet(name) { return prefix + name; }",
+      ),
+      ("lib/prefix.lox", "var prefix = \"hello \";"),
+    ],
+  );
+  let vm = run_file(&dir.join("main.lox")).unwrap();
+  assert_eq!(
+    vm.global("message"),
+    Some(Value::String("hello lox".into()))
+  );
+}
+
+#[test]
+fn modules_run_once() {
+  let dir = project(
+    "once",
+    &[
+      (
+        "main.lox",
+        "var runs = 0; import \"a\"; import \"b\"; import \"counter\";",
+      ),
+      ("a.lox", "import \"counter\";"),
+      ("b.lox", "import \"./counter.lox\";"),
+      ("counter.lox", "runs = runs + 1;"),
+    ],
+  );
+  let vm = run_file(&dir.join("main.lox")).unwrap();
+  assert_eq!(vm.global("runs"), Some(Value::Number(1.0)));
+}
+
+#[test]
+fn imports_fall_back_to_search_path_then_stdlib() {
+  let dir = project(
+    "search_path",
+    &[
+      (
+        "app/main.lox",
+        "import \"math\"; import \"shared\"; var x = clamp(shared, 0, 10);",
+      ),
+      ("vendor/shared.lox", "var shared = abs(-42);"),
+    ],
+  );
+  std::env::set_var("RLOX_PATH", dir.join("vendor"));
+  let vm = run_file(&dir.join("app/main.lox"));
+  std::env::remove_var("RLOX_PATH");
+  assert_eq!(vm.unwrap().global("x"), Some(Value::Number(10.0)));
+}
+
+#[test]
+fn stdlib_modules_are_embedded() {
+  let mut vm = Vm::new();
+  let src = "import \"math\"; sum([1, 2, 3]) + max(4, 5) + min(4, 5);";
+  let script = vm.compile(&Source::new(src)).unwrap();
+  assert_eq!(vm.run(&script).unwrap(), Some(Value::Number(15.0)));
+}
+
+#[test]
+fn import_cycles_are_reported_with_the_chain() {
+  let dir = project(
+    "cycle",
+    &[
+      ("a.lox", "import \"b\";"),
+      ("b.lox", "import \"c\";"),
+      ("c.lox", "import \"a\";"),
+    ],
+  );
+  let error = run_file(&dir.join("a.lox")).unwrap_err();
+  assert!(
+    error.contains("Import cycle: `")
+      && error.ends_with("a.lox` imports `b` imports `c` imports `a`."),
+    "{}",
+    error
+  );
+}
+
+#[test]
+fn missing_and_broken_modules_are_compile_errors() {
+  let dir = project(
+    "errors",
+    &[
+      ("missing.lox", "import \"nowhere\";"),
+      ("broken.lox", "import \"syntax\";"),
+      ("syntax.lox", "var = 1;"),
+    ],
+  );
+  let error = run_file(&dir.join("missing.lox")).unwrap_err();
+  assert!(
+    error.contains("Can't find module `nowhere.lox`"),
+    "{}",
+    error
+  );
+  let error = run_file(&dir.join("broken.lox")).unwrap_err();
+  assert!(
+    error.contains("syntax.lox:1:5] Error at '=': Expect variable name."),
+    "{}",
+    error
+  );
+}
@@ -0,0 +1,137 @@
+mod common;
+
+use common::{compile_error, run};
+
+#[test]
+fn prefix_results_in_the_changed_value() {
+  let src = r#"
+    var g = 1;
+    assert(++g == 2);
+    assert(g == 2);
+    assert(--g == 1);
+    assert(g == 1);
+  "#;
+  assert!(run(src).is_ok());
+}
+
+#[test]
+fn postfix_results_in_the_value_before_the_change() {
+  let src = r#"
+    var g = 1;
+    assert(g++ == 1);
+    assert(g == 2);
+    assert(g-- == 2);
+    assert(g == 1);
+  "#;
+  assert!(run(src).is_ok());
+}
+
+#[test]
+fn locals_and_captured_variables_are_changed() {
+  let src = r#"
+    fun counter() {
+      var count = 0;
+      fun next() { return ++count; }
+      return next;
+    }
+    var next = counter();
+    next();
+    assert(next() == 2);
+
+    {
+      var x = 5;
+      var y = x++ + ++x;
+      assert(y == 12);
+      assert(x == 7);
+      assert(-x++ == -7);
+      assert(x == 8);
+    }
+  "#;
+  assert!(run(src).is_ok());
+}
+
+#[test]
+fn increments_update_loops() {
+  let src = r#"
+    var s = 0;
+    for (var i = 0; i < 4; i++) s = s + i;
+    assert(s == 6);
+    var n = 3;
+    while (n-- > 0) s = s + 1;
+    assert(s == 9);
+    assert(n == -1);
+  "#;
+  assert!(run(src).is_ok());
+}
+
+#[test]
+fn properties_and_items_are_changed() {
+  let src = r#"
+    class Counter { init() { this.count = 0; } next() { return ++this.count; } }
+    var c = Counter();
+    c.next();
+    assert(c.next() == 2);
+    assert(c.count++ == 2);
+    assert(c.count == 3);
+    assert(--c.count == 2);
+    assert(c.count-- == 2);
+    assert(c.count == 1);
+
+    var xs = [1, [10, 20]];
+    var i = 0;
+    assert(xs[i++]++ == 1);
+    assert(i == 1 and xs[0] == 2);
+    assert(++xs[i][1] == 21);
+    assert(xs[1][1]-- == 21);
+    assert(xs[1][0] == 10 and xs[1][1] == 20);
+    assert(xs[0]++ + ++xs[0] == 6);
+  "#;
+  assert!(run(src).is_ok());
+}
+
+#[test]
+fn targets_are_evaluated_once() {
+  let src = r#"
+    var calls = 0;
+    var xs = [0, 0];
+    fun at(i) { calls = calls + 1; return i; }
+    xs[at(1)]++;
+    ++xs[at(1)];
+    assert(calls == 2);
+    assert(xs[0] == 0 and xs[1] == 2);
+
+    class Box {}
+    var box = Box();
+    box.n = 0;
+    fun get() { calls = calls + 1; return box; }
+    get().n++;
+    ++get().n;
+    assert(calls == 4);
+    assert(box.n == 2);
+  "#;
+  assert!(run(src).is_ok());
+}
+
+#[test]
+fn only_variables_properties_and_items_are_incremented() {
+  for src in [
+    "1++;",
+    "fun f() {} f()++;",
+    "++1;",
+    "var a = 1; ++(a);",
+    "var a = [1]; ++a[0:1];",
+    "class C { m() {} } var c = C(); ++c.m();",
+    "var a = 1; ++-a;",
+  ] {
+    assert!(
+      compile_error(src)
+        .contains("Can only increment or decrement variables, properties or items."),
+      "{}",
+      src
+    );
+  }
+  assert!(compile_error("var a = 1; ++a = 2;").contains("Invalid assignment target."));
+  assert!(
+    compile_error("class C {} var c = C(); c.n++ = 2;").contains("Invalid assignment target.")
+  );
+}
@@ -0,0 +1,172 @@
+mod common;
+
+use common::run;
+use rlox::{utils::Init, value::display, vm::VM};
+
+fn inspect(src: &str, name: &str) -> Option<String> {
+  let mut vm = VM::init();
+  vm.interpret(src.to_owned()).unwrap();
+  vm.inspect(name)
+}
+
+#[test]
+fn type_of_names_every_type() {
+  run(
+    r#"
+class A { m() {} }
+var a = A();
+assert(type_of(nil) == "nil");
+assert(type_of(true) == "bool");
+assert(type_of(1) == "number");
+assert(type_of("s") == "string");
+assert(type_of(assert) == "function");
+assert(type_of(clock) == "native");
+assert(type_of(A) == "class");
+assert(type_of(a) == "instance");
+assert(type_of(a.m) == "bound method");
+"#,
+  )
+  .unwrap();
+}
+
+#[test]
+fn inspect_string() {
+  assert_eq!(
+    inspect(r#"var s = "héllo";"#, "s").unwrap(),
+    "s = \"héllo\"\n  type: string\n  length: 6 bytes\n"
+  );
+}
+
+#[test]
+fn inspect_function_with_defaults() {
+  assert_eq!(
+    inspect("fun f(a, b = 1) {}", "f").unwrap(),
+    "f = <fn f>\n  type: function\n  arity: 2 (1 required)\n"
+  );
+}
+
+#[test]
+fn inspect_class_and_instance() {
+  let src = r#"
+class Point {
+  init(x) { this.x = x; this.y = 2; }
+  len() { return this.x; }
+  get sum { return this.x + this.y; }
+}
+var p = Point(1);
+"#;
+  let class = inspect(src, "Point").unwrap();
+  assert!(class.contains("  type: class\n"), "{}", class);
+  assert!(class.contains("  methods: init, len\n"), "{}", class);
+  assert!(class.contains("  getters: sum\n"), "{}", class);
+  assert_eq!(
+    inspect(src, "p").unwrap(),
+    "p = Point instance\n  type: instance\n  class: Point\n  fields: 2\n    x = 1\n    y = 2\n"
+  );
+}
+
+#[test]
+fn inspect_undefined() {
+  assert_eq!(inspect("var a = 1;", "b"), None);
+}
+
+fn pretty(src: &str, depth: usize) -> String {
+  let mut vm = VM::init();
+  let value = vm.interpret(src.to_owned()).unwrap().unwrap();
+  display::pretty(&value, depth)
+}
+
+#[test]
+fn pretty_nested_instances() {
+  let src = r#"
+class Node {}
+var a = Node();
+a.name = "a";
+a.next = Node();
+a.next.next = Node();
+a;
+"#;
+  assert_eq!(
+    pretty(src, 4),
+    "Node {\n  name: \"a\",\n  next: Node {\n    next: Node {},\n  },\n}"
+  );
+  assert_eq!(
+    pretty(src, 1),
+    "Node {\n  name: \"a\",\n  next: Node {..},\n}"
+  );
+  assert_eq!(pretty(src, 0), "Node {..}");
+  assert_eq!(pretty("1;", 0), "1");
+}
+
+#[test]
+fn pretty_cycles() {
+  let src = r#"
+class Node {}
+var a = Node();
+a.next = Node();
+a.next.next = a;
+a;
+"#;
+  assert_eq!(
+    pretty(src, 8),
+    "Node {\n  next: Node {\n    next: <cycle>,\n  },\n}"
+  );
+}
+
+#[test]
+fn pretty_nested_lists() {
+  let src = "[1, [2, [3, [4]]], []];";
+  assert_eq!(
+    pretty(src, 4),
+    "[\n  1,\n  [\n    2,\n    [\n      3,\n      [\n        4,\n      ],\n    ],\n  ],\n  [],\n]"
+  );
+  assert_eq!(
+    pretty(src, 2),
+    "[\n  1,\n  [\n    2,\n    [..],\n  ],\n  [],\n]"
+  );
+  assert_eq!(pretty(src, 0), "[..]");
+  assert_eq!(pretty("[];", 0), "[]");
+}
+
+#[test]
+fn pretty_self_containing_lists() {
+  let src = "var xs = [1]; push(xs, xs); xs;";
+  assert_eq!(pretty(src, 8), "[\n  1,\n  <cycle>,\n]");
+  let src = r#"
+class Node {}
+var a = Node();
+a.items = [a];
+a;
+"#;
+  assert_eq!(pretty(src, 8), "Node {\n  items: [\n    <cycle>,\n  ],\n}");
+}
+
+#[test]
+fn pretty_lists_of_instances() {
+  let src = r#"
+class P {}
+var p = P();
+p.x = 1;
+[p, P()];
+"#;
+  assert_eq!(pretty(src, 4), "[\n  P {\n    x: 1,\n  },\n  P {},\n]");
+  assert_eq!(pretty(src, 1), "[\n  P {..},\n  P {},\n]");
+}
+
+#[test]
+fn pretty_native() {
+  run(
+    r#"
+class P {}
+var p = P();
+p.x = 1;
+assert(pretty(p, 1) == "P {
+  x: 1,
+}");
+assert(pretty("s", 0) == "\"s\"");
+"#,
+  )
+  .unwrap();
+  assert!(common::runtime_error("pretty(1, -1);")
+    .contains("`pretty` expected a non-negative integer depth."));
+}
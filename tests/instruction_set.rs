@@ -0,0 +1,46 @@
+use rlox::{
+  chunk::{Chunk, OpCode},
+  debug::Debug,
+  isa,
+  utils::Init,
+};
+
+#[test]
+fn reference_is_up_to_date() {
+  let path = project_root::get_project_root()
+    .unwrap()
+    .join("docs/instruction_set.md");
+  let documented = std::fs::read_to_string(path).unwrap();
+  assert!(
+    documented == isa::reference(),
+    "`docs/instruction_set.md` is stale, regenerate it by `cargo run -- isa > docs/instruction_set.md`"
+  );
+}
+
+#[test]
+fn widths_agree_with_the_disassembler() {
+  let mut chunk = Chunk::init();
+  chunk.add_constant(1.0.into());
+  for op_code in OpCode::all() {
+    chunk.write_chunk(op_code.into(), 1);
+    for _ in 1..op_code.spec().width() {
+      chunk.write_chunk(0, 1);
+    }
+  }
+  let disassembly = chunk.disassembly("all");
+  let instructions = disassembly
+    .lines()
+    .filter(|line| line.starts_with(|c: char| c.is_ascii_digit()));
+  assert_eq!(instructions.count(), OpCode::all().count());
+  assert!(!disassembly.contains("Unknown opcode"));
+}
+
+#[test]
+fn encoding_changes_bump_the_version() {
+  // update both (after bumping `ISA_VERSION`) once the encoding of any opcode changes
+  assert_eq!(
+    (isa::ISA_VERSION, isa::fingerprint()),
+    (15, 7391631262732395320),
+    "the encoding of opcodes changed, bump `isa::ISA_VERSION`, then update the fingerprint"
+  );
+}
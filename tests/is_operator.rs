@@ -0,0 +1,29 @@
+mod common;
+
+use common::{run, runtime_error};
+
+#[test]
+fn instance_of_class() {
+  let src = r#"
+    class A {}
+    class B {}
+    var a = A();
+    assert(a is A);
+    assert(!(a is B));
+    assert(!(1 is A));
+    assert(!(nil is A));
+    assert(!(A is A));
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn right_operand_must_be_class() {
+  let src = "class A {} A() is 1;";
+  assert!(runtime_error(src).contains("Right operand of `is` must be a class."));
+}
+
+#[test]
+fn is_is_still_a_prefix_of_identifiers() {
+  run("var island = 1; var i = 2; if (island == 1) i = 3; assert(i == 3);").unwrap();
+}
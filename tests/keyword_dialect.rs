@@ -0,0 +1,120 @@
+use rlox::{
+  scanner::{Keywords, TokenType, STANDARD_KEYWORDS},
+  utils::Init,
+  value::Value,
+  vm::{InterpretError, VM},
+};
+
+fn vm_with(keywords: Keywords) -> VM {
+  let mut vm = VM::init();
+  vm.set_keywords(keywords);
+  vm
+}
+
+#[test]
+fn default_keywords_are_standard_lox() {
+  let keywords = Keywords::default();
+  assert_eq!(keywords, Keywords::standard());
+  for &(spelling, token_type) in STANDARD_KEYWORDS {
+    assert_eq!(keywords.lookup(spelling), token_type);
+  }
+  assert_eq!(keywords.lookup("iff"), TokenType::Identifier);
+  assert_eq!(keywords.lookup("clas"), TokenType::Identifier);
+  assert_eq!(keywords.lookup("classes"), TokenType::Identifier);
+}
+
+#[test]
+fn changed_copies_are_not_standard() {
+  let mut keywords = Keywords::standard();
+  assert!(keywords.clone().is_standard());
+  keywords.insert("let", TokenType::Var).unwrap();
+  assert!(!keywords.is_standard());
+  assert!(!Keywords::empty().is_standard());
+}
+
+#[test]
+fn alternative_spellings_are_keywords_too() {
+  let mut keywords = Keywords::standard();
+  keywords.insert("let", TokenType::Var).unwrap();
+  keywords.insert("fn", TokenType::Fun).unwrap();
+  let mut vm = vm_with(keywords);
+  let src = "fn add(a, b) { return a + b; } let x = 1; var y = 2; add(x, y);";
+  assert_eq!(vm.interpret(src.into()).unwrap(), Some(Value::from(3.0)));
+}
+
+#[test]
+fn localized_keywords() {
+  let mut keywords = Keywords::empty();
+  for (spelling, token_type) in [
+    ("klasse", TokenType::Class),
+    ("wenn", TokenType::If),
+    ("sonst", TokenType::Else),
+    ("wahr", TokenType::True),
+    ("falsch", TokenType::False),
+    ("variable", TokenType::Var),
+    ("dies", TokenType::This),
+    ("zurueck", TokenType::Return),
+  ] {
+    keywords.insert(spelling, token_type).unwrap();
+  }
+  let mut vm = vm_with(keywords);
+  let src = r#"
+    klasse Punkt {
+      init(x) { dies.x = x; }
+      doppelt() { zurueck dies.x * 2; }
+    }
+    variable p = Punkt(21);
+    variable ergebnis = falsch;
+    wenn (wahr) ergebnis = p.doppelt(); sonst ergebnis = nil;
+    ergebnis;
+  "#;
+  assert_eq!(vm.interpret(src.into()).unwrap(), Some(Value::from(42.0)));
+}
+
+#[test]
+fn removed_keywords_are_identifiers() {
+  let mut keywords = Keywords::standard();
+  assert_eq!(keywords.remove("print"), Some(TokenType::Print));
+  let mut vm = vm_with(keywords);
+  assert_eq!(
+    vm.interpret("var print = 1; print + 1;".into()).unwrap(),
+    Some(Value::from(2.0))
+  );
+  // the standard dialect still reserves it
+  assert!(matches!(
+    VM::init().interpret("var print = 1;".into()),
+    Err(InterpretError::CompileError(_))
+  ));
+}
+
+#[test]
+fn spellings_of_a_keyword() {
+  let mut keywords = Keywords::standard();
+  keywords.insert("elif", TokenType::Else).unwrap();
+  assert_eq!(keywords.spellings(TokenType::Else), ["elif", "else"]);
+}
+
+#[test]
+fn invalid_spellings_are_rejected() {
+  let mut keywords = Keywords::standard();
+  for spelling in ["", "1st", "fün", "a-b"] {
+    assert!(
+      keywords.insert(spelling, TokenType::Var).is_err(),
+      "{}",
+      spelling
+    );
+  }
+  assert!(keywords.insert("plus", TokenType::Plus).is_err());
+  assert_eq!(keywords, Keywords::standard());
+}
+
+#[test]
+fn changing_keywords_invalidates_cached_scripts() {
+  let mut vm = VM::init();
+  let src = "let x = 1;";
+  assert!(vm.interpret(src.into()).is_err());
+  let mut keywords = Keywords::standard();
+  keywords.insert("let", TokenType::Var).unwrap();
+  vm.set_keywords(keywords);
+  vm.interpret(src.into()).unwrap();
+}
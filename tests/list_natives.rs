@@ -0,0 +1,152 @@
+mod common;
+
+use common::{run, runtime_error};
+use rlox::{
+  object::ObjString,
+  utils::Init,
+  value::Value,
+  vm::{InterpretError, VM},
+};
+
+#[test]
+fn functions_are_called_back() {
+  let src = r#"
+    fun double(x) { return x * 2; }
+    fun small(x) { return x < 3; }
+    fun add(a, b) { return a + b; }
+    var xs = [1, 2, 3, 4];
+    assert(deep_equals(map(xs, double), [2, 4, 6, 8]));
+    assert(deep_equals(filter(xs, small), [1, 2]));
+    assert(reduce(xs, add, 0) == 10);
+    assert(reduce([], add, "init") == "init");
+    assert(deep_equals(xs, [1, 2, 3, 4]));
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn closures_and_bound_methods_are_called_back() {
+  let src = r#"
+    class Scale {
+      init(factor) { this.factor = factor; }
+      apply(x) { return x * this.factor; }
+    }
+    var offset = 10;
+    fun shift(x) { return x + offset; }
+    fun shifted(xs) {
+      var by = 100;
+      fun add(x) { return x + by; }
+      return map(xs, add);
+    }
+    assert(deep_equals(map([1, 2], Scale(3).apply), [3, 6]));
+    assert(deep_equals(map([1], shift), [11]));
+    assert(deep_equals(shifted([1]), [101]));
+    assert(deep_equals(map(["a"], str), ["a"]));
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn lists_are_sorted_into_copies() {
+  let src = r#"
+    var xs = [3, 1, 2];
+    assert(deep_equals(sort(xs), [1, 2, 3]));
+    assert(deep_equals(xs, [3, 1, 2]));
+    assert(deep_equals(sort(["b", "c", "a"]), ["a", "b", "c"]));
+    fun descending(a, b) { return b - a; }
+    assert(deep_equals(sort(xs, descending), [3, 2, 1]));
+    fun by_first(a, b) { return a[0] - b[0]; }
+    var pairs = [[2, "a"], [1, "b"], [2, "c"], [1, "d"]];
+    assert(deep_equals(sort(pairs, by_first), [[1, "b"], [1, "d"], [2, "a"], [2, "c"]]));
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn inconsistent_comparators_never_panic() {
+  let src = r#"
+    var flip = 1;
+    fun random_order(a, b) { flip = -flip; return flip; }
+    var xs = [];
+    for (var i = 0; i < 50; i = i + 1) push(xs, i);
+    assert(len(sort(xs, random_order)) == 50);
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn invalid_arguments_are_runtime_errors() {
+  assert!(runtime_error("sort([1, \"a\"]);")
+    .contains("`sort` expected numbers or strings, or a comparator."));
+  assert!(
+    runtime_error("fun cmp(a, b) { return true; } sort([1, 2], cmp);")
+      .contains("`sort` expected the comparator to return a number.")
+  );
+  assert!(runtime_error("map(1, str);").contains("`map` expected a list."));
+  assert!(runtime_error("map([1], 1);").contains("Can only call functions and classes."));
+  assert!(runtime_error("fun f() {} map([1], f);")
+    .contains("Function `f` expected 0 arguments but got 1."));
+  assert!(runtime_error("sort();").contains("Native function `sort` expected 1 to 2 arguments"));
+}
+
+#[test]
+fn errors_of_callbacks_are_caught_around_the_native() {
+  let src = r#"
+    fun broken(x) { return x.field; }
+    var caught = nil;
+    try { map([1], broken); } catch (e) { caught = e.message; }
+    assert(caught == "Only instances have properties.");
+    fun recovering(x) {
+      try { throw x; } catch (e) { return e + 1; }
+    }
+    assert(deep_equals(map([1, 2], recovering), [2, 3]));
+    var after = "stack is intact";
+    assert(after == "stack is intact");
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn values_thrown_by_callbacks_are_caught_as_they_are() {
+  let src = r#"
+    fun thrower(x) { if (x == 2) throw 42; return x; }
+    var caught = nil;
+    try { map([1, 2, 3], thrower); } catch (e) { caught = e; }
+    assert(caught == 42);
+    fun cmp(a, b) { throw error("by cmp"); }
+    try { sort([2, 1], cmp); } catch (e) { caught = e; }
+    assert(caught is Error and caught.kind == "Error" and caught.message == "by cmp");
+    // nested natives pass the value on as well
+    fun inner(x) { return filter([x], thrower); }
+    try { map([2], inner); } catch (e) { caught = e + 1; }
+    assert(caught == 43);
+  "#;
+  run(src).unwrap();
+  let message = runtime_error("fun f(x) { throw \"boom\"; }\nmap([1], f);");
+  assert!(message.contains("Uncaught \"boom\""), "{}", message);
+}
+
+#[test]
+fn uncaught_errors_of_callbacks_stop_the_script() {
+  let message = runtime_error("fun broken(x) { return x + nil; }\nmap([1], broken);");
+  assert!(message.contains("in script"), "{}", message);
+}
+
+#[test]
+fn hosts_call_functions_of_scripts() {
+  let mut vm = VM::init();
+  vm.interpret("fun add(a, b) { return a + b; }".into())
+    .unwrap();
+  let add = *vm
+    .globals()
+    .get(&ObjString::from("add".to_owned()))
+    .unwrap();
+  let sum = vm.call_function(add, &[Value::number_val(1.0), Value::number_val(2.0)]);
+  assert_eq!(sum.unwrap().as_number(), 3.0);
+  assert!(vm.is_idle());
+  let error = vm.call_function(add, &[Value::nil_val()]).unwrap_err();
+  assert!(
+    matches!(error, InterpretError::RuntimeError(message) if message.contains("expected 2 arguments"))
+  );
+  assert!(vm.is_idle());
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use common::{compile_error, run, runtime_error};
+
+#[test]
+fn literals_build_lists() {
+  let src = r#"
+    var empty = [];
+    var xs = [1, "two", nil, [3],];
+    assert(len(empty) == 0);
+    assert(len(xs) == 4);
+    assert(str(xs) == "[1, \"two\", nil, [3]]");
+    assert(type_of(xs) == "list");
+    assert(xs != [1, "two", nil, [3]]);
+    assert(xs == xs);
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn items_are_got_and_set_by_index() {
+  let src = r#"
+    var xs = [1, 2, 3];
+    assert(xs[0] + xs[2] == 4);
+    assert((xs[1] = 20) == 20);
+    assert(xs[1] == 20);
+    var grid = [[1, 2], [3, 4]];
+    grid[1][0] = 30;
+    assert(grid[1][0] == 30);
+    push(xs, 4);
+    assert(xs[3] == 4 and len(xs) == 4);
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn lists_nested_in_themselves_are_shown_once() {
+  let src = r#"
+    var xs = [1];
+    push(xs, xs);
+    assert(str(xs) == "[1, [...]]");
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn deep_natives_handle_lists() {
+  let src = r#"
+    var xs = [1, [2, "three"]];
+    var copy = clone(xs);
+    assert(deep_equals(xs, copy));
+    copy[1][0] = 20;
+    assert(xs[1][0] == 2);
+    assert(!deep_equals(xs, copy));
+    assert(!deep_equals([1], [1, 2]));
+    var cycle = [];
+    push(cycle, cycle);
+    assert(deep_equals(cycle, clone(cycle)));
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn invalid_indexing_is_a_runtime_error() {
+  assert!(runtime_error("[1, 2][2];").contains("List index 2 is out of bounds for length 2."));
+  assert!(runtime_error("[1][0.5];").contains("List index must be an integer."));
+  assert!(runtime_error("var xs = [1]; xs[-2] = 0;")
+    .contains("List index -2 is out of bounds for length 1."));
+  assert!(runtime_error("var n = 1; n[0];").contains("Only lists and strings can be indexed."));
+  assert!(runtime_error("len(1);").contains("`len` expected a list or a string."));
+}
+
+#[test]
+fn malformed_lists_are_compile_errors() {
+  assert!(compile_error("var xs = [1, 2;").contains("Expect `]` after list items."));
+  assert!(compile_error("var xs = [1]; xs[0;").contains("Expect `]` after index."));
+  let items = vec!["nil"; 256].join(", ");
+  assert!(compile_error(&format!("var xs = [{}];", items))
+    .contains("Can't have more than 255 items in a list literal."));
+}
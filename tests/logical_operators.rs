@@ -0,0 +1,44 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn results_are_operands() {
+  let src = r#"
+    assert((1 and 2) == 2);
+    assert((nil and 2) == nil);
+    assert((false and 2) == false);
+    assert((1 or 2) == 1);
+    assert((nil or "default") == "default");
+    assert((false or nil) == nil);
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn right_operands_are_skipped() {
+  let src = r#"
+    var calls = 0;
+    fun touch(value) { calls = calls + 1; return value; }
+    false and touch(1);
+    true or touch(1);
+    assert(calls == 0);
+    true and touch(1);
+    false or touch(1);
+    assert(calls == 2);
+    nil and undefined_global;
+    1 or undefined_global;
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn and_binds_tighter_than_or() {
+  let src = r#"
+    assert((true or false and false) == true);
+    assert((false and true or "right") == "right");
+    assert((nil or false and 1) == false);
+    assert((1 == 1 and 2 < 3) == true);
+  "#;
+  run(src).unwrap();
+}
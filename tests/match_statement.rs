@@ -0,0 +1,120 @@
+mod common;
+
+use common::{compile_error, run};
+
+#[test]
+fn literal_patterns_match_equal_values() {
+  let src = r#"
+    fun describe(value) {
+      match value {
+        0 => return "zero";
+        -1 => return "minus one";
+        "s" => return "string";
+        true => return "true";
+        nil => return "nil";
+        _ => return "other";
+      }
+    }
+    assert(describe(0) == "zero");
+    assert(describe(-1) == "minus one");
+    assert(describe("s") == "string");
+    assert(describe(true) == "true");
+    assert(describe(nil) == "nil");
+    assert(describe(false) == "other");
+    assert(describe("0") == "other");
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn only_the_first_matching_arm_runs() {
+  let src = r#"
+    var runs = [];
+    match 1 {
+      1 => { push(runs, "first"); },
+      n => { push(runs, "second"); },
+    }
+    assert(deep_equals(runs, ["first"]));
+    match 2 { 1 => push(runs, "no"); }
+    assert(len(runs) == 1);
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn names_are_bound_in_their_arm() {
+  let src = r#"
+    var n = "global";
+    match 1 + 2 {
+      n => { assert(n == 3); }
+    }
+    assert(n == "global");
+    fun adder(x) {
+      match x {
+        y => {
+          fun add(z) { return y + z; }
+          return add;
+        }
+      }
+    }
+    assert(adder(1)(2) == 3);
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn list_patterns_match_lengths_and_items() {
+  let src = r#"
+    fun shape(value) {
+      match value {
+        [] => return "empty";
+        [x] => return "one " + str(x);
+        [0, y] => return "zero then " + str(y);
+        [a, b] => return str(a + b);
+        [[a, _], ...rest] => return "nested " + str(a) + " " + str(len(rest));
+        [first, ..._] => return "long " + str(first);
+        _ => return "not a list";
+      }
+    }
+    assert(shape([]) == "empty");
+    assert(shape([5]) == "one 5");
+    assert(shape([0, 7]) == "zero then 7");
+    assert(shape([1, 2]) == "3");
+    assert(shape([[9, 8], 1, 2]) == "nested 9 2");
+    assert(shape([[9], 1, 2]) == "long [9]");
+    assert(shape([1, 2, 3]) == "long 1");
+    assert(shape("ab") == "not a list");
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn matches_nest_at_top_level_and_in_functions() {
+  let src = r#"
+    var result;
+    match [1, [2, 3]] {
+      [a, rest] => match rest {
+        [b, c] => result = a + b + c;
+      }
+    }
+    assert(result == 6);
+    var after = "stack is intact";
+    assert(after == "stack is intact");
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn malformed_matches_are_compile_errors() {
+  assert!(compile_error("match 1 { 1 print 1; }").contains("Expect `=>` after pattern."));
+  assert!(compile_error("match 1 { 1 + 2 => print 1; }").contains("Expect `=>` after pattern."));
+  assert!(compile_error("match 1 { (1) => print 1; }").contains("Expect pattern."));
+  assert!(compile_error("match 1 1 => print 1;").contains("Expect `{` after value of `match`."));
+  assert!(compile_error("match 1 { [...a, b] => print 1; }")
+    .contains("Rest of a list pattern must be its last item."));
+  assert!(
+    compile_error("match 1 { [a, a] => print 1; }").contains("Already a variable with this name")
+  );
+  assert!(compile_error("match 1 { _ => print 1;").contains("Expect `}` after arms of `match`."));
+  assert!(compile_error("var match = 1;").contains("Expect variable name."));
+}
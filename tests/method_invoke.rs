@@ -0,0 +1,35 @@
+mod common;
+
+use common::{run, runtime_error};
+use rlox::bench::bench_once;
+
+#[test]
+fn invoke_methods_and_callable_fields() {
+  let src = r#"
+    class Counter {
+      init() { this.count = 0; }
+      inc(by = 1) { this.count = this.count + by; return this; }
+    }
+    var c = Counter();
+    c.inc().inc(2);
+    assert(c.count == 3);
+    fun double(x) { return x * 2; }
+    c.callback = double;
+    assert(c.callback(4) == 8);
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn invoke_undefined_method() {
+  assert!(runtime_error("class C {} C().m();").contains("Undefined property `m`."));
+  assert!(runtime_error("var a = 1; a.m();").contains("Only instances have methods."));
+}
+
+#[test]
+fn invoke_saves_one_instruction_per_call() {
+  let class = "class C { m() {} } var c = C();";
+  let invoke = bench_once(&format!("{} c.m(); c.m();", class)).unwrap();
+  let bound = bench_once(&format!("{} (c.m)(); (c.m)();", class)).unwrap();
+  assert_eq!(invoke.instructions + 2, bound.instructions);
+}
@@ -0,0 +1,72 @@
+mod common;
+
+use common::{compile_error, run, runtime_error};
+
+#[test]
+fn multiple_values_are_returned_as_a_list() {
+  let src = r#"
+    fun min_max(xs) {
+      var sorted = sort(xs);
+      return sorted[0], sorted[-1];
+    }
+    var pair = min_max([3, 1, 2]);
+    assert(type_of(pair) == "list");
+    assert(deep_equals(pair, [1, 3]));
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn lists_are_unpacked_into_variables() {
+  let src = r#"
+    fun div_mod(a, b) {
+      var q = 0;
+      for (var rest = a; rest >= b; rest = rest - b) q = q + 1;
+      return q, a - q * b;
+    }
+    var q, r = div_mod(7, 2);
+    assert(q == 3 and r == 1);
+
+    fun local_scope() {
+      var before = "before";
+      var a, b, c = [1, 2, 3];
+      var after = "after";
+      fun sum() { return a + b + c; }
+      return before, sum(), after;
+    }
+    var before, sum, after = local_scope();
+    assert(before == "before" and sum == 6 and after == "after");
+
+    {
+      var x, y = ["x", "y"];
+      var swapped = [y, x];
+      var y2, x2 = swapped;
+      assert(x2 == "x" and y2 == "y");
+    }
+    var n1, n2;
+    assert(n1 == nil and n2 == nil);
+    for (var i, step = [0, 2]; i < 4; i = i + step) {}
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn unpacking_checks_the_count() {
+  assert!(
+    runtime_error("var a, b = [1, 2, 3];").contains("Can't unpack 3 values into 2 variables.")
+  );
+  assert!(runtime_error("fun f() { var a, b = [1]; } f();")
+    .contains("Can't unpack 1 values into 2 variables."));
+  assert!(runtime_error("var a, b = 1;").contains("Only lists can be unpacked."));
+}
+
+#[test]
+fn malformed_declarations_are_compile_errors() {
+  assert!(compile_error("var a, = [1];").contains("Expect variable name."));
+  assert!(compile_error("fun f() { return 1, ; }").contains("Expect expression."));
+  assert!(
+    compile_error("fun f() { var a, a = [1, 2]; }").contains("Already a variable with this name")
+  );
+  assert!(compile_error("fun f() { var a, b = [a]; }")
+    .contains("Can't read local variable in its own initializer."));
+}
@@ -0,0 +1,132 @@
+mod common;
+
+use common::compile_error;
+use rlox::{chunk::SourceLocation, compiler::resolution::DeclarationKind, utils::Init, vm::VM};
+
+fn at(line: usize, column: usize) -> SourceLocation {
+  SourceLocation { line, column }
+}
+
+#[test]
+fn declarations_record_kind_site_function_and_slot() {
+  let src = "var total = 0;\nfun add(a, b) {\n  var sum = a + b;\n  return sum;\n}\n";
+  let mut vm = VM::init();
+  vm.interpret(src.to_owned()).unwrap();
+  let declarations: Vec<_> = vm
+    .resolutions()
+    .declarations()
+    .iter()
+    .map(|d| (&*d.name, d.kind, d.location, &*d.function, d.slot))
+    .collect();
+  assert_eq!(
+    declarations,
+    [
+      ("total", DeclarationKind::Global, at(1, 5), "", None),
+      ("add", DeclarationKind::Global, at(2, 5), "", None),
+      ("a", DeclarationKind::Parameter, at(2, 9), "add", Some(1)),
+      ("b", DeclarationKind::Parameter, at(2, 12), "add", Some(2)),
+      ("sum", DeclarationKind::Local, at(3, 7), "add", Some(3)),
+    ]
+  );
+}
+
+#[test]
+fn references_go_to_their_definitions() {
+  let src = "fun f() { return later + x; }\nvar later = 1;\n{ var x = 2; print x + clock(); }\n";
+  let mut vm = VM::init();
+  vm.interpret(src.to_owned()).unwrap();
+  let resolutions = vm.resolutions();
+
+  // globals are linked once the whole script is parsed
+  let later = resolutions.definition_at(at(1, 20)).unwrap();
+  assert_eq!((&*later.name, later.location), ("later", at(2, 5)));
+  // `x` in `f` isn't the local declared later, but an undeclared global
+  assert!(resolutions.definition_at(at(1, 28)).is_none());
+  let x = resolutions.definition_at(at(3, 20)).unwrap();
+  assert_eq!((x.kind, x.location), (DeclarationKind::Local, at(3, 7)));
+  // natives aren't declared by the script
+  let clock = resolutions
+    .references()
+    .iter()
+    .find(|r| &*r.name == "clock");
+  assert_eq!(clock.unwrap().declaration, None);
+}
+
+#[test]
+fn duplicate_locals_note_where_they_are_declared() {
+  let src = "{\n  var a = 1;\n  var a = 2;\n}";
+  assert!(compile_error(src)
+    .contains("Already a variable with this name in this scope, declared at line 3."));
+}
+
+#[test]
+fn locals_are_found_by_slot_and_line() {
+  let src = "{\n  var a = 1;\n}\n{\n  var b = 2;\n}\nfun f(x) {\n  var y = x;\n}\n";
+  let mut vm = VM::init();
+  vm.interpret(src.to_owned()).unwrap();
+  let resolutions = vm.resolutions();
+  let name_at = |function, slot, line| {
+    resolutions
+      .local_at(function, slot, line)
+      .map(|local| (&*local.name, local.location.line))
+  };
+
+  // slot 1 of the script is reused by sibling blocks
+  assert_eq!(name_at("", 1, 2), Some(("a", 2)));
+  assert_eq!(name_at("", 1, 3), Some(("a", 2)));
+  assert_eq!(name_at("", 1, 5), Some(("b", 5)));
+  assert_eq!(name_at("f", 1, 8), Some(("x", 7)));
+  assert_eq!(name_at("f", 2, 8), Some(("y", 8)));
+  assert_eq!(name_at("", 1, 1), None);
+  assert_eq!(name_at("f", 3, 8), None);
+}
+
+#[test]
+fn undefined_locals_of_hand_compiled_chunks_are_reported_by_slot() {
+  use rlox::chunk::{Chunk, OpCode};
+  use rlox::vm::InterpretError;
+
+  let mut vm = VM::init();
+  vm.interpret("{ var a = 1; }".to_owned()).unwrap();
+  let mut chunk = Chunk::init();
+  chunk.write_chunk(OpCode::GetLocal.into(), 1);
+  chunk.write_chunk(1, 1);
+  chunk.write_chunk(OpCode::Return.into(), 1);
+  vm.rebind(chunk);
+  match vm.run() {
+    Err(InterpretError::Fault(message)) => {
+      assert!(
+        message.ends_with("Undefined local variable at slot `1`."),
+        "{}",
+        message
+      )
+    }
+    other => panic!("expect a fault, but got: {:?}", other),
+  }
+}
+
+#[test]
+fn undefined_locals_of_compiled_scripts_are_reported_by_name() {
+  use rlox::chunk::OpCode;
+  use rlox::compiler::plugin::{OperatorAction, OperatorRule};
+  use rlox::vm::InterpretError;
+
+  // a misbehaving operator, which drops the local below its operand
+  let mut vm = VM::init();
+  vm.register_operator(OperatorRule::prefix(
+    "~",
+    OperatorAction::OpCodes(vec![OpCode::Pop, OpCode::Pop]),
+  ))
+  .unwrap();
+  let src = "{\n  var a = 1;\n  ~0;\n  a;\n}\n";
+  match vm.interpret(src.to_owned()) {
+    Err(InterpretError::Fault(message)) => {
+      assert!(
+        message.ends_with("Undefined local variable `a` (declared at line 2)."),
+        "{}",
+        message
+      )
+    }
+    other => panic!("expect a fault, but got: {:?}", other),
+  }
+}
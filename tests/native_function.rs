@@ -0,0 +1,44 @@
+mod common;
+
+use common::{run, runtime_error};
+use rlox::{
+  object::{ObjNative, ObjTrait},
+  value::Value,
+};
+
+#[test]
+fn natives_are_ordinary_values() {
+  let src = r#"
+    assert(clock() >= 0);
+    var now = clock;
+    assert(now == clock);
+    assert(now() >= 0);
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn functions_compare_by_identity() {
+  let src = r#"
+    fun a() {}
+    fun b() {}
+    var c = a;
+    assert(a == c);
+    assert(a != b);
+    assert(a != clock);
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn native_arity_error() {
+  let message = runtime_error("clock(1);");
+  assert!(message.contains("Native function `clock` expected 0 arguments but got 1."));
+}
+
+#[test]
+fn native_display() {
+  let native = ObjNative::new("answer", 0, |_| Ok(Value::number_val(42.0)));
+  let value = Value::obj_val(native.cast_to_obj_ptr());
+  assert_eq!(format!("{}", value), "<native fn>");
+}
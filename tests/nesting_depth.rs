@@ -0,0 +1,56 @@
+mod common;
+
+use common::{compile_error, run};
+use rlox::{
+  compiler::{CompileOptions, NESTING_DEPTH_MAX},
+  utils::Init,
+  vm::{InterpretError, VM},
+};
+
+/// `depth` nested groupings around `1`.
+fn nested_groupings(depth: usize) -> String {
+  format!("{}1{};", "(".repeat(depth), ")".repeat(depth))
+}
+
+#[test]
+fn nesting_within_limit_compiles() {
+  run(&nested_groupings(250)).unwrap();
+  run(&format!("print {}true;", "!".repeat(100))).unwrap();
+  run(&format!("{}{}", "{".repeat(100), "}".repeat(100))).unwrap();
+}
+
+#[test]
+fn pathological_groupings_fail_cleanly() {
+  let message = compile_error(&nested_groupings(100_000));
+  assert!(
+    message.contains("Code is nested too deeply (At most: 256)."),
+    "{}",
+    message
+  );
+}
+
+#[test]
+fn pathological_blocks_and_statements_fail_cleanly() {
+  let message = compile_error(&"{".repeat(100_000));
+  assert!(message.contains("Code is nested too deeply (At most: 256)."));
+  let message = compile_error(&"if (true) ".repeat(100_000));
+  assert!(message.contains("Code is nested too deeply (At most: 256)."));
+  // spaced, as `--` is decrement
+  let message = compile_error(&format!("{}1;", "- ".repeat(100_000)));
+  assert!(message.contains("Code is nested too deeply (At most: 256)."));
+}
+
+#[test]
+fn nesting_depth_limit_is_configurable() {
+  let mut vm = VM::init();
+  vm.set_compile_options(CompileOptions {
+    max_nesting_depth: 8,
+    ..CompileOptions::init()
+  });
+  assert!(matches!(
+    vm.interpret(nested_groupings(8)),
+    Err(InterpretError::CompileError(_))
+  ));
+  assert!(vm.interpret(nested_groupings(4)).is_ok());
+  assert_eq!(CompileOptions::init().max_nesting_depth, NESTING_DEPTH_MAX);
+}
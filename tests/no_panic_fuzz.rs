@@ -0,0 +1,135 @@
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use rlox::{utils::Init, vm::VM};
+
+/// A tiny deterministic pseudo random generator (xorshift64).
+struct Rng(u64);
+
+impl Rng {
+  fn next(&mut self) -> u64 {
+    self.0 ^= self.0 << 13;
+    self.0 ^= self.0 >> 7;
+    self.0 ^= self.0 << 17;
+    self.0
+  }
+
+  fn below(&mut self, n: usize) -> usize {
+    (self.next() % n as u64) as usize
+  }
+}
+
+const VOCABULARY: &[&str] = &[
+  "(", ")", "{", "}", ",", ".", "-", "+", ";", "/", "*", "!", "!=", "=", "==", ">", ">=", "<",
+  "<=", "a", "b", "f", "C", "T", "this", "init", "\"s\"", "\"", "1", "2.5", "and", "class", "else",
+  "false", "fun", "if", "nil", "or", "print", "return", "true", "var", "delete", "is", "in",
+  "trait", "with", "get", "set", "clock", "$", "@", "é", "/*", "//", "\n", "[", "]", "?", ":",
+  "...", "div", "match", "=>", "_", "enum", "import", "++", "--", "\"a${", "}b\"", "}${",
+];
+
+const SEEDS: &[&str] = &[
+  "var a = 1; print a + 2;",
+  "fun f(a, b = 2) { return a * b; } print f(3);",
+  "class C { init(x) { this.x = x; } get y { return this.x; } } print C(1).y;",
+  "trait T { m() { return 1; } } class C with T {} print C().m();",
+  "{ var a = 1; { var a = 2; print a; } print a; }",
+  "class C {} var c = C(); c.f = 1; print \"f\" in c; delete c.f; print c is C;",
+  "if (1 < 2 and !false or nil) print 1; else print 2;",
+];
+
+/// Interpret the given source, asserting it doesn't panic.
+fn assert_no_panic(src: String) {
+  let result = catch_unwind(AssertUnwindSafe(|| {
+    let mut vm = VM::init();
+    let _ = vm.interpret(src.to_owned());
+  }));
+  assert!(result.is_ok(), "interpreter panicked on: {:?}", src);
+}
+
+#[test]
+fn random_token_soup_never_panics() {
+  let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+  for _ in 0..3000 {
+    let len = rng.below(40);
+    let src = (0..len)
+      .map(|_| VOCABULARY[rng.below(VOCABULARY.len())])
+      .collect::<Vec<_>>()
+      .join(" ");
+    assert_no_panic(src);
+  }
+}
+
+#[test]
+fn mutated_programs_never_panic() {
+  let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+  for _ in 0..3000 {
+    let mut src = SEEDS[rng.below(SEEDS.len())].as_bytes().to_vec();
+    for _ in 0..1 + rng.below(4) {
+      let at = rng.below(src.len());
+      match rng.below(3) {
+        0 => {
+          src.remove(at);
+        }
+        1 => src.insert(at, b"(){};.=!\"a1"[rng.below(11)]),
+        _ => {
+          let other = rng.below(src.len());
+          src.swap(at, other)
+        }
+      }
+    }
+    assert_no_panic(String::from_utf8_lossy(&src).into_owned());
+  }
+}
+
+#[test]
+fn invalid_bytecode_never_panics() {
+  use rlox::chunk::{Chunk, OpCode};
+
+  let mut rng = Rng(0xdead_beef_cafe_f00d);
+  for _ in 0..200 {
+    let mut chunk = Chunk::init();
+    chunk.add_constant(1.0.into());
+    for _ in 0..rng.below(16) {
+      // jumps are excluded, as a backward one may loop forever
+      let byte = match rng.below(256) as u8 {
+        byte if byte == OpCode::Jump as u8 || byte == OpCode::JumpIfFalse as u8 => {
+          OpCode::Nil as u8
+        }
+        byte => byte,
+      };
+      chunk.write_chunk(byte, 1);
+    }
+    let result = catch_unwind(AssertUnwindSafe(|| {
+      let mut vm = VM::init();
+      vm.rebind(chunk);
+      let _ = vm.run();
+    }));
+    assert!(result.is_ok(), "interpreter panicked on bytecode");
+  }
+}
+
+#[test]
+fn invalid_bytecode_is_fault() {
+  use rlox::{chunk::Chunk, vm::InterpretError};
+
+  let mut chunk = Chunk::init();
+  chunk.write_chunk(u8::MAX, 7);
+  let mut vm = VM::init();
+  vm.rebind(chunk);
+  match vm.run() {
+    Err(InterpretError::Fault(message)) => {
+      assert_eq!(message, "[line 7] in script: Unknown opcode `255`.")
+    }
+    other => panic!("expect a fault, but got: {:?}", other),
+  }
+
+  let mut chunk = Chunk::init();
+  chunk.write_chunk(rlox::chunk::OpCode::Constant as u8, 3);
+  let mut vm = VM::init();
+  vm.rebind(chunk);
+  match vm.run() {
+    Err(InterpretError::Fault(message)) => {
+      assert_eq!(message, "[line 3] in script: Unexpected end of bytecode.")
+    }
+    other => panic!("expect a fault, but got: {:?}", other),
+  }
+}
@@ -0,0 +1,69 @@
+mod common;
+
+use common::{run, runtime_error};
+use rlox::native::number::{fixed, precision};
+
+#[test]
+fn fixed_digits_after_the_decimal_point() {
+  assert_eq!(fixed(1.23456, 2), "1.23");
+  assert_eq!(fixed(2.0, 3), "2.000");
+  assert_eq!(fixed(1234.5678, 0), "1235");
+  assert_eq!(fixed(-0.125, 1), "-0.1");
+  assert_eq!(fixed(2.5, 0), "2");
+  assert_eq!(fixed(1e21, 1), "1000000000000000000000.0");
+  assert_eq!(fixed(f64::INFINITY, 2), "inf");
+  assert_eq!(fixed(f64::NAN, 2), "NaN");
+}
+
+#[test]
+fn precision_switches_to_exponential_notation() {
+  assert_eq!(precision(1.23456, 3), "1.23");
+  assert_eq!(precision(0.000123, 2), "0.00012");
+  assert_eq!(precision(1.0, 3), "1.00");
+  assert_eq!(precision(123.0, 3), "123");
+  assert_eq!(precision(123456.0, 2), "1.2e5");
+  assert_eq!(precision(9.99, 2), "10");
+  assert_eq!(precision(99.9, 2), "1.0e2");
+  assert_eq!(precision(0.0000001, 1), "1e-7");
+  assert_eq!(precision(0.0, 2), "0.0");
+  assert_eq!(precision(f64::NEG_INFINITY, 2), "-inf");
+}
+
+#[test]
+fn integers_in_other_radixes() {
+  let src = r#"
+    assert(to_hex(255) == "ff");
+    assert(to_hex(-4096) == "-1000");
+    assert(to_oct(8) == "10");
+    assert(to_bin(5) == "101");
+    assert(to_bin(0) == "0");
+    assert(to_hex(9007199254740992) == "20000000000000");
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn formatted_strings_could_be_parsed_back() {
+  let src = r#"
+    assert(to_fixed(1 / 3, 4) == "0.3333");
+    assert(parse_num(to_fixed(1 / 3, 4)) == 0.3333);
+    assert(parse_num(to_precision(123456, 2)) == 120000);
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn invalid_arguments_are_runtime_errors() {
+  assert!(runtime_error(r#"to_fixed("1", 2);"#).contains("`to_fixed` expected a number."));
+  assert!(runtime_error("to_fixed(1, -1);")
+    .contains("`to_fixed` expected an integer count of digits from 0 to 100."));
+  assert!(runtime_error("to_fixed(1, 101);").contains("from 0 to 100"));
+  assert!(runtime_error("to_precision(1, 0);")
+    .contains("`to_precision` expected an integer count of digits from 1 to 100."));
+  assert!(runtime_error("to_precision(1, 1.5);").contains("from 1 to 100"));
+  assert!(
+    runtime_error("to_hex(1.5);").contains("`to_hex` expected an integer between -2^53 and 2^53.")
+  );
+  assert!(runtime_error("to_bin(9007199254740994);").contains("`to_bin` expected an integer"));
+  assert!(runtime_error("to_oct(nil);").contains("`to_oct` expected a number."));
+}
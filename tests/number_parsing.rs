@@ -0,0 +1,67 @@
+mod common;
+
+use common::{run, runtime_error};
+use rlox::native::number::parse;
+
+#[test]
+fn decimals_are_parsed() {
+  assert_eq!(parse("42"), Some(42.0));
+  assert_eq!(parse("-1.5"), Some(-1.5));
+  assert_eq!(parse("+1.5"), Some(1.5));
+  assert_eq!(parse("1."), Some(1.0));
+  assert_eq!(parse(".5"), Some(0.5));
+  assert_eq!(parse("1.5e3"), Some(1500.0));
+  assert_eq!(parse("25E-2"), Some(0.25));
+  assert_eq!(parse("007"), Some(7.0));
+}
+
+#[test]
+fn surrounding_whitespace_is_ignored() {
+  assert_eq!(parse("  3.25\n"), Some(3.25));
+  assert_eq!(parse("\t+8 "), Some(8.0));
+  assert_eq!(parse("+ 8"), None);
+}
+
+#[test]
+fn special_values_are_case_sensitive() {
+  assert_eq!(parse("Infinity"), Some(f64::INFINITY));
+  assert_eq!(parse("+Infinity"), Some(f64::INFINITY));
+  assert_eq!(parse("-Infinity"), Some(f64::NEG_INFINITY));
+  assert_eq!(parse("inf"), Some(f64::INFINITY));
+  assert_eq!(parse("-inf"), Some(f64::NEG_INFINITY));
+  assert!(parse("NaN").unwrap().is_nan());
+  for s in ["infinity", "INF", "nan", "-NaN", "Infinityx"] {
+    assert_eq!(parse(s), None, "{}", s);
+  }
+}
+
+#[test]
+fn locale_formatted_numbers_are_rejected() {
+  for s in [
+    "1,5", "1.000,5", "1 000", "1_000", "1'000", "٣", "0x10", "1e", "e3", ".", "+", "", "  ",
+    "--1", "1.2.3",
+  ] {
+    assert_eq!(parse(s), None, "{:?}", s);
+  }
+}
+
+#[test]
+fn printed_numbers_round_trip() {
+  let src = r#"
+    var numbers = 0;
+    fun check(x) { assert(parse_num(str(x)) == x); numbers = numbers + 1; }
+    check(0); check(-0.5); check(1.25); check(1000000); check(0.1 + 0.2);
+    check(1 / 0); check(-1 / 0);
+    var nan = parse_num(str(0 / 0));
+    assert(nan != nan);
+    assert(str(1234567.5) == "1234567.5");
+    assert(numbers == 7);
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn invalid_strings_are_nil() {
+  run(r#"assert(parse_num("1,5") == nil); assert(parse_num(" -2 ") == -2);"#).unwrap();
+  assert!(runtime_error("parse_num(1);").contains("`parse_num` expected a string."));
+}
@@ -0,0 +1,74 @@
+use rlox::{DiagnosticKind, Source, Value, Vm};
+
+/// Messages of warnings of compiling the source.
+fn warnings_of(src: &str) -> Vec<String> {
+  let mut vm = Vm::new();
+  let script = vm.compile(&Source::new(src)).unwrap();
+  script
+    .warnings()
+    .iter()
+    .map(|warning| {
+      assert_eq!(warning.kind(), DiagnosticKind::Warning);
+      warning.message().to_owned()
+    })
+    .collect()
+}
+
+#[test]
+fn exact_literals_are_not_warned() {
+  for literal in [
+    "0",
+    "42",
+    "9007199254740992",
+    "1152921504606846976",
+    "100000000000000000000",
+    "0.1",
+    "0.30000000000000004",
+    "1.50",
+    "007.25",
+    "3.141592653589793",
+  ] {
+    assert_eq!(
+      warnings_of(&format!("{};", literal)),
+      Vec::<String>::new(),
+      "{}",
+      literal
+    );
+  }
+}
+
+#[test]
+fn huge_integers_are_warned_with_the_rounded_value() {
+  assert_eq!(
+    warnings_of("var id = 9007199254740993;"),
+    ["[line 1] Warning at '9007199254740993': Number literal can't be represented exactly, it's rounded to `9007199254740992`."]
+  );
+  let warnings = warnings_of("print 12345678901234567890123.0;");
+  assert_eq!(warnings.len(), 1);
+  assert!(warnings[0].contains("rounded to `12345678901234568000000`"));
+}
+
+#[test]
+fn extra_fraction_digits_are_warned() {
+  let warnings = warnings_of("print 0.10000000000000000001;");
+  assert_eq!(warnings.len(), 1);
+  assert!(warnings[0].ends_with("it's rounded to `0.1`."));
+}
+
+#[test]
+fn overflowing_literals_are_warned() {
+  let src = format!("1{};", "0".repeat(400));
+  let warnings = warnings_of(&src);
+  assert_eq!(warnings.len(), 1);
+  assert!(warnings[0].ends_with("it's rounded to `inf`."));
+}
+
+#[test]
+fn rounded_literals_still_compile() {
+  let mut vm = Vm::new();
+  let script = vm
+    .compile(&Source::new("9007199254740993 == 9007199254740992;"))
+    .unwrap();
+  assert_eq!(script.warnings().len(), 1);
+  assert_eq!(vm.run(&script).unwrap(), Some(Value::Bool(true)));
+}
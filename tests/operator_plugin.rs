@@ -0,0 +1,196 @@
+use rlox::{
+  chunk::OpCode,
+  compiler::{
+    plugin::{OperatorAction, OperatorRule},
+    Precedence,
+  },
+  utils::Init,
+  value::Value,
+  vm::{InterpretError, VM},
+};
+
+/// `matches(text, pattern)`: if `text` contains `pattern`.
+fn matches(args: &[Value]) -> Result<Value, InterpretError> {
+  let text = args[0].as_rust_string()?;
+  let pattern = args[1].as_rust_string()?;
+  Ok(Value::from(text.contains(pattern)))
+}
+
+/// `len(text)`: length of `text`.
+fn len(args: &[Value]) -> Result<Value, InterpretError> {
+  Ok(Value::from(args[0].as_rust_string()?.len() as f64))
+}
+
+/// A virtual machine with `~` (matches), `<>` (not equal) and prefix `#` (len) registered.
+fn dialect_vm() -> VM {
+  let mut vm = VM::init();
+  vm.define_native("matches", 2, matches);
+  vm.define_native("len", 1, len);
+  vm.register_operator(OperatorRule::infix(
+    "~",
+    Precedence::Comparison,
+    OperatorAction::Call("matches".into()),
+  ))
+  .unwrap();
+  vm.register_operator(OperatorRule::infix(
+    "<>",
+    Precedence::Equality,
+    OperatorAction::OpCodes(vec![OpCode::NotEqual]),
+  ))
+  .unwrap();
+  vm.register_operator(OperatorRule::prefix(
+    "#",
+    OperatorAction::Call("len".into()),
+  ))
+  .unwrap();
+  vm
+}
+
+fn result_of(vm: &mut VM, src: &str) -> Value {
+  vm.interpret(src.to_owned()).unwrap().unwrap()
+}
+
+#[test]
+fn infix_operator_calls_a_native() {
+  let mut vm = dialect_vm();
+  assert_eq!(result_of(&mut vm, r#""rlox" ~ "lox";"#), Value::from(true));
+  assert_eq!(
+    result_of(&mut vm, r#""rlox" ~ "clox";"#),
+    Value::from(false)
+  );
+  // `+` binds tighter than `~`, which binds tighter than `==`
+  assert_eq!(
+    result_of(&mut vm, r#""r" + "lox" ~ "rl" == true;"#),
+    Value::from(true)
+  );
+}
+
+#[test]
+fn infix_operator_emits_opcodes() {
+  let mut vm = dialect_vm();
+  assert_eq!(result_of(&mut vm, "1 <> 2;"), Value::from(true));
+  assert_eq!(result_of(&mut vm, "1 + 1 <> 2;"), Value::from(false));
+  // built-in operators sharing a prefix are still scanned as is
+  assert_eq!(result_of(&mut vm, "1 < 2;"), Value::from(true));
+  assert_eq!(result_of(&mut vm, "1 <= 2;"), Value::from(true));
+}
+
+#[test]
+fn prefix_operator_binds_as_unary() {
+  let mut vm = dialect_vm();
+  assert_eq!(result_of(&mut vm, r#"#"lox" * 2;"#), Value::from(6.0));
+  assert_eq!(result_of(&mut vm, r#"-#"lox";"#), Value::from(-3.0));
+}
+
+#[test]
+fn native_call_is_compiled_into_call_global() {
+  let mut vm = dialect_vm();
+  let disassembly = vm.disassemble_source(r#""a" ~ "b";"#.into()).unwrap();
+  assert!(disassembly.contains("=>CallGlobal"), "{}", disassembly);
+}
+
+#[test]
+fn undefined_callee_is_a_runtime_error() {
+  let mut vm = VM::init();
+  vm.register_operator(OperatorRule::infix(
+    "~",
+    Precedence::Comparison,
+    OperatorAction::Call("matches".into()),
+  ))
+  .unwrap();
+  match vm.interpret(r#""a" ~ "b";"#.into()) {
+    Err(InterpretError::RuntimeError(message)) => {
+      assert!(
+        message.contains("Undefined variable `matches`."),
+        "{}",
+        message
+      )
+    }
+    other => panic!("expect a runtime error, but got: {:?}", other),
+  }
+}
+
+#[test]
+fn unregistered_operator_is_unexpected() {
+  match VM::init().interpret(r#""a" ~ "b";"#.into()) {
+    Err(InterpretError::CompileError(message)) => {
+      assert!(message.contains("Unexpected character."), "{}", message)
+    }
+    other => panic!("expect a compile error, but got: {:?}", other),
+  }
+}
+
+#[test]
+fn registering_invalidates_cached_scripts() {
+  let mut vm = VM::init();
+  vm.define_native("matches", 2, matches);
+  let src = r#""rlox" ~ "lox";"#;
+  assert!(vm.interpret(src.to_owned()).is_err());
+  vm.register_operator(OperatorRule::infix(
+    "~",
+    Precedence::Comparison,
+    OperatorAction::Call("matches".into()),
+  ))
+  .unwrap();
+  assert_eq!(result_of(&mut vm, src), Value::from(true));
+}
+
+#[test]
+fn invalid_rules_are_rejected() {
+  let call = || Some(OperatorAction::Call("f".into()));
+  let rules = [
+    // reserved by built-in operators or comments
+    OperatorRule::infix("==", Precedence::Equality, OperatorAction::Call("f".into())),
+    OperatorRule::prefix("//", OperatorAction::Call("f".into())),
+    OperatorRule::infix("?", Precedence::Term, OperatorAction::Call("f".into())),
+    // not spelled by punctuation
+    OperatorRule::prefix("op", OperatorAction::Call("f".into())),
+    OperatorRule::prefix("", OperatorAction::Call("f".into())),
+    // no action
+    OperatorRule {
+      symbol: "~".into(),
+      precedence: Precedence::Term,
+      prefix: None,
+      infix: None,
+    },
+    // infix operators must have a precedence
+    OperatorRule {
+      symbol: "~".into(),
+      precedence: Precedence::None,
+      prefix: None,
+      infix: call(),
+    },
+    // opcodes with operands
+    OperatorRule::prefix("~", OperatorAction::OpCodes(vec![OpCode::Jump])),
+    OperatorRule::prefix("~", OperatorAction::OpCodes(vec![OpCode::GetLocal])),
+    OperatorRule::prefix("~", OperatorAction::OpCodes(vec![OpCode::Invoke])),
+    // opcodes leaving the frame, or changing handlers or upvalues of it
+    OperatorRule::prefix("~", OperatorAction::OpCodes(vec![OpCode::Return])),
+    OperatorRule::prefix("~", OperatorAction::OpCodes(vec![OpCode::PopHandler])),
+  ];
+  let mut vm = VM::init();
+  for rule in rules {
+    assert!(
+      matches!(
+        vm.register_operator(rule.clone()),
+        Err(InterpretError::CompileError(_))
+      ),
+      "{:?}",
+      rule
+    );
+  }
+  assert!(vm.plugins().operator("~").is_none());
+}
+
+#[test]
+fn operators_could_emit_any_opcode_without_operands() {
+  let mut vm = VM::init();
+  vm.register_operator(OperatorRule::infix(
+    "@",
+    Precedence::Call,
+    OperatorAction::OpCodes(vec![OpCode::GetIndex]),
+  ))
+  .unwrap();
+  let value = vm.interpret("[1, 2, 3] @ 1;".to_owned()).unwrap().unwrap();
+  assert_eq!(value.as_number(), 2.0);
+}
@@ -0,0 +1,221 @@
+use rlox::{
+  compiler::{CompileOptions, OptLevel},
+  utils::Init,
+  value::Value,
+  vm::{InterpretError, VM},
+};
+
+const LEVELS: [OptLevel; 3] = [OptLevel::O0, OptLevel::O1, OptLevel::O2];
+
+fn vm_at(opt_level: OptLevel) -> VM {
+  let mut vm = VM::init();
+  vm.set_compile_options(CompileOptions {
+    opt_level,
+    ..CompileOptions::init()
+  });
+  vm
+}
+
+/// Instructions of the script (and all functions in it), without offsets and lines.
+fn listing(src: &str, opt_level: OptLevel) -> Vec<String> {
+  let disassembly = vm_at(opt_level).disassemble_source(src.to_owned()).unwrap();
+  disassembly
+    .lines()
+    .filter(|line| !line.is_empty() && !line.starts_with("-*-"))
+    .map(|line| line[10..].split_whitespace().collect::<Vec<_>>().join(" "))
+    .collect()
+}
+
+/// Line diff of listings at two levels (by longest common subsequence),
+/// removed instructions are prefixed by `-`, added ones by `+`.
+fn diff(src: &str, from: OptLevel, to: OptLevel) -> Vec<String> {
+  let (from, to) = (listing(src, from), listing(src, to));
+  // lcs[i][j] => length of LCS of from[i..] and to[j..]
+  let mut lcs = vec![vec![0; to.len() + 1]; from.len() + 1];
+  for i in (0..from.len()).rev() {
+    for j in (0..to.len()).rev() {
+      lcs[i][j] = if from[i] == to[j] {
+        lcs[i + 1][j + 1] + 1
+      } else {
+        lcs[i + 1][j].max(lcs[i][j + 1])
+      };
+    }
+  }
+  let (mut i, mut j, mut diff) = (0, 0, vec![]);
+  while i < from.len() || j < to.len() {
+    if i < from.len() && j < to.len() && from[i] == to[j] {
+      (i, j) = (i + 1, j + 1);
+    } else if j == to.len() || (i < from.len() && lcs[i + 1][j] >= lcs[i][j + 1]) {
+      diff.push(format!("- {}", from[i]));
+      i += 1;
+    } else {
+      diff.push(format!("+ {}", to[j]));
+      j += 1;
+    }
+  }
+  diff
+}
+
+#[test]
+fn o0_keeps_bytecode_as_emitted() {
+  assert_eq!(
+    listing("print 1 + 2 * 3;", OptLevel::O0),
+    [
+      "</Constant/> 0 :: 1",
+      "</Constant/> 1 :: 2",
+      "</Constant/> 2 :: 3",
+      "@ Multiply",
+      "@ Add",
+      "..Print",
+      "</Nil/>",
+      "..Return",
+    ]
+  );
+}
+
+#[test]
+fn o1_folds_constants() {
+  assert_eq!(
+    diff("print 1 + 2 * 3;", OptLevel::O0, OptLevel::O1),
+    [
+      "- </Constant/> 0 :: 1",
+      "- </Constant/> 1 :: 2",
+      "- </Constant/> 2 :: 3",
+      "- @ Multiply",
+      "- @ Add",
+      "+ </Constant/> 0 :: 7",
+    ]
+  );
+  assert_eq!(
+    listing(
+      r#"print !(1 < 2) == false; print "a" + "b"; print -(4 / 2);"#,
+      OptLevel::O1
+    ),
+    [
+      "</True/>",
+      "..Print",
+      "</Constant/> 0 :: \"ab\"",
+      "..Print",
+      "</Constant/> 1 :: -2",
+      "..Print",
+      "</Nil/>",
+      "..Return",
+    ]
+  );
+}
+
+#[test]
+fn o1_leaves_runtime_errors_to_the_vm() {
+  for src in [r#"print -"a";"#, r#"print 1 + "a";"#, "print !0;"] {
+    assert_eq!(diff(src, OptLevel::O0, OptLevel::O1), Vec::<String>::new());
+  }
+}
+
+#[test]
+fn o1_deduplicates_constants() {
+  // strings are already shared by the compiler, only numbers are left to O1
+  let src = r#"var a = 1; var b = 1; print "a"; print a + b;"#;
+  assert_eq!(
+    diff(src, OptLevel::O0, OptLevel::O1),
+    ["- </Constant/> 3 :: 1", "+ </Constant/> 1 :: 1"]
+  );
+}
+
+#[test]
+fn o2_removes_dead_pushes_and_fuses_pops() {
+  let src = "{ var a = clock(); var b = clock(); 1; a; print b; }";
+  assert_eq!(
+    diff(src, OptLevel::O1, OptLevel::O2),
+    [
+      "- </Constant/> 1 :: 1",
+      "- ..Pop",
+      "- <-GetLocal 1(slot)",
+      "- ..Pop",
+      "- ..Pop",
+      "- ..Pop",
+      "+ ..PopN 2(values)",
+    ]
+  );
+}
+
+#[test]
+fn o2_threads_jumps_to_jumps() {
+  let src = "var x = clock(); if (x and x) print x;";
+  assert_eq!(
+    diff(src, OptLevel::O1, OptLevel::O2),
+    ["- =>JumpIfFalse 8 -> 14", "+ =>JumpIfFalse 8 -> 24",]
+  );
+}
+
+#[test]
+fn loops_are_optimized_in_place() {
+  let src = "var i = 0; while (i < 2 + 3) { 1; i = i + 1; }";
+  assert_eq!(
+    listing(src, OptLevel::O2),
+    [
+      "</Constant/> 1 :: 0",
+      ":=DefineGlobal 0 :: \"i\"",
+      "<-GetGlobal 0 :: \"i\"",
+      "</Constant/> 3 :: 5",
+      "@ Less",
+      "=>JumpIfFalse 9 -> 24",
+      "..Pop",
+      "<-GetGlobal 0 :: \"i\"",
+      "</Constant/> 2 :: 1",
+      "@ Add",
+      "->SetGlobal 0 :: \"i\"",
+      "..Pop",
+      "=>Loop 21 -> 4",
+      "..Pop",
+      "</Nil/>",
+      "..Return",
+    ]
+  );
+}
+
+#[test]
+fn each_level_runs_the_same() {
+  let programs = [
+    "1 + 2 * 3 - 4 / 8;",
+    "-7 div 2 * 10 + 7 div 2;",
+    r#""con" + "cat" == "concat";"#,
+    "var a = 1; { var b = 2; var c = 3; a = b * c; } a;",
+    "fun f(x, y = 10) { if (x > 1 and true) return x + y; return nil; } f(2);",
+    "fun f(x) { if (x) { 1; } else { 2; } var y = !x; return y or false; } f(nil);",
+    "class A { init(x) { this.x = x; } get() { return this.x * 2; } } A(21).get();",
+    "var s = 0; if (!(1 > 2)) s = 1; else s = 2; s;",
+    "-(-1);",
+    "var i = 0; var s = 0; while (i < 2 + 3) { s = s + i; i = i + 1; } s;",
+    "fun f(n) { var s = 0; while (n > 0) { var m = n; while (m > 0) m = m - 1; s = s + n; n = n - 1; } return s; } f(4);",
+    "var s = 0; for (var i = 0; i < 2 * 3; i = i + 1) for (var j = i; j > 0; j = j - 1) s = s + j; s;",
+    "fun f() { var s = 0; for (var i = 0; i < 4; i++) s = s + i++ + --i; return s; } f();",
+  ];
+  for src in programs {
+    let results = LEVELS
+      .map(|level| vm_at(level).interpret(src.to_owned()).unwrap())
+      .to_vec();
+    assert!(results[0].is_some(), "{}", src);
+    assert_eq!(results[1], results[0], "{}", src);
+    assert_eq!(results[2], results[0], "{}", src);
+  }
+}
+
+#[test]
+fn each_level_fails_the_same() {
+  for src in ["var a = 1;\nprint -\"a\";", "var a = 1 + nil;", "print !1;"] {
+    let errors = LEVELS.map(|level| match vm_at(level).interpret(src.to_owned()) {
+      Err(InterpretError::RuntimeError(message)) => message,
+      other => panic!("expect a runtime error, but got: {:?}", other),
+    });
+    assert_eq!(errors[1], errors[0]);
+    assert_eq!(errors[2], errors[0]);
+  }
+}
+
+#[test]
+fn folded_result_is_a_fresh_value() {
+  assert_eq!(
+    vm_at(OptLevel::O2).interpret("2 * 21;".into()).unwrap(),
+    Some(Value::from(42.0))
+  );
+}
@@ -0,0 +1,48 @@
+use rlox::{object::ObjString, table::Table, utils::Init, value::Value, vm::VM};
+
+fn key(name: &str) -> ObjString {
+  ObjString::from(name.to_owned())
+}
+
+fn keys(table: &Table) -> Vec<String> {
+  table.iter().map(|(key, _)| key.to_string()).collect()
+}
+
+#[test]
+fn table_iterates_in_insertion_order() {
+  let mut table = Table::default();
+  for name in ["zeta", "alpha", "mid", "beta"] {
+    table.set(key(name), Value::nil_val());
+  }
+  assert_eq!(keys(&table), ["zeta", "alpha", "mid", "beta"]);
+
+  // overwritten keys keep their position, re-inserted ones move to the end
+  table.set(key("alpha"), Value::from(1.0));
+  table.remove(&key("zeta"));
+  table.set(key("zeta"), Value::from(2.0));
+  assert_eq!(keys(&table), ["alpha", "mid", "beta", "zeta"]);
+  assert_eq!(table.get(&key("alpha")), Some(&Value::from(1.0)));
+  assert_eq!(table.len(), 4);
+}
+
+#[test]
+fn globals_are_dumped_in_definition_order() {
+  let mut vm = VM::init();
+  let src = r#"
+    var zebra = 1;
+    fun apple() {}
+    class Mango {}
+    var banana = "b";
+    zebra = 3;
+  "#;
+  vm.interpret(src.to_owned()).unwrap();
+  assert_eq!(
+    vm.dump_globals(),
+    "zebra = 3\napple = <fn apple>\nMango = Mango\nbanana = \"b\"\n"
+  );
+  // natives come first (and are excluded from dumps)
+  assert_eq!(
+    vm.globals().iter().next().map(|(name, _)| name.to_string()),
+    Some("clock".to_owned())
+  );
+}
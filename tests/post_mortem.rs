@@ -0,0 +1,105 @@
+use rlox::{
+  utils::Init,
+  vm::{InterpretError, VM},
+  DiagnosticKind, Source, Value, Vm,
+};
+
+const SRC: &str = "fun scale(x) {
+  var doubled = x * 2;
+  return doubled + nil;
+}
+scale(21);";
+
+/// A virtual machine keeping failed runs, which ran the source into its runtime error.
+fn failed(src: &str) -> Vm {
+  let mut vm = Vm::new();
+  vm.set_post_mortem(true);
+  let error = vm.eval(&Source::new(src)).unwrap_err();
+  assert_eq!(error.kind(), DiagnosticKind::RuntimeError);
+  vm
+}
+
+#[test]
+fn locals_of_the_failed_call_are_kept() {
+  let mut vm = failed(SRC);
+  let debugger = vm.debugger();
+  assert_eq!(
+    debugger.locals(0),
+    [
+      ("x".to_owned(), Value::Number(21.0)),
+      ("doubled".to_owned(), Value::Number(42.0)),
+    ]
+  );
+  assert!(debugger
+    .failure()
+    .unwrap()
+    .contains("Operands must be 2 numbers or 2 strings."));
+}
+
+#[test]
+fn failing_instruction_is_disassembled() {
+  let mut vm = failed(SRC);
+  let instruction = vm.debugger().failing_instruction().unwrap();
+  assert!(instruction.contains("@ Add"), "{}", instruction);
+}
+
+#[test]
+fn watches_are_evaluated_against_the_failed_run() {
+  let mut vm = failed(SRC);
+  vm.add_watch("doubled + x").unwrap();
+  let watches = vm.debugger().watches();
+  assert_eq!(watches[0].1.as_ref().unwrap(), &Value::Number(63.0));
+  assert!(
+    vm.debugger().failure().is_some(),
+    "watching keeps the failed run"
+  );
+}
+
+#[test]
+fn failed_runs_never_continue() {
+  let mut vm = VM::init();
+  vm.set_post_mortem(true);
+  vm.disassemble_source(SRC.to_owned()).unwrap();
+  let first = vm.run().unwrap_err().into_message();
+  assert!(!vm.is_idle());
+  assert_eq!(vm.run().unwrap_err().into_message(), first);
+  assert_eq!(vm.frame_locals(0).len(), 2);
+}
+
+#[test]
+fn next_script_discards_the_failed_run() {
+  let mut vm = VM::init();
+  vm.set_post_mortem(true);
+  assert!(matches!(
+    vm.interpret(SRC.to_owned()),
+    Err(InterpretError::RuntimeError(_))
+  ));
+  assert_eq!(
+    vm.interpret("1 + 2;".to_owned())
+      .unwrap()
+      .unwrap()
+      .as_number(),
+    3.0
+  );
+  assert!(vm.failure().is_none());
+  assert!(vm.is_idle());
+}
+
+#[test]
+fn failed_runs_are_discarded_by_default() {
+  let mut vm = VM::init();
+  assert!(vm.interpret(SRC.to_owned()).is_err());
+  assert!(vm.is_idle());
+  assert!(vm.failure().is_none());
+  assert!(vm.failing_instruction().is_none());
+}
+
+#[test]
+fn caught_errors_are_not_kept() {
+  let mut vm = VM::init();
+  vm.set_post_mortem(true);
+  vm.interpret("try { nil + 1; } catch (e) { print e; }".to_owned())
+    .unwrap();
+  assert!(vm.failure().is_none());
+  assert!(vm.is_idle());
+}
@@ -0,0 +1,60 @@
+//! Only the public API (re-exported at the root) is used here.
+
+use rlox::{Capabilities, DiagnosticKind, Source, Value, Vm};
+
+#[test]
+fn compiled_scripts_run_again_without_compiling() {
+  let mut vm = Vm::new();
+  let script = vm
+    .compile(&Source::new(
+      "var n = 1; fun add(a, b) { return a + b; } add(n, 2);",
+    ))
+    .unwrap();
+  assert!(script.has_result() && script.warnings().is_empty());
+  assert_eq!(vm.run(&script).unwrap(), Some(Value::Number(3.0)));
+  assert_eq!(vm.run(&script).unwrap(), Some(Value::Number(3.0)));
+
+  let debugger = vm.debugger();
+  assert!(debugger.disassemble(&script).contains("add"));
+  assert!(debugger.globals().starts_with("n = 1\n"));
+  assert!(debugger.instruction_count() > 0 && debugger.peak_stack_size() > 0);
+}
+
+#[test]
+fn values_are_copied_out_of_the_vm() {
+  let mut vm = Vm::new();
+  vm.eval(&"var s = \"a\" + \"b\"; class Point {} var p = Point();".into())
+    .unwrap();
+  assert_eq!(vm.global("s"), Some(Value::String("ab".to_owned())));
+  assert_eq!(vm.global("missing"), None);
+  match vm.global("p").unwrap() {
+    Value::Object { type_name, repr } => {
+      assert_eq!((type_name, repr.as_str()), ("instance", "Point instance"))
+    }
+    other => panic!("unexpected value: {:?}", other),
+  }
+  assert_eq!(vm.eval(&"nil;".into()).unwrap(), Some(Value::Nil));
+  assert_eq!(vm.eval(&"print 1;".into()).unwrap(), None);
+  assert_eq!(Value::Number(2.5).to_string(), "2.5");
+}
+
+#[test]
+fn errors_are_reported_as_diagnostics() {
+  let mut vm = Vm::new();
+  let error = vm
+    .compile(&Source::named("bad.lox", "var = 1;"))
+    .unwrap_err();
+  assert_eq!(error.kind(), DiagnosticKind::CompileError);
+  assert!(error.is_error() && error.message().contains("bad.lox"));
+
+  let error = vm.eval(&"-nil;".into()).unwrap_err();
+  assert_eq!(error.kind(), DiagnosticKind::RuntimeError);
+  assert_eq!(error.to_string(), error.message());
+
+  vm.set_capabilities(Capabilities::none());
+  let error = vm.eval(&"exec(\"ls\");".into()).unwrap_err();
+  assert_eq!(error.kind(), DiagnosticKind::RuntimeError);
+
+  let error = Source::from_file("no/such/file.lox").unwrap_err();
+  assert_eq!(error.message(), "Failed to read file `no/such/file.lox`.");
+}
@@ -0,0 +1,88 @@
+#![cfg(feature = "regex")]
+
+mod common;
+
+use common::{run, runtime_error};
+use rlox::{object::ObjClass, utils::Init, vm::VM};
+
+#[test]
+fn first_match_with_captures() {
+  let src = r#"
+    var m = regex_match(r"(\w+)@(?<host>\w+)\.org", "mail: lox@crafting.org");
+    assert(m.text == "lox@crafting.org");
+    assert(m.start == 6 and m.end == 22);
+    assert(len(m.groups) == 3);
+    assert(m.groups[0] == m.text);
+    assert(m.groups[1] == "lox");
+    assert(m.named.host == "crafting");
+    assert(m.groups[2] == m.named.host);
+    assert(regex_match("x", "abc") == nil);
+    assert(regex_match("(a)|b", "b").groups[1] == nil);
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn named_groups_never_replace_fields_of_matches() {
+  let src = r#"
+    var m = regex_match(r"(?<text>a)(?<start>b)(?<groups>c)", "xabc");
+    assert(m.text == "abc" and m.start == 1);
+    assert(m.named.text == "a" and m.named.start == "b" and m.named.groups == "c");
+    assert(len(m.groups) == 4);
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn all_matches_are_listed() {
+  let src = r#"
+    var all = regex_find_all(r"\d+", "1 22 333");
+    assert(len(all) == 3);
+    assert(all[0].text == "1");
+    assert(all[1].text == "22" and all[1].start == 2);
+    assert(all[2].text == "333");
+    assert(len(regex_find_all(r"\d", "none")) == 0);
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn matches_share_their_class() {
+  let mut vm = VM::init();
+  let mut class_of = |src: &str| {
+    let value = vm.interpret(src.to_owned()).unwrap().unwrap();
+    let instance = unsafe { value.as_instance().unwrap().as_ref() };
+    instance.class() as *const ObjClass
+  };
+  let first = class_of(r#"regex_match("a", "a");"#);
+  assert_eq!(first, class_of(r#"regex_match("b", "abc");"#));
+  assert_eq!(first, class_of(r#"regex_find_all("c", "cc")[1];"#));
+  let named = class_of(r#"regex_match("(?<x>a)", "a").named;"#);
+  assert_eq!(named, class_of(r#"regex_match("(?<y>b)", "b").named;"#));
+}
+
+#[test]
+fn replace_all_matches() {
+  let src = r#"
+    assert(regex_replace(r"\s+", " a  b   c ", "_") == "_a_b_c_");
+    assert(regex_replace(r"(\w+)=(?<v>\w+)", "a=1, b=2", "\${v}:$1") == "1:a, 2:b");
+    assert(regex_replace(r"(?<v>\w+)", "a", r"<${v}>") == "<a>");
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn invalid_pattern() {
+  let message = runtime_error(r#"regex_match("(", "");"#);
+  assert!(
+    message.contains("`regex_match` got an invalid pattern"),
+    "{}",
+    message
+  );
+  let message = runtime_error(r#"regex_replace("a", "a", nil);"#);
+  assert!(
+    message.contains("`regex_replace` expected a string."),
+    "{}",
+    message
+  );
+}
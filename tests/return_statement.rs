@@ -0,0 +1,59 @@
+mod common;
+
+use common::{compile_error, run};
+
+#[test]
+fn values_are_returned_to_callers() {
+  let src = r#"
+    fun square(n) { return n * n; }
+    fun nothing() { return; }
+    fun implicit() {}
+    assert(square(3) == 9);
+    assert(nothing() == nil);
+    assert(implicit() == nil);
+    assert(square(square(2)) + 1 == 17);
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn returns_unwind_blocks_and_loops() {
+  let src = r#"
+    fun first_even(limit) {
+      for (var i = 1; i < limit; i = i + 1) {
+        var doubled = i * 2;
+        { var unused = 0; if (i == 3) return doubled; }
+      }
+      return -1;
+    }
+    assert(first_even(10) == 6);
+    assert(first_even(2) == -1);
+    var after = "caller stack is intact";
+    assert(after == "caller stack is intact");
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn returned_closures_keep_captured_locals() {
+  let src = r#"
+    fun counter() {
+      var count = 0;
+      fun next() { count = count + 1; return count; }
+      return next;
+    }
+    var next = counter();
+    next();
+    assert(next() == 2);
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn returns_are_checked_by_context() {
+  assert!(compile_error("return 1;").contains("Can't return from top-level code."));
+  assert!(compile_error("{ return; }").contains("Can't return from top-level code."));
+  assert!(compile_error("class A { init() { return 1; } }")
+    .contains("Can't return a value from an initializer."));
+  assert!(compile_error("fun f() { return 1 }").contains("Expect `;` after return value."));
+}
@@ -0,0 +1,184 @@
+use std::time::Duration;
+
+use rlox::{
+  native::{sandbox::HOST_NATIVES, Capabilities},
+  utils::Init,
+  vm::{InterpretError, VM},
+  DiagnosticKind, SandboxProfile, Source, Value, Vm,
+};
+
+/// A virtual machine with the sandbox profile applied.
+fn sandboxed(profile: SandboxProfile) -> VM {
+  let mut vm = VM::init();
+  vm.set_sandbox(profile);
+  vm
+}
+
+#[test]
+fn profiles_are_named() {
+  for profile in SandboxProfile::ALL {
+    assert_eq!(SandboxProfile::from_name(profile.name()), Some(profile));
+    assert_eq!(profile.to_string(), profile.name());
+  }
+  assert_eq!(SandboxProfile::from_name("unsafe"), None);
+}
+
+#[test]
+fn pure_undefines_natives_of_the_host() {
+  let mut vm = sandboxed(SandboxProfile::Pure);
+  assert_eq!(vm.sandbox(), Some(SandboxProfile::Pure));
+  assert_eq!(vm.capabilities(), Capabilities::none());
+  assert_eq!(vm.timeout(), Some(Duration::from_secs(1)));
+  assert_eq!(vm.fuel(), Some(10_000_000));
+  assert_eq!(vm.heap_limit(), Some(16 << 20));
+  for name in HOST_NATIVES {
+    match vm.interpret(format!("{}();", name)) {
+      Err(InterpretError::RuntimeError(message)) => assert!(
+        message.contains(&format!("Undefined variable `{}`.", name)),
+        "{}",
+        message
+      ),
+      other => panic!("expect `{}` to be undefined, but got: {:?}", name, other),
+    }
+  }
+  assert!(vm.interpret("str(1) + type_of(nil);".to_owned()).is_ok());
+}
+
+#[test]
+fn pure_is_kept_by_deterministic_mode() {
+  let mut vm = sandboxed(SandboxProfile::Pure);
+  vm.set_deterministic(Some(0));
+  assert!(vm.interpret("clock();".to_owned()).is_err());
+}
+
+#[test]
+fn io_grants_sleep_but_not_exec() {
+  let mut vm = sandboxed(SandboxProfile::Io);
+  assert_eq!(vm.timeout(), Some(Duration::from_secs(10)));
+  assert!(vm.interpret("sleep(0); clock();".to_owned()).is_ok());
+  match vm.interpret(r#"exec("true", nil);"#.to_owned()) {
+    Err(InterpretError::RuntimeError(message)) => assert!(
+      message.contains("Native function `exec` needs the `exec` capability."),
+      "{}",
+      message
+    ),
+    other => panic!("expect a runtime error, but got: {:?}", other),
+  }
+}
+
+#[test]
+fn full_restores_everything() {
+  let mut vm = sandboxed(SandboxProfile::Pure);
+  vm.set_sandbox(SandboxProfile::Full);
+  assert_eq!(vm.capabilities(), Capabilities::all());
+  assert_eq!(vm.timeout(), None);
+  assert_eq!(vm.fuel(), None);
+  assert_eq!(vm.heap_limit(), None);
+  assert!(vm.interpret("clock(); random();".to_owned()).is_ok());
+}
+
+#[test]
+fn fuel_aborts_runaway_scripts() {
+  let mut vm = sandboxed(SandboxProfile::Pure);
+  vm.set_fuel(Some(1000));
+  let src = "var n = 0; try { while (true) n = n + 1; } catch (e) { print e; }";
+  match vm.interpret(src.to_owned()) {
+    Err(InterpretError::LimitExceeded(message)) => assert!(
+      message.contains("Script ran out of fuel after 1000 instructions."),
+      "{}",
+      message
+    ),
+    other => panic!("expect running out of fuel, but got: {:?}", other),
+  }
+  assert_eq!(vm.instruction_count(), 1000);
+  // each run has fuel of its own
+  assert!(vm.interpret("1 + 2;".to_owned()).is_ok());
+}
+
+#[test]
+fn heap_limit_aborts_runaway_scripts() {
+  let mut vm = sandboxed(SandboxProfile::Pure);
+  vm.set_fuel(None);
+  vm.set_heap_limit(Some(1 << 16));
+  let src = r#"var s = "ab"; try { while (true) s = s + s; } catch (e) { print e; }"#;
+  match vm.interpret(src.to_owned()) {
+    Err(InterpretError::LimitExceeded(message)) => assert!(
+      message.contains("Script allocated more than 65536 bytes of objects."),
+      "{}",
+      message
+    ),
+    other => panic!("expect exceeding the heap limit, but got: {:?}", other),
+  }
+  assert!(vm.interpret("[1, 2, 3];".to_owned()).is_ok());
+
+  let mut vm = Vm::sandboxed(SandboxProfile::Pure);
+  vm.set_fuel(Some(100));
+  let error = vm.eval(&Source::new("while (true) {}")).unwrap_err();
+  assert_eq!(error.kind(), DiagnosticKind::LimitExceeded);
+}
+
+#[test]
+fn sandboxed_facade_applies_the_profile() {
+  let mut vm = Vm::sandboxed(SandboxProfile::Pure);
+  assert_eq!(
+    vm.eval(&Source::new("1 + 2;")).unwrap(),
+    Some(Value::Number(3.0))
+  );
+  let error = vm.eval(&Source::new("random();")).unwrap_err();
+  assert_eq!(error.kind(), DiagnosticKind::RuntimeError);
+  assert!(vm.global("random").is_none());
+}
+
+/// A fresh temporary directory (named after the test) with the files, as `(path, source)`.
+fn project(name: &str, files: &[(&str, &str)]) -> std::path::PathBuf {
+  let dir = std::env::temp_dir().join(format!("rlox_sandbox_{}", name));
+  let _ = std::fs::remove_dir_all(&dir);
+  for (path, src) in files {
+    let path = dir.join(path);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, src).unwrap();
+  }
+  dir
+}
+
+fn assert_import_refused(result: Result<Option<rlox::value::Value>, InterpretError>, path: &str) {
+  match result {
+    Err(InterpretError::CompileError(message)) => assert!(
+      message.contains(&format!(
+        "Can't import module `{}` from files without the `files` capability",
+        path
+      )),
+      "{}",
+      message
+    ),
+    other => panic!("expect the import to be refused, but got: {:?}", other),
+  }
+}
+
+#[test]
+fn pure_refuses_relative_imports() {
+  let dir = project(
+    "relative",
+    &[
+      ("main.lox", "import \"secret\"; print secret;"),
+      ("secret.lox", "var secret = \"s3cr3t\";"),
+    ],
+  );
+  let main = dir.join("main.lox").display().to_string();
+  let mut vm = sandboxed(SandboxProfile::Pure);
+  assert_import_refused(vm.interpret_file(main.clone()), "secret.lox");
+  // the standard library is embedded, rather than read from the host
+  assert!(vm.interpret("import \"math\"; abs(-1);".to_owned()).is_ok());
+  let mut vm = sandboxed(SandboxProfile::Io);
+  assert!(vm.interpret_file(main).is_ok());
+}
+
+#[test]
+fn pure_refuses_imports_from_search_path() {
+  let dir = project("search_path", &[("shared.lox", "var shared = 42;")]);
+  std::env::set_var("RLOX_PATH", &dir);
+  let mut vm = sandboxed(SandboxProfile::Pure);
+  let result = vm.interpret("import \"shared\"; shared;".to_owned());
+  std::env::remove_var("RLOX_PATH");
+  assert_import_refused(result, "shared.lox");
+}
@@ -0,0 +1,91 @@
+use rlox::scanner::{Scanner, Token, TokenType, Trivia, TriviaKind};
+
+/// Scan all tokens (including `Eof`) of the source in trivia mode.
+fn tokens_of(src: &str) -> Vec<Token> {
+  let mut scanner = Scanner::bind_with_trivia(src.to_owned());
+  let mut tokens = vec![];
+  loop {
+    let token = scanner.scan_token();
+    let end = matches!(token.token_type(), TokenType::Eof | TokenType::Error);
+    tokens.push(token);
+    if end {
+      return tokens;
+    }
+  }
+}
+
+fn kinds(trivia: &[Trivia]) -> Vec<TriviaKind> {
+  trivia.iter().map(|t| t.kind).collect()
+}
+
+#[test]
+fn trivia_is_attached_to_the_next_token() {
+  let src = "// header\nvar a = 1; /* inline */ print a;\n";
+  let tokens = tokens_of(src);
+
+  let var = &tokens[0];
+  assert_eq!(var.token_type(), TokenType::Var);
+  assert_eq!(
+    kinds(var.leading_trivia()),
+    [TriviaKind::LineComment, TriviaKind::Newline]
+  );
+  assert_eq!(var.leading_trivia()[0].text, "// header");
+
+  let print = tokens
+    .iter()
+    .find(|t| t.token_type() == TokenType::Print)
+    .unwrap();
+  assert_eq!(
+    kinds(print.leading_trivia()),
+    [
+      TriviaKind::Whitespace,
+      TriviaKind::BlockComment,
+      TriviaKind::Whitespace
+    ]
+  );
+  let comment = &print.leading_trivia()[1];
+  assert_eq!(comment.text, "/* inline */");
+  assert_eq!((comment.line, comment.column), (2, 12));
+
+  // trailing trivia belongs to `Eof`
+  let eof = tokens.last().unwrap();
+  assert_eq!(eof.token_type(), TokenType::Eof);
+  assert_eq!(kinds(eof.leading_trivia()), [TriviaKind::Newline]);
+}
+
+#[test]
+fn trivia_and_lexemes_reproduce_the_source() {
+  let src = "class A {\n\t/* multi\n   line */\n  f() { return \"s\"; } // done\n}\r\n";
+  let rebuilt: String = tokens_of(src)
+    .iter()
+    .flat_map(|token| {
+      let trivia = token.leading_trivia().iter().map(|t| t.text.as_str());
+      trivia.chain([token.lexeme()])
+    })
+    .collect();
+  assert_eq!(rebuilt, src);
+}
+
+#[test]
+fn lines_are_counted_through_trivia() {
+  let tokens = tokens_of("/* a\nb */\n\n  x");
+  let x = &tokens[0];
+  assert_eq!((x.line(), x.column()), (4, 3));
+  assert_eq!(
+    kinds(x.leading_trivia()),
+    [
+      TriviaKind::BlockComment,
+      TriviaKind::Newline,
+      TriviaKind::Newline,
+      TriviaKind::Whitespace
+    ]
+  );
+}
+
+#[test]
+fn trivia_is_discarded_by_default() {
+  let mut scanner = Scanner::bind("// comment\n  x".to_owned());
+  let token = scanner.scan_token();
+  assert_eq!(token.lexeme(), "x");
+  assert!(token.leading_trivia().is_empty());
+}
@@ -0,0 +1,51 @@
+use rlox::{utils::Init, value::Value, vm::VM};
+
+/// Interpret the given source, then return its result.
+fn result_of(src: &str) -> Option<Value> {
+  VM::init().interpret(src.to_owned()).unwrap()
+}
+
+#[test]
+fn final_expression_statement_is_the_result() {
+  assert_eq!(result_of("1 + 2;"), Some(Value::from(3.0)));
+  assert_eq!(
+    result_of("var a = 1; a = a + 1; a * 10;"),
+    Some(Value::from(20.0))
+  );
+  assert_eq!(result_of("nil;"), Some(Value::nil_val()));
+}
+
+#[test]
+fn statements_have_no_result() {
+  assert_eq!(result_of("var a = 1;"), None);
+  assert_eq!(result_of("print 1;"), None);
+  assert_eq!(result_of("1; print 2;"), None);
+  assert_eq!(result_of("fun f() { return 1; }"), None);
+  assert_eq!(result_of(""), None);
+}
+
+#[test]
+fn nested_expression_statements_have_no_result() {
+  assert_eq!(result_of("{ 1; }"), None);
+  assert_eq!(result_of("if (true) 1; else 2;"), None);
+  assert_eq!(result_of("if (false) 1; else 2;"), None);
+}
+
+#[test]
+fn result_of_calls() {
+  let src = r#"
+    fun square(x) { return x * x; }
+    square(3) + 1;
+  "#;
+  assert_eq!(result_of(src), Some(Value::from(10.0)));
+}
+
+#[test]
+fn results_of_consecutive_lines() {
+  let mut vm = VM::init();
+  assert_eq!(vm.interpret("var a = 2;".into()).unwrap(), None);
+  assert_eq!(
+    vm.interpret("a * a;".into()).unwrap(),
+    Some(Value::from(4.0))
+  );
+}
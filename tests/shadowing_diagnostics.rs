@@ -0,0 +1,71 @@
+use rlox::{compiler::CompileOptions, utils::Init, vm::VM};
+
+/// Interpret the given source, then return the warnings reported.
+fn warnings_of(src: &str, warn_shadowing: bool) -> Vec<String> {
+  let mut vm = VM::init();
+  vm.set_compile_options(CompileOptions {
+    warn_shadowing,
+    ..CompileOptions::init()
+  });
+  vm.interpret(src.to_owned()).unwrap();
+  vm.warnings().to_vec()
+}
+
+#[test]
+fn shadowing_is_silent_by_default() {
+  let src = r#"
+    var a = 1;
+    { var a = 2; }
+  "#;
+  assert!(warnings_of(src, false).is_empty());
+}
+
+#[test]
+fn local_shadows_outer_local() {
+  let src = r#"
+    {
+      var a = 1;
+      {
+        var a = 2;
+      }
+    }
+  "#;
+  assert_eq!(
+    warnings_of(src, true),
+    ["[line 5] Warning at 'a': Local variable shadows local `a` declared at line 3."]
+  );
+}
+
+#[test]
+fn local_shadows_global() {
+  let src = r#"
+    var a = 1;
+    fun f(a) {
+      var b = a;
+    }
+  "#;
+  assert_eq!(
+    warnings_of(src, true),
+    ["[line 3] Warning at 'a': Local variable shadows global `a` declared at line 2."]
+  );
+}
+
+#[test]
+fn innermost_shadowed_local_is_located() {
+  let src = r#"
+    var a = 0;
+    {
+      var a = 1;
+      {
+        var a = 2;
+      }
+    }
+  "#;
+  assert_eq!(
+    warnings_of(src, true),
+    [
+      "[line 4] Warning at 'a': Local variable shadows global `a` declared at line 2.",
+      "[line 6] Warning at 'a': Local variable shadows local `a` declared at line 4.",
+    ]
+  );
+}
@@ -0,0 +1,71 @@
+mod common;
+
+use common::{compile_error, run, runtime_error};
+
+#[test]
+fn negative_indexes_count_from_the_end() {
+  let src = r#"
+    var xs = [1, 2, 3];
+    assert(xs[-1] == 3 and xs[-3] == 1);
+    xs[-1] = 30;
+    assert(xs[2] == 30);
+    var s = "héllo";
+    assert(s[1] == "é" and s[-1] == "o");
+    assert(len(s) == 5);
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn slices_copy_items_within_bounds() {
+  let src = r#"
+    var xs = [0, 1, 2, 3, 4];
+    assert(deep_equals(xs[1:3], [1, 2]));
+    assert(deep_equals(xs[:2], [0, 1]));
+    assert(deep_equals(xs[3:], [3, 4]));
+    assert(deep_equals(xs[-2:], [3, 4]));
+    assert(deep_equals(xs[:-1], [0, 1, 2, 3]));
+    assert(deep_equals(xs[2:2], []));
+    var copy = xs[:];
+    copy[0] = 100;
+    assert(xs[0] == 0 and len(copy) == 5);
+    var n = 2;
+    assert(deep_equals(xs[n - 1:n + 1], [1, 2]));
+    assert(deep_equals(xs[true ? 3 : 0:], [3, 4]));
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn strings_are_sliced_by_characters() {
+  let src = r#"
+    var s = "naïve café";
+    assert(s[:5] == "naïve");
+    assert(s[-4:] == "café");
+    assert(s[2:3] == "ï");
+    assert(s[3:3] == "");
+    assert(type_of(s[0]) == "string");
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn invalid_bounds_are_runtime_errors() {
+  assert!(runtime_error("[1, 2][1:3];").contains("List slice [1:3] is out of bounds for length 2."));
+  assert!(runtime_error("[1, 2][2:1];").contains("List slice [2:1] is out of bounds for length 2."));
+  assert!(runtime_error("[1, 2][-3:];").contains("List slice [-3:] is out of bounds for length 2."));
+  assert!(runtime_error("\"ab\"[:0.5];").contains("String slice bounds must be integers."));
+  assert!(runtime_error("\"ab\"[-3];").contains("String index -3 is out of bounds for length 2."));
+  assert!(runtime_error("\"ab\"[nil];").contains("String index must be an integer."));
+  assert!(runtime_error("var n = 1; n[:];").contains("Only lists and strings can be sliced."));
+  assert!(
+    runtime_error("var s = \"ab\"; s[0] = \"c\";").contains("Only items of lists can be assigned.")
+  );
+}
+
+#[test]
+fn malformed_slices_are_compile_errors() {
+  assert!(compile_error("var xs = [1]; xs[0:1;").contains("Expect `]` after slice."));
+  assert!(compile_error("var xs = [1]; xs[0:1] = 2;").contains("Invalid assignment target."));
+  assert!(compile_error("var xs = [1]; xs[0:1:2];").contains("Expect `]` after slice."));
+}
@@ -0,0 +1,84 @@
+use rlox::{
+  chunk::{Chunk, SourceLocation},
+  utils::Init,
+  vm::{InterpretError, VM},
+};
+use std::path::PathBuf;
+
+/// Write the source into a temporary file, named after the test.
+fn script(name: &str, src: &str) -> PathBuf {
+  let path = std::env::temp_dir().join(format!("rlox_source_map_{}.lox", name));
+  std::fs::write(&path, src).unwrap();
+  path
+}
+
+fn interpret_file(name: &str, src: &str) -> (String, Result<(), InterpretError>) {
+  let path = script(name, src);
+  let mut vm = VM::init();
+  let result = vm.interpret_file(path.to_string_lossy().into()).map(|_| ());
+  std::fs::remove_file(&path).unwrap();
+  (path.to_string_lossy().into(), result)
+}
+
+#[test]
+fn runtime_error_is_located_by_file_line_and_column() {
+  let src = "fun f() {\n  return 1 + nil;\n}\nf();\n";
+  let (path, result) = interpret_file("runtime", src);
+  match result {
+    Err(InterpretError::RuntimeError(message)) => {
+      assert!(message.starts_with(&format!("[{}:2:", path)), "{}", message);
+      assert!(message.contains("] in f: "), "{}", message);
+      assert!(message.contains(&format!("[{}:4:", path)), "{}", message);
+    }
+    other => panic!("expect a runtime error, but got: {:?}", other),
+  }
+}
+
+#[test]
+fn compile_error_is_located_by_file_line_and_column() {
+  let (path, result) = interpret_file("compile", "var a = 1;\n  var = 2;\n");
+  match result {
+    Err(InterpretError::CompileError(message)) => {
+      assert_eq!(
+        message,
+        format!("[{}:2:7] Error at '=': Expect variable name.", path)
+      );
+    }
+    other => panic!("expect a compile error, but got: {:?}", other),
+  }
+}
+
+#[test]
+fn string_source_keeps_line_only_diagnostics() {
+  let mut vm = VM::init();
+  match vm.interpret("1 + nil;".into()) {
+    Err(InterpretError::RuntimeError(message)) => {
+      assert!(message.starts_with("[line 1] in script: "), "{}", message)
+    }
+    other => panic!("expect a runtime error, but got: {:?}", other),
+  }
+}
+
+#[test]
+fn missing_file_is_reported() {
+  let mut vm = VM::init();
+  let result = vm.interpret_file("/nonexistent/rlox/script.lox".into());
+  assert!(matches!(result, Err(InterpretError::CompileError(_))));
+}
+
+#[test]
+fn debug_info_records_each_byte() {
+  let mut chunk = Chunk::init();
+  chunk.write_chunk_at(0, SourceLocation { line: 1, column: 5 });
+  chunk.write_chunk(1, 3);
+  let debug_info = chunk.debug_info();
+  assert_eq!(debug_info.file(), None);
+  assert_eq!(
+    debug_info.location(0),
+    SourceLocation { line: 1, column: 5 }
+  );
+  assert_eq!(debug_info.location(1).line, 3);
+  // out of range offsets fall back to the last location
+  assert_eq!(debug_info.location(7).line, 3);
+  assert_eq!(debug_info.describe(0), "line 1");
+}
@@ -0,0 +1,69 @@
+mod common;
+
+use common::{compile_error, run, runtime_error};
+
+#[test]
+fn lists_are_spread_into_list_literals() {
+  let src = r#"
+    var rest = [2, 3];
+    assert(deep_equals([1, ...rest], [1, 2, 3]));
+    assert(deep_equals([...rest, 4, 5], [2, 3, 4, 5]));
+    assert(deep_equals([0, ...rest, 4, ...rest], [0, 2, 3, 4, 2, 3]));
+    assert(deep_equals([...[], ...rest,], [2, 3]));
+    var copy = [...rest];
+    push(copy, 4);
+    assert(len(rest) == 2);
+    var xs = [1];
+    xs = [...xs, ...xs];
+    assert(deep_equals(xs, [1, 1]));
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn lists_are_spread_into_arguments() {
+  let src = r#"
+    fun add3(a, b, c) { return a + b + c; }
+    var args = [1, 2, 3];
+    assert(add3(...args) == 6);
+    assert(add3(10, ...args[1:]) == 15);
+    assert(add3(...args[:1], 5, ...[100]) == 106);
+    assert(len(...[[1, 2]]) == 2);
+
+    class Point {
+      init(x, y) { this.x = x; this.y = y; }
+      sum(a, b) { return this.x + this.y + a + b; }
+    }
+    var point = Point(...[1, 2]);
+    assert(point.sum(...[3, 4]) == 10);
+
+    class Point3 < Point {
+      sum(a, b) { return super.sum(...[a, b]) * 10; }
+    }
+    assert(Point3(1, 2).sum(3, 4) == 100);
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn spread_arguments_are_checked_against_arity() {
+  assert!(runtime_error("fun f(a, b) {} f(...[1]);")
+    .contains("Function `f` expected 2 arguments but got 1."));
+  assert!(runtime_error(
+    "fun f() {} var big = []; for (var i = 0; i < 300; i = i + 1) push(big, i); f(...big);"
+  )
+  .contains("expected 0 arguments but got 300."));
+}
+
+#[test]
+fn only_lists_are_spread() {
+  assert!(runtime_error("[...1];").contains("Only lists can be spread."));
+  assert!(runtime_error("fun f(a) {} f(...\"ab\");").contains("Only lists can be spread."));
+}
+
+#[test]
+fn malformed_spreads_are_compile_errors() {
+  assert!(compile_error("[...];").contains("Expect expression."));
+  assert!(compile_error("fun f(a) {} f(1,);").contains("Expect expression."));
+  assert!(compile_error("...xs;").contains("Expect expression."));
+}
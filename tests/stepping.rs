@@ -0,0 +1,103 @@
+use rlox::{RunOutcome, Source, Step, Value, Vm};
+
+const SRC: &str = "var a = 1;
+fun double(x) {
+  return x * 2;
+}
+var b = double(a); var c = b + 1;
+print c;
+c;";
+
+/// Compile the source, then start it (without running any instruction).
+fn started(src: &str) -> Vm {
+  let mut vm = Vm::new();
+  let script = vm.compile(&Source::new(src)).unwrap();
+  vm.start(&script);
+  vm
+}
+
+/// Lines where each step stops, until the script is finished.
+fn stops(vm: &mut Vm, step: Step) -> Vec<usize> {
+  let mut lines = vec![];
+  loop {
+    match vm.step(step).unwrap() {
+      RunOutcome::Paused { line } => lines.push(line),
+      RunOutcome::Finished(_) => return lines,
+    }
+  }
+}
+
+#[test]
+fn steps_by_line_stop_at_each_line_reached() {
+  let mut vm = started(SRC);
+  // the function is defined where its body ends (line 4), then its body is stepped into
+  assert_eq!(stops(&mut vm, Step::Line), [4, 5, 3, 6, 7]);
+}
+
+#[test]
+fn steps_by_statement_stop_at_each_statement() {
+  let mut vm = started(SRC);
+  // both statements of line 5 stop, as well as the call in between
+  assert_eq!(stops(&mut vm, Step::Statement), [4, 5, 3, 5, 6, 7]);
+}
+
+#[test]
+fn steps_by_instruction_stop_in_the_middle_of_lines() {
+  let mut vm = started("var a = 1 + 2;\na;");
+  assert_eq!(
+    vm.step(Step::Instruction).unwrap(),
+    RunOutcome::Paused { line: 1 }
+  );
+  assert_eq!(vm.global("a"), None);
+  assert_eq!(stops(&mut vm, Step::Instruction).len(), 4);
+}
+
+#[test]
+fn each_step_makes_progress() {
+  let mut vm = started(SRC);
+  assert_eq!(vm.step(Step::Line).unwrap(), RunOutcome::Paused { line: 4 });
+  assert_eq!(vm.global("a"), Some(Value::Number(1.0)));
+  assert_eq!(vm.step(Step::Line).unwrap(), RunOutcome::Paused { line: 5 });
+  assert_eq!(vm.global("b"), None);
+  assert_eq!(
+    vm.step(Step::Statement).unwrap(),
+    RunOutcome::Paused { line: 3 }
+  );
+  assert_eq!(
+    vm.resume().unwrap(),
+    RunOutcome::Finished(Some(Value::Number(3.0)))
+  );
+}
+
+#[test]
+fn breakpoints_still_pause_steps() {
+  let mut vm = started("var a = 1;\nvar b = 2;\nvar c = 3;");
+  vm.set_breakpoint(2);
+  assert_eq!(vm.resume().unwrap(), RunOutcome::Paused { line: 2 });
+  assert_eq!(vm.step(Step::Line).unwrap(), RunOutcome::Paused { line: 3 });
+  assert_eq!(vm.step(Step::Line).unwrap(), RunOutcome::Finished(None));
+}
+
+#[test]
+fn statement_starts_survive_optimizations() {
+  use rlox::{
+    compiler::{CompileOptions, OptLevel},
+    utils::Init,
+    vm::{RunOutcome as RawRunOutcome, VM},
+  };
+
+  let mut vm = VM::init();
+  vm.set_compile_options(CompileOptions {
+    opt_level: OptLevel::O2,
+    ..CompileOptions::init()
+  });
+  // compiled without running
+  vm.disassemble_source("1 + 2; var a = 3; { 4; } print a;".to_owned())
+    .unwrap();
+  let mut stops = 0;
+  while let RawRunOutcome::Paused { .. } = vm.step(Step::Statement).unwrap() {
+    stops += 1;
+  }
+  // `1 + 2;` and `4;` are optimized out, their statements start at the next one
+  assert_eq!(stops, 1);
+}
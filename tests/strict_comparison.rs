@@ -0,0 +1,81 @@
+mod common;
+
+use rlox::{
+  compiler::{CompileOptions, OptLevel},
+  utils::Init,
+  vm::{InterpretError, VM},
+};
+
+/// Interpret the source in strict comparison mode (at the optimization level).
+fn strict_run(src: &str, opt_level: OptLevel) -> Result<(), InterpretError> {
+  let mut vm = VM::init();
+  vm.set_strict_comparison(true);
+  vm.set_compile_options(CompileOptions {
+    opt_level,
+    ..CompileOptions::init()
+  });
+  vm.interpret(common::PRELUDE.to_owned() + src).map(|_| ())
+}
+
+#[test]
+fn mixed_types_compare_false_by_default() {
+  common::run(
+    r#"
+assert(!(1 < "a"));
+assert(!(nil >= 0));
+assert(!(true > nil));
+"#,
+  )
+  .unwrap();
+}
+
+#[test]
+fn mixed_types_are_runtime_errors_in_strict_mode() {
+  for (src, lhs, rhs) in [
+    (r#"1 < "a";"#, "number", "string"),
+    ("nil >= 0;", "nil", "number"),
+    ("true > nil;", "bool", "nil"),
+    ("fun f() {} f <= 1;", "function", "number"),
+  ] {
+    for opt_level in [OptLevel::O0, OptLevel::O2] {
+      match strict_run(src, opt_level) {
+        Err(InterpretError::RuntimeError(message)) => assert!(
+          message.contains(&format!(
+            "Operands must be of the same type to compare, got `{}` and `{}`.",
+            lhs, rhs
+          )),
+          "{}",
+          message
+        ),
+        other => panic!("expect a runtime error, but got: {:?}", other),
+      }
+    }
+  }
+}
+
+#[test]
+fn same_types_compare_in_strict_mode() {
+  strict_run(
+    r#"
+assert(1 < 2);
+assert("a" <= "b");
+assert(!(false > true));
+assert(1 != "1");
+"#,
+    OptLevel::O0,
+  )
+  .unwrap();
+}
+
+#[test]
+fn strict_errors_are_catchable() {
+  strict_run(
+    r#"
+var caught = false;
+try { 1 < "a"; } catch (e) { caught = true; }
+assert(caught);
+"#,
+    OptLevel::O0,
+  )
+  .unwrap();
+}
@@ -0,0 +1,56 @@
+use rlox::{
+  compiler::CompileOptions,
+  utils::Init,
+  vm::{InterpretError, VM},
+};
+
+/// A fresh virtual machine in strict mode.
+fn strict_vm() -> VM {
+  let mut vm = VM::init();
+  vm.set_compile_options(CompileOptions {
+    strict: true,
+    ..CompileOptions::init()
+  });
+  vm
+}
+
+#[test]
+fn assigning_undeclared_global_is_compile_error() {
+  let src = r#"
+    var count = 0;
+    fun bump() {
+      cuont = count + 1;
+    }
+  "#;
+  match strict_vm().interpret(src.to_owned()) {
+    Err(InterpretError::CompileError(message)) => assert_eq!(
+      message,
+      "[line 4] Error at 'cuont': Assignment to undeclared variable `cuont`."
+    ),
+    other => panic!("expect a compile error, but got: {:?}", other),
+  }
+}
+
+#[test]
+fn globals_declared_later_are_assignable() {
+  let src = r#"
+    fun reset() { total = 0; }
+    var total = 1;
+    reset();
+  "#;
+  strict_vm().interpret(src.to_owned()).unwrap();
+}
+
+#[test]
+fn globals_of_previous_lines_are_assignable() {
+  let mut vm = strict_vm();
+  vm.interpret("var a = 1;".into()).unwrap();
+  vm.interpret("a = 2;".into()).unwrap();
+  vm.interpret("clock = nil;".into()).unwrap();
+}
+
+#[test]
+fn non_strict_mode_fails_at_runtime() {
+  let result = VM::init().interpret("typo = 1;".into());
+  assert!(matches!(result, Err(InterpretError::RuntimeError(_))));
+}
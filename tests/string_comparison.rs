@@ -0,0 +1,70 @@
+mod common;
+
+use common::run;
+use rlox::{utils::Init, vm::VM};
+
+#[test]
+fn strings_are_ordered_lexicographically() {
+  let src = r#"
+    assert("a" < "b");
+    assert("abc" < "abd");
+    assert("ab" < "abc");
+    assert("" < "a");
+    assert(!("b" < "a"));
+    assert("b" > "a");
+    assert("abd" > "abc");
+    assert("abc" <= "abc");
+    assert("abc" >= "abc");
+    assert(!("abc" < "abc"));
+    assert(!("abc" > "abc"));
+    // by code points, so upper case comes first
+    assert("Z" < "a");
+    assert("z" < "é");
+  "#;
+  assert!(run(src).is_ok());
+}
+
+#[test]
+fn concatenated_strings_compare_by_content() {
+  let src = r#"
+    var s = "ab" + "c";
+    assert(s <= "abc" and s >= "abc");
+    assert(s < "ab" + "d");
+  "#;
+  assert!(run(src).is_ok());
+}
+
+#[test]
+fn lists_of_strings_sort() {
+  let src = r#"
+    var sorted = sort(["pear", "apple", "fig", "Banana"]);
+    assert(sorted[0] == "Banana");
+    assert(sorted[1] == "apple");
+    assert(sorted[2] == "fig");
+    assert(sorted[3] == "pear");
+  "#;
+  assert!(run(src).is_ok());
+}
+
+#[test]
+fn strings_are_unordered_with_other_types() {
+  let src = r#"
+    assert(!("1" < 2));
+    assert(!("1" >= 2));
+  "#;
+  assert!(run(src).is_ok());
+}
+
+#[test]
+fn strict_comparison_orders_strings() {
+  let mut vm = VM::init();
+  vm.set_strict_comparison(true);
+  assert!(vm.interpret(r#""a" < "b";"#.to_owned()).is_ok());
+  let mut vm = VM::init();
+  vm.set_strict_comparison(true);
+  let error = vm
+    .interpret(r#""a" < 1;"#.to_owned())
+    .unwrap_err()
+    .into_message();
+  assert!(error.ends_with("got `string` and `number`."), "{}", error);
+}
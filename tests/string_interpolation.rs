@@ -0,0 +1,110 @@
+use rlox::{
+  scanner::{Scanner, TokenType},
+  utils::Init,
+  vm::{InterpretError, VM},
+};
+
+/// Interpret the given source, then return its (string) result.
+fn string_of(src: &str) -> String {
+  let value = VM::init().interpret(src.to_owned()).unwrap().unwrap();
+  value.as_rust_string().unwrap().to_owned()
+}
+
+fn compile_error_of(src: &str) -> String {
+  match VM::init().interpret(src.to_owned()) {
+    Err(InterpretError::CompileError(message)) => message,
+    other => panic!("expect a compile error, but got: {:?}", other),
+  }
+}
+
+#[test]
+fn expressions_are_interpolated() {
+  assert_eq!(string_of(r#"var x = 41; "x = ${x + 1}";"#), "x = 42");
+  assert_eq!(
+    string_of(r#"var a = 1; var b = 2; "${a} + ${b} = ${a + b}!";"#),
+    "1 + 2 = 3!"
+  );
+  // a single expression still results in a string
+  assert_eq!(string_of(r#""${1}" + "${2}";"#), "12");
+}
+
+#[test]
+fn values_are_stringified_as_printed() {
+  assert_eq!(
+    string_of(r#"class C {} "${nil} ${true} ${2.5} ${[1, "a"]} ${C}";"#),
+    "nil true 2.5 [1, \"a\"] C"
+  );
+  assert_eq!(string_of(r#"var s = "lox"; "${s}";"#), "lox");
+}
+
+#[test]
+fn interpolations_nest() {
+  assert_eq!(string_of(r#""a${ "b${ "c" }d" }e";"#), "abcde");
+  assert_eq!(string_of(r#""${ "}" }";"#), "}");
+  assert_eq!(string_of(r#""${ [1, "${2}"][1] }";"#), "2");
+}
+
+#[test]
+fn dollar_signs_are_escaped() {
+  assert_eq!(
+    string_of(r#"var x = 1; "\${x} costs $1";"#),
+    "${x} costs $1"
+  );
+  assert_eq!(string_of(r#"r"${x}";"#), "${x}");
+}
+
+#[test]
+fn triple_quoted_strings_are_interpolated() {
+  let src = "var n = 2;\n\"\"\"\n${n} lines\n\"quoted\" ${n * 2}\"\"\";";
+  assert_eq!(string_of(src), "2 lines\n\"quoted\" 4");
+}
+
+#[test]
+fn segments_are_scanned_as_tokens() {
+  let mut scanner = Scanner::bind("\"a${x}b${ {} }c\" }".to_owned());
+  let tokens: Vec<_> = std::iter::from_fn(|| {
+    let token = scanner.scan_token();
+    (token.token_type() != TokenType::Eof).then(|| token.token_type())
+  })
+  .collect();
+  assert_eq!(
+    tokens,
+    [
+      TokenType::Dollar,
+      TokenType::Identifier,
+      TokenType::Dollar,
+      TokenType::LeftBrace,
+      TokenType::RightBrace,
+      TokenType::String,
+      TokenType::RightBrace,
+    ]
+  );
+}
+
+#[test]
+fn literal_dollar_braces_are_single_strings() {
+  for src in [r#""\${x}""#, r#"r"${x}""#, r#""$1 $ {x}""#] {
+    let mut scanner = Scanner::bind(src.to_owned());
+    let token = scanner.scan_token();
+    assert_eq!(token.token_type(), TokenType::String, "{}", src);
+    assert_eq!(scanner.scan_token().token_type(), TokenType::Eof, "{}", src);
+  }
+}
+
+#[test]
+fn broken_interpolations_are_reported() {
+  let message = compile_error_of(r#"print "${}";"#);
+  assert!(
+    message.contains("Expect expression inside '${}'."),
+    "{}",
+    message
+  );
+  let message = compile_error_of(r#"print "${1 "2"}";"#);
+  assert!(
+    message.contains("Expect '}' after interpolated expression."),
+    "{}",
+    message
+  );
+  let message = compile_error_of("print \"${1}\n");
+  assert!(message.contains("Unterminated string."), "{}", message);
+}
@@ -0,0 +1,84 @@
+use rlox::{
+  scanner::{Scanner, TokenType},
+  utils::Init,
+  vm::{InterpretError, VM},
+};
+
+/// Interpret the given source, then return its (string) result.
+fn string_of(src: &str) -> String {
+  let value = VM::init().interpret(src.to_owned()).unwrap().unwrap();
+  value.as_rust_string().unwrap().to_owned()
+}
+
+fn compile_error_of(src: &str) -> String {
+  match VM::init().interpret(src.to_owned()) {
+    Err(InterpretError::CompileError(message)) => message,
+    other => panic!("expect a compile error, but got: {:?}", other),
+  }
+}
+
+#[test]
+fn escape_sequences_are_processed() {
+  assert_eq!(string_of(r#""a\tb\n";"#), "a\tb\n");
+  assert_eq!(string_of(r#""say \"hi\" \\ \0";"#), "say \"hi\" \\ \0");
+  assert_eq!(string_of(r#""\"" + "\"";"#), "\"\"");
+}
+
+#[test]
+fn raw_strings_keep_backslashes() {
+  assert_eq!(string_of(r#"r"C:\new\table";"#), r"C:\new\table");
+  assert_eq!(string_of(r#"r"" + r"\";"#), "\\");
+  // `r` is still an ordinary identifier
+  assert_eq!(string_of(r#"var r = "r"; r + r"r";"#), "rr");
+}
+
+#[test]
+fn triple_quoted_strings_span_lines() {
+  let src = "\"\"\"\n  first \"quoted\"\n  second\\t!\n\"\"\";";
+  assert_eq!(string_of(src), "  first \"quoted\"\n  second\t!\n");
+  let src = "r\"\"\"a\\n\nb\"\"\";";
+  assert_eq!(string_of(src), "a\\n\nb");
+  assert_eq!(string_of("\"\"\"\"\"\";"), "");
+}
+
+#[test]
+fn lines_after_multi_line_strings_are_not_skewed() {
+  let src = "var a = \"\"\"\none\ntwo\n\"\"\";\nvar b = r\"\"\"\n\\\n\"\"\";\nvar = 1;";
+  let message = compile_error_of(src);
+  assert!(message.contains("[line 8]"), "{}", message);
+
+  let mut scanner = Scanner::bind("\"a\nb\" r\"\"\"\n\n\"\"\" x".to_owned());
+  let lines: Vec<_> = std::iter::from_fn(|| {
+    let token = scanner.scan_token();
+    (token.token_type() != TokenType::Eof).then(|| token.line())
+  })
+  .collect();
+  assert_eq!(lines, [2, 4, 4]);
+}
+
+#[test]
+fn unknown_escapes_are_kept_with_a_warning() {
+  // backslashes were literal before escape sequences, so scripts like this keep working
+  let mut vm = VM::init();
+  let value = vm
+    .interpret(r#""C:\dir\new" + "\q";"#.to_owned())
+    .unwrap()
+    .unwrap();
+  assert_eq!(value.as_rust_string().unwrap(), "C:\\dir\new\\q");
+  assert_eq!(vm.warnings().len(), 2, "{:?}", vm.warnings());
+  assert!(
+    vm.warnings()[0].contains("Unknown escape sequence `\\d` is kept as it is"),
+    "{:?}",
+    vm.warnings()
+  );
+  // raw strings never warn
+  vm.interpret(r#"r"C:\dir";"#.to_owned()).unwrap();
+  assert!(vm.warnings().is_empty());
+}
+
+#[test]
+fn invalid_strings_are_reported() {
+  let message = compile_error_of("\n\"\"\"\nunterminated\n\"\";");
+  assert!(message.contains("[line 2]"), "{}", message);
+  assert!(message.contains("Unterminated string."), "{}", message);
+}
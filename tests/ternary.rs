@@ -0,0 +1,52 @@
+mod common;
+
+use common::{compile_error, run};
+
+#[test]
+fn only_the_chosen_branch_is_evaluated() {
+  let src = r#"
+    var calls = 0;
+    fun count(value) { calls = calls + 1; return value; }
+    assert((true ? count("then") : count("else")) == "then");
+    assert((nil ? count("then") : count("else")) == "else");
+    assert(calls == 2);
+    assert((0 ? "zero is truthy" : "falsey") == "zero is truthy");
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn ternaries_nest_to_the_right() {
+  let src = r#"
+    fun sign(n) { return n > 0 ? "+" : n < 0 ? "-" : "0"; }
+    assert(sign(5) == "+" and sign(-5) == "-" and sign(0) == "0");
+    var nested = true ? false ? 1 : 2 : 3;
+    assert(nested == 2);
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn ternaries_bind_looser_than_logical_operators() {
+  let src = r#"
+    var x = 1 < 2 and 3 < 4 ? "both" : "not both";
+    assert(x == "both");
+    assert((false or nil ? 1 : 2) == 2);
+    assert(1 + (true ? 1 : 2) * 10 == 11);
+    var y;
+    y = false ? 1 : 2;
+    assert(y == 2);
+    var z = true ? y = 3 : 4;
+    assert(y == 3 and z == 3);
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn malformed_ternaries_are_compile_errors() {
+  assert!(compile_error("var x = true ? 1;")
+    .contains("Expect `:` after then branch of conditional expression."));
+  assert!(compile_error("var x = true ? 1 : ;").contains("Expect expression."));
+  assert!(compile_error("var y; var x = true ? 1 : y = 2;").contains("Invalid assignment target."));
+  assert!(compile_error("var x = : 1;").contains("Expect expression."));
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use common::{run, runtime_error, PRELUDE};
+use rlox::{
+  native::Capabilities,
+  utils::Init,
+  vm::{InterpretError, VM},
+};
+
+#[test]
+fn current_time() {
+  let src = r#"
+    var now = now_unix();
+    assert(now > 1600000000);
+    assert(now == now_unix() or now + 1 == now_unix());
+    assert(format_time(now, "%Y") != "1970");
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn format_timestamps_in_utc() {
+  let src = r#"
+    assert(format_time(0, "%Y-%m-%d %H:%M:%S %Z") == "1970-01-01 00:00:00 UTC");
+    assert(format_time(951782400, "%a %b %d %Y, day %j") == "Tue Feb 29 2000, day 060");
+    assert(format_time(1700000000.9, "%s %H:%M:%S") == "1700000000 22:13:20");
+    assert(format_time(-1, "%Y-%m-%d %H:%M:%S") == "1969-12-31 23:59:59");
+    assert(format_time(4102444800, "%j of %Y, 100%%") == "001 of 2100, 100%");
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn invalid_format() {
+  let message = runtime_error(r#"format_time(0, "%Q");"#);
+  assert!(
+    message.contains("`format_time` got an unknown specifier `%Q`."),
+    "{}",
+    message
+  );
+  let message = runtime_error(r#"format_time("now", "%Y");"#);
+  assert!(
+    message.contains("`format_time` expected a timestamp."),
+    "{}",
+    message
+  );
+}
+
+#[test]
+fn sleep_needs_the_capability() {
+  let message = runtime_error("sleep(1);");
+  assert!(
+    message.contains("Native function `sleep` needs the `sleep` capability."),
+    "{}",
+    message
+  );
+
+  let mut vm = VM::init();
+  vm.set_capabilities(Capabilities::all());
+  let src = PRELUDE.to_owned() + "var t = clock(); sleep(20); assert(clock() - t >= 0.02);";
+  vm.interpret(src).unwrap();
+  assert!(matches!(
+    vm.interpret("sleep(-1);".to_owned()),
+    Err(InterpretError::RuntimeError(message)) if message.contains("non-negative")
+  ));
+  let huge = format!("1{}", "0".repeat(300));
+  for ms in [huge.as_str(), "1 / 0", "0 / 0"] {
+    assert!(
+      matches!(
+        vm.interpret(format!("sleep({});", ms)),
+        Err(InterpretError::RuntimeError(_))
+      ),
+      "{}",
+      ms
+    );
+  }
+}
@@ -0,0 +1,62 @@
+use std::time::Duration;
+
+use rlox::{
+  native::Capabilities,
+  utils::Init,
+  value::Value,
+  vm::{InterpretError, VM},
+};
+
+/// A virtual machine whose runs may take the given milliseconds, and may `sleep`.
+fn vm_with_timeout(millis: u64) -> VM {
+  let mut vm = VM::init();
+  vm.set_timeout(Some(Duration::from_millis(millis)));
+  vm.set_capabilities(Capabilities {
+    sleep: true,
+    ..Capabilities::none()
+  });
+  vm
+}
+
+/// Calls itself `2^n` times, so runs thousands of instructions (without loops).
+const BUSY: &str = "fun busy(n) { if (n > 0) { busy(n - 1); busy(n - 1); } }\n";
+
+#[test]
+fn runs_past_the_deadline_time_out() {
+  let mut vm = vm_with_timeout(0);
+  match vm.interpret("var a = 1;".to_owned()) {
+    Err(InterpretError::Timeout(message)) => {
+      assert_eq!(message, "[line 1] in script: Script timed out after 0ms.")
+    }
+    other => panic!("expect a timeout, but got: {:?}", other),
+  }
+  assert!(vm.is_idle());
+}
+
+#[test]
+fn timeouts_are_not_caught() {
+  let mut vm = vm_with_timeout(10);
+  let src = BUSY.to_owned()
+    + "var caught = false;\ntry { sleep(20); busy(8); } catch (e) { caught = true; }\n";
+  assert!(matches!(vm.interpret(src), Err(InterpretError::Timeout(_))));
+  assert_eq!(
+    vm.interpret("caught;".to_owned()).unwrap(),
+    Some(Value::bool_val(false))
+  );
+}
+
+#[test]
+fn runs_within_the_deadline_finish() {
+  let mut vm = vm_with_timeout(60_000);
+  vm.interpret(BUSY.to_owned() + "busy(8);").unwrap();
+  assert!(vm.instruction_count() > rlox::vm::TIMEOUT_CHECK_INTERVAL);
+
+  // each run has a deadline of its own
+  vm.set_timeout(Some(Duration::ZERO));
+  assert!(matches!(
+    vm.interpret("busy(1);".to_owned()),
+    Err(InterpretError::Timeout(_))
+  ));
+  vm.set_timeout(None);
+  vm.interpret("busy(1);".to_owned()).unwrap();
+}
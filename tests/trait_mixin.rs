@@ -0,0 +1,48 @@
+mod common;
+
+use common::{run, runtime_error};
+
+#[test]
+fn methods_are_mixed_into_class() {
+  let src = r#"
+    trait Named {
+      name() { return this.first + " " + this.last; }
+      get initials { return "?"; }
+    }
+    trait Greeter {
+      greet() { return "Hi, " + this.name(); }
+    }
+    class Person with Named, Greeter {
+      init(first, last) { this.first = first; this.last = last; }
+      get initials { return "!"; }
+    }
+    var p = Person("Ada", "Lovelace");
+    assert(p.greet() == "Hi, Ada Lovelace");
+    assert(p.initials == "!");
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn conflicting_traits() {
+  let src = r#"
+    trait A { m() {} }
+    trait B { m() {} }
+    class C with A, B {}
+  "#;
+  let message = runtime_error(src);
+  assert!(
+    message.contains("Method `m` of trait `B` conflicts with one of trait(s) [A] mixed into `C`.")
+  );
+}
+
+#[test]
+fn traits_are_not_instantiable() {
+  assert!(runtime_error("trait T {} T();").contains("Can't instantiate trait `T`."));
+}
+
+#[test]
+fn only_traits_can_be_mixed_in() {
+  let message = runtime_error("class A {} class B with A {}");
+  assert!(message.contains("Can only mix traits into `B`, but `A` is a class."));
+}
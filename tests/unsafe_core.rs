@@ -0,0 +1,76 @@
+//! Raw-pointer paths of the object model (`cast_to_obj_ptr`, then casting back by `as_*`),
+//! without running scripts, so they're cheap enough to run under Miri and AddressSanitizer.
+
+use rlox::{
+  native::{deep, error},
+  object::{
+    ObjBoundMethod, ObjClass, ObjClosure, ObjFunction, ObjInstance, ObjNative, ObjString, ObjTrait,
+    ObjType,
+  },
+  value::{display, Value},
+  vm::InterpretError,
+};
+
+fn string(s: &str) -> Value {
+  Value::obj_val(ObjString::from(s.to_owned()).cast_to_obj_ptr())
+}
+
+fn nil(_: &[Value]) -> Result<Value, InterpretError> {
+  Ok(Value::nil_val())
+}
+
+/// Call the native of the given name in `deep::NATIVES`.
+fn call_deep(name: &str, args: &[Value]) -> Value {
+  let &(_, _, function) = deep::NATIVES.iter().find(|n| n.0 == name).unwrap();
+  function(args).unwrap()
+}
+
+#[test]
+fn objects_are_cast_back_to_their_types() {
+  let function = ObjFunction::new(Some(ObjString::from("f".to_owned())));
+  let function = Value::obj_val(function.cast_to_obj_ptr());
+  assert_eq!(function.obj_type().unwrap(), ObjType::Function);
+  let function_ptr = function.as_function().unwrap();
+  assert_eq!(unsafe { function_ptr.as_ref() }.name(), "f");
+  assert!(function.as_class().is_err() && function.as_string().is_err());
+
+  let native = Value::obj_val(ObjNative::new("nil", 0, nil).cast_to_obj_ptr());
+  assert_eq!(native.obj_type().unwrap(), ObjType::Native);
+  assert!(native.as_native().is_ok() && native.as_function().is_err());
+
+  let class = Value::obj_val(ObjClass::new(ObjString::from("Point".to_owned())).cast_to_obj_ptr());
+  let instance = ObjInstance::new(class.as_class().unwrap());
+  let instance = Value::obj_val(instance.cast_to_obj_ptr());
+  assert!(instance.is_instance_of(class).unwrap());
+  assert_eq!(display::repr(&instance), "Point instance");
+
+  let closure = Value::obj_val(ObjClosure::new(function_ptr).cast_to_obj_ptr());
+  assert_eq!(closure.obj_type().unwrap(), ObjType::Closure);
+  assert_eq!(
+    closure.as_callable().unwrap(),
+    (function_ptr, closure.as_closure().ok())
+  );
+  assert!(closure.as_function().is_err() && function.as_closure().is_err());
+
+  let bound = ObjBoundMethod::new(instance, closure);
+  let bound = Value::obj_val(bound.cast_to_obj_ptr());
+  assert_eq!(bound.obj_type().unwrap(), ObjType::BoundMethod);
+  assert!(bound.as_bound_method().is_ok() && bound.as_instance().is_err());
+  assert_eq!(display::repr(&bound), "<fn f>");
+}
+
+#[test]
+fn fields_are_shared_through_pointers() {
+  let cause = error::new_error("Error", "cause".to_owned(), Value::nil_val());
+  let err = error::new_error("TypeError", "boom".to_owned(), cause);
+  assert!(error::as_error(err).is_some());
+  assert!(error::as_error(string("boom")).is_none());
+  assert!(string("message").contains(err).is_err());
+  assert!(err.contains(string("message")).unwrap());
+  assert_eq!(error::describe(err), "TypeError: boom");
+
+  let copy = call_deep("clone", &[err]);
+  assert!(copy != err);
+  assert!(call_deep("deep_equals", &[err, copy]).as_bool());
+  assert!(!call_deep("deep_equals", &[err, cause]).as_bool());
+}
@@ -0,0 +1,52 @@
+//! Accessors of the unsafe core (values, objects and tables), without running scripts,
+//! so they're cheap enough to run under Miri.
+
+use rlox::{
+  object::{ObjString, ObjTrait},
+  table::Table,
+  value::Value,
+};
+
+fn string(s: &str) -> Value {
+  Value::obj_val(ObjString::from(s.to_owned()).cast_to_obj_ptr())
+}
+
+#[test]
+fn strings_are_borrowed_immutably() {
+  let a = string("lox");
+  let b = string("lox");
+  // two shared borrows of the same object at once
+  let (x, y) = (a.as_rust_string().unwrap(), a.as_rust_string().unwrap());
+  assert_eq!(x, y);
+  assert_eq!(a, b);
+  assert!(string("a") < string("b"));
+
+  let joined = (a + b).unwrap();
+  assert_eq!(joined.as_rust_string().unwrap(), "loxlox");
+  assert_eq!(a.as_rust_string().unwrap(), "lox");
+  assert!(Value::from(1.0).as_rust_string().is_err());
+}
+
+#[test]
+fn mismatched_accessors_never_read_other_fields() {
+  assert!(!Value::from(1.0).as_bool());
+  assert!(!Value::nil_val().as_bool());
+  assert!(Value::from(true).as_number().is_nan());
+  assert!(string("1").as_number().is_nan());
+  assert_eq!(format!("{:?}", Value::from(true)), "Bool(true)");
+  assert_eq!(format!("{:?}", Value::nil_val()), "Nil");
+  assert_eq!(format!("{:?}", Value::from(2.5)), "Number(2.5)");
+  assert_eq!(format!("{:?}", string("s")), "Obj(\"s\")");
+}
+
+#[test]
+fn tables_keep_values_of_object_keys() {
+  let mut table = Table::default();
+  table.set(ObjString::from("a".to_owned()), string("x"));
+  table.set(ObjString::from("b".to_owned()), Value::from(2.0));
+  table.set(ObjString::from("a".to_owned()), string("y"));
+  let a = table.get(&ObjString::from("a".to_owned())).unwrap();
+  assert_eq!(a.as_rust_string().unwrap(), "y");
+  assert!(table.remove(&ObjString::from("b".to_owned())).is_some());
+  assert_eq!(table.len(), 1);
+}
@@ -0,0 +1,67 @@
+mod common;
+
+use common::run;
+use rlox::{
+  utils::Init,
+  value::{display, Value},
+  vm::VM,
+};
+
+fn result_of(src: &str) -> Value {
+  VM::init().interpret(src.to_owned()).unwrap().unwrap()
+}
+
+#[test]
+fn plain_and_repr_styles() {
+  let s = result_of(r#""say \"hi\"\n";"#);
+  assert_eq!(display::plain(&s), "say \"hi\"\n");
+  assert_eq!(display::repr(&s), r#""say \"hi\"\n""#);
+  assert_eq!(s.to_string(), display::repr(&s));
+
+  for (src, expected) in [
+    ("1.5;", "1.5"),
+    ("3;", "3"),
+    ("true;", "true"),
+    ("nil;", "nil"),
+    ("fun f() {} f;", "<fn f>"),
+    ("clock;", "<native fn>"),
+    ("class C {} C;", "C"),
+    ("class C {} C();", "C instance"),
+    ("class C { m() {} } C().m;", "<fn m>"),
+  ] {
+    let value = result_of(src);
+    assert_eq!(display::plain(&value), expected, "{}", src);
+    assert_eq!(display::repr(&value), expected, "{}", src);
+  }
+}
+
+#[test]
+fn quoted_strings_round_trip_as_literals() {
+  let original = "tab\t, quote \", backslash \\, nul \0, newline\n";
+  let quoted = display::quote(original);
+  let value = result_of(&format!("{};", quoted));
+  assert_eq!(display::plain(&value), original);
+}
+
+#[test]
+fn str_native_is_plain() {
+  let src = r#"
+    assert(str("s") == "s");
+    assert(str(12) == "12");
+    assert(str(0.25) == "0.25");
+    assert(str(nil) == "nil");
+    assert(str(false) == "false");
+    class Point {}
+    assert(str(Point()) == "Point instance");
+    assert("n = " + str(1 + 2) == "n = 3");
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn disassembly_quotes_string_constants() {
+  let listing = VM::init()
+    .disassemble_source("var a = \"x\\ty\";".to_owned())
+    .unwrap();
+  assert!(listing.contains(r#""x\ty""#), "{}", listing);
+}
@@ -0,0 +1,133 @@
+use rlox::{DiagnosticKind, RunOutcome, Source, Value, Vm};
+
+const SRC: &str = "var total = 0;
+fun add(x) {
+  var doubled = x * 2;
+  total = total + doubled;
+  return total;
+}
+add(1);
+add(2);
+total;";
+
+/// Compile the source, then start it (without running any instruction).
+fn started(src: &str) -> Vm {
+  let mut vm = Vm::new();
+  let script = vm.compile(&Source::new(src)).unwrap();
+  vm.start(&script);
+  vm
+}
+
+/// Values of all watches, which must be evaluated without errors.
+fn values(vm: &mut Vm) -> Vec<(String, Value)> {
+  vm.debugger()
+    .watches()
+    .into_iter()
+    .map(|(expression, value)| (expression, value.unwrap()))
+    .collect()
+}
+
+#[test]
+fn watches_are_evaluated_at_each_stop() {
+  let mut vm = started(SRC);
+  vm.add_watch("total").unwrap();
+  vm.add_watch("total + 100;").unwrap();
+  vm.set_breakpoint(4);
+  assert_eq!(vm.resume().unwrap(), RunOutcome::Paused { line: 4 });
+  assert_eq!(
+    values(&mut vm),
+    [
+      ("total".to_owned(), Value::Number(0.0)),
+      ("total + 100".to_owned(), Value::Number(100.0)),
+    ]
+  );
+  assert_eq!(vm.resume().unwrap(), RunOutcome::Paused { line: 4 });
+  assert_eq!(values(&mut vm)[0].1, Value::Number(2.0));
+}
+
+#[test]
+fn locals_of_the_paused_call_are_visible() {
+  let mut vm = started(SRC);
+  vm.add_watch("x + doubled").unwrap();
+  vm.set_breakpoint(4);
+  vm.resume().unwrap();
+  assert_eq!(
+    vm.debugger().locals(0),
+    [
+      ("x".to_owned(), Value::Number(1.0)),
+      ("doubled".to_owned(), Value::Number(2.0)),
+    ]
+  );
+  assert!(
+    vm.debugger().locals(1).is_empty(),
+    "the script has no locals"
+  );
+  assert_eq!(values(&mut vm)[0].1, Value::Number(3.0));
+}
+
+#[test]
+fn watches_dont_change_the_script() {
+  let mut vm = started(SRC);
+  vm.add_watch("total = 1000").unwrap();
+  vm.set_breakpoint(4);
+  vm.resume().unwrap();
+  assert_eq!(values(&mut vm)[0].1, Value::Number(1000.0));
+  vm.clear_breakpoint(4);
+  assert_eq!(
+    vm.resume().unwrap(),
+    RunOutcome::Finished(Some(Value::Number(6.0)))
+  );
+}
+
+#[test]
+fn changes_of_objects_are_kept() {
+  let src = "var items = [1];\nfun grow() {\n  return len(items);\n}\ngrow();";
+  let mut vm = started(src);
+  vm.add_watch("push(items, 2)").unwrap();
+  vm.set_breakpoint(3);
+  vm.resume().unwrap();
+  values(&mut vm);
+  // the list is shared with the run, unlike the variable referring to it
+  assert_eq!(
+    vm.resume().unwrap(),
+    RunOutcome::Finished(Some(Value::Number(2.0)))
+  );
+}
+
+#[test]
+fn errors_of_watches_are_their_values() {
+  let mut vm = started(SRC);
+  vm.add_watch("missing").unwrap();
+  vm.add_watch("total").unwrap();
+  vm.set_breakpoint(4);
+  vm.resume().unwrap();
+  let watches = vm.debugger().watches();
+  let error = watches[0].1.as_ref().unwrap_err();
+  assert_eq!(error.kind(), DiagnosticKind::RuntimeError);
+  assert!(error.message().contains("Undefined variable `missing`."));
+  assert_eq!(watches[1].1, Ok(Value::Number(0.0)));
+  // the paused run is intact
+  assert_eq!(vm.resume().unwrap(), RunOutcome::Paused { line: 4 });
+}
+
+#[test]
+fn only_expressions_are_watched() {
+  let mut vm = Vm::new();
+  let error = vm.add_watch("var a = 1").unwrap_err();
+  assert_eq!(error.kind(), DiagnosticKind::CompileError);
+  assert_eq!(error.message(), "Watch `var a = 1` isn't an expression.");
+  assert_eq!(
+    vm.add_watch("1 +").unwrap_err().kind(),
+    DiagnosticKind::CompileError
+  );
+}
+
+#[test]
+fn watches_are_removed_by_their_expressions() {
+  let mut vm = started(SRC);
+  vm.add_watch("total").unwrap();
+  vm.add_watch("total").unwrap();
+  assert!(vm.remove_watch("total;"));
+  assert!(!vm.remove_watch("total"));
+  assert!(vm.debugger().watches().is_empty());
+}
@@ -0,0 +1,117 @@
+mod common;
+
+use std::time::Duration;
+
+use common::{compile_error, run};
+use rlox::{
+  utils::Init,
+  vm::{InterpretError, VM},
+  RunOutcome, Source, Value, Vm,
+};
+
+#[test]
+fn body_runs_while_condition_holds() {
+  let src = r#"
+    var i = 0;
+    var sum = 0;
+    while (i < 5) {
+      sum = sum + i;
+      i = i + 1;
+    }
+    assert(i == 5 and sum == 10);
+    while (false) assertion_failed;
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn loops_nest_inside_functions() {
+  let src = r#"
+    fun table(n) {
+      var cells = 0;
+      var row = 0;
+      while (row < n) {
+        var col = 0;
+        while (col < n) {
+          cells = cells + 1;
+          col = col + 1;
+        }
+        row = row + 1;
+      }
+      return cells;
+    }
+    assert(table(4) == 16);
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn each_iteration_has_its_own_locals() {
+  let src = r#"
+    var first;
+    var last;
+    var i = 0;
+    while (i < 3) {
+      var j = i;
+      fun get() { return j; }
+      if (i == 0) first = get;
+      last = get;
+      i = i + 1;
+    }
+    assert(first() == 0 and last() == 2);
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn loops_are_exited_by_return_and_throw() {
+  let src = r#"
+    fun find(limit) {
+      var i = 0;
+      while (true) {
+        if (i * i > limit) return i;
+        i = i + 1;
+      }
+    }
+    assert(find(10) == 4);
+    var caught;
+    try {
+      var n = 0;
+      while (true) { n = n + 1; if (n == 3) throw n; }
+    } catch (e) { caught = e; }
+    assert(caught == 3);
+  "#;
+  run(src).unwrap();
+}
+
+#[test]
+fn syntax_errors() {
+  assert!(compile_error("while true {}").contains("Expect `(` after `while`."));
+  assert!(compile_error("while (true {}").contains("Expect `)` after condition."));
+}
+
+#[test]
+fn breakpoints_pause_each_iteration() {
+  let src = "var i = 0;\nwhile (i < 3) {\n  i = i + 1;\n}\ni;";
+  let mut vm = Vm::new();
+  let script = vm.compile(&Source::new(src)).unwrap();
+  vm.start(&script);
+  vm.set_breakpoint(3);
+  for _ in 0..3 {
+    assert_eq!(vm.resume().unwrap(), RunOutcome::Paused { line: 3 });
+  }
+  assert_eq!(
+    vm.resume().unwrap(),
+    RunOutcome::Finished(Some(Value::Number(3.0)))
+  );
+}
+
+#[test]
+fn endless_loops_time_out() {
+  let mut vm = VM::init();
+  vm.set_timeout(Some(Duration::from_millis(10)));
+  assert!(matches!(
+    vm.interpret("while (true) {}".to_owned()),
+    Err(InterpretError::Timeout(_))
+  ));
+}
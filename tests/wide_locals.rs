@@ -0,0 +1,51 @@
+mod common;
+
+use common::{run, PRELUDE};
+use rlox::{utils::Init, vm::VM};
+
+/// Declare `count` locals (`v0`, `v1`, ...) as `nil`,
+/// so that no constant is made for them.
+fn declare_locals(count: usize) -> String {
+  (0..count).map(|i| format!("var v{};\n", i)).collect()
+}
+
+#[test]
+fn script_block_beyond_256_locals() {
+  let src = format!(
+    "{{\n{}v255 = 1;\nv256 = 2;\nv299 = v255 + v256;\nassert(v0 == nil);\nassert(v299 == 3);\n}}",
+    declare_locals(300)
+  );
+  run(&src).unwrap();
+}
+
+#[test]
+fn function_beyond_256_locals() {
+  let src = format!(
+    "fun f(x) {{\n{}v280 = x * 2;\nreturn v280 + v1;\n}}\nassert(f(f(1)) == 4);",
+    declare_locals(281).replace("var v1;", "var v1 = 0;")
+  );
+  run(&src).unwrap();
+}
+
+#[test]
+fn spilled_declarations_are_reported() {
+  // slot 0 is reserved, so `v255` is the first one being spilled
+  let src = format!("{{\n{}}}", declare_locals(257));
+  let mut vm = VM::init();
+  vm.interpret(PRELUDE.to_owned() + &src).unwrap();
+  assert_eq!(
+    vm.warnings(),
+    [
+      "[line 258] Warning at 'v255': Local variable spilled into wide slot `256`.",
+      "[line 259] Warning at 'v256': Local variable spilled into wide slot `257`.",
+    ]
+  );
+}
+
+#[test]
+fn no_warnings_within_256_locals() {
+  let src = format!("{{\n{}}}", declare_locals(255));
+  let mut vm = VM::init();
+  vm.interpret(src).unwrap();
+  assert!(vm.warnings().is_empty());
+}
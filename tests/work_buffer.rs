@@ -0,0 +1,62 @@
+//! Allocations are counted by the global allocator (shared by all threads),
+//! so there's only one test in this file.
+
+use rlox::{
+  bench::{bench, bench_on, BenchOptions, CountingAllocator},
+  cache::CacheOptions,
+  utils::Init,
+  vm::VM,
+};
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+#[test]
+fn reused_vm_allocates_less() {
+  let src = r#"
+    fun sum(n) {
+      var total = 0;
+      { var a = n; var b = n; total = a + b; }
+      return total;
+    }
+    var label = "sum";
+    var result = sum(1) + sum(2) + sum(3);
+    print label + ": " + str(result);
+  "#;
+  let options = BenchOptions {
+    iterations: 3,
+    warmup: 1,
+    reuse_vm: false,
+  };
+  let fresh = bench(src, options).unwrap();
+  let reused = bench(
+    src,
+    BenchOptions {
+      reuse_vm: true,
+      ..options
+    },
+  )
+  .unwrap();
+  for (fresh, reused) in fresh.iter().zip(&reused) {
+    assert_eq!(fresh.instructions, reused.instructions);
+    assert!(reused.allocations < fresh.allocations);
+  }
+
+  // tokens and repeated names allocate nothing, only bytecode grows
+  // (the function is never called, so only compiling is measured)
+  let mut vm = VM::init();
+  vm.set_cache_options(CacheOptions {
+    in_memory: false,
+    on_disk: false,
+  });
+  let statements = |n| format!("fun f() {{ {} }}", "{ var b = a; b = b + 1; }".repeat(n));
+  bench_on(&mut vm, &statements(1)).unwrap();
+  let short = bench_on(&mut vm, &statements(10)).unwrap();
+  let long = bench_on(&mut vm, &statements(200)).unwrap();
+  assert!(
+    long.allocations - short.allocations < 32,
+    "{} -> {}",
+    short.allocations,
+    long.allocations
+  );
+}